@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use opendal::services::Fs;
+use opendal::Operator;
+
+use super::{Catalog, TableIdentifier};
+use crate::config::Config;
+use crate::table::VERSION_HINT_FILENAME;
+use crate::types::{serialize_table_meta, TableMetadata};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// FileSystemCatalog manages hadoop style tables under a warehouse
+/// directory, table `a.b.t` is located at `{warehouse}/a/b/t`.
+///
+/// Tables are loaded by [`Table::open`] and commit by writing metadata files
+/// and version hint, so commits are only safe if the filesystem renames
+/// atomically or a lock provider is set to the loaded tables.
+pub struct FileSystemCatalog {
+    warehouse: String,
+    op: Operator,
+    config: Config,
+}
+
+impl FileSystemCatalog {
+    /// Create a new catalog over the warehouse at given local path.
+    pub fn try_new(warehouse: impl Into<String>) -> Result<Self> {
+        let warehouse = warehouse.into().trim_end_matches('/').to_string();
+
+        let mut builder = Fs::default();
+        builder.root(&warehouse);
+        let op = Operator::new(builder)?.finish();
+
+        Ok(Self {
+            warehouse,
+            op,
+            config: Config::new(),
+        })
+    }
+
+    /// Set the config of loaded tables, see [`Table::set_config`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns the path of the table relative to the warehouse, ends with
+    /// `/`.
+    fn table_path(table: &TableIdentifier) -> String {
+        let mut path: String = table.namespace.iter().map(|v| format!("{v}/")).collect();
+        path.push_str(&table.name);
+        path.push('/');
+        path
+    }
+
+    async fn is_table(&self, path: &str) -> Result<bool> {
+        Ok(self.op.is_exist(&format!("{path}metadata/")).await?)
+    }
+
+    /// Returns the path of the namespace relative to the warehouse, which
+    /// must be a directory not being a table.
+    async fn check_namespace(&self, namespace: &[String]) -> Result<String> {
+        let path: String = namespace.iter().map(|v| format!("{v}/")).collect();
+        if path.is_empty() || !self.op.is_exist(&path).await? || self.is_table(&path).await? {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Namespace {} doesn't exist", namespace.join(".")),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// List names of directories under `dir`, which ends with `/`.
+    async fn list_dirs(&self, dir: &str) -> Result<Vec<String>> {
+        let mut lister = self.op.list(dir).await?;
+        let mut dirs = vec![];
+        while let Some(entry) = lister.next().await {
+            let entry = entry?;
+            // Dir entries in opendal always end with `/`.
+            if entry.path().ends_with('/') && entry.path() != dir {
+                dirs.push(entry.name().trim_end_matches('/').to_string());
+            }
+        }
+        dirs.sort();
+        Ok(dirs)
+    }
+
+    async fn check_table(&self, table: &TableIdentifier) -> Result<String> {
+        let path = Self::table_path(table);
+        if !self.is_table(&path).await? {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Table {table} doesn't exist"),
+            ));
+        }
+        Ok(path)
+    }
+}
+
+#[async_trait]
+impl Catalog for FileSystemCatalog {
+    async fn list_tables(&self, namespace: &[String]) -> Result<Vec<TableIdentifier>> {
+        let dir: String = namespace.iter().map(|v| format!("{v}/")).collect();
+        if !dir.is_empty() && !self.op.is_exist(&dir).await? {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Namespace {} doesn't exist", namespace.join(".")),
+            ));
+        }
+        let dir = if dir.is_empty() { "/".to_string() } else { dir };
+
+        let mut tables = vec![];
+        for name in self.list_dirs(&dir).await? {
+            if self.is_table(&format!("{dir}{name}/")).await? {
+                tables.push(TableIdentifier::new(namespace.iter().cloned(), name));
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Namespaces are directories which are not tables.
+    async fn list_namespaces(&self, parent: &[String]) -> Result<Vec<Vec<String>>> {
+        let dir = if parent.is_empty() {
+            "/".to_string()
+        } else {
+            self.check_namespace(parent).await?
+        };
+
+        let mut namespaces = vec![];
+        for name in self.list_dirs(&dir).await? {
+            if !self.is_table(&format!("{dir}{name}/")).await? {
+                let mut namespace = parent.to_vec();
+                namespace.push(name);
+                namespaces.push(namespace);
+            }
+        }
+        Ok(namespaces)
+    }
+
+    /// Create the directory of the namespace, properties are not supported
+    /// by hadoop catalogs.
+    async fn create_namespace(
+        &self,
+        namespace: &[String],
+        properties: HashMap<String, String>,
+    ) -> Result<()> {
+        if !properties.is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                "Namespace properties of hadoop catalog",
+            ));
+        }
+        let path: String = namespace.iter().map(|v| format!("{v}/")).collect();
+        if path.is_empty() || self.op.is_exist(&path).await? {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Namespace {} already exists", namespace.join(".")),
+            ));
+        }
+        self.op.create_dir(&path).await?;
+        Ok(())
+    }
+
+    /// Namespaces only have property `location` of their directories.
+    async fn load_namespace_properties(
+        &self,
+        namespace: &[String],
+    ) -> Result<HashMap<String, String>> {
+        let path = self.check_namespace(namespace).await?;
+        Ok(HashMap::from([(
+            "location".to_string(),
+            format!("{}/{}", self.warehouse, path.trim_end_matches('/')),
+        )]))
+    }
+
+    /// Drop the directory of the namespace, which must be empty.
+    async fn drop_namespace(&self, namespace: &[String]) -> Result<()> {
+        let path = self.check_namespace(namespace).await?;
+        let mut lister = self.op.list(&path).await?;
+        while let Some(entry) = lister.next().await {
+            if entry?.path() != path {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Namespace {} is not empty", namespace.join(".")),
+                ));
+            }
+        }
+        self.op.delete(&path).await?;
+        Ok(())
+    }
+
+    async fn create_table(
+        &self,
+        table: &TableIdentifier,
+        mut metadata: TableMetadata,
+    ) -> Result<Table> {
+        let path = Self::table_path(table);
+        if self.op.is_exist(&path).await? {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Table {table} already exists"),
+            ));
+        }
+
+        metadata.location = format!("{}/{}", self.warehouse, path.trim_end_matches('/'));
+        self.op
+            .write(
+                &format!("{path}{}", Table::metadata_file_path(1)),
+                serialize_table_meta(metadata)?,
+            )
+            .await?;
+        self.op
+            .write(
+                &format!("{path}{}", Table::metadata_path(VERSION_HINT_FILENAME)),
+                "1",
+            )
+            .await?;
+
+        self.load_table(table).await
+    }
+
+    async fn load_table(&self, table: &TableIdentifier) -> Result<Table> {
+        let path = self.check_table(table).await?;
+        let mut table = Table::open(&format!("{}/{path}", self.warehouse)).await?;
+        table.set_config(self.config.clone());
+        Ok(table)
+    }
+
+    async fn drop_table(&self, table: &TableIdentifier) -> Result<()> {
+        let path = self.check_table(table).await?;
+        self.op.remove_all(&path).await?;
+        Ok(())
+    }
+
+    async fn rename_table(&self, from: &TableIdentifier, _to: &TableIdentifier) -> Result<()> {
+        // Locations of hadoop tables are recorded in metadata and manifests.
+        Err(Error::new(
+            ErrorKind::IcebergFeatureUnsupported,
+            format!("Renaming hadoop table {from}"),
+        ))
+    }
+
+    async fn update_table(
+        &self,
+        table: &TableIdentifier,
+        base_location: &str,
+        _base: &TableMetadata,
+        next: &TableMetadata,
+    ) -> Result<(String, TableMetadata)> {
+        let mut table = self.load_table(table).await?;
+        if table.current_metadata_file_location() != base_location {
+            return Err(Error::new(
+                ErrorKind::CommitConflict,
+                format!(
+                    "Cannot commit table because its metadata has been updated from {base_location} to {}",
+                    table.current_metadata_file_location()
+                ),
+            ));
+        }
+        table.commit(next.clone()).await?;
+        Ok((
+            table.current_metadata_file_location(),
+            table.current_table_metadata().clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::table_properties::{
+        METADATA_DELETE_AFTER_COMMIT_ENABLED, METADATA_PREVIOUS_VERSIONS_MAX,
+    };
+    use crate::types::parse_table_metadata;
+
+    #[tokio::test]
+    async fn test_file_system_catalog() {
+        let warehouse = TempDir::new().unwrap();
+        let catalog = FileSystemCatalog::try_new(warehouse.path().to_str().unwrap()).unwrap();
+        let identifier = TableIdentifier::new(["db"], "t");
+        assert!(catalog.load_table(&identifier).await.is_err());
+
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v1.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        let mut table = catalog
+            .create_table(&identifier, metadata.clone())
+            .await
+            .unwrap();
+        let location = format!("{}/db/t", warehouse.path().to_str().unwrap());
+        assert_eq!(table.current_table_metadata().location, location);
+        assert!(catalog
+            .create_table(&identifier, metadata.clone())
+            .await
+            .is_err());
+        assert_eq!(
+            catalog.list_tables(&["db".to_string()]).await.unwrap(),
+            vec![identifier.clone()]
+        );
+        assert!(catalog.list_tables(&["x".to_string()]).await.is_err());
+
+        // Commits of stale metadata are rejected.
+        let base = table.current_table_metadata().clone();
+        let base_location = table.current_metadata_file_location();
+        let mut tx = table.new_transaction();
+        tx.set_properties([("k".to_string(), "v".to_string())].into());
+        tx.commit().await.unwrap();
+        // The base metadata file is logged once.
+        let logs = table.current_table_metadata().metadata_log.clone().unwrap();
+        assert_eq!(
+            logs.iter()
+                .filter(|v| v.metadata_file == base_location)
+                .count(),
+            1
+        );
+        let (committed_location, committed) = catalog
+            .update_table(
+                &identifier,
+                &table.current_metadata_file_location(),
+                table.current_table_metadata(),
+                &base,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            committed_location,
+            format!("{location}/metadata/v3.metadata.json")
+        );
+        assert!(!committed.properties.unwrap_or_default().contains_key("k"));
+        assert!(catalog
+            .update_table(&identifier, &base_location, &base, &base)
+            .await
+            .is_err());
+
+        assert!(catalog
+            .rename_table(&identifier, &TableIdentifier::new(["db"], "t2"))
+            .await
+            .is_err());
+        catalog.drop_table(&identifier).await.unwrap();
+        assert!(catalog
+            .list_tables(&["db".to_string()])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_system_catalog_metadata_log() {
+        let warehouse = TempDir::new().unwrap();
+        let catalog = FileSystemCatalog::try_new(warehouse.path().to_str().unwrap()).unwrap();
+        let identifier = TableIdentifier::new(["db"], "t");
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v1.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        let mut table = catalog.create_table(&identifier, metadata).await.unwrap();
+        let location = table.current_table_metadata().location.clone();
+        let version = |v: i32| format!("{location}/metadata/v{v}.metadata.json");
+        let (v1, v2, v3, v4) = (version(1), version(2), version(3), version(4));
+
+        let mut tx = table.new_transaction();
+        tx.set_properties(
+            [
+                (METADATA_PREVIOUS_VERSIONS_MAX.to_string(), "2".to_string()),
+                (
+                    METADATA_DELETE_AFTER_COMMIT_ENABLED.to_string(),
+                    "true".to_string(),
+                ),
+            ]
+            .into(),
+        );
+        tx.commit().await.unwrap();
+        for n in 1..=3 {
+            let mut tx = table.new_transaction();
+            tx.set_properties([("n".to_string(), n.to_string())].into());
+            tx.commit().await.unwrap();
+        }
+        assert_eq!(table.current_metadata_file_location(), version(5));
+
+        // Only the latest previous versions are logged, from the oldest.
+        let logs = table.current_table_metadata().metadata_log.clone().unwrap();
+        assert_eq!(
+            logs.iter()
+                .map(|v| v.metadata_file.as_str())
+                .collect::<Vec<_>>(),
+            vec![v3.as_str(), v4.as_str()]
+        );
+        assert!(logs[0].timestamp_ms <= logs[1].timestamp_ms);
+        for (location, exists) in [(&v1, false), (&v2, false), (&v3, true), (&v4, true)] {
+            assert_eq!(fs::metadata(location).is_ok(), exists, "{location}");
+        }
+
+        for (location, n) in [(&v3, "1"), (&v4, "2")] {
+            let previous = table.open_previous_metadata(location).await.unwrap();
+            assert_eq!(previous.current_metadata_file_location(), *location);
+            assert_eq!(previous.properties().get("n").map(String::as_str), Some(n));
+        }
+        assert!(table.open_previous_metadata(&v2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_system_catalog_namespaces() {
+        let warehouse = TempDir::new().unwrap();
+        let catalog = FileSystemCatalog::try_new(warehouse.path().to_str().unwrap()).unwrap();
+        let db = vec!["db".to_string()];
+        let schema = vec!["db".to_string(), "s".to_string()];
+        catalog.create_namespace(&db, HashMap::new()).await.unwrap();
+        catalog
+            .create_namespace(&schema, HashMap::new())
+            .await
+            .unwrap();
+        assert!(catalog.create_namespace(&db, HashMap::new()).await.is_err());
+        assert!(catalog
+            .create_namespace(
+                &["x".to_string()],
+                HashMap::from([("k".into(), "v".into())])
+            )
+            .await
+            .is_err());
+
+        // Tables are not namespaces.
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v1.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        let identifier = TableIdentifier::new(["db"], "t");
+        catalog.create_table(&identifier, metadata).await.unwrap();
+        assert_eq!(
+            catalog.list_namespaces(&[]).await.unwrap(),
+            vec![db.clone()]
+        );
+        assert_eq!(
+            catalog.list_namespaces(&db).await.unwrap(),
+            vec![schema.clone()]
+        );
+        assert!(catalog
+            .list_namespaces(&["db".to_string(), "t".to_string()])
+            .await
+            .is_err());
+        assert_eq!(
+            catalog.load_namespace_properties(&schema).await.unwrap()["location"],
+            format!("{}/db/s", warehouse.path().to_str().unwrap())
+        );
+
+        assert!(catalog.drop_namespace(&db).await.is_err());
+        catalog.drop_namespace(&schema).await.unwrap();
+        catalog.drop_table(&identifier).await.unwrap();
+        catalog.drop_namespace(&db).await.unwrap();
+        assert!(catalog.list_namespaces(&[]).await.unwrap().is_empty());
+        assert!(catalog.load_namespace_properties(&db).await.is_err());
+    }
+}
@@ -0,0 +1,985 @@
+//! glue module provides [`GlueCatalog`], which manages iceberg tables by
+//! [AWS Glue data catalog](https://docs.aws.amazon.com/glue/latest/dg/catalog-and-crawler.html)
+//! like Athena and EMR do.
+//!
+//! A glue table of iceberg has parameter `table_type` of `ICEBERG`, and
+//! parameter `metadata_location` pointing to its current metadata file.
+//! Commits write a new metadata file, then swap the pointer only if glue
+//! table is not updated since the base metadata is loaded.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_glue::config::Credentials;
+use aws_sdk_glue::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_glue::types::{DatabaseInput, StorageDescriptor, Table as GlueTable, TableInput};
+use aws_sdk_glue::Client;
+use opendal::Operator;
+use tokio::sync::OnceCell;
+use url::Url;
+
+use super::{
+    first_metadata_path, next_metadata_path, split_metadata_location, Catalog, TableIdentifier,
+};
+use crate::config::{Config, S3_ACCESS_KEY_ID, S3_REGION, S3_SECRET_ACCESS_KEY, S3_SESSION_TOKEN};
+use crate::io::storage::build_operator;
+use crate::types::{parse_table_metadata, serialize_table_meta, TableMetadata};
+use crate::{Error, ErrorKind, Result, Table};
+
+const TABLE_TYPE_PARAMETER: &str = "table_type";
+const TABLE_TYPE_ICEBERG: &str = "ICEBERG";
+const METADATA_LOCATION_PARAMETER: &str = "metadata_location";
+const PREVIOUS_METADATA_LOCATION_PARAMETER: &str = "previous_metadata_location";
+
+/// AWS credential used to access glue and s3.
+#[derive(Clone)]
+struct AwsCredential {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// GlueCatalog loads iceberg tables registered in AWS Glue, commits of the
+/// loaded tables update the metadata location of glue tables.
+///
+/// Glue is accessed by the AWS SDK, whose credential is loaded by the
+/// default credential chain of the SDK, like environment variables,
+/// profiles and instance metadata, unless set by
+/// [`GlueCatalog::with_credential`]. Storage of tables is inferred from
+/// their locations like s3, see [`crate::io::storage`], and loads its
+/// credential in the same way if not set.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// use icelake::catalog::{Catalog, GlueCatalog, TableIdentifier};
+///
+/// let catalog = GlueCatalog::try_new("us-east-1")?;
+/// let mut table = catalog.load_table(&TableIdentifier::new(["db"], "table")).await?;
+/// let mut tx = table.new_transaction();
+/// tx.set_properties([("k".to_string(), "v".to_string())].into());
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct GlueCatalog {
+    region: String,
+    endpoint: Option<Url>,
+    catalog_id: Option<String>,
+    credential: Option<AwsCredential>,
+    /// Client of glue, created on first use since loading the SDK config
+    /// is async.
+    client: Arc<OnceCell<Client>>,
+    config: Config,
+}
+
+impl GlueCatalog {
+    /// Create a new glue catalog of the region.
+    pub fn try_new(region: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            region: region.into(),
+            endpoint: None,
+            catalog_id: None,
+            credential: None,
+            client: Arc::new(OnceCell::new()),
+            config: Config::new(),
+        })
+    }
+
+    /// Set the glue endpoint, like a VPC endpoint.
+    pub fn with_endpoint(mut self, endpoint: &str) -> Result<Self> {
+        self.endpoint = Some(Url::parse(endpoint)?);
+        self.client = Arc::new(OnceCell::new());
+        Ok(self)
+    }
+
+    /// Set the id of the catalog, which is the AWS account id. Defaults to
+    /// the account of the credential.
+    pub fn with_catalog_id(mut self, catalog_id: impl Into<String>) -> Self {
+        self.catalog_id = Some(catalog_id.into());
+        self
+    }
+
+    /// Set the credential used to access glue and s3.
+    pub fn with_credential(
+        mut self,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        self.credential = Some(AwsCredential {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token,
+        });
+        self.client = Arc::new(OnceCell::new());
+        self
+    }
+
+    /// Set the config of loaded tables, see [`Table::set_config`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    async fn client(&self) -> &Client {
+        self.client
+            .get_or_init(|| async {
+                let mut loader = aws_config::defaults(BehaviorVersion::latest())
+                    .region(Region::new(self.region.clone()));
+                if let Some(endpoint) = &self.endpoint {
+                    loader = loader.endpoint_url(endpoint.as_str().trim_end_matches('/'));
+                }
+                if let Some(credential) = &self.credential {
+                    loader = loader.credentials_provider(Credentials::new(
+                        &credential.access_key_id,
+                        &credential.secret_access_key,
+                        credential.session_token.clone(),
+                        None,
+                        "icelake",
+                    ));
+                }
+                Client::new(&loader.load().await)
+            })
+            .await
+    }
+
+    /// List names of all databases.
+    pub async fn list_databases(&self) -> Result<Vec<String>> {
+        let mut databases = vec![];
+        let mut next_token: Option<String> = None;
+        loop {
+            let response = self
+                .client()
+                .await
+                .get_databases()
+                .set_catalog_id(self.catalog_id.clone())
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| glue_error("GetDatabases", e))?;
+            databases.extend(
+                response
+                    .database_list()
+                    .iter()
+                    .map(|database| database.name().to_string()),
+            );
+            match response.next_token() {
+                Some(token) if !token.is_empty() => next_token = Some(token.to_string()),
+                _ => return Ok(databases),
+            }
+        }
+    }
+
+    async fn get_database(&self, database: &str) -> Result<aws_sdk_glue::types::Database> {
+        let response = self
+            .client()
+            .await
+            .get_database()
+            .set_catalog_id(self.catalog_id.clone())
+            .name(database)
+            .send()
+            .await
+            .map_err(|e| glue_error("GetDatabase", e))?;
+        response.database.ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unexpected,
+                format!("Glue database {database} is not returned"),
+            )
+        })
+    }
+
+    async fn get_table(&self, database: &str, name: &str) -> Result<GlueTable> {
+        let response = self
+            .client()
+            .await
+            .get_table()
+            .set_catalog_id(self.catalog_id.clone())
+            .database_name(database)
+            .name(name)
+            .send()
+            .await
+            .map_err(|e| glue_error("GetTable", e))?;
+        match response.table {
+            Some(table) if is_iceberg_table(&table) => Ok(table),
+            _ => Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Glue table {database}.{name} is not an iceberg table"),
+            )),
+        }
+    }
+
+    async fn create_glue_table(&self, database: &str, input: TableInput) -> Result<()> {
+        self.client()
+            .await
+            .create_table()
+            .set_catalog_id(self.catalog_id.clone())
+            .database_name(database)
+            .table_input(input)
+            .send()
+            .await
+            .map_err(|e| glue_error("CreateTable", e))?;
+        Ok(())
+    }
+
+    /// Returns the operator rooted at the table location, s3 is accessed by
+    /// the region and credential of this catalog.
+    fn operator(&self, location: &str) -> Result<Operator> {
+        let mut props = self.config.overrides();
+        props.insert(S3_REGION.to_string(), self.region.clone());
+        if let Some(credential) = &self.credential {
+            props.insert(
+                S3_ACCESS_KEY_ID.to_string(),
+                credential.access_key_id.clone(),
+            );
+            props.insert(
+                S3_SECRET_ACCESS_KEY.to_string(),
+                credential.secret_access_key.clone(),
+            );
+            if let Some(token) = &credential.session_token {
+                props.insert(S3_SESSION_TOKEN.to_string(), token.clone());
+            }
+        }
+        build_operator(location, &props)
+    }
+}
+
+/// Convert an error of glue request `action`, concurrent modifications are
+/// commit conflicts and missing entities are not found.
+fn glue_error<E, R>(action: &str, err: SdkError<E, R>) -> Error
+where
+    E: ProvideErrorMetadata + std::error::Error + Send + Sync + 'static,
+    R: Debug + Send + Sync + 'static,
+{
+    let kind = match err.code() {
+        Some("ConcurrentModificationException") => ErrorKind::CommitConflict,
+        Some("EntityNotFoundException") => ErrorKind::NotFound,
+        _ => ErrorKind::Unexpected,
+    };
+    let message = match err.message() {
+        Some(message) => message.to_string(),
+        None => DisplayErrorContext(&err).to_string(),
+    };
+    let mut error = Error::new(kind, format!("Glue request {action} failed: {message}"));
+    if let Some(code) = err.code() {
+        error = error.with_context("type", code);
+    }
+    error.set_source(err)
+}
+
+fn is_iceberg_table(table: &GlueTable) -> bool {
+    table
+        .parameters()
+        .and_then(|v| v.get(TABLE_TYPE_PARAMETER))
+        .map(|v| v.eq_ignore_ascii_case(TABLE_TYPE_ICEBERG))
+        .unwrap_or(false)
+}
+
+fn metadata_location(table: &GlueTable) -> Result<&str> {
+    table
+        .parameters()
+        .and_then(|v| v.get(METADATA_LOCATION_PARAMETER))
+        .map(String::as_str)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Metadata location of glue table is not found",
+            )
+        })
+}
+
+/// Returns the glue database of the namespace, which must have one level.
+fn database(namespace: &[String]) -> Result<&str> {
+    match namespace {
+        [database] => Ok(database),
+        _ => Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!(
+                "Namespace {} of glue catalog must be a database",
+                namespace.join(".")
+            ),
+        )),
+    }
+}
+
+/// Returns the `TableInput` of glue table `name`.
+fn table_input(
+    name: &str,
+    parameters: HashMap<String, String>,
+    table_type: Option<&str>,
+    location: Option<&str>,
+) -> Result<TableInput> {
+    TableInput::builder()
+        .name(name)
+        .set_table_type(table_type.map(str::to_string))
+        .set_parameters(Some(parameters))
+        .storage_descriptor(
+            StorageDescriptor::builder()
+                .set_location(location.map(str::to_string))
+                .build(),
+        )
+        .build()
+        .map_err(|e| {
+            Error::new(ErrorKind::Unexpected, "Failed to build glue table input").set_source(e)
+        })
+}
+
+/// Parameters of a new glue table of iceberg.
+fn iceberg_parameters(metadata_location: &str) -> HashMap<String, String> {
+    HashMap::from([
+        (
+            TABLE_TYPE_PARAMETER.to_string(),
+            TABLE_TYPE_ICEBERG.to_string(),
+        ),
+        (
+            METADATA_LOCATION_PARAMETER.to_string(),
+            metadata_location.to_string(),
+        ),
+    ])
+}
+
+#[async_trait]
+impl Catalog for GlueCatalog {
+    /// Namespaces are databases, which have no nested namespaces.
+    async fn list_namespaces(&self, parent: &[String]) -> Result<Vec<Vec<String>>> {
+        if !parent.is_empty() {
+            self.get_database(database(parent)?).await?;
+            return Ok(vec![]);
+        }
+        Ok(self
+            .list_databases()
+            .await?
+            .into_iter()
+            .map(|v| vec![v])
+            .collect())
+    }
+
+    /// Create a database with properties as its parameters.
+    async fn create_namespace(
+        &self,
+        namespace: &[String],
+        properties: HashMap<String, String>,
+    ) -> Result<()> {
+        let input = DatabaseInput::builder()
+            .name(database(namespace)?)
+            .set_parameters(Some(properties))
+            .build()
+            .map_err(|e| {
+                Error::new(ErrorKind::Unexpected, "Failed to build glue database input")
+                    .set_source(e)
+            })?;
+        self.client()
+            .await
+            .create_database()
+            .set_catalog_id(self.catalog_id.clone())
+            .database_input(input)
+            .send()
+            .await
+            .map_err(|e| glue_error("CreateDatabase", e))?;
+        Ok(())
+    }
+
+    /// Returns parameters of the database, and its location as `location`
+    /// if set.
+    async fn load_namespace_properties(
+        &self,
+        namespace: &[String],
+    ) -> Result<HashMap<String, String>> {
+        let database = self.get_database(database(namespace)?).await?;
+        let mut properties = database.parameters().cloned().unwrap_or_default();
+        if let Some(location) = database.location_uri() {
+            properties.insert("location".to_string(), location.to_string());
+        }
+        Ok(properties)
+    }
+
+    async fn update_namespace_properties(
+        &self,
+        namespace: &[String],
+        removals: &[String],
+        updates: HashMap<String, String>,
+    ) -> Result<()> {
+        let name = database(namespace)?;
+        let database = self.get_database(name).await?;
+        let mut parameters = database.parameters().cloned().unwrap_or_default();
+        for key in removals {
+            parameters.remove(key);
+        }
+        parameters.extend(updates);
+
+        let input = DatabaseInput::builder()
+            .name(name)
+            .set_description(database.description().map(str::to_string))
+            .set_location_uri(database.location_uri().map(str::to_string))
+            .set_parameters(Some(parameters))
+            .build()
+            .map_err(|e| {
+                Error::new(ErrorKind::Unexpected, "Failed to build glue database input")
+                    .set_source(e)
+            })?;
+        self.client()
+            .await
+            .update_database()
+            .set_catalog_id(self.catalog_id.clone())
+            .name(name)
+            .database_input(input)
+            .send()
+            .await
+            .map_err(|e| glue_error("UpdateDatabase", e))?;
+        Ok(())
+    }
+
+    /// Drop the database, which must contain no tables, including tables
+    /// other than iceberg tables since glue drops them with the database.
+    async fn drop_namespace(&self, namespace: &[String]) -> Result<()> {
+        let name = database(namespace)?;
+        let response = self
+            .client()
+            .await
+            .get_tables()
+            .set_catalog_id(self.catalog_id.clone())
+            .database_name(name)
+            .max_results(1)
+            .send()
+            .await
+            .map_err(|e| glue_error("GetTables", e))?;
+        if !response.table_list().is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Glue database {name} is not empty"),
+            ));
+        }
+
+        self.client()
+            .await
+            .delete_database()
+            .set_catalog_id(self.catalog_id.clone())
+            .name(name)
+            .send()
+            .await
+            .map_err(|e| glue_error("DeleteDatabase", e))?;
+        Ok(())
+    }
+
+    /// List iceberg tables in the database, other glue tables are skipped.
+    async fn list_tables(&self, namespace: &[String]) -> Result<Vec<TableIdentifier>> {
+        let database = database(namespace)?;
+        let mut tables = vec![];
+        let mut next_token: Option<String> = None;
+        loop {
+            let response = self
+                .client()
+                .await
+                .get_tables()
+                .set_catalog_id(self.catalog_id.clone())
+                .database_name(database)
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| glue_error("GetTables", e))?;
+            for table in response.table_list() {
+                if is_iceberg_table(table) {
+                    tables.push(TableIdentifier::new([database], table.name()));
+                }
+            }
+            match response.next_token() {
+                Some(token) if !token.is_empty() => next_token = Some(token.to_string()),
+                _ => return Ok(tables),
+            }
+        }
+    }
+
+    /// Create the table at [`TableMetadata::location`], which must be set.
+    async fn create_table(
+        &self,
+        table: &TableIdentifier,
+        metadata: TableMetadata,
+    ) -> Result<Table> {
+        let database = database(&table.namespace)?;
+        if metadata.location.is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Location of table {table} must be set"),
+            ));
+        }
+
+        let op = self.operator(&metadata.location)?;
+        let path = first_metadata_path();
+        let location = format!("{}/{path}", metadata.location);
+        op.write(&path, serialize_table_meta(metadata.clone())?)
+            .await?;
+
+        let input = table_input(
+            &table.name,
+            iceberg_parameters(&location),
+            Some("EXTERNAL_TABLE"),
+            Some(&metadata.location),
+        )?;
+        if let Err(err) = self.create_glue_table(database, input).await {
+            if let Err(e) = op.delete(&path).await {
+                log::warn!("Failed to delete uncommitted metadata file {location}: {e}");
+            }
+            return Err(err);
+        }
+
+        self.load_table(table).await
+    }
+
+    /// Create a glue table of iceberg pointing to the metadata file, after
+    /// checking it can be read.
+    async fn register_table(
+        &self,
+        table: &TableIdentifier,
+        metadata_location: &str,
+    ) -> Result<Table> {
+        let database = database(&table.namespace)?;
+        let (table_location, metadata_path) = split_metadata_location(metadata_location)?;
+        let op = self.operator(table_location)?;
+        let metadata = parse_table_metadata(&op.read(metadata_path).await?)
+            .map_err(|e| e.with_context("metadata_location", metadata_location))?;
+
+        let input = table_input(
+            &table.name,
+            iceberg_parameters(metadata_location),
+            Some("EXTERNAL_TABLE"),
+            Some(&metadata.location),
+        )?;
+        self.create_glue_table(database, input).await?;
+
+        self.load_table(table).await
+    }
+
+    async fn load_table(&self, table: &TableIdentifier) -> Result<Table> {
+        let glue_table = self
+            .get_table(database(&table.namespace)?, &table.name)
+            .await?;
+        let metadata_location = metadata_location(&glue_table)?;
+        let (table_location, metadata_path) = split_metadata_location(metadata_location)?;
+
+        let op = self.operator(table_location)?;
+        let metadata = parse_table_metadata(&op.read(metadata_path).await?)
+            .map_err(|e| e.with_context("metadata_location", metadata_location))?;
+
+        let mut table = Table::with_catalog(
+            op,
+            Arc::new(self.clone()),
+            table.clone(),
+            metadata_location.to_string(),
+            metadata,
+        )?;
+        table.set_config(self.config.clone());
+        Ok(table)
+    }
+
+    /// Drop the glue table, files of the table are kept.
+    async fn drop_table(&self, table: &TableIdentifier) -> Result<()> {
+        self.client()
+            .await
+            .delete_table()
+            .set_catalog_id(self.catalog_id.clone())
+            .database_name(database(&table.namespace)?)
+            .name(&table.name)
+            .send()
+            .await
+            .map_err(|e| glue_error("DeleteTable", e))?;
+        Ok(())
+    }
+
+    /// Glue can't rename tables, the table is created by the new name then
+    /// dropped.
+    async fn rename_table(&self, from: &TableIdentifier, to: &TableIdentifier) -> Result<()> {
+        let glue_table = self
+            .get_table(database(&from.namespace)?, &from.name)
+            .await?;
+        let input = table_input(
+            &to.name,
+            glue_table.parameters().cloned().unwrap_or_default(),
+            glue_table.table_type(),
+            glue_table.storage_descriptor().and_then(|v| v.location()),
+        )?;
+        self.create_glue_table(database(&to.namespace)?, input)
+            .await?;
+        self.drop_table(from).await
+    }
+
+    async fn update_table(
+        &self,
+        table: &TableIdentifier,
+        base_location: &str,
+        _base: &TableMetadata,
+        next: &TableMetadata,
+    ) -> Result<(String, TableMetadata)> {
+        let database = database(&table.namespace)?;
+        let glue_table = self.get_table(database, &table.name).await?;
+        let current_location = metadata_location(&glue_table)?;
+        if current_location != base_location {
+            return Err(Error::new(
+                ErrorKind::CommitConflict,
+                format!(
+                    "Cannot commit {table} because its metadata location has been changed from {base_location} to {current_location}"
+                ),
+            ));
+        }
+
+        let path = next_metadata_path(base_location)?;
+        let location = format!("{}/{path}", next.location);
+        let op = self.operator(&next.location)?;
+        op.write(&path, serialize_table_meta(next.clone())?).await?;
+
+        // Glue rejects the update if the table is updated after `VersionId`.
+        let mut parameters = glue_table.parameters().cloned().unwrap_or_default();
+        parameters.insert(METADATA_LOCATION_PARAMETER.to_string(), location.clone());
+        parameters.insert(
+            PREVIOUS_METADATA_LOCATION_PARAMETER.to_string(),
+            base_location.to_string(),
+        );
+        let input = table_input(
+            &table.name,
+            parameters,
+            glue_table.table_type(),
+            Some(&next.location),
+        )?;
+        let result = self
+            .client()
+            .await
+            .update_table()
+            .set_catalog_id(self.catalog_id.clone())
+            .database_name(database)
+            .table_input(input)
+            .set_version_id(glue_table.version_id().map(str::to_string))
+            .skip_archive(true)
+            .send()
+            .await;
+        if let Err(err) = result {
+            if let Err(e) = op.delete(&path).await {
+                log::warn!("Failed to delete uncommitted metadata file {location}: {e}");
+            }
+            return Err(glue_error("UpdateTable", err));
+        }
+
+        Ok((location, next.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::net::TcpListener;
+
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use tempfile::TempDir;
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    /// A glue server keeping tables of all databases by their names.
+    #[derive(Default)]
+    struct FakeGlue {
+        databases: HashMap<String, Value>,
+        tables: HashMap<String, Value>,
+    }
+
+    async fn handle(
+        State(glue): State<Arc<Mutex<FakeGlue>>>,
+        headers: HeaderMap,
+        body: String,
+    ) -> (StatusCode, Json<Value>) {
+        let error = |typ: &str, message: &str| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "__type": typ, "Message": message })),
+            )
+        };
+        if !headers.contains_key("authorization") {
+            return error("AccessDeniedException", "request is not signed");
+        }
+        let request: Value = serde_json::from_str(&body).unwrap();
+        let mut glue = glue.lock().await;
+        let target = headers["x-amz-target"].to_str().unwrap();
+        match target {
+            "AWSGlue.GetDatabases" => (
+                StatusCode::OK,
+                Json(json!({ "DatabaseList": glue.databases.values().collect::<Vec<_>>() })),
+            ),
+            "AWSGlue.GetDatabase" => match glue.databases.get(request["Name"].as_str().unwrap()) {
+                Some(database) => (StatusCode::OK, Json(json!({ "Database": database }))),
+                None => error("EntityNotFoundException", "database not found"),
+            },
+            "AWSGlue.CreateDatabase" => {
+                let input = request["DatabaseInput"].clone();
+                let name = input["Name"].as_str().unwrap().to_string();
+                if glue.databases.contains_key(&name) {
+                    return error("AlreadyExistsException", "database already exists");
+                }
+                glue.databases.insert(name, input);
+                (StatusCode::OK, Json(json!({})))
+            }
+            "AWSGlue.UpdateDatabase" => {
+                let name = request["Name"].as_str().unwrap();
+                match glue.databases.get_mut(name) {
+                    Some(database) => {
+                        *database = request["DatabaseInput"].clone();
+                        (StatusCode::OK, Json(json!({})))
+                    }
+                    None => error("EntityNotFoundException", "database not found"),
+                }
+            }
+            "AWSGlue.DeleteDatabase" => {
+                let name = request["Name"].as_str().unwrap().to_string();
+                glue.tables.retain(|_, v| v["DatabaseName"] != name);
+                match glue.databases.remove(&name) {
+                    Some(_) => (StatusCode::OK, Json(json!({}))),
+                    None => error("EntityNotFoundException", "database not found"),
+                }
+            }
+            "AWSGlue.GetTables" => (
+                StatusCode::OK,
+                Json(json!({
+                    "TableList": glue
+                        .tables
+                        .values()
+                        .filter(|v| v["DatabaseName"] == request["DatabaseName"])
+                        .collect::<Vec<_>>()
+                })),
+            ),
+            "AWSGlue.GetTable" => match glue.tables.get(request["Name"].as_str().unwrap()) {
+                Some(table) => (StatusCode::OK, Json(json!({ "Table": table }))),
+                None => error("EntityNotFoundException", "table not found"),
+            },
+            "AWSGlue.UpdateTable" => {
+                let input = &request["TableInput"];
+                let table = glue
+                    .tables
+                    .get_mut(input["Name"].as_str().unwrap())
+                    .unwrap();
+                if table["VersionId"] != request["VersionId"] {
+                    return error("ConcurrentModificationException", "version mismatch");
+                }
+                let version: i64 = table["VersionId"].as_str().unwrap().parse().unwrap();
+                table["Parameters"] = input["Parameters"].clone();
+                table["StorageDescriptor"] = input["StorageDescriptor"].clone();
+                table["VersionId"] = json!((version + 1).to_string());
+                (StatusCode::OK, Json(json!({})))
+            }
+            "AWSGlue.CreateTable" => {
+                let mut input = request["TableInput"].clone();
+                let name = input["Name"].as_str().unwrap().to_string();
+                if glue.tables.contains_key(&name) {
+                    return error("AlreadyExistsException", "table already exists");
+                }
+                input["DatabaseName"] = request["DatabaseName"].clone();
+                input["VersionId"] = json!("1");
+                glue.tables.insert(name, input);
+                (StatusCode::OK, Json(json!({})))
+            }
+            "AWSGlue.DeleteTable" => match glue.tables.remove(request["Name"].as_str().unwrap()) {
+                Some(_) => (StatusCode::OK, Json(json!({}))),
+                None => error("EntityNotFoundException", "table not found"),
+            },
+            _ => error("InvalidInputException", target),
+        }
+    }
+
+    /// Copy simple table into a temporary directory with its location in
+    /// metadata files pointing to the copy, then register it in glue.
+    fn prepare_table(glue: &mut FakeGlue) -> TempDir {
+        let tmp_dir = TempDir::new().unwrap();
+        let src = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let dst = tmp_dir.path().to_str().unwrap();
+        for dir in ["metadata", "data"] {
+            fs::create_dir_all(tmp_dir.path().join(dir)).unwrap();
+            for entry in fs::read_dir(format!("{src}/{dir}")).unwrap() {
+                let entry = entry.unwrap();
+                let target = tmp_dir.path().join(dir).join(entry.file_name());
+                if entry.path().to_str().unwrap().ends_with(".metadata.json") {
+                    let content = fs::read_to_string(entry.path())
+                        .unwrap()
+                        .replace("/opt/bitnami/spark/warehouse/db/table", dst);
+                    fs::write(target, content).unwrap();
+                } else {
+                    fs::copy(entry.path(), target).unwrap();
+                }
+            }
+        }
+        glue.databases
+            .insert("db".to_string(), json!({ "Name": "db" }));
+        glue.tables.insert(
+            "simple_table".to_string(),
+            json!({
+                "Name": "simple_table",
+                "DatabaseName": "db",
+                "TableType": "EXTERNAL_TABLE",
+                "VersionId": "1",
+                "Parameters": {
+                    "table_type": "ICEBERG",
+                    "metadata_location": format!("{dst}/metadata/v2.metadata.json"),
+                },
+                "StorageDescriptor": { "Location": dst },
+            }),
+        );
+        glue.tables.insert(
+            "hive_table".to_string(),
+            json!({ "Name": "hive_table", "DatabaseName": "db", "VersionId": "1" }),
+        );
+        tmp_dir
+    }
+
+    #[tokio::test]
+    async fn test_glue_catalog() {
+        let mut glue = FakeGlue::default();
+        let tmp_dir = prepare_table(&mut glue);
+        let location = tmp_dir.path().to_str().unwrap();
+        let glue = Arc::new(Mutex::new(glue));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = Router::new()
+            .route("/", post(handle))
+            .with_state(glue.clone());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+        let catalog = GlueCatalog::try_new("us-east-1")
+            .unwrap()
+            .with_endpoint(&format!("http://{addr}"))
+            .unwrap()
+            .with_credential("ak", "sk", None);
+
+        assert_eq!(catalog.list_databases().await.unwrap(), vec!["db"]);
+        let namespace = ["db".to_string()];
+        let identifier = TableIdentifier::new(["db"], "simple_table");
+        assert_eq!(
+            catalog.list_tables(&namespace).await.unwrap(),
+            vec![identifier.clone()]
+        );
+        assert!(catalog
+            .load_table(&TableIdentifier::new(["db"], "hive_table"))
+            .await
+            .is_err());
+        assert!(catalog
+            .load_table(&TableIdentifier::new(["db", "x"], "simple_table"))
+            .await
+            .is_err());
+
+        let mut table = catalog.load_table(&identifier).await.unwrap();
+        let stale = catalog.load_table(&identifier).await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.set_properties([("k".to_string(), "v".to_string())].into());
+        tx.commit().await.unwrap();
+
+        let committed = table.current_metadata_file_location();
+        assert!(committed.starts_with(&format!("{location}/metadata/00003-")));
+        {
+            let glue = glue.lock().await;
+            let parameters = &glue.tables["simple_table"]["Parameters"];
+            assert_eq!(parameters["metadata_location"], committed.as_str());
+            assert_eq!(
+                parameters["previous_metadata_location"],
+                format!("{location}/metadata/v2.metadata.json")
+            );
+        }
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert_eq!(
+            reloaded.properties().get("k").map(String::as_str),
+            Some("v")
+        );
+
+        // Commits based on stale metadata are rejected, transactions retry
+        // them after refreshing.
+        let metadata = stale.current_table_metadata();
+        let err = catalog
+            .update_table(
+                &identifier,
+                &stale.current_metadata_file_location(),
+                metadata,
+                metadata,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommitConflict);
+        let metadata_files = fs::read_dir(tmp_dir.path().join("metadata"))
+            .unwrap()
+            .filter(|v| {
+                let name = v.as_ref().unwrap().file_name();
+                name.to_str().unwrap().ends_with(".metadata.json")
+            })
+            .count();
+        assert_eq!(metadata_files, 3);
+
+        // Created tables are located at the location of metadata.
+        let created = TableIdentifier::new(["db"], "created");
+        let mut metadata = table.current_table_metadata().clone();
+        metadata.location = format!("{location}/created");
+        let table = catalog.create_table(&created, metadata).await.unwrap();
+        assert!(table
+            .current_metadata_file_location()
+            .starts_with(&format!("{location}/created/metadata/00000-")));
+        assert_eq!(catalog.list_tables(&namespace).await.unwrap().len(), 2);
+
+        let renamed = TableIdentifier::new(["db"], "renamed");
+        catalog.rename_table(&created, &renamed).await.unwrap();
+        assert!(catalog.load_table(&created).await.is_err());
+        let table = catalog.load_table(&renamed).await.unwrap();
+        assert_eq!(table.identifier(), Some(&renamed));
+        catalog.drop_table(&renamed).await.unwrap();
+        assert_eq!(
+            catalog.list_tables(&namespace).await.unwrap(),
+            vec![identifier]
+        );
+
+        // Registered tables point to existing metadata files.
+        let registered = TableIdentifier::new(["db"], "registered");
+        let table = catalog
+            .register_table(&registered, &committed)
+            .await
+            .unwrap();
+        assert_eq!(table.current_metadata_file_location(), committed);
+        assert!(catalog
+            .register_table(&registered, &committed)
+            .await
+            .is_err());
+        catalog.drop_table(&registered).await.unwrap();
+
+        // Namespaces are databases.
+        let other = vec!["other".to_string()];
+        catalog
+            .create_namespace(&other, HashMap::from([("k".into(), "v".into())]))
+            .await
+            .unwrap();
+        assert!(catalog
+            .create_namespace(&other, HashMap::new())
+            .await
+            .is_err());
+        assert_eq!(catalog.list_namespaces(&[]).await.unwrap().len(), 2);
+        assert!(catalog.list_namespaces(&other).await.unwrap().is_empty());
+        catalog
+            .update_namespace_properties(
+                &other,
+                &["k".to_string()],
+                HashMap::from([("a".into(), "b".into())]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            catalog.load_namespace_properties(&other).await.unwrap(),
+            HashMap::from([("a".to_string(), "b".to_string())])
+        );
+        assert!(catalog.drop_namespace(&namespace).await.is_err());
+        catalog.drop_namespace(&other).await.unwrap();
+        assert_eq!(
+            catalog.list_namespaces(&[]).await.unwrap(),
+            vec![namespace.to_vec()]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! catalog module provides the catalogs that can be used to manage iceberg
+//! tables.
+//!
+//! Every catalog implements [`Catalog`], so engines can manage tables
+//! without knowing the catalog behind them, and plug in their own catalogs.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use async_trait::async_trait;
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::types::TableMetadata;
+use crate::{Error, ErrorKind, Result, Table};
+
+mod fs;
+pub use fs::FileSystemCatalog;
+
+#[cfg(feature = "glue")]
+mod glue;
+#[cfg(feature = "glue")]
+pub use glue::GlueCatalog;
+
+mod sql;
+pub use sql::{SqlCatalog, SqlConnection, SqlDialect};
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteConnection;
+
+#[cfg(feature = "rest-catalog")]
+mod rest;
+#[cfg(feature = "rest-catalog")]
+pub use rest::RestCatalog;
+
+#[cfg(feature = "rest-server")]
+pub mod rest_server;
+#[cfg(feature = "rest-server")]
+pub use rest_server::RestCatalogServer;
+
+/// TableIdentifier identifies a table in a catalog by its namespace and
+/// name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableIdentifier {
+    /// Levels of the namespace, like `["db"]`.
+    pub namespace: Vec<String>,
+    /// Name of the table in the namespace.
+    pub name: String,
+}
+
+impl TableIdentifier {
+    /// Create a new identifier of table `name` in the namespace.
+    pub fn new(
+        namespace: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            namespace: namespace.into_iter().map(Into::into).collect(),
+            name: name.into(),
+        }
+    }
+}
+
+impl Display for TableIdentifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for level in &self.namespace {
+            write!(f, "{level}.")?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Catalog manages namespaces and tables identified by [`TableIdentifier`].
+///
+/// Tables loaded from a catalog commit through [`Catalog::update_table`] of
+/// the same catalog, which must swap the table metadata atomically.
+///
+/// Namespaces are levels like `["db"]`. Operations of namespaces are not
+/// supported unless implemented by the catalog.
+#[async_trait]
+pub trait Catalog: Send + Sync {
+    /// List namespaces directly under `parent`, or top level namespaces if
+    /// `parent` is empty.
+    async fn list_namespaces(&self, _parent: &[String]) -> Result<Vec<Vec<String>>> {
+        Err(unsupported("Listing namespaces"))
+    }
+
+    /// Create a namespace with given properties.
+    async fn create_namespace(
+        &self,
+        _namespace: &[String],
+        _properties: HashMap<String, String>,
+    ) -> Result<()> {
+        Err(unsupported("Creating namespaces"))
+    }
+
+    /// Load properties of the namespace.
+    async fn load_namespace_properties(
+        &self,
+        _namespace: &[String],
+    ) -> Result<HashMap<String, String>> {
+        Err(unsupported("Loading namespaces"))
+    }
+
+    /// Remove properties of `removals` and set properties of `updates` of
+    /// the namespace.
+    async fn update_namespace_properties(
+        &self,
+        _namespace: &[String],
+        _removals: &[String],
+        _updates: HashMap<String, String>,
+    ) -> Result<()> {
+        Err(unsupported("Updating namespace properties"))
+    }
+
+    /// Drop the namespace, which must contain no tables.
+    async fn drop_namespace(&self, _namespace: &[String]) -> Result<()> {
+        Err(unsupported("Dropping namespaces"))
+    }
+
+    /// List tables in the namespace.
+    async fn list_tables(&self, namespace: &[String]) -> Result<Vec<TableIdentifier>>;
+
+    /// Create a table of given metadata, returns the created table.
+    ///
+    /// Catalogs may decide the table location, ignoring
+    /// [`TableMetadata::location`].
+    async fn create_table(&self, table: &TableIdentifier, metadata: TableMetadata)
+        -> Result<Table>;
+
+    /// Load the table.
+    async fn load_table(&self, table: &TableIdentifier) -> Result<Table>;
+
+    /// Register a table whose current metadata file is at
+    /// `metadata_location`, returns the registered table.
+    ///
+    /// The metadata file is used as is, so tables created by other engines
+    /// can be imported into the catalog.
+    async fn register_table(
+        &self,
+        _table: &TableIdentifier,
+        _metadata_location: &str,
+    ) -> Result<Table> {
+        Err(unsupported("Registering tables"))
+    }
+
+    /// Drop the table from the catalog.
+    async fn drop_table(&self, table: &TableIdentifier) -> Result<()>;
+
+    /// Rename the table.
+    async fn rename_table(&self, from: &TableIdentifier, to: &TableIdentifier) -> Result<()>;
+
+    /// Commit changes of the table from `base` at `base_location` to
+    /// `next`, returns the location and content of committed metadata.
+    ///
+    /// The commit must fail if the table has been updated since `base`.
+    async fn update_table(
+        &self,
+        table: &TableIdentifier,
+        base_location: &str,
+        base: &TableMetadata,
+        next: &TableMetadata,
+    ) -> Result<(String, TableMetadata)>;
+}
+
+fn unsupported(operation: &str) -> Error {
+    Error::new(
+        ErrorKind::IcebergFeatureUnsupported,
+        format!("{operation} of the catalog"),
+    )
+}
+
+/// Metadata files written by catalog commits are like
+/// `00001-{uuid}.metadata.json`, while hadoop tables write `v1.metadata.json`.
+const METADATA_FILE_VERSION_PATTERN: &str = r"^v?([0-9]+)[-.].*metadata\.json$";
+
+/// Split metadata location into the table location and path of the metadata
+/// file relative to it.
+pub(crate) fn split_metadata_location(location: &str) -> Result<(&str, &str)> {
+    match location.rsplit_once("/metadata/") {
+        Some((table_location, name)) if !name.contains('/') => {
+            Ok((table_location, &location[table_location.len() + 1..]))
+        }
+        _ => Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Invalid metadata location {location}"),
+        )),
+    }
+}
+
+/// Returns the path of the first metadata file of a table created by a
+/// catalog, relative to the table location.
+pub(crate) fn first_metadata_path() -> String {
+    format!("metadata/00000-{}.metadata.json", Uuid::new_v4())
+}
+
+/// Returns the path of the metadata file committed after the one at
+/// `base_location`, relative to the table location.
+pub(crate) fn next_metadata_path(base_location: &str) -> Result<String> {
+    let (_, base_path) = split_metadata_location(base_location)?;
+    let version = Regex::new(METADATA_FILE_VERSION_PATTERN)?
+        .captures(base_path.trim_start_matches("metadata/"))
+        .map(|v| v[1].parse::<i64>())
+        .transpose()?
+        .unwrap_or(0);
+    Ok(format!(
+        "metadata/{:05}-{}.metadata.json",
+        version + 1,
+        Uuid::new_v4()
+    ))
+}
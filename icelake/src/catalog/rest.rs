@@ -0,0 +1,847 @@
+//! rest module provides [`RestCatalog`], a client of the
+//! [Iceberg REST catalog protocol](https://github.com/apache/iceberg/blob/master/open-api/rest-catalog-open-api.yaml).
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{Catalog, TableIdentifier};
+use crate::config::Config;
+use crate::io::storage::build_operator;
+use crate::types::{
+    parse_table_metadata, serialize_schema, serialize_snapshot, serialize_sort_order,
+    serialize_statistics_file, serialize_table_meta, TableMetadata,
+};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// Separator of namespace levels in urls.
+const NAMESPACE_SEPARATOR: &str = "\u{1f}";
+
+/// RestCatalog loads tables from an iceberg rest catalog, commits of the
+/// loaded tables are sent to the catalog as table requirements and updates,
+/// instead of writing metadata files and version hint.
+///
+/// Storage of loaded tables is inferred from their locations, configured by
+/// the config of this catalog and the config returned with the table, see
+/// [`crate::io::storage`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// use icelake::catalog::{Catalog, RestCatalog, TableIdentifier};
+///
+/// let catalog = RestCatalog::try_new("http://127.0.0.1:8181")?;
+/// let mut table = catalog.load_table(&TableIdentifier::new(["db"], "table")).await?;
+/// let mut tx = table.new_transaction();
+/// tx.set_properties([("k".to_string(), "v".to_string())].into());
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RestCatalog {
+    uri: Url,
+    prefix: Option<String>,
+    client: Client,
+    config: Config,
+}
+
+impl RestCatalog {
+    /// Create a new client of the rest catalog at `uri`, like
+    /// `http://127.0.0.1:8181`.
+    pub fn try_new(uri: &str) -> Result<Self> {
+        Ok(Self {
+            uri: Url::parse(uri)?,
+            prefix: None,
+            client: Client::new(),
+            config: Config::new(),
+        })
+    }
+
+    /// Set the prefix of all routes, which is returned by `GET /v1/config`
+    /// of catalogs that serve several warehouses.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set the config of loaded tables, see [`Table::set_config`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    fn request(&self, method: Method, path: &[&str]) -> Result<RequestBuilder> {
+        let mut url = self.uri.clone();
+        url.path_segments_mut()
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid rest catalog uri {}", self.uri),
+                )
+            })?
+            .pop_if_empty()
+            .push("v1")
+            .extend(self.prefix.as_deref())
+            .extend(path);
+        Ok(self.client.request(method, url))
+    }
+
+    fn namespace_request(
+        &self,
+        method: Method,
+        namespace: &[String],
+        path: &[&str],
+    ) -> Result<RequestBuilder> {
+        let namespace = namespace.join(NAMESPACE_SEPARATOR);
+        let mut segments = vec!["namespaces", namespace.as_str()];
+        segments.extend(path);
+        self.request(method, &segments)
+    }
+
+    fn table_request(&self, method: Method, table: &TableIdentifier) -> Result<RequestBuilder> {
+        self.namespace_request(method, &table.namespace, &["tables", &table.name])
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(&self, request: RequestBuilder) -> Result<T> {
+        Ok(serde_json::from_slice(&self.execute(request).await?)?)
+    }
+
+    /// Send the request, returns the body of successful responses.
+    async fn execute(&self, request: RequestBuilder) -> Result<Bytes> {
+        let response = request.send().await?;
+        let status = response.status();
+        let url = response.url().to_string();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            // Errors are like `{"error": {"message": "", "type": "", "code": 404}}`.
+            let error: Value = serde_json::from_slice(&body).unwrap_or_default();
+            let message = error["error"]["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string();
+            let typ = error["error"]["type"].as_str().unwrap_or_default();
+            // Requirements of a commit are not met.
+            let kind = if status == StatusCode::CONFLICT && typ == "CommitFailedException" {
+                ErrorKind::CommitConflict
+            } else if status == StatusCode::NOT_FOUND {
+                ErrorKind::NotFound
+            } else {
+                ErrorKind::Unexpected
+            };
+            return Err(
+                Error::new(kind, format!("Rest catalog request failed: {message}"))
+                    .with_context("url", url)
+                    .with_context("status", status.as_str())
+                    .with_context("type", typ),
+            );
+        }
+        Ok(body)
+    }
+
+    /// Build the table of a load table response.
+    fn table(&self, table: &TableIdentifier, response: TableResponse) -> Result<Table> {
+        // Storage configs returned with the table, like vended credentials,
+        // take precedence.
+        let mut props = self.config.overrides();
+        props.extend(response.config.clone());
+        let (metadata_location, metadata) = response.into_metadata()?;
+        let op = build_operator(&metadata.location, &props)?;
+
+        let mut table = Table::with_catalog(
+            op,
+            Arc::new(self.clone()),
+            table.clone(),
+            metadata_location,
+            metadata,
+        )?;
+        table.set_config(self.config.clone());
+        Ok(table)
+    }
+}
+
+#[async_trait]
+impl Catalog for RestCatalog {
+    async fn list_namespaces(&self, parent: &[String]) -> Result<Vec<Vec<String>>> {
+        #[derive(Deserialize)]
+        struct Response {
+            namespaces: Vec<Vec<String>>,
+        }
+
+        let mut request = self.request(Method::GET, &["namespaces"])?;
+        if !parent.is_empty() {
+            request = request.query(&[("parent", parent.join(NAMESPACE_SEPARATOR))]);
+        }
+        let response: Response = self.send(request).await?;
+        Ok(response.namespaces)
+    }
+
+    async fn create_namespace(
+        &self,
+        namespace: &[String],
+        properties: HashMap<String, String>,
+    ) -> Result<()> {
+        let request = self
+            .request(Method::POST, &["namespaces"])?
+            .json(&json!({ "namespace": namespace, "properties": properties }));
+        self.execute(request).await?;
+        Ok(())
+    }
+
+    async fn load_namespace_properties(
+        &self,
+        namespace: &[String],
+    ) -> Result<HashMap<String, String>> {
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(default)]
+            properties: HashMap<String, String>,
+        }
+
+        let request = self.namespace_request(Method::GET, namespace, &[])?;
+        let response: Response = self.send(request).await?;
+        Ok(response.properties)
+    }
+
+    async fn update_namespace_properties(
+        &self,
+        namespace: &[String],
+        removals: &[String],
+        updates: HashMap<String, String>,
+    ) -> Result<()> {
+        let request = self
+            .namespace_request(Method::POST, namespace, &["properties"])?
+            .json(&json!({ "removals": removals, "updates": updates }));
+        self.execute(request).await?;
+        Ok(())
+    }
+
+    async fn drop_namespace(&self, namespace: &[String]) -> Result<()> {
+        let request = self.namespace_request(Method::DELETE, namespace, &[])?;
+        self.execute(request).await?;
+        Ok(())
+    }
+
+    async fn list_tables(&self, namespace: &[String]) -> Result<Vec<TableIdentifier>> {
+        #[derive(Deserialize)]
+        struct Identifier {
+            namespace: Vec<String>,
+            name: String,
+        }
+        #[derive(Deserialize)]
+        struct Response {
+            identifiers: Vec<Identifier>,
+        }
+
+        let request = self.namespace_request(Method::GET, namespace, &["tables"])?;
+        let response: Response = self.send(request).await?;
+        Ok(response
+            .identifiers
+            .into_iter()
+            .map(|v| TableIdentifier::new(v.namespace, v.name))
+            .collect())
+    }
+
+    async fn create_table(
+        &self,
+        table: &TableIdentifier,
+        metadata: TableMetadata,
+    ) -> Result<Table> {
+        let metadata: Value = serde_json::from_str(&serialize_table_meta(metadata)?)?;
+        // Current schema, default partition spec and sort order.
+        let find = |list: &str, id: &str, current: &str| {
+            metadata[list]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|v| v[id] == metadata[current])
+                .cloned()
+        };
+        let mut request = json!({
+            "name": table.name,
+            "schema": find("schemas", "schema-id", "current-schema-id"),
+            "partition-spec": find("partition-specs", "spec-id", "default-spec-id"),
+            "write-order": find("sort-orders", "order-id", "default-sort-order-id"),
+            "properties": metadata["properties"],
+        });
+        if metadata["location"].as_str().is_some_and(|v| !v.is_empty()) {
+            request["location"] = metadata["location"].clone();
+        }
+
+        let request = self
+            .namespace_request(Method::POST, &table.namespace, &["tables"])?
+            .json(&request);
+        let response: TableResponse = self.send(request).await?;
+        self.table(table, response)
+    }
+
+    async fn register_table(
+        &self,
+        table: &TableIdentifier,
+        metadata_location: &str,
+    ) -> Result<Table> {
+        let request = self
+            .namespace_request(Method::POST, &table.namespace, &["register"])?
+            .json(&json!({ "name": table.name, "metadata-location": metadata_location }));
+        let response: TableResponse = self.send(request).await?;
+        self.table(table, response)
+    }
+
+    async fn load_table(&self, table: &TableIdentifier) -> Result<Table> {
+        let response: TableResponse = self.send(self.table_request(Method::GET, table)?).await?;
+        self.table(table, response)
+    }
+
+    async fn drop_table(&self, table: &TableIdentifier) -> Result<()> {
+        self.execute(self.table_request(Method::DELETE, table)?)
+            .await?;
+        Ok(())
+    }
+
+    async fn rename_table(&self, from: &TableIdentifier, to: &TableIdentifier) -> Result<()> {
+        let request = self
+            .request(Method::POST, &["tables", "rename"])?
+            .json(&json!({
+                "source": { "namespace": from.namespace, "name": from.name },
+                "destination": { "namespace": to.namespace, "name": to.name },
+            }));
+        self.execute(request).await?;
+        Ok(())
+    }
+
+    async fn update_table(
+        &self,
+        table: &TableIdentifier,
+        _base_location: &str,
+        base: &TableMetadata,
+        next: &TableMetadata,
+    ) -> Result<(String, TableMetadata)> {
+        let (requirements, updates) = table_changes(base, next)?;
+        let request = self.table_request(Method::POST, table)?.json(&json!({
+            "requirements": requirements,
+            "updates": updates,
+        }));
+        let response: TableResponse = self.send(request).await?;
+        response.into_metadata()
+    }
+}
+
+/// Response of loading and committing tables.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TableResponse {
+    metadata_location: String,
+    metadata: Value,
+    #[serde(default)]
+    config: HashMap<String, String>,
+}
+
+impl TableResponse {
+    fn into_metadata(self) -> Result<(String, TableMetadata)> {
+        let metadata = parse_table_metadata(&serde_json::to_vec(&self.metadata)?)?;
+        Ok((self.metadata_location, metadata))
+    }
+}
+
+/// Returns requirements and updates of the commit from `base` to `next`.
+///
+/// Besides the table uuid, the commit requires refs it changes to be
+/// unchanged since `base`, so that concurrent commits of the same branch
+/// are rejected by the catalog.
+fn table_changes(base: &TableMetadata, next: &TableMetadata) -> Result<(Vec<Value>, Vec<Value>)> {
+    if base.partition_specs != next.partition_specs || base.default_spec_id != next.default_spec_id
+    {
+        return Err(Error::new(
+            ErrorKind::IcebergFeatureUnsupported,
+            "Committing partition spec changes through rest catalog",
+        ));
+    }
+
+    let mut requirements = vec![json!({
+        "type": "assert-table-uuid",
+        "uuid": base.table_uuid,
+    })];
+    let mut updates = vec![];
+
+    if base.format_version != next.format_version {
+        updates.push(json!({
+            "action": "upgrade-format-version",
+            "format-version": next.format_version as u8,
+        }));
+    }
+
+    if base.schemas != next.schemas || base.current_schema_id != next.current_schema_id {
+        requirements.push(json!({
+            "type": "assert-current-schema-id",
+            "current-schema-id": base.current_schema_id,
+        }));
+        requirements.push(json!({
+            "type": "assert-last-assigned-field-id",
+            "last-assigned-field-id": base.last_column_id,
+        }));
+        for schema in &next.schemas {
+            if !base.schemas.iter().any(|v| v.schema_id == schema.schema_id) {
+                let schema: Value = serde_json::from_str(&serialize_schema(schema)?)?;
+                updates.push(json!({
+                    "action": "add-schema",
+                    "schema": schema,
+                    "last-column-id": next.last_column_id,
+                }));
+            }
+        }
+        if base.current_schema_id != next.current_schema_id {
+            updates.push(json!({
+                "action": "set-current-schema",
+                "schema-id": next.current_schema_id,
+            }));
+        }
+    }
+
+    if base.sort_orders != next.sort_orders
+        || base.default_sort_order_id != next.default_sort_order_id
+    {
+        requirements.push(json!({
+            "type": "assert-default-sort-order-id",
+            "default-sort-order-id": base.default_sort_order_id,
+        }));
+        for sort_order in &next.sort_orders {
+            if !base
+                .sort_orders
+                .iter()
+                .any(|v| v.order_id == sort_order.order_id)
+            {
+                let sort_order: Value = serde_json::from_str(&serialize_sort_order(sort_order)?)?;
+                updates.push(json!({
+                    "action": "add-sort-order",
+                    "sort-order": sort_order,
+                }));
+            }
+        }
+        if base.default_sort_order_id != next.default_sort_order_id {
+            updates.push(json!({
+                "action": "set-default-sort-order",
+                "sort-order-id": next.default_sort_order_id,
+            }));
+        }
+    }
+
+    // Snapshots must be added before refs pointing to them.
+    let base_snapshots: HashSet<i64> = base
+        .snapshots
+        .iter()
+        .flatten()
+        .map(|v| v.snapshot_id)
+        .collect();
+    let next_snapshots: HashSet<i64> = next
+        .snapshots
+        .iter()
+        .flatten()
+        .map(|v| v.snapshot_id)
+        .collect();
+    for snapshot in next.snapshots.iter().flatten() {
+        if !base_snapshots.contains(&snapshot.snapshot_id) {
+            let snapshot: Value = serde_json::from_str(&serialize_snapshot(snapshot.clone())?)?;
+            updates.push(json!({
+                "action": "add-snapshot",
+                "snapshot": snapshot,
+            }));
+        }
+    }
+    let mut removed_snapshots: Vec<i64> = base_snapshots
+        .difference(&next_snapshots)
+        .copied()
+        .collect();
+    if !removed_snapshots.is_empty() {
+        removed_snapshots.sort();
+        updates.push(json!({
+            "action": "remove-snapshots",
+            "snapshot-ids": removed_snapshots,
+        }));
+    }
+
+    let mut ref_names: Vec<&String> = base.refs.keys().chain(next.refs.keys()).collect();
+    ref_names.sort();
+    ref_names.dedup();
+    for name in ref_names {
+        let base_ref = base.refs.get(name);
+        let next_ref = next.refs.get(name);
+        if base_ref == next_ref {
+            continue;
+        }
+        requirements.push(json!({
+            "type": "assert-ref-snapshot-id",
+            "ref": name,
+            "snapshot-id": base_ref.map(|v| v.snapshot_id),
+        }));
+        match next_ref {
+            Some(v) => updates.push(json!({
+                "action": "set-snapshot-ref",
+                "ref-name": name,
+                "type": v.typ.to_string(),
+                "snapshot-id": v.snapshot_id,
+                "min-snapshots-to-keep": v.min_snapshots_to_keep,
+                "max-snapshot-age-ms": v.max_snapshot_age_ms,
+                "max-ref-age-ms": v.max_ref_age_ms,
+            })),
+            None => updates.push(json!({
+                "action": "remove-snapshot-ref",
+                "ref-name": name,
+            })),
+        }
+    }
+
+    for statistics_file in &next.statistics {
+        if base.statistics_file(statistics_file.snapshot_id) != Some(statistics_file) {
+            let statistics: Value =
+                serde_json::from_str(&serialize_statistics_file(statistics_file)?)?;
+            updates.push(json!({
+                "action": "set-statistics",
+                "snapshot-id": statistics_file.snapshot_id,
+                "statistics": statistics,
+            }));
+        }
+    }
+    for statistics_file in &base.statistics {
+        // Statistics of removed snapshots are removed with snapshots.
+        if next.statistics_file(statistics_file.snapshot_id).is_none()
+            && next_snapshots.contains(&statistics_file.snapshot_id)
+        {
+            updates.push(json!({
+                "action": "remove-statistics",
+                "snapshot-id": statistics_file.snapshot_id,
+            }));
+        }
+    }
+
+    let base_properties = base.properties.clone().unwrap_or_default();
+    let next_properties = next.properties.clone().unwrap_or_default();
+    let mut removals: Vec<&String> = base_properties
+        .keys()
+        .filter(|k| !next_properties.contains_key(*k))
+        .collect();
+    if !removals.is_empty() {
+        removals.sort();
+        updates.push(json!({
+            "action": "remove-properties",
+            "removals": removals,
+        }));
+    }
+    let changed: serde_json::Map<String, Value> = next_properties
+        .iter()
+        .filter(|(k, v)| base_properties.get(*k) != Some(v))
+        .map(|(k, v)| (k.clone(), Value::from(v.clone())))
+        .collect();
+    if !changed.is_empty() {
+        updates.push(json!({
+            "action": "set-properties",
+            "updates": changed,
+        }));
+    }
+
+    if base.location != next.location {
+        updates.push(json!({
+            "action": "set-location",
+            "location": next.location,
+        }));
+    }
+
+    Ok((requirements, updates))
+}
+
+#[cfg(all(test, feature = "rest-server"))]
+mod tests {
+    use std::fs;
+    use std::net::TcpListener;
+
+    use axum::extract::Path;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::catalog::RestCatalogServer;
+    use crate::transaction::{UpdateSchema, UpdateSortOrder};
+    use crate::types::{Any, Primitive, StatisticsFile};
+
+    /// Copy simple table into `{warehouse}/db/simple_table`, with its
+    /// location in metadata files pointing to the copy.
+    fn prepare_warehouse() -> TempDir {
+        let tmp_dir = TempDir::new().unwrap();
+        let src = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let dst = tmp_dir.path().join("db").join("simple_table");
+        for dir in ["metadata", "data"] {
+            fs::create_dir_all(dst.join(dir)).unwrap();
+            for entry in fs::read_dir(format!("{src}/{dir}")).unwrap() {
+                let entry = entry.unwrap();
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.ends_with(".metadata.json") {
+                    let content = fs::read_to_string(entry.path()).unwrap().replace(
+                        "/opt/bitnami/spark/warehouse/db/table",
+                        dst.to_str().unwrap(),
+                    );
+                    fs::write(dst.join(dir).join(name), content).unwrap();
+                } else {
+                    fs::copy(entry.path(), dst.join(dir).join(name)).unwrap();
+                }
+            }
+        }
+        tmp_dir
+    }
+
+    fn serve(warehouse: &TempDir) -> RestCatalog {
+        serve_with(warehouse, Router::new())
+    }
+
+    /// Serve the warehouse with extra routes, which the server doesn't
+    /// support.
+    fn serve_with(warehouse: &TempDir, routes: Router) -> RestCatalog {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = RestCatalogServer::try_new(warehouse.path().to_str().unwrap())
+            .unwrap()
+            .router()
+            .merge(routes);
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+        RestCatalog::try_new(&format!("http://{addr}")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rest_catalog_list() {
+        let warehouse = prepare_warehouse();
+        let catalog = serve(&warehouse);
+
+        assert_eq!(
+            catalog.list_namespaces(&[]).await.unwrap(),
+            vec![vec!["db".to_string()]]
+        );
+        assert_eq!(
+            catalog.list_tables(&["db".to_string()]).await.unwrap(),
+            vec![TableIdentifier::new(["db"], "simple_table")]
+        );
+        let err = catalog.list_tables(&["unknown".to_string()]).await;
+        assert!(err.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rest_catalog_namespaces() {
+        let warehouse = prepare_warehouse();
+        let catalog = serve(&warehouse);
+        let namespace = vec!["db".to_string(), "s".to_string()];
+
+        catalog
+            .create_namespace(&namespace, HashMap::new())
+            .await
+            .unwrap();
+        assert!(catalog
+            .create_namespace(&namespace, HashMap::new())
+            .await
+            .is_err());
+        assert_eq!(
+            catalog.list_namespaces(&namespace[..1]).await.unwrap(),
+            vec![namespace.clone()]
+        );
+        assert!(catalog
+            .load_namespace_properties(&namespace)
+            .await
+            .unwrap()
+            .is_empty());
+        // The server doesn't keep properties of namespaces.
+        assert!(catalog
+            .update_namespace_properties(&namespace, &[], HashMap::from([("k".into(), "v".into())]))
+            .await
+            .is_err());
+
+        assert!(catalog.drop_namespace(&namespace[..1]).await.is_err());
+        catalog.drop_namespace(&namespace).await.unwrap();
+        assert!(catalog.load_namespace_properties(&namespace).await.is_err());
+        assert!(catalog
+            .list_namespaces(&namespace[..1])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rest_catalog_register_table() {
+        let warehouse = prepare_warehouse();
+        // Register requests are recorded, and answered by loading the
+        // metadata file, a registered location is rejected.
+        let requests: Arc<std::sync::Mutex<Vec<Value>>> = Arc::default();
+        let recorded = requests.clone();
+        let register = move |Path(namespace): Path<String>, Json(body): Json<Value>| async move {
+            let mut requests = recorded.lock().unwrap();
+            if requests.contains(&body) {
+                let error = json!({"error": {
+                    "message": format!("Table already exists in {namespace}"),
+                    "type": "AlreadyExistsException",
+                    "code": 409,
+                }});
+                return (StatusCode::CONFLICT, Json(error));
+            }
+            requests.push(body.clone());
+            let location = body["metadata-location"].as_str().unwrap();
+            let metadata: Value = serde_json::from_slice(&fs::read(location).unwrap()).unwrap();
+            let response = json!({
+                "metadata-location": location,
+                "metadata": metadata,
+                "config": {},
+            });
+            (StatusCode::OK, Json(response))
+        };
+        let catalog = serve_with(
+            &warehouse,
+            Router::new().route("/v1/namespaces/:namespace/register", post(register)),
+        );
+
+        let identifier = TableIdentifier::new(["db"], "registered");
+        let metadata_location = warehouse
+            .path()
+            .join("db/simple_table/metadata/v2.metadata.json");
+        let metadata_location = metadata_location.to_str().unwrap();
+        let table = catalog
+            .register_table(&identifier, metadata_location)
+            .await
+            .unwrap();
+        assert_eq!(
+            requests.lock().unwrap().as_slice(),
+            &[json!({ "name": "registered", "metadata-location": metadata_location })]
+        );
+        assert_eq!(table.identifier(), Some(&identifier));
+        assert_eq!(table.current_metadata_file_location(), metadata_location);
+        assert_eq!(
+            table.current_table_metadata().location,
+            warehouse.path().join("db/simple_table").to_str().unwrap()
+        );
+
+        let Err(err) = catalog.register_table(&identifier, metadata_location).await else {
+            panic!("registering a registered table should fail");
+        };
+        assert!(err
+            .to_string()
+            .contains("Rest catalog request failed: Table already exists in db"));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rest_catalog_load_and_commit() {
+        let warehouse = prepare_warehouse();
+        let catalog = serve(&warehouse);
+        let identifier = TableIdentifier::new(["db"], "simple_table");
+
+        let mut table = catalog.load_table(&identifier).await.unwrap();
+        let location = warehouse.path().join("db").join("simple_table");
+        let location = location.to_str().unwrap();
+        assert_eq!(
+            table.current_metadata_file_location(),
+            format!("{location}/metadata/v2.metadata.json")
+        );
+
+        let mut stale = catalog.load_table(&identifier).await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.set_properties([("k".to_string(), "v".to_string())].into());
+        tx.commit().await.unwrap();
+        assert_eq!(
+            table.current_metadata_file_location(),
+            format!("{location}/metadata/v3.metadata.json")
+        );
+        assert_eq!(table.properties().get("k").map(String::as_str), Some("v"));
+
+        // Committed by the catalog.
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert_eq!(
+            reloaded.current_table_metadata(),
+            table.current_table_metadata()
+        );
+
+        // Property changes of a stale table don't conflict, the catalog
+        // applies them to its current metadata.
+        let mut tx = stale.new_transaction();
+        tx.set_properties([("k2".to_string(), "v2".to_string())].into());
+        tx.commit().await.unwrap();
+        assert_eq!(stale.properties().get("k").map(String::as_str), Some("v"));
+        assert_eq!(stale.properties().get("k2").map(String::as_str), Some("v2"));
+
+        // Schema changes require the schema to be unchanged since base.
+        let mut tx = stale.new_transaction();
+        tx.update_schema(UpdateSchema::new().add_column(
+            None,
+            "ts",
+            Any::Primitive(Primitive::Timestamp),
+        ));
+        tx.commit().await.unwrap();
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        let metadata = reloaded.current_table_metadata();
+        assert_eq!(metadata.current_schema_id, 1);
+        assert_eq!(metadata.last_column_id, 3);
+        assert_eq!(metadata.current_schema().unwrap().fields[2].name, "ts");
+        // The conflicting schema change is retried on the refreshed table.
+        let mut tx = table.new_transaction();
+        tx.update_schema(UpdateSchema::new().delete_column("data"));
+        tx.commit().await.unwrap();
+        let fields = &table
+            .current_table_metadata()
+            .current_schema()
+            .unwrap()
+            .fields;
+        assert_eq!(
+            fields.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(),
+            vec!["id", "ts"]
+        );
+
+        let mut tx = stale.new_transaction();
+        tx.update_sort_order(UpdateSortOrder::new().desc("id"));
+        tx.commit().await.unwrap();
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert_eq!(reloaded.current_table_metadata().default_sort_order_id, 1);
+        assert_eq!(reloaded.current_table_metadata().sort_orders.len(), 2);
+
+        // Moving main branch from a stale snapshot is rejected.
+        let mut base = stale.current_table_metadata().clone();
+        base.refs.get_mut("main").unwrap().snapshot_id += 1;
+        let mut next = base.clone();
+        next.refs.remove("main");
+        let (requirements, updates) = table_changes(&base, &next).unwrap();
+        assert_eq!(requirements[1]["type"], "assert-ref-snapshot-id");
+        assert_eq!(updates[0]["action"], "remove-snapshot-ref");
+        assert!(catalog
+            .update_table(&identifier, "", &base, &next)
+            .await
+            .is_err());
+
+        // Statistics files are set and removed by snapshot ids.
+        let snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+        let statistics_file = StatisticsFile {
+            snapshot_id,
+            statistics_path: format!("{location}/metadata/stats.puffin"),
+            file_size_in_bytes: 100,
+            file_footer_size_in_bytes: 50,
+            key_metadata: None,
+            blob_metadata: vec![],
+        };
+        let mut tx = table.new_transaction();
+        tx.set_statistics(statistics_file.clone());
+        tx.commit().await.unwrap();
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert_eq!(
+            reloaded.current_table_metadata().statistics,
+            vec![statistics_file]
+        );
+        let mut tx = table.new_transaction();
+        tx.remove_statistics(snapshot_id);
+        tx.commit().await.unwrap();
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert!(reloaded.current_table_metadata().statistics.is_empty());
+    }
+}
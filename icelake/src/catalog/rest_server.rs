@@ -0,0 +1,915 @@
+//! rest_server module provides a small server that exposes tables in a
+//! filesystem warehouse through the
+//! [Iceberg REST catalog protocol](https://github.com/apache/iceberg/blob/master/open-api/rest-catalog-open-api.yaml).
+//!
+//! The warehouse is expected to have the following layout, and every table
+//! is a hadoop style table that can be opened by [`Table::open`]:
+//!
+//! ```text
+//! {warehouse}/{namespace}/.../{table}/metadata/v{n}.metadata.json
+//! ```
+//!
+//! Supported endpoints:
+//!
+//! - `GET /v1/config`
+//! - `GET /v1/namespaces`, `POST /v1/namespaces`
+//! - `GET /v1/namespaces/{namespace}`, `HEAD /v1/namespaces/{namespace}`,
+//!   `DELETE /v1/namespaces/{namespace}`
+//! - `GET /v1/namespaces/{namespace}/tables`
+//! - `GET /v1/namespaces/{namespace}/tables/{table}`, `HEAD /v1/namespaces/{namespace}/tables/{table}`
+//! - `POST /v1/namespaces/{namespace}/tables/{table}`
+//!
+//! Table creation, renaming and dropping are not supported yet.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::StreamExt;
+use opendal::services::Fs;
+use opendal::Operator;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::types::{
+    parse_schema, parse_snapshot, parse_sort_order, parse_statistics_file, serialize_table_meta,
+    SnapshotReference, SnapshotReferenceType, TableMetadata,
+};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// Separator of namespace levels in url path, defined by REST catalog spec.
+const NAMESPACE_SEPARATOR: char = '\u{1f}';
+
+/// RestCatalogServer serves tables under a filesystem warehouse.
+#[derive(Clone)]
+pub struct RestCatalogServer {
+    warehouse: String,
+    op: Operator,
+    /// Config of tables served by this server.
+    config: Config,
+    /// Commits are serialized inside one server.
+    commit_lock: Arc<Mutex<()>>,
+}
+
+impl RestCatalogServer {
+    /// Create a new server over the warehouse at given local path.
+    pub fn try_new(warehouse: impl Into<String>) -> Result<Self> {
+        let warehouse = warehouse.into().trim_end_matches('/').to_string();
+
+        let mut builder = Fs::default();
+        builder.root(&warehouse);
+        let op = Operator::new(builder)?.finish();
+
+        Ok(Self {
+            warehouse,
+            op,
+            config: Config::new(),
+            commit_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Configure the config of served tables.
+    ///
+    /// Its overrides are also returned to clients by `/v1/config`.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Build the router of this server, it can be nested into other axum
+    /// applications.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/v1/config", get(get_config))
+            .route(
+                "/v1/namespaces",
+                get(list_namespaces).post(create_namespace),
+            )
+            .route(
+                "/v1/namespaces/:namespace",
+                get(load_namespace)
+                    .head(namespace_exists)
+                    .delete(drop_namespace),
+            )
+            .route("/v1/namespaces/:namespace/tables", get(list_tables))
+            .route(
+                "/v1/namespaces/:namespace/tables/:table",
+                get(load_table).head(table_exists).post(commit_table),
+            )
+            .with_state(self)
+    }
+
+    /// Serve the catalog at given address until the server is shut down.
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        axum::Server::bind(&addr)
+            .serve(self.router().into_make_service())
+            .await
+            .map_err(|e| {
+                Error::new(ErrorKind::Unexpected, "rest catalog server failed").set_source(e)
+            })
+    }
+
+    fn namespace_path(namespace: &[String]) -> String {
+        format!("{}/", namespace.join("/"))
+    }
+
+    fn table_path(namespace: &[String], table: &str) -> String {
+        format!("{}{table}/", Self::namespace_path(namespace))
+    }
+
+    async fn is_table(&self, path: &str) -> Result<bool> {
+        Ok(self.op.is_exist(&format!("{path}metadata/")).await?)
+    }
+
+    /// List the names of sub directories of given path.
+    async fn list_dirs(&self, path: &str) -> Result<Vec<String>> {
+        let mut lister = self.op.list(path).await?;
+        let mut dirs = vec![];
+        while let Some(entry) = lister.next().await {
+            let entry = entry?;
+            // Dir entries in opendal always end with `/`.
+            if !entry.path().ends_with('/') || entry.path() == path {
+                continue;
+            }
+            dirs.push(entry.name().trim_end_matches('/').to_string());
+        }
+        dirs.sort();
+        Ok(dirs)
+    }
+
+    async fn check_namespace(&self, namespace: &[String]) -> ApiResult<()> {
+        let path = Self::namespace_path(namespace);
+        if namespace.is_empty() || !self.op.is_exist(&path).await? || self.is_table(&path).await? {
+            return Err(ApiError::NoSuchNamespace(namespace.join(".")));
+        }
+        Ok(())
+    }
+
+    async fn open_table(&self, namespace: &[String], table: &str) -> ApiResult<Table> {
+        let path = Self::table_path(namespace, table);
+        if !self.is_table(&path).await? {
+            return Err(ApiError::NoSuchTable(format!(
+                "{}.{table}",
+                namespace.join(".")
+            )));
+        }
+        let mut table = Table::open(&format!("{}/{path}", self.warehouse)).await?;
+        table.set_config(self.config.clone());
+        Ok(table)
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// Errors returned to clients, encoded as REST catalog `ErrorModel`.
+enum ApiError {
+    BadRequest(String),
+    NoSuchNamespace(String),
+    NamespaceNotEmpty(String),
+    NoSuchTable(String),
+    AlreadyExists(String),
+    CommitFailed(String),
+    Unsupported(String),
+    Internal(Error),
+}
+
+impl From<Error> for ApiError {
+    fn from(v: Error) -> Self {
+        match v.kind() {
+            ErrorKind::IcebergFeatureUnsupported => ApiError::Unsupported(v.to_string()),
+            _ => ApiError::Internal(v),
+        }
+    }
+}
+
+impl From<opendal::Error> for ApiError {
+    fn from(v: opendal::Error) -> Self {
+        ApiError::Internal(v.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (code, typ, message) = match self {
+            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "BadRequestException", msg),
+            ApiError::NoSuchNamespace(ns) => (
+                StatusCode::NOT_FOUND,
+                "NoSuchNamespaceException",
+                format!("Namespace does not exist: {ns}"),
+            ),
+            ApiError::NamespaceNotEmpty(ns) => (
+                StatusCode::CONFLICT,
+                "NamespaceNotEmptyException",
+                format!("Namespace is not empty: {ns}"),
+            ),
+            ApiError::NoSuchTable(table) => (
+                StatusCode::NOT_FOUND,
+                "NoSuchTableException",
+                format!("Table does not exist: {table}"),
+            ),
+            ApiError::AlreadyExists(msg) => (StatusCode::CONFLICT, "AlreadyExistsException", msg),
+            ApiError::CommitFailed(msg) => (StatusCode::CONFLICT, "CommitFailedException", msg),
+            ApiError::Unsupported(msg) => (
+                StatusCode::NOT_ACCEPTABLE,
+                "UnsupportedOperationException",
+                msg,
+            ),
+            ApiError::Internal(err) => {
+                log::error!("rest catalog server internal error: {err:?}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "ServiceFailureException",
+                    err.to_string(),
+                )
+            }
+        };
+
+        let body = json!({
+            "error": {
+                "message": message,
+                "type": typ,
+                "code": code.as_u16(),
+            }
+        });
+        (code, Json(body)).into_response()
+    }
+}
+
+fn parse_namespace(namespace: &str) -> ApiResult<Vec<String>> {
+    let levels: Vec<String> = namespace
+        .split(NAMESPACE_SEPARATOR)
+        .map(|v| v.to_string())
+        .collect();
+    if levels
+        .iter()
+        .any(|v| v.is_empty() || v.contains('/') || v == "." || v == "..")
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid namespace: {namespace:?}"
+        )));
+    }
+    Ok(levels)
+}
+
+async fn get_config(State(server): State<RestCatalogServer>) -> Json<Value> {
+    Json(json!({ "defaults": {}, "overrides": server.config.overrides() }))
+}
+
+async fn list_namespaces(
+    State(server): State<RestCatalogServer>,
+    Query(params): Query<HashMap<String, String>>,
+) -> ApiResult<Json<Value>> {
+    let parent = match params.get("parent") {
+        Some(parent) => {
+            let parent = parse_namespace(parent)?;
+            server.check_namespace(&parent).await?;
+            parent
+        }
+        None => vec![],
+    };
+    let parent_path = if parent.is_empty() {
+        "/".to_string()
+    } else {
+        RestCatalogServer::namespace_path(&parent)
+    };
+
+    let mut namespaces = vec![];
+    for dir in server.list_dirs(&parent_path).await? {
+        let mut namespace = parent.clone();
+        namespace.push(dir);
+        if !server
+            .is_table(&RestCatalogServer::namespace_path(&namespace))
+            .await?
+        {
+            namespaces.push(namespace);
+        }
+    }
+
+    Ok(Json(json!({ "namespaces": namespaces })))
+}
+
+#[derive(Deserialize)]
+struct CreateNamespaceRequest {
+    namespace: Vec<String>,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+}
+
+async fn create_namespace(
+    State(server): State<RestCatalogServer>,
+    Json(req): Json<CreateNamespaceRequest>,
+) -> ApiResult<Json<Value>> {
+    let namespace = parse_namespace(&req.namespace.join(&NAMESPACE_SEPARATOR.to_string()))?;
+    if !req.properties.is_empty() {
+        return Err(ApiError::Unsupported(
+            "Namespace properties are not supported".to_string(),
+        ));
+    }
+
+    let path = RestCatalogServer::namespace_path(&namespace);
+    if server.op.is_exist(&path).await? {
+        return Err(ApiError::AlreadyExists(format!(
+            "Namespace already exists: {}",
+            namespace.join(".")
+        )));
+    }
+    server.op.create_dir(&path).await?;
+
+    Ok(Json(json!({ "namespace": namespace, "properties": {} })))
+}
+
+async fn load_namespace(
+    State(server): State<RestCatalogServer>,
+    Path(namespace): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let namespace = parse_namespace(&namespace)?;
+    server.check_namespace(&namespace).await?;
+
+    Ok(Json(json!({ "namespace": namespace, "properties": {} })))
+}
+
+async fn namespace_exists(
+    State(server): State<RestCatalogServer>,
+    Path(namespace): Path<String>,
+) -> ApiResult<StatusCode> {
+    let namespace = parse_namespace(&namespace)?;
+    server.check_namespace(&namespace).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Drop the namespace, which must be empty.
+async fn drop_namespace(
+    State(server): State<RestCatalogServer>,
+    Path(namespace): Path<String>,
+) -> ApiResult<StatusCode> {
+    let namespace = parse_namespace(&namespace)?;
+    server.check_namespace(&namespace).await?;
+
+    let path = RestCatalogServer::namespace_path(&namespace);
+    let mut lister = server.op.list(&path).await?;
+    while let Some(entry) = lister.next().await {
+        if entry?.path() != path {
+            return Err(ApiError::NamespaceNotEmpty(namespace.join(".")));
+        }
+    }
+    server.op.delete(&path).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_tables(
+    State(server): State<RestCatalogServer>,
+    Path(namespace): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let namespace = parse_namespace(&namespace)?;
+    server.check_namespace(&namespace).await?;
+
+    let mut identifiers = vec![];
+    for dir in server
+        .list_dirs(&RestCatalogServer::namespace_path(&namespace))
+        .await?
+    {
+        if server
+            .is_table(&RestCatalogServer::table_path(&namespace, &dir))
+            .await?
+        {
+            identifiers.push(json!({ "namespace": namespace, "name": dir }));
+        }
+    }
+
+    Ok(Json(json!({ "identifiers": identifiers })))
+}
+
+fn load_table_result(table: &Table) -> Result<Value> {
+    let metadata: Value = serde_json::from_str(&serialize_table_meta(
+        table.current_table_metadata().clone(),
+    )?)?;
+
+    Ok(json!({
+        "metadata-location": table.current_metadata_file_location(),
+        "metadata": metadata,
+        "config": {},
+    }))
+}
+
+async fn load_table(
+    State(server): State<RestCatalogServer>,
+    Path((namespace, table)): Path<(String, String)>,
+) -> ApiResult<Json<Value>> {
+    let namespace = parse_namespace(&namespace)?;
+    let table = server.open_table(&namespace, &table).await?;
+
+    Ok(Json(load_table_result(&table)?))
+}
+
+async fn table_exists(
+    State(server): State<RestCatalogServer>,
+    Path((namespace, table)): Path<(String, String)>,
+) -> ApiResult<StatusCode> {
+    let namespace = parse_namespace(&namespace)?;
+    server.open_table(&namespace, &table).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum TableRequirement {
+    AssertCreate,
+    AssertTableUuid {
+        uuid: String,
+    },
+    AssertRefSnapshotId {
+        #[serde(rename = "ref")]
+        reference: String,
+        #[serde(rename = "snapshot-id")]
+        snapshot_id: Option<i64>,
+    },
+    AssertCurrentSchemaId {
+        #[serde(rename = "current-schema-id")]
+        current_schema_id: i32,
+    },
+    AssertLastAssignedFieldId {
+        #[serde(rename = "last-assigned-field-id")]
+        last_assigned_field_id: i32,
+    },
+    AssertDefaultSpecId {
+        #[serde(rename = "default-spec-id")]
+        default_spec_id: i32,
+    },
+    AssertDefaultSortOrderId {
+        #[serde(rename = "default-sort-order-id")]
+        default_sort_order_id: i32,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl TableRequirement {
+    fn check(&self, metadata: &TableMetadata) -> ApiResult<()> {
+        let failed = |msg: String| Err(ApiError::CommitFailed(msg));
+        match self {
+            TableRequirement::AssertCreate => failed("Table already exists".to_string()),
+            TableRequirement::AssertTableUuid { uuid } if uuid != &metadata.table_uuid => failed(
+                format!("Table uuid mismatch: expected {uuid} != {}", metadata.table_uuid),
+            ),
+            TableRequirement::AssertRefSnapshotId {
+                reference,
+                snapshot_id,
+            } => {
+                let current = metadata.refs.get(reference).map(|v| v.snapshot_id);
+                if &current != snapshot_id {
+                    failed(format!(
+                        "Requirement failed: {reference} was {current:?}, expected {snapshot_id:?}"
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            TableRequirement::AssertCurrentSchemaId { current_schema_id }
+                if *current_schema_id != metadata.current_schema_id =>
+            {
+                failed(format!(
+                    "Requirement failed: current schema changed: expected id {current_schema_id} != {}",
+                    metadata.current_schema_id
+                ))
+            }
+            TableRequirement::AssertLastAssignedFieldId {
+                last_assigned_field_id,
+            } if *last_assigned_field_id != metadata.last_column_id => failed(format!(
+                "Requirement failed: last assigned field id changed: expected id {last_assigned_field_id} != {}",
+                metadata.last_column_id
+            )),
+            TableRequirement::AssertDefaultSpecId { default_spec_id }
+                if *default_spec_id != metadata.default_spec_id =>
+            {
+                failed(format!(
+                    "Requirement failed: default partition spec changed: expected id {default_spec_id} != {}",
+                    metadata.default_spec_id
+                ))
+            }
+            TableRequirement::AssertDefaultSortOrderId {
+                default_sort_order_id,
+            } if *default_sort_order_id != metadata.default_sort_order_id => failed(format!(
+                "Requirement failed: default sort order changed: expected id {default_sort_order_id} != {}",
+                metadata.default_sort_order_id
+            )),
+            TableRequirement::Unknown => Err(ApiError::Unsupported(
+                "Unsupported table requirement".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+enum TableUpdate {
+    AddSchema {
+        schema: Value,
+        #[serde(rename = "last-column-id")]
+        last_column_id: i32,
+    },
+    SetCurrentSchema {
+        /// `-1` for the last added schema.
+        #[serde(rename = "schema-id")]
+        schema_id: i32,
+    },
+    AddSortOrder {
+        #[serde(rename = "sort-order")]
+        sort_order: Value,
+    },
+    SetDefaultSortOrder {
+        /// `-1` for the last added sort order.
+        #[serde(rename = "sort-order-id")]
+        sort_order_id: i32,
+    },
+    AddSnapshot {
+        snapshot: Value,
+    },
+    SetSnapshotRef {
+        #[serde(rename = "ref-name")]
+        ref_name: String,
+        #[serde(rename = "type")]
+        typ: String,
+        #[serde(rename = "snapshot-id")]
+        snapshot_id: i64,
+    },
+    RemoveSnapshotRef {
+        #[serde(rename = "ref-name")]
+        ref_name: String,
+    },
+    SetProperties {
+        updates: HashMap<String, String>,
+    },
+    RemoveProperties {
+        removals: Vec<String>,
+    },
+    SetLocation {
+        location: String,
+    },
+    SetStatistics {
+        #[serde(rename = "snapshot-id")]
+        snapshot_id: i64,
+        statistics: Value,
+    },
+    RemoveStatistics {
+        #[serde(rename = "snapshot-id")]
+        snapshot_id: i64,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl TableUpdate {
+    fn apply(self, metadata: &mut TableMetadata) -> ApiResult<()> {
+        match self {
+            TableUpdate::AddSchema {
+                schema,
+                last_column_id,
+            } => {
+                let schema = parse_schema(&serde_json::to_vec(&schema).map_err(Error::from)?)
+                    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                if metadata
+                    .schemas
+                    .iter()
+                    .any(|v| v.schema_id == schema.schema_id)
+                {
+                    return Err(ApiError::CommitFailed(format!(
+                        "Schema {} already exists",
+                        schema.schema_id
+                    )));
+                }
+                metadata.last_column_id = metadata.last_column_id.max(last_column_id);
+                metadata.schemas.push(schema);
+            }
+            TableUpdate::SetCurrentSchema { schema_id } => {
+                let schema_id = match schema_id {
+                    -1 => metadata.schemas.last().map(|v| v.schema_id),
+                    id => metadata
+                        .schemas
+                        .iter()
+                        .find(|v| v.schema_id == id)
+                        .map(|v| v.schema_id),
+                };
+                metadata.current_schema_id = schema_id.ok_or_else(|| {
+                    ApiError::BadRequest("Cannot set current schema to unknown schema".to_string())
+                })?;
+            }
+            TableUpdate::AddSortOrder { sort_order } => {
+                let sort_order =
+                    parse_sort_order(&serde_json::to_vec(&sort_order).map_err(Error::from)?)
+                        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                if metadata
+                    .sort_orders
+                    .iter()
+                    .any(|v| v.order_id == sort_order.order_id)
+                {
+                    return Err(ApiError::CommitFailed(format!(
+                        "Sort order {} already exists",
+                        sort_order.order_id
+                    )));
+                }
+                metadata.sort_orders.push(sort_order);
+            }
+            TableUpdate::SetDefaultSortOrder { sort_order_id } => {
+                let sort_order_id = match sort_order_id {
+                    -1 => metadata.sort_orders.last().map(|v| v.order_id),
+                    id => metadata
+                        .sort_orders
+                        .iter()
+                        .find(|v| v.order_id == id)
+                        .map(|v| v.order_id),
+                };
+                metadata.default_sort_order_id = sort_order_id.ok_or_else(|| {
+                    ApiError::BadRequest(
+                        "Cannot set default sort order to unknown sort order".to_string(),
+                    )
+                })?;
+            }
+            TableUpdate::AddSnapshot { snapshot } => {
+                let snapshot = parse_snapshot(&serde_json::to_vec(&snapshot).map_err(Error::from)?)
+                    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                if snapshot.sequence_number <= metadata.last_sequence_number
+                    && snapshot.parent_snapshot_id.is_some()
+                {
+                    return Err(ApiError::CommitFailed(format!(
+                        "Cannot add snapshot with sequence number {} older than last sequence number {}",
+                        snapshot.sequence_number, metadata.last_sequence_number
+                    )));
+                }
+                metadata.last_updated_ms = snapshot.timestamp_ms;
+                metadata.last_sequence_number = snapshot.sequence_number;
+                metadata
+                    .snapshots
+                    .get_or_insert_with(Vec::new)
+                    .push(snapshot);
+            }
+            TableUpdate::SetSnapshotRef {
+                ref_name,
+                typ,
+                snapshot_id,
+            } => {
+                let typ: SnapshotReferenceType = typ
+                    .parse()
+                    .map_err(|e: Error| ApiError::BadRequest(e.to_string()))?;
+                let snapshot = metadata
+                    .snapshots
+                    .iter()
+                    .flatten()
+                    .find(|v| v.snapshot_id == snapshot_id)
+                    .ok_or_else(|| {
+                        ApiError::BadRequest(format!(
+                            "Cannot set {ref_name} to unknown snapshot: {snapshot_id}"
+                        ))
+                    })?
+                    .clone();
+                if ref_name == "main" {
+                    metadata.current_snapshot_id = Some(snapshot_id);
+                    metadata
+                        .snapshot_log
+                        .get_or_insert_with(Vec::new)
+                        .push(snapshot.log());
+                }
+                metadata
+                    .refs
+                    .insert(ref_name, SnapshotReference::new(snapshot_id, typ));
+            }
+            TableUpdate::RemoveSnapshotRef { ref_name } => {
+                if ref_name == "main" {
+                    metadata.current_snapshot_id = None;
+                }
+                metadata.refs.remove(&ref_name);
+            }
+            TableUpdate::SetProperties { updates } => {
+                metadata
+                    .properties
+                    .get_or_insert_with(HashMap::new)
+                    .extend(updates);
+            }
+            TableUpdate::RemoveProperties { removals } => {
+                if let Some(properties) = metadata.properties.as_mut() {
+                    for key in removals {
+                        properties.remove(&key);
+                    }
+                }
+            }
+            TableUpdate::SetLocation { location } => {
+                metadata.location = location;
+            }
+            TableUpdate::SetStatistics {
+                snapshot_id,
+                statistics,
+            } => {
+                let statistics_file =
+                    parse_statistics_file(&serde_json::to_vec(&statistics).map_err(Error::from)?)
+                        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                if statistics_file.snapshot_id != snapshot_id {
+                    return Err(ApiError::BadRequest(format!(
+                        "Statistics file of snapshot {} is set for snapshot {snapshot_id}",
+                        statistics_file.snapshot_id
+                    )));
+                }
+                metadata.statistics.retain(|v| v.snapshot_id != snapshot_id);
+                metadata.statistics.push(statistics_file);
+            }
+            TableUpdate::RemoveStatistics { snapshot_id } => {
+                metadata.statistics.retain(|v| v.snapshot_id != snapshot_id);
+            }
+            TableUpdate::Unknown => {
+                return Err(ApiError::Unsupported(
+                    "Unsupported table update".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct CommitTableRequest {
+    #[serde(default)]
+    requirements: Vec<TableRequirement>,
+    #[serde(default)]
+    updates: Vec<TableUpdate>,
+}
+
+async fn commit_table(
+    State(server): State<RestCatalogServer>,
+    Path((namespace, table)): Path<(String, String)>,
+    Json(req): Json<CommitTableRequest>,
+) -> ApiResult<Json<Value>> {
+    let namespace = parse_namespace(&namespace)?;
+    let _guard = server.commit_lock.lock().await;
+    let mut table = server.open_table(&namespace, &table).await?;
+
+    let mut metadata = table.current_table_metadata().clone();
+    for requirement in &req.requirements {
+        requirement.check(&metadata)?;
+    }
+    for update in req.updates {
+        update.apply(&mut metadata)?;
+    }
+    metadata.last_updated_ms = metadata
+        .last_updated_ms
+        .max(table.current_table_metadata().last_updated_ms + 1);
+
+    table.commit(metadata).await?;
+
+    let mut result = load_table_result(&table)?;
+    if let Some(result) = result.as_object_mut() {
+        result.remove("config");
+    }
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// Copy simple table into `{warehouse}/db/simple_table`.
+    fn prepare_warehouse() -> TempDir {
+        let tmp_dir = TempDir::new().unwrap();
+        let src = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let dst = tmp_dir.path().join("db").join("simple_table");
+        for dir in ["metadata", "data"] {
+            fs::create_dir_all(dst.join(dir)).unwrap();
+            for entry in fs::read_dir(format!("{src}/{dir}")).unwrap() {
+                let entry = entry.unwrap();
+                fs::copy(entry.path(), dst.join(dir).join(entry.file_name())).unwrap();
+            }
+        }
+        tmp_dir
+    }
+
+    async fn call(router: &Router, method: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bs = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = if bs.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bs).unwrap()
+        };
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_rest_server_namespaces_and_tables() {
+        let warehouse = prepare_warehouse();
+        let router = RestCatalogServer::try_new(warehouse.path().to_str().unwrap())
+            .unwrap()
+            .router();
+
+        let (status, body) = call(&router, "GET", "/v1/namespaces", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "namespaces": [["db"]] }));
+
+        let (status, _) = call(
+            &router,
+            "POST",
+            "/v1/namespaces",
+            json!({ "namespace": ["db2"] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &router,
+            "POST",
+            "/v1/namespaces",
+            json!({ "namespace": ["db2"] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, body) = call(&router, "GET", "/v1/namespaces/db/tables", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({ "identifiers": [{ "namespace": ["db"], "name": "simple_table" }] })
+        );
+
+        let (status, body) = call(
+            &router,
+            "GET",
+            "/v1/namespaces/db/tables/simple_table",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["metadata-location"],
+            "/opt/bitnami/spark/warehouse/db/table/metadata/v2.metadata.json"
+        );
+        assert_eq!(body["metadata"]["last-updated-ms"], 1686911671713i64);
+
+        let (status, body) = call(
+            &router,
+            "GET",
+            "/v1/namespaces/db/tables/unknown",
+            Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["type"], "NoSuchTableException");
+    }
+
+    #[tokio::test]
+    async fn test_rest_server_commit_table() {
+        let warehouse = prepare_warehouse();
+        let router = RestCatalogServer::try_new(warehouse.path().to_str().unwrap())
+            .unwrap()
+            .router();
+
+        let (status, body) = call(
+            &router,
+            "POST",
+            "/v1/namespaces/db/tables/simple_table",
+            json!({
+                "requirements": [{ "type": "assert-current-schema-id", "current-schema-id": 0 }],
+                "updates": [{ "action": "set-properties", "updates": { "k": "v" } }],
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(
+            body["metadata-location"],
+            "/opt/bitnami/spark/warehouse/db/table/metadata/v3.metadata.json"
+        );
+        assert_eq!(body["metadata"]["properties"]["k"], "v");
+
+        let (status, body) = call(
+            &router,
+            "POST",
+            "/v1/namespaces/db/tables/simple_table",
+            json!({
+                "requirements": [{ "type": "assert-ref-snapshot-id", "ref": "main", "snapshot-id": 1 }],
+                "updates": [],
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"]["type"], "CommitFailedException");
+    }
+}
@@ -0,0 +1,947 @@
+//! sql module provides [`SqlCatalog`], which manages iceberg tables in
+//! tables of a relational database laid out like the JDBC catalog of the
+//! java implementation, so small deployments can share a catalog with
+//! Spark and Flink without a hive metastore.
+//!
+//! Tables are rows of `iceberg_tables`, keyed by catalog name, namespace
+//! joined by `.` and table name, with `metadata_location` pointing to the
+//! current metadata file. Commits write a new metadata file, then swap
+//! `metadata_location` by an `UPDATE` matching the base location, so
+//! concurrent commits of the same base can't both succeed.
+//!
+//! Properties of namespaces are rows of `iceberg_namespace_properties`, a
+//! namespace exists if it has been created or contains tables.
+//!
+//! The database is accessed by a [`SqlConnection`]. Sqlite databases are
+//! accessed by `SqliteConnection` with feature `sqlite`, other databases
+//! by implementing it by a connection pool of `sqlx` or any other driver:
+//!
+//! ```ignore
+//! struct SqlxConnection(sqlx::AnyPool);
+//!
+//! #[async_trait]
+//! impl SqlConnection for SqlxConnection {
+//!     fn dialect(&self) -> SqlDialect {
+//!         SqlDialect::Postgres
+//!     }
+//!
+//!     async fn execute(&self, sql: &str, params: &[Option<&str>]) -> Result<u64> {
+//!         let mut query = sqlx::query(sql);
+//!         for param in params {
+//!             query = query.bind(*param);
+//!         }
+//!         let result = query.execute(&self.0).await.map_err(|e| {
+//!             Error::new(ErrorKind::Unexpected, "Failed to execute sql").set_source(e)
+//!         })?;
+//!         Ok(result.rows_affected())
+//!     }
+//!
+//!     // `query` fetches rows and gets columns as `Option<String>`.
+//! }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use opendal::Operator;
+
+use super::{
+    first_metadata_path, next_metadata_path, split_metadata_location, Catalog, TableIdentifier,
+};
+use crate::config::Config;
+use crate::io::storage::build_operator;
+use crate::types::{parse_table_metadata, serialize_table_meta, TableMetadata};
+use crate::{Error, ErrorKind, Result, Table};
+
+const CREATE_TABLES_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS iceberg_tables (\
+    catalog_name VARCHAR(255) NOT NULL, \
+    table_namespace VARCHAR(255) NOT NULL, \
+    table_name VARCHAR(255) NOT NULL, \
+    metadata_location VARCHAR(1000), \
+    previous_metadata_location VARCHAR(1000), \
+    iceberg_type VARCHAR(5), \
+    PRIMARY KEY (catalog_name, table_namespace, table_name))";
+const CREATE_NAMESPACE_PROPERTIES_TABLE_SQL: &str =
+    "CREATE TABLE IF NOT EXISTS iceberg_namespace_properties (\
+    catalog_name VARCHAR(255) NOT NULL, \
+    namespace VARCHAR(255) NOT NULL, \
+    property_key VARCHAR(255), \
+    property_value VARCHAR(1000), \
+    PRIMARY KEY (catalog_name, namespace, property_key))";
+const LIST_TABLES_SQL: &str = "SELECT table_name FROM iceberg_tables \
+    WHERE catalog_name = ? AND table_namespace = ? \
+    AND (iceberg_type = 'TABLE' OR iceberg_type IS NULL)";
+const GET_TABLE_SQL: &str = "SELECT metadata_location FROM iceberg_tables \
+    WHERE catalog_name = ? AND table_namespace = ? AND table_name = ? \
+    AND (iceberg_type = 'TABLE' OR iceberg_type IS NULL)";
+const INSERT_TABLE_SQL: &str = "INSERT INTO iceberg_tables \
+    (catalog_name, table_namespace, table_name, metadata_location, previous_metadata_location, iceberg_type) \
+    VALUES (?, ?, ?, ?, NULL, 'TABLE')";
+const UPDATE_TABLE_SQL: &str =
+    "UPDATE iceberg_tables SET metadata_location = ?, previous_metadata_location = ? \
+    WHERE catalog_name = ? AND table_namespace = ? AND table_name = ? AND metadata_location = ?";
+const RENAME_TABLE_SQL: &str = "UPDATE iceberg_tables SET table_namespace = ?, table_name = ? \
+    WHERE catalog_name = ? AND table_namespace = ? AND table_name = ?";
+const DROP_TABLE_SQL: &str = "DELETE FROM iceberg_tables \
+    WHERE catalog_name = ? AND table_namespace = ? AND table_name = ?";
+const LIST_TABLE_NAMESPACES_SQL: &str = "SELECT DISTINCT table_namespace FROM iceberg_tables \
+    WHERE catalog_name = ? AND table_namespace LIKE ?";
+const LIST_PROPERTY_NAMESPACES_SQL: &str =
+    "SELECT DISTINCT namespace FROM iceberg_namespace_properties \
+    WHERE catalog_name = ? AND namespace LIKE ?";
+const GET_NAMESPACE_PROPERTIES_SQL: &str =
+    "SELECT property_key, property_value FROM iceberg_namespace_properties \
+    WHERE catalog_name = ? AND namespace = ?";
+const INSERT_NAMESPACE_PROPERTY_SQL: &str = "INSERT INTO iceberg_namespace_properties \
+    (catalog_name, namespace, property_key, property_value) VALUES (?, ?, ?, ?)";
+const DELETE_NAMESPACE_PROPERTY_SQL: &str = "DELETE FROM iceberg_namespace_properties \
+    WHERE catalog_name = ? AND namespace = ? AND property_key = ?";
+const DROP_NAMESPACE_SQL: &str = "DELETE FROM iceberg_namespace_properties \
+    WHERE catalog_name = ? AND namespace = ?";
+
+/// Property recorded for every created namespace, so namespaces without
+/// properties and tables still exist.
+const NAMESPACE_EXISTS_PROPERTY: &str = "exists";
+
+/// Dialect of the database, which decides placeholders of parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// Parameters are `$1`, `$2` and so on.
+    Postgres,
+    /// Parameters are `?`.
+    MySql,
+    /// Parameters are `?`.
+    Sqlite,
+}
+
+impl SqlDialect {
+    /// Rewrite `?` placeholders of `sql` to ones of this dialect, `sql` must
+    /// not contain `?` in literals.
+    pub fn render(&self, sql: &str) -> String {
+        match self {
+            SqlDialect::MySql | SqlDialect::Sqlite => sql.to_string(),
+            SqlDialect::Postgres => {
+                let mut rendered = String::with_capacity(sql.len() + 8);
+                let mut index = 0;
+                for c in sql.chars() {
+                    if c == '?' {
+                        index += 1;
+                        rendered.push_str(&format!("${index}"));
+                    } else {
+                        rendered.push(c);
+                    }
+                }
+                rendered
+            }
+        }
+    }
+}
+
+/// SqlConnection executes sql statements of [`SqlCatalog`].
+///
+/// Statements are rendered by [`SqlDialect::render`] of the dialect of the
+/// connection, all parameters and columns are strings or `NULL`.
+#[async_trait]
+pub trait SqlConnection: Send + Sync {
+    /// Returns the dialect of the database.
+    fn dialect(&self) -> SqlDialect;
+
+    /// Execute a statement, returns the number of affected rows.
+    async fn execute(&self, sql: &str, params: &[Option<&str>]) -> Result<u64>;
+
+    /// Execute a query, returns columns of the returned rows.
+    async fn query(&self, sql: &str, params: &[Option<&str>]) -> Result<Vec<Vec<Option<String>>>>;
+}
+
+/// SqlCatalog loads and commits iceberg tables registered in a relational
+/// database by the layout of the JDBC catalog.
+///
+/// Storage of tables is inferred from their locations, see
+/// [`crate::io::storage`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # async fn example(connection: Arc<dyn icelake::catalog::SqlConnection>) -> icelake::Result<()> {
+/// use icelake::catalog::{Catalog, SqlCatalog, TableIdentifier};
+///
+/// let catalog = SqlCatalog::new("prod", connection);
+/// catalog.initialize().await?;
+/// let mut table = catalog.load_table(&TableIdentifier::new(["db"], "table")).await?;
+/// let mut tx = table.new_transaction();
+/// tx.set_properties([("k".to_string(), "v".to_string())].into());
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SqlCatalog {
+    name: String,
+    connection: Arc<dyn SqlConnection>,
+    warehouse: Option<String>,
+    config: Config,
+}
+
+impl SqlCatalog {
+    /// Create a new catalog of given name, which is the `catalog_name` of
+    /// its rows.
+    pub fn new(name: impl Into<String>, connection: Arc<dyn SqlConnection>) -> Self {
+        Self {
+            name: name.into(),
+            connection,
+            warehouse: None,
+            config: Config::new(),
+        }
+    }
+
+    /// Set the warehouse location, tables created without locations are
+    /// located at `{warehouse}/{namespace}/{name}`.
+    pub fn with_warehouse(mut self, warehouse: impl Into<String>) -> Self {
+        self.warehouse = Some(warehouse.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Set the config of loaded tables, see [`Table::set_config`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Create tables of the catalog in the database if they don't exist.
+    pub async fn initialize(&self) -> Result<()> {
+        self.execute(CREATE_TABLES_TABLE_SQL, &[]).await?;
+        self.execute(CREATE_NAMESPACE_PROPERTIES_TABLE_SQL, &[])
+            .await?;
+        Ok(())
+    }
+
+    async fn execute(&self, sql: &str, params: &[Option<&str>]) -> Result<u64> {
+        let sql = self.connection.dialect().render(sql);
+        self.connection.execute(&sql, params).await
+    }
+
+    async fn query(&self, sql: &str, params: &[Option<&str>]) -> Result<Vec<Vec<Option<String>>>> {
+        let sql = self.connection.dialect().render(sql);
+        self.connection.query(&sql, params).await
+    }
+
+    /// Returns the current metadata location of the table.
+    async fn metadata_location(&self, table: &TableIdentifier) -> Result<String> {
+        let namespace = namespace_column(&table.namespace);
+        let rows = self
+            .query(
+                GET_TABLE_SQL,
+                &[Some(&self.name), Some(&namespace), Some(&table.name)],
+            )
+            .await?;
+        match rows.into_iter().next().and_then(|v| v.into_iter().next()) {
+            Some(Some(location)) => Ok(location),
+            Some(None) => Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Metadata location of table {table} is not set"),
+            )),
+            None => Err(not_found(table)),
+        }
+    }
+
+    /// Returns namespace columns of tables and namespace properties
+    /// matching the `LIKE` pattern.
+    async fn namespaces(&self, pattern: &str) -> Result<BTreeSet<String>> {
+        let mut namespaces = BTreeSet::new();
+        for sql in [LIST_TABLE_NAMESPACES_SQL, LIST_PROPERTY_NAMESPACES_SQL] {
+            let rows = self.query(sql, &[Some(&self.name), Some(pattern)]).await?;
+            namespaces.extend(
+                rows.into_iter()
+                    .filter_map(|v| v.into_iter().next().flatten()),
+            );
+        }
+        Ok(namespaces)
+    }
+
+    /// Returns namespaces under the namespace, including itself.
+    async fn namespaces_under(&self, namespace: &[String]) -> Result<BTreeSet<String>> {
+        let namespace = namespace_column(namespace);
+        let prefix = format!("{namespace}.");
+        Ok(self
+            .namespaces(&format!("{namespace}%"))
+            .await?
+            .into_iter()
+            .filter(|v| v == &namespace || v.starts_with(&prefix))
+            .collect())
+    }
+
+    /// A namespace exists if it has been created, or contains tables or
+    /// namespaces.
+    async fn check_namespace(&self, namespace: &[String]) -> Result<()> {
+        if namespace.is_empty() || self.namespaces_under(namespace).await?.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Namespace {} doesn't exist", namespace_column(namespace)),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn set_namespace_property(&self, namespace: &str, key: &str, value: &str) -> Result<()> {
+        self.execute(
+            DELETE_NAMESPACE_PROPERTY_SQL,
+            &[Some(&self.name), Some(namespace), Some(key)],
+        )
+        .await?;
+        self.execute(
+            INSERT_NAMESPACE_PROPERTY_SQL,
+            &[Some(&self.name), Some(namespace), Some(key), Some(value)],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the operator rooted at the table location.
+    fn operator(&self, location: &str) -> Result<Operator> {
+        build_operator(location, &self.config.overrides())
+    }
+}
+
+/// Returns the namespace column of the namespace.
+fn namespace_column(namespace: &[String]) -> String {
+    namespace.join(".")
+}
+
+fn not_found(table: &TableIdentifier) -> Error {
+    Error::new(ErrorKind::NotFound, format!("Table {table} is not found"))
+}
+
+#[async_trait]
+impl Catalog for SqlCatalog {
+    async fn list_namespaces(&self, parent: &[String]) -> Result<Vec<Vec<String>>> {
+        let pattern = if parent.is_empty() {
+            "%".to_string()
+        } else {
+            self.check_namespace(parent).await?;
+            format!("{}.%", namespace_column(parent))
+        };
+        // Namespaces are known by their descendants as well.
+        let children: BTreeSet<_> = self
+            .namespaces(&pattern)
+            .await?
+            .into_iter()
+            .filter_map(|v| {
+                let levels: Vec<_> = v.split('.').map(str::to_string).collect();
+                (levels.len() > parent.len() && levels.starts_with(parent))
+                    .then(|| levels[..=parent.len()].to_vec())
+            })
+            .collect();
+        Ok(children.into_iter().collect())
+    }
+
+    async fn create_namespace(
+        &self,
+        namespace: &[String],
+        properties: HashMap<String, String>,
+    ) -> Result<()> {
+        if namespace.is_empty() || self.check_namespace(namespace).await.is_ok() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Namespace {} already exists", namespace_column(namespace)),
+            ));
+        }
+        let namespace = namespace_column(namespace);
+        let properties = properties
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain([(NAMESPACE_EXISTS_PROPERTY, "true")]);
+        for (key, value) in properties {
+            self.execute(
+                INSERT_NAMESPACE_PROPERTY_SQL,
+                &[Some(&self.name), Some(&namespace), Some(key), Some(value)],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn load_namespace_properties(
+        &self,
+        namespace: &[String],
+    ) -> Result<HashMap<String, String>> {
+        self.check_namespace(namespace).await?;
+        let rows = self
+            .query(
+                GET_NAMESPACE_PROPERTIES_SQL,
+                &[Some(&self.name), Some(&namespace_column(namespace))],
+            )
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| match row.as_slice() {
+                [Some(k), v] if k != NAMESPACE_EXISTS_PROPERTY => {
+                    Some((k.clone(), v.clone().unwrap_or_default()))
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn update_namespace_properties(
+        &self,
+        namespace: &[String],
+        removals: &[String],
+        updates: HashMap<String, String>,
+    ) -> Result<()> {
+        self.check_namespace(namespace).await?;
+        let namespace = namespace_column(namespace);
+        for key in removals {
+            self.execute(
+                DELETE_NAMESPACE_PROPERTY_SQL,
+                &[Some(&self.name), Some(&namespace), Some(key)],
+            )
+            .await?;
+        }
+        for (key, value) in &updates {
+            self.set_namespace_property(&namespace, key, value).await?;
+        }
+        // Namespaces known by their tables are created by updates.
+        self.set_namespace_property(&namespace, NAMESPACE_EXISTS_PROPERTY, "true")
+            .await
+    }
+
+    /// Drop properties of the namespace, which must contain no tables nor
+    /// namespaces.
+    async fn drop_namespace(&self, namespace: &[String]) -> Result<()> {
+        self.check_namespace(namespace).await?;
+        let name = namespace_column(namespace);
+        if self.namespaces_under(namespace).await? != BTreeSet::from([name.clone()])
+            || !self.list_tables(namespace).await?.is_empty()
+        {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Namespace {name} is not empty"),
+            ));
+        }
+        self.execute(DROP_NAMESPACE_SQL, &[Some(&self.name), Some(&name)])
+            .await?;
+        Ok(())
+    }
+
+    async fn list_tables(&self, namespace: &[String]) -> Result<Vec<TableIdentifier>> {
+        let rows = self
+            .query(
+                LIST_TABLES_SQL,
+                &[Some(&self.name), Some(&namespace_column(namespace))],
+            )
+            .await?;
+        let mut tables: Vec<_> = rows
+            .into_iter()
+            .filter_map(|v| v.into_iter().next().flatten())
+            .map(|name| TableIdentifier::new(namespace.iter().cloned(), name))
+            .collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tables)
+    }
+
+    /// Create the table at [`TableMetadata::location`], or under the
+    /// warehouse if the location is not set.
+    async fn create_table(
+        &self,
+        table: &TableIdentifier,
+        mut metadata: TableMetadata,
+    ) -> Result<Table> {
+        if metadata.location.is_empty() {
+            let Some(warehouse) = &self.warehouse else {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Location of table {table} must be set without warehouse"),
+                ));
+            };
+            let mut location = warehouse.clone();
+            for level in table.namespace.iter().chain([&table.name]) {
+                location.push('/');
+                location.push_str(level);
+            }
+            metadata.location = location;
+        }
+        let namespace = namespace_column(&table.namespace);
+        let exists = !self
+            .query(
+                GET_TABLE_SQL,
+                &[Some(&self.name), Some(&namespace), Some(&table.name)],
+            )
+            .await?
+            .is_empty();
+        if exists {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Table {table} already exists"),
+            ));
+        }
+
+        let op = self.operator(&metadata.location)?;
+        let path = first_metadata_path();
+        let location = format!("{}/{path}", metadata.location);
+        op.write(&path, serialize_table_meta(metadata)?).await?;
+
+        let inserted = self
+            .execute(
+                INSERT_TABLE_SQL,
+                &[
+                    Some(&self.name),
+                    Some(&namespace),
+                    Some(&table.name),
+                    Some(&location),
+                ],
+            )
+            .await;
+        if let Err(err) = inserted {
+            if let Err(e) = op.delete(&path).await {
+                log::warn!("Failed to delete uncommitted metadata file {location}: {e}");
+            }
+            return Err(err);
+        }
+
+        self.load_table(table).await
+    }
+
+    /// Insert the row of the table after checking the metadata file can be
+    /// read.
+    async fn register_table(
+        &self,
+        table: &TableIdentifier,
+        metadata_location: &str,
+    ) -> Result<Table> {
+        let (table_location, metadata_path) = split_metadata_location(metadata_location)?;
+        let op = self.operator(table_location)?;
+        parse_table_metadata(&op.read(metadata_path).await?)
+            .map_err(|e| e.with_context("metadata_location", metadata_location))?;
+
+        if self.metadata_location(table).await.is_ok() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Table {table} already exists"),
+            ));
+        }
+        self.execute(
+            INSERT_TABLE_SQL,
+            &[
+                Some(&self.name),
+                Some(&namespace_column(&table.namespace)),
+                Some(&table.name),
+                Some(metadata_location),
+            ],
+        )
+        .await?;
+
+        self.load_table(table).await
+    }
+
+    async fn load_table(&self, table: &TableIdentifier) -> Result<Table> {
+        let metadata_location = self.metadata_location(table).await?;
+        let (table_location, metadata_path) = split_metadata_location(&metadata_location)?;
+
+        let op = self.operator(table_location)?;
+        let metadata = parse_table_metadata(&op.read(metadata_path).await?)
+            .map_err(|e| e.with_context("metadata_location", &metadata_location))?;
+
+        let mut table = Table::with_catalog(
+            op,
+            Arc::new(self.clone()),
+            table.clone(),
+            metadata_location,
+            metadata,
+        )?;
+        table.set_config(self.config.clone());
+        Ok(table)
+    }
+
+    /// Drop the row of the table, files of the table are kept.
+    async fn drop_table(&self, table: &TableIdentifier) -> Result<()> {
+        let dropped = self
+            .execute(
+                DROP_TABLE_SQL,
+                &[
+                    Some(&self.name),
+                    Some(&namespace_column(&table.namespace)),
+                    Some(&table.name),
+                ],
+            )
+            .await?;
+        if dropped == 0 {
+            return Err(not_found(table));
+        }
+        Ok(())
+    }
+
+    async fn rename_table(&self, from: &TableIdentifier, to: &TableIdentifier) -> Result<()> {
+        let renamed = self
+            .execute(
+                RENAME_TABLE_SQL,
+                &[
+                    Some(&namespace_column(&to.namespace)),
+                    Some(&to.name),
+                    Some(&self.name),
+                    Some(&namespace_column(&from.namespace)),
+                    Some(&from.name),
+                ],
+            )
+            .await?;
+        if renamed == 0 {
+            return Err(not_found(from));
+        }
+        Ok(())
+    }
+
+    async fn update_table(
+        &self,
+        table: &TableIdentifier,
+        base_location: &str,
+        _base: &TableMetadata,
+        next: &TableMetadata,
+    ) -> Result<(String, TableMetadata)> {
+        let path = next_metadata_path(base_location)?;
+        let location = format!("{}/{path}", next.location);
+        let op = self.operator(&next.location)?;
+        op.write(&path, serialize_table_meta(next.clone())?).await?;
+
+        // The row is only updated if no other commit has swapped the
+        // location since `base_location`.
+        let updated = self
+            .execute(
+                UPDATE_TABLE_SQL,
+                &[
+                    Some(&location),
+                    Some(base_location),
+                    Some(&self.name),
+                    Some(&namespace_column(&table.namespace)),
+                    Some(&table.name),
+                    Some(base_location),
+                ],
+            )
+            .await;
+        let err = match updated {
+            Ok(1) => return Ok((location, next.clone())),
+            Ok(_) => Error::new(
+                ErrorKind::CommitConflict,
+                format!(
+                    "Cannot commit {table} because its metadata location has been changed from {base_location}"
+                ),
+            ),
+            Err(err) => err,
+        };
+        if let Err(e) = op.delete(&path).await {
+            log::warn!("Failed to delete uncommitted metadata file {location}: {e}");
+        }
+        Err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use tempfile::TempDir;
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    /// Key of rows of `iceberg_tables` by catalog, namespace and name.
+    type TableKey = (String, String, String);
+
+    /// A database only understanding statements of the catalog, rows of
+    /// `iceberg_tables` keep current and previous metadata locations.
+    #[derive(Default)]
+    struct FakeDatabase {
+        tables: Mutex<HashMap<TableKey, (String, Option<String>)>>,
+        /// Rows of `iceberg_namespace_properties` keyed by catalog,
+        /// namespace and property key.
+        properties: Mutex<BTreeMap<(String, String, String), String>>,
+    }
+
+    /// Returns whether `v` matches `LIKE` pattern, which only contains `%` at
+    /// the end.
+    fn like(v: &str, pattern: &str) -> bool {
+        v.starts_with(pattern.trim_end_matches('%'))
+    }
+
+    #[async_trait]
+    impl SqlConnection for FakeDatabase {
+        fn dialect(&self) -> SqlDialect {
+            SqlDialect::Sqlite
+        }
+
+        async fn execute(&self, sql: &str, params: &[Option<&str>]) -> Result<u64> {
+            let p: Vec<String> = params
+                .iter()
+                .map(|v| v.unwrap_or_default().to_string())
+                .collect();
+            let mut tables = self.tables.lock().await;
+            let mut properties = self.properties.lock().await;
+            let affected = match sql {
+                CREATE_TABLES_TABLE_SQL | CREATE_NAMESPACE_PROPERTIES_TABLE_SQL => 0,
+                INSERT_TABLE_SQL => {
+                    let key = (p[0].clone(), p[1].clone(), p[2].clone());
+                    if tables.contains_key(&key) {
+                        return Err(Error::new(ErrorKind::Unexpected, "duplicate key"));
+                    }
+                    tables.insert(key, (p[3].clone(), None));
+                    1
+                }
+                UPDATE_TABLE_SQL => {
+                    let key = (p[2].clone(), p[3].clone(), p[4].clone());
+                    match tables.get_mut(&key) {
+                        Some(row) if row.0 == p[5] => {
+                            *row = (p[0].clone(), Some(p[1].clone()));
+                            1
+                        }
+                        _ => 0,
+                    }
+                }
+                RENAME_TABLE_SQL => {
+                    match tables.remove(&(p[2].clone(), p[3].clone(), p[4].clone())) {
+                        Some(row) => {
+                            tables.insert((p[2].clone(), p[0].clone(), p[1].clone()), row);
+                            1
+                        }
+                        None => 0,
+                    }
+                }
+                DROP_TABLE_SQL => tables
+                    .remove(&(p[0].clone(), p[1].clone(), p[2].clone()))
+                    .map_or(0, |_| 1),
+                INSERT_NAMESPACE_PROPERTY_SQL => {
+                    let key = (p[0].clone(), p[1].clone(), p[2].clone());
+                    if properties.insert(key, p[3].clone()).is_some() {
+                        return Err(Error::new(ErrorKind::Unexpected, "duplicate key"));
+                    }
+                    1
+                }
+                DELETE_NAMESPACE_PROPERTY_SQL => properties
+                    .remove(&(p[0].clone(), p[1].clone(), p[2].clone()))
+                    .map_or(0, |_| 1),
+                DROP_NAMESPACE_SQL => {
+                    let len = properties.len();
+                    properties.retain(|(c, n, _), _| c != &p[0] || n != &p[1]);
+                    (len - properties.len()) as u64
+                }
+                _ => return Err(Error::new(ErrorKind::Unexpected, sql)),
+            };
+            Ok(affected)
+        }
+
+        async fn query(
+            &self,
+            sql: &str,
+            params: &[Option<&str>],
+        ) -> Result<Vec<Vec<Option<String>>>> {
+            let p: Vec<&str> = params.iter().map(|v| v.unwrap_or_default()).collect();
+            let tables = self.tables.lock().await;
+            let properties = self.properties.lock().await;
+            let distinct = |namespaces: BTreeSet<&String>| {
+                namespaces
+                    .into_iter()
+                    .map(|v| vec![Some(v.clone())])
+                    .collect()
+            };
+            match sql {
+                LIST_TABLES_SQL => Ok(tables
+                    .keys()
+                    .filter(|(c, n, _)| c == p[0] && n == p[1])
+                    .map(|(_, _, name)| vec![Some(name.clone())])
+                    .collect()),
+                GET_TABLE_SQL => Ok(tables
+                    .get(&(p[0].to_string(), p[1].to_string(), p[2].to_string()))
+                    .map(|(location, _)| vec![Some(location.clone())])
+                    .into_iter()
+                    .collect()),
+                LIST_TABLE_NAMESPACES_SQL => Ok(distinct(
+                    tables
+                        .keys()
+                        .filter(|(c, n, _)| c == p[0] && like(n, p[1]))
+                        .map(|(_, n, _)| n)
+                        .collect(),
+                )),
+                LIST_PROPERTY_NAMESPACES_SQL => Ok(distinct(
+                    properties
+                        .keys()
+                        .filter(|(c, n, _)| c == p[0] && like(n, p[1]))
+                        .map(|(_, n, _)| n)
+                        .collect(),
+                )),
+                GET_NAMESPACE_PROPERTIES_SQL => Ok(properties
+                    .iter()
+                    .filter(|((c, n, _), _)| c == p[0] && n == p[1])
+                    .map(|((_, _, k), v)| vec![Some(k.clone()), Some(v.clone())])
+                    .collect()),
+                _ => Err(Error::new(ErrorKind::Unexpected, sql)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_sql() {
+        let sql = "UPDATE t SET a = ? WHERE b = ? AND c = 'x'";
+        assert_eq!(SqlDialect::Sqlite.render(sql), sql);
+        assert_eq!(
+            SqlDialect::Postgres.render(sql),
+            "UPDATE t SET a = $1 WHERE b = $2 AND c = 'x'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sql_catalog() {
+        let tmp_dir = TempDir::new().unwrap();
+        let warehouse = tmp_dir.path().to_str().unwrap();
+        let database = Arc::new(FakeDatabase::default());
+        let catalog = SqlCatalog::new("test", database.clone()).with_warehouse(warehouse);
+        catalog.initialize().await.unwrap();
+
+        // Create a table from metadata of simple table without location.
+        let src = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&fs::read(src).unwrap()).unwrap();
+        metadata.location = String::new();
+        let identifier = TableIdentifier::new(["db"], "t");
+        let mut table = catalog
+            .create_table(&identifier, metadata.clone())
+            .await
+            .unwrap();
+        assert!(table
+            .current_metadata_file_location()
+            .starts_with(&format!("{warehouse}/db/t/metadata/00000-")));
+        assert!(catalog
+            .create_table(&identifier, metadata.clone())
+            .await
+            .is_err());
+        assert_eq!(
+            catalog.list_tables(&["db".to_string()]).await.unwrap(),
+            vec![identifier.clone()]
+        );
+
+        let base_location = table.current_metadata_file_location();
+        let mut tx = table.new_transaction();
+        tx.set_properties([("k".to_string(), "v".to_string())].into());
+        tx.commit().await.unwrap();
+        let committed = table.current_metadata_file_location();
+        assert!(committed.starts_with(&format!("{warehouse}/db/t/metadata/00001-")));
+        assert_eq!(
+            database.tables.lock().await[&("test".to_string(), "db".to_string(), "t".to_string())],
+            (committed.clone(), Some(base_location.clone()))
+        );
+
+        // Commits based on stale metadata are rejected, and their metadata
+        // files are deleted.
+        let metadata = table.current_table_metadata();
+        let err = catalog
+            .update_table(&identifier, &base_location, metadata, metadata)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommitConflict);
+        assert_eq!(
+            fs::read_dir(tmp_dir.path().join("db/t/metadata"))
+                .unwrap()
+                .count(),
+            2
+        );
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert_eq!(
+            reloaded.properties().get("k").map(String::as_str),
+            Some("v")
+        );
+
+        // Registered tables use the metadata file as is.
+        let registered = TableIdentifier::new(["db"], "registered");
+        let table = catalog
+            .register_table(&registered, &committed)
+            .await
+            .unwrap();
+        assert_eq!(table.current_metadata_file_location(), committed);
+        assert_eq!(table.properties().get("k").map(String::as_str), Some("v"));
+        assert!(catalog
+            .register_table(&registered, &committed)
+            .await
+            .is_err());
+        assert!(catalog
+            .register_table(
+                &TableIdentifier::new(["db"], "missing"),
+                &format!("{warehouse}/db/t/metadata/missing.metadata.json")
+            )
+            .await
+            .is_err());
+        catalog.drop_table(&registered).await.unwrap();
+
+        let renamed = TableIdentifier::new(["db", "s"], "r");
+        catalog.rename_table(&identifier, &renamed).await.unwrap();
+        assert!(catalog.load_table(&identifier).await.is_err());
+        assert_eq!(
+            catalog.load_table(&renamed).await.unwrap().identifier(),
+            Some(&renamed)
+        );
+        catalog.drop_table(&renamed).await.unwrap();
+        assert!(catalog.drop_table(&renamed).await.is_err());
+        assert!(catalog
+            .list_tables(&["db".to_string(), "s".to_string()])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sql_catalog_namespaces() {
+        let database = Arc::new(FakeDatabase::default());
+        let catalog = SqlCatalog::new("test", database.clone());
+        let db = vec!["db".to_string()];
+        let schema = vec!["db".to_string(), "s".to_string()];
+        catalog
+            .create_namespace(&db, HashMap::from([("k".into(), "v".into())]))
+            .await
+            .unwrap();
+        assert!(catalog.create_namespace(&db, HashMap::new()).await.is_err());
+        // Namespaces of other catalogs are not visible.
+        SqlCatalog::new("other", database.clone())
+            .create_namespace(&["x".to_string()], HashMap::new())
+            .await
+            .unwrap();
+
+        // Namespaces are known by their tables as well.
+        database.tables.lock().await.insert(
+            ("test".to_string(), "db.s.t".to_string(), "t".to_string()),
+            ("location".to_string(), None),
+        );
+        assert_eq!(
+            catalog.list_namespaces(&[]).await.unwrap(),
+            vec![db.clone()]
+        );
+        assert_eq!(
+            catalog.list_namespaces(&db).await.unwrap(),
+            vec![schema.clone()]
+        );
+        assert!(catalog.list_namespaces(&["x".to_string()]).await.is_err());
+        assert!(catalog
+            .load_namespace_properties(&schema)
+            .await
+            .unwrap()
+            .is_empty());
+
+        catalog
+            .update_namespace_properties(
+                &db,
+                &["k".to_string()],
+                HashMap::from([("a".into(), "b".into())]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            catalog.load_namespace_properties(&db).await.unwrap(),
+            HashMap::from([("a".to_string(), "b".to_string())])
+        );
+
+        assert!(catalog.drop_namespace(&db).await.is_err());
+        database.tables.lock().await.clear();
+        catalog.drop_namespace(&db).await.unwrap();
+        assert!(catalog.list_namespaces(&[]).await.unwrap().is_empty());
+        assert!(catalog.load_namespace_properties(&db).await.is_err());
+    }
+}
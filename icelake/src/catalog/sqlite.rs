@@ -0,0 +1,179 @@
+//! sqlite module provides [`SqliteConnection`], which accesses a sqlite
+//! database of [`super::SqlCatalog`] by a connection pool of `sqlx`.
+
+use async_trait::async_trait;
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{Row, Sqlite};
+
+use super::{SqlConnection, SqlDialect};
+use crate::Result;
+
+/// SqliteConnection executes statements of [`super::SqlCatalog`] in a
+/// sqlite database.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # async fn example() -> icelake::Result<()> {
+/// use icelake::catalog::{SqlCatalog, SqliteConnection};
+///
+/// let connection = SqliteConnection::connect("sqlite:///tmp/catalog.db").await?;
+/// let catalog = SqlCatalog::new("prod", Arc::new(connection)).with_warehouse("/tmp/warehouse");
+/// catalog.initialize().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SqliteConnection {
+    pool: SqlitePool,
+}
+
+impl SqliteConnection {
+    /// Connect to the database of url like `sqlite:///path/to/catalog.db`,
+    /// which is created if missing.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let options: SqliteConnectOptions = url.parse()?;
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options.create_if_missing(true))
+            .await?;
+        Ok(Self { pool })
+    }
+
+    /// Create a connection of the pool.
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn bind<'q>(sql: &'q str, params: &[Option<&'q str>]) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    params
+        .iter()
+        .fold(sqlx::query(sql), |query, param| query.bind(*param))
+}
+
+#[async_trait]
+impl SqlConnection for SqliteConnection {
+    fn dialect(&self) -> SqlDialect {
+        SqlDialect::Sqlite
+    }
+
+    async fn execute(&self, sql: &str, params: &[Option<&str>]) -> Result<u64> {
+        let result = bind(sql, params).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn query(&self, sql: &str, params: &[Option<&str>]) -> Result<Vec<Vec<Option<String>>>> {
+        let rows = bind(sql, params).fetch_all(&self.pool).await?;
+        rows.iter()
+            .map(|row| {
+                (0..row.len())
+                    .map(|i| Ok(row.try_get::<Option<String>, _>(i)?))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::sync::Arc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::catalog::{Catalog, SqlCatalog, TableIdentifier};
+    use crate::types::parse_table_metadata;
+    use crate::ErrorKind;
+
+    async fn catalog(tmp_dir: &TempDir) -> SqlCatalog {
+        let path = tmp_dir.path().join("catalog.db");
+        let connection = SqliteConnection::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        let catalog = SqlCatalog::new("test", Arc::new(connection))
+            .with_warehouse(tmp_dir.path().join("warehouse").to_str().unwrap());
+        catalog.initialize().await.unwrap();
+        catalog
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_catalog_commit() {
+        let tmp_dir = TempDir::new().unwrap();
+        let catalog = catalog(&tmp_dir).await;
+        let src = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&fs::read(src).unwrap()).unwrap();
+        metadata.location = String::new();
+        let identifier = TableIdentifier::new(["db"], "t");
+        let mut table = catalog.create_table(&identifier, metadata).await.unwrap();
+        let mut stale = catalog.load_table(&identifier).await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("k".to_string(), "v".to_string())]));
+        tx.commit().await.unwrap();
+
+        // The row only swaps from the base location, so the stale table
+        // can't overwrite the commit.
+        let base = stale.current_table_metadata();
+        let err = catalog
+            .update_table(
+                &identifier,
+                &stale.current_metadata_file_location(),
+                base,
+                base,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommitConflict);
+
+        // Transactions of the stale table retry on top of the refreshed
+        // metadata.
+        let mut tx = stale.new_transaction();
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        tx.commit().await.unwrap();
+        let reloaded = catalog.load_table(&identifier).await.unwrap();
+        assert_eq!(
+            reloaded.current_metadata_file_location(),
+            stale.current_metadata_file_location()
+        );
+        assert_eq!(
+            reloaded.properties().get("k").map(String::as_str),
+            Some("v")
+        );
+        assert_eq!(
+            reloaded.properties().get("a").map(String::as_str),
+            Some("b")
+        );
+        assert_eq!(
+            catalog.list_tables(&["db".to_string()]).await.unwrap(),
+            vec![identifier.clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_catalog_not_found() {
+        let tmp_dir = TempDir::new().unwrap();
+        let catalog = catalog(&tmp_dir).await;
+        let missing = TableIdentifier::new(["db"], "missing");
+        for err in [
+            catalog.load_table(&missing).await.err().unwrap(),
+            catalog.drop_table(&missing).await.unwrap_err(),
+            catalog
+                .rename_table(&missing, &TableIdentifier::new(["db"], "t"))
+                .await
+                .unwrap_err(),
+            catalog
+                .load_namespace_properties(&["db".to_string()])
+                .await
+                .unwrap_err(),
+        ] {
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! commit module provides the [`CommitStrategy`] used to publish new
+//! metadata files of hadoop style tables.
+//!
+//! A commit of a hadoop style table succeeds only if its metadata file
+//! `v{n+1}.metadata.json` didn't exist, so publishing the file must fail if
+//! another writer has created it in the meantime. How this is guaranteed
+//! depends on the storage:
+//!
+//! - Local (or mounted) file systems create the file by hard link, which
+//!   fails if the target exists, see [`FileSystemCommitStrategy`].
+//! - Storages that rename atomically, like hdfs, rename a temporary file,
+//!   see [`RenameCommitStrategy`].
+//! - Object stores can't rename, copy and delete is not atomic. Commits to
+//!   them should be guarded by a [`crate::lock::LockProvider`], or go
+//!   through a [`crate::catalog::Catalog`] which swaps the metadata pointer
+//!   atomically by itself. Stores supporting conditional writes can
+//!   implement [`CommitStrategy`] by writing the file only if absent.
+//!
+//! The strategy of a table is chosen by [`default_commit_strategy`] unless
+//! set by [`crate::Table::set_commit_strategy`].
+
+use std::io::ErrorKind as IoErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use opendal::{Operator, Scheme};
+use uuid::Uuid;
+
+use crate::{Error, ErrorKind, Result};
+
+/// CommitStrategy writes a file only if it doesn't exist.
+#[async_trait]
+pub trait CommitStrategy: Send + Sync {
+    /// Write `content` to `path` of `op`, readers must see either nothing
+    /// or the whole content at `path`.
+    ///
+    /// Returns error of [`ErrorKind::CommitConflict`] if `path` exists.
+    async fn write_exclusive(&self, op: &Operator, path: &str, content: Vec<u8>) -> Result<()>;
+}
+
+/// Returns the strategy that is safe for the storage of `op`.
+///
+/// Commits to storages that can't rename, like s3, fall back to
+/// [`RenameCommitStrategy`], which is not atomic, unless guarded by a lock.
+pub fn default_commit_strategy(op: &Operator) -> Arc<dyn CommitStrategy> {
+    if op.info().scheme() == Scheme::Fs {
+        Arc::new(FileSystemCommitStrategy)
+    } else {
+        Arc::new(RenameCommitStrategy)
+    }
+}
+
+/// RenameCommitStrategy writes a temporary file and renames it to the
+/// target path if the target doesn't exist.
+///
+/// The check and the rename are two operations, it's safe only if the
+/// storage renames without overwriting existing files, or commits are
+/// guarded by a [`crate::lock::LockProvider`]. Storages that can't rename
+/// copy and delete the temporary file instead.
+pub struct RenameCommitStrategy;
+
+#[async_trait]
+impl CommitStrategy for RenameCommitStrategy {
+    async fn write_exclusive(&self, op: &Operator, path: &str, content: Vec<u8>) -> Result<()> {
+        if op.is_exist(path).await? {
+            return Err(conflict_error(path));
+        }
+
+        let tmp_path = temporary_path(path);
+        op.write(&tmp_path, content).await?;
+        // Another writer may have won while the temporary file was written.
+        if op.is_exist(path).await? {
+            delete_temporary_file(op, &tmp_path).await;
+            return Err(conflict_error(path));
+        }
+
+        log::debug!("Renaming temporary file [{tmp_path}] to [{path}]");
+        if op.info().can_rename() {
+            op.rename(&tmp_path, path).await?;
+        } else {
+            op.copy(&tmp_path, path).await?;
+            op.delete(&tmp_path).await?;
+        }
+        Ok(())
+    }
+}
+
+/// FileSystemCommitStrategy writes a temporary file and hard links it to
+/// the target path, which fails atomically if the target exists.
+///
+/// It only works with operators of local (or mounted) file systems.
+pub struct FileSystemCommitStrategy;
+
+#[async_trait]
+impl CommitStrategy for FileSystemCommitStrategy {
+    async fn write_exclusive(&self, op: &Operator, path: &str, content: Vec<u8>) -> Result<()> {
+        let info = op.info();
+        if info.scheme() != Scheme::Fs {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!(
+                    "Committing by hard link to storage {}",
+                    info.scheme().into_static()
+                ),
+            ));
+        }
+
+        let tmp_path = temporary_path(path);
+        op.write(&tmp_path, content).await?;
+
+        let root = Path::new(info.root());
+        let res = tokio::fs::hard_link(root.join(&tmp_path), root.join(path)).await;
+        delete_temporary_file(op, &tmp_path).await;
+        match res {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == IoErrorKind::AlreadyExists => Err(conflict_error(path)),
+            Err(e) => Err(Error::new(
+                ErrorKind::Unexpected,
+                format!("Failed to link temporary file {tmp_path} to {path}"),
+            )
+            .set_source(e)),
+        }
+    }
+}
+
+/// Returns a unique temporary path next to `path`.
+fn temporary_path(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, _)) => format!("{dir}/{}.tmp", Uuid::new_v4()),
+        None => format!("{}.tmp", Uuid::new_v4()),
+    }
+}
+
+async fn delete_temporary_file(op: &Operator, path: &str) {
+    if let Err(err) = op.delete(path).await {
+        log::warn!("Failed to delete temporary file {path}: {err}");
+    }
+}
+
+fn conflict_error(path: &str) -> Error {
+    Error::new(
+        ErrorKind::CommitConflict,
+        format!("{path} already exists, table has been updated concurrently"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use opendal::services::Fs;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_exclusive() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut builder = Fs::default();
+        builder.root(tmp_dir.path().to_str().unwrap());
+        let op = Operator::new(builder)?.finish();
+
+        let strategies: Vec<Arc<dyn CommitStrategy>> = vec![
+            default_commit_strategy(&op),
+            Arc::new(RenameCommitStrategy),
+            Arc::new(FileSystemCommitStrategy),
+        ];
+        for (i, strategy) in strategies.into_iter().enumerate() {
+            let path = format!("metadata/v{i}.metadata.json");
+            strategy.write_exclusive(&op, &path, b"a".to_vec()).await?;
+            let err = strategy
+                .write_exclusive(&op, &path, b"b".to_vec())
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::CommitConflict);
+            assert_eq!(op.read(&path).await?, b"a");
+        }
+
+        // Temporary files are cleaned up.
+        let files = std::fs::read_dir(tmp_dir.path().join("metadata")).unwrap();
+        assert_eq!(files.count(), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! config module provides [`Config`] that merges configurations from table
+//! properties, environment variables and programmatic options.
+//!
+//! Configurations share the same keys as table properties, see
+//! [`crate::table_properties`]. When the same key is set in multiple places,
+//! the precedence from high to low is:
+//!
+//! 1. options set by [`Config::with_option`]
+//! 2. environment variables captured by [`Config::from_env`]
+//! 3. table properties
+//! 4. default values of each key
+
+use std::collections::HashMap;
+
+/// Prefix of environment variables mapped to configuration keys.
+///
+/// The rest of the variable name is lowercased, `__` is replaced by `-`
+/// and `_` is replaced by `.`, for example:
+///
+/// - `ICELAKE_WRITE_TARGET__FILE__SIZE__BYTES` => `write.target-file-size-bytes`
+/// - `ICELAKE_WRITE_FORMAT_DEFAULT` => `write.format.default`
+pub const ENV_PREFIX: &str = "ICELAKE_";
+
+/// Access key id of s3 storage, read from `AWS_ACCESS_KEY_ID`.
+pub const S3_ACCESS_KEY_ID: &str = "s3.access-key-id";
+/// Secret access key of s3 storage, read from `AWS_SECRET_ACCESS_KEY`.
+pub const S3_SECRET_ACCESS_KEY: &str = "s3.secret-access-key";
+/// Session token of s3 storage, read from `AWS_SESSION_TOKEN`.
+pub const S3_SESSION_TOKEN: &str = "s3.session-token";
+/// Region of s3 storage, read from `AWS_REGION` or `AWS_DEFAULT_REGION`.
+pub const S3_REGION: &str = "s3.region";
+/// Endpoint of s3 storage, read from `AWS_ENDPOINT_URL`.
+pub const S3_ENDPOINT: &str = "s3.endpoint";
+/// Whether to access s3 buckets by path style urls like
+/// `https://s3.amazonaws.com/bucket/key`, default to `true`, virtual host
+/// style urls like `https://bucket.s3.amazonaws.com/key` are used if
+/// `false`.
+pub const S3_PATH_STYLE_ACCESS: &str = "s3.path-style-access";
+/// ARN of the role assumed to access s3 storage.
+pub const S3_ASSUME_ROLE_ARN: &str = "client.assume-role.arn";
+/// External id used to assume [`S3_ASSUME_ROLE_ARN`].
+pub const S3_ASSUME_ROLE_EXTERNAL_ID: &str = "client.assume-role.external-id";
+/// Credential of gcs storage, the base64 encoded service account key.
+pub const GCS_CREDENTIAL: &str = "gcs.credential";
+/// Path of the service account key of gcs storage, read from
+/// `GOOGLE_APPLICATION_CREDENTIALS`.
+pub const GCS_CREDENTIAL_PATH: &str = "gcs.credential-path";
+/// Endpoint of gcs storage.
+pub const GCS_ENDPOINT: &str = "gcs.endpoint";
+/// Endpoint of oss storage like `https://oss-cn-hangzhou.aliyuncs.com`,
+/// read from `OSS_ENDPOINT`.
+pub const OSS_ENDPOINT: &str = "oss.endpoint";
+/// Access key id of oss storage, read from `OSS_ACCESS_KEY_ID`.
+pub const OSS_ACCESS_KEY_ID: &str = "oss.access-key-id";
+/// Access key secret of oss storage, read from `OSS_ACCESS_KEY_SECRET`.
+pub const OSS_ACCESS_KEY_SECRET: &str = "oss.access-key-secret";
+/// Account name of azure blob storage, read from
+/// `AZURE_STORAGE_ACCOUNT_NAME`.
+pub const ADLS_ACCOUNT_NAME: &str = "adls.account-name";
+/// Account key of azure blob storage, read from `AZURE_STORAGE_ACCOUNT_KEY`.
+pub const ADLS_ACCOUNT_KEY: &str = "adls.account-key";
+/// Endpoint of azure blob storage, default to
+/// `https://{account name}.blob.core.windows.net`.
+pub const ADLS_ENDPOINT: &str = "adls.endpoint";
+/// WebHDFS endpoint used to access `hdfs://` locations, default to
+/// `http://{namenode host}:9870`.
+pub const HDFS_WEBHDFS_ENDPOINT: &str = "hdfs.webhdfs-endpoint";
+
+/// Environment variables of storage services and their configuration keys,
+/// earlier variables take precedence for the same key.
+const STORAGE_ENV_VARS: &[(&str, &str)] = &[
+    ("AWS_ACCESS_KEY_ID", S3_ACCESS_KEY_ID),
+    ("AWS_SECRET_ACCESS_KEY", S3_SECRET_ACCESS_KEY),
+    ("AWS_SESSION_TOKEN", S3_SESSION_TOKEN),
+    ("AWS_REGION", S3_REGION),
+    ("AWS_DEFAULT_REGION", S3_REGION),
+    ("AWS_ENDPOINT_URL", S3_ENDPOINT),
+    ("GOOGLE_APPLICATION_CREDENTIALS", GCS_CREDENTIAL_PATH),
+    ("OSS_ENDPOINT", OSS_ENDPOINT),
+    ("OSS_ACCESS_KEY_ID", OSS_ACCESS_KEY_ID),
+    ("OSS_ACCESS_KEY_SECRET", OSS_ACCESS_KEY_SECRET),
+    ("AZURE_STORAGE_ACCOUNT_NAME", ADLS_ACCOUNT_NAME),
+    ("AZURE_STORAGE_ACCOUNT_KEY", ADLS_ACCOUNT_KEY),
+];
+
+/// Config holds configurations that take precedence over table
+/// properties.
+///
+/// # Examples
+///
+/// ```
+/// use icelake::config::Config;
+///
+/// let config = Config::from_env().with_option("write.format.default", "parquet");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    env: HashMap<String, String>,
+    options: HashMap<String, String>,
+}
+
+impl Config {
+    /// Create an empty config, which uses table properties only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a config with environment variables of current process.
+    pub fn from_env() -> Self {
+        Self::from_env_vars(std::env::vars())
+    }
+
+    /// Create a config with given environment variables.
+    ///
+    /// Variables unrelated to icelake are ignored.
+    pub fn from_env_vars(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+
+        let mut env = HashMap::new();
+        for (name, value) in &vars {
+            if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                if !key.is_empty() {
+                    env.insert(env_name_to_key(key), value.clone());
+                }
+            }
+        }
+        for (name, key) in STORAGE_ENV_VARS {
+            if let Some(value) = vars.get(*name) {
+                env.entry(key.to_string()).or_insert_with(|| value.clone());
+            }
+        }
+
+        Self {
+            env,
+            options: HashMap::new(),
+        }
+    }
+
+    /// Set an option, which takes precedence over all other sources.
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set options, which take precedence over all other sources.
+    pub fn with_options(mut self, options: HashMap<String, String>) -> Self {
+        self.options.extend(options);
+        self
+    }
+
+    /// Returns configurations set by environment variables and options,
+    /// without table properties.
+    pub fn overrides(&self) -> HashMap<String, String> {
+        let mut overrides = self.env.clone();
+        overrides.extend(self.options.iter().map(|(k, v)| (k.clone(), v.clone())));
+        overrides
+    }
+
+    /// Merge this config over given table properties.
+    pub fn merge(&self, properties: Option<&HashMap<String, String>>) -> HashMap<String, String> {
+        let mut merged = properties.cloned().unwrap_or_default();
+        merged.extend(self.overrides());
+        merged
+    }
+
+    /// Returns the value of `key` over given table properties.
+    pub fn get(&self, properties: Option<&HashMap<String, String>>, key: &str) -> Option<String> {
+        self.options
+            .get(key)
+            .or_else(|| self.env.get(key))
+            .or_else(|| properties.and_then(|v| v.get(key)))
+            .cloned()
+    }
+}
+
+fn env_name_to_key(name: &str) -> String {
+    name.to_lowercase()
+        .split("__")
+        .map(|v| v.replace('_', "."))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_env_vars() {
+        let config = Config::from_env_vars([
+            (
+                "ICELAKE_WRITE_TARGET__FILE__SIZE__BYTES".to_string(),
+                "1024".to_string(),
+            ),
+            ("AWS_DEFAULT_REGION".to_string(), "us-east-2".to_string()),
+            ("AWS_REGION".to_string(), "us-east-1".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ]);
+
+        assert_eq!(
+            config.overrides(),
+            HashMap::from([
+                (
+                    "write.target-file-size-bytes".to_string(),
+                    "1024".to_string()
+                ),
+                (S3_REGION.to_string(), "us-east-1".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_config_precedence() {
+        let properties = HashMap::from([
+            ("a".to_string(), "table".to_string()),
+            ("b".to_string(), "table".to_string()),
+            ("c".to_string(), "table".to_string()),
+        ]);
+        let config = Config::from_env_vars([
+            ("ICELAKE_B".to_string(), "env".to_string()),
+            ("ICELAKE_C".to_string(), "env".to_string()),
+        ])
+        .with_option("c", "option");
+
+        assert_eq!(config.get(Some(&properties), "a").unwrap(), "table");
+        assert_eq!(config.get(Some(&properties), "b").unwrap(), "env");
+        assert_eq!(config.get(Some(&properties), "c").unwrap(), "option");
+        assert_eq!(config.get(Some(&properties), "d"), None);
+
+        let merged = config.merge(Some(&properties));
+        assert_eq!(merged["a"], "table");
+        assert_eq!(merged["b"], "env");
+        assert_eq!(merged["c"], "option");
+    }
+}
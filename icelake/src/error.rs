@@ -27,6 +27,22 @@ pub enum ErrorKind {
     ///
     /// This error is returned when given iceberg feature is not supported.
     IcebergFeatureUnsupported,
+    /// Commit conflicts with a concurrent commit.
+    ///
+    /// This error is returned when the table has been updated by another
+    /// writer since its metadata was loaded, the commit can be retried on
+    /// top of the refreshed metadata.
+    CommitConflict,
+    /// Commit fails validation against changes committed concurrently.
+    ///
+    /// This error is returned when files committed by other writers since
+    /// the base snapshot of a transaction conflict with it, for example,
+    /// data files added to partitions it overwrites. Unlike
+    /// [`ErrorKind::CommitConflict`], it's not retried since the
+    /// transaction must be computed again from the new data.
+    ValidationFailed,
+    /// Table or namespace is not found in the catalog.
+    NotFound,
 }
 
 impl ErrorKind {
@@ -48,6 +64,9 @@ impl From<ErrorKind> for &'static str {
             ErrorKind::Unexpected => "Unexpected",
             ErrorKind::IcebergDataInvalid => "IcebergDataInvalid",
             ErrorKind::IcebergFeatureUnsupported => "IcebergFeatureUnsupported",
+            ErrorKind::CommitConflict => "CommitConflict",
+            ErrorKind::ValidationFailed => "ValidationFailed",
+            ErrorKind::NotFound => "NotFound",
         }
     }
 }
@@ -179,6 +198,16 @@ impl From<apache_avro::Error> for Error {
     }
 }
 
+impl From<prost::DecodeError> for Error {
+    fn from(v: prost::DecodeError) -> Self {
+        Self::new(
+            ErrorKind::IcebergDataInvalid,
+            "handling protobuf data failed",
+        )
+        .set_source(v)
+    }
+}
+
 impl From<serde_json::Error> for Error {
     fn from(v: serde_json::Error) -> Self {
         Self::new(ErrorKind::Unexpected, "handling json data failed").set_source(v)
@@ -197,12 +226,24 @@ impl From<parquet::errors::ParquetError> for Error {
     }
 }
 
+impl From<arrow::error::ArrowError> for Error {
+    fn from(v: arrow::error::ArrowError) -> Self {
+        Self::new(ErrorKind::Unexpected, "handling arrow data failed").set_source(v)
+    }
+}
+
 impl From<std::time::SystemTimeError> for Error {
     fn from(v: std::time::SystemTimeError) -> Self {
         Self::new(ErrorKind::Unexpected, "handling system time errror").set_source(v)
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(v: std::io::Error) -> Self {
+        Self::new(ErrorKind::Unexpected, "IO operation failed").set_source(v)
+    }
+}
+
 impl From<url::ParseError> for Error {
     fn from(v: url::ParseError) -> Self {
         Self::new(ErrorKind::IcebergDataInvalid, "Can't parse url.").set_source(v)
@@ -221,6 +262,20 @@ impl From<std::num::ParseIntError> for Error {
     }
 }
 
+#[cfg(feature = "rest-catalog")]
+impl From<reqwest::Error> for Error {
+    fn from(v: reqwest::Error) -> Self {
+        Self::new(ErrorKind::Unexpected, "http request failed").set_source(v)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<sqlx::Error> for Error {
+    fn from(v: sqlx::Error) -> Self {
+        Self::new(ErrorKind::Unexpected, "sql statement failed").set_source(v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::anyhow;
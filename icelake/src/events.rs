@@ -0,0 +1,185 @@
+//! events module notifies host systems of what happens to tables, like
+//! scans planned and snapshots committed, so that they can emit lineage or
+//! audit events.
+//!
+//! Listeners are registered to a table by
+//! [`crate::Table::register_event_listener`], and called synchronously by
+//! scans and transactions of the table after each event.
+
+use crate::types::expression::Predicate;
+use crate::types::{DataFile, Snapshot};
+use crate::Error;
+
+/// TableEventListener is notified of events of a table.
+///
+/// All callbacks do nothing by default, so listeners only implement events
+/// they're interested in. Callbacks should return quickly, since they're
+/// called on the path of scans and commits.
+pub trait TableEventListener: Send + Sync {
+    /// Called after a scan has planned files to read.
+    fn on_scan_planned(&self, _event: &ScanPlannedEvent<'_>) {}
+
+    /// Called after a transaction has committed a new snapshot.
+    fn on_snapshot_committed(&self, _event: &SnapshotCommittedEvent<'_>) {}
+
+    /// Called after a transaction has committed a snapshot adding files,
+    /// including delete files.
+    fn on_files_added(&self, _event: &FilesChangedEvent<'_>) {}
+
+    /// Called after a transaction has committed a snapshot removing files.
+    fn on_files_removed(&self, _event: &FilesChangedEvent<'_>) {}
+
+    /// Called after a transaction has failed to commit, conflicts retried
+    /// successfully are not failures.
+    fn on_commit_failed(&self, _event: &CommitFailedEvent<'_>) {}
+}
+
+/// A scan has planned files to read.
+#[derive(Debug)]
+pub struct ScanPlannedEvent<'a> {
+    /// Location of the table.
+    pub table_location: &'a str,
+    /// Id of the scanned snapshot, `None` if the table is empty.
+    pub snapshot_id: Option<i64>,
+    /// Id of the schema the scan reads by.
+    pub schema_id: i32,
+    /// Filter of the scan.
+    pub filter: Option<&'a Predicate>,
+    /// Number of data files to read.
+    pub data_files: usize,
+    /// Number of live delete files of the snapshot, which may apply to
+    /// data files to read.
+    pub delete_files: usize,
+}
+
+/// A transaction has committed a new snapshot.
+#[derive(Debug)]
+pub struct SnapshotCommittedEvent<'a> {
+    /// Location of the table.
+    pub table_location: &'a str,
+    /// The committed snapshot.
+    pub snapshot: &'a Snapshot,
+    /// The branch updated to the snapshot, `None` if the snapshot is only
+    /// staged.
+    pub branch: Option<&'a str>,
+}
+
+/// A transaction has committed a snapshot adding or removing files.
+#[derive(Debug)]
+pub struct FilesChangedEvent<'a> {
+    /// Location of the table.
+    pub table_location: &'a str,
+    /// Id of the committed snapshot.
+    pub snapshot_id: i64,
+    /// Added or removed files.
+    pub files: &'a [DataFile],
+}
+
+/// A transaction has failed to commit.
+#[derive(Debug)]
+pub struct CommitFailedEvent<'a> {
+    /// Location of the table.
+    pub table_location: &'a str,
+    /// The error the commit failed with.
+    pub error: &'a Error,
+    /// Number of attempts made, including retries of conflicts.
+    pub attempts: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::{ErrorKind, Result, Table};
+
+    #[derive(Default)]
+    struct RecordingListener {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl TableEventListener for RecordingListener {
+        fn on_scan_planned(&self, event: &ScanPlannedEvent<'_>) {
+            self.events.lock().unwrap().push(format!(
+                "scan {} files",
+                event.data_files + event.delete_files
+            ));
+        }
+
+        fn on_snapshot_committed(&self, event: &SnapshotCommittedEvent<'_>) {
+            self.events.lock().unwrap().push(format!(
+                "commit {} to {:?}",
+                event.snapshot.summary["operation"], event.branch
+            ));
+        }
+
+        fn on_files_added(&self, event: &FilesChangedEvent<'_>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("add {} files", event.files.len()));
+        }
+
+        fn on_files_removed(&self, event: &FilesChangedEvent<'_>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("remove {} files", event.files.len()));
+        }
+
+        fn on_commit_failed(&self, event: &CommitFailedEvent<'_>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("fail {}", event.error.kind()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_listener() -> Result<()> {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let listener = Arc::new(RecordingListener::default());
+        table.register_event_listener(listener.clone());
+        let take = || std::mem::take(&mut *listener.events.lock().unwrap());
+
+        let files = table.current_data_files().await?;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.delete_file(files[..2].to_vec());
+        tx.commit().await?;
+        assert_eq!(
+            take(),
+            vec![
+                "commit overwrite to Some(\"main\")",
+                "add 1 files",
+                "remove 2 files"
+            ]
+        );
+
+        table.scan().build()?.plan_tasks().await?;
+        assert_eq!(take(), vec!["scan 2 files"]);
+
+        // Files already deleted can't be deleted again.
+        let mut tx = table.new_transaction();
+        tx.delete_file(files[..1].to_vec());
+        assert!(tx.commit().await.is_err());
+        assert_eq!(
+            take(),
+            vec![format!("fail {}", ErrorKind::IcebergDataInvalid)]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,721 @@
+//! inspect module provides [`MetadataTables`] to read metadata of a table
+//! as arrow record batches, like metadata tables of spark.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BooleanArray, Int32Array, Int64Array, MapBuilder, StringArray, StringBuilder,
+    TimestampMillisecondArray,
+};
+use arrow::record_batch::RecordBatch;
+
+use crate::io::task_writer::partition_path;
+use crate::types::{
+    DataContentType, DataFile, ManifestEntry, ManifestListEntry, Snapshot, StructValue,
+};
+use crate::{Result, Table};
+
+/// TableStatistics are statistics of live data files of the current
+/// snapshot of a table, created by [`Table::statistics`].
+///
+/// Statistics are aggregated from manifests without reading data files,
+/// rows deleted by delete files are still counted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableStatistics {
+    /// Number of records of all data files.
+    pub record_count: i64,
+    /// Number of data files.
+    pub file_count: i64,
+    /// Total size of data files in bytes.
+    pub total_size_in_bytes: i64,
+    /// Statistics of each partition, ordered by spec id and partition path.
+    pub partitions: Vec<PartitionStatistics>,
+}
+
+/// PartitionStatistics are statistics of live data files of a partition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionStatistics {
+    /// Id of the partition spec of the partition.
+    pub spec_id: i32,
+    /// Partition values, empty for unpartitioned tables.
+    pub partition: StructValue,
+    /// Number of records of data files of the partition.
+    pub record_count: i64,
+    /// Number of data files of the partition.
+    pub file_count: i64,
+    /// Total size of data files of the partition in bytes.
+    pub total_size_in_bytes: i64,
+    /// Id of the snapshot adding the latest data file of the partition.
+    pub last_updated_snapshot_id: Option<i64>,
+    /// Commit time of the snapshot of `last_updated_snapshot_id` in
+    /// milliseconds, `None` if the snapshot is expired.
+    pub last_updated_at: Option<i64>,
+}
+
+/// MetadataTables exposes metadata of a table as record batches, created by
+/// [`Table::inspect`].
+///
+/// Tables are named and shaped after metadata tables of spark, so that
+/// tables can be introspected without external tools:
+///
+/// - `snapshots`: all valid snapshots of the table.
+/// - `history`: snapshots that have been current of the table.
+/// - `manifests`: manifests of the current snapshot.
+/// - `files`: live data and delete files of the current snapshot.
+/// - `partitions`: data files of the current snapshot grouped by partition.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// let table = icelake::Table::open("/path/to/table").await?;
+/// let snapshots = table.inspect().snapshots()?;
+/// println!("table has {} snapshots", snapshots.num_rows());
+/// # Ok(())
+/// # }
+/// ```
+pub struct MetadataTables<'a> {
+    table: &'a Table,
+}
+
+impl<'a> MetadataTables<'a> {
+    pub(crate) fn new(table: &'a Table) -> Self {
+        Self { table }
+    }
+
+    /// Returns snapshots of the table, with columns `committed_at`,
+    /// `snapshot_id`, `parent_id`, `operation`, `manifest_list` and
+    /// `summary`.
+    pub fn snapshots(&self) -> Result<RecordBatch> {
+        let snapshots = self.all_snapshots();
+
+        let mut summary = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+        for snapshot in &snapshots {
+            let mut entries: Vec<_> = snapshot
+                .summary
+                .iter()
+                .filter(|(k, _)| k.as_str() != "operation")
+                .collect();
+            entries.sort();
+            for (k, v) in entries {
+                summary.keys().append_value(k);
+                summary.values().append_value(v);
+            }
+            summary.append(true)?;
+        }
+
+        Ok(RecordBatch::try_from_iter_with_nullable([
+            (
+                "committed_at",
+                timestamp_array(snapshots.iter().map(|v| v.timestamp_ms)),
+                false,
+            ),
+            (
+                "snapshot_id",
+                Arc::new(Int64Array::from_iter_values(
+                    snapshots.iter().map(|v| v.snapshot_id),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "parent_id",
+                Arc::new(Int64Array::from_iter(
+                    snapshots.iter().map(|v| v.parent_snapshot_id),
+                )) as ArrayRef,
+                true,
+            ),
+            (
+                "operation",
+                Arc::new(StringArray::from_iter(
+                    snapshots.iter().map(|v| v.summary.get("operation")),
+                )) as ArrayRef,
+                true,
+            ),
+            (
+                "manifest_list",
+                Arc::new(StringArray::from_iter_values(
+                    snapshots.iter().map(|v| v.manifest_list.as_str()),
+                )) as ArrayRef,
+                false,
+            ),
+            ("summary", Arc::new(summary.finish()) as ArrayRef, false),
+        ])?)
+    }
+
+    /// Returns snapshots that have been current of the table in the order
+    /// they became current, with columns `made_current_at`, `snapshot_id`,
+    /// `parent_id` and `is_current_ancestor`.
+    ///
+    /// Snapshots rolled back are not ancestors of the current snapshot.
+    pub fn history(&self) -> Result<RecordBatch> {
+        let metadata = self.table.current_table_metadata();
+        let snapshots = self.all_snapshots();
+        let parent_id = |snapshot_id: i64| {
+            snapshots
+                .iter()
+                .find(|v| v.snapshot_id == snapshot_id)
+                .and_then(|v| v.parent_snapshot_id)
+        };
+
+        let ancestors: HashSet<i64> = match metadata.current_snapshot_id {
+            Some(snapshot_id) => metadata
+                .ancestors(snapshot_id)
+                .iter()
+                .map(|v| v.snapshot_id)
+                .collect(),
+            None => HashSet::new(),
+        };
+
+        let logs = metadata.snapshot_log.clone().unwrap_or_default();
+        Ok(RecordBatch::try_from_iter_with_nullable([
+            (
+                "made_current_at",
+                timestamp_array(logs.iter().map(|v| v.timestamp_ms)),
+                false,
+            ),
+            (
+                "snapshot_id",
+                Arc::new(Int64Array::from_iter_values(
+                    logs.iter().map(|v| v.snapshot_id),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "parent_id",
+                Arc::new(Int64Array::from_iter(
+                    logs.iter().map(|v| parent_id(v.snapshot_id)),
+                )) as ArrayRef,
+                true,
+            ),
+            (
+                "is_current_ancestor",
+                Arc::new(BooleanArray::from_iter(
+                    logs.iter()
+                        .map(|v| Some(ancestors.contains(&v.snapshot_id))),
+                )) as ArrayRef,
+                false,
+            ),
+        ])?)
+    }
+
+    /// Returns manifests of the current snapshot, with columns `content`,
+    /// `path`, `length`, `partition_spec_id`, `added_snapshot_id`,
+    /// `added_data_files_count`, `existing_data_files_count` and
+    /// `deleted_data_files_count`.
+    pub async fn manifests(&self) -> Result<RecordBatch> {
+        let entries = self.current_manifests().await?;
+        Ok(RecordBatch::try_from_iter_with_nullable([
+            (
+                "content",
+                Arc::new(Int32Array::from_iter_values(
+                    entries.iter().map(|v| v.content as i32),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "path",
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|v| v.manifest_path.as_str()),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "length",
+                Arc::new(Int64Array::from_iter_values(
+                    entries.iter().map(|v| v.manifest_length),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "partition_spec_id",
+                Arc::new(Int32Array::from_iter_values(
+                    entries.iter().map(|v| v.partition_spec_id),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "added_snapshot_id",
+                Arc::new(Int64Array::from_iter_values(
+                    entries.iter().map(|v| v.added_snapshot_id),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "added_data_files_count",
+                Arc::new(Int32Array::from_iter_values(
+                    entries.iter().map(|v| v.added_data_files_count),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "existing_data_files_count",
+                Arc::new(Int32Array::from_iter_values(
+                    entries.iter().map(|v| v.existing_data_files_count),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "deleted_data_files_count",
+                Arc::new(Int32Array::from_iter_values(
+                    entries.iter().map(|v| v.deleted_data_files_count),
+                )) as ArrayRef,
+                false,
+            ),
+        ])?)
+    }
+
+    /// Returns live data and delete files of the current snapshot, with
+    /// columns `content`, `file_path`, `file_format`, `spec_id`,
+    /// `partition`, `record_count` and `file_size_in_bytes`.
+    ///
+    /// `partition` is formatted like partition paths, e.g. `a=1/b=x`.
+    pub async fn files(&self) -> Result<RecordBatch> {
+        let files = self.current_files().await?;
+        Ok(RecordBatch::try_from_iter_with_nullable([
+            (
+                "content",
+                Arc::new(Int32Array::from_iter_values(
+                    files.iter().map(|(_, v)| v.content as i32),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "file_path",
+                Arc::new(StringArray::from_iter_values(
+                    files.iter().map(|(_, v)| v.file_path.as_str()),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "file_format",
+                Arc::new(StringArray::from_iter_values(
+                    files.iter().map(|(_, v)| v.file_format.to_string()),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "spec_id",
+                Arc::new(Int32Array::from_iter_values(
+                    files.iter().map(|(spec_id, _)| *spec_id),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "partition",
+                Arc::new(StringArray::from_iter_values(
+                    files.iter().map(|(_, v)| partition_path(&v.partition)),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "record_count",
+                Arc::new(Int64Array::from_iter_values(
+                    files.iter().map(|(_, v)| v.record_count),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "file_size_in_bytes",
+                Arc::new(Int64Array::from_iter_values(
+                    files.iter().map(|(_, v)| v.file_size_in_bytes),
+                )) as ArrayRef,
+                false,
+            ),
+        ])?)
+    }
+
+    /// Returns live data files of the current snapshot grouped by
+    /// partition, with columns `partition`, `spec_id`, `record_count`,
+    /// `file_count`, `total_data_file_size_in_bytes`, `last_updated_at` and
+    /// `last_updated_snapshot_id`, see [`PartitionStatistics`].
+    ///
+    /// `partition` is formatted like [`MetadataTables::files`], rows are
+    /// ordered by `spec_id` and `partition`.
+    pub async fn partitions(&self) -> Result<RecordBatch> {
+        let partitions = self.statistics().await?.partitions;
+        Ok(RecordBatch::try_from_iter_with_nullable([
+            (
+                "partition",
+                Arc::new(StringArray::from_iter_values(
+                    partitions.iter().map(|v| partition_path(&v.partition)),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "spec_id",
+                Arc::new(Int32Array::from_iter_values(
+                    partitions.iter().map(|v| v.spec_id),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "record_count",
+                Arc::new(Int64Array::from_iter_values(
+                    partitions.iter().map(|v| v.record_count),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "file_count",
+                Arc::new(Int32Array::from_iter_values(
+                    partitions.iter().map(|v| v.file_count as i32),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "total_data_file_size_in_bytes",
+                Arc::new(Int64Array::from_iter_values(
+                    partitions.iter().map(|v| v.total_size_in_bytes),
+                )) as ArrayRef,
+                false,
+            ),
+            (
+                "last_updated_at",
+                Arc::new(
+                    TimestampMillisecondArray::from_iter(
+                        partitions.iter().map(|v| v.last_updated_at),
+                    )
+                    .with_timezone("UTC"),
+                ) as ArrayRef,
+                true,
+            ),
+            (
+                "last_updated_snapshot_id",
+                Arc::new(Int64Array::from_iter(
+                    partitions.iter().map(|v| v.last_updated_snapshot_id),
+                )) as ArrayRef,
+                true,
+            ),
+        ])?)
+    }
+
+    /// Returns statistics of live data files of the current snapshot, see
+    /// [`TableStatistics`].
+    pub async fn statistics(&self) -> Result<TableStatistics> {
+        let metadata = self.table.current_table_metadata();
+        let mut partitions: BTreeMap<(i32, String), PartitionStatistics> = BTreeMap::new();
+        // Sequence numbers of the latest data files of partitions.
+        let mut last_updated: BTreeMap<(i32, String), i64> = BTreeMap::new();
+        for (spec_id, entry) in self.current_entries().await? {
+            let data_file = &entry.data_file;
+            if data_file.content != DataContentType::Data {
+                continue;
+            }
+            let key = (spec_id, partition_path(&data_file.partition));
+            let sequence_number = entry.sequence_number.unwrap_or(0);
+            let stats = partitions
+                .entry(key.clone())
+                .or_insert_with(|| PartitionStatistics {
+                    spec_id,
+                    partition: data_file.partition.clone(),
+                    record_count: 0,
+                    file_count: 0,
+                    total_size_in_bytes: 0,
+                    last_updated_snapshot_id: None,
+                    last_updated_at: None,
+                });
+            stats.record_count += data_file.record_count;
+            stats.file_count += 1;
+            stats.total_size_in_bytes += data_file.file_size_in_bytes;
+            let last = last_updated.entry(key).or_insert(i64::MIN);
+            if sequence_number >= *last {
+                *last = sequence_number;
+                stats.last_updated_snapshot_id = entry.snapshot_id;
+                stats.last_updated_at = entry
+                    .snapshot_id
+                    .and_then(|id| metadata.snapshot(id))
+                    .map(|v| v.timestamp_ms);
+            }
+        }
+
+        let partitions: Vec<_> = partitions.into_values().collect();
+        Ok(TableStatistics {
+            record_count: partitions.iter().map(|v| v.record_count).sum(),
+            file_count: partitions.iter().map(|v| v.file_count).sum(),
+            total_size_in_bytes: partitions.iter().map(|v| v.total_size_in_bytes).sum(),
+            partitions,
+        })
+    }
+
+    fn all_snapshots(&self) -> Vec<&'a Snapshot> {
+        self.table
+            .current_table_metadata()
+            .snapshots
+            .iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Manifests of the current snapshot, empty if the table has no
+    /// snapshot.
+    async fn current_manifests(&self) -> Result<Vec<ManifestListEntry>> {
+        let metadata = self.table.current_table_metadata();
+        if metadata.current_snapshot_id.is_none() {
+            return Ok(vec![]);
+        }
+        Ok(metadata
+            .current_snapshot()?
+            .load_manifest_list(self.table)
+            .await?
+            .entries)
+    }
+
+    /// Live files of the current snapshot with their partition spec ids.
+    async fn current_files(&self) -> Result<Vec<(i32, DataFile)>> {
+        Ok(self
+            .current_entries()
+            .await?
+            .into_iter()
+            .map(|(spec_id, v)| (spec_id, v.data_file))
+            .collect())
+    }
+
+    /// Manifest entries of live files of the current snapshot with their
+    /// partition spec ids.
+    async fn current_entries(&self) -> Result<Vec<(i32, ManifestEntry)>> {
+        let mut entries = vec![];
+        for entry in self.current_manifests().await? {
+            let manifest = entry.load_manifest(self.table).await?;
+            entries.extend(
+                manifest
+                    .entries
+                    .into_iter()
+                    .filter(|v| v.is_alive())
+                    .map(|v| (entry.partition_spec_id, v)),
+            );
+        }
+        Ok(entries)
+    }
+}
+
+fn timestamp_array(values: impl IntoIterator<Item = i64>) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from_iter_values(values).with_timezone("UTC"))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, AsArray, MapArray};
+    use arrow::datatypes::{Int32Type, Int64Type};
+
+    use super::*;
+    use crate::test_utils::{prepare_partitioned_table_dir, prepare_table_dir};
+
+    #[tokio::test]
+    async fn test_metadata_tables() {
+        let tmp_dir = prepare_table_dir();
+        let table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let metadata = table.current_table_metadata();
+        let current_snapshot_id = metadata.current_snapshot_id.unwrap();
+        let inspect = table.inspect();
+
+        let snapshots = inspect.snapshots().unwrap();
+        assert_eq!(
+            snapshots.num_rows(),
+            metadata.snapshots.as_ref().unwrap().len()
+        );
+        assert_eq!(
+            snapshots
+                .column_by_name("operation")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "append"
+        );
+        let summary = snapshots
+            .column_by_name("summary")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<MapArray>()
+            .unwrap();
+        assert!(summary
+            .value(0)
+            .column(0)
+            .as_string::<i32>()
+            .iter()
+            .any(|v| v == Some("added-data-files")));
+
+        let history = inspect.history().unwrap();
+        assert_eq!(
+            history.num_rows(),
+            metadata.snapshot_log.as_ref().unwrap().len()
+        );
+        let snapshot_ids = history
+            .column_by_name("snapshot_id")
+            .unwrap()
+            .as_primitive::<Int64Type>();
+        assert_eq!(
+            snapshot_ids.value(history.num_rows() - 1),
+            current_snapshot_id
+        );
+        assert!(history
+            .column_by_name("is_current_ancestor")
+            .unwrap()
+            .as_boolean()
+            .value(history.num_rows() - 1));
+
+        let manifests = inspect.manifests().await.unwrap();
+        assert_eq!(manifests.num_rows(), 1);
+
+        let files = inspect.files().await.unwrap();
+        assert_eq!(files.num_rows(), 3);
+        assert_eq!(
+            files
+                .column_by_name("file_format")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "parquet"
+        );
+
+        let partitions = inspect.partitions().await.unwrap();
+        assert_eq!(partitions.num_rows(), 1);
+        assert_eq!(
+            partitions
+                .column_by_name("record_count")
+                .unwrap()
+                .as_primitive::<Int64Type>()
+                .value(0),
+            3
+        );
+        assert_eq!(
+            partitions
+                .column_by_name("file_count")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .value(0),
+            3
+        );
+
+        let statistics = table.statistics().await.unwrap();
+        let files = table.current_data_files().await.unwrap();
+        assert_eq!(statistics.record_count, 3);
+        assert_eq!(statistics.file_count, 3);
+        assert_eq!(
+            statistics.total_size_in_bytes,
+            files.iter().map(|v| v.file_size_in_bytes).sum::<i64>()
+        );
+        assert_eq!(statistics.partitions.len(), 1);
+        assert_eq!(statistics.partitions[0].spec_id, 0);
+        assert_eq!(statistics.partitions[0].record_count, 3);
+
+        let partitions = table.partitions().await.unwrap();
+        assert_eq!(partitions, statistics.partitions);
+        assert_eq!(
+            partitions[0].last_updated_snapshot_id,
+            Some(current_snapshot_id)
+        );
+        assert_eq!(
+            partitions[0].last_updated_at,
+            metadata
+                .snapshot(current_snapshot_id)
+                .map(|v| v.timestamp_ms)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_statistics_of_partition_specs() {
+        let tmp_dir = prepare_partitioned_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let mut snapshot_ids = vec![table.current_table_metadata().current_snapshot_id];
+        for data in [vec!["a", "b", "a"], vec!["a"]] {
+            let ids = (0..data.len() as i64).collect::<Vec<_>>();
+            let batch = RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])
+            .unwrap();
+            let mut writer = table.task_writer().await.unwrap();
+            writer.write(&batch).await.unwrap();
+            let mut append = table.new_append();
+            append.append_file(writer.close().await.unwrap());
+            append.commit().await.unwrap();
+            snapshot_ids.push(table.current_table_metadata().current_snapshot_id);
+        }
+
+        // Files of the unpartitioned spec are grouped apart from files of
+        // the new spec.
+        let statistics = table.statistics().await.unwrap();
+        let files = table.current_data_files().await.unwrap();
+        assert_eq!(statistics.record_count, 7);
+        assert_eq!(statistics.file_count, 6);
+        assert_eq!(
+            statistics.total_size_in_bytes,
+            files.iter().map(|v| v.file_size_in_bytes).sum::<i64>()
+        );
+        assert_eq!(
+            statistics
+                .partitions
+                .iter()
+                .map(|v| (
+                    v.spec_id,
+                    partition_path(&v.partition),
+                    v.record_count,
+                    v.file_count
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "".to_string(), 3, 3),
+                (1, "data=a".to_string(), 3, 2),
+                (1, "data=b".to_string(), 1, 1),
+            ]
+        );
+        for partition in &statistics.partitions {
+            let size: i64 = files
+                .iter()
+                .filter(|v| v.partition == partition.partition)
+                .map(|v| v.file_size_in_bytes)
+                .sum();
+            assert_eq!(partition.total_size_in_bytes, size);
+        }
+
+        // Partitions are last updated by their latest appends.
+        let partitions = table.partitions().await.unwrap();
+        assert_eq!(partitions, statistics.partitions);
+        assert_eq!(
+            partitions
+                .iter()
+                .map(|v| v.last_updated_snapshot_id)
+                .collect::<Vec<_>>(),
+            vec![snapshot_ids[0], snapshot_ids[2], snapshot_ids[1]]
+        );
+        let metadata = table.current_table_metadata();
+        for partition in &partitions {
+            assert_eq!(
+                partition.last_updated_at,
+                metadata
+                    .snapshot(partition.last_updated_snapshot_id.unwrap())
+                    .map(|v| v.timestamp_ms)
+            );
+        }
+
+        let batch = table.inspect().partitions().await.unwrap();
+        assert_eq!(
+            batch
+                .column_by_name("partition")
+                .unwrap()
+                .as_string::<i32>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(""), Some("data=a"), Some("data=b")]
+        );
+        assert_eq!(
+            batch
+                .column_by_name("spec_id")
+                .unwrap()
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![0, 1, 1]
+        );
+        assert_eq!(
+            batch
+                .column_by_name("last_updated_snapshot_id")
+                .unwrap()
+                .as_primitive::<Int64Type>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![snapshot_ids[0], snapshot_ids[2], snapshot_ids[1]]
+        );
+    }
+}
@@ -0,0 +1,737 @@
+//! avro module decodes avro data files into arrow record batches by the
+//! table schema.
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use apache_avro::schema::{RecordField, RecordSchema};
+use apache_avro::types::Value;
+use apache_avro::{Reader, Schema as AvroSchema};
+use arrow::array::{
+    new_null_array, Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array,
+    FixedSizeBinaryArray, Float32Array, Float64Array, Int32Array, Int64Array, LargeBinaryArray,
+    ListArray, MapArray, StringArray, StructArray, Time64MicrosecondArray,
+    TimestampMicrosecondArray, TimestampNanosecondArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{DataType, Field as ArrowField, Fields, Schema as ArrowSchema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+
+use super::parquet::ParquetProjection;
+use crate::types::{Any, Field, Primitive, Schema, UTC_TIMEZONE};
+use crate::{Error, ErrorKind, Result};
+
+/// Number of rows in a decoded batch.
+const BATCH_SIZE: usize = 1024;
+
+/// Attribute of avro record fields recording iceberg field id.
+const FIELD_ID: &str = "field-id";
+
+/// AvroBatchReader decodes records of an avro data file into batches of
+/// top level columns of the table schema.
+///
+/// Columns are matched by field ids recorded in the avro schema. Files
+/// written without field ids are matched by names in the table schema
+/// instead. Columns missing in the file are skipped, and values are
+/// promoted to the types in the table schema.
+pub(crate) struct AvroBatchReader {
+    reader: Reader<'static, Cursor<Vec<u8>>>,
+    /// Table fields to decode and their index in the avro record.
+    columns: Vec<(Field, usize)>,
+}
+
+impl AvroBatchReader {
+    /// Create a reader of the avro file content.
+    ///
+    /// Only top level columns containing projected fields are decoded if
+    /// `projection` is given.
+    pub fn try_new(
+        content: Vec<u8>,
+        schema: &Schema,
+        projection: Option<&ParquetProjection>,
+    ) -> Result<Self> {
+        let reader = Reader::new(Cursor::new(content))?;
+        let AvroSchema::Record(record) = reader.writer_schema() else {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Schema of avro data file is not a record",
+            ));
+        };
+
+        let fields: Vec<&Field> = match projection {
+            Some(projection) => {
+                let field_ids = projection.field_ids();
+                let mut fields = vec![];
+                for id in field_ids {
+                    if let Some(field) = schema.fields.iter().find(|v| contains_field(v, id)) {
+                        if !fields.iter().any(|v: &&Field| v.id == field.id) {
+                            fields.push(field);
+                        }
+                    }
+                }
+                fields
+            }
+            None => schema.fields.iter().collect(),
+        };
+        let columns = fields
+            .into_iter()
+            .filter_map(|field| Some((field.clone(), match_field(record, field)?)))
+            .collect();
+
+        Ok(Self { reader, columns })
+    }
+
+    fn next_batch(&mut self) -> Option<Result<RecordBatch>> {
+        let mut records = Vec::with_capacity(BATCH_SIZE);
+        for value in self.reader.by_ref().take(BATCH_SIZE) {
+            match value {
+                Ok(Value::Record(fields)) => records.push(fields),
+                Ok(v) => {
+                    return Some(Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Avro data file contains a non record value: {v:?}"),
+                    )))
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+        if records.is_empty() {
+            return None;
+        }
+        Some(self.decode(&records))
+    }
+
+    fn decode(&self, records: &[Vec<(String, Value)>]) -> Result<RecordBatch> {
+        let AvroSchema::Record(record) = self.reader.writer_schema() else {
+            unreachable!("checked when the reader is created");
+        };
+
+        let mut fields = Vec::with_capacity(self.columns.len());
+        let mut arrays = Vec::with_capacity(self.columns.len());
+        for (field, idx) in &self.columns {
+            let values: Vec<_> = records.iter().map(|v| non_null_value(&v[*idx].1)).collect();
+            let array = build_array(
+                &values,
+                non_null_schema(&record.fields[*idx].schema),
+                &field.field_type,
+            )?;
+            fields.push(ArrowField::new(
+                &field.name,
+                array.data_type().clone(),
+                !field.required,
+            ));
+            arrays.push(array);
+        }
+
+        Ok(RecordBatch::try_new_with_options(
+            Arc::new(ArrowSchema::new(fields)),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(records.len())),
+        )?)
+    }
+}
+
+impl Iterator for AvroBatchReader {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch()
+    }
+}
+
+/// Returns whether `field` is or contains the field of `id`.
+fn contains_field(field: &Field, id: i32) -> bool {
+    fn contains(ty: &Any, id: i32) -> bool {
+        match ty {
+            Any::Primitive(_) => false,
+            Any::Struct(v) => v.fields().iter().any(|v| contains_field(v, id)),
+            Any::List(v) => v.element_id == id || contains(&v.element_type, id),
+            Any::Map(v) => {
+                v.key_id == id
+                    || v.value_id == id
+                    || contains(&v.key_type, id)
+                    || contains(&v.value_type, id)
+            }
+        }
+    }
+    field.id == id || contains(&field.field_type, id)
+}
+
+fn field_id(field: &RecordField) -> Option<i32> {
+    field
+        .custom_attributes
+        .get(FIELD_ID)
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32)
+}
+
+/// Returns the index of the avro record field matching `field`.
+fn match_field(record: &RecordSchema, field: &Field) -> Option<usize> {
+    if record.fields.iter().any(|v| field_id(v).is_some()) {
+        record
+            .fields
+            .iter()
+            .position(|v| field_id(v) == Some(field.id))
+    } else {
+        record.lookup.get(&field.name).copied()
+    }
+}
+
+/// Returns the non null branch of optional schemas like `["null", T]`.
+fn non_null_schema(schema: &AvroSchema) -> &AvroSchema {
+    match schema {
+        AvroSchema::Union(union) => union
+            .variants()
+            .iter()
+            .find(|v| **v != AvroSchema::Null)
+            .unwrap_or(schema),
+        _ => schema,
+    }
+}
+
+fn non_null_value(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Null => None,
+        Value::Union(_, v) => non_null_value(v),
+        v => Some(v),
+    }
+}
+
+fn invalid_value(value: &Value, ty: &Any) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Can't decode avro value {value:?} as {ty:?}"),
+    )
+}
+
+fn nulls(values: &[Option<&Value>]) -> Option<NullBuffer> {
+    let nulls = NullBuffer::from_iter(values.iter().map(|v| v.is_some()));
+    (nulls.null_count() > 0).then_some(nulls)
+}
+
+/// Convert values of `schema` into an array of iceberg type `ty`.
+fn build_array(values: &[Option<&Value>], schema: &AvroSchema, ty: &Any) -> Result<ArrayRef> {
+    // Convert every non null value by `f`.
+    fn convert<'a, T>(
+        values: &[Option<&'a Value>],
+        ty: &Any,
+        f: impl Fn(&'a Value) -> Option<T>,
+    ) -> Result<Vec<Option<T>>> {
+        values
+            .iter()
+            .map(|v| {
+                v.map(|v| f(v).ok_or_else(|| invalid_value(v, ty)))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    let array: ArrayRef = match ty {
+        Any::Primitive(primitive) => match primitive {
+            Primitive::Boolean => Arc::new(BooleanArray::from(convert(values, ty, |v| match v {
+                Value::Boolean(v) => Some(*v),
+                _ => None,
+            })?)),
+            Primitive::Int => Arc::new(Int32Array::from(convert(values, ty, |v| match v {
+                Value::Int(v) => Some(*v),
+                _ => None,
+            })?)),
+            Primitive::Long => Arc::new(Int64Array::from(convert(values, ty, |v| match v {
+                Value::Long(v) => Some(*v),
+                Value::Int(v) => Some(*v as i64),
+                _ => None,
+            })?)),
+            Primitive::Float => Arc::new(Float32Array::from(convert(values, ty, |v| match v {
+                Value::Float(v) => Some(*v),
+                _ => None,
+            })?)),
+            Primitive::Double => Arc::new(Float64Array::from(convert(values, ty, |v| match v {
+                Value::Double(v) => Some(*v),
+                Value::Float(v) => Some(*v as f64),
+                _ => None,
+            })?)),
+            Primitive::Decimal { precision, scale } => {
+                let values = convert(values, ty, |v| match v {
+                    Value::Decimal(v) => Vec::<u8>::try_from(v).ok().and_then(|v| to_i128(&v)),
+                    Value::Bytes(v) | Value::Fixed(_, v) => to_i128(v),
+                    _ => None,
+                })?;
+                Arc::new(
+                    Decimal128Array::from(values)
+                        .with_precision_and_scale(*precision, *scale as i8)?,
+                )
+            }
+            Primitive::Date => Arc::new(Date32Array::from(convert(values, ty, |v| match v {
+                Value::Date(v) | Value::Int(v) => Some(*v),
+                _ => None,
+            })?)),
+            Primitive::Time => Arc::new(Time64MicrosecondArray::from(convert(
+                values,
+                ty,
+                |v| match v {
+                    Value::TimeMicros(v) | Value::Long(v) => Some(*v),
+                    Value::TimeMillis(v) => Some(*v as i64 * 1000),
+                    _ => None,
+                },
+            )?)),
+            Primitive::Timestamp | Primitive::Timestampz => Arc::new(
+                TimestampMicrosecondArray::from(convert(values, ty, |v| match v {
+                    Value::TimestampMicros(v) | Value::Long(v) => Some(*v),
+                    Value::TimestampMillis(v) => Some(*v * 1000),
+                    _ => None,
+                })?)
+                .with_timezone_opt((primitive == &Primitive::Timestampz).then_some(UTC_TIMEZONE)),
+            ),
+            Primitive::TimestampNs | Primitive::TimestampzNs => Arc::new(
+                TimestampNanosecondArray::from(convert(values, ty, |v| match v {
+                    Value::Long(v) => Some(*v),
+                    _ => None,
+                })?)
+                .with_timezone_opt((primitive == &Primitive::TimestampzNs).then_some(UTC_TIMEZONE)),
+            ),
+            Primitive::String => Arc::new(StringArray::from(convert(values, ty, |v| match v {
+                Value::String(v) | Value::Enum(_, v) => Some(v.as_str()),
+                _ => None,
+            })?)),
+            Primitive::Uuid => {
+                let values = convert(values, ty, |v| match v {
+                    Value::Uuid(v) => Some(v.as_bytes().to_vec()),
+                    Value::Fixed(16, v) => Some(v.clone()),
+                    _ => None,
+                })?;
+                Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                    values.into_iter(),
+                    16,
+                )?)
+            }
+            Primitive::Fixed(len) => {
+                let values = convert(values, ty, |v| match v {
+                    Value::Fixed(_, v) => Some(v.as_slice()),
+                    _ => None,
+                })?;
+                Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                    values.into_iter(),
+                    *len as i32,
+                )?)
+            }
+            Primitive::Binary => {
+                Arc::new(LargeBinaryArray::from(convert(values, ty, |v| match v {
+                    Value::Bytes(v) | Value::Fixed(_, v) => Some(v.as_slice()),
+                    _ => None,
+                })?))
+            }
+        },
+        Any::Struct(v) => {
+            let AvroSchema::Record(record) = schema else {
+                return Err(invalid_schema(schema, ty));
+            };
+            let records = convert(values, ty, |v| match v {
+                Value::Record(v) => Some(v),
+                _ => None,
+            })?;
+
+            let mut fields = Vec::with_capacity(v.fields().len());
+            let mut arrays = Vec::with_capacity(v.fields().len());
+            for field in v.fields() {
+                let array = match match_field(record, field) {
+                    Some(idx) => {
+                        let values: Vec<_> = records
+                            .iter()
+                            .map(|v| v.and_then(|v| non_null_value(&v[idx].1)))
+                            .collect();
+                        build_array(
+                            &values,
+                            non_null_schema(&record.fields[idx].schema),
+                            &field.field_type,
+                        )?
+                    }
+                    None => {
+                        new_null_array(&DataType::try_from(field.field_type.clone())?, values.len())
+                    }
+                };
+                fields.push(ArrowField::new(
+                    &field.name,
+                    array.data_type().clone(),
+                    !field.required,
+                ));
+                arrays.push(array);
+            }
+            Arc::new(StructArray::try_new(
+                Fields::from(fields),
+                arrays,
+                nulls(values),
+            )?)
+        }
+        Any::List(v) => {
+            let AvroSchema::Array(item_schema) = schema else {
+                return Err(invalid_schema(schema, ty));
+            };
+            let lists = convert(values, ty, |v| match v {
+                Value::Array(v) => Some(v),
+                _ => None,
+            })?;
+
+            let mut offsets = vec![0i32];
+            let mut items = vec![];
+            for list in &lists {
+                let list = list.map(|v| v.as_slice()).unwrap_or_default();
+                items.extend(list.iter().map(non_null_value));
+                offsets.push(items.len() as i32);
+            }
+            let items = build_array(&items, non_null_schema(item_schema), &v.element_type)?;
+            let field = ArrowField::new("item", items.data_type().clone(), !v.element_required);
+            Arc::new(ListArray::try_new(
+                Arc::new(field),
+                OffsetBuffer::new(offsets.into()),
+                items,
+                nulls(values),
+            )?)
+        }
+        Any::Map(v) => {
+            // Maps with string keys are encoded as avro maps, others as
+            // arrays of key value records.
+            let mut offsets = vec![0i32];
+            let mut owned_keys = vec![];
+            let mut entries = vec![];
+            let (key_schema, value_schema) = match schema {
+                AvroSchema::Map(value_schema) => {
+                    let maps = convert(values, ty, |v| match v {
+                        Value::Map(v) => Some(v),
+                        _ => None,
+                    })?;
+                    for map in maps.iter().flatten() {
+                        for (key, value) in map.iter() {
+                            owned_keys.push(Value::String(key.clone()));
+                            entries.push(non_null_value(value));
+                        }
+                    }
+                    for map in &maps {
+                        offsets.push(offsets.last().unwrap() + map.map_or(0, |v| v.len() as i32));
+                    }
+                    (&AvroSchema::String, value_schema.as_ref())
+                }
+                AvroSchema::Array(item_schema) => {
+                    let AvroSchema::Record(record) = item_schema.as_ref() else {
+                        return Err(invalid_schema(schema, ty));
+                    };
+                    let (Some(key_idx), Some(value_idx)) =
+                        (record.lookup.get("key"), record.lookup.get("value"))
+                    else {
+                        return Err(invalid_schema(schema, ty));
+                    };
+                    let lists = convert(values, ty, |v| match v {
+                        Value::Array(v) => Some(v),
+                        _ => None,
+                    })?;
+                    for list in &lists {
+                        let list = list.map(|v| v.as_slice()).unwrap_or_default();
+                        for entry in list {
+                            let Value::Record(entry) = entry else {
+                                return Err(invalid_value(entry, ty));
+                            };
+                            owned_keys.push(entry[*key_idx].1.clone());
+                            entries.push(non_null_value(&entry[*value_idx].1));
+                        }
+                        offsets.push(offsets.last().unwrap() + list.len() as i32);
+                    }
+                    (
+                        &record.fields[*key_idx].schema,
+                        &record.fields[*value_idx].schema,
+                    )
+                }
+                _ => return Err(invalid_schema(schema, ty)),
+            };
+
+            let keys: Vec<_> = owned_keys.iter().map(non_null_value).collect();
+            let keys = build_array(&keys, non_null_schema(key_schema), &v.key_type)?;
+            let values_array = build_array(&entries, non_null_schema(value_schema), &v.value_type)?;
+            let entry_fields = Fields::from(vec![
+                ArrowField::new("key", keys.data_type().clone(), false),
+                ArrowField::new("value", values_array.data_type().clone(), !v.value_required),
+            ]);
+            let entries =
+                StructArray::try_new(entry_fields.clone(), vec![keys, values_array], None)?;
+            Arc::new(MapArray::try_new(
+                Arc::new(ArrowField::new(
+                    "entries",
+                    DataType::Struct(entry_fields),
+                    false,
+                )),
+                OffsetBuffer::new(offsets.into()),
+                entries,
+                nulls(values),
+                false,
+            )?)
+        }
+    };
+    Ok(array)
+}
+
+fn invalid_schema(schema: &AvroSchema, ty: &Any) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Can't decode avro schema {schema:?} as {ty:?}"),
+    )
+}
+
+/// Decode big endian two's complement bytes of decimals.
+fn to_i128(bytes: &[u8]) -> Option<i128> {
+    if bytes.len() > 16 {
+        return None;
+    }
+    let sign = if bytes.first().is_some_and(|v| *v & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut buf = [sign; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    Some(i128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::to_avro_datum;
+    use arrow::array::{AsArray, Int32Array};
+    use arrow::datatypes::Int64Type;
+
+    use super::*;
+    use crate::types::{List, Map, Struct};
+
+    fn table_schema() -> Schema {
+        Schema {
+            schema_id: 1,
+            identifier_field_ids: None,
+            fields: vec![
+                Field::required(1, "id", Any::Primitive(Primitive::Long)),
+                Field::optional(2, "data", Any::Primitive(Primitive::String)),
+                Field::optional(
+                    3,
+                    "point",
+                    Any::Struct(
+                        Struct::new(vec![
+                            Field::required(4, "x", Any::Primitive(Primitive::Double)),
+                            Field::optional(5, "y", Any::Primitive(Primitive::Double)),
+                        ])
+                        .into(),
+                    ),
+                ),
+                Field::optional(
+                    6,
+                    "tags",
+                    Any::List(List {
+                        element_id: 7,
+                        element_required: false,
+                        element_type: Box::new(Any::Primitive(Primitive::String)),
+                    }),
+                ),
+                Field::optional(
+                    8,
+                    "counts",
+                    Any::Map(Map {
+                        key_id: 9,
+                        key_type: Box::new(Any::Primitive(Primitive::Int)),
+                        value_id: 10,
+                        value_required: true,
+                        value_type: Box::new(Any::Primitive(Primitive::Long)),
+                    }),
+                ),
+                Field::optional(11, "missing", Any::Primitive(Primitive::Int)),
+            ],
+        }
+    }
+
+    /// Written by an older schema, where `id` was an int and `data` was
+    /// named `payload`.
+    fn write_file() -> Vec<u8> {
+        let schema_json = r#"{
+                "type": "record",
+                "name": "r1",
+                "fields": [
+                    {"name": "payload", "type": ["null", "string"], "default": null, "field-id": 2},
+                    {"name": "id", "type": "int", "field-id": 1},
+                    {"name": "point", "type": ["null", {
+                        "type": "record",
+                        "name": "r3",
+                        "fields": [
+                            {"name": "x", "type": "double", "field-id": 4}
+                        ]
+                    }], "default": null, "field-id": 3},
+                    {"name": "tags", "type": ["null", {
+                        "type": "array",
+                        "items": ["null", "string"],
+                        "element-id": 7
+                    }], "default": null, "field-id": 6},
+                    {"name": "counts", "type": ["null", {
+                        "type": "array",
+                        "logicalType": "map",
+                        "items": {
+                            "type": "record",
+                            "name": "k9_v10",
+                            "fields": [
+                                {"name": "key", "type": "int", "field-id": 9},
+                                {"name": "value", "type": "long", "field-id": 10}
+                            ]
+                        }
+                    }], "default": null, "field-id": 8}
+                ]
+            }"#;
+        let schema = AvroSchema::parse_str(schema_json).unwrap();
+
+        let mut records = vec![];
+        for i in 0..3 {
+            let point = if i == 1 {
+                Value::Union(0, Box::new(Value::Null))
+            } else {
+                Value::Union(
+                    1,
+                    Box::new(Value::Record(vec![(
+                        "x".to_string(),
+                        Value::Double(i as f64),
+                    )])),
+                )
+            };
+            let tags = Value::Union(
+                1,
+                Box::new(Value::Array(vec![
+                    Value::Union(1, Box::new(Value::String(format!("t{i}")))),
+                    Value::Union(0, Box::new(Value::Null)),
+                ])),
+            );
+            let counts = Value::Union(
+                1,
+                Box::new(Value::Array(
+                    (0..i)
+                        .map(|k| {
+                            Value::Record(vec![
+                                ("key".to_string(), Value::Int(k)),
+                                ("value".to_string(), Value::Long(k as i64 * 10)),
+                            ])
+                        })
+                        .collect(),
+                )),
+            );
+            records.push(Value::Record(vec![
+                (
+                    "payload".to_string(),
+                    Value::Union(1, Box::new(Value::String(format!("d{i}")))),
+                ),
+                ("id".to_string(), Value::Int(i)),
+                ("point".to_string(), point),
+                ("tags".to_string(), tags),
+                ("counts".to_string(), counts),
+            ]));
+        }
+        container(schema_json, &schema, records)
+    }
+
+    /// Encode an avro object container file by hand, since the writer of
+    /// apache avro drops field ids from the schema.
+    fn container(schema_json: &str, schema: &AvroSchema, records: Vec<Value>) -> Vec<u8> {
+        let long = |v: i64| to_avro_datum(&AvroSchema::Long, Value::Long(v)).unwrap();
+        let sync = [7u8; 16];
+
+        let mut content = b"Obj\x01".to_vec();
+        let metadata = Value::Map(
+            [
+                ("avro.schema", schema_json.as_bytes().to_vec()),
+                ("avro.codec", b"null".to_vec()),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), Value::Bytes(v)))
+            .collect(),
+        );
+        content.extend(
+            to_avro_datum(&AvroSchema::Map(Box::new(AvroSchema::Bytes)), metadata).unwrap(),
+        );
+        content.extend(sync);
+
+        let mut block = vec![];
+        let count = records.len();
+        for record in records {
+            block.extend(to_avro_datum(schema, record).unwrap());
+        }
+        content.extend(long(count as i64));
+        content.extend(long(block.len() as i64));
+        content.extend(block);
+        content.extend(sync);
+        content
+    }
+
+    #[test]
+    fn test_read_avro() -> Result<()> {
+        let schema = table_schema();
+        let batches =
+            AvroBatchReader::try_new(write_file(), &schema, None)?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+
+        // `missing` is skipped, columns are in the order of the table schema.
+        let names: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|v| v.name().clone())
+            .collect();
+        assert_eq!(names, vec!["id", "data", "point", "tags", "counts"]);
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &Int64Array::from(vec![0, 1, 2]) as &dyn Array
+        );
+        assert_eq!(
+            batch.column(1).as_ref(),
+            &StringArray::from(vec!["d0", "d1", "d2"]) as &dyn Array
+        );
+
+        let point = batch.column(2).as_struct();
+        assert!(point.is_null(1));
+        assert_eq!(
+            point
+                .column(0)
+                .as_primitive::<arrow::datatypes::Float64Type>()
+                .value(2),
+            2.0
+        );
+        assert_eq!(point.column(1).null_count(), 3);
+
+        let tags = batch.column(3).as_list::<i32>();
+        assert_eq!(
+            tags.value(2).as_ref(),
+            &StringArray::from(vec![Some("t2"), None]) as &dyn Array
+        );
+
+        let counts = batch.column(4).as_map();
+        assert_eq!(counts.value_length(2), 2);
+        let entries = counts.value(2);
+        assert_eq!(
+            entries.column(0).as_ref(),
+            &Int32Array::from(vec![0, 1]) as &dyn Array
+        );
+        assert_eq!(
+            entries.column(1).as_primitive::<Int64Type>().values(),
+            &[0, 10]
+        );
+
+        // Projected columns are decoded in the projected order.
+        let projection = ParquetProjection::try_new(&schema, &[5, 1])?;
+        let batches = AvroBatchReader::try_new(write_file(), &schema, Some(&projection))?
+            .collect::<Result<Vec<_>>>()?;
+        let names: Vec<_> = batches[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|v| v.name().clone())
+            .collect();
+        assert_eq!(names, vec!["point", "id"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_bytes() {
+        assert_eq!(to_i128(&[0x01, 0x00]), Some(256));
+        assert_eq!(to_i128(&[0xff, 0x00]), Some(-256));
+        assert_eq!(to_i128(&[]), Some(0));
+        assert_eq!(to_i128(&[0; 17]), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! data_file_reader module provides the reader to read data files of all
+//! formats into arrow record batches.
+
+use std::sync::Arc;
+
+use arrow::record_batch::RecordBatch;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use opendal::Operator;
+
+use super::avro::AvroBatchReader;
+use super::orc::OrcBatchReader;
+use super::parquet::{ParquetProjection, ParquetStreamBuilder};
+use crate::types::expression::DataFileEvaluator;
+use crate::types::{DataFileFormat, NameMapping, Schema};
+use crate::{Error, ErrorKind, Result};
+
+/// Stream of record batches produced by readers.
+pub type RecordBatchStream = BoxStream<'static, Result<RecordBatch>>;
+
+/// DataFileReader reads a data file by the decoder of its file format.
+///
+/// A table may contain data files of different formats, for example, after
+/// migrated from hive. The format recorded in each `DataFile` decides how
+/// it's decoded, instead of the table's `write.format.default`.
+///
+/// Parquet, orc and avro files are supported. Orc and avro files are read
+/// into memory as a whole before decoding, avro files are decoded by the
+/// schema given by [`DataFileReader::with_schema`].
+#[derive(Clone)]
+pub struct DataFileReader {
+    op: Operator,
+    row_group_concurrency: usize,
+    projection: Option<ParquetProjection>,
+    schema: Option<Schema>,
+    name_mapping: Option<Arc<NameMapping>>,
+    filter: Option<DataFileEvaluator>,
+    /// Start and length of the byte range to read.
+    range: Option<(u64, u64)>,
+}
+
+impl DataFileReader {
+    /// Create a new reader that reads files by given operator.
+    pub fn new(op: Operator) -> Self {
+        Self {
+            op,
+            row_group_concurrency: 1,
+            projection: None,
+            schema: None,
+            name_mapping: None,
+            filter: None,
+            range: None,
+        }
+    }
+
+    /// Only decode columns of given projection.
+    pub fn with_projection(mut self, projection: ParquetProjection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Decode files without embedded arrow types, like avro files, by the
+    /// table schema.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Match columns of parquet files written without field ids by given
+    /// name mapping.
+    pub fn with_name_mapping(mut self, name_mapping: Arc<NameMapping>) -> Self {
+        self.name_mapping = Some(name_mapping);
+        self
+    }
+
+    /// Skip row groups and pages of parquet files that can't contain rows
+    /// matching the filter, see [`ParquetStreamBuilder::with_filter`].
+    pub fn with_filter(mut self, filter: DataFileEvaluator) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Only read row groups of parquet files starting in the byte range of
+    /// `start` and `length`, files of other formats are always read
+    /// entirely.
+    pub fn with_range(mut self, start: u64, length: u64) -> Self {
+        self.range = Some((start, length));
+        self
+    }
+
+    /// Decode at most `concurrency` row groups of a parquet file
+    /// concurrently, so that a large file can use more than one core.
+    ///
+    /// Default to `1`, which decodes row groups one by one.
+    pub fn with_row_group_concurrency(mut self, concurrency: usize) -> Self {
+        self.row_group_concurrency = concurrency;
+        self
+    }
+
+    /// Read the file at `path` (relative to operator) in given format.
+    pub async fn read(&self, path: &str, file_format: DataFileFormat) -> Result<RecordBatchStream> {
+        match file_format {
+            DataFileFormat::Parquet => {
+                let r = self.op.reader(path).await?;
+                let mut builder = ParquetStreamBuilder::new(r).with_row_group_concurrency(
+                    self.op.clone(),
+                    path,
+                    self.row_group_concurrency,
+                );
+                if let Some(projection) = &self.projection {
+                    builder = builder.with_projection(projection.clone());
+                }
+                if let Some(name_mapping) = &self.name_mapping {
+                    builder = builder.with_name_mapping(name_mapping.clone());
+                }
+                if let Some(filter) = &self.filter {
+                    builder = builder.with_filter(filter.clone());
+                }
+                if let Some((start, length)) = self.range {
+                    builder = builder.with_range(start, length);
+                }
+                let stream = builder.build().await?;
+                Ok(stream.boxed())
+            }
+            DataFileFormat::Orc => {
+                let data = self.op.read(path).await?;
+                let reader = OrcBatchReader::try_new(data.into(), self.projection.as_ref())
+                    .map_err(|e| e.with_context("path", path))?;
+                Ok(futures::stream::iter(reader).boxed())
+            }
+            DataFileFormat::Avro => {
+                let schema = self.schema.as_ref().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        "Reading avro data files requires the table schema",
+                    )
+                    .with_context("path", path)
+                })?;
+                let content = self.op.read(path).await?;
+                let reader = AvroBatchReader::try_new(content, schema, self.projection.as_ref())?;
+                Ok(futures::stream::iter(reader).boxed())
+            }
+        }
+    }
+
+    /// Read files one by one into a single stream.
+    ///
+    /// Every file is given as `(reader, path, format)` so that files can be
+    /// located in different storages.
+    pub fn read_all(files: Vec<(DataFileReader, String, DataFileFormat)>) -> RecordBatchStream {
+        futures::stream::iter(files)
+            .then(
+                |(reader, path, file_format)| async move { reader.read(&path, file_format).await },
+            )
+            .try_flatten()
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
+    use apache_avro::types::Value as AvroValue;
+    use apache_avro::{Schema as AvroSchema, Writer as AvroWriter};
+    use arrow::array::{ArrayRef, Int64Array};
+    use opendal::services::Memory;
+    use parquet::arrow::AsyncArrowWriter;
+
+    use super::*;
+    use crate::io::orc::{OrcWriter, OrcWriterProperties};
+    use crate::types::{Any, Field, Primitive};
+
+    #[tokio::test]
+    async fn test_read_mixed_formats() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let col = Arc::new(Int64Array::from_iter_values(0..16)) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter_with_nullable([("col", col, true)]).unwrap();
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, to_write.schema(), 0, None)?;
+        w.write(&to_write).await?;
+        w.close().await?;
+        op.write("a.parquet", buf).await?;
+
+        let mut w = OrcWriter::try_new(
+            op.writer("b.orc").await?,
+            to_write.schema(),
+            OrcWriterProperties::default(),
+        )?;
+        w.write(&to_write).await?;
+        w.close(None).await?;
+
+        let avro_schema = AvroSchema::parse_str(
+            r#"{"type": "record", "name": "r", "fields": [{"name": "col", "type": ["null", "long"]}]}"#,
+        )?;
+        let mut w = AvroWriter::new(&avro_schema, vec![]);
+        for v in 0..16 {
+            w.append(AvroValue::Record(vec![(
+                "col".to_string(),
+                AvroValue::Union(1, Box::new(AvroValue::Long(v))),
+            )]))?;
+        }
+        op.write("c.avro", w.into_inner()?).await?;
+
+        let reader = DataFileReader::new(op);
+        let schema = Schema {
+            schema_id: 1,
+            identifier_field_ids: None,
+            fields: vec![Field::optional(1, "col", Any::Primitive(Primitive::Long))],
+        };
+        let batches: Vec<_> = DataFileReader::read_all(vec![
+            (
+                reader.clone(),
+                "a.parquet".to_string(),
+                DataFileFormat::Parquet,
+            ),
+            (reader.clone(), "b.orc".to_string(), DataFileFormat::Orc),
+            (
+                reader.clone().with_schema(schema),
+                "c.avro".to_string(),
+                DataFileFormat::Avro,
+            ),
+        ])
+        .try_collect()
+        .await?;
+        assert_eq!(batches.len(), 3);
+        for batch in batches {
+            assert_eq!(batch.columns(), to_write.columns());
+        }
+
+        let res: crate::Result<Vec<_>> = DataFileReader::read_all(vec![
+            (
+                reader.clone(),
+                "a.parquet".to_string(),
+                DataFileFormat::Parquet,
+            ),
+            (reader.clone(), "c.avro".to_string(), DataFileFormat::Avro),
+        ])
+        .try_collect()
+        .await;
+        // Avro files can't be decoded without the table schema.
+        assert_eq!(res.err().unwrap().kind(), ErrorKind::IcebergDataInvalid);
+
+        Ok(())
+    }
+}
@@ -3,35 +3,67 @@
 use std::collections::HashMap;
 
 use crate::{
-    types::{DataFile, StructValue},
-    Result,
+    types::{cast_timestamps, DataContentType, DataFile, DataFileFormat, Schema, StructValue},
+    Error, ErrorKind, Result,
 };
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use opendal::Operator;
-use parquet::file::properties::{WriterProperties, WriterVersion};
 use parquet::format::FileMetaData;
 
 use super::{
     location_generator::DataFileLocationGenerator,
-    parquet::{ParquetWriter, ParquetWriterBuilder},
+    orc::{OrcFileMetaData, OrcWriter},
+    parquet::{
+        metrics::{ColumnMetrics, MetricsCollector},
+        ParquetWriter, ParquetWriterBuilder,
+    },
+    sorted_merge::SortColumns,
+    writer_config::WriterConfig,
 };
 
+/// Writer of the current file in the configured format.
+enum FileWriter {
+    Parquet(ParquetWriter),
+    Orc(OrcWriter),
+}
+
+impl FileWriter {
+    async fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            FileWriter::Parquet(w) => w.write(batch).await,
+            FileWriter::Orc(w) => w.write(batch).await,
+        }
+    }
+
+    /// Size of written data plus data buffered in memory.
+    fn get_written_size(&self) -> u64 {
+        match self {
+            FileWriter::Parquet(w) => w.get_written_size(),
+            FileWriter::Orc(w) => w.get_written_size() + w.get_buffered_size(),
+        }
+    }
+}
+
 /// A writer capable of splitting incoming data into multiple files within one spec/partition based on the target file size.
 /// When complete, it will return a list of `DataFile`.
 pub struct DataFileWriter {
     operator: Operator,
-    table_location: String,
     location_generator: DataFileLocationGenerator,
     arrow_schema: SchemaRef,
-
-    rows_divisor: usize,
-    target_file_size_in_bytes: u64,
-
-    /// # TODO
-    ///
-    /// support to config `ParquetWriter` using `buffer_size` and writer properties.
-    current_writer: Option<ParquetWriter>,
+    config: WriterConfig,
+    partition: StructValue,
+    content: DataContentType,
+    /// Sort columns and id of the sort order of written files.
+    sort: Option<(SortColumns, i32)>,
+    /// Rows of the current file buffered to be sorted.
+    sort_buffer: Vec<RecordBatch>,
+    sort_buffer_bytes: usize,
+    /// Collects column metrics of the current file, only if the iceberg
+    /// schema of rows is known.
+    metrics: Option<Box<MetricsCollector>>,
+
+    current_writer: Option<FileWriter>,
     current_row_num: usize,
     /// `current_location` used to clean up the file when no row is written to it.
     current_location: String,
@@ -43,19 +75,28 @@ impl DataFileWriter {
     /// Create a new `DataFileWriter`.
     pub async fn try_new(
         operator: Operator,
-        table_location: String,
         location_generator: DataFileLocationGenerator,
         arrow_schema: SchemaRef,
-        rows_divisor: usize,
-        target_file_size_in_bytes: u64,
+        config: WriterConfig,
     ) -> Result<Self> {
+        if config.file_format() == DataFileFormat::Avro {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("Writing {} data files", config.file_format()),
+            ));
+        }
+
         let mut writer = Self {
             operator,
-            table_location,
             location_generator,
             arrow_schema,
-            rows_divisor,
-            target_file_size_in_bytes,
+            config,
+            partition: StructValue::default(),
+            content: DataContentType::Data,
+            sort: None,
+            sort_buffer: vec![],
+            sort_buffer_bytes: 0,
+            metrics: None,
             current_writer: None,
             current_row_num: 0,
             current_location: String::new(),
@@ -65,14 +106,55 @@ impl DataFileWriter {
         Ok(writer)
     }
 
+    /// Set partition values of written data files, all written rows must
+    /// belong to this partition.
+    pub fn with_partition(mut self, partition: StructValue) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    /// Set content type of written files, default to data. Delete writers
+    /// use it to write delete files.
+    pub fn with_content(mut self, content: DataContentType) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Set the iceberg schema of written rows, which must be converted to
+    /// the arrow schema of the writer.
+    ///
+    /// Column metrics of written files are keyed by field ids, so they are
+    /// only collected if the schema is set.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.metrics = Some(Box::new(MetricsCollector::new(schema)));
+        self
+    }
+
+    /// Sort rows of each written file by the sort order, which is recorded
+    /// as `sort_order_id` of the files.
+    ///
+    /// Rows are buffered in memory until their size reaches the target file
+    /// size, then sorted and written as a single file.
+    pub fn with_sort_order(mut self, sort_columns: SortColumns, order_id: i32) -> Self {
+        self.sort = Some((sort_columns, order_id));
+        self
+    }
+
     /// Write a record batch. The `DataFileWriter` will create a new file when the current row num is greater than `target_file_row_num`.
     pub async fn write(&mut self, batch: RecordBatch) -> Result<()> {
-        self.current_writer
-            .as_mut()
-            .expect("Should not be none here")
-            .write(&batch)
-            .await?;
-        self.current_row_num += batch.num_rows();
+        let batch = cast_timestamps(&batch, &self.arrow_schema)?;
+        if self.sort.is_some() {
+            self.sort_buffer_bytes += batch.get_array_memory_size();
+            self.sort_buffer.push(batch);
+            if self.sort_buffer_bytes as u64 >= self.config.target_file_size_in_bytes() {
+                self.flush_sort_buffer().await?;
+                self.close_current_writer().await?;
+                self.open_new_writer().await?;
+            }
+            return Ok(());
+        }
+
+        self.write_current(&batch).await?;
 
         if self.should_split() {
             self.close_current_writer().await?;
@@ -81,24 +163,93 @@ impl DataFileWriter {
         Ok(())
     }
 
+    /// Returns the location of the current file and the position of the next
+    /// written row in it.
+    ///
+    /// Files are only split after writes, so rows of the next batch are
+    /// written to this file from this position. Positions are unknown if
+    /// rows are sorted.
+    pub(crate) fn current_position(&self) -> (String, i64) {
+        debug_assert!(self.sort.is_none());
+        (
+            self.location_generator.location_of(&self.current_location),
+            self.current_row_num as i64,
+        )
+    }
+
+    /// Complete files written so far and return them, following rows are
+    /// written to new files.
+    pub async fn flush(&mut self) -> Result<Vec<DataFile>> {
+        self.flush_sort_buffer().await?;
+        self.close_current_writer().await?;
+        self.open_new_writer().await?;
+        Ok(std::mem::take(&mut self.result))
+    }
+
     /// Complte the write and return the list of `DataFile` as result.
     pub async fn close(mut self) -> Result<Vec<DataFile>> {
+        self.flush_sort_buffer().await?;
         self.close_current_writer().await?;
         Ok(self.result)
     }
 
+    /// Sort buffered rows and write them into the current file.
+    async fn flush_sort_buffer(&mut self) -> Result<()> {
+        let Some((sort_columns, _)) = &self.sort else {
+            return Ok(());
+        };
+        let batches = std::mem::take(&mut self.sort_buffer);
+        self.sort_buffer_bytes = 0;
+        if let Some(batch) = sort_columns.sort_batches(&batches)? {
+            self.write_current(&batch).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_current(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.current_writer
+            .as_mut()
+            .expect("Should not be none here")
+            .write(batch)
+            .await?;
+        self.current_row_num += batch.num_rows();
+        if let Some(metrics) = &mut self.metrics {
+            metrics.update(batch.columns());
+        }
+        Ok(())
+    }
+
     fn should_split(&self) -> bool {
-        self.current_row_num % self.rows_divisor == 0
+        self.current_row_num
+            .is_multiple_of(self.config.rows_divisor())
             && self.current_writer.as_ref().unwrap().get_written_size()
-                >= self.target_file_size_in_bytes
+                >= self.config.target_file_size_in_bytes()
     }
 
     async fn close_current_writer(&mut self) -> Result<()> {
         let current_writer = self.current_writer.take().expect("Should not be none here");
-        let (meta_data, written_size) = current_writer.close().await?;
+        let file = match current_writer {
+            FileWriter::Parquet(w) => {
+                let (meta_data, written_size) = w.close().await?;
+                let metrics = self
+                    .metrics
+                    .as_mut()
+                    .map(|v| v.finish(&meta_data, &self.config));
+                self.convert_meta_to_datafile(meta_data, written_size, metrics)
+            }
+            FileWriter::Orc(w) => {
+                let schema = self.metrics.as_ref().map(|v| v.schema());
+                let (meta_data, written_size) = w.close(schema).await?;
+                let metrics = self
+                    .metrics
+                    .as_mut()
+                    .map(|v| v.finish_orc(&meta_data, &self.config));
+                self.convert_orc_meta_to_datafile(meta_data, written_size, metrics)
+            }
+        };
 
         // Check if this file is empty
-        if meta_data.num_rows == 0 {
+        if file.record_count == 0 {
             self.operator
                 .delete(&self.current_location)
                 .await
@@ -106,7 +257,6 @@ impl DataFileWriter {
             return Ok(());
         }
 
-        let file = self.convert_meta_to_datafile(meta_data, written_size);
         self.result.push(file);
         Ok(())
     }
@@ -117,13 +267,18 @@ impl DataFileWriter {
 
         let location = self.location_generator.generate_name();
         let file_writer = self.operator.writer(&location).await?;
-        let current_writer = {
-            let props = WriterProperties::builder()
-                .set_writer_version(WriterVersion::PARQUET_2_0)
-                .build();
-            ParquetWriterBuilder::new(file_writer, self.arrow_schema.clone())
-                .with_properties(props)
-                .build()?
+        let current_writer = match self.config.file_format() {
+            DataFileFormat::Orc => FileWriter::Orc(OrcWriter::try_new(
+                file_writer,
+                self.arrow_schema.clone(),
+                self.config.orc_writer_properties(),
+            )?),
+            _ => FileWriter::Parquet(
+                ParquetWriterBuilder::new(file_writer, self.arrow_schema.clone())
+                    .with_properties(self.config.parquet_writer_properties())
+                    .with_row_group_size_bytes(self.config.parquet_row_group_size_bytes())
+                    .build()?,
+            ),
         };
         self.current_writer = Some(current_writer);
         self.current_row_num = 0;
@@ -131,95 +286,94 @@ impl DataFileWriter {
         Ok(())
     }
 
-    /// # TODO
-    ///
-    /// This function may be refactor when we support more file format.
-    fn convert_meta_to_datafile(&self, meta_data: FileMetaData, written_size: u64) -> DataFile {
+    fn convert_meta_to_datafile(
+        &self,
+        meta_data: FileMetaData,
+        written_size: u64,
+        metrics: Option<ColumnMetrics>,
+    ) -> DataFile {
         log::info!("{meta_data:?}");
-        let (column_sizes, value_counts, null_value_counts, distinct_counts) = {
-            // how to decide column id
-            let mut per_col_size: HashMap<i32, _> = HashMap::new();
-            let mut per_col_val_num: HashMap<i32, _> = HashMap::new();
-            let mut per_col_null_val_num: HashMap<i32, _> = HashMap::new();
-            let mut per_col_distinct_val_num: HashMap<i32, _> = HashMap::new();
-            meta_data.row_groups.iter().for_each(|group| {
-                group
-                    .columns
-                    .iter()
-                    .enumerate()
-                    .for_each(|(column_id, column_chunk)| {
-                        if let Some(column_chunk_metadata) = &column_chunk.meta_data {
-                            *per_col_size.entry(column_id as i32).or_insert(0) +=
-                                column_chunk_metadata.total_compressed_size;
-                            *per_col_val_num.entry(column_id as i32).or_insert(0) +=
-                                column_chunk_metadata.num_values;
-                            *per_col_null_val_num
-                                .entry(column_id as i32)
-                                .or_insert(0_i64) += column_chunk_metadata
-                                .statistics
-                                .as_ref()
-                                .map(|s| s.null_count)
-                                .unwrap_or(None)
-                                .unwrap_or(0);
-                            *per_col_distinct_val_num
-                                .entry(column_id as i32)
-                                .or_insert(0_i64) += column_chunk_metadata
-                                .statistics
-                                .as_ref()
-                                .map(|s| s.distinct_count)
-                                .unwrap_or(None)
-                                .unwrap_or(0);
-                        }
-                    })
-            });
-            (
-                per_col_size,
-                per_col_val_num,
-                per_col_null_val_num,
-                per_col_distinct_val_num,
-            )
-        };
+        let mut file = self.new_data_file(
+            DataFileFormat::Parquet,
+            meta_data.num_rows,
+            written_size,
+            metrics,
+        );
+        file.key_metadata = meta_data.footer_signing_key_metadata;
+        // Arrow parquet writer doesn't fill row group offsets, and
+        // `file_offset` of column chunks points to their ends, so row
+        // groups start at the first page of their first column chunks.
+        file.split_offsets = meta_data
+            .row_groups
+            .iter()
+            .filter_map(|group| {
+                let meta = group.columns.first()?.meta_data.as_ref()?;
+                Some(match meta.dictionary_page_offset {
+                    Some(offset) if offset > 0 => offset.min(meta.data_page_offset),
+                    _ => meta.data_page_offset,
+                })
+            })
+            .collect();
+        file
+    }
+
+    fn convert_orc_meta_to_datafile(
+        &self,
+        meta_data: OrcFileMetaData,
+        written_size: u64,
+        metrics: Option<ColumnMetrics>,
+    ) -> DataFile {
+        let mut file = self.new_data_file(
+            DataFileFormat::Orc,
+            meta_data.num_rows as i64,
+            written_size,
+            metrics,
+        );
+        file.split_offsets = meta_data.stripe_offsets;
+        file
+    }
+
+    fn new_data_file(
+        &self,
+        file_format: DataFileFormat,
+        record_count: i64,
+        written_size: u64,
+        metrics: Option<ColumnMetrics>,
+    ) -> DataFile {
+        let metrics = metrics.unwrap_or_default();
         DataFile {
-            content: crate::types::DataContentType::Data,
-            file_path: format!("{}/{}", &self.table_location, &self.current_location),
-            file_format: crate::types::DataFileFormat::Parquet,
-            // /// # NOTE
-            // ///
-            // /// DataFileWriter only response to write data. Partition should place by more high level writer.
-            partition: StructValue::default(),
-            record_count: meta_data.num_rows,
-            column_sizes: Some(column_sizes),
-            value_counts: Some(value_counts),
-            null_value_counts: Some(null_value_counts),
-            distinct_counts: Some(distinct_counts),
-            key_metadata: meta_data.footer_signing_key_metadata,
+            content: self.content,
+            file_path: self.location_generator.location_of(&self.current_location),
+            file_format,
+            partition: self.partition.clone(),
+            record_count,
+            column_sizes: non_empty(metrics.column_sizes),
+            value_counts: non_empty(metrics.value_counts),
+            null_value_counts: non_empty(metrics.null_value_counts),
+            // Distinct counts of row groups or stripes can't be merged.
+            distinct_counts: None,
+            key_metadata: None,
             file_size_in_bytes: written_size as i64,
-            /// # TODO
-            ///
-            /// Following fields unsupported now:
-            /// - `file_size_in_bytes` can't get from `FileMetaData` now.
-            /// - `file_offset` in `FileMetaData` always be None now.
-            /// - `nan_value_counts` can't get from `FileMetaData` now.
-            // Currently arrow parquet writer doesn't fill row group offsets, we can use first column chunk offset for it.
-            split_offsets: meta_data
-                .row_groups
-                .iter()
-                .filter_map(|group| group.columns.get(0).map(|c| c.file_offset))
-                .collect(),
-            nan_value_counts: None,
-            lower_bounds: None,
-            upper_bounds: None,
+            split_offsets: vec![],
+            nan_value_counts: non_empty(metrics.nan_value_counts),
+            lower_bounds: non_empty(metrics.lower_bounds),
+            upper_bounds: non_empty(metrics.upper_bounds),
             equality_ids: vec![],
-            sort_order_id: None,
+            sort_order_id: self.sort.as_ref().map(|(_, order_id)| *order_id),
         }
     }
 }
 
+fn non_empty<V>(metrics: HashMap<i32, V>) -> Option<HashMap<i32, V>> {
+    (!metrics.is_empty()).then_some(metrics)
+}
+
 #[cfg(test)]
 mod test {
-    use std::{env, fs, sync::Arc};
+    use std::{collections::HashMap, env, fs, sync::Arc};
 
-    use arrow::array::{ArrayRef, Int64Array};
+    use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray, StructArray};
+    use arrow::datatypes::{DataType, Schema as ArrowSchema};
     use arrow::record_batch::RecordBatch;
     use bytes::Bytes;
     use opendal::{services::Memory, Operator};
@@ -228,8 +382,15 @@ mod test {
     use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 
     use crate::{
-        io::{data_file_writer, location_generator::DataFileLocationGenerator},
-        types::parse_table_metadata,
+        io::{
+            data_file_writer, location_generator::DataFileLocationGenerator,
+            writer_config::WriterConfig,
+        },
+        table_properties::{
+            DEFAULT_FILE_FORMAT, DEFAULT_WRITE_METRICS_MODE, METRICS_MODE_COLUMN_CONF_PREFIX,
+            WRITE_TARGET_FILE_SIZE_BYTES,
+        },
+        types::{parse_table_metadata, Any, DataFileFormat, Field, Primitive, Schema, Struct},
     };
 
     #[tokio::test]
@@ -260,11 +421,12 @@ mod test {
 
         let mut writer = data_file_writer::DataFileWriter::try_new(
             op.clone(),
-            "/tmp/table".to_string(),
             location_generator,
             to_write.schema(),
-            1024,
-            1024 * 1024,
+            WriterConfig::from_properties(&HashMap::from([(
+                WRITE_TARGET_FILE_SIZE_BYTES.to_string(),
+                (1024 * 1024).to_string(),
+            )]))?,
         )
         .await?;
 
@@ -291,4 +453,129 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_data_file_metrics() -> Result<()> {
+        check_data_file_metrics("parquet", DataFileFormat::Parquet).await
+    }
+
+    #[tokio::test]
+    async fn test_orc_data_file_metrics() -> Result<()> {
+        check_data_file_metrics("orc", DataFileFormat::Orc).await
+    }
+
+    async fn check_data_file_metrics(format: &str, file_format: DataFileFormat) -> Result<()> {
+        let mut builder = Memory::default();
+        builder.root("/tmp/table");
+        let op = Operator::new(builder)?.finish();
+        let location_generator = {
+            let path = format!(
+                "{}/../testdata/simple_table/metadata/v1.metadata.json",
+                env!("CARGO_MANIFEST_DIR")
+            );
+            let mut metadata = parse_table_metadata(&fs::read(path)?)?;
+            metadata.location = "/tmp/table".to_string();
+            DataFileLocationGenerator::try_new(&metadata, 0, 0, None)?.with_file_format(file_format)
+        };
+
+        let field = |id, name: &str, field_type| Field {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        };
+        let schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "id", Any::Primitive(Primitive::Long)),
+                field(2, "data", Any::Primitive(Primitive::String)),
+                field(
+                    3,
+                    "s",
+                    Any::Struct(Arc::new(Struct::new(vec![field(
+                        4,
+                        "v",
+                        Any::Primitive(Primitive::Double),
+                    )]))),
+                ),
+            ],
+        };
+        let arrow_schema: ArrowSchema = schema.clone().try_into()?;
+        let arrow_schema = Arc::new(arrow_schema);
+        let DataType::Struct(struct_fields) = arrow_schema.field(2).data_type() else {
+            unreachable!()
+        };
+        let batch = RecordBatch::try_new(
+            arrow_schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(3), None, Some(1)])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["abc", "b", "abd"])) as ArrayRef,
+                Arc::new(StructArray::new(
+                    struct_fields.clone(),
+                    vec![Arc::new(Float64Array::from(vec![1.0, f64::NAN, -2.0])) as ArrayRef],
+                    None,
+                )) as ArrayRef,
+            ],
+        )?;
+
+        let mut writer = data_file_writer::DataFileWriter::try_new(
+            op.clone(),
+            location_generator,
+            arrow_schema,
+            WriterConfig::from_properties(&HashMap::from([
+                (
+                    DEFAULT_WRITE_METRICS_MODE.to_string(),
+                    "truncate(2)".to_string(),
+                ),
+                (
+                    format!("{METRICS_MODE_COLUMN_CONF_PREFIX}s.v"),
+                    "counts".to_string(),
+                ),
+                (DEFAULT_FILE_FORMAT.to_string(), format.to_string()),
+            ]))?,
+        )
+        .await?
+        .with_schema(schema);
+        writer.write(batch).await?;
+        let data_files = writer.close().await?;
+        assert_eq!(data_files.len(), 1);
+        let data_file = &data_files[0];
+        assert_eq!(data_file.file_format, file_format);
+        assert!(data_file.file_path.ends_with(&format!(".{format}")));
+
+        let ids = |v: &HashMap<i32, _>| {
+            let mut ids = v.keys().copied().collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(data_file.column_sizes.as_ref().unwrap()), vec![1, 2, 4]);
+        assert_eq!(
+            data_file.value_counts,
+            Some(HashMap::from([(1, 3), (2, 3), (4, 3)]))
+        );
+        assert_eq!(
+            data_file.null_value_counts,
+            Some(HashMap::from([(1, 1), (2, 0), (4, 0)]))
+        );
+        assert_eq!(data_file.nan_value_counts, Some(HashMap::from([(4, 1)])));
+        assert_eq!(
+            data_file.lower_bounds,
+            Some(HashMap::from([
+                (1, 1_i64.to_le_bytes().to_vec()),
+                (2, b"ab".to_vec())
+            ]))
+        );
+        assert_eq!(
+            data_file.upper_bounds,
+            Some(HashMap::from([
+                (1, 3_i64.to_le_bytes().to_vec()),
+                (2, b"b".to_vec())
+            ]))
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,333 @@
+//! delete_filter module filters rows deleted by position and equality
+//! delete files out of data files while reading.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arrow::array::{new_null_array, Array, ArrayRef, BooleanArray, Int64Array, StringArray};
+use arrow::compute::{cast, filter_record_batch};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
+use futures::{StreamExt, TryStreamExt};
+
+use super::data_file_reader::RecordBatchStream;
+use super::parquet::ParquetProjection;
+use crate::scan::TableScanContext;
+use crate::types::{DataContentType, DataFile, Schema};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// Delete keys of an equality delete file.
+pub(crate) struct EqualityDeleteSet {
+    /// Top level names of equality fields in the table schema.
+    names: Vec<String>,
+    field_ids: Vec<i32>,
+    data_types: Vec<DataType>,
+    /// Keys encoded by arrow row format.
+    keys: HashSet<Vec<u8>>,
+}
+
+/// Delete files loaded into memory, shared by delete filters of all data
+/// files in a scan.
+#[derive(Default)]
+pub(crate) struct DeleteIndex {
+    /// Deleted positions by data file paths of each position delete file.
+    positions: HashMap<String, HashMap<String, Vec<i64>>>,
+    /// Delete keys of each equality delete file.
+    equality: HashMap<String, Arc<EqualityDeleteSet>>,
+}
+
+impl DeleteIndex {
+    /// Load given delete files of the table by the schema of `context`,
+    /// files of the same path are loaded once.
+    pub(crate) async fn load<'a>(
+        table: &Table,
+        context: &TableScanContext,
+        delete_files: impl IntoIterator<Item = &'a DataFile>,
+    ) -> Result<Self> {
+        let schema = context.schema();
+        let mut index = Self::default();
+        for delete_file in delete_files {
+            let path = &delete_file.file_path;
+            if index.positions.contains_key(path) || index.equality.contains_key(path) {
+                continue;
+            }
+            let batches: Vec<RecordBatch> = table
+                .read_data_files_with_projection(
+                    context,
+                    std::slice::from_ref(delete_file),
+                    None,
+                    None,
+                )?
+                .try_collect()
+                .await?;
+            match delete_file.content {
+                DataContentType::PostionDeletes => {
+                    let positions = load_position_deletes(&batches)
+                        .map_err(|e| e.with_context("delete_file", path))?;
+                    index.positions.insert(path.clone(), positions);
+                }
+                DataContentType::EqualityDeletes => {
+                    let set = load_equality_deletes(schema, &delete_file.equality_ids, &batches)
+                        .map_err(|e| e.with_context("delete_file", path))?;
+                    index.equality.insert(path.clone(), Arc::new(set));
+                }
+                DataContentType::Data => {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("{path} is not a delete file"),
+                    ))
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Returns the filter of a data file by its delete files, which must be
+    /// loaded.
+    pub(crate) fn filter<'a>(
+        &self,
+        data_file: &DataFile,
+        delete_files: impl IntoIterator<Item = &'a DataFile>,
+    ) -> DeleteFilter {
+        let mut filter = DeleteFilter::default();
+        for delete_file in delete_files {
+            if let Some(positions) = self
+                .positions
+                .get(&delete_file.file_path)
+                .and_then(|v| v.get(&data_file.file_path))
+            {
+                filter.positions.extend(positions);
+            }
+            if let Some(set) = self.equality.get(&delete_file.file_path) {
+                filter.equality.push(set.clone());
+            }
+        }
+        filter
+    }
+}
+
+/// Returns deleted positions by data file paths of position delete files
+/// read into `batches`.
+pub(crate) fn load_position_deletes(batches: &[RecordBatch]) -> Result<HashMap<String, Vec<i64>>> {
+    let mut positions: HashMap<String, Vec<i64>> = HashMap::new();
+    for batch in batches {
+        let column = |name: &str| {
+            batch.column_by_name(name).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("column {name} of position delete file is not found"),
+                )
+            })
+        };
+        let file_paths = column("file_path")?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "file_path of position delete file must be string",
+                )
+            })?;
+        let pos = column("pos")?
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "pos of position delete file must be long",
+                )
+            })?;
+        for (file_path, pos) in file_paths.iter().zip(pos.iter()) {
+            if let (Some(file_path), Some(pos)) = (file_path, pos) {
+                match positions.get_mut(file_path) {
+                    Some(v) => v.push(pos),
+                    None => {
+                        positions.insert(file_path.to_string(), vec![pos]);
+                    }
+                }
+            }
+        }
+    }
+    Ok(positions)
+}
+
+fn load_equality_deletes(
+    schema: &Schema,
+    equality_ids: &[i32],
+    batches: &[RecordBatch],
+) -> Result<EqualityDeleteSet> {
+    let names = equality_ids
+        .iter()
+        .map(|id| {
+            schema
+                .fields
+                .iter()
+                .find(|v| v.id == *id)
+                .map(|v| v.name.clone())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergFeatureUnsupported,
+                        format!("Equality field {id} is not a top level column"),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut set = EqualityDeleteSet {
+        names,
+        field_ids: equality_ids.to_vec(),
+        data_types: vec![],
+        keys: HashSet::new(),
+    };
+    for batch in batches {
+        let columns = set
+            .names
+            .iter()
+            .map(|name| {
+                batch.column_by_name(name).cloned().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("column {name} of equality delete file is not found"),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if set.data_types.is_empty() {
+            set.data_types = columns.iter().map(|v| v.data_type().clone()).collect();
+        }
+        let rows = set.converter()?.convert_columns(&set.cast(columns)?)?;
+        set.keys.extend(rows.iter().map(|v| v.as_ref().to_vec()));
+    }
+    Ok(set)
+}
+
+impl EqualityDeleteSet {
+    fn converter(&self) -> Result<RowConverter> {
+        Ok(RowConverter::new(
+            self.data_types
+                .iter()
+                .map(|v| SortField::new(v.clone()))
+                .collect(),
+        )?)
+    }
+
+    /// Cast columns into types of delete keys.
+    fn cast(&self, columns: Vec<ArrayRef>) -> Result<Vec<ArrayRef>> {
+        columns
+            .into_iter()
+            .zip(&self.data_types)
+            .map(|(column, data_type)| {
+                if column.data_type() == data_type {
+                    Ok(column)
+                } else {
+                    Ok(cast(&column, data_type)?)
+                }
+            })
+            .collect()
+    }
+}
+
+/// DeleteFilter removes rows deleted by delete files of a data file.
+#[derive(Default)]
+pub(crate) struct DeleteFilter {
+    /// Deleted row positions in the data file.
+    positions: HashSet<i64>,
+    equality: Vec<Arc<EqualityDeleteSet>>,
+}
+
+impl DeleteFilter {
+    /// Returns the projection to read the data file with, which also
+    /// contains equality fields, and names of columns added to it that
+    /// should be removed after filtering.
+    pub(crate) fn projection(
+        &self,
+        schema: &Schema,
+        projection: Option<&ParquetProjection>,
+    ) -> Result<(Option<ParquetProjection>, Vec<String>)> {
+        let Some(projection) = projection else {
+            return Ok((None, vec![]));
+        };
+        let mut field_ids = projection.field_ids();
+        let mut extra_columns = vec![];
+        for set in &self.equality {
+            for (id, name) in set.field_ids.iter().zip(&set.names) {
+                if !field_ids.contains(id) {
+                    field_ids.push(*id);
+                    extra_columns.push(name.clone());
+                }
+            }
+        }
+        if extra_columns.is_empty() {
+            return Ok((Some(projection.clone()), vec![]));
+        }
+        Ok((
+            Some(ParquetProjection::try_new(schema, &field_ids)?),
+            extra_columns,
+        ))
+    }
+
+    /// Returns whether rows are deleted by positions, which requires all
+    /// rows of the data file to be read in order.
+    pub(crate) fn has_position_deletes(&self) -> bool {
+        !self.positions.is_empty()
+    }
+
+    /// Filter deleted rows out of batches read from the data file in order,
+    /// then remove `extra_columns`.
+    pub(crate) fn apply(
+        self,
+        stream: RecordBatchStream,
+        extra_columns: Vec<String>,
+    ) -> RecordBatchStream {
+        let mut offset = 0;
+        stream
+            .map(move |batch| {
+                let batch = batch?;
+                let start = offset;
+                offset += batch.num_rows() as i64;
+                let batch = self.filter(&batch, start)?;
+                if extra_columns.is_empty() {
+                    return Ok(batch);
+                }
+                let indices: Vec<usize> = batch
+                    .schema()
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, v)| !extra_columns.contains(v.name()))
+                    .map(|(i, _)| i)
+                    .collect();
+                Ok(batch.project(&indices)?)
+            })
+            .boxed()
+    }
+
+    /// Filter deleted rows out of a batch starting at row `offset` of the
+    /// data file.
+    fn filter(&self, batch: &RecordBatch, offset: i64) -> Result<RecordBatch> {
+        if batch.num_rows() == 0 || (self.positions.is_empty() && self.equality.is_empty()) {
+            return Ok(batch.clone());
+        }
+        let mut keep: Vec<bool> = (0..batch.num_rows() as i64)
+            .map(|i| !self.positions.contains(&(offset + i)))
+            .collect();
+        for set in self.equality.iter().filter(|v| !v.keys.is_empty()) {
+            // Columns added after the data file was written are null.
+            let columns = set
+                .names
+                .iter()
+                .zip(&set.data_types)
+                .map(|(name, data_type)| match batch.column_by_name(name) {
+                    Some(column) => column.clone(),
+                    None => new_null_array(data_type, batch.num_rows()),
+                })
+                .collect();
+            let rows = set.converter()?.convert_columns(&set.cast(columns)?)?;
+            for (keep, row) in keep.iter_mut().zip(rows.iter()) {
+                *keep = *keep && !set.keys.contains(row.as_ref());
+            }
+        }
+        Ok(filter_record_batch(batch, &BooleanArray::from(keep))?)
+    }
+}
@@ -0,0 +1,446 @@
+//! delta_writer module provides a writer of row changes keyed by equality
+//! fields, like changelogs of upserts and deletes from a CDC source.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::datatypes::{DataType, Schema as ArrowSchema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{OwnedRow, RowConverter, Rows, SortField};
+use opendal::Operator;
+
+use super::data_file_writer::DataFileWriter;
+use super::equality_delete_writer::{equality_fields, EqualityDeleteWriter};
+use super::location_generator::DataFileLocationGenerator;
+use super::position_delete_writer::PositionDeleteWriter;
+use super::task_writer::{partition_path, PartitionSplitter, WriteResult};
+use super::writer_config::WriterConfig;
+use crate::types::{DataFile, StructValue, TableMetadata};
+use crate::{Error, ErrorKind, Result};
+
+/// Kind of a row change written by [`DeltaWriter::write`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOp {
+    /// Insert the row.
+    Insert,
+    /// Replace rows of the same key with the row, or insert it if there are
+    /// none.
+    Upsert,
+    /// Delete rows of the same key.
+    Delete,
+}
+
+/// `DeltaWriter` writes row changes keyed by equality fields, and returns
+/// data files and delete files to be committed together by
+/// [`crate::transaction::Transaction::append_file`].
+///
+/// - Inserted rows are written into data files.
+/// - Deleted keys are written into equality delete files, which delete rows
+///   committed before.
+/// - Rows inserted by this writer are deleted by positions instead, since
+///   equality deletes don't apply to data files of the same commit.
+///
+/// Deleted rows must contain columns of equality fields, and source columns
+/// of partition fields if the table is partitioned. Rows are never sorted,
+/// [`WriterConfig::sort_enabled`] is ignored.
+pub struct DeltaWriter {
+    table_metadata: TableMetadata,
+    operator: Operator,
+    task_id: usize,
+    config: WriterConfig,
+    arrow_schema: SchemaRef,
+
+    equality_ids: Vec<i32>,
+    /// Names of equality fields, key columns are picked by them.
+    key_names: Vec<String>,
+    /// Converts key columns into comparable keys.
+    key_converter: RowConverter,
+
+    /// Splits rows by partitions, none if the table is unpartitioned.
+    splitter: Option<PartitionSplitter>,
+    writers: HashMap<Option<OwnedRow>, PartitionDeltaWriter>,
+}
+
+impl DeltaWriter {
+    /// Create a new `DeltaWriter` of rows keyed by fields of `equality_ids`
+    /// in the current schema, default to identifier fields of the schema
+    /// if empty.
+    ///
+    /// `operator` is used to write files, it must be rooted at the table
+    /// location or the data location (`write.data.path`) of the table.
+    pub fn try_new(
+        table_metadata: TableMetadata,
+        operator: Operator,
+        task_id: usize,
+        equality_ids: Vec<i32>,
+        config: WriterConfig,
+    ) -> Result<Self> {
+        let schema = table_metadata.current_schema()?;
+        let equality_ids = match equality_ids.is_empty() {
+            true => schema.identifier_field_ids.clone().unwrap_or_default(),
+            false => equality_ids,
+        };
+        let arrow_schema: ArrowSchema = schema.clone().try_into()?;
+        let key_fields = equality_fields(schema, &equality_ids)?;
+        let key_names = key_fields.iter().map(|v| v.name.clone()).collect();
+        let sort_fields = key_fields
+            .into_iter()
+            .map(|v| Ok(SortField::new(DataType::try_from(v.field_type)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let partition_spec = table_metadata.current_partition_spec()?;
+        let splitter = if partition_spec.is_unpartitioned() {
+            None
+        } else {
+            Some(PartitionSplitter::try_new(schema, partition_spec)?)
+        };
+
+        Ok(Self {
+            table_metadata,
+            operator,
+            task_id,
+            config,
+            arrow_schema: Arc::new(arrow_schema),
+            equality_ids,
+            key_names,
+            key_converter: RowConverter::new(sort_fields)?,
+            splitter,
+            writers: HashMap::new(),
+        })
+    }
+
+    /// Insert rows.
+    ///
+    /// Rows inserted by this writer before with the same keys are deleted,
+    /// so each key has at most one row written by this writer.
+    pub async fn insert(&mut self, batch: &RecordBatch) -> Result<()> {
+        for (key, partition, batch) in self.split(batch)? {
+            let keys = self.keys(&batch)?;
+            self.writer(key, partition)
+                .await?
+                .insert(batch, keys)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Delete rows of the same keys as rows of `batch`.
+    pub async fn delete(&mut self, batch: &RecordBatch) -> Result<()> {
+        for (key, partition, batch) in self.split(batch)? {
+            let keys = self.keys(&batch)?;
+            self.writer(key, partition)
+                .await?
+                .delete(&batch, &keys)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Replace rows of the same keys with rows of `batch`.
+    pub async fn upsert(&mut self, batch: &RecordBatch) -> Result<()> {
+        for (key, partition, batch) in self.split(batch)? {
+            let keys = self.keys(&batch)?;
+            let writer = self.writer(key, partition).await?;
+            writer.delete(&batch, &keys).await?;
+            writer.insert(batch, keys).await?;
+        }
+        Ok(())
+    }
+
+    /// Write changes of rows in order, `ops` is the change of each row of
+    /// `batch`.
+    pub async fn write(&mut self, batch: &RecordBatch, ops: &[ChangeOp]) -> Result<()> {
+        if ops.len() != batch.num_rows() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Got {} change ops of {} rows", ops.len(), batch.num_rows()),
+            ));
+        }
+
+        // Write runs of rows of the same op at a time.
+        let mut start = 0;
+        while start < ops.len() {
+            let op = ops[start];
+            let end = ops[start..]
+                .iter()
+                .position(|v| *v != op)
+                .map_or(ops.len(), |v| start + v);
+            let rows = batch.slice(start, end - start);
+            match op {
+                ChangeOp::Insert => self.insert(&rows).await?,
+                ChangeOp::Upsert => self.upsert(&rows).await?,
+                ChangeOp::Delete => self.delete(&rows).await?,
+            }
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Complete files written so far of all partitions and return them,
+    /// see [`super::task_writer::TaskWriter::flush`].
+    ///
+    /// Rows inserted before are deleted by equality deletes afterwards,
+    /// since they might be committed separately.
+    pub async fn flush(&mut self) -> Result<WriteResult> {
+        let mut files = vec![];
+        for (_, writer) in self.writers.drain() {
+            files.extend(writer.close().await?);
+        }
+        Ok(WriteResult::from(files))
+    }
+
+    /// Complete the write and return data files and delete files of all
+    /// partitions.
+    pub async fn close(self) -> Result<Vec<DataFile>> {
+        let mut files = vec![];
+        for (_, writer) in self.writers {
+            files.extend(writer.close().await?);
+        }
+        Ok(files)
+    }
+
+    fn split(
+        &mut self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<(Option<OwnedRow>, StructValue, RecordBatch)>> {
+        match &mut self.splitter {
+            Some(splitter) => Ok(splitter
+                .split(batch)?
+                .into_iter()
+                .map(|(key, partition, batch)| (Some(key), partition, batch))
+                .collect()),
+            None => Ok(vec![(None, StructValue::default(), batch.clone())]),
+        }
+    }
+
+    /// Returns comparable keys of rows of `batch`.
+    fn keys(&mut self, batch: &RecordBatch) -> Result<Rows> {
+        let columns = self
+            .key_names
+            .iter()
+            .map(|name| {
+                batch.column_by_name(name).cloned().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Equality field {name} is not found in batch"),
+                    )
+                })
+            })
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        Ok(self.key_converter.convert_columns(&columns)?)
+    }
+
+    async fn writer(
+        &mut self,
+        key: Option<OwnedRow>,
+        partition: StructValue,
+    ) -> Result<&mut PartitionDeltaWriter> {
+        if !self.writers.contains_key(&key) {
+            let writer = self.new_writer(partition).await?;
+            self.writers.insert(key.clone(), writer);
+        }
+        Ok(self.writers.get_mut(&key).expect("writer must be created"))
+    }
+
+    async fn new_writer(&self, partition: StructValue) -> Result<PartitionDeltaWriter> {
+        // Each writer generates names of a unique operation id, so files
+        // don't collide.
+        let location_generator = || {
+            DataFileLocationGenerator::try_new(&self.table_metadata, 0, self.task_id, None).map(
+                |v| {
+                    v.with_file_format(self.config.file_format())
+                        .with_location_provider(self.config.location_provider())
+                        .with_partition_path(partition_path(&partition))
+                },
+            )
+        };
+        let schema = self.table_metadata.current_schema()?;
+
+        let data_writer = DataFileWriter::try_new(
+            self.operator.clone(),
+            location_generator()?,
+            self.arrow_schema.clone(),
+            self.config.clone(),
+        )
+        .await?
+        .with_partition(partition.clone())
+        .with_schema(schema.clone());
+        let equality_delete_writer = EqualityDeleteWriter::try_new(
+            self.operator.clone(),
+            location_generator()?,
+            schema,
+            self.equality_ids.clone(),
+            self.config.clone(),
+        )
+        .await?
+        .with_partition(partition.clone());
+        let position_delete_writer = PositionDeleteWriter::try_new(
+            self.operator.clone(),
+            location_generator()?,
+            None,
+            self.config.clone(),
+        )
+        .await?
+        .with_partition(partition);
+
+        Ok(PartitionDeltaWriter {
+            data_writer,
+            equality_delete_writer,
+            position_delete_writer,
+            inserted: HashMap::new(),
+        })
+    }
+}
+
+/// Writes changes of rows in one partition.
+struct PartitionDeltaWriter {
+    data_writer: DataFileWriter,
+    equality_delete_writer: EqualityDeleteWriter,
+    position_delete_writer: PositionDeleteWriter,
+    /// File path and position of rows inserted by this writer, by keys.
+    inserted: HashMap<OwnedRow, (Arc<str>, i64)>,
+}
+
+impl PartitionDeltaWriter {
+    async fn insert(&mut self, batch: RecordBatch, keys: Rows) -> Result<()> {
+        let (file_path, start) = self.data_writer.current_position();
+        let file_path: Arc<str> = file_path.into();
+        self.data_writer.write(batch).await?;
+
+        let mut replaced = vec![];
+        for (idx, key) in keys.iter().enumerate() {
+            let position = (file_path.clone(), start + idx as i64);
+            if let Some(previous) = self.inserted.insert(key.owned(), position) {
+                replaced.push(previous);
+            }
+        }
+        self.delete_positions(replaced)
+    }
+
+    async fn delete(&mut self, batch: &RecordBatch, keys: &Rows) -> Result<()> {
+        let deleted = keys
+            .iter()
+            .filter_map(|key| self.inserted.remove(&key.owned()))
+            .collect();
+        self.delete_positions(deleted)?;
+        // Rows of the keys may be committed before too.
+        self.equality_delete_writer.write(batch).await
+    }
+
+    fn delete_positions(&mut self, mut positions: Vec<(Arc<str>, i64)>) -> Result<()> {
+        positions.sort();
+        for group in positions.chunk_by(|a, b| a.0 == b.0) {
+            self.position_delete_writer
+                .delete(&group[0].0, group.iter().map(|v| v.1))?;
+        }
+        Ok(())
+    }
+
+    async fn close(self) -> Result<Vec<DataFile>> {
+        let mut files = self.data_writer.close().await?;
+        files.extend(self.equality_delete_writer.close().await?);
+        files.extend(self.position_delete_writer.close().await?);
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int64Array, StringArray};
+    use futures::TryStreamExt;
+    use opendal::services::Memory;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::types::DataContentType;
+    use crate::Table;
+
+    #[tokio::test]
+    async fn test_delta_writer() -> Result<()> {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let rows = |ids: Vec<i64>, data: Vec<&str>| {
+            RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])
+        };
+
+        assert!(table.delta_writer(vec![]).is_err());
+        // Equality fields default to identifier fields.
+        let mut metadata = table.current_table_metadata().clone();
+        metadata.schemas[0].identifier_field_ids = Some(vec![1]);
+        let writer = DeltaWriter::try_new(
+            metadata,
+            Operator::new(Memory::default())?.finish(),
+            0,
+            vec![],
+            WriterConfig::from_properties(&HashMap::new())?,
+        )?;
+        assert_eq!(writer.equality_ids, vec![1]);
+        let mut writer = table.delta_writer(vec![1])?;
+        // Rows 1, 2 and 3 are committed before.
+        writer.upsert(&rows(vec![2], vec!["b2"])?).await?;
+        writer.insert(&rows(vec![4, 5], vec!["d", "e"])?).await?;
+        writer
+            .write(
+                &rows(vec![4, 5, 1, 6], vec!["d", "e2", "a", "f"])?,
+                &[
+                    ChangeOp::Delete,
+                    ChangeOp::Upsert,
+                    ChangeOp::Delete,
+                    ChangeOp::Insert,
+                ],
+            )
+            .await?;
+        assert!(writer.write(&rows(vec![6], vec!["f"])?, &[]).await.is_err());
+        writer
+            .delete(&RecordBatch::try_from_iter([(
+                "id",
+                Arc::new(Int64Array::from(vec![6])) as ArrayRef,
+            )])?)
+            .await?;
+        let files = writer.close().await?;
+
+        let count = |content: DataContentType| {
+            files
+                .iter()
+                .filter(|v| v.content == content)
+                .map(|v| v.record_count)
+                .sum::<i64>()
+        };
+        assert_eq!(count(DataContentType::Data), 5);
+        assert_eq!(count(DataContentType::EqualityDeletes), 5);
+        // Rows 4, 5 and 6 inserted by the writer are deleted by positions.
+        assert_eq!(count(DataContentType::PostionDeletes), 3);
+
+        let mut append = table.new_append();
+        append.append_file(files);
+        append.commit().await?;
+
+        let batches: Vec<RecordBatch> =
+            table.scan().build()?.execute().await?.try_collect().await?;
+        let mut result = vec![];
+        for batch in &batches {
+            let ids = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            let data = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            for i in 0..batch.num_rows() {
+                result.push((ids.value(i), data.value(i).to_string()));
+            }
+        }
+        result.sort();
+        let expected: Vec<(i64, String)> =
+            vec![(2, "b2".into()), (3, "c".into()), (5, "e2".into())];
+        assert_eq!(result, expected);
+        Ok(())
+    }
+}
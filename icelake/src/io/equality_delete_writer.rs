@@ -0,0 +1,207 @@
+//! equality_delete_writer is used to write equality delete files, which
+//! delete rows whose values of equality fields equal to a delete key.
+
+use std::sync::Arc;
+
+use arrow::datatypes::{Field as ArrowField, Schema as ArrowSchema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use opendal::Operator;
+
+use super::data_file_writer::DataFileWriter;
+use super::location_generator::DataFileLocationGenerator;
+use super::writer_config::WriterConfig;
+use crate::types::{Any, DataContentType, DataFile, Field, Primitive, Schema, StructValue};
+use crate::{Error, ErrorKind, Result};
+
+/// A writer of equality delete files, which stores delete keys made of
+/// values of the equality fields.
+///
+/// Equality fields must be top level primitive columns of the table schema
+/// other than float and double, whose equality is ambiguous.
+///
+/// Written files are committed like data files by
+/// [`crate::transaction::Transaction::append_file`], which adds them into
+/// delete manifests.
+pub struct EqualityDeleteWriter {
+    writer: DataFileWriter,
+    schema: SchemaRef,
+    equality_ids: Vec<i32>,
+}
+
+impl EqualityDeleteWriter {
+    /// Create a new `EqualityDeleteWriter` of equality fields of the table
+    /// schema.
+    pub async fn try_new(
+        operator: Operator,
+        location_generator: DataFileLocationGenerator,
+        table_schema: &Schema,
+        equality_ids: Vec<i32>,
+        config: WriterConfig,
+    ) -> Result<Self> {
+        let equality_fields = equality_fields(table_schema, &equality_ids)?;
+        let fields = equality_fields
+            .iter()
+            .map(|field| ArrowField::try_from(field.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let schema = Arc::new(ArrowSchema::new(fields));
+
+        let writer = DataFileWriter::try_new(operator, location_generator, schema.clone(), config)
+            .await?
+            .with_content(DataContentType::EqualityDeletes)
+            .with_schema(Schema {
+                schema_id: table_schema.schema_id,
+                identifier_field_ids: None,
+                fields: equality_fields,
+            });
+        Ok(Self {
+            writer,
+            schema,
+            equality_ids,
+        })
+    }
+
+    /// Set partition values of written files, delete keys only delete rows
+    /// of data files in the same partition.
+    pub fn with_partition(mut self, partition: StructValue) -> Self {
+        self.writer = self.writer.with_partition(partition);
+        self
+    }
+
+    /// Arrow schema of written files, which has columns of equality fields.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Write a batch of delete keys.
+    ///
+    /// Columns of equality fields are picked by name, so batches of whole
+    /// table rows are accepted too.
+    pub async fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| {
+                batch.column_by_name(field.name()).cloned().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Equality field {} is not found in batch", field.name()),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(|e| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Batch doesn't match the schema of equality delete files",
+            )
+            .set_source(e)
+        })?;
+        self.writer.write(batch).await
+    }
+
+    /// Complete the write and return written delete files.
+    pub async fn close(self) -> Result<Vec<DataFile>> {
+        let mut delete_files = self.writer.close().await?;
+        for delete_file in &mut delete_files {
+            delete_file.equality_ids = self.equality_ids.clone();
+        }
+        Ok(delete_files)
+    }
+}
+
+/// Returns fields of `equality_ids` in the table schema, checking they can
+/// be used as equality fields.
+pub(crate) fn equality_fields(table_schema: &Schema, equality_ids: &[i32]) -> Result<Vec<Field>> {
+    if equality_ids.is_empty() {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            "Equality fields of equality deletes must not be empty",
+        ));
+    }
+    equality_ids
+        .iter()
+        .map(|id| {
+            let field = table_schema
+                .fields
+                .iter()
+                .find(|v| v.id == *id)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergFeatureUnsupported,
+                        format!("Equality field {id} is not a top level column"),
+                    )
+                })?;
+            match &field.field_type {
+                Any::Primitive(Primitive::Float | Primitive::Double) => Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Equality field {} must not be float or double", field.name),
+                )),
+                Any::Primitive(_) => Ok(field.clone()),
+                _ => Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Equality field {} must be a primitive column", field.name),
+                )),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use opendal::services::Memory;
+
+    use super::*;
+    use crate::types::parse_table_metadata;
+
+    #[tokio::test]
+    async fn test_equality_delete_writer() {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v1.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        metadata.location = "/tmp/table".to_string();
+        let schema = metadata.current_schema().unwrap().clone();
+        let mut builder = Memory::default();
+        builder.root("/tmp/table");
+        let op = Operator::new(builder).unwrap().finish();
+        let writer = |equality_ids: Vec<i32>| {
+            EqualityDeleteWriter::try_new(
+                op.clone(),
+                DataFileLocationGenerator::try_new(&metadata, 0, 0, None).unwrap(),
+                &schema,
+                equality_ids,
+                WriterConfig::from_properties(&HashMap::new()).unwrap(),
+            )
+        };
+
+        assert!(writer(vec![]).await.is_err());
+        assert!(writer(vec![100]).await.is_err());
+
+        // Equality fields are picked from table rows.
+        let mut writer = writer(vec![2]).await.unwrap();
+        assert_eq!(writer.schema().fields().len(), 1);
+        assert_eq!(writer.schema().field(0).name(), "data");
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        writer.write(&batch).await.unwrap();
+        assert!(writer.write(&batch.project(&[0]).unwrap()).await.is_err());
+        let delete_files = writer.close().await.unwrap();
+
+        assert_eq!(delete_files.len(), 1);
+        assert_eq!(delete_files[0].content, DataContentType::EqualityDeletes);
+        assert_eq!(delete_files[0].equality_ids, vec![2]);
+        assert_eq!(delete_files[0].record_count, 2);
+    }
+}
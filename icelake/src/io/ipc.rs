@@ -0,0 +1,145 @@
+//! ipc module encodes record batches into the Arrow IPC stream format, so
+//! that rows read by icelake can be consumed by any arrow implementation.
+
+use std::sync::Arc;
+
+use arrow::datatypes::{Schema as ArrowSchema, SchemaRef};
+use arrow::ipc::writer::{write_message, DictionaryTracker, IpcDataGenerator, IpcWriteOptions};
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+
+use super::data_file_reader::RecordBatchStream;
+use crate::Result;
+
+/// Stream of chunks of an Arrow IPC stream.
+pub type IpcByteStream = BoxStream<'static, Result<Bytes>>;
+
+/// Continuation marker followed by zero length, which ends an IPC stream.
+const END_OF_STREAM: [u8; 8] = [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0];
+
+/// Encode batches of `stream` into the Arrow IPC stream format.
+///
+/// A chunk is yielded per batch, the first one starts with the schema of
+/// the first batch, and the last one ends the IPC stream. Concatenated
+/// chunks can be read by `arrow::ipc::reader::StreamReader`, or sent as the
+/// body of a http response of `application/vnd.apache.arrow.stream`.
+///
+/// An empty `stream` is encoded with `schema` if given, otherwise with a
+/// schema of no columns.
+pub fn ipc_stream(stream: RecordBatchStream, schema: Option<SchemaRef>) -> IpcByteStream {
+    let encoder = IpcEncoder {
+        generator: IpcDataGenerator::default(),
+        options: IpcWriteOptions::default(),
+        dictionary_tracker: DictionaryTracker::new(false),
+        schema_written: false,
+    };
+    futures::stream::try_unfold((stream, Some(encoder)), move |(mut stream, encoder)| {
+        let schema = schema.clone();
+        async move {
+            let Some(mut encoder) = encoder else {
+                return Ok(None);
+            };
+            match stream.try_next().await? {
+                Some(batch) => {
+                    let chunk = encoder.encode_batch(&batch)?;
+                    Ok(Some((chunk, (stream, Some(encoder)))))
+                }
+                None => {
+                    let schema = schema.unwrap_or_else(|| Arc::new(ArrowSchema::empty()));
+                    let chunk = encoder.finish(&schema)?;
+                    Ok(Some((chunk, (stream, None))))
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+struct IpcEncoder {
+    generator: IpcDataGenerator,
+    options: IpcWriteOptions,
+    dictionary_tracker: DictionaryTracker,
+    schema_written: bool,
+}
+
+impl IpcEncoder {
+    fn encode_batch(&mut self, batch: &RecordBatch) -> Result<Bytes> {
+        let mut buf = vec![];
+        self.write_schema(&mut buf, &batch.schema())?;
+        let (dictionaries, message) =
+            self.generator
+                .encoded_batch(batch, &mut self.dictionary_tracker, &self.options)?;
+        for dictionary in dictionaries {
+            write_message(&mut buf, dictionary, &self.options)?;
+        }
+        write_message(&mut buf, message, &self.options)?;
+        Ok(buf.into())
+    }
+
+    /// Returns the end of the stream, `schema` is written if no batch has
+    /// been written.
+    fn finish(&mut self, schema: &ArrowSchema) -> Result<Bytes> {
+        let mut buf = vec![];
+        self.write_schema(&mut buf, schema)?;
+        buf.extend_from_slice(&END_OF_STREAM);
+        Ok(buf.into())
+    }
+
+    fn write_schema(&mut self, buf: &mut Vec<u8>, schema: &ArrowSchema) -> Result<()> {
+        if !self.schema_written {
+            let message = self.generator.schema_to_bytes(schema, &self.options);
+            write_message(buf, message, &self.options)?;
+            self.schema_written = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field as ArrowField};
+    use arrow::ipc::reader::StreamReader;
+
+    use super::*;
+
+    async fn read_back(stream: IpcByteStream) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+        let chunks: Vec<Bytes> = stream.try_collect().await?;
+        let reader = StreamReader::try_new(Cursor::new(chunks.concat()), None)?;
+        let schema = reader.schema();
+        let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok((schema, batches))
+    }
+
+    #[tokio::test]
+    async fn test_ipc_stream() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec![Some("a"), None])) as ArrayRef,
+            ),
+        ])?;
+        let batches = vec![Ok(batch.clone()), Ok(batch.slice(1, 1))];
+        let stream = ipc_stream(futures::stream::iter(batches).boxed(), None);
+        let (schema, batches) = read_back(stream).await?;
+        assert_eq!(schema, batch.schema());
+        assert_eq!(batches, vec![batch.clone(), batch.slice(1, 1)]);
+
+        // Empty streams are encoded with the given schema.
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "id",
+            DataType::Int64,
+            true,
+        )]));
+        let stream = ipc_stream(futures::stream::empty().boxed(), Some(schema.clone()));
+        assert_eq!(read_back(stream).await?, (schema, vec![]));
+        let stream = ipc_stream(futures::stream::empty().boxed(), None);
+        assert_eq!(read_back(stream).await?.0.fields().len(), 0);
+        Ok(())
+    }
+}
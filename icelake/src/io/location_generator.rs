@@ -3,17 +3,14 @@
 
 use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
+use crate::io::location_provider::{location_provider, LocationProvider};
+use crate::table_properties::{DEFAULT_FILE_FORMAT, DEFAULT_FILE_FORMAT_DEFAULT};
 use crate::types::{DataFileFormat, TableMetadata};
-use crate::{Error, ErrorKind, Result};
+use crate::Result;
 use uuid::Uuid;
 
-/// TODO: move to table_properties.rs
-const DEFAULT_FILE_FORMAT: &str = "write.format.default";
-const DEFAULT_FILE_FORMAT_DEFAULT: &str = "parquet";
-const WRITE_DATA_LOCATION: &str = "write.data.path";
-const WRITE_FOLDER_STORAGE_LOCATION: &str = "write.folder-storage.path";
-
 /// DataFileLocationGenerator will generate a file location for the writer.
 pub struct DataFileLocationGenerator {
     file_count: AtomicUsize,
@@ -22,8 +19,19 @@ pub struct DataFileLocationGenerator {
     operation_id: String,
     file_format: DataFileFormat,
     suffix: Option<String>,
+    /// The base location of data files recorded in metadata, like
+    /// `s3://bucket/table/data`, or `data` in relative path mode.
+    data_location: String,
     /// The data file relpath related to the base of table location.
+    ///
+    /// It's empty if data location is outside of the table location, in
+    /// which case data files are written by the operator rooted at data
+    /// location.
     data_rel_location: String,
+    /// Hive style partition directory of generated files, like
+    /// `day=2023-01-01`, empty for unpartitioned files.
+    partition_path: String,
+    location_provider: Arc<dyn LocationProvider>,
 }
 
 impl DataFileLocationGenerator {
@@ -46,28 +54,25 @@ impl DataFileLocationGenerator {
         )
         .unwrap_or(DataFileFormat::Parquet);
 
-        let data_rel_location = {
-            let base_location = &table_metatdata.location;
-            let data_location = table_metatdata.properties.as_ref().and_then(|prop| {
-                prop.get(WRITE_DATA_LOCATION)
-                    .or(prop.get(WRITE_FOLDER_STORAGE_LOCATION))
-                    .cloned()
-            });
-            if let Some(data_location) = data_location {
-                data_location
-                    .strip_prefix(base_location)
-                    .ok_or(Error::new(
-                        ErrorKind::IcebergDataInvalid,
-                        format!(
-                            "data location {} is not a subpath of table location {}",
-                            data_location, base_location
-                        ),
-                    ))?
-                    .to_string()
-            } else {
-                "data".to_string()
-            }
+        let properties = table_metatdata.properties.clone().unwrap_or_default();
+        let data_location = table_metatdata.data_location(&properties);
+        let data_rel_location = if data_location == format!("{}/data", table_metatdata.location) {
+            "data".to_string()
+        } else {
+            // `s3://bucket/table_data` is not inside `s3://bucket/table`.
+            data_location
+                .strip_prefix(&table_metatdata.location)
+                .filter(|v| v.starts_with('/'))
+                .map(|v| v.to_string())
+                .unwrap_or_default()
         };
+        let data_location = table_metatdata.recorded_location(&properties, &data_location)?;
+        let location_provider = location_provider(
+            table_metatdata
+                .properties
+                .as_ref()
+                .unwrap_or(&Default::default()),
+        )?;
 
         Ok(Self {
             file_count: AtomicUsize::new(0),
@@ -76,16 +81,46 @@ impl DataFileLocationGenerator {
             operation_id,
             file_format,
             suffix,
+            data_location,
             data_rel_location,
+            partition_path: String::new(),
+            location_provider,
         })
     }
 
-    /// Generate a related file location for the writer.
-    ///
-    /// # TODO
-    ///
-    /// - Only support to generate file name for unpartitioned write.
-    /// - May need a way(e.g LocationProvider) to generate custom file name in future. It's useful in some case: <https://github.com/apache/iceberg/issues/1911>.
+    /// Generate files under given partition directory, used by
+    /// partitioned writers.
+    pub fn with_partition_path(mut self, partition_path: impl Into<String>) -> Self {
+        self.partition_path = partition_path.into();
+        self
+    }
+
+    /// Override the file format decided by table properties, used by
+    /// writers configured with write options.
+    pub fn with_file_format(mut self, file_format: DataFileFormat) -> Self {
+        self.file_format = file_format;
+        self
+    }
+
+    /// Override the location provider decided by table properties, used by
+    /// tables with a custom location provider.
+    pub fn with_location_provider(mut self, location_provider: Arc<dyn LocationProvider>) -> Self {
+        self.location_provider = location_provider;
+        self
+    }
+
+    /// Returns the full location of a file name returned by
+    /// [`DataFileLocationGenerator::generate_name`].
+    pub fn location_of(&self, name: &str) -> String {
+        let file_name = name
+            .strip_prefix(self.data_rel_location.as_str())
+            .unwrap_or(name)
+            .trim_start_matches('/');
+        format!("{}/{}", self.data_location, file_name)
+    }
+
+    /// Generate a related file location for the writer, placed by the
+    /// location provider.
     pub fn generate_name(&self) -> String {
         let suffix = if let Some(suffix) = &self.suffix {
             format!("-{}", suffix)
@@ -110,7 +145,15 @@ impl DataFileLocationGenerator {
             format!("{}.{}", file_name, extension)
         };
 
-        format!("{}/{}", self.data_rel_location, file_name)
+        let file_name = self
+            .location_provider
+            .new_data_location(&self.partition_path, &file_name);
+
+        if self.data_rel_location.is_empty() {
+            file_name
+        } else {
+            format!("{}/{}", self.data_rel_location, file_name)
+        }
     }
 }
 
@@ -121,8 +164,9 @@ mod test {
     use anyhow::Result;
 
     use crate::{
-        io::location_generator::{
-            DataFileLocationGenerator, WRITE_DATA_LOCATION, WRITE_FOLDER_STORAGE_LOCATION,
+        io::location_generator::DataFileLocationGenerator,
+        table_properties::{
+            WRITE_DATA_LOCATION, WRITE_FOLDER_STORAGE_LOCATION, WRITE_OBJECT_STORE_ENABLED,
         },
         types::parse_table_metadata,
     };
@@ -212,4 +256,105 @@ mod test {
         assert!(name.starts_with("/mock_storage"));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_location_generator_with_external_data_location() -> Result<()> {
+        let mut metadata = {
+            let path = format!(
+                "{}/../testdata/simple_table/metadata/v1.metadata.json",
+                env!("CARGO_MANIFEST_DIR")
+            );
+
+            let bs = fs::read(path).expect("read_file must succeed");
+
+            parse_table_metadata(&bs).expect("parse_table_metadata v1 must succeed")
+        };
+
+        // Mock metadata to test
+        metadata.location = "s3://bucket/table".to_string();
+        let mock_properties = {
+            let mut map = HashMap::new();
+            map.insert(
+                WRITE_DATA_LOCATION.to_string(),
+                "s3://other/data/".to_string(),
+            );
+            map
+        };
+        metadata.properties = Some(mock_properties);
+
+        let generator = DataFileLocationGenerator::try_new(&metadata, 0, 0, None)?;
+        let name = generator.generate_name();
+        assert!(!name.starts_with('/'));
+        assert_eq!(
+            generator.location_of(&name),
+            format!("s3://other/data/{name}")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_location_generator_with_sibling_data_location() -> Result<()> {
+        let mut metadata = {
+            let path = format!(
+                "{}/../testdata/simple_table/metadata/v1.metadata.json",
+                env!("CARGO_MANIFEST_DIR")
+            );
+
+            let bs = fs::read(path).expect("read_file must succeed");
+
+            parse_table_metadata(&bs).expect("parse_table_metadata v1 must succeed")
+        };
+
+        // Data location shares the prefix of table location, but is not
+        // inside it.
+        metadata.location = "s3://bucket/table".to_string();
+        metadata.properties = Some(HashMap::from([(
+            WRITE_DATA_LOCATION.to_string(),
+            "s3://bucket/table_data".to_string(),
+        )]));
+
+        let generator = DataFileLocationGenerator::try_new(&metadata, 0, 0, None)?;
+        let name = generator.generate_name();
+        assert!(!name.starts_with("_data"));
+        assert!(!name.starts_with('/'));
+        assert_eq!(
+            generator.location_of(&name),
+            format!("s3://bucket/table_data/{name}")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_location_generator_with_object_store() -> Result<()> {
+        let mut metadata = {
+            let path = format!(
+                "{}/../testdata/simple_table/metadata/v1.metadata.json",
+                env!("CARGO_MANIFEST_DIR")
+            );
+
+            let bs = fs::read(path).expect("read_file must succeed");
+
+            parse_table_metadata(&bs).expect("parse_table_metadata v1 must succeed")
+        };
+
+        metadata.location = "s3://bucket/table".to_string();
+        metadata.properties = Some(HashMap::from([(
+            WRITE_OBJECT_STORE_ENABLED.to_string(),
+            "true".to_string(),
+        )]));
+
+        let generator =
+            DataFileLocationGenerator::try_new(&metadata, 0, 0, None)?.with_partition_path("id=1");
+        let name = generator.generate_name();
+        let parts: Vec<_> = name.split('/').collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "data");
+        assert_eq!(parts[1].len(), 8);
+        assert_eq!(parts[2], "id=1");
+        assert_eq!(
+            generator.location_of(&name),
+            format!("s3://bucket/table/{name}")
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,143 @@
+//! location_provider module decides where new data files are placed under
+//! the data location of a table.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::io::writer_config::parse_or;
+use crate::table_properties::{
+    WRITE_OBJECT_STORE_ENABLED, WRITE_OBJECT_STORE_ENABLED_DEFAULT,
+    WRITE_OBJECT_STORE_PARTITIONED_PATHS, WRITE_OBJECT_STORE_PARTITIONED_PATHS_DEFAULT,
+};
+use crate::types::murmur3_32;
+use crate::Result;
+
+/// LocationProvider decides the path of new data files.
+///
+/// Paths are relative to the data location of the table, so files are still
+/// written by the operator serving the data location.
+pub trait LocationProvider: Debug + Send + Sync {
+    /// Returns the path of a new file named `file_name` in the partition of
+    /// hive style directory `partition_path`, which is empty for
+    /// unpartitioned files.
+    fn new_data_location(&self, partition_path: &str, file_name: &str) -> String;
+}
+
+/// DefaultLocationProvider places files under their partition directories,
+/// like `day=2023-01-01/00000-0-{uuid}-00001.parquet`.
+#[derive(Debug, Default)]
+pub struct DefaultLocationProvider;
+
+impl LocationProvider for DefaultLocationProvider {
+    fn new_data_location(&self, partition_path: &str, file_name: &str) -> String {
+        if partition_path.is_empty() {
+            file_name.to_string()
+        } else {
+            format!("{partition_path}/{file_name}")
+        }
+    }
+}
+
+/// ObjectStoreLocationProvider places files under directories prefixed by
+/// the hash of their names, like
+/// `2d9fd2c4/day=2023-01-01/00000-0-{uuid}-00001.parquet`.
+///
+/// Object stores like S3 throttle requests by key prefix, files of
+/// high-throughput writers are spread over many prefixes in this way.
+#[derive(Debug)]
+pub struct ObjectStoreLocationProvider {
+    partitioned_paths: bool,
+}
+
+impl ObjectStoreLocationProvider {
+    /// Create a provider, partition directories are omitted unless
+    /// `partitioned_paths` is set.
+    pub fn new(partitioned_paths: bool) -> Self {
+        Self { partitioned_paths }
+    }
+}
+
+impl Default for ObjectStoreLocationProvider {
+    fn default() -> Self {
+        Self::new(WRITE_OBJECT_STORE_PARTITIONED_PATHS_DEFAULT)
+    }
+}
+
+impl LocationProvider for ObjectStoreLocationProvider {
+    fn new_data_location(&self, partition_path: &str, file_name: &str) -> String {
+        let hash = murmur3_32(file_name.as_bytes()) as u32;
+        if partition_path.is_empty() || !self.partitioned_paths {
+            format!("{hash:08x}/{file_name}")
+        } else {
+            format!("{hash:08x}/{partition_path}/{file_name}")
+        }
+    }
+}
+
+/// Returns the location provider configured by table properties, see
+/// [`WRITE_OBJECT_STORE_ENABLED`].
+pub fn location_provider(props: &HashMap<String, String>) -> Result<Arc<dyn LocationProvider>> {
+    if !parse_or(
+        props,
+        WRITE_OBJECT_STORE_ENABLED,
+        WRITE_OBJECT_STORE_ENABLED_DEFAULT,
+    )? {
+        return Ok(Arc::new(DefaultLocationProvider));
+    }
+    let partitioned_paths = parse_or(
+        props,
+        WRITE_OBJECT_STORE_PARTITIONED_PATHS,
+        WRITE_OBJECT_STORE_PARTITIONED_PATHS_DEFAULT,
+    )?;
+    Ok(Arc::new(ObjectStoreLocationProvider::new(
+        partitioned_paths,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_provider() -> Result<()> {
+        let mut props = HashMap::new();
+        let provider = location_provider(&props)?;
+        assert_eq!(provider.new_data_location("", "a.parquet"), "a.parquet");
+        assert_eq!(
+            provider.new_data_location("id=1", "a.parquet"),
+            "id=1/a.parquet"
+        );
+
+        props.insert(WRITE_OBJECT_STORE_ENABLED.to_string(), "true".to_string());
+        let provider = location_provider(&props)?;
+        let hash = format!("{:08x}", murmur3_32(b"a.parquet") as u32);
+        assert_eq!(
+            provider.new_data_location("", "a.parquet"),
+            format!("{hash}/a.parquet")
+        );
+        assert_eq!(
+            provider.new_data_location("id=1", "a.parquet"),
+            format!("{hash}/id=1/a.parquet")
+        );
+        // Files of the same partition are spread over prefixes.
+        assert_ne!(
+            provider.new_data_location("id=1", "b.parquet"),
+            format!("{hash}/id=1/b.parquet")
+        );
+
+        props.insert(
+            WRITE_OBJECT_STORE_PARTITIONED_PATHS.to_string(),
+            "false".to_string(),
+        );
+        let provider = location_provider(&props)?;
+        assert_eq!(
+            provider.new_data_location("id=1", "a.parquet"),
+            format!("{hash}/a.parquet")
+        );
+
+        props.insert(WRITE_OBJECT_STORE_ENABLED.to_string(), "x".to_string());
+        assert!(location_provider(&props).is_err());
+        Ok(())
+    }
+}
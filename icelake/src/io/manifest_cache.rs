@@ -0,0 +1,198 @@
+//! manifest_cache module provides [`ManifestCache`] to keep parsed manifest
+//! files and manifest lists in memory.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::types::{ManifestFile, ManifestList};
+
+/// ManifestCache caches parsed manifest files and manifest lists by their
+/// paths, so repeated scans of the same snapshot don't read and parse them
+/// again.
+///
+/// Manifest files and manifest lists are never modified once written, so
+/// cached contents never go stale. Contents are weighed by the length of
+/// their files, the least recently used ones are evicted once the total
+/// exceeds the capacity.
+///
+/// A cache can be shared by tables, see [`crate::Table::set_manifest_cache`].
+pub struct ManifestCache {
+    capacity_bytes: u64,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Clone)]
+enum Cached {
+    Manifest(Arc<ManifestFile>),
+    ManifestList(Arc<ManifestList>),
+}
+
+struct CacheEntry {
+    value: Cached,
+    size: u64,
+    last_access: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    /// Paths of entries by their last access.
+    access_order: BTreeMap<u64, String>,
+    tick: u64,
+    size: u64,
+}
+
+impl ManifestCache {
+    /// Create a cache of contents of files up to `capacity_bytes` in total.
+    pub fn new(capacity_bytes: u64) -> Self {
+        Self {
+            capacity_bytes,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Returns the total length of files of cached contents.
+    pub fn size(&self) -> u64 {
+        self.state.lock().unwrap().size
+    }
+
+    /// Returns the number of cached files.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns whether no file is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached contents.
+    pub fn clear(&self) {
+        *self.state.lock().unwrap() = CacheState::default();
+    }
+
+    pub(crate) fn get_manifest(&self, path: &str) -> Option<Arc<ManifestFile>> {
+        match self.get(path)? {
+            Cached::Manifest(v) => Some(v),
+            Cached::ManifestList(_) => None,
+        }
+    }
+
+    pub(crate) fn insert_manifest(&self, path: &str, size: u64, manifest: Arc<ManifestFile>) {
+        self.insert(path, size, Cached::Manifest(manifest))
+    }
+
+    pub(crate) fn get_manifest_list(&self, path: &str) -> Option<Arc<ManifestList>> {
+        match self.get(path)? {
+            Cached::ManifestList(v) => Some(v),
+            Cached::Manifest(_) => None,
+        }
+    }
+
+    pub(crate) fn insert_manifest_list(
+        &self,
+        path: &str,
+        size: u64,
+        manifest_list: Arc<ManifestList>,
+    ) {
+        self.insert(path, size, Cached::ManifestList(manifest_list))
+    }
+
+    fn get(&self, path: &str) -> Option<Cached> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(path)?;
+        let last_access = std::mem::replace(&mut entry.last_access, tick);
+        let value = entry.value.clone();
+        state.access_order.remove(&last_access);
+        state.access_order.insert(tick, path.to_string());
+        Some(value)
+    }
+
+    fn insert(&self, path: &str, size: u64, value: Cached) {
+        // Files larger than the capacity would evict everything else.
+        if size > self.capacity_bytes {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let entry = CacheEntry {
+            value,
+            size,
+            last_access: state.tick,
+        };
+        if let Some(old) = state.entries.insert(path.to_string(), entry) {
+            state.access_order.remove(&old.last_access);
+            state.size -= old.size;
+        }
+        let tick = state.tick;
+        state.access_order.insert(tick, path.to_string());
+        state.size += size;
+
+        while state.size > self.capacity_bytes {
+            let Some((_, evicted)) = state.access_order.pop_first() else {
+                break;
+            };
+            if let Some(old) = state.entries.remove(&evicted) {
+                state.size -= old.size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::{Result, Table};
+
+    fn manifest_list() -> Arc<ManifestList> {
+        Arc::new(ManifestList { entries: vec![] })
+    }
+
+    #[test]
+    fn test_manifest_cache_eviction() {
+        let cache = ManifestCache::new(10);
+        cache.insert_manifest_list("a", 4, manifest_list());
+        cache.insert_manifest_list("b", 4, manifest_list());
+        assert!(cache.get_manifest_list("a").is_some());
+        assert!(cache.get_manifest("a").is_none());
+
+        // "b" is the least recently used one.
+        cache.insert_manifest_list("c", 4, manifest_list());
+        assert!(cache.get_manifest_list("b").is_none());
+        assert!(cache.get_manifest_list("a").is_some());
+        assert!(cache.get_manifest_list("c").is_some());
+        assert_eq!((cache.len(), cache.size()), (2, 8));
+
+        // Replacing an entry updates the size.
+        cache.insert_manifest_list("c", 2, manifest_list());
+        assert_eq!((cache.len(), cache.size()), (2, 6));
+
+        // Files larger than the capacity are not cached.
+        cache.insert_manifest_list("d", 11, manifest_list());
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_manifest_cache_scan() -> Result<()> {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let cache = Arc::new(ManifestCache::new(1024 * 1024));
+        table.set_manifest_cache(cache.clone());
+
+        let files = table.scan().build()?.plan_files().await?;
+        // The manifest list and the manifest of the snapshot are cached.
+        assert_eq!(cache.len(), 2);
+
+        // Cached contents are used once files are gone.
+        std::fs::remove_dir_all(tmp_dir.path().join("metadata"))?;
+        assert_eq!(table.scan().build()?.plan_files().await?, files);
+        Ok(())
+    }
+}
@@ -1,7 +1,21 @@
 //! io module provides the ability to read and write data from various
 //! sources.
 
+pub(crate) mod avro;
+pub mod data_file_reader;
 pub mod data_file_writer;
+pub(crate) mod delete_filter;
+pub mod delta_writer;
+pub mod equality_delete_writer;
+pub mod ipc;
 pub mod location_generator;
+pub mod location_provider;
+pub mod manifest_cache;
+pub mod orc;
 pub mod parquet;
+pub mod policy;
+pub mod position_delete_writer;
+pub mod sorted_merge;
+pub mod storage;
 pub mod task_writer;
+pub mod writer_config;
@@ -0,0 +1,206 @@
+//! compression module compresses and decompresses orc streams.
+//!
+//! Compressed streams are split into chunks of at most the compression
+//! block size, each chunk starts with a 3 bytes header of its length and
+//! whether it's stored uncompressed.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use crate::{Error, ErrorKind, Result};
+
+/// Compression codecs of orc files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrcCompression {
+    /// Streams are not compressed.
+    None,
+    /// Raw deflate without zlib headers.
+    Zlib,
+    /// Raw snappy.
+    Snappy,
+    /// LZ4 block format.
+    Lz4,
+    /// Zstandard.
+    Zstd,
+}
+
+impl OrcCompression {
+    pub(crate) fn kind(&self) -> i32 {
+        match self {
+            OrcCompression::None => 0,
+            OrcCompression::Zlib => 1,
+            OrcCompression::Snappy => 2,
+            OrcCompression::Lz4 => 4,
+            OrcCompression::Zstd => 5,
+        }
+    }
+
+    pub(crate) fn from_kind(kind: i32) -> Result<Self> {
+        match kind {
+            0 => Ok(OrcCompression::None),
+            1 => Ok(OrcCompression::Zlib),
+            2 => Ok(OrcCompression::Snappy),
+            4 => Ok(OrcCompression::Lz4),
+            5 => Ok(OrcCompression::Zstd),
+            _ => Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("Orc compression kind {kind}"),
+            )),
+        }
+    }
+
+    /// Compress `data` into chunks of at most `block_size` bytes.
+    pub(crate) fn compress(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        if *self == OrcCompression::None {
+            return Ok(data.to_vec());
+        }
+        let mut out = vec![];
+        for chunk in data.chunks(block_size) {
+            let compressed = self.compress_chunk(chunk)?;
+            // Chunks not smaller after compression are stored as is.
+            let (is_original, body) = if compressed.len() < chunk.len() {
+                (0, compressed.as_slice())
+            } else {
+                (1, chunk)
+            };
+            let header = ((body.len() as u32) << 1) | is_original;
+            out.extend_from_slice(&header.to_le_bytes()[..3]);
+            out.extend_from_slice(body);
+        }
+        Ok(out)
+    }
+
+    /// Decompress a stream, `block_size` is the max size of decompressed
+    /// chunks.
+    pub(crate) fn decompress(&self, data: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        if *self == OrcCompression::None {
+            return Ok(data.to_vec());
+        }
+        let mut out = vec![];
+        let mut pos = 0;
+        while pos < data.len() {
+            let header = data
+                .get(pos..pos + 3)
+                .ok_or_else(|| invalid("truncated chunk header"))?;
+            let header = u32::from_le_bytes([header[0], header[1], header[2], 0]);
+            let len = (header >> 1) as usize;
+            let body = data
+                .get(pos + 3..pos + 3 + len)
+                .ok_or_else(|| invalid("truncated chunk"))?;
+            if header & 1 == 1 {
+                out.extend_from_slice(body);
+            } else {
+                out.extend(self.decompress_chunk(body, block_size)?);
+            }
+            pos += 3 + len;
+        }
+        Ok(out)
+    }
+
+    fn compress_chunk(&self, chunk: &[u8]) -> Result<Vec<u8>> {
+        let compressed = match self {
+            OrcCompression::None => Ok(chunk.to_vec()),
+            OrcCompression::Zlib => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(chunk).and_then(|_| encoder.finish())
+            }
+            OrcCompression::Snappy => snap::raw::Encoder::new()
+                .compress_vec(chunk)
+                .map_err(std::io::Error::from),
+            OrcCompression::Lz4 => lz4::block::compress(chunk, None, false),
+            OrcCompression::Zstd => zstd::bulk::compress(chunk, zstd::DEFAULT_COMPRESSION_LEVEL),
+        };
+        compressed.map_err(|e| {
+            Error::new(
+                ErrorKind::Unexpected,
+                format!("Failed to compress by {self}"),
+            )
+            .set_source(e)
+        })
+    }
+
+    fn decompress_chunk(&self, chunk: &[u8], block_size: usize) -> Result<Vec<u8>> {
+        let decompressed = match self {
+            OrcCompression::None => Ok(chunk.to_vec()),
+            OrcCompression::Zlib => {
+                let mut out = vec![];
+                flate2::read::DeflateDecoder::new(chunk)
+                    .read_to_end(&mut out)
+                    .map(|_| out)
+            }
+            OrcCompression::Snappy => snap::raw::Decoder::new()
+                .decompress_vec(chunk)
+                .map_err(std::io::Error::from),
+            OrcCompression::Lz4 => lz4::block::decompress(chunk, Some(block_size as i32)),
+            OrcCompression::Zstd => zstd::stream::decode_all(chunk),
+        };
+        decompressed.map_err(|e| invalid(&format!("failed to decompress by {self}")).set_source(e))
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Invalid orc compressed stream: {message}"),
+    )
+}
+
+impl FromStr for OrcCompression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "uncompressed" => Ok(OrcCompression::None),
+            "zlib" => Ok(OrcCompression::Zlib),
+            "snappy" => Ok(OrcCompression::Snappy),
+            "lz4" => Ok(OrcCompression::Lz4),
+            "zstd" => Ok(OrcCompression::Zstd),
+            _ => Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("Unsupported orc compression codec: {s}"),
+            )),
+        }
+    }
+}
+
+impl Display for OrcCompression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OrcCompression::None => write!(f, "none"),
+            OrcCompression::Zlib => write!(f, "zlib"),
+            OrcCompression::Snappy => write!(f, "snappy"),
+            OrcCompression::Lz4 => write!(f, "lz4"),
+            OrcCompression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_roundtrip() {
+        let data: Vec<u8> = (0..100_000u32)
+            .flat_map(|v| (v % 97).to_le_bytes())
+            .collect();
+        let random: Vec<u8> = (0..1000u32)
+            .map(|v| (v.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        for codec in [
+            OrcCompression::None,
+            OrcCompression::Zlib,
+            OrcCompression::Snappy,
+            OrcCompression::Lz4,
+            OrcCompression::Zstd,
+        ] {
+            for data in [&data, &random] {
+                let compressed = codec.compress(data, 4096).unwrap();
+                assert_eq!(&codec.decompress(&compressed, 4096).unwrap(), data);
+            }
+            assert_eq!(codec.to_string().parse::<OrcCompression>().unwrap(), codec);
+        }
+    }
+}
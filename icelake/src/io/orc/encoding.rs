@@ -0,0 +1,487 @@
+//! encoding module implements run length encodings of orc streams.
+//!
+//! Writers only use version 1 of integer run length encoding, readers
+//! support both version 1 and version 2.
+
+use crate::{Error, ErrorKind, Result};
+
+fn invalid(message: &str) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Invalid orc stream: {message}"),
+    )
+}
+
+fn zigzag_encode(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn zigzag_decode(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// Write an unsigned base 128 varint.
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut v: u128) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+/// Write a zigzag encoded base 128 varint, used by decimals.
+pub(crate) fn write_signed_varint(out: &mut Vec<u8>, v: i128) {
+    write_varint(out, ((v << 1) ^ (v >> 127)) as u128)
+}
+
+/// Cursor over the bytes of a decompressed stream.
+pub(crate) struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let v = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of stream"))?;
+        self.pos += 1;
+        Ok(v)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|v| *v <= self.data.len())
+            .ok_or_else(|| invalid("unexpected end of stream"))?;
+        let v = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(v)
+    }
+
+    pub fn read_varint(&mut self) -> Result<u128> {
+        let mut v = 0u128;
+        let mut shift = 0;
+        loop {
+            let b = self.read_u8()?;
+            if shift >= 128 {
+                return Err(invalid("varint overflow"));
+            }
+            v |= ((b & 0x7f) as u128) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+            shift += 7;
+        }
+    }
+
+    pub fn read_signed_varint(&mut self) -> Result<i128> {
+        let v = self.read_varint()?;
+        Ok((v >> 1) as i128 ^ -((v & 1) as i128))
+    }
+
+    /// Read a big-endian integer of `width` bytes.
+    fn read_be(&mut self, width: usize) -> Result<u64> {
+        Ok(self
+            .read_bytes(width)?
+            .iter()
+            .fold(0u64, |v, b| (v << 8) | *b as u64))
+    }
+}
+
+/// Encode bytes by byte run length encoding.
+pub(crate) fn encode_bytes(values: &[u8], out: &mut Vec<u8>) {
+    let mut literals: Vec<u8> = vec![];
+    let flush = |literals: &mut Vec<u8>, out: &mut Vec<u8>| {
+        if !literals.is_empty() {
+            out.push((-(literals.len() as i32)) as u8);
+            out.append(literals);
+        }
+    };
+
+    let mut i = 0;
+    while i < values.len() {
+        let mut run = 1;
+        while run < 130 && i + run < values.len() && values[i + run] == values[i] {
+            run += 1;
+        }
+        if run >= 3 {
+            flush(&mut literals, out);
+            out.push((run - 3) as u8);
+            out.push(values[i]);
+            i += run;
+        } else {
+            literals.push(values[i]);
+            i += 1;
+            if literals.len() == 128 {
+                flush(&mut literals, out);
+            }
+        }
+    }
+    flush(&mut literals, out);
+}
+
+/// Decode `n` bytes encoded by byte run length encoding.
+pub(crate) fn decode_bytes(data: &[u8], n: usize) -> Result<Vec<u8>> {
+    let mut r = ByteReader::new(data);
+    let mut values = Vec::with_capacity(n);
+    while values.len() < n {
+        let header = r.read_u8()? as i8;
+        if header >= 0 {
+            let v = r.read_u8()?;
+            values.extend(std::iter::repeat_n(v, header as usize + 3));
+        } else {
+            values.extend_from_slice(r.read_bytes(-(header as i32) as usize)?);
+        }
+    }
+    values.truncate(n);
+    Ok(values)
+}
+
+/// Encode booleans by packing them into bytes from the most significant
+/// bit, then by byte run length encoding.
+pub(crate) fn encode_booleans(values: &[bool], out: &mut Vec<u8>) {
+    let bytes: Vec<u8> = values
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |b, (i, v)| if *v { b | (0x80 >> i) } else { b })
+        })
+        .collect();
+    encode_bytes(&bytes, out);
+}
+
+/// Decode `n` booleans.
+pub(crate) fn decode_booleans(data: &[u8], n: usize) -> Result<Vec<bool>> {
+    let bytes = decode_bytes(data, n.div_ceil(8))?;
+    Ok((0..n)
+        .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+        .collect())
+}
+
+/// Encode integers by version 1 of integer run length encoding.
+pub(crate) fn encode_ints(values: &[i64], signed: bool, out: &mut Vec<u8>) {
+    let write = |out: &mut Vec<u8>, v: i64| {
+        let v = if signed { zigzag_encode(v) } else { v as u64 };
+        write_varint(out, v as u128);
+    };
+    let mut literals: Vec<i64> = vec![];
+    let flush = |literals: &mut Vec<i64>, out: &mut Vec<u8>| {
+        if !literals.is_empty() {
+            out.push((-(literals.len() as i32)) as u8);
+            for v in literals.drain(..) {
+                write(out, v);
+            }
+        }
+    };
+
+    let delta_at = |i: usize| -> Option<i64> {
+        values[i + 1]
+            .checked_sub(values[i])
+            .filter(|v| (-128..=127).contains(v))
+    };
+    let mut i = 0;
+    while i < values.len() {
+        if i + 2 < values.len() {
+            if let Some(delta) = delta_at(i) {
+                let mut run = 2;
+                while run < 130 && i + run < values.len() && delta_at(i + run - 1) == Some(delta) {
+                    run += 1;
+                }
+                if run >= 3 {
+                    flush(&mut literals, out);
+                    out.push((run - 3) as u8);
+                    out.push(delta as i8 as u8);
+                    write(out, values[i]);
+                    i += run;
+                    continue;
+                }
+            }
+        }
+        literals.push(values[i]);
+        i += 1;
+        if literals.len() == 128 {
+            flush(&mut literals, out);
+        }
+    }
+    flush(&mut literals, out);
+}
+
+/// Version of integer run length encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RleVersion {
+    V1,
+    V2,
+}
+
+/// Decode `n` integers encoded by integer run length encoding.
+pub(crate) fn decode_ints(
+    data: &[u8],
+    n: usize,
+    signed: bool,
+    version: RleVersion,
+) -> Result<Vec<i64>> {
+    let mut r = ByteReader::new(data);
+    let mut values = Vec::with_capacity(n);
+    while values.len() < n {
+        match version {
+            RleVersion::V1 => decode_v1_run(&mut r, signed, &mut values)?,
+            RleVersion::V2 => decode_v2_run(&mut r, signed, &mut values)?,
+        }
+    }
+    values.truncate(n);
+    Ok(values)
+}
+
+fn read_int(r: &mut ByteReader, signed: bool) -> Result<i64> {
+    let v = r.read_varint()? as u64;
+    Ok(if signed { zigzag_decode(v) } else { v as i64 })
+}
+
+fn decode_v1_run(r: &mut ByteReader, signed: bool, values: &mut Vec<i64>) -> Result<()> {
+    let header = r.read_u8()? as i8;
+    if header >= 0 {
+        let delta = r.read_u8()? as i8 as i64;
+        let base = read_int(r, signed)?;
+        for i in 0..header as i64 + 3 {
+            values.push(base.wrapping_add(i * delta));
+        }
+    } else {
+        for _ in 0..-(header as i32) {
+            values.push(read_int(r, signed)?);
+        }
+    }
+    Ok(())
+}
+
+/// Bit width of encoded 5 bits width in version 2.
+fn decode_bit_width(code: u8) -> usize {
+    match code {
+        0..=23 => code as usize + 1,
+        24 => 26,
+        25 => 28,
+        26 => 30,
+        27 => 32,
+        28 => 40,
+        29 => 48,
+        30 => 56,
+        _ => 64,
+    }
+}
+
+/// Closest bit width supported by bit packing of version 2.
+fn closest_fixed_bits(n: usize) -> usize {
+    match n {
+        0 => 1,
+        1..=24 => n,
+        25..=26 => 26,
+        27..=28 => 28,
+        29..=30 => 30,
+        31..=32 => 32,
+        33..=40 => 40,
+        41..=48 => 48,
+        49..=56 => 56,
+        _ => 64,
+    }
+}
+
+/// Read `n` big-endian bit packed values of `width` bits.
+fn read_bit_packed(r: &mut ByteReader, n: usize, width: usize) -> Result<Vec<u64>> {
+    let bytes = r.read_bytes((n * width).div_ceil(8))?;
+    let mut values = Vec::with_capacity(n);
+    let mut bit = 0;
+    for _ in 0..n {
+        let mut v = 0u64;
+        for _ in 0..width {
+            let b = (bytes[bit / 8] >> (7 - bit % 8)) & 1;
+            v = (v << 1) | b as u64;
+            bit += 1;
+        }
+        values.push(v);
+    }
+    Ok(values)
+}
+
+fn decode_v2_run(r: &mut ByteReader, signed: bool, values: &mut Vec<i64>) -> Result<()> {
+    let first = r.read_u8()?;
+    let unzigzag = |v: u64| if signed { zigzag_decode(v) } else { v as i64 };
+    match first >> 6 {
+        // Short repeat.
+        0 => {
+            let width = ((first >> 3) & 0x07) as usize + 1;
+            let count = (first & 0x07) as usize + 3;
+            let v = unzigzag(r.read_be(width)?);
+            values.extend(std::iter::repeat_n(v, count));
+        }
+        // Direct.
+        1 => {
+            let width = decode_bit_width((first >> 1) & 0x1f);
+            let len = (((first & 1) as usize) << 8 | r.read_u8()? as usize) + 1;
+            values.extend(read_bit_packed(r, len, width)?.into_iter().map(unzigzag));
+        }
+        // Patched base.
+        2 => {
+            let width = decode_bit_width((first >> 1) & 0x1f);
+            let len = (((first & 1) as usize) << 8 | r.read_u8()? as usize) + 1;
+            let third = r.read_u8()?;
+            let base_width = (third >> 5) as usize + 1;
+            let patch_width = decode_bit_width(third & 0x1f);
+            let fourth = r.read_u8()?;
+            let gap_width = (fourth >> 5) as usize + 1;
+            let patch_len = (fourth & 0x1f) as usize;
+
+            // Base is stored in sign magnitude form.
+            let base = r.read_be(base_width)?;
+            let sign_bit = 1u64 << (base_width * 8 - 1);
+            let base = if base & sign_bit != 0 {
+                -((base & !sign_bit) as i64)
+            } else {
+                base as i64
+            };
+            let mut data = read_bit_packed(r, len, width)?;
+            let patches =
+                read_bit_packed(r, patch_len, closest_fixed_bits(patch_width + gap_width))?;
+            if patch_width + width > 64 {
+                return Err(invalid("patch width overflow"));
+            }
+            let mut pos = 0;
+            for patch in patches {
+                pos += (patch >> patch_width) as usize;
+                let patch = patch & ((1u64 << patch_width) - 1);
+                // A gap larger than 255 is split into patches of zero.
+                if patch == 0 {
+                    continue;
+                }
+                let v = data
+                    .get_mut(pos)
+                    .ok_or_else(|| invalid("patch out of range"))?;
+                *v |= patch << width;
+            }
+            values.extend(data.into_iter().map(|v| base.wrapping_add(v as i64)));
+        }
+        // Delta.
+        _ => {
+            let code = (first >> 1) & 0x1f;
+            let width = if code == 0 { 0 } else { decode_bit_width(code) };
+            let len = (((first & 1) as usize) << 8 | r.read_u8()? as usize) + 1;
+            let base = read_int(r, signed)?;
+            let delta = read_int(r, true)?;
+            values.push(base);
+            if len == 1 {
+                return Ok(());
+            }
+            let mut last = base.wrapping_add(delta);
+            values.push(last);
+            if width == 0 {
+                for _ in 2..len {
+                    last = last.wrapping_add(delta);
+                    values.push(last);
+                }
+            } else {
+                for v in read_bit_packed(r, len - 2, width)? {
+                    last = if delta < 0 {
+                        last.wrapping_sub(v as i64)
+                    } else {
+                        last.wrapping_add(v as i64)
+                    };
+                    values.push(last);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_rle() {
+        let mut out = vec![];
+        encode_bytes(&[0; 100], &mut out);
+        assert_eq!(out, vec![0x61, 0x00]);
+
+        let mut out = vec![];
+        encode_bytes(&[0x44, 0x45], &mut out);
+        assert_eq!(out, vec![0xfe, 0x44, 0x45]);
+
+        let values: Vec<u8> = (0..300).map(|v| (v / 7) as u8).collect();
+        let mut out = vec![];
+        encode_bytes(&values, &mut out);
+        assert_eq!(decode_bytes(&out, values.len()).unwrap(), values);
+
+        let values = vec![
+            true, false, false, true, true, true, true, true, true, false,
+        ];
+        let mut out = vec![];
+        encode_booleans(&values, &mut out);
+        assert_eq!(decode_booleans(&out, values.len()).unwrap(), values);
+    }
+
+    #[test]
+    fn test_int_rle_v1() {
+        let mut out = vec![];
+        encode_ints(&[7; 100], false, &mut out);
+        assert_eq!(out, vec![0x61, 0x00, 0x07]);
+
+        let mut out = vec![];
+        encode_ints(&[2, 3, 6, 7, 11], false, &mut out);
+        assert_eq!(out, vec![0xfb, 0x02, 0x03, 0x06, 0x07, 0x0b]);
+
+        let values: Vec<i64> = (0..1000)
+            .map(|v| if v % 300 < 150 { v * 3 } else { -v * v })
+            .chain([i64::MIN, i64::MAX, 0, i64::MIN])
+            .collect();
+        let mut out = vec![];
+        encode_ints(&values, true, &mut out);
+        assert_eq!(
+            decode_ints(&out, values.len(), true, RleVersion::V1).unwrap(),
+            values
+        );
+    }
+
+    #[test]
+    fn test_int_rle_v2() {
+        // Examples of the orc specification.
+        let decode = |data: &[u8], n| decode_ints(data, n, false, RleVersion::V2).unwrap();
+        assert_eq!(decode(&[0x0a, 0x27, 0x10], 5), vec![10000; 5]);
+        assert_eq!(
+            decode(
+                &[0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef],
+                4
+            ),
+            vec![23713, 43806, 57005, 48879]
+        );
+        assert_eq!(
+            decode(
+                &[
+                    0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c,
+                    0x46, 0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe,
+                    0xfc, 0xe8
+                ],
+                20
+            ),
+            vec![
+                2030, 2000, 2020, 1000000, 2040, 2050, 2060, 2070, 2080, 2090, 2100, 2110, 2120,
+                2130, 2140, 2150, 2160, 2170, 2180, 2190
+            ]
+        );
+        assert_eq!(
+            decode(&[0xc6, 0x09, 0x02, 0x02, 0x22, 0x42, 0x42, 0x46], 10),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+    }
+}
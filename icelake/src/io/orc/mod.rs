@@ -0,0 +1,28 @@
+//! orc module provides the ability to read and write orc data.
+//!
+//! The orc format is implemented in icelake since no orc crate supports the
+//! arrow version used by icelake. Writers use version 1 of run length
+//! encodings, readers support all encodings except LZO compression.
+//!
+//! Data files are written in orc if table property `write.format.default`
+//! is `orc`. Iceberg field ids and types without orc counterparts, like
+//! `time` and `uuid`, are recorded in attributes of orc types like the java
+//! implementation does, such as `iceberg.id` and `iceberg.long-type`.
+//!
+//! Reference: <https://orc.apache.org/specification/ORCv1/>
+
+mod compression;
+pub use compression::OrcCompression;
+
+mod encoding;
+
+mod proto;
+
+mod write;
+pub(crate) use write::OrcBound;
+pub use write::OrcFileMetaData;
+pub use write::OrcWriter;
+pub use write::OrcWriterProperties;
+
+mod read;
+pub use read::OrcBatchReader;
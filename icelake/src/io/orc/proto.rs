@@ -0,0 +1,251 @@
+//! proto module defines the protobuf messages of orc file tails and stripe
+//! footers used by icelake.
+//!
+//! Messages follow `orc_proto.proto` of the orc specification, fields not
+//! used by icelake are omitted. Enums are kept as raw `i32` values, see
+//! [`type_kind`], [`stream_kind`] and friends.
+//!
+//! Reference: <https://orc.apache.org/specification/ORCv1/>
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct IntegerStatistics {
+    #[prost(sint64, optional, tag = "1")]
+    pub minimum: Option<i64>,
+    #[prost(sint64, optional, tag = "2")]
+    pub maximum: Option<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct DoubleStatistics {
+    #[prost(double, optional, tag = "1")]
+    pub minimum: Option<f64>,
+    #[prost(double, optional, tag = "2")]
+    pub maximum: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct StringStatistics {
+    #[prost(string, optional, tag = "1")]
+    pub minimum: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub maximum: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct BucketStatistics {
+    #[prost(uint64, repeated, tag = "1")]
+    pub count: Vec<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct DecimalStatistics {
+    #[prost(string, optional, tag = "1")]
+    pub minimum: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub maximum: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct DateStatistics {
+    #[prost(sint32, optional, tag = "1")]
+    pub minimum: Option<i32>,
+    #[prost(sint32, optional, tag = "2")]
+    pub maximum: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct TimestampStatistics {
+    /// Min and max in milliseconds of the writer timezone.
+    #[prost(sint64, optional, tag = "1")]
+    pub minimum: Option<i64>,
+    #[prost(sint64, optional, tag = "2")]
+    pub maximum: Option<i64>,
+    /// Min and max in milliseconds of UTC.
+    #[prost(sint64, optional, tag = "3")]
+    pub minimum_utc: Option<i64>,
+    #[prost(sint64, optional, tag = "4")]
+    pub maximum_utc: Option<i64>,
+    /// Nanoseconds of the last millisecond plus one.
+    #[prost(int32, optional, tag = "5")]
+    pub minimum_nanos: Option<i32>,
+    #[prost(int32, optional, tag = "6")]
+    pub maximum_nanos: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ColumnStatistics {
+    /// Number of non null values.
+    #[prost(uint64, optional, tag = "1")]
+    pub number_of_values: Option<u64>,
+    #[prost(message, optional, tag = "2")]
+    pub int_statistics: Option<IntegerStatistics>,
+    #[prost(message, optional, tag = "3")]
+    pub double_statistics: Option<DoubleStatistics>,
+    #[prost(message, optional, tag = "4")]
+    pub string_statistics: Option<StringStatistics>,
+    #[prost(message, optional, tag = "5")]
+    pub bucket_statistics: Option<BucketStatistics>,
+    #[prost(message, optional, tag = "6")]
+    pub decimal_statistics: Option<DecimalStatistics>,
+    #[prost(message, optional, tag = "7")]
+    pub date_statistics: Option<DateStatistics>,
+    #[prost(message, optional, tag = "9")]
+    pub timestamp_statistics: Option<TimestampStatistics>,
+    #[prost(bool, optional, tag = "10")]
+    pub has_null: Option<bool>,
+    #[prost(uint64, optional, tag = "11")]
+    pub bytes_on_disk: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Stream {
+    #[prost(int32, optional, tag = "1")]
+    pub kind: Option<i32>,
+    #[prost(uint32, optional, tag = "2")]
+    pub column: Option<u32>,
+    #[prost(uint64, optional, tag = "3")]
+    pub length: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ColumnEncoding {
+    #[prost(int32, optional, tag = "1")]
+    pub kind: Option<i32>,
+    #[prost(uint32, optional, tag = "2")]
+    pub dictionary_size: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct StripeFooter {
+    #[prost(message, repeated, tag = "1")]
+    pub streams: Vec<Stream>,
+    #[prost(message, repeated, tag = "2")]
+    pub columns: Vec<ColumnEncoding>,
+    #[prost(string, optional, tag = "3")]
+    pub writer_timezone: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct StringPair {
+    #[prost(string, optional, tag = "1")]
+    pub key: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub value: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Type {
+    #[prost(int32, optional, tag = "1")]
+    pub kind: Option<i32>,
+    #[prost(uint32, repeated, tag = "2")]
+    pub subtypes: Vec<u32>,
+    #[prost(string, repeated, tag = "3")]
+    pub field_names: Vec<String>,
+    #[prost(uint32, optional, tag = "4")]
+    pub maximum_length: Option<u32>,
+    #[prost(uint32, optional, tag = "5")]
+    pub precision: Option<u32>,
+    #[prost(uint32, optional, tag = "6")]
+    pub scale: Option<u32>,
+    #[prost(message, repeated, tag = "7")]
+    pub attributes: Vec<StringPair>,
+}
+
+impl Type {
+    /// Returns the value of attribute `key`.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|v| v.key.as_deref() == Some(key))
+            .and_then(|v| v.value.as_deref())
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct StripeInformation {
+    #[prost(uint64, optional, tag = "1")]
+    pub offset: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub index_length: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub data_length: Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub footer_length: Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub number_of_rows: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Footer {
+    #[prost(uint64, optional, tag = "1")]
+    pub header_length: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub content_length: Option<u64>,
+    #[prost(message, repeated, tag = "3")]
+    pub stripes: Vec<StripeInformation>,
+    #[prost(message, repeated, tag = "4")]
+    pub types: Vec<Type>,
+    #[prost(uint64, optional, tag = "6")]
+    pub number_of_rows: Option<u64>,
+    #[prost(message, repeated, tag = "7")]
+    pub statistics: Vec<ColumnStatistics>,
+    #[prost(uint32, optional, tag = "8")]
+    pub row_index_stride: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct PostScript {
+    #[prost(uint64, optional, tag = "1")]
+    pub footer_length: Option<u64>,
+    #[prost(int32, optional, tag = "2")]
+    pub compression: Option<i32>,
+    #[prost(uint64, optional, tag = "3")]
+    pub compression_block_size: Option<u64>,
+    #[prost(uint32, repeated, tag = "4")]
+    pub version: Vec<u32>,
+    #[prost(uint64, optional, tag = "5")]
+    pub metadata_length: Option<u64>,
+    #[prost(uint32, optional, tag = "6")]
+    pub writer_version: Option<u32>,
+    #[prost(string, optional, tag = "8000")]
+    pub magic: Option<String>,
+}
+
+/// Values of `Type.kind`.
+pub(crate) mod type_kind {
+    pub const BOOLEAN: i32 = 0;
+    pub const BYTE: i32 = 1;
+    pub const SHORT: i32 = 2;
+    pub const INT: i32 = 3;
+    pub const LONG: i32 = 4;
+    pub const FLOAT: i32 = 5;
+    pub const DOUBLE: i32 = 6;
+    pub const STRING: i32 = 7;
+    pub const BINARY: i32 = 8;
+    pub const TIMESTAMP: i32 = 9;
+    pub const LIST: i32 = 10;
+    pub const MAP: i32 = 11;
+    pub const STRUCT: i32 = 12;
+    pub const DECIMAL: i32 = 14;
+    pub const DATE: i32 = 15;
+    pub const VARCHAR: i32 = 16;
+    pub const CHAR: i32 = 17;
+    pub const TIMESTAMP_INSTANT: i32 = 18;
+}
+
+/// Values of `Stream.kind`.
+pub(crate) mod stream_kind {
+    pub const PRESENT: i32 = 0;
+    pub const DATA: i32 = 1;
+    pub const LENGTH: i32 = 2;
+    pub const DICTIONARY_DATA: i32 = 3;
+    pub const SECONDARY: i32 = 5;
+}
+
+/// Values of `ColumnEncoding.kind`.
+pub(crate) mod encoding_kind {
+    pub const DIRECT: i32 = 0;
+    pub const DICTIONARY: i32 = 1;
+    pub const DIRECT_V2: i32 = 2;
+    pub const DICTIONARY_V2: i32 = 3;
+}
@@ -0,0 +1,797 @@
+//! read module reads orc files into arrow record batches.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::{
+    make_array, new_empty_array, Array, ArrayData, ArrayRef, BinaryArray, BooleanArray,
+    Date32Array, Decimal128Array, FixedSizeBinaryArray, Float32Array, Float64Array,
+    GenericListArray, Int32Array, Int64Array, LargeBinaryArray, LargeStringArray, MapArray,
+    OffsetSizeTrait, StringArray, StructArray, UInt32Array,
+};
+use arrow::buffer::{Buffer, NullBuffer, OffsetBuffer};
+use arrow::compute::take;
+use arrow::datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use bytes::Bytes;
+use prost::Message;
+
+use super::compression::OrcCompression;
+use super::encoding::{decode_booleans, decode_bytes, decode_ints, ByteReader, RleVersion};
+use super::proto::{self, encoding_kind, stream_kind, type_kind};
+use super::write::{time_unit_scale, MAGIC, TIMESTAMP_BASE_SECONDS};
+use crate::io::parquet::ParquetProjection;
+use crate::types::{Any, Field, List, Map, Primitive, Schema, Struct};
+use crate::{Error, ErrorKind, Result};
+
+/// Max number of rows of record batches produced by the reader.
+const BATCH_SIZE: usize = 1024;
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Invalid orc file: {}", message.into()),
+    )
+}
+
+/// OrcBatchReader decodes an orc file into record batches stripe by stripe.
+///
+/// Types of columns are converted to iceberg types by attributes written by
+/// iceberg writers, then to arrow types the same as reading parquet files.
+/// Files written without field ids are projected by names.
+pub struct OrcBatchReader {
+    data: Bytes,
+    compression: OrcCompression,
+    block_size: usize,
+    footer: proto::Footer,
+    schema: SchemaRef,
+    /// Orc columns of top level fields of `schema`.
+    columns: Vec<usize>,
+
+    next_stripe: usize,
+    current: Option<(RecordBatch, usize)>,
+}
+
+impl OrcBatchReader {
+    /// Create a reader of the whole file in `data`, only fields in
+    /// `projection` are decoded if it's given.
+    pub fn try_new(data: Bytes, projection: Option<&ParquetProjection>) -> Result<Self> {
+        let ps_len = *data.last().ok_or_else(|| invalid("empty file"))? as usize;
+        let ps_start = data
+            .len()
+            .checked_sub(ps_len + 1)
+            .ok_or_else(|| invalid("truncated postscript"))?;
+        let postscript = proto::PostScript::decode(&data[ps_start..data.len() - 1])?;
+        if postscript.magic.as_deref().map(|v| v.as_bytes()) != Some(MAGIC) {
+            return Err(invalid("magic mismatch"));
+        }
+        let compression = OrcCompression::from_kind(postscript.compression.unwrap_or_default())?;
+        let block_size = postscript.compression_block_size.unwrap_or(256 * 1024) as usize;
+        let footer_len = postscript.footer_length.unwrap_or_default() as usize;
+        let footer_start = ps_start
+            .checked_sub(footer_len)
+            .ok_or_else(|| invalid("truncated footer"))?;
+        let footer = compression.decompress(&data[footer_start..ps_start], block_size)?;
+        let footer = proto::Footer::decode(footer.as_slice())?;
+
+        let root = footer.types.first().ok_or_else(|| invalid("no types"))?;
+        if root.kind != Some(type_kind::STRUCT) {
+            return Err(invalid("root type is not a struct"));
+        }
+        // Field ids are only used if all columns are written with them.
+        let use_field_id = footer.types[1..]
+            .iter()
+            .all(|v| v.attribute("iceberg.id").is_some());
+        let file_schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: struct_fields(&footer.types, root, use_field_id)?,
+        };
+        let fields = match projection {
+            Some(projection) => project(&file_schema, projection, use_field_id),
+            None => file_schema.fields.clone(),
+        };
+        let columns = fields
+            .iter()
+            .map(|field| {
+                root.field_names
+                    .iter()
+                    .position(|v| v == &field.name)
+                    .map(|idx| root.subtypes[idx] as usize)
+                    .expect("projected fields come from the root struct")
+            })
+            .collect();
+        let schema = ArrowSchema::try_from(Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields,
+        })?;
+
+        Ok(Self {
+            data,
+            compression,
+            block_size,
+            footer,
+            schema: Arc::new(schema),
+            columns,
+            next_stripe: 0,
+            current: None,
+        })
+    }
+
+    /// Arrow schema of produced record batches.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn read_stripe(&self, stripe: &proto::StripeInformation) -> Result<RecordBatch> {
+        let offset = stripe.offset.unwrap_or_default() as usize;
+        let footer_offset = offset
+            + stripe.index_length.unwrap_or_default() as usize
+            + stripe.data_length.unwrap_or_default() as usize;
+        let footer_end = footer_offset + stripe.footer_length.unwrap_or_default() as usize;
+        let footer = self.data.get(footer_offset..footer_end).ok_or_else(|| {
+            invalid(format!(
+                "stripe footer {footer_offset}..{footer_end} out of range"
+            ))
+        })?;
+        let footer = proto::StripeFooter::decode(
+            self.compression
+                .decompress(footer, self.block_size)?
+                .as_slice(),
+        )?;
+
+        let mut streams = HashMap::new();
+        let mut pos = offset;
+        for stream in &footer.streams {
+            let len = stream.length.unwrap_or_default() as usize;
+            streams.insert(
+                (
+                    stream.column.unwrap_or_default() as usize,
+                    stream.kind.unwrap_or_default(),
+                ),
+                pos..pos + len,
+            );
+            pos += len;
+        }
+        let stripe_reader = StripeReader {
+            reader: self,
+            footer: &footer,
+            streams,
+        };
+
+        let num_rows = stripe.number_of_rows.unwrap_or_default() as usize;
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .zip(&self.columns)
+            .map(|(field, column)| stripe_reader.decode(*column, field.data_type(), num_rows))
+            .collect::<Result<_>>()?;
+        // Row count is kept if no column is projected.
+        Ok(RecordBatch::try_new_with_options(
+            self.schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?)
+    }
+}
+
+impl Iterator for OrcBatchReader {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((batch, offset)) = &mut self.current {
+                if *offset < batch.num_rows() {
+                    let len = BATCH_SIZE.min(batch.num_rows() - *offset);
+                    let result = batch.slice(*offset, len);
+                    *offset += len;
+                    return Some(Ok(result));
+                }
+                self.current = None;
+            }
+            let stripe = self.footer.stripes.get(self.next_stripe)?;
+            self.next_stripe += 1;
+            match self.read_stripe(stripe) {
+                Ok(batch) => self.current = Some((batch, 0)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Convert children of an orc struct type into iceberg fields.
+fn struct_fields(
+    types: &[proto::Type],
+    ty: &proto::Type,
+    use_field_id: bool,
+) -> Result<Vec<Field>> {
+    ty.subtypes
+        .iter()
+        .zip(&ty.field_names)
+        .map(|(column, name)| {
+            let (id, required, field_type) = iceberg_type(types, *column as usize, use_field_id)?;
+            Ok(Field {
+                id,
+                name: name.clone(),
+                required,
+                field_type,
+                comment: None,
+                initial_default: None,
+                write_default: None,
+            })
+        })
+        .collect()
+}
+
+/// Convert the orc type of `column` into the field id, required and the
+/// iceberg type.
+///
+/// Columns without field ids use the column id instead.
+fn iceberg_type(
+    types: &[proto::Type],
+    column: usize,
+    use_field_id: bool,
+) -> Result<(i32, bool, Any)> {
+    let ty = types
+        .get(column)
+        .ok_or_else(|| invalid(format!("type of column {column} not found")))?;
+    let id = match ty.attribute("iceberg.id") {
+        Some(id) if use_field_id => id.parse()?,
+        _ => column as i32,
+    };
+    let required = ty.attribute("iceberg.required") == Some("true");
+    let subtype = |idx: usize| {
+        let column = *ty
+            .subtypes
+            .get(idx)
+            .ok_or_else(|| invalid(format!("subtype of column {column} not found")))?;
+        iceberg_type(types, column as usize, use_field_id)
+    };
+
+    let primitive = match ty.kind.unwrap_or_default() {
+        type_kind::BOOLEAN => Primitive::Boolean,
+        type_kind::BYTE | type_kind::SHORT | type_kind::INT => Primitive::Int,
+        type_kind::LONG => match ty.attribute("iceberg.long-type") {
+            Some("TIME") => Primitive::Time,
+            _ => Primitive::Long,
+        },
+        type_kind::FLOAT => Primitive::Float,
+        type_kind::DOUBLE => Primitive::Double,
+        type_kind::STRING | type_kind::VARCHAR | type_kind::CHAR => Primitive::String,
+        type_kind::BINARY => match ty.attribute("iceberg.binary-type") {
+            Some("UUID") => Primitive::Uuid,
+            Some("FIXED") => Primitive::Fixed(
+                ty.attribute("iceberg.length")
+                    .ok_or_else(|| invalid("length of fixed type not found"))?
+                    .parse()?,
+            ),
+            _ => Primitive::Binary,
+        },
+        type_kind::TIMESTAMP => match ty.attribute("iceberg.timestamp-unit") {
+            Some("NANOS") => Primitive::TimestampNs,
+            _ => Primitive::Timestamp,
+        },
+        type_kind::TIMESTAMP_INSTANT => match ty.attribute("iceberg.timestamp-unit") {
+            Some("NANOS") => Primitive::TimestampzNs,
+            _ => Primitive::Timestampz,
+        },
+        type_kind::DATE => Primitive::Date,
+        type_kind::DECIMAL => Primitive::Decimal {
+            precision: ty.precision.unwrap_or(38) as u8,
+            scale: ty.scale.unwrap_or(10) as u8,
+        },
+        type_kind::STRUCT => {
+            return Ok((
+                id,
+                required,
+                Any::Struct(Arc::new(Struct::new(struct_fields(
+                    types,
+                    ty,
+                    use_field_id,
+                )?))),
+            ))
+        }
+        type_kind::LIST => {
+            let (element_id, element_required, element_type) = subtype(0)?;
+            return Ok((
+                id,
+                required,
+                Any::List(List {
+                    element_id,
+                    element_required,
+                    element_type: Box::new(element_type),
+                }),
+            ));
+        }
+        type_kind::MAP => {
+            let (key_id, _, key_type) = subtype(0)?;
+            let (value_id, value_required, value_type) = subtype(1)?;
+            return Ok((
+                id,
+                required,
+                Any::Map(Map {
+                    key_id,
+                    key_type: Box::new(key_type),
+                    value_id,
+                    value_required,
+                    value_type: Box::new(value_type),
+                }),
+            ));
+        }
+        kind => {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("Reading orc type kind {kind}"),
+            ))
+        }
+    };
+    Ok((id, required, Any::Primitive(primitive)))
+}
+
+/// Project fields of the file schema.
+///
+/// Top level fields are in the projected order, nested fields of structs
+/// are in the order of the file. Projected fields missing in the file are
+/// skipped.
+fn project(file_schema: &Schema, projection: &ParquetProjection, use_field_id: bool) -> Vec<Field> {
+    let paths: Vec<Vec<String>> = projection
+        .fields()
+        .iter()
+        .filter_map(|(id, names)| {
+            if use_field_id {
+                file_schema
+                    .field_path(*id)
+                    .map(|path| path.into_iter().map(|v| v.to_string()).collect())
+            } else {
+                Some(names.clone())
+            }
+        })
+        .collect();
+
+    let mut fields: Vec<Field> = vec![];
+    for path in &paths {
+        if fields.iter().any(|v| v.name == path[0]) {
+            continue;
+        }
+        let Some(field) = file_schema.fields.iter().find(|v| v.name == path[0]) else {
+            continue;
+        };
+        let sub_paths: Vec<&[String]> = paths
+            .iter()
+            .filter(|v| v[0] == path[0])
+            .map(|v| &v[1..])
+            .collect();
+        fields.push(prune_field(field, &sub_paths));
+    }
+    fields
+}
+
+/// Keep nested fields of `field` in `paths`, the whole field is kept if any
+/// path is empty.
+fn prune_field(field: &Field, paths: &[&[String]]) -> Field {
+    let Any::Struct(ty) = &field.field_type else {
+        return field.clone();
+    };
+    if paths.iter().any(|v| v.is_empty()) {
+        return field.clone();
+    }
+    let children = ty
+        .fields()
+        .iter()
+        .filter_map(|child| {
+            let sub_paths: Vec<&[String]> = paths
+                .iter()
+                .filter(|v| v[0] == child.name)
+                .map(|v| &v[1..])
+                .collect();
+            (!sub_paths.is_empty()).then(|| prune_field(child, &sub_paths))
+        })
+        .collect();
+    Field {
+        field_type: Any::Struct(Arc::new(Struct::new(children))),
+        ..field.clone()
+    }
+}
+
+/// Decodes columns of a stripe.
+struct StripeReader<'a> {
+    reader: &'a OrcBatchReader,
+    footer: &'a proto::StripeFooter,
+    /// Byte ranges of streams keyed by column and stream kind.
+    streams: HashMap<(usize, i32), std::ops::Range<usize>>,
+}
+
+impl StripeReader<'_> {
+    fn stream(&self, column: usize, kind: i32) -> Result<Option<Vec<u8>>> {
+        let Some(range) = self.streams.get(&(column, kind)) else {
+            return Ok(None);
+        };
+        let data = self
+            .reader
+            .data
+            .get(range.clone())
+            .ok_or_else(|| invalid(format!("stream of column {column} out of range")))?;
+        Ok(Some(
+            self.reader
+                .compression
+                .decompress(data, self.reader.block_size)?,
+        ))
+    }
+
+    fn required_stream(&self, column: usize, kind: i32) -> Result<Vec<u8>> {
+        self.stream(column, kind)?
+            .ok_or_else(|| invalid(format!("stream {kind} of column {column} not found")))
+    }
+
+    fn encoding(&self, column: usize) -> i32 {
+        self.footer
+            .columns
+            .get(column)
+            .and_then(|v| v.kind)
+            .unwrap_or(encoding_kind::DIRECT)
+    }
+
+    fn rle_version(&self, column: usize) -> RleVersion {
+        match self.encoding(column) {
+            encoding_kind::DIRECT_V2 | encoding_kind::DICTIONARY_V2 => RleVersion::V2,
+            _ => RleVersion::V1,
+        }
+    }
+
+    fn ints(&self, column: usize, kind: i32, n: usize, signed: bool) -> Result<Vec<i64>> {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        let data = self.required_stream(column, kind)?;
+        decode_ints(&data, n, signed, self.rle_version(column))
+    }
+
+    fn orc_type(&self, column: usize) -> &proto::Type {
+        &self.reader.footer.types[column]
+    }
+
+    /// Decode `n` rows of `column` into an array of `data_type`.
+    fn decode(&self, column: usize, data_type: &DataType, n: usize) -> Result<ArrayRef> {
+        let present = self
+            .stream(column, stream_kind::PRESENT)?
+            .map(|v| decode_booleans(&v, n))
+            .transpose()?;
+        let count = present
+            .as_ref()
+            .map_or(n, |v| v.iter().filter(|v| **v).count());
+        let values = self.decode_values(column, data_type, count)?;
+        match present {
+            Some(present) if count < n => {
+                // Nulls are not stored, expand values by taking null indices
+                // at null rows.
+                let mut idx = 0;
+                let indices: UInt32Array = present
+                    .iter()
+                    .map(|valid| {
+                        valid.then(|| {
+                            idx += 1;
+                            idx - 1
+                        })
+                    })
+                    .collect();
+                let values = take(&values, &indices, None)?;
+                // Take of some types ignores null indices, so nulls are set
+                // explicitly.
+                Ok(make_array(
+                    values
+                        .into_data()
+                        .into_builder()
+                        .nulls(Some(NullBuffer::from(present)))
+                        .build()?,
+                ))
+            }
+            _ => Ok(values),
+        }
+    }
+
+    /// Decode `n` non null values of `column`.
+    fn decode_values(&self, column: usize, data_type: &DataType, n: usize) -> Result<ArrayRef> {
+        if n == 0 {
+            return Ok(new_empty_array(data_type));
+        }
+        let ty = self.orc_type(column);
+        let kind = ty.kind.unwrap_or_default();
+        let array: ArrayRef = match data_type {
+            DataType::Boolean => Arc::new(BooleanArray::from(decode_booleans(
+                &self.required_stream(column, stream_kind::DATA)?,
+                n,
+            )?)),
+            DataType::Int32 if kind == type_kind::BYTE => {
+                let values = decode_bytes(&self.required_stream(column, stream_kind::DATA)?, n)?;
+                Arc::new(Int32Array::from_iter_values(
+                    values.into_iter().map(|v| v as i8 as i32),
+                ))
+            }
+            DataType::Int32 => Arc::new(Int32Array::from_iter_values(
+                self.ints(column, stream_kind::DATA, n, true)?
+                    .into_iter()
+                    .map(|v| v as i32),
+            )),
+            DataType::Date32 => Arc::new(Date32Array::from_iter_values(
+                self.ints(column, stream_kind::DATA, n, true)?
+                    .into_iter()
+                    .map(|v| v as i32),
+            )),
+            DataType::Int64 | DataType::Time64(_) => with_data_type(
+                Int64Array::from(self.ints(column, stream_kind::DATA, n, true)?),
+                data_type,
+            )?,
+            DataType::Float32 => {
+                let data = self.fixed_width_stream(column, n, 4)?;
+                Arc::new(Float32Array::from_iter_values(
+                    data.chunks_exact(4)
+                        .map(|v| f32::from_le_bytes(v.try_into().unwrap())),
+                ))
+            }
+            DataType::Float64 => {
+                let data = self.fixed_width_stream(column, n, 8)?;
+                Arc::new(Float64Array::from_iter_values(
+                    data.chunks_exact(8)
+                        .map(|v| f64::from_le_bytes(v.try_into().unwrap())),
+                ))
+            }
+            DataType::Decimal128(_, scale) => {
+                let data = self.required_stream(column, stream_kind::DATA)?;
+                let mut r = ByteReader::new(&data);
+                let scales = self.ints(column, stream_kind::SECONDARY, n, true)?;
+                let mut values = Vec::with_capacity(n);
+                for value_scale in scales {
+                    let value = r.read_signed_varint()?;
+                    // Values may be stored in scales other than the type's.
+                    let value = match *scale as i64 - value_scale {
+                        0 => Some(value),
+                        diff if diff > 0 => 10_i128
+                            .checked_pow(diff as u32)
+                            .and_then(|v| value.checked_mul(v)),
+                        diff => 10_i128.checked_pow(-diff as u32).map(|v| value / v),
+                    };
+                    values.push(value.ok_or_else(|| invalid("decimal overflow"))?);
+                }
+                Arc::new(Decimal128Array::from(values).with_data_type(data_type.clone()))
+            }
+            DataType::Timestamp(unit, _) => {
+                if kind == type_kind::TIMESTAMP {
+                    let timezone = self.footer.writer_timezone.as_deref().unwrap_or_default();
+                    if !matches!(timezone, "" | "UTC" | "GMT" | "Etc/UTC" | "Z") {
+                        return Err(Error::new(
+                            ErrorKind::IcebergFeatureUnsupported,
+                            format!("Reading orc timestamps written in timezone {timezone}"),
+                        ));
+                    }
+                }
+                let seconds = self.ints(column, stream_kind::DATA, n, true)?;
+                let nanos = self.ints(column, stream_kind::SECONDARY, n, false)?;
+                let (units_per_second, nanos_per_unit) = time_unit_scale(unit);
+                let values = seconds.into_iter().zip(nanos).map(|(second, nano)| {
+                    let mut second = second + TIMESTAMP_BASE_SECONDS;
+                    let nano = decode_nanos(nano);
+                    if second < 0 && nano > 999_999 {
+                        second -= 1;
+                    }
+                    second * units_per_second + nano / nanos_per_unit
+                });
+                with_data_type(Int64Array::from_iter_values(values), data_type)?
+            }
+            DataType::Utf8 => {
+                let (offsets, values) = self.decode_bytes_column(column, n)?;
+                Arc::new(StringArray::try_new(
+                    OffsetBuffer::new(offsets.into()),
+                    values,
+                    None,
+                )?)
+            }
+            DataType::LargeUtf8 => {
+                let (offsets, values) = self.decode_bytes_column(column, n)?;
+                Arc::new(LargeStringArray::try_new(
+                    OffsetBuffer::new(to_large_offsets(offsets).into()),
+                    values,
+                    None,
+                )?)
+            }
+            DataType::Binary => {
+                let (offsets, values) = self.decode_bytes_column(column, n)?;
+                Arc::new(BinaryArray::try_new(
+                    OffsetBuffer::new(offsets.into()),
+                    values,
+                    None,
+                )?)
+            }
+            DataType::LargeBinary => {
+                let (offsets, values) = self.decode_bytes_column(column, n)?;
+                Arc::new(LargeBinaryArray::try_new(
+                    OffsetBuffer::new(to_large_offsets(offsets).into()),
+                    values,
+                    None,
+                )?)
+            }
+            DataType::FixedSizeBinary(size) => {
+                let (offsets, values) = self.decode_bytes_column(column, n)?;
+                if offsets.windows(2).any(|v| v[1] - v[0] != *size) {
+                    return Err(invalid(format!(
+                        "length of values of column {column} is not {size}"
+                    )));
+                }
+                Arc::new(FixedSizeBinaryArray::try_new(*size, values, None)?)
+            }
+            DataType::Struct(fields) => {
+                let children = fields
+                    .iter()
+                    .map(|field| {
+                        let idx = ty
+                            .field_names
+                            .iter()
+                            .position(|v| v == field.name())
+                            .ok_or_else(|| invalid(format!("field {} not found", field.name())))?;
+                        self.decode(ty.subtypes[idx] as usize, field.data_type(), n)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if children.is_empty() {
+                    // Struct without fields keeps the row count by nulls.
+                    Arc::new(StructArray::from(
+                        ArrayData::builder(data_type.clone()).len(n).build()?,
+                    ))
+                } else {
+                    Arc::new(StructArray::try_new(fields.clone(), children, None)?)
+                }
+            }
+            DataType::List(field) => Arc::new(self.decode_list::<i32>(column, field, n)?),
+            DataType::LargeList(field) => Arc::new(self.decode_list::<i64>(column, field, n)?),
+            DataType::Map(field, _) => {
+                let DataType::Struct(fields) = field.data_type() else {
+                    return Err(invalid(format!("invalid map type {data_type}")));
+                };
+                let offsets = self.decode_offsets(column, n)?;
+                let len = *offsets.last().unwrap() as usize;
+                let children = fields
+                    .iter()
+                    .zip(&ty.subtypes)
+                    .map(|(field, column)| self.decode(*column as usize, field.data_type(), len))
+                    .collect::<Result<Vec<_>>>()?;
+                let entries = StructArray::try_new(fields.clone(), children, None)?;
+                Arc::new(MapArray::from(
+                    ArrayData::builder(data_type.clone())
+                        .len(n)
+                        .add_buffer(Buffer::from_vec(offsets))
+                        .add_child_data(entries.into_data())
+                        .build()?,
+                ))
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::IcebergFeatureUnsupported,
+                    format!("Reading orc column {column} as {data_type}"),
+                ))
+            }
+        };
+        Ok(array)
+    }
+
+    fn fixed_width_stream(&self, column: usize, n: usize, width: usize) -> Result<Vec<u8>> {
+        let data = self.required_stream(column, stream_kind::DATA)?;
+        if data.len() < n * width {
+            return Err(invalid(format!("data of column {column} is truncated")));
+        }
+        Ok(data)
+    }
+
+    /// Offsets of `n` lists or maps from their lengths.
+    fn decode_offsets(&self, column: usize, n: usize) -> Result<Vec<i32>> {
+        let lengths = self.ints(column, stream_kind::LENGTH, n, false)?;
+        let mut offsets = Vec::with_capacity(n + 1);
+        offsets.push(0_i32);
+        for len in lengths {
+            let last = *offsets.last().unwrap();
+            let next = i32::try_from(len)
+                .ok()
+                .and_then(|v| last.checked_add(v))
+                .ok_or_else(|| invalid("offset overflow"))?;
+            offsets.push(next);
+        }
+        Ok(offsets)
+    }
+
+    fn decode_list<O: OffsetSizeTrait>(
+        &self,
+        column: usize,
+        field: &Arc<ArrowField>,
+        n: usize,
+    ) -> Result<GenericListArray<O>> {
+        let offsets = self.decode_offsets(column, n)?;
+        let len = *offsets.last().unwrap() as usize;
+        let child = self.orc_type(column).subtypes.first().copied();
+        let values = self.decode(
+            child.ok_or_else(|| invalid(format!("subtype of column {column} not found")))? as usize,
+            field.data_type(),
+            len,
+        )?;
+        let offsets = offsets.into_iter().map(|v| O::usize_as(v as usize));
+        Ok(GenericListArray::try_new(
+            field.clone(),
+            OffsetBuffer::new(offsets.collect::<Vec<_>>().into()),
+            values,
+            None,
+        )?)
+    }
+
+    /// Decode `n` strings or binaries into offsets and values.
+    fn decode_bytes_column(&self, column: usize, n: usize) -> Result<(Vec<i32>, Buffer)> {
+        match self.encoding(column) {
+            encoding_kind::DICTIONARY | encoding_kind::DICTIONARY_V2 => {
+                let dictionary_size = self
+                    .footer
+                    .columns
+                    .get(column)
+                    .and_then(|v| v.dictionary_size)
+                    .unwrap_or_default() as usize;
+                let dictionary = self
+                    .stream(column, stream_kind::DICTIONARY_DATA)?
+                    .unwrap_or_default();
+                let lengths = self.ints(column, stream_kind::LENGTH, dictionary_size, false)?;
+                let mut entries = Vec::with_capacity(dictionary_size);
+                let mut r = ByteReader::new(&dictionary);
+                for len in lengths {
+                    entries.push(r.read_bytes(len as usize)?);
+                }
+                let indexes = self.ints(column, stream_kind::DATA, n, false)?;
+                let mut offsets = Vec::with_capacity(n + 1);
+                offsets.push(0);
+                let mut values = vec![];
+                for idx in indexes {
+                    let entry = entries
+                        .get(idx as usize)
+                        .ok_or_else(|| invalid("dictionary index out of range"))?;
+                    values.extend_from_slice(entry);
+                    offsets.push(values.len() as i32);
+                }
+                Ok((offsets, Buffer::from_vec(values)))
+            }
+            _ => {
+                let lengths = self.ints(column, stream_kind::LENGTH, n, false)?;
+                let data = self.required_stream(column, stream_kind::DATA)?;
+                let mut offsets = Vec::with_capacity(n + 1);
+                offsets.push(0_i32);
+                for len in lengths {
+                    offsets.push(*offsets.last().unwrap() + len as i32);
+                }
+                let total = *offsets.last().unwrap() as usize;
+                let values = data
+                    .get(..total)
+                    .ok_or_else(|| invalid(format!("data of column {column} is truncated")))?;
+                Ok((offsets, Buffer::from(values)))
+            }
+        }
+    }
+}
+
+/// Reinterpret `array` as `data_type` of the same physical layout.
+fn with_data_type(array: impl Array, data_type: &DataType) -> Result<ArrayRef> {
+    Ok(make_array(
+        array
+            .into_data()
+            .into_builder()
+            .data_type(data_type.clone())
+            .build()?,
+    ))
+}
+
+fn to_large_offsets(offsets: Vec<i32>) -> Vec<i64> {
+    offsets.into_iter().map(|v| v as i64).collect()
+}
+
+/// Decode nanoseconds with trailing zeros removed.
+fn decode_nanos(v: i64) -> i64 {
+    let zeros = v & 0x07;
+    let nanos = v >> 3;
+    if zeros == 0 {
+        nanos
+    } else {
+        nanos * 10_i64.pow(zeros as u32 + 1)
+    }
+}
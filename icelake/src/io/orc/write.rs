@@ -0,0 +1,1297 @@
+//! write module writes arrow record batches into orc files.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, BooleanArray, FixedSizeBinaryArray, GenericListArray,
+    OffsetSizeTrait, StructArray,
+};
+use arrow::compute::{concat_batches, filter};
+use arrow::datatypes::{
+    DataType, Date32Type, Decimal128Type, Float32Type, Float64Type, Int32Type, Int64Type,
+    SchemaRef, Time64MicrosecondType, TimeUnit, TimestampMicrosecondType, TimestampMillisecondType,
+    TimestampNanosecondType, TimestampSecondType,
+};
+use arrow::record_batch::RecordBatch;
+use opendal::Writer;
+use prost::Message;
+
+use super::compression::OrcCompression;
+use super::encoding::{encode_booleans, encode_ints, write_signed_varint};
+use super::proto::{self, encoding_kind, stream_kind, type_kind};
+use crate::types::{Any, Field, Primitive, Schema};
+use crate::{Error, ErrorKind, Result};
+
+/// Seconds of `2015-01-01T00:00:00Z`, the base of timestamps in orc.
+pub(crate) const TIMESTAMP_BASE_SECONDS: i64 = 1420070400;
+
+/// Magic bytes at the beginning and in the postscript of orc files.
+pub(crate) const MAGIC: &[u8] = b"ORC";
+
+/// OrcWriterProperties is the configuration of [`OrcWriter`].
+#[derive(Debug, Clone)]
+pub struct OrcWriterProperties {
+    compression: OrcCompression,
+    stripe_size_bytes: usize,
+    compression_block_size: usize,
+}
+
+impl Default for OrcWriterProperties {
+    fn default() -> Self {
+        Self {
+            compression: OrcCompression::Zlib,
+            stripe_size_bytes: 64 * 1024 * 1024,
+            compression_block_size: 256 * 1024,
+        }
+    }
+}
+
+impl OrcWriterProperties {
+    /// Set the compression codec of streams.
+    pub fn with_compression(mut self, compression: OrcCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the size of buffered rows in bytes to flush as a stripe.
+    pub fn with_stripe_size_bytes(mut self, stripe_size_bytes: usize) -> Self {
+        self.stripe_size_bytes = stripe_size_bytes;
+        self
+    }
+}
+
+/// A column of the orc file, columns are numbered in pre-order of the type
+/// tree, starting from the root struct of rows.
+#[derive(Debug, Clone)]
+struct OrcColumn {
+    data_type: DataType,
+    kind: i32,
+    subtypes: Vec<u32>,
+    field_names: Vec<String>,
+}
+
+/// Min or max value of a column, in the unit of its arrow type.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub(crate) enum OrcBound {
+    Boolean(bool),
+    Int(i64),
+    Double(f64),
+    Decimal(i128),
+    Bytes(Vec<u8>),
+}
+
+/// Statistics of a column in the whole file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OrcColumnStatistics {
+    /// Number of non null values.
+    pub num_values: u64,
+    /// Number of null values, values under null parents are not counted.
+    pub num_nulls: u64,
+    /// Size of the column's streams after compression.
+    pub bytes_on_disk: u64,
+    /// Min and max of non null values, NaN is excluded.
+    pub bounds: Option<(OrcBound, OrcBound)>,
+    /// Number of true values of boolean columns.
+    true_count: u64,
+}
+
+impl OrcColumnStatistics {
+    fn update(&mut self, value: OrcBound) {
+        match &mut self.bounds {
+            Some((min, max)) => {
+                if value < *min {
+                    *min = value;
+                } else if value > *max {
+                    *max = value;
+                }
+            }
+            None => self.bounds = Some((value.clone(), value)),
+        }
+    }
+}
+
+/// Metadata of a written orc file.
+#[derive(Debug, Clone)]
+pub struct OrcFileMetaData {
+    /// Number of rows.
+    pub num_rows: u64,
+    /// Offsets of stripes.
+    pub stripe_offsets: Vec<i64>,
+    columns: Vec<OrcColumn>,
+    statistics: Vec<OrcColumnStatistics>,
+}
+
+impl OrcFileMetaData {
+    /// Statistics of primitive columns in pre-order, which is also the
+    /// order of leaves of the iceberg schema.
+    pub(crate) fn leaf_statistics(&self) -> impl Iterator<Item = &OrcColumnStatistics> {
+        self.columns
+            .iter()
+            .zip(&self.statistics)
+            .filter(|(column, _)| {
+                !matches!(
+                    column.kind,
+                    type_kind::STRUCT | type_kind::LIST | type_kind::MAP
+                )
+            })
+            .map(|(_, statistics)| statistics)
+    }
+}
+
+/// OrcWriter is used to write arrow data into orc file on storage.
+///
+/// Rows are buffered in memory and flushed as a stripe once their size
+/// reaches the stripe size. All columns are written in `DIRECT` encoding.
+pub struct OrcWriter {
+    writer: Writer,
+    arrow_schema: SchemaRef,
+    props: OrcWriterProperties,
+    columns: Vec<OrcColumn>,
+
+    buffer: Vec<RecordBatch>,
+    buffer_size: usize,
+
+    written_size: u64,
+    num_rows: u64,
+    stripes: Vec<proto::StripeInformation>,
+    statistics: Vec<OrcColumnStatistics>,
+}
+
+impl OrcWriter {
+    /// Create a new writer, returns error if the schema contains types
+    /// not supported by orc.
+    pub fn try_new(
+        writer: Writer,
+        arrow_schema: SchemaRef,
+        props: OrcWriterProperties,
+    ) -> Result<Self> {
+        let mut columns = vec![];
+        build_columns(
+            &DataType::Struct(arrow_schema.fields().clone()),
+            &mut columns,
+        )?;
+        let statistics = vec![OrcColumnStatistics::default(); columns.len()];
+        Ok(Self {
+            writer,
+            arrow_schema,
+            props,
+            columns,
+            buffer: vec![],
+            buffer_size: 0,
+            written_size: 0,
+            num_rows: 0,
+            stripes: vec![],
+            statistics,
+        })
+    }
+
+    /// Write data into the file.
+    ///
+    /// Note: It will not guarantee to take effect imediately.
+    pub async fn write(&mut self, data: &RecordBatch) -> Result<()> {
+        self.buffer_size += data.get_array_memory_size();
+        self.buffer.push(data.clone());
+        if self.buffer_size >= self.props.stripe_size_bytes {
+            self.flush_stripe().await?;
+        }
+        Ok(())
+    }
+
+    /// Return the written size.
+    pub fn get_written_size(&self) -> u64 {
+        self.written_size
+    }
+
+    /// Return the in memory size of buffered rows, which are not written
+    /// yet.
+    pub fn get_buffered_size(&self) -> u64 {
+        self.buffer_size as u64
+    }
+
+    /// Write footer, flush rest data and close file.
+    ///
+    /// `schema` is the iceberg schema of rows, whose field ids and types
+    /// are recorded as attributes of orc types.
+    ///
+    /// # Note
+    ///
+    /// This function must be called before complete the write process.
+    pub async fn close(mut self, schema: Option<&Schema>) -> Result<(OrcFileMetaData, u64)> {
+        self.flush_stripe().await?;
+
+        let types = self.build_types(schema)?;
+        let statistics = self
+            .columns
+            .iter()
+            .zip(&self.statistics)
+            .map(|(column, statistics)| to_proto_statistics(column, statistics))
+            .collect();
+        let content_length = self.written_size.max(MAGIC.len() as u64);
+        let footer = proto::Footer {
+            header_length: Some(MAGIC.len() as u64),
+            content_length: Some(content_length),
+            stripes: self.stripes.clone(),
+            types,
+            number_of_rows: Some(self.num_rows),
+            statistics,
+            row_index_stride: Some(0),
+        };
+        let footer = self.compress(&footer.encode_to_vec())?;
+        let postscript = proto::PostScript {
+            footer_length: Some(footer.len() as u64),
+            compression: Some(self.props.compression.kind()),
+            compression_block_size: Some(self.props.compression_block_size as u64),
+            version: vec![0, 12],
+            metadata_length: Some(0),
+            writer_version: Some(6),
+            magic: Some(String::from_utf8_lossy(MAGIC).to_string()),
+        }
+        .encode_to_vec();
+
+        let mut tail = footer;
+        tail.extend_from_slice(&postscript);
+        tail.push(postscript.len() as u8);
+        self.write_bytes(tail).await?;
+        self.writer.close().await?;
+
+        let meta = OrcFileMetaData {
+            num_rows: self.num_rows,
+            stripe_offsets: self
+                .stripes
+                .iter()
+                .map(|v| v.offset.unwrap_or_default() as i64)
+                .collect(),
+            columns: self.columns,
+            statistics: self.statistics,
+        };
+        Ok((meta, self.written_size))
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.props
+            .compression
+            .compress(data, self.props.compression_block_size)
+    }
+
+    /// Write bytes after the magic header.
+    async fn write_bytes(&mut self, mut bytes: Vec<u8>) -> Result<()> {
+        if self.written_size == 0 {
+            bytes.splice(0..0, MAGIC.iter().copied());
+        }
+        self.written_size += bytes.len() as u64;
+        self.writer.write(bytes).await?;
+        Ok(())
+    }
+
+    async fn flush_stripe(&mut self) -> Result<()> {
+        let batches = std::mem::take(&mut self.buffer);
+        self.buffer_size = 0;
+        let batch = concat_batches(&self.arrow_schema, &batches)?;
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+
+        let mut streams = vec![];
+        let root = Arc::new(StructArray::from(batch.clone())) as ArrayRef;
+        encode_column(&self.columns, &mut self.statistics, 0, &root, &mut streams)?;
+
+        let mut footer = proto::StripeFooter {
+            streams: vec![],
+            columns: vec![
+                proto::ColumnEncoding {
+                    kind: Some(encoding_kind::DIRECT),
+                    dictionary_size: None,
+                };
+                self.columns.len()
+            ],
+            writer_timezone: Some("UTC".to_string()),
+        };
+        let mut data = vec![];
+        for (column, kind, bytes) in streams {
+            let bytes = self.compress(&bytes)?;
+            self.statistics[column].bytes_on_disk += bytes.len() as u64;
+            footer.streams.push(proto::Stream {
+                kind: Some(kind),
+                column: Some(column as u32),
+                length: Some(bytes.len() as u64),
+            });
+            data.extend(bytes);
+        }
+        let footer = self.compress(&footer.encode_to_vec())?;
+
+        self.stripes.push(proto::StripeInformation {
+            offset: Some(self.written_size.max(MAGIC.len() as u64)),
+            index_length: Some(0),
+            data_length: Some(data.len() as u64),
+            footer_length: Some(footer.len() as u64),
+            number_of_rows: Some(batch.num_rows() as u64),
+        });
+        self.num_rows += batch.num_rows() as u64;
+        data.extend(footer);
+        self.write_bytes(data).await
+    }
+
+    /// Build orc types with attributes from the iceberg schema.
+    fn build_types(&self, schema: Option<&Schema>) -> Result<Vec<proto::Type>> {
+        let mut iceberg_columns = vec![None];
+        if let Some(schema) = schema {
+            collect_iceberg_columns(&schema.fields, &mut iceberg_columns);
+            if iceberg_columns.len() != self.columns.len() {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Iceberg schema doesn't match the arrow schema of the orc writer",
+                ));
+            }
+        }
+
+        let types = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let mut ty = proto::Type {
+                    kind: Some(column.kind),
+                    subtypes: column.subtypes.clone(),
+                    field_names: column.field_names.clone(),
+                    ..Default::default()
+                };
+                if let DataType::Decimal128(precision, scale) = column.data_type {
+                    ty.precision = Some(precision as u32);
+                    ty.scale = Some(scale as u32);
+                }
+                if let Some((field_id, required, field_type)) =
+                    iceberg_columns.get(i).cloned().flatten()
+                {
+                    let mut attribute = |key: &str, value: String| {
+                        ty.attributes.push(proto::StringPair {
+                            key: Some(key.to_string()),
+                            value: Some(value),
+                        })
+                    };
+                    attribute("iceberg.id", field_id.to_string());
+                    attribute("iceberg.required", required.to_string());
+                    match field_type {
+                        Some(Primitive::Time) => attribute("iceberg.long-type", "TIME".to_string()),
+                        Some(Primitive::Uuid) => {
+                            attribute("iceberg.binary-type", "UUID".to_string())
+                        }
+                        Some(Primitive::Fixed(length)) => {
+                            attribute("iceberg.binary-type", "FIXED".to_string());
+                            attribute("iceberg.length", length.to_string());
+                        }
+                        Some(Primitive::Timestampz) => ty.kind = Some(type_kind::TIMESTAMP_INSTANT),
+                        Some(Primitive::TimestampNs) => {
+                            attribute("iceberg.timestamp-unit", "NANOS".to_string())
+                        }
+                        Some(Primitive::TimestampzNs) => {
+                            attribute("iceberg.timestamp-unit", "NANOS".to_string());
+                            ty.kind = Some(type_kind::TIMESTAMP_INSTANT);
+                        }
+                        _ => {}
+                    }
+                }
+                ty
+            })
+            .collect();
+        Ok(types)
+    }
+}
+
+/// Field id, required and primitive type of an iceberg column.
+type IcebergColumn = Option<(i32, bool, Option<Primitive>)>;
+
+fn collect_iceberg_columns(fields: &[Field], columns: &mut Vec<IcebergColumn>) {
+    for field in fields {
+        collect_iceberg_column(field.id, field.required, &field.field_type, columns);
+    }
+}
+
+fn collect_iceberg_column(id: i32, required: bool, ty: &Any, columns: &mut Vec<IcebergColumn>) {
+    match ty {
+        Any::Primitive(v) => columns.push(Some((id, required, Some(*v)))),
+        Any::Struct(v) => {
+            columns.push(Some((id, required, None)));
+            collect_iceberg_columns(v.fields(), columns);
+        }
+        Any::List(v) => {
+            columns.push(Some((id, required, None)));
+            collect_iceberg_column(v.element_id, v.element_required, &v.element_type, columns);
+        }
+        Any::Map(v) => {
+            columns.push(Some((id, required, None)));
+            collect_iceberg_column(v.key_id, true, &v.key_type, columns);
+            collect_iceberg_column(v.value_id, v.value_required, &v.value_type, columns);
+        }
+    }
+}
+
+/// Build orc columns of `data_type` in pre-order, returns the id of the
+/// column.
+fn build_columns(data_type: &DataType, columns: &mut Vec<OrcColumn>) -> Result<usize> {
+    let id = columns.len();
+    let kind = match data_type {
+        DataType::Boolean => type_kind::BOOLEAN,
+        DataType::Int32 => type_kind::INT,
+        DataType::Int64 | DataType::Time64(TimeUnit::Microsecond) => type_kind::LONG,
+        DataType::Float32 => type_kind::FLOAT,
+        DataType::Float64 => type_kind::DOUBLE,
+        DataType::Decimal128(_, _) => type_kind::DECIMAL,
+        DataType::Date32 => type_kind::DATE,
+        DataType::Timestamp(_, None) => type_kind::TIMESTAMP,
+        DataType::Timestamp(_, Some(_)) => type_kind::TIMESTAMP_INSTANT,
+        DataType::Utf8 | DataType::LargeUtf8 => type_kind::STRING,
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+            type_kind::BINARY
+        }
+        DataType::Struct(_) => type_kind::STRUCT,
+        DataType::List(_) | DataType::LargeList(_) => type_kind::LIST,
+        DataType::Map(_, _) => type_kind::MAP,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("Writing {data_type} into orc files"),
+            ))
+        }
+    };
+    columns.push(OrcColumn {
+        data_type: data_type.clone(),
+        kind,
+        subtypes: vec![],
+        field_names: vec![],
+    });
+
+    let (subtypes, field_names) = match data_type {
+        DataType::Struct(fields) => {
+            let mut subtypes = vec![];
+            for field in fields {
+                subtypes.push(build_columns(field.data_type(), columns)? as u32);
+            }
+            (subtypes, fields.iter().map(|v| v.name().clone()).collect())
+        }
+        DataType::List(field) | DataType::LargeList(field) => (
+            vec![build_columns(field.data_type(), columns)? as u32],
+            vec![],
+        ),
+        DataType::Map(field, _) => {
+            let DataType::Struct(fields) = field.data_type() else {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid map type {data_type}"),
+                ));
+            };
+            let mut subtypes = vec![];
+            for field in fields {
+                subtypes.push(build_columns(field.data_type(), columns)? as u32);
+            }
+            (subtypes, vec![])
+        }
+        _ => (vec![], vec![]),
+    };
+    columns[id].subtypes = subtypes;
+    columns[id].field_names = field_names;
+    Ok(id)
+}
+
+/// Streams of a stripe as `(column, kind, uncompressed bytes)`.
+type Streams = Vec<(usize, i32, Vec<u8>)>;
+
+/// Encode `array` as column `id` and its descendants.
+fn encode_column(
+    columns: &[OrcColumn],
+    statistics: &mut [OrcColumnStatistics],
+    id: usize,
+    array: &ArrayRef,
+    streams: &mut Streams,
+) -> Result<()> {
+    let column = &columns[id];
+    let array = match array.nulls().filter(|v| v.null_count() > 0) {
+        Some(nulls) => {
+            let present: Vec<bool> = nulls.iter().collect();
+            let mut bytes = vec![];
+            encode_booleans(&present, &mut bytes);
+            streams.push((id, stream_kind::PRESENT, bytes));
+            statistics[id].num_nulls += nulls.null_count() as u64;
+            filter(array, &BooleanArray::new(nulls.inner().clone(), None))?
+        }
+        None => array.clone(),
+    };
+    let stats = &mut statistics[id];
+    stats.num_values += array.len() as u64;
+
+    let mut data = vec![];
+    match &column.data_type {
+        DataType::Boolean => {
+            let values: Vec<bool> = array.as_boolean().values().iter().collect();
+            for v in &values {
+                stats.true_count += *v as u64;
+                stats.update(OrcBound::Boolean(*v));
+            }
+            encode_booleans(&values, &mut data);
+        }
+        DataType::Int32 => {
+            let values: Vec<i64> = array
+                .as_primitive::<Int32Type>()
+                .values()
+                .iter()
+                .map(|v| *v as i64)
+                .collect();
+            encode_longs(stats, &values, &mut data);
+        }
+        DataType::Date32 => {
+            let values: Vec<i64> = array
+                .as_primitive::<Date32Type>()
+                .values()
+                .iter()
+                .map(|v| *v as i64)
+                .collect();
+            encode_longs(stats, &values, &mut data);
+        }
+        DataType::Int64 => {
+            encode_longs(stats, array.as_primitive::<Int64Type>().values(), &mut data)
+        }
+        DataType::Time64(_) => encode_longs(
+            stats,
+            array.as_primitive::<Time64MicrosecondType>().values(),
+            &mut data,
+        ),
+        DataType::Float32 => {
+            for v in array.as_primitive::<Float32Type>().values().iter() {
+                data.extend(v.to_le_bytes());
+                if !v.is_nan() {
+                    stats.update(OrcBound::Double(*v as f64));
+                }
+            }
+        }
+        DataType::Float64 => {
+            for v in array.as_primitive::<Float64Type>().values().iter() {
+                data.extend(v.to_le_bytes());
+                if !v.is_nan() {
+                    stats.update(OrcBound::Double(*v));
+                }
+            }
+        }
+        DataType::Decimal128(_, scale) => {
+            let values = array.as_primitive::<Decimal128Type>().values();
+            for v in values.iter() {
+                write_signed_varint(&mut data, *v);
+                stats.update(OrcBound::Decimal(*v));
+            }
+            let mut scales = vec![];
+            encode_ints(&vec![*scale as i64; values.len()], true, &mut scales);
+            streams.push((id, stream_kind::SECONDARY, scales));
+        }
+        DataType::Timestamp(unit, _) => {
+            let values = match unit {
+                TimeUnit::Second => array.as_primitive::<TimestampSecondType>().values(),
+                TimeUnit::Millisecond => array.as_primitive::<TimestampMillisecondType>().values(),
+                TimeUnit::Microsecond => array.as_primitive::<TimestampMicrosecondType>().values(),
+                TimeUnit::Nanosecond => array.as_primitive::<TimestampNanosecondType>().values(),
+            };
+            let (units_per_second, nanos_per_unit) = time_unit_scale(unit);
+            let mut seconds = Vec::with_capacity(values.len());
+            let mut nanos = Vec::with_capacity(values.len());
+            for v in values.iter() {
+                stats.update(OrcBound::Int(*v));
+                let mut second = v.div_euclid(units_per_second);
+                let nano = v.rem_euclid(units_per_second) * nanos_per_unit;
+                // Seconds are truncated towards zero for negative timestamps
+                // before a whole millisecond, which is compatible with the
+                // java implementation.
+                if second < 0 && nano > 999_999 {
+                    second += 1;
+                }
+                seconds.push(second - TIMESTAMP_BASE_SECONDS);
+                nanos.push(encode_nanos(nano));
+            }
+            encode_ints(&seconds, true, &mut data);
+            let mut secondary = vec![];
+            encode_ints(&nanos, false, &mut secondary);
+            streams.push((id, stream_kind::SECONDARY, secondary));
+        }
+        DataType::Utf8 => encode_bytes_column(
+            stats,
+            array
+                .as_string::<i32>()
+                .iter()
+                .flatten()
+                .map(|v| v.as_bytes()),
+            id,
+            &mut data,
+            streams,
+        ),
+        DataType::LargeUtf8 => encode_bytes_column(
+            stats,
+            array
+                .as_string::<i64>()
+                .iter()
+                .flatten()
+                .map(|v| v.as_bytes()),
+            id,
+            &mut data,
+            streams,
+        ),
+        DataType::Binary => encode_bytes_column(
+            stats,
+            array.as_binary::<i32>().iter().flatten(),
+            id,
+            &mut data,
+            streams,
+        ),
+        DataType::LargeBinary => encode_bytes_column(
+            stats,
+            array.as_binary::<i64>().iter().flatten(),
+            id,
+            &mut data,
+            streams,
+        ),
+        DataType::FixedSizeBinary(_) => encode_bytes_column(
+            stats,
+            array
+                .as_any()
+                .downcast_ref::<FixedSizeBinaryArray>()
+                .expect("type is checked")
+                .iter()
+                .flatten(),
+            id,
+            &mut data,
+            streams,
+        ),
+        DataType::Struct(_) => {
+            let array = array.as_struct();
+            for (child, subtype) in array.columns().iter().zip(&column.subtypes) {
+                encode_column(columns, statistics, *subtype as usize, child, streams)?;
+            }
+            return Ok(());
+        }
+        DataType::List(_) => {
+            return encode_list(columns, statistics, id, array.as_list::<i32>(), streams)
+        }
+        DataType::LargeList(_) => {
+            return encode_list(columns, statistics, id, array.as_list::<i64>(), streams)
+        }
+        DataType::Map(_, _) => {
+            let array = array.as_map();
+            let offsets = array.value_offsets();
+            let first = offsets[0] as usize;
+            let last = offsets[offsets.len() - 1] as usize;
+            encode_lengths(
+                offsets.windows(2).map(|v| (v[1] - v[0]) as i64),
+                id,
+                streams,
+            );
+            let entries = array.entries().slice(first, last - first);
+            for (child, subtype) in entries.columns().iter().zip(&column.subtypes) {
+                encode_column(columns, statistics, *subtype as usize, child, streams)?;
+            }
+            return Ok(());
+        }
+        _ => unreachable!("unsupported types are rejected on creation"),
+    }
+    streams.push((id, stream_kind::DATA, data));
+    Ok(())
+}
+
+fn encode_longs(stats: &mut OrcColumnStatistics, values: &[i64], data: &mut Vec<u8>) {
+    for v in values {
+        stats.update(OrcBound::Int(*v));
+    }
+    encode_ints(values, true, data);
+}
+
+fn encode_lengths(lengths: impl Iterator<Item = i64>, id: usize, streams: &mut Streams) {
+    let lengths: Vec<i64> = lengths.collect();
+    let mut bytes = vec![];
+    encode_ints(&lengths, false, &mut bytes);
+    streams.push((id, stream_kind::LENGTH, bytes));
+}
+
+fn encode_bytes_column<'a>(
+    stats: &mut OrcColumnStatistics,
+    values: impl Iterator<Item = &'a [u8]>,
+    id: usize,
+    data: &mut Vec<u8>,
+    streams: &mut Streams,
+) {
+    let mut lengths = vec![];
+    for v in values {
+        data.extend_from_slice(v);
+        lengths.push(v.len() as i64);
+        stats.update(OrcBound::Bytes(v.to_vec()));
+    }
+    encode_lengths(lengths.into_iter(), id, streams);
+}
+
+fn encode_list<O: OffsetSizeTrait>(
+    columns: &[OrcColumn],
+    statistics: &mut [OrcColumnStatistics],
+    id: usize,
+    array: &GenericListArray<O>,
+    streams: &mut Streams,
+) -> Result<()> {
+    let offsets = array.value_offsets();
+    let first = offsets[0].as_usize();
+    let last = offsets[offsets.len() - 1].as_usize();
+    encode_lengths(
+        offsets.windows(2).map(|v| (v[1] - v[0]).as_usize() as i64),
+        id,
+        streams,
+    );
+    let values = array.values().slice(first, last - first);
+    encode_column(
+        columns,
+        statistics,
+        columns[id].subtypes[0] as usize,
+        &values,
+        streams,
+    )
+}
+
+/// Units per second and nanoseconds per unit of a time unit.
+pub(crate) fn time_unit_scale(unit: &TimeUnit) -> (i64, i64) {
+    match unit {
+        TimeUnit::Second => (1, 1_000_000_000),
+        TimeUnit::Millisecond => (1_000, 1_000_000),
+        TimeUnit::Microsecond => (1_000_000, 1_000),
+        TimeUnit::Nanosecond => (1_000_000_000, 1),
+    }
+}
+
+/// Encode nanoseconds with trailing zeros removed, the low 3 bits are the
+/// number of removed zeros minus one.
+fn encode_nanos(nanos: i64) -> i64 {
+    if nanos == 0 || nanos % 100 != 0 {
+        return nanos << 3;
+    }
+    let mut nanos = nanos / 100;
+    let mut zeros = 1;
+    while nanos % 10 == 0 && zeros < 7 {
+        nanos /= 10;
+        zeros += 1;
+    }
+    (nanos << 3) | zeros
+}
+
+/// Format unscaled decimal value with the scale, like `-1.23`.
+fn decimal_to_string(unscaled: i128, scale: i8) -> String {
+    if scale <= 0 {
+        return (unscaled * 10_i128.pow(-scale as u32)).to_string();
+    }
+    let digits = unscaled.unsigned_abs().to_string();
+    let scale = scale as usize;
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    let sign = if unscaled < 0 { "-" } else { "" };
+    format!("{sign}{int}.{frac}")
+}
+
+fn to_proto_statistics(
+    column: &OrcColumn,
+    statistics: &OrcColumnStatistics,
+) -> proto::ColumnStatistics {
+    let mut result = proto::ColumnStatistics {
+        number_of_values: Some(statistics.num_values),
+        has_null: Some(statistics.num_nulls > 0),
+        bytes_on_disk: Some(statistics.bytes_on_disk),
+        ..Default::default()
+    };
+    if column.kind == type_kind::BOOLEAN {
+        result.bucket_statistics = Some(proto::BucketStatistics {
+            count: vec![statistics.true_count],
+        });
+    }
+    let Some((min, max)) = &statistics.bounds else {
+        return result;
+    };
+    match (&column.data_type, min, max) {
+        (
+            DataType::Int32 | DataType::Int64 | DataType::Time64(_),
+            OrcBound::Int(min),
+            OrcBound::Int(max),
+        ) => {
+            result.int_statistics = Some(proto::IntegerStatistics {
+                minimum: Some(*min),
+                maximum: Some(*max),
+            });
+        }
+        (DataType::Date32, OrcBound::Int(min), OrcBound::Int(max)) => {
+            result.date_statistics = Some(proto::DateStatistics {
+                minimum: Some(*min as i32),
+                maximum: Some(*max as i32),
+            });
+        }
+        (DataType::Timestamp(unit, _), OrcBound::Int(min), OrcBound::Int(max)) => {
+            let (units_per_second, nanos_per_unit) = time_unit_scale(unit);
+            let units_per_milli = (units_per_second / 1000).max(1);
+            let millis = |v: i64| {
+                if units_per_second < 1000 {
+                    v * 1000
+                } else {
+                    v.div_euclid(units_per_milli)
+                }
+            };
+            let nanos = |v: i64| {
+                let nanos = (v.rem_euclid(units_per_milli) * nanos_per_unit) as i32;
+                (nanos > 0).then_some(nanos + 1)
+            };
+            result.timestamp_statistics = Some(proto::TimestampStatistics {
+                minimum: Some(millis(*min)),
+                maximum: Some(millis(*max)),
+                minimum_utc: Some(millis(*min)),
+                maximum_utc: Some(millis(*max)),
+                minimum_nanos: nanos(*min),
+                maximum_nanos: nanos(*max),
+            });
+        }
+        (_, OrcBound::Double(min), OrcBound::Double(max)) => {
+            result.double_statistics = Some(proto::DoubleStatistics {
+                minimum: Some(*min),
+                maximum: Some(*max),
+            });
+        }
+        (DataType::Decimal128(_, scale), OrcBound::Decimal(min), OrcBound::Decimal(max)) => {
+            result.decimal_statistics = Some(proto::DecimalStatistics {
+                minimum: Some(decimal_to_string(*min, *scale)),
+                maximum: Some(decimal_to_string(*max, *scale)),
+            });
+        }
+        (DataType::Utf8 | DataType::LargeUtf8, OrcBound::Bytes(min), OrcBound::Bytes(max)) => {
+            result.string_statistics = Some(proto::StringStatistics {
+                minimum: Some(String::from_utf8_lossy(min).to_string()),
+                maximum: Some(String::from_utf8_lossy(max).to_string()),
+            });
+        }
+        _ => {}
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{
+        ArrayData, BooleanBuilder, Date32Array, Decimal128Array, Float32Array, Float64Array,
+        Int32Array, Int64Array, LargeBinaryArray, ListArray, MapArray, StringArray,
+        TimestampMicrosecondArray, TimestampNanosecondArray,
+    };
+    use arrow::buffer::{Buffer, NullBuffer, OffsetBuffer};
+    use arrow::datatypes::Schema as ArrowSchema;
+    use futures::TryStreamExt;
+    use opendal::services::Memory;
+    use opendal::Operator;
+
+    use super::*;
+    use crate::io::orc::OrcBatchReader;
+    use crate::io::parquet::ParquetProjection;
+    use crate::types::{List, Map, Struct};
+
+    fn field(id: i32, name: &str, required: bool, field_type: Any) -> Field {
+        Field {
+            id,
+            name: name.to_string(),
+            required,
+            field_type,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        }
+    }
+
+    fn test_schema() -> Schema {
+        let primitive = |v| Any::Primitive(v);
+        Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "id", true, primitive(Primitive::Long)),
+                field(2, "i", false, primitive(Primitive::Int)),
+                field(3, "f", false, primitive(Primitive::Float)),
+                field(4, "d", false, primitive(Primitive::Double)),
+                field(
+                    5,
+                    "dec",
+                    false,
+                    primitive(Primitive::Decimal {
+                        precision: 10,
+                        scale: 2,
+                    }),
+                ),
+                field(6, "date", false, primitive(Primitive::Date)),
+                field(7, "ts", false, primitive(Primitive::Timestamp)),
+                field(8, "tstz", false, primitive(Primitive::Timestampz)),
+                field(9, "s", false, primitive(Primitive::String)),
+                field(10, "b", false, primitive(Primitive::Binary)),
+                field(11, "fx", false, primitive(Primitive::Fixed(3))),
+                field(12, "flag", false, primitive(Primitive::Boolean)),
+                field(
+                    13,
+                    "st",
+                    false,
+                    Any::Struct(Arc::new(Struct::new(vec![
+                        field(14, "x", false, primitive(Primitive::Int)),
+                        field(15, "y", true, primitive(Primitive::String)),
+                    ]))),
+                ),
+                field(
+                    16,
+                    "l",
+                    false,
+                    Any::List(List {
+                        element_id: 17,
+                        element_required: false,
+                        element_type: Box::new(primitive(Primitive::Long)),
+                    }),
+                ),
+                field(
+                    18,
+                    "m",
+                    false,
+                    Any::Map(Map {
+                        key_id: 19,
+                        key_type: Box::new(primitive(Primitive::String)),
+                        value_id: 20,
+                        value_required: false,
+                        value_type: Box::new(primitive(Primitive::Int)),
+                    }),
+                ),
+            ],
+        }
+    }
+
+    /// Rows of [`test_schema`], every column has nulls except `id`.
+    fn test_batch(schema: &Schema, rows: usize) -> RecordBatch {
+        let arrow_schema = Arc::new(ArrowSchema::try_from(schema.clone()).unwrap());
+        let valid = |i: usize, m: usize| !i.is_multiple_of(m);
+        let ids: Vec<i64> = (0..rows as i64).collect();
+
+        let DataType::Struct(st_fields) = arrow_schema.field(12).data_type() else {
+            unreachable!()
+        };
+        let st_nulls = NullBuffer::from((0..rows).map(|i| valid(i, 7)).collect::<Vec<_>>());
+        let st = StructArray::try_new(
+            st_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter(
+                    (0..rows).map(|i| valid(i, 3).then_some(i as i32 * 7)),
+                )) as ArrayRef,
+                // Required field only has values under valid parents.
+                Arc::new(StringArray::from_iter(
+                    (0..rows).map(|i| valid(i, 7).then(|| format!("y{i}"))),
+                )),
+            ],
+            Some(st_nulls),
+        )
+        .unwrap();
+
+        let DataType::List(l_field) = arrow_schema.field(13).data_type() else {
+            unreachable!()
+        };
+        let mut offsets = vec![0_i32];
+        let mut elements = vec![];
+        for i in 0..rows {
+            for j in 0..i % 4 {
+                elements.push(valid(j, 2).then_some((i * j) as i64));
+            }
+            offsets.push(elements.len() as i32);
+        }
+        let l = ListArray::try_new(
+            l_field.clone(),
+            OffsetBuffer::new(offsets.into()),
+            Arc::new(Int64Array::from(elements)),
+            Some(NullBuffer::from(
+                (0..rows).map(|i| valid(i, 5)).collect::<Vec<_>>(),
+            )),
+        )
+        .unwrap();
+
+        let DataType::Map(m_field, _) = arrow_schema.field(14).data_type() else {
+            unreachable!()
+        };
+        let DataType::Struct(entry_fields) = m_field.data_type() else {
+            unreachable!()
+        };
+        let mut offsets = vec![0_i32];
+        let mut keys = vec![];
+        let mut values = vec![];
+        for i in 0..rows {
+            for j in 0..i % 3 {
+                keys.push(format!("k{j}"));
+                values.push(valid(i + j, 4).then_some((i + j) as i32));
+            }
+            offsets.push(keys.len() as i32);
+        }
+        let entries = StructArray::try_new(
+            entry_fields.clone(),
+            vec![
+                Arc::new(StringArray::from(keys)) as ArrayRef,
+                Arc::new(Int32Array::from(values)),
+            ],
+            None,
+        )
+        .unwrap();
+        let m = MapArray::from(
+            ArrayData::builder(arrow_schema.field(14).data_type().clone())
+                .len(rows)
+                .add_buffer(Buffer::from_vec(offsets))
+                .add_child_data(entries.into_data())
+                .nulls(Some(NullBuffer::from(
+                    (0..rows).map(|i| valid(i, 6)).collect::<Vec<_>>(),
+                )))
+                .build()
+                .unwrap(),
+        );
+
+        let mut flag = BooleanBuilder::new();
+        for i in 0..rows {
+            flag.append_option(valid(i, 9).then_some(i % 2 == 0));
+        }
+        let timestamps = |column: usize, m: usize| {
+            let DataType::Timestamp(_, tz) = arrow_schema.field(column).data_type() else {
+                unreachable!()
+            };
+            TimestampMicrosecondArray::from_iter((0..rows).map(|i| {
+                // Covers timestamps before epoch and before the base of orc.
+                valid(i, m).then_some((i as i64 - 100) * 1_500_123_457 + 3)
+            }))
+            .with_timezone_opt(tz.clone())
+        };
+
+        RecordBatch::try_new(
+            arrow_schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(ids)) as ArrayRef,
+                Arc::new(Int32Array::from_iter(
+                    (0..rows).map(|i| valid(i, 2).then_some(i as i32 - 1000)),
+                )),
+                Arc::new(Float32Array::from_iter((0..rows).map(|i| {
+                    valid(i, 3).then_some(if i % 11 == 0 {
+                        f32::NAN
+                    } else {
+                        i as f32 / 3.0
+                    })
+                }))),
+                Arc::new(Float64Array::from_iter(
+                    (0..rows).map(|i| valid(i, 4).then_some(-(i as f64) / 7.0)),
+                )),
+                Arc::new(
+                    Decimal128Array::from_iter(
+                        (0..rows).map(|i| valid(i, 5).then_some(i as i128 * 12345 - 99999)),
+                    )
+                    .with_precision_and_scale(10, 2)
+                    .unwrap(),
+                ),
+                Arc::new(Date32Array::from_iter(
+                    (0..rows).map(|i| valid(i, 6).then_some(i as i32 * 13 - 20000)),
+                )),
+                Arc::new(timestamps(6, 7)),
+                Arc::new(timestamps(7, 8)),
+                Arc::new(StringArray::from_iter((0..rows).map(|i| {
+                    valid(i, 9).then(|| "abc".repeat(i % 5) + &(i % 17).to_string())
+                }))),
+                Arc::new(LargeBinaryArray::from_iter(
+                    (0..rows).map(|i| valid(i, 10).then(|| vec![(i % 256) as u8; i % 4])),
+                )),
+                Arc::new(
+                    FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                        (0..rows).map(|i| valid(i, 11).then_some([i as u8, 1, 2])),
+                        3,
+                    )
+                    .unwrap(),
+                ),
+                Arc::new(flag.finish()),
+                Arc::new(st),
+                Arc::new(l),
+                Arc::new(m),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn orc_write_test() -> anyhow::Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+        let schema = test_schema();
+        let batch = test_batch(&schema, 3000);
+
+        for compression in [
+            OrcCompression::None,
+            OrcCompression::Zlib,
+            OrcCompression::Snappy,
+            OrcCompression::Lz4,
+            OrcCompression::Zstd,
+        ] {
+            let props = OrcWriterProperties::default()
+                .with_compression(compression)
+                .with_stripe_size_bytes(64 * 1024);
+            let mut w = OrcWriter::try_new(op.writer("test").await?, batch.schema(), props)?;
+            for offset in (0..batch.num_rows()).step_by(500) {
+                w.write(&batch.slice(offset, 500)).await?;
+            }
+            let (meta, written_size) = w.close(Some(&schema)).await?;
+            assert_eq!(meta.num_rows, 3000);
+            assert!(meta.stripe_offsets.len() > 1);
+
+            let data = op.read("test").await?;
+            assert_eq!(data.len() as u64, written_size);
+            let reader = OrcBatchReader::try_new(data.into(), None)?;
+            assert_eq!(reader.schema(), batch.schema());
+            let batches: Vec<_> = futures::stream::iter(reader).try_collect().await?;
+            assert!(batches.iter().all(|v| v.num_rows() <= 1024));
+            assert_eq!(concat_batches(&batch.schema(), &batches)?, batch);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orc_projection_test() -> anyhow::Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+        let schema = test_schema();
+        let batch = test_batch(&schema, 100);
+        let mut w = OrcWriter::try_new(
+            op.writer("test").await?,
+            batch.schema(),
+            OrcWriterProperties::default(),
+        )?;
+        w.write(&batch).await?;
+        w.close(Some(&schema)).await?;
+
+        let projection = ParquetProjection::try_new(&schema, &[15, 9, 1])?;
+        let reader = OrcBatchReader::try_new(op.read("test").await?.into(), Some(&projection))?;
+        let batches: Vec<_> = futures::stream::iter(reader).try_collect().await?;
+
+        let st = batch
+            .column(12)
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        let DataType::Struct(st_fields) = st.data_type() else {
+            unreachable!()
+        };
+        let st = StructArray::new(
+            st_fields.iter().skip(1).cloned().collect(),
+            vec![st.column(1).clone()],
+            st.nulls().cloned(),
+        );
+        let result = concat_batches(&batches[0].schema(), &batches)?;
+        let names: Vec<_> = result
+            .schema()
+            .fields()
+            .iter()
+            .map(|v| v.name().clone())
+            .collect();
+        assert_eq!(names, vec!["st", "s", "id"]);
+        assert_eq!(
+            result.columns(),
+            &[
+                Arc::new(st) as ArrayRef,
+                batch.column(8).clone(),
+                batch.column(0).clone()
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orc_timestamp_ns_test() -> anyhow::Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+        let schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "ts", false, Any::Primitive(Primitive::TimestampNs)),
+                field(2, "tstz", false, Any::Primitive(Primitive::TimestampzNs)),
+            ],
+        };
+        let arrow_schema = Arc::new(ArrowSchema::try_from(schema.clone())?);
+        let values = [
+            Some(-1_500_123_456_789),
+            None,
+            Some(1_700_000_000_123_456_789),
+        ];
+        let batch = RecordBatch::try_new(
+            arrow_schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from_iter(values)),
+                Arc::new(TimestampNanosecondArray::from_iter(values).with_timezone("+00:00")),
+            ],
+        )?;
+
+        let mut w = OrcWriter::try_new(
+            op.writer("test").await?,
+            arrow_schema,
+            OrcWriterProperties::default(),
+        )?;
+        w.write(&batch).await?;
+        let (meta, _) = w.close(Some(&schema)).await?;
+        let leaves: Vec<_> = meta.leaf_statistics().collect();
+        assert_eq!(
+            leaves[0].bounds,
+            Some((
+                OrcBound::Int(-1_500_123_456_789),
+                OrcBound::Int(1_700_000_000_123_456_789)
+            ))
+        );
+
+        let reader = OrcBatchReader::try_new(op.read("test").await?.into(), None)?;
+        assert_eq!(reader.schema(), batch.schema());
+        let batches: Vec<_> = futures::stream::iter(reader).try_collect().await?;
+        assert_eq!(batches, vec![batch]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn orc_statistics_test() -> anyhow::Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+        let schema = test_schema();
+        let batch = test_batch(&schema, 100);
+
+        let mut w = OrcWriter::try_new(
+            op.writer("test").await?,
+            batch.schema(),
+            OrcWriterProperties::default(),
+        )?;
+        w.write(&batch).await?;
+        let (meta, _) = w.close(Some(&schema)).await?;
+
+        // Leaves are id, i, f, d, dec, date, ts, tstz, s, b, fx, flag, st.x,
+        // st.y, l.element, m.key, m.value.
+        let leaves: Vec<_> = meta.leaf_statistics().collect();
+        assert_eq!(leaves.len(), 17);
+        assert_eq!(leaves[0].num_values, 100);
+        assert_eq!(
+            leaves[0].bounds,
+            Some((OrcBound::Int(0), OrcBound::Int(99)))
+        );
+        assert_eq!(leaves[1].num_nulls, 50);
+        assert_eq!(
+            leaves[1].bounds,
+            Some((OrcBound::Int(-999), OrcBound::Int(-901)))
+        );
+        // NaN is excluded from bounds.
+        assert_eq!(
+            leaves[2].bounds,
+            Some((
+                OrcBound::Double((1.0_f32 / 3.0) as f64),
+                OrcBound::Double((98.0_f32 / 3.0) as f64)
+            ))
+        );
+        // Values of `st.x` are only stored under non null structs.
+        assert_eq!(leaves[12].num_values + leaves[12].num_nulls, 85);
+        assert!(leaves.iter().all(|v| v.bytes_on_disk > 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_nanos() {
+        assert_eq!(encode_nanos(0), 0);
+        assert_eq!(encode_nanos(1), 1 << 3);
+        assert_eq!(encode_nanos(1000), (1 << 3) | 2);
+        assert_eq!(encode_nanos(500_000_000), (5 << 3) | 7);
+        assert_eq!(encode_nanos(123_000_000), (123 << 3) | 5);
+    }
+
+    #[test]
+    fn test_decimal_to_string() {
+        assert_eq!(decimal_to_string(12345, 2), "123.45");
+        assert_eq!(decimal_to_string(-5, 3), "-0.005");
+        assert_eq!(decimal_to_string(7, 0), "7");
+    }
+}
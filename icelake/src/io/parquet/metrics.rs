@@ -0,0 +1,489 @@
+//! metrics module collects column metrics of written parquet and orc files,
+//! which are stored in manifests for readers to prune files.
+
+use std::collections::{HashMap, HashSet};
+
+use arrow::array::{Array, ArrayRef, Float32Array, Float64Array, StructArray};
+use arrow::buffer::NullBuffer;
+use parquet::format::{FileMetaData, Type as PhysicalType};
+
+use crate::io::orc::{OrcBound, OrcFileMetaData};
+use crate::io::writer_config::{MetricsMode, WriterConfig};
+use crate::types::expression::{compare_values, decimal_bytes};
+use crate::types::{decode_bound, encode_bound};
+use crate::types::{Any, Field, Primitive, PrimitiveValue, Schema};
+
+/// A leaf column of the parquet file, in the order of column chunks.
+#[derive(Debug, Clone)]
+struct LeafColumn {
+    id: i32,
+    /// Full name of the column, like `a.b` or `l.element`.
+    name: String,
+    ty: Primitive,
+    /// Whether the column is nested in a list or map, whose bounds are not
+    /// collected.
+    repeated: bool,
+}
+
+/// Metrics of columns keyed by field ids.
+#[derive(Debug, Default)]
+pub(crate) struct ColumnMetrics {
+    pub column_sizes: HashMap<i32, i64>,
+    pub value_counts: HashMap<i32, i64>,
+    pub null_value_counts: HashMap<i32, i64>,
+    pub nan_value_counts: HashMap<i32, i64>,
+    pub lower_bounds: HashMap<i32, Vec<u8>>,
+    pub upper_bounds: HashMap<i32, Vec<u8>>,
+}
+
+/// Collects metrics of a parquet or orc file written from rows of an
+/// iceberg schema.
+///
+/// Most metrics are read from the footer on close, NaN counts are not
+/// recorded by the formats so they are counted from written batches.
+#[derive(Debug, Clone)]
+pub(crate) struct MetricsCollector {
+    schema: Schema,
+    leaves: Vec<LeafColumn>,
+    nan_counts: HashMap<i32, i64>,
+}
+
+impl MetricsCollector {
+    pub fn new(schema: Schema) -> Self {
+        let mut leaves = vec![];
+        collect_leaves(&schema.fields, "", false, &mut leaves);
+        Self {
+            schema,
+            leaves,
+            nan_counts: HashMap::new(),
+        }
+    }
+
+    /// Iceberg schema of written rows.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Count NaN values of float and double columns in a written batch.
+    pub fn update(&mut self, columns: &[ArrayRef]) {
+        count_nans(&self.schema.fields, columns, None, &mut self.nan_counts);
+    }
+
+    /// Build metrics of the written file from its footer, and reset the
+    /// collector for the next file.
+    pub fn finish(&mut self, meta_data: &FileMetaData, config: &WriterConfig) -> ColumnMetrics {
+        let mut metrics = ColumnMetrics::default();
+        let mut unknown_null_counts = HashSet::new();
+        let mut unknown_bounds = HashSet::new();
+        let mut bounds: HashMap<i32, (Bound, Bound)> = HashMap::new();
+
+        for group in &meta_data.row_groups {
+            for (leaf, chunk) in self.leaves.iter().zip(&group.columns) {
+                let Some(chunk) = &chunk.meta_data else {
+                    unknown_null_counts.insert(leaf.id);
+                    unknown_bounds.insert(leaf.id);
+                    continue;
+                };
+                *metrics.column_sizes.entry(leaf.id).or_insert(0) += chunk.total_compressed_size;
+
+                let mode = config.metrics_mode(&leaf.name);
+                if !mode.collect_counts() {
+                    continue;
+                }
+                *metrics.value_counts.entry(leaf.id).or_insert(0) += chunk.num_values;
+                let statistics = chunk.statistics.as_ref();
+                // Null count is omitted by the arrow writer if there are no
+                // nulls.
+                let null_count = statistics.map(|s| s.null_count.unwrap_or(0));
+                match null_count {
+                    Some(n) => *metrics.null_value_counts.entry(leaf.id).or_insert(0) += n,
+                    None => {
+                        unknown_null_counts.insert(leaf.id);
+                    }
+                }
+
+                if !mode.collect_bounds() || leaf.repeated {
+                    continue;
+                }
+                // Chunks of only nulls have no bounds.
+                if null_count == Some(chunk.num_values) {
+                    continue;
+                }
+                let bound = |bytes: Option<&Vec<u8>>| {
+                    bytes.and_then(|v| to_iceberg_bound(&leaf.ty, chunk.type_, v))
+                };
+                match (
+                    bound(statistics.and_then(|s| s.min_value.as_ref())),
+                    bound(statistics.and_then(|s| s.max_value.as_ref())),
+                ) {
+                    (Some(min), Some(max)) => {
+                        let (lower, upper) =
+                            bounds.entry(leaf.id).or_insert((min.clone(), max.clone()));
+                        if compare_values(&min.0, &lower.0).is_some_and(|v| v.is_lt()) {
+                            *lower = min;
+                        }
+                        if compare_values(&max.0, &upper.0).is_some_and(|v| v.is_gt()) {
+                            *upper = max;
+                        }
+                    }
+                    _ => {
+                        unknown_bounds.insert(leaf.id);
+                    }
+                }
+            }
+        }
+
+        self.finish_metrics(metrics, bounds, unknown_null_counts, unknown_bounds, config)
+    }
+
+    /// Build metrics of the written orc file from its statistics, and reset
+    /// the collector for the next file.
+    pub fn finish_orc(
+        &mut self,
+        meta_data: &OrcFileMetaData,
+        config: &WriterConfig,
+    ) -> ColumnMetrics {
+        let mut metrics = ColumnMetrics::default();
+        let mut bounds: HashMap<i32, (Bound, Bound)> = HashMap::new();
+
+        for (leaf, statistics) in self.leaves.iter().zip(meta_data.leaf_statistics()) {
+            metrics
+                .column_sizes
+                .insert(leaf.id, statistics.bytes_on_disk as i64);
+
+            let mode = config.metrics_mode(&leaf.name);
+            if !mode.collect_counts() {
+                continue;
+            }
+            // Values of columns not in lists or maps are stored once per row
+            // unless a parent struct is null.
+            let (value_count, null_count) = if leaf.repeated {
+                (
+                    statistics.num_values + statistics.num_nulls,
+                    statistics.num_nulls,
+                )
+            } else {
+                (
+                    meta_data.num_rows,
+                    meta_data.num_rows - statistics.num_values,
+                )
+            };
+            metrics.value_counts.insert(leaf.id, value_count as i64);
+            metrics.null_value_counts.insert(leaf.id, null_count as i64);
+
+            if !mode.collect_bounds() || leaf.repeated {
+                continue;
+            }
+            if let Some((min, max)) = &statistics.bounds {
+                if let (Some(min), Some(max)) = (
+                    orc_to_iceberg_bound(&leaf.ty, min),
+                    orc_to_iceberg_bound(&leaf.ty, max),
+                ) {
+                    bounds.insert(leaf.id, (min, max));
+                }
+            }
+        }
+
+        self.finish_metrics(metrics, bounds, HashSet::new(), HashSet::new(), config)
+    }
+
+    /// Fill NaN counts and truncated bounds, metrics of columns in
+    /// `unknown_null_counts` and `unknown_bounds` are dropped.
+    fn finish_metrics(
+        &mut self,
+        mut metrics: ColumnMetrics,
+        bounds: HashMap<i32, (Bound, Bound)>,
+        unknown_null_counts: HashSet<i32>,
+        unknown_bounds: HashSet<i32>,
+        config: &WriterConfig,
+    ) -> ColumnMetrics {
+        let nan_counts = std::mem::take(&mut self.nan_counts);
+        for leaf in &self.leaves {
+            if let Some(count) = nan_counts.get(&leaf.id) {
+                if config.metrics_mode(&leaf.name).collect_counts() {
+                    metrics.nan_value_counts.insert(leaf.id, *count);
+                }
+            }
+        }
+        for id in unknown_null_counts {
+            metrics.null_value_counts.remove(&id);
+        }
+        for (id, (lower, upper)) in bounds {
+            if unknown_bounds.contains(&id) {
+                continue;
+            }
+            let leaf = self
+                .leaves
+                .iter()
+                .find(|v| v.id == id)
+                .expect("bounds are only collected for leaves");
+            match config.metrics_mode(&leaf.name) {
+                MetricsMode::Truncate(len) => {
+                    metrics
+                        .lower_bounds
+                        .insert(id, truncate_lower_bound(lower, len));
+                    if let Some(upper) = truncate_upper_bound(upper, len) {
+                        metrics.upper_bounds.insert(id, upper);
+                    }
+                }
+                _ => {
+                    metrics.lower_bounds.insert(id, lower.1);
+                    metrics.upper_bounds.insert(id, upper.1);
+                }
+            }
+        }
+        metrics
+    }
+}
+
+/// A bound value and its binary single value serialization.
+pub(crate) type Bound = (PrimitiveValue, Vec<u8>);
+
+fn collect_leaves(fields: &[Field], prefix: &str, repeated: bool, leaves: &mut Vec<LeafColumn>) {
+    for field in fields {
+        let name = if prefix.is_empty() {
+            field.name.clone()
+        } else {
+            format!("{prefix}.{}", field.name)
+        };
+        collect_type(field.id, &field.field_type, name, repeated, leaves);
+    }
+}
+
+/// Leaves are collected in the order of the arrow conversion of the type,
+/// which is the order of parquet column chunks.
+fn collect_type(id: i32, ty: &Any, name: String, repeated: bool, leaves: &mut Vec<LeafColumn>) {
+    match ty {
+        Any::Primitive(ty) => leaves.push(LeafColumn {
+            id,
+            name,
+            ty: *ty,
+            repeated,
+        }),
+        Any::Struct(v) => collect_leaves(v.fields(), &name, repeated, leaves),
+        Any::List(v) => collect_type(
+            v.element_id,
+            &v.element_type,
+            format!("{name}.element"),
+            true,
+            leaves,
+        ),
+        Any::Map(v) => {
+            collect_type(v.key_id, &v.key_type, format!("{name}.key"), true, leaves);
+            collect_type(
+                v.value_id,
+                &v.value_type,
+                format!("{name}.value"),
+                true,
+                leaves,
+            );
+        }
+    }
+}
+
+/// Count NaN values of float and double columns not nested in lists or
+/// maps. Values under null structs are not counted.
+fn count_nans(
+    fields: &[Field],
+    columns: &[ArrayRef],
+    parent_nulls: Option<&NullBuffer>,
+    counts: &mut HashMap<i32, i64>,
+) {
+    fn count<T: Array>(
+        array: &T,
+        nulls: Option<&NullBuffer>,
+        is_nan: impl Fn(usize) -> bool,
+    ) -> i64 {
+        (0..array.len())
+            .filter(|i| nulls.is_none_or(|v| v.is_valid(*i)) && is_nan(*i))
+            .count() as i64
+    }
+
+    for (field, column) in fields.iter().zip(columns) {
+        let nulls = NullBuffer::union(parent_nulls, column.nulls());
+        let nans = match &field.field_type {
+            Any::Primitive(Primitive::Float) => column
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .map(|v| count(v, nulls.as_ref(), |i| v.value(i).is_nan())),
+            Any::Primitive(Primitive::Double) => column
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .map(|v| count(v, nulls.as_ref(), |i| v.value(i).is_nan())),
+            Any::Struct(ty) => {
+                if let Some(v) = column.as_any().downcast_ref::<StructArray>() {
+                    count_nans(ty.fields(), v.columns(), nulls.as_ref(), counts);
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some(nans) = nans {
+            *counts.entry(field.id).or_insert(0) += nans;
+        }
+    }
+}
+
+/// Convert min or max statistics of parquet into a bound of iceberg.
+///
+/// Plain encoded statistics are the same as the single value serialization
+/// except decimals, which are serialized in minimum bytes. NaN is not a valid bound.
+pub(crate) fn to_iceberg_bound(
+    ty: &Primitive,
+    physical_type: PhysicalType,
+    bytes: &[u8],
+) -> Option<Bound> {
+    let bytes = match (ty, physical_type) {
+        (Primitive::Decimal { .. }, PhysicalType::INT32) => {
+            decimal_bytes(i32::from_le_bytes(bytes.try_into().ok()?) as i128)
+        }
+        (Primitive::Decimal { .. }, PhysicalType::INT64) => {
+            decimal_bytes(i64::from_le_bytes(bytes.try_into().ok()?) as i128)
+        }
+        _ => bytes.to_vec(),
+    };
+    let value = decode_bound(ty, &bytes)?;
+    match &value {
+        PrimitiveValue::Float(v) if v.0.is_nan() => None,
+        PrimitiveValue::Double(v) if v.0.is_nan() => None,
+        // Decimals stored as fixed or binary are padded to their lengths.
+        PrimitiveValue::Decimal(_) => {
+            let bytes = encode_bound(&value);
+            Some((value, bytes))
+        }
+        _ => Some((value, bytes)),
+    }
+}
+
+/// Convert min or max statistics of orc into a bound of iceberg.
+fn orc_to_iceberg_bound(ty: &Primitive, bound: &OrcBound) -> Option<Bound> {
+    let bytes = match (ty, bound) {
+        (Primitive::Boolean, OrcBound::Boolean(v)) => vec![*v as u8],
+        (Primitive::Int | Primitive::Date, OrcBound::Int(v)) => (*v as i32).to_le_bytes().to_vec(),
+        (
+            Primitive::Long
+            | Primitive::Time
+            | Primitive::Timestamp
+            | Primitive::Timestampz
+            | Primitive::TimestampNs
+            | Primitive::TimestampzNs,
+            OrcBound::Int(v),
+        ) => v.to_le_bytes().to_vec(),
+        (Primitive::Float, OrcBound::Double(v)) => (*v as f32).to_le_bytes().to_vec(),
+        (Primitive::Double, OrcBound::Double(v)) => v.to_le_bytes().to_vec(),
+        (Primitive::Decimal { .. }, OrcBound::Decimal(v)) => decimal_bytes(*v),
+        (
+            Primitive::String | Primitive::Binary | Primitive::Fixed(_) | Primitive::Uuid,
+            OrcBound::Bytes(v),
+        ) => v.clone(),
+        _ => return None,
+    };
+    let value = decode_bound(ty, &bytes)?;
+    Some((value, bytes))
+}
+
+/// Truncated prefix of strings and binaries is still a lower bound.
+fn truncate_lower_bound((value, bytes): Bound, len: usize) -> Vec<u8> {
+    match value {
+        PrimitiveValue::String(v) if v.chars().count() > len => {
+            v.chars().take(len).collect::<String>().into_bytes()
+        }
+        PrimitiveValue::Binary(v) if v.len() > len => v[..len].to_vec(),
+        _ => bytes,
+    }
+}
+
+/// Truncated prefix of strings and binaries is an upper bound after the
+/// last character or byte incremented, returns `None` if no character or
+/// byte can be incremented.
+fn truncate_upper_bound((value, bytes): Bound, len: usize) -> Option<Vec<u8>> {
+    match value {
+        PrimitiveValue::String(v) if v.chars().count() > len => {
+            let mut chars: Vec<char> = v.chars().take(len).collect();
+            while let Some(c) = chars.pop() {
+                // Skip surrogates which are not valid chars.
+                let next = if c == '\u{d7ff}' {
+                    0xe000
+                } else {
+                    c as u32 + 1
+                };
+                if let Some(next) = char::from_u32(next) {
+                    chars.push(next);
+                    return Some(chars.into_iter().collect::<String>().into_bytes());
+                }
+            }
+            None
+        }
+        PrimitiveValue::Binary(v) if v.len() > len => {
+            let mut v = v[..len].to_vec();
+            while let Some(b) = v.pop() {
+                if b < u8::MAX {
+                    v.push(b + 1);
+                    return Some(v);
+                }
+            }
+            None
+        }
+        _ => Some(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_bytes() {
+        assert_eq!(decimal_bytes(0), vec![0]);
+        assert_eq!(decimal_bytes(127), vec![0x7f]);
+        assert_eq!(decimal_bytes(128), vec![0, 0x80]);
+        assert_eq!(decimal_bytes(-1), vec![0xff]);
+        assert_eq!(decimal_bytes(-129), vec![0xff, 0x7f]);
+
+        // Decimals of all physical types are bounded in minimum bytes.
+        let ty = Primitive::Decimal {
+            precision: 20,
+            scale: 2,
+        };
+        let bound = |physical_type, bytes: &[u8]| to_iceberg_bound(&ty, physical_type, bytes);
+        let expected = Some((
+            PrimitiveValue::Decimal(rust_decimal::Decimal::new(-1420, 2)),
+            vec![0xfa, 0x74],
+        ));
+        assert_eq!(
+            bound(PhysicalType::INT32, &(-1420_i32).to_le_bytes()),
+            expected
+        );
+        assert_eq!(
+            bound(PhysicalType::INT64, &(-1420_i64).to_le_bytes()),
+            expected
+        );
+        let mut padded = vec![0xff; 9];
+        padded[7..].copy_from_slice(&[0xfa, 0x74]);
+        assert_eq!(bound(PhysicalType::FIXED_LEN_BYTE_ARRAY, &padded), expected);
+        assert_eq!(bound(PhysicalType::BYTE_ARRAY, &padded), expected);
+    }
+
+    #[test]
+    fn test_truncate_bounds() {
+        let string = |v: &str| (PrimitiveValue::String(v.to_string()), v.as_bytes().to_vec());
+        assert_eq!(truncate_lower_bound(string("abcd"), 2), b"ab".to_vec());
+        assert_eq!(
+            truncate_upper_bound(string("abcd"), 2),
+            Some(b"ac".to_vec())
+        );
+        assert_eq!(truncate_upper_bound(string("ab"), 2), Some(b"ab".to_vec()));
+        assert_eq!(
+            truncate_upper_bound(string("a\u{10ffff}c"), 2),
+            Some(b"b".to_vec())
+        );
+
+        let binary = |v: &[u8]| (PrimitiveValue::Binary(v.to_vec()), v.to_vec());
+        assert_eq!(truncate_lower_bound(binary(&[1, 2, 3]), 2), vec![1, 2]);
+        assert_eq!(
+            truncate_upper_bound(binary(&[1, 0xff, 3]), 2),
+            Some(vec![2])
+        );
+        assert_eq!(truncate_upper_bound(binary(&[0xff, 0xff, 3]), 2), None);
+    }
+}
@@ -8,4 +8,11 @@ mod stream;
 pub use stream::ParquetStream;
 pub use stream::ParquetStreamBuilder;
 
+mod projection;
+pub use projection::ParquetProjection;
+
 mod track_writer;
+
+mod prune;
+
+pub(crate) mod metrics;
@@ -0,0 +1,383 @@
+use parquet::arrow::ProjectionMask;
+use parquet::basic::Repetition;
+use parquet::schema::types::{SchemaDescriptor, Type};
+
+use crate::types::{MappedField, NameMapping, Schema};
+use crate::{Error, ErrorKind, Result};
+
+/// ParquetProjection decides the columns decoded from parquet files.
+///
+/// Columns are matched by field ids recorded in parquet schema. Files
+/// written without field ids are matched by the name mapping of the table
+/// if given, otherwise by names in the table schema.
+#[derive(Debug, Clone)]
+pub struct ParquetProjection {
+    /// Projected field ids and their names from the top level.
+    fields: Vec<(i32, Vec<String>)>,
+}
+
+/// Resolved projection of a file.
+pub(crate) struct ResolvedProjection {
+    pub mask: ProjectionMask,
+    /// Indices of decoded top level columns in the projected order.
+    pub column_indices: Vec<usize>,
+}
+
+impl ParquetProjection {
+    /// Project given field ids of the table schema.
+    pub fn try_new(schema: &Schema, field_ids: &[i32]) -> Result<Self> {
+        let fields = field_ids
+            .iter()
+            .map(|id| {
+                let path = schema.field_path(*id).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("field id {id} is not found in schema"),
+                    )
+                })?;
+                Ok((*id, path.into_iter().map(|v| v.to_string()).collect()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { fields })
+    }
+
+    /// Returns projected field ids and their names in order.
+    pub(crate) fn fields(&self) -> &[(i32, Vec<String>)] {
+        &self.fields
+    }
+
+    /// Returns projected field ids in order.
+    pub(crate) fn field_ids(&self) -> Vec<i32> {
+        self.fields.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Resolve the projection against the schema of a parquet file.
+    ///
+    /// Projected fields missing in the file are skipped.
+    pub(crate) fn resolve(
+        &self,
+        schema_descr: &SchemaDescriptor,
+        name_mapping: Option<&NameMapping>,
+    ) -> ResolvedProjection {
+        let roots = schema_descr.root_schema().get_fields();
+        let use_field_id = schema_descr
+            .columns()
+            .iter()
+            .any(|v| v.self_type().get_basic_info().has_id());
+        let match_by = match name_mapping {
+            _ if use_field_id => MatchBy::FieldId,
+            Some(name_mapping) => MatchBy::NameMapping(&name_mapping.fields),
+            None => MatchBy::Name,
+        };
+
+        let mut leaves = vec![];
+        // The root index matched by each projected field.
+        let mut matched_roots: Vec<Option<usize>> = vec![None; self.fields.len()];
+        let mut leaf_idx = 0;
+        for (root_idx, root) in roots.iter().enumerate() {
+            let mut path = vec![];
+            self.collect_leaves(
+                root,
+                &match_by,
+                &mut path,
+                None,
+                &mut leaf_idx,
+                &mut leaves,
+                &mut |field_idx| {
+                    matched_roots[field_idx].get_or_insert(root_idx);
+                },
+            );
+        }
+
+        // Decoded columns are in the order of the file, reorder them into the
+        // order of projected fields.
+        let mut decoded_roots: Vec<usize> = matched_roots.iter().flatten().copied().collect();
+        decoded_roots.sort();
+        decoded_roots.dedup();
+        let mut column_indices = vec![];
+        for root_idx in matched_roots.iter().flatten() {
+            let idx = decoded_roots.binary_search(root_idx).unwrap();
+            if !column_indices.contains(&idx) {
+                column_indices.push(idx);
+            }
+        }
+
+        ResolvedProjection {
+            mask: ProjectionMask::leaves(schema_descr, leaves),
+            column_indices,
+        }
+    }
+
+    /// Collect leaves of `ty` if it or one of its ancestors is projected.
+    ///
+    /// `selected` is the index of the projected field covering `ty`.
+    #[allow(clippy::too_many_arguments)]
+    fn collect_leaves(
+        &self,
+        ty: &Type,
+        match_by: &MatchBy,
+        path: &mut Vec<String>,
+        selected: Option<usize>,
+        leaf_idx: &mut usize,
+        leaves: &mut Vec<usize>,
+        on_match: &mut dyn FnMut(usize),
+    ) {
+        path.push(ty.name().to_string());
+        let mapped = match match_by {
+            MatchBy::NameMapping(fields) => fields
+                .iter()
+                .find(|v| v.names.iter().any(|v| v == ty.name())),
+            _ => None,
+        };
+        let matched = self.fields.iter().position(|(id, names)| match match_by {
+            MatchBy::FieldId => {
+                let info = ty.get_basic_info();
+                info.has_id() && info.id() == *id
+            }
+            MatchBy::NameMapping(_) => mapped.and_then(|v| v.field_id) == Some(*id),
+            MatchBy::Name => names == path,
+        });
+        if let Some(field_idx) = matched {
+            on_match(field_idx);
+        }
+        let selected = selected.or(matched);
+
+        if ty.is_group() {
+            let child_match_by = match (match_by, mapped) {
+                (MatchBy::NameMapping(_), Some(mapped)) => MatchBy::NameMapping(&mapped.fields),
+                // Repeated groups of lists and maps are not in the mapping,
+                // their children are mapped as children of the list or map.
+                (MatchBy::NameMapping(fields), None)
+                    if ty.get_basic_info().has_repetition()
+                        && ty.get_basic_info().repetition() == Repetition::REPEATED =>
+                {
+                    MatchBy::NameMapping(fields)
+                }
+                (MatchBy::NameMapping(_), None) => MatchBy::NameMapping(&[]),
+                (MatchBy::FieldId, _) => MatchBy::FieldId,
+                (MatchBy::Name, _) => MatchBy::Name,
+            };
+            for child in ty.get_fields() {
+                self.collect_leaves(
+                    child,
+                    &child_match_by,
+                    path,
+                    selected,
+                    leaf_idx,
+                    leaves,
+                    on_match,
+                );
+            }
+        } else {
+            if selected.is_some() {
+                leaves.push(*leaf_idx);
+            }
+            *leaf_idx += 1;
+        }
+        path.pop();
+    }
+}
+
+/// How columns of a file are matched to projected fields.
+enum MatchBy<'a> {
+    /// By field ids recorded in the file.
+    FieldId,
+    /// By the name mapping, with mapped fields of the current level.
+    NameMapping(&'a [MappedField]),
+    /// By names in the table schema.
+    Name,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int32Array, StructArray};
+    use arrow::datatypes::{DataType, Field as ArrowField};
+    use arrow::record_batch::RecordBatch;
+    use futures::TryStreamExt;
+    use opendal::services::Memory;
+    use opendal::Operator;
+    use parquet::arrow::AsyncArrowWriter;
+
+    use super::*;
+    use crate::io::parquet::ParquetStreamBuilder;
+    use crate::types::{Any, Field, Primitive, Struct};
+
+    fn field(id: i32, name: &str, field_type: Any) -> Field {
+        Field {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_projection_by_name_without_field_ids() -> anyhow::Result<()> {
+        let schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "a", Any::Primitive(Primitive::Int)),
+                field(
+                    2,
+                    "s",
+                    Any::Struct(Arc::new(Struct::new(vec![
+                        field(3, "x", Any::Primitive(Primitive::Int)),
+                        field(4, "y", Any::Primitive(Primitive::Int)),
+                    ]))),
+                ),
+            ],
+        };
+
+        let a = Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef;
+        let s = Arc::new(StructArray::from(vec![
+            (
+                Arc::new(ArrowField::new("x", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![3, 4])) as ArrayRef,
+            ),
+            (
+                Arc::new(ArrowField::new("y", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![5, 6])) as ArrayRef,
+            ),
+        ])) as ArrayRef;
+        let batch = RecordBatch::try_from_iter([("a", a.clone()), ("s", s)])?;
+
+        let op = Operator::new(Memory::default())?.finish();
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, batch.schema(), 0, None)?;
+        w.write(&batch).await?;
+        w.close().await?;
+        op.write("test", buf).await?;
+
+        let projection = ParquetProjection::try_new(&schema, &[4, 1])?;
+        let batches: Vec<_> = ParquetStreamBuilder::new(op.reader("test").await?)
+            .with_projection(projection)
+            .build()
+            .await?
+            .try_collect()
+            .await?;
+
+        let result = &batches[0];
+        assert_eq!(result.num_columns(), 2);
+        assert_eq!(result.schema().field(0).name(), "s");
+        let s = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        assert_eq!(s.num_columns(), 1);
+        assert_eq!(s.column_names(), vec!["y"]);
+        assert_eq!(result.column(1), &a);
+
+        assert!(ParquetProjection::try_new(&schema, &[5]).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_projection_by_name_mapping() -> anyhow::Result<()> {
+        use arrow::array::ListArray;
+        use arrow::datatypes::Int32Type;
+
+        use crate::types::{parse_name_mapping, List};
+
+        let schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "id", Any::Primitive(Primitive::Int)),
+                field(
+                    2,
+                    "location",
+                    Any::Struct(Arc::new(Struct::new(vec![
+                        field(3, "latitude", Any::Primitive(Primitive::Int)),
+                        field(4, "longitude", Any::Primitive(Primitive::Int)),
+                    ]))),
+                ),
+                field(
+                    5,
+                    "tags",
+                    Any::List(List {
+                        element_id: 6,
+                        element_required: false,
+                        element_type: Box::new(Any::Primitive(Primitive::Int)),
+                    }),
+                ),
+            ],
+        };
+        let name_mapping = parse_name_mapping(
+            br#"[
+                { "field-id": 1, "names": ["id", "record_id"] },
+                { "field-id": 2, "names": ["location", "loc"], "fields": [
+                    { "field-id": 3, "names": ["latitude", "lat"] },
+                    { "field-id": 4, "names": ["longitude", "lng"] } ] },
+                { "field-id": 5, "names": ["tags"], "fields": [
+                    { "field-id": 6, "names": ["element", "item"] } ] }
+            ]"#,
+        )?;
+
+        // Columns are written by old names without field ids.
+        let id = Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef;
+        let loc = Arc::new(StructArray::from(vec![
+            (
+                Arc::new(ArrowField::new("lat", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![3, 4])) as ArrayRef,
+            ),
+            (
+                Arc::new(ArrowField::new("lng", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![5, 6])) as ArrayRef,
+            ),
+        ])) as ArrayRef;
+        let tags = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(7)]),
+            None,
+        ])) as ArrayRef;
+        let batch = RecordBatch::try_from_iter([
+            ("record_id", id.clone()),
+            ("loc", loc),
+            ("tags", tags.clone()),
+        ])?;
+
+        let op = Operator::new(Memory::default())?.finish();
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, batch.schema(), 0, None)?;
+        w.write(&batch).await?;
+        w.close().await?;
+        op.write("test", buf).await?;
+
+        let read = |name_mapping: Option<NameMapping>| {
+            let op = op.clone();
+            let projection = ParquetProjection::try_new(&schema, &[4, 1, 5]);
+            async move {
+                let mut builder = ParquetStreamBuilder::new(op.reader("test").await?)
+                    .with_projection(projection?);
+                if let Some(name_mapping) = name_mapping {
+                    builder = builder.with_name_mapping(Arc::new(name_mapping));
+                }
+                let batches: Vec<RecordBatch> = builder.build().await?.try_collect().await?;
+                anyhow::Ok(batches)
+            }
+        };
+
+        let result = &read(Some(name_mapping)).await?[0];
+        assert_eq!(result.num_columns(), 3);
+        let loc = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        assert_eq!(loc.column_names(), vec!["lng"]);
+        assert_eq!(result.column(1), &id);
+        assert_eq!(result.column(2), &tags);
+
+        // Only columns of unchanged names match without the name mapping.
+        let result = &read(None).await?[0];
+        assert_eq!(result.num_columns(), 1);
+        assert_eq!(result.schema().field(0).name(), "tags");
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+//! prune module selects row groups and pages of a parquet file that might
+//! contain rows matching a filter, by statistics of column chunks and page
+//! indexes, so that other row groups and pages are never fetched or
+//! decoded.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use parquet::arrow::arrow_reader::{RowSelection, RowSelector};
+use parquet::data_type::AsBytes;
+use parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
+use parquet::file::page_index::index::{Index, PageIndex};
+use parquet::format::PageLocation;
+
+use super::metrics::to_iceberg_bound;
+use crate::types::expression::{stats_might_match, ColumnStats, DataFileEvaluator};
+use crate::types::{NameMapping, Primitive, PrimitiveValue};
+
+/// Selected rows of a parquet file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileSelection {
+    /// Row groups that might contain matching rows, with rows of them
+    /// selected by page indexes, `None` if all rows are selected.
+    pub row_groups: Vec<(usize, Option<Vec<Range<usize>>>)>,
+}
+
+impl FileSelection {
+    /// Returns indexes of selected row groups.
+    pub fn row_group_indexes(&self) -> Vec<usize> {
+        self.row_groups.iter().map(|(idx, _)| *idx).collect()
+    }
+
+    /// Returns the row selection of given selected row groups, `None` if all
+    /// their rows are selected.
+    pub fn row_selection(
+        &self,
+        metadata: &ParquetMetaData,
+        row_groups: &[usize],
+    ) -> Option<RowSelection> {
+        let selected: Vec<_> = self
+            .row_groups
+            .iter()
+            .filter(|(idx, _)| row_groups.contains(idx))
+            .collect();
+        if selected.iter().all(|(_, ranges)| ranges.is_none()) {
+            return None;
+        }
+
+        let mut selectors = vec![];
+        for (idx, ranges) in selected {
+            let num_rows = metadata.row_group(*idx).num_rows() as usize;
+            let Some(ranges) = ranges else {
+                selectors.push(RowSelector::select(num_rows));
+                continue;
+            };
+            let mut last_end = 0;
+            for range in ranges {
+                if range.start > last_end {
+                    selectors.push(RowSelector::skip(range.start - last_end));
+                }
+                selectors.push(RowSelector::select(range.end - range.start));
+                last_end = range.end;
+            }
+            if last_end < num_rows {
+                selectors.push(RowSelector::skip(num_rows - last_end));
+            }
+        }
+        Some(selectors.into())
+    }
+}
+
+/// Select row groups and pages of the file that might contain rows
+/// matching the filter.
+///
+/// Columns are matched like projections, by field ids, or by the name
+/// mapping or names if the file is written without field ids. Columns
+/// nested in lists or maps never prune rows. Pages are only pruned if the
+/// page index is loaded in `metadata`.
+pub(crate) fn prune(
+    metadata: &ParquetMetaData,
+    filter: &DataFileEvaluator,
+    name_mapping: Option<&NameMapping>,
+) -> FileSelection {
+    let schema_descr = metadata.file_metadata().schema_descr();
+    let use_field_id = schema_descr
+        .columns()
+        .iter()
+        .any(|v| v.self_type().get_basic_info().has_id());
+    let columns: HashMap<i32, usize> = schema_descr
+        .columns()
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| column.max_rep_level() == 0)
+        .filter_map(|(idx, column)| {
+            let info = column.self_type().get_basic_info();
+            let field_id = match name_mapping {
+                _ if use_field_id => info.has_id().then(|| info.id()),
+                Some(name_mapping) => {
+                    let mut parts = column.path().parts().iter();
+                    let mut field = name_mapping.find(parts.next()?)?;
+                    for part in parts {
+                        field = field.find(part)?;
+                    }
+                    field.field_id
+                }
+                None => filter.field_id(&column.path().string()),
+            };
+            Some((field_id?, idx))
+        })
+        .collect();
+
+    let mut row_groups = vec![];
+    for (idx, row_group) in metadata.row_groups().iter().enumerate() {
+        let matched = filter.might_match_stats(&|field_id, ty| {
+            let column = *columns.get(&field_id)?;
+            row_group_stats(row_group, column, ty)
+        });
+        if !matched {
+            continue;
+        }
+
+        let (Some(column_index), Some(offset_index)) =
+            (metadata.column_index(), metadata.offset_index())
+        else {
+            row_groups.push((idx, None));
+            continue;
+        };
+        let num_rows = row_group.num_rows() as usize;
+        let all_rows = || -> Vec<Range<usize>> { std::iter::once(0..num_rows).collect() };
+        let ranges = filter.fold(
+            &|p, field_id, ty| {
+                let pages = columns.get(&field_id).and_then(|column| {
+                    let index = column_index.get(idx)?.get(*column)?;
+                    let locations = offset_index.get(idx)?.get(*column)?;
+                    let physical_type = row_group.column(*column).column_type().into();
+                    page_stats(index, locations, num_rows, &|bytes| {
+                        to_iceberg_bound(ty, physical_type, bytes).map(|v| v.0)
+                    })
+                });
+                match pages {
+                    Some(pages) => merge(
+                        pages
+                            .into_iter()
+                            .filter(|(_, stats)| stats_might_match(p, stats))
+                            .map(|(range, _)| range)
+                            .collect(),
+                    ),
+                    None => all_rows(),
+                }
+            },
+            &|v| if v { all_rows() } else { vec![] },
+            &intersect,
+            &|l, r| merge(l.into_iter().chain(r).collect()),
+        );
+        match ranges.as_slice() {
+            [] => {}
+            [range] if *range == (0..num_rows) => row_groups.push((idx, None)),
+            _ => row_groups.push((idx, Some(ranges))),
+        }
+    }
+    FileSelection { row_groups }
+}
+
+/// Statistics of a column chunk.
+fn row_group_stats(
+    row_group: &RowGroupMetaData,
+    column: usize,
+    ty: &Primitive,
+) -> Option<ColumnStats> {
+    let chunk = row_group.column(column);
+    let statistics = chunk.statistics()?;
+    let mut stats = ColumnStats {
+        value_count: Some(chunk.num_values()),
+        null_count: Some(statistics.null_count() as i64),
+        ..Default::default()
+    };
+    // Deprecated min and max may be ordered as signed values.
+    if statistics.has_min_max_set() && !statistics.is_min_max_deprecated() {
+        let physical_type = chunk.column_type().into();
+        stats.lower = to_iceberg_bound(ty, physical_type, statistics.min_bytes()).map(|v| v.0);
+        stats.upper = to_iceberg_bound(ty, physical_type, statistics.max_bytes()).map(|v| v.0);
+    }
+    Some(stats)
+}
+
+/// Row ranges and statistics of pages of a column chunk, `bound` decodes
+/// min and max values of pages.
+///
+/// Returns `None` if the chunk has no page index.
+fn page_stats(
+    index: &Index,
+    locations: &[PageLocation],
+    num_rows: usize,
+    bound: &dyn Fn(&[u8]) -> Option<PrimitiveValue>,
+) -> Option<Vec<(Range<usize>, ColumnStats)>> {
+    fn collect<T: AsBytes>(
+        indexes: &[PageIndex<T>],
+        locations: &[PageLocation],
+        num_rows: usize,
+        bound: &dyn Fn(&[u8]) -> Option<PrimitiveValue>,
+    ) -> Option<Vec<(Range<usize>, ColumnStats)>> {
+        if indexes.len() != locations.len() {
+            return None;
+        }
+        let pages = indexes
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                let start = locations[i].first_row_index as usize;
+                let end = locations
+                    .get(i + 1)
+                    .map(|v| v.first_row_index as usize)
+                    .unwrap_or(num_rows);
+                let stats = ColumnStats {
+                    value_count: Some((end - start) as i64),
+                    null_count: page.null_count,
+                    nan_count: None,
+                    lower: page.min.as_ref().and_then(|v| bound(v.as_bytes())),
+                    upper: page.max.as_ref().and_then(|v| bound(v.as_bytes())),
+                };
+                (start..end, stats)
+            })
+            .collect();
+        Some(pages)
+    }
+
+    match index {
+        Index::BOOLEAN(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::INT32(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::INT64(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::FLOAT(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::DOUBLE(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::BYTE_ARRAY(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::FIXED_LEN_BYTE_ARRAY(v) => collect(&v.indexes, locations, num_rows, bound),
+        Index::INT96(_) | Index::NONE => None,
+    }
+}
+
+/// Sort and merge overlapping or adjacent ranges.
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|v| v.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges.into_iter().filter(|v| !v.is_empty()) {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Intersect two sorted lists of disjoint ranges.
+fn intersect(l: Vec<Range<usize>>, r: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < l.len() && j < r.len() {
+        let start = l[i].start.max(r[j].start);
+        let end = l[i].end.min(r[j].end);
+        if start < end {
+            ranges.push(start..end);
+        }
+        if l[i].end < r[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(
+            merge(vec![5..8, 0..2, 2..3, 7..10, 4..4]),
+            vec![0..3, 5..10]
+        );
+        assert_eq!(
+            intersect(vec![0..3, 5..10], vec![2..6, 8..12]),
+            vec![2..3, 5..6, 8..10]
+        );
+        assert!(intersect(vec![0..3, 6..7], vec![3..5, 8..9]).is_empty());
+    }
+}
@@ -1,27 +1,46 @@
+use std::ops::Range;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
 
 use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::FutureExt;
 use futures::Stream;
 use futures::StreamExt;
+use futures::TryStreamExt;
+use opendal::Operator;
 use opendal::Reader;
 use parquet::arrow::arrow_reader::ArrowReaderBuilder;
 use parquet::arrow::arrow_reader::ArrowReaderOptions;
-use parquet::arrow::async_reader::ParquetRecordBatchStream;
+use parquet::arrow::arrow_reader::RowSelection;
+use parquet::arrow::async_reader::AsyncFileReader;
+use parquet::arrow::ProjectionMask;
+use parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
 
+use super::prune::prune;
+use super::ParquetProjection;
+use crate::types::expression::DataFileEvaluator;
+use crate::types::NameMapping;
 use crate::Error;
+use crate::ErrorKind;
 use crate::Result;
 
 /// ParquetStreamBuilder is used to builder a [`ParquetStream`].
-///
-///
-/// # TODO
-///
-/// We should support options like: row_groups, projection and so on.
 pub struct ParquetStreamBuilder {
     r: Reader,
     options: ArrowReaderOptions,
+    projection: Option<ParquetProjection>,
+    name_mapping: Option<Arc<NameMapping>>,
+    filter: Option<DataFileEvaluator>,
+    /// Byte range of the file containing starts of row groups to read.
+    range: Option<Range<u64>>,
+    /// Operator and path to open a reader for each row group.
+    file: Option<(Operator, String)>,
+    row_group_concurrency: usize,
 }
 
 impl ParquetStreamBuilder {
@@ -30,16 +49,196 @@ impl ParquetStreamBuilder {
         Self {
             r,
             options: ArrowReaderOptions::default(),
+            projection: None,
+            name_mapping: None,
+            filter: None,
+            range: None,
+            file: None,
+            row_group_concurrency: 1,
         }
     }
 
+    /// Decode at most `concurrency` row groups of the file concurrently.
+    ///
+    /// Every row group is decoded in its own task with a new reader opened
+    /// from `op` at `path`, which must be the same file of the reader given
+    /// in [`ParquetStreamBuilder::new`]. Batches are still produced in the
+    /// order of row groups.
+    pub fn with_row_group_concurrency(
+        mut self,
+        op: Operator,
+        path: impl Into<String>,
+        concurrency: usize,
+    ) -> Self {
+        self.file = Some((op, path.into()));
+        self.row_group_concurrency = concurrency;
+        self
+    }
+
+    /// Only decode columns of given projection.
+    ///
+    /// Top level columns are produced in the order of projected fields.
+    pub fn with_projection(mut self, projection: ParquetProjection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Resolve the projection by given name mapping if the file is written
+    /// without field ids.
+    pub fn with_name_mapping(mut self, name_mapping: Arc<NameMapping>) -> Self {
+        self.name_mapping = Some(name_mapping);
+        self
+    }
+
+    /// Skip row groups and pages that can't contain rows matching the
+    /// filter, decided by column statistics and page indexes.
+    ///
+    /// Rows of the remaining pages are not filtered, so the stream may still
+    /// contain rows not matching the filter.
+    pub fn with_filter(mut self, filter: DataFileEvaluator) -> Self {
+        self.options = self.options.with_page_index(true);
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Only read row groups starting in the byte range of `start` and
+    /// `length`, so that a file split at row group offsets is read by
+    /// splits, see [`crate::scan::TableScan::plan_combined_tasks`].
+    pub fn with_range(mut self, start: u64, length: u64) -> Self {
+        self.range = Some(start..start.saturating_add(length));
+        self
+    }
+
     /// Consume the current builder to build a new writer.
     pub async fn build(self) -> Result<ParquetStream> {
-        let builder = ArrowReaderBuilder::new_with_options(self.r, self.options).await?;
+        let builder = ArrowReaderBuilder::new_with_options(self.r, self.options.clone()).await?;
+        let selection = self
+            .filter
+            .as_ref()
+            .map(|filter| prune(builder.metadata(), filter, self.name_mapping.as_deref()));
+        let mut row_groups = match &selection {
+            Some(selection) => selection.row_group_indexes(),
+            None => (0..builder.metadata().num_row_groups()).collect(),
+        };
+        if let Some(range) = &self.range {
+            let metadata = builder.metadata();
+            row_groups.retain(|idx| range.contains(&row_group_offset(metadata.row_group(*idx))));
+        }
+        let (mask, column_indices) = match &self.projection {
+            Some(projection) => {
+                let resolved = projection.resolve(
+                    builder.metadata().file_metadata().schema_descr(),
+                    self.name_mapping.as_deref(),
+                );
+                (resolved.mask, Some(resolved.column_indices))
+            }
+            None => (ProjectionMask::all(), None),
+        };
+
+        let (op, path) = match self.file {
+            Some(file) if self.row_group_concurrency > 1 => file,
+            _ => {
+                let mut builder = builder.with_row_groups(row_groups.clone());
+                if let Some(row_selection) = selection
+                    .as_ref()
+                    .and_then(|v| v.row_selection(builder.metadata(), &row_groups))
+                {
+                    builder = builder.with_row_selection(row_selection);
+                }
+                let reader = builder
+                    .with_projection(mask)
+                    .build()?
+                    .map_err(Error::from)
+                    .boxed();
+                return Ok(ParquetStream::new(reader, column_indices));
+            }
+        };
+
+        let metadata = builder.metadata().clone();
+        let options = self.options;
+        let reader = futures::stream::iter(row_groups)
+            .map(move |row_group| {
+                let row_selection = selection
+                    .as_ref()
+                    .and_then(|v| v.row_selection(&metadata, &[row_group]));
+                let task = read_row_group(
+                    op.clone(),
+                    path.clone(),
+                    metadata.clone(),
+                    options.clone(),
+                    mask.clone(),
+                    row_group,
+                    row_selection,
+                );
+                tokio::spawn(task).map(|v| {
+                    v.map_err(|e| {
+                        Error::new(ErrorKind::Unexpected, "decoding row group task failed")
+                            .set_source(e)
+                    })?
+                })
+            })
+            .buffered(self.row_group_concurrency)
+            .map_ok(|batches| futures::stream::iter(batches.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed();
+
+        Ok(ParquetStream::new(reader, column_indices))
+    }
+}
 
-        Ok(ParquetStream {
-            reader: builder.build()?,
-        })
+/// Returns the offset of the first page of a row group, which is recorded in
+/// `split_offsets` of data files.
+fn row_group_offset(row_group: &RowGroupMetaData) -> u64 {
+    let Some(column) = row_group.columns().first() else {
+        return 0;
+    };
+    let offset = match column.dictionary_page_offset() {
+        Some(offset) if offset > 0 => offset.min(column.data_page_offset()),
+        _ => column.data_page_offset(),
+    };
+    offset.max(0) as u64
+}
+
+/// Decode all batches of given row group, only selected rows are decoded
+/// if `row_selection` is set.
+async fn read_row_group(
+    op: Operator,
+    path: String,
+    metadata: Arc<ParquetMetaData>,
+    options: ArrowReaderOptions,
+    mask: ProjectionMask,
+    row_group: usize,
+    row_selection: Option<RowSelection>,
+) -> Result<Vec<RecordBatch>> {
+    let r = MetadataCachedReader {
+        r: op.reader(&path).await?,
+        metadata,
+    };
+    let mut builder = ArrowReaderBuilder::new_with_options(r, options)
+        .await?
+        .with_row_groups(vec![row_group]);
+    if let Some(row_selection) = row_selection {
+        builder = builder.with_row_selection(row_selection);
+    }
+    let batches = builder.with_projection(mask).build()?.try_collect().await?;
+    Ok(batches)
+}
+
+/// MetadataCachedReader avoids reading and decoding the footer again for
+/// every row group.
+struct MetadataCachedReader {
+    r: Reader,
+    metadata: Arc<ParquetMetaData>,
+}
+
+impl AsyncFileReader for MetadataCachedReader {
+    fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
+        self.r.get_bytes(range)
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        let metadata = self.metadata.clone();
+        async move { Ok(metadata) }.boxed()
     }
 }
 
@@ -54,16 +253,32 @@ impl ParquetStreamBuilder {
 ///
 /// - If we have known the size of the file, we can avoid once seek.
 pub struct ParquetStream {
-    reader: ParquetRecordBatchStream<Reader>,
+    reader: BoxStream<'static, Result<RecordBatch>>,
+}
+
+impl ParquetStream {
+    fn new(
+        reader: BoxStream<'static, Result<RecordBatch>>,
+        column_indices: Option<Vec<usize>>,
+    ) -> Self {
+        let reader = match column_indices {
+            Some(indices) => reader
+                .and_then(move |batch| {
+                    let batch = batch.project(&indices).map_err(Error::from);
+                    async move { batch }
+                })
+                .boxed(),
+            None => reader,
+        };
+        Self { reader }
+    }
 }
 
 impl Stream for ParquetStream {
     type Item = Result<RecordBatch>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.reader
-            .poll_next_unpin(cx)
-            .map(|v| v.map(|v| v.map_err(Error::from)))
+        self.reader.poll_next_unpin(cx)
     }
 }
 
@@ -106,4 +321,172 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn parquet_stream_row_group_concurrency_test() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let props = parquet::file::properties::WriterProperties::builder()
+            .set_max_row_group_size(100)
+            .build();
+        let col = Arc::new(Int64Array::from_iter_values(0..1000)) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)]).unwrap();
+
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, to_write.schema(), 0, Some(props))?;
+        w.write(&to_write).await?;
+        w.close().await?;
+        op.write("test", buf).await?;
+
+        let r = op.reader("test").await?;
+        let batches: Vec<_> = ParquetStreamBuilder::new(r)
+            .with_row_group_concurrency(op.clone(), "test", 4)
+            .build()
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(batches.len(), 10);
+        let res = arrow::compute::concat_batches(&to_write.schema(), &batches)?;
+        assert_eq!(to_write, res);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parquet_stream_range_test() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+        let props = parquet::file::properties::WriterProperties::builder()
+            .set_max_row_group_size(100)
+            .build();
+        let col = Arc::new(Int64Array::from_iter_values(0..1000)) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)]).unwrap();
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, to_write.schema(), 0, Some(props))?;
+        w.write(&to_write).await?;
+        let metadata = w.close().await?;
+        let size = buf.len() as u64;
+        op.write("test", buf).await?;
+
+        let offsets: Vec<u64> = metadata
+            .row_groups
+            .iter()
+            .map(|v| {
+                let meta = v.columns[0].meta_data.as_ref().unwrap();
+                meta.dictionary_page_offset.unwrap_or(meta.data_page_offset) as u64
+            })
+            .collect();
+        assert_eq!(offsets.len(), 10);
+        // Splits at row group offsets read every row exactly once.
+        let bounds: Vec<u64> = [0, offsets[3], offsets[7], size].to_vec();
+        for concurrency in [1, 4] {
+            let mut values = vec![];
+            for range in bounds.windows(2) {
+                let batches: Vec<_> = ParquetStreamBuilder::new(op.reader("test").await?)
+                    .with_row_group_concurrency(op.clone(), "test", concurrency)
+                    .with_range(range[0], range[1] - range[0])
+                    .build()
+                    .await?
+                    .try_collect()
+                    .await?;
+                values.push(batches.iter().map(|v| v.num_rows()).sum::<usize>());
+            }
+            assert_eq!(values, vec![300, 400, 300]);
+        }
+
+        // No row group starts in the range.
+        let batches: Vec<_> = ParquetStreamBuilder::new(op.reader("test").await?)
+            .with_range(offsets[0] + 1, 2)
+            .build()
+            .await?
+            .try_collect()
+            .await?;
+        assert!(batches.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parquet_stream_filter_test() -> Result<()> {
+        use crate::types::expression::Reference;
+        use crate::types::{Any, Field, PartitionSpec, Primitive, PrimitiveValue, Schema};
+
+        let op = Operator::new(Memory::default())?.finish();
+        let props = parquet::file::properties::WriterProperties::builder()
+            .set_max_row_group_size(300)
+            .set_data_page_row_count_limit(50)
+            .set_write_batch_size(50)
+            .build();
+        let col = Arc::new(Int64Array::from_iter_values(0..1000)) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)]).unwrap();
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, to_write.schema(), 0, Some(props))?;
+        w.write(&to_write).await?;
+        w.close().await?;
+        op.write("test", buf).await?;
+
+        let schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![Field {
+                id: 1,
+                name: "col".to_string(),
+                required: false,
+                field_type: Any::Primitive(Primitive::Long),
+                comment: None,
+                initial_default: None,
+                write_default: None,
+            }],
+        };
+        let spec = PartitionSpec {
+            spec_id: 0,
+            fields: vec![],
+        };
+        let col = || Reference::new("col");
+        let read = |predicate, concurrency| {
+            let op = op.clone();
+            let filter = DataFileEvaluator::try_new(&predicate, &schema, &spec).unwrap();
+            async move {
+                let batches: Vec<_> = ParquetStreamBuilder::new(op.reader("test").await?)
+                    .with_row_group_concurrency(op.clone(), "test", concurrency)
+                    .with_filter(filter)
+                    .build()
+                    .await?
+                    .try_collect()
+                    .await?;
+                let values: Vec<i64> = batches
+                    .iter()
+                    .flat_map(|v| {
+                        let array = v.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                        array.values().to_vec()
+                    })
+                    .collect();
+                Ok::<_, anyhow::Error>(values)
+            }
+        };
+
+        // Only pages of rows 600..650 and 650..700 of the third row group
+        // are read.
+        let predicate = col()
+            .greater_than_or_equal_to(PrimitiveValue::Long(620))
+            .and(col().less_than(PrimitiveValue::Long(660)));
+        for concurrency in [1, 4] {
+            assert_eq!(
+                read(predicate.clone(), concurrency).await?,
+                (600..700).collect::<Vec<_>>()
+            );
+        }
+
+        let predicate = col()
+            .less_than(PrimitiveValue::Long(10))
+            .or(col().greater_than_or_equal_to(PrimitiveValue::Long(990)));
+        for concurrency in [1, 4] {
+            assert_eq!(
+                read(predicate.clone(), concurrency).await?,
+                (0..50).chain(950..1000).collect::<Vec<_>>()
+            );
+        }
+
+        let predicate = col().greater_than(PrimitiveValue::Long(1000));
+        assert!(read(predicate, 1).await?.is_empty());
+        Ok(())
+    }
 }
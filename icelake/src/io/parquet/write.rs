@@ -1,12 +1,14 @@
+use std::io::Write;
 use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use opendal::Writer;
-use parquet::arrow::AsyncArrowWriter;
+use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
 use parquet::format::FileMetaData;
+use tokio::io::AsyncWriteExt;
 
 use crate::Result;
 
@@ -21,6 +23,7 @@ pub struct ParquetWriterBuilder {
     /// The intermediate buffer will automatically be resized if necessary
     buffer_size: usize,
     props: Option<WriterProperties>,
+    row_group_size_bytes: Option<usize>,
 }
 
 impl ParquetWriterBuilder {
@@ -32,6 +35,7 @@ impl ParquetWriterBuilder {
 
             buffer_size: 0,
             props: None,
+            row_group_size_bytes: None,
         }
     }
 
@@ -55,16 +59,27 @@ impl ParquetWriterBuilder {
         self
     }
 
+    /// Close a row group once its estimated size reaches `bytes`, in
+    /// addition to the max row group size in rows of writer properties.
+    pub fn with_row_group_size_bytes(mut self, bytes: usize) -> Self {
+        self.row_group_size_bytes = Some(bytes);
+        self
+    }
+
     /// Consume the current builder to build a new writer.
     pub fn build(self) -> Result<ParquetWriter> {
         let writer = TrackWriter::new(self.writer);
         let written_size = writer.get_wrriten_size();
 
-        let writer =
-            AsyncArrowWriter::try_new(writer, self.arrow_schema, self.buffer_size, self.props)?;
+        let buffer = SharedBuffer::new(self.buffer_size);
+        let sync_writer = ArrowWriter::try_new(buffer.clone(), self.arrow_schema, self.props)?;
 
         Ok(ParquetWriter {
+            sync_writer,
             writer,
+            buffer,
+            buffer_size: self.buffer_size,
+            row_group_size_bytes: self.row_group_size_bytes,
             written_size,
         })
     }
@@ -72,9 +87,17 @@ impl ParquetWriterBuilder {
 
 /// ParquetWriter is used to write arrow data into parquet file on storage.
 ///
+/// Encoded data is buffered in memory and flushed to storage once the
+/// buffer reaches its size, like `AsyncArrowWriter` of parquet, but row
+/// groups can also be closed by their size in bytes.
+///
 /// Initiate a new writer with `ParquetWriterBuilder::new()`.
 pub struct ParquetWriter {
-    writer: AsyncArrowWriter<TrackWriter>,
+    sync_writer: ArrowWriter<SharedBuffer>,
+    writer: TrackWriter,
+    buffer: SharedBuffer,
+    buffer_size: usize,
+    row_group_size_bytes: Option<usize>,
     written_size: Arc<AtomicU64>,
 }
 
@@ -83,8 +106,13 @@ impl ParquetWriter {
     ///
     /// Note: It will not guarantee to take effect imediately.
     pub async fn write(&mut self, data: &RecordBatch) -> Result<()> {
-        self.writer.write(data).await?;
-        Ok(())
+        self.sync_writer.write(data)?;
+        if let Some(row_group_size_bytes) = self.row_group_size_bytes {
+            if self.sync_writer.in_progress_size() >= row_group_size_bytes {
+                self.sync_writer.flush()?;
+            }
+        }
+        self.flush_buffer(self.buffer_size).await
     }
 
     /// Write footer, flush rest data and close file.
@@ -92,19 +120,72 @@ impl ParquetWriter {
     /// # Note
     ///
     /// This function must be called before complete the write process.
-    pub async fn close(self) -> Result<(FileMetaData, u64)> {
-        let written_size = self.written_size.clone();
-        let file_metadata = self.writer.close().await?;
-        let written_size = written_size.load(std::sync::atomic::Ordering::SeqCst);
+    pub async fn close(mut self) -> Result<(FileMetaData, u64)> {
+        let file_metadata = self.sync_writer.close()?;
+        Self::flush(&self.buffer, &mut self.writer, 0).await?;
+        self.writer.shutdown().await?;
+        let written_size = self.written_size.load(std::sync::atomic::Ordering::SeqCst);
         Ok((file_metadata, written_size))
     }
 
-    /// Return the written size.
+    /// Return the written size, including data buffered in memory.
     ///
     /// # Note
-    /// The size is incorrect until we call close (data could be still in buffer). It is only used as a suggestion.
+    /// The size of the row group in progress is estimated before it's encoded. It is only used as a suggestion.
     pub fn get_written_size(&self) -> u64 {
         self.written_size.load(std::sync::atomic::Ordering::SeqCst)
+            + self.buffer.len() as u64
+            + self.sync_writer.in_progress_size() as u64
+    }
+
+    async fn flush_buffer(&mut self, buffer_size: usize) -> Result<()> {
+        Self::flush(&self.buffer, &mut self.writer, buffer_size).await
+    }
+
+    /// Flush the buffer into `writer` if its size reaches `buffer_size`.
+    async fn flush(
+        buffer: &SharedBuffer,
+        writer: &mut TrackWriter,
+        buffer_size: usize,
+    ) -> Result<()> {
+        let data = {
+            let mut buffer = buffer.buffer.lock().unwrap();
+            if buffer.is_empty() || buffer.len() < buffer_size {
+                return Ok(());
+            }
+            std::mem::take(&mut *buffer)
+        };
+        writer.write_all(&data).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+/// Buffer shared by the sync arrow writer and [`ParquetWriter`].
+#[derive(Clone)]
+struct SharedBuffer {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl SharedBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Arc::new(Mutex::new(Vec::with_capacity(capacity))),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.buffer.lock().unwrap().len()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Write::write(&mut *self.buffer.lock().unwrap(), buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
@@ -149,4 +230,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn parquet_write_row_group_size_test() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let col = Arc::new(Int64Array::from_iter_values(0..1024)) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)]).unwrap();
+
+        let w = op.writer("test").await?;
+        let mut pw = ParquetWriterBuilder::new(w, to_write.schema())
+            .with_row_group_size_bytes(1024)
+            .build()?;
+        pw.write(&to_write).await?;
+        assert!(pw.get_written_size() > 0);
+        pw.write(&to_write).await?;
+        let (metadata, written_size) = pw.close().await?;
+
+        // Every batch exceeds the row group size.
+        assert_eq!(metadata.row_groups.len(), 2);
+        assert_eq!(metadata.num_rows, 2048);
+        assert_eq!(written_size, op.stat("test").await?.content_length());
+        Ok(())
+    }
 }
@@ -0,0 +1,207 @@
+//! policy module provides [`IoPolicy`] to retry and time out IO of tables
+//! regardless of how their operators are built.
+
+use std::time::Duration;
+
+use opendal::layers::{RetryLayer, TimeoutLayer};
+use opendal::Operator;
+
+/// Kind of IO of a table, each kind can have its own [`IoPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoKind {
+    /// Reads of metadata files, manifest lists and manifest files.
+    Metadata,
+    /// Reads and writes of data files and delete files.
+    Data,
+    /// Writes of manifests and metadata files when committing.
+    Commit,
+}
+
+/// IoPolicy decides how IO is retried and timed out.
+///
+/// Only temporary errors like timeouts and throttling are retried, with
+/// exponential backoff between `min_delay` and `max_delay`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IoPolicy {
+    max_retries: usize,
+    min_delay: Duration,
+    max_delay: Duration,
+    timeout: Option<Duration>,
+}
+
+impl Default for IoPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            min_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            timeout: None,
+        }
+    }
+}
+
+impl IoPolicy {
+    /// Create a policy retrying 3 times with delays between 1 second and 60
+    /// seconds, without timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy never retrying nor timing out.
+    pub fn none() -> Self {
+        Self::default().with_max_retries(0)
+    }
+
+    /// Set the max number of retries, `0` disables retries.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Set the max delay between retries.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the timeout of each attempt of an operation.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the max number of retries.
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// Returns the timeout of each attempt of an operation.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Wrap `op` with layers of this policy.
+    ///
+    /// The timeout layer is inside the retry layer, so timed out attempts
+    /// are retried.
+    pub(crate) fn apply(&self, op: Operator) -> Operator {
+        let op = match self.timeout {
+            Some(timeout) => op.layer(TimeoutLayer::new().with_timeout(timeout)),
+            None => op,
+        };
+        if self.max_retries == 0 {
+            return op;
+        }
+        op.layer(
+            RetryLayer::new()
+                .with_max_times(self.max_retries)
+                .with_min_delay(self.min_delay)
+                .with_max_delay(self.max_delay.max(self.min_delay))
+                .with_jitter(),
+        )
+    }
+}
+
+/// IoPolicies are [`IoPolicy`]s of each [`IoKind`] of a table, IO of kinds
+/// without policies goes to operators directly.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IoPolicies {
+    metadata: Option<IoPolicy>,
+    data: Option<IoPolicy>,
+    commit: Option<IoPolicy>,
+}
+
+impl IoPolicies {
+    /// Create policies of no kind.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy of given kind of IO.
+    pub fn with_policy(mut self, kind: IoKind, policy: IoPolicy) -> Self {
+        *self.slot(kind) = Some(policy);
+        self
+    }
+
+    /// Returns the policy of given kind of IO.
+    pub fn policy(&self, kind: IoKind) -> Option<&IoPolicy> {
+        match kind {
+            IoKind::Metadata => self.metadata.as_ref(),
+            IoKind::Data => self.data.as_ref(),
+            IoKind::Commit => self.commit.as_ref(),
+        }
+    }
+
+    fn slot(&mut self, kind: IoKind) -> &mut Option<IoPolicy> {
+        match kind {
+            IoKind::Metadata => &mut self.metadata,
+            IoKind::Data => &mut self.data,
+            IoKind::Commit => &mut self.commit,
+        }
+    }
+
+    /// Wrap `op` with layers of the policy of given kind of IO.
+    pub(crate) fn apply(&self, kind: IoKind, op: Operator) -> Operator {
+        match self.policy(kind) {
+            Some(policy) => policy.apply(op),
+            None => op,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::{Result, Table};
+
+    #[tokio::test]
+    async fn test_io_policies() -> Result<()> {
+        let policies = IoPolicies::new()
+            .with_policy(
+                IoKind::Metadata,
+                IoPolicy::new().with_timeout(Duration::from_secs(10)),
+            )
+            .with_policy(IoKind::Data, IoPolicy::new().with_max_retries(5))
+            .with_policy(IoKind::Commit, IoPolicy::none());
+        assert_eq!(policies.policy(IoKind::Data).unwrap().max_retries(), 5);
+        assert_eq!(
+            policies.policy(IoKind::Metadata).unwrap().timeout(),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(policies.policy(IoKind::Commit).unwrap().max_retries(), 0);
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        table.set_io_policies(policies);
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let mut tx = table.new_transaction();
+        tx.append_file(writer.close().await?);
+        tx.commit().await?;
+        assert!(!table.refresh().await?);
+
+        let files = table.current_data_files().await?;
+        assert_eq!(files.len(), 4);
+        let batches: Vec<_> = table.read_data_files(&files)?.try_collect().await?;
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 4);
+        Ok(())
+    }
+}
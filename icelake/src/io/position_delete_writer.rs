@@ -0,0 +1,345 @@
+//! position_delete_writer is used to write position delete files, which
+//! delete rows of data files by their positions.
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, Int64Array, StringArray};
+use arrow::compute::{concat_batches, lexsort_to_indices, take, SortColumn};
+use arrow::datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use opendal::Operator;
+
+use super::data_file_writer::DataFileWriter;
+use super::location_generator::DataFileLocationGenerator;
+use super::writer_config::WriterConfig;
+use crate::types::{Any, DataContentType, DataFile, Field, Primitive, Schema, StructValue};
+use crate::{Error, ErrorKind, Result};
+
+/// Field id of `file_path` column in position delete files.
+pub const POSITION_DELETE_FILE_PATH_FIELD_ID: i32 = 2147483546;
+/// Field id of `pos` column in position delete files.
+pub const POSITION_DELETE_POS_FIELD_ID: i32 = 2147483545;
+/// Field id of `row` column in position delete files.
+pub const POSITION_DELETE_ROW_FIELD_ID: i32 = 2147483544;
+
+/// A writer of position delete files with columns `file_path` and `pos`,
+/// and an optional `row` column storing the deleted rows.
+///
+/// Iceberg requires deletes in a file to be sorted by `file_path` then
+/// `pos`, so deletes are buffered in memory and sorted on close. Files are
+/// split by the target file size like data files.
+///
+/// Written files are committed like data files by
+/// [`crate::transaction::Transaction::append_file`], which adds them into
+/// delete manifests.
+pub struct PositionDeleteWriter {
+    writer: DataFileWriter,
+    schema: SchemaRef,
+    /// Rows written to the file writer at a time.
+    rows_divisor: usize,
+    batches: Vec<RecordBatch>,
+}
+
+impl PositionDeleteWriter {
+    /// Create a new `PositionDeleteWriter`.
+    ///
+    /// `row_schema` is the arrow schema of deleted rows if they are stored.
+    pub async fn try_new(
+        operator: Operator,
+        location_generator: DataFileLocationGenerator,
+        row_schema: Option<SchemaRef>,
+        config: WriterConfig,
+    ) -> Result<Self> {
+        let mut fields = vec![
+            ArrowField::new("file_path", DataType::Utf8, false),
+            ArrowField::new("pos", DataType::Int64, false),
+        ];
+        if let Some(row_schema) = row_schema {
+            fields.push(ArrowField::new(
+                "row",
+                DataType::Struct(row_schema.fields().clone()),
+                true,
+            ));
+        }
+        let schema = Arc::new(ArrowSchema::new(fields));
+        let rows_divisor = config.rows_divisor();
+
+        let writer = DataFileWriter::try_new(operator, location_generator, schema.clone(), config)
+            .await?
+            .with_content(DataContentType::PostionDeletes)
+            .with_schema(Schema {
+                schema_id: 0,
+                identifier_field_ids: None,
+                fields: vec![
+                    Field::required(
+                        POSITION_DELETE_FILE_PATH_FIELD_ID,
+                        "file_path",
+                        Any::Primitive(Primitive::String),
+                    ),
+                    Field::required(
+                        POSITION_DELETE_POS_FIELD_ID,
+                        "pos",
+                        Any::Primitive(Primitive::Long),
+                    ),
+                ],
+            });
+        Ok(Self {
+            writer,
+            schema,
+            rows_divisor,
+            batches: vec![],
+        })
+    }
+
+    /// Set partition values of written files, which must be the partition
+    /// of deleted data files.
+    pub fn with_partition(mut self, partition: StructValue) -> Self {
+        self.writer = self.writer.with_partition(partition);
+        self
+    }
+
+    /// Arrow schema of batches accepted by [`PositionDeleteWriter::write`].
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Delete rows at `positions` of the data file.
+    ///
+    /// Only available if deleted rows are not stored, use
+    /// [`PositionDeleteWriter::write`] with the rows instead.
+    pub fn delete(
+        &mut self,
+        file_path: &str,
+        positions: impl IntoIterator<Item = i64>,
+    ) -> Result<()> {
+        if self.schema.fields().len() != 2 {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Deleted rows are required by the position delete writer",
+            ));
+        }
+        let pos = Int64Array::from_iter_values(positions);
+        let file_path = StringArray::from_iter_values(std::iter::repeat_n(file_path, pos.len()));
+        self.batches.push(RecordBatch::try_new(
+            self.schema.clone(),
+            vec![Arc::new(file_path) as ArrayRef, Arc::new(pos) as ArrayRef],
+        )?);
+        Ok(())
+    }
+
+    /// Write a batch of deletes, columns must be the same as
+    /// [`PositionDeleteWriter::schema`].
+    pub fn write(&mut self, batch: RecordBatch) -> Result<()> {
+        let batch =
+            RecordBatch::try_new(self.schema.clone(), batch.columns().to_vec()).map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Batch doesn't match the schema of position delete files",
+                )
+                .set_source(e)
+            })?;
+        self.batches.push(batch);
+        Ok(())
+    }
+
+    /// Sort and write all deletes, then return written delete files.
+    pub async fn close(mut self) -> Result<Vec<DataFile>> {
+        let batch = concat_batches(&self.schema, &self.batches)?;
+        let indices = lexsort_to_indices(
+            &[
+                SortColumn {
+                    values: batch.column(0).clone(),
+                    options: None,
+                },
+                SortColumn {
+                    values: batch.column(1).clone(),
+                    options: None,
+                },
+            ],
+            None,
+        )?;
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|v| take(v.as_ref(), &indices, None))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+
+        // Write by slices so that files can be split by size.
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let len = self.rows_divisor.min(batch.num_rows() - offset);
+            self.writer.write(batch.slice(offset, len)).await?;
+            offset += len;
+        }
+        let mut delete_files = self.writer.close().await?;
+
+        // Bounds of `file_path` let readers skip delete files of other data
+        // files, only recorded if all deletes are of a single data file.
+        // Collected bounds are replaced since they may be truncated.
+        let file_paths = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .expect("file_path must be a string array");
+        let bounds = (!file_paths.is_empty()
+            && file_paths.value(0) == file_paths.value(file_paths.len() - 1))
+        .then(|| {
+            [(
+                POSITION_DELETE_FILE_PATH_FIELD_ID,
+                file_paths.value(0).as_bytes().to_vec(),
+            )]
+            .into_iter()
+            .collect()
+        });
+        for delete_file in &mut delete_files {
+            delete_file.lower_bounds = bounds.clone();
+            delete_file.upper_bounds = bounds.clone();
+        }
+        Ok(delete_files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use arrow::array::StructArray;
+    use bytes::Bytes;
+    use opendal::services::Memory;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::types::parse_table_metadata;
+
+    fn location_generator() -> DataFileLocationGenerator {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v1.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        metadata.location = "/tmp/table".to_string();
+        DataFileLocationGenerator::try_new(&metadata, 0, 0, None).unwrap()
+    }
+
+    fn memory_operator() -> Operator {
+        let mut builder = Memory::default();
+        builder.root("/tmp/table");
+        Operator::new(builder).unwrap().finish()
+    }
+
+    async fn read(op: &Operator, delete_file: &DataFile) -> RecordBatch {
+        let bs = op
+            .read(delete_file.file_path.strip_prefix("/tmp/table").unwrap())
+            .await
+            .unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(bs))
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        concat_batches(&batches[0].schema(), &batches).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_position_delete_writer() {
+        let op = memory_operator();
+        let mut writer = PositionDeleteWriter::try_new(
+            op.clone(),
+            location_generator(),
+            None,
+            WriterConfig::from_properties(&HashMap::new()).unwrap(),
+        )
+        .await
+        .unwrap();
+        writer.delete("/tmp/table/data/b.parquet", [3, 1]).unwrap();
+        writer.delete("/tmp/table/data/a.parquet", [2]).unwrap();
+        let delete_files = writer.close().await.unwrap();
+
+        assert_eq!(delete_files.len(), 1);
+        let delete_file = &delete_files[0];
+        assert_eq!(delete_file.content, DataContentType::PostionDeletes);
+        assert_eq!(delete_file.record_count, 3);
+        assert!(delete_file.lower_bounds.is_none());
+
+        // Deletes are sorted by file path and position.
+        let batch = read(&op, delete_file).await;
+        let file_paths = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let pos = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(
+            file_paths.iter().flatten().collect::<Vec<_>>(),
+            vec![
+                "/tmp/table/data/a.parquet",
+                "/tmp/table/data/b.parquet",
+                "/tmp/table/data/b.parquet"
+            ]
+        );
+        assert_eq!(pos.values().to_vec(), vec![2, 1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_position_delete_writer_with_row() {
+        let op = memory_operator();
+        let row_schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "id",
+            DataType::Int64,
+            true,
+        )]));
+        let mut writer = PositionDeleteWriter::try_new(
+            op.clone(),
+            location_generator(),
+            Some(row_schema.clone()),
+            WriterConfig::from_properties(&HashMap::new()).unwrap(),
+        )
+        .await
+        .unwrap();
+        assert!(writer.delete("/tmp/table/data/a.parquet", [0]).is_err());
+
+        let rows = StructArray::from(vec![(
+            Arc::new(row_schema.field(0).clone()),
+            Arc::new(Int64Array::from(vec![11, 10])) as ArrayRef,
+        )]);
+        let batch = RecordBatch::try_new(
+            writer.schema(),
+            vec![
+                Arc::new(StringArray::from(vec!["/tmp/table/data/a.parquet"; 2])),
+                Arc::new(Int64Array::from(vec![1, 0])),
+                Arc::new(rows),
+            ],
+        )
+        .unwrap();
+        writer.write(batch).unwrap();
+        let delete_files = writer.close().await.unwrap();
+
+        assert_eq!(delete_files.len(), 1);
+        assert_eq!(
+            delete_files[0].lower_bounds,
+            Some(HashMap::from([(
+                POSITION_DELETE_FILE_PATH_FIELD_ID,
+                b"/tmp/table/data/a.parquet".to_vec()
+            )]))
+        );
+        let batch = read(&op, &delete_files[0]).await;
+        let rows = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StructArray>()
+            .unwrap();
+        let ids = rows
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.values().to_vec(), vec![10, 11]);
+    }
+}
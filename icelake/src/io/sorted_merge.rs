@@ -0,0 +1,412 @@
+//! sorted_merge module merges sorted record batch streams into a single
+//! sorted stream.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use arrow::array::ArrayRef;
+use arrow::compute::{
+    concat_batches, interleave, lexsort_to_indices, take, SortColumn, SortOptions,
+};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{OwnedRow, RowConverter, Rows, SortField as RowSortField};
+use futures::{StreamExt, TryStreamExt};
+
+use super::data_file_reader::RecordBatchStream;
+use crate::types::{NullOrder, Schema, SortDirection, SortOrder, Transform};
+use crate::{Error, ErrorKind, Result};
+
+/// Default number of rows of batches produced by merge.
+const DEFAULT_BATCH_SIZE: usize = 1024;
+
+/// SortColumns is the sort order resolved into column names.
+#[derive(Debug, Clone)]
+pub struct SortColumns {
+    columns: Vec<(String, SortOptions)>,
+}
+
+impl SortColumns {
+    /// Resolve iceberg sort order against schema.
+    ///
+    /// Only identity transforms of top level columns are supported.
+    pub fn try_new(sort_order: &SortOrder, schema: &Schema) -> Result<Self> {
+        if sort_order.fields.is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Sort order {} is unsorted", sort_order.order_id),
+            ));
+        }
+
+        let columns = sort_order
+            .fields
+            .iter()
+            .map(|field| {
+                if field.transform != Transform::Identity {
+                    return Err(Error::new(
+                        ErrorKind::IcebergFeatureUnsupported,
+                        format!("Merging files sorted by transform {}", field.transform),
+                    ));
+                }
+                let column = schema
+                    .fields
+                    .iter()
+                    .find(|v| v.id == field.source_column_id)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergFeatureUnsupported,
+                            format!(
+                                "Sort column {} is not a top level column",
+                                field.source_column_id
+                            ),
+                        )
+                    })?;
+                let options = SortOptions {
+                    descending: field.direction == SortDirection::DESC,
+                    nulls_first: field.null_order == NullOrder::First,
+                };
+                Ok((column.name.clone(), options))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { columns })
+    }
+
+    fn sort_arrays(&self, batch: &RecordBatch) -> Result<Vec<SortColumn>> {
+        self.columns
+            .iter()
+            .map(|(name, options)| {
+                Ok(SortColumn {
+                    values: batch.column(batch.schema().index_of(name)?).clone(),
+                    options: Some(*options),
+                })
+            })
+            .collect()
+    }
+
+    /// Concat batches of the same schema into a single sorted batch.
+    pub fn sort_batches(&self, batches: &[RecordBatch]) -> Result<Option<RecordBatch>> {
+        let Some(first) = batches.first() else {
+            return Ok(None);
+        };
+        let batch = concat_batches(&first.schema(), batches)?;
+        let indices = lexsort_to_indices(&self.sort_arrays(&batch)?, None)?;
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|c| take(c.as_ref(), &indices, None))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(Some(RecordBatch::try_new(batch.schema(), columns)?))
+    }
+
+    fn row_converter(&self, batch: &RecordBatch) -> Result<RowConverter> {
+        let fields = self
+            .sort_arrays(batch)?
+            .into_iter()
+            .map(|v| {
+                RowSortField::new_with_options(v.values.data_type().clone(), v.options.unwrap())
+            })
+            .collect();
+        Ok(RowConverter::new(fields)?)
+    }
+}
+
+/// Sort a whole stream in memory, used for files not written in order.
+pub fn sort_stream(input: RecordBatchStream, sort_columns: SortColumns) -> RecordBatchStream {
+    futures::stream::once(async move {
+        let batches: Vec<RecordBatch> = input.try_collect().await?;
+        sort_columns.sort_batches(&batches)
+    })
+    .filter_map(|v: Result<Option<RecordBatch>>| async move { v.transpose() })
+    .boxed()
+}
+
+/// Current batch of an input.
+struct Cursor {
+    /// Index of the batch in `MergeState::batches`.
+    slot: usize,
+    rows: Rows,
+    offset: usize,
+}
+
+struct MergeState {
+    inputs: Vec<RecordBatchStream>,
+    cursors: Vec<Option<Cursor>>,
+    heap: BinaryHeap<Reverse<(OwnedRow, usize)>>,
+    /// Batches referenced by cursors and pending output rows.
+    batches: Vec<RecordBatch>,
+    sort_columns: SortColumns,
+    converter: Option<RowConverter>,
+    batch_size: usize,
+    initialized: bool,
+}
+
+impl MergeState {
+    /// Load next non-empty batch of input `i`, push its first row into heap.
+    async fn advance_input(&mut self, i: usize) -> Result<()> {
+        self.cursors[i] = None;
+        while let Some(batch) = self.inputs[i].try_next().await? {
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            if self.converter.is_none() {
+                self.converter = Some(self.sort_columns.row_converter(&batch)?);
+            }
+            let arrays = self
+                .sort_columns
+                .sort_arrays(&batch)?
+                .into_iter()
+                .map(|v| v.values)
+                .collect::<Vec<ArrayRef>>();
+            let rows = self.converter.as_mut().unwrap().convert_columns(&arrays)?;
+            self.heap.push(Reverse((rows.row(0).owned(), i)));
+
+            self.batches.push(batch);
+            self.cursors[i] = Some(Cursor {
+                slot: self.batches.len() - 1,
+                rows,
+                offset: 0,
+            });
+            break;
+        }
+        Ok(())
+    }
+
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if !self.initialized {
+            self.initialized = true;
+            for i in 0..self.inputs.len() {
+                self.advance_input(i).await?;
+            }
+        }
+
+        let mut indices = Vec::with_capacity(self.batch_size);
+        while indices.len() < self.batch_size {
+            let Some(Reverse((_, i))) = self.heap.pop() else {
+                break;
+            };
+            let cursor = self.cursors[i].as_mut().expect("cursor in heap must exist");
+            indices.push((cursor.slot, cursor.offset));
+            cursor.offset += 1;
+            if cursor.offset < cursor.rows.num_rows() {
+                let row = cursor.rows.row(cursor.offset).owned();
+                self.heap.push(Reverse((row, i)));
+            } else {
+                self.advance_input(i).await?;
+            }
+        }
+
+        if indices.is_empty() {
+            return Ok(None);
+        }
+
+        let schema = self.batches[indices[0].0].schema();
+        let columns = (0..schema.fields().len())
+            .map(|c| {
+                let arrays = self
+                    .batches
+                    .iter()
+                    .map(|b| b.column(c).as_ref())
+                    .collect::<Vec<_>>();
+                interleave(&arrays, &indices)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let output = RecordBatch::try_new(schema, columns)?;
+
+        self.compact_batches();
+        Ok(Some(output))
+    }
+
+    /// Drop batches that are not referenced by cursors anymore.
+    fn compact_batches(&mut self) {
+        let mut batches = Vec::with_capacity(self.cursors.len());
+        for cursor in self.cursors.iter_mut().flatten() {
+            batches.push(self.batches[cursor.slot].clone());
+            cursor.slot = batches.len() - 1;
+        }
+        self.batches = batches;
+    }
+}
+
+/// Merge streams sorted by `sort_columns` into a single sorted stream.
+///
+/// All inputs must have the same schema.
+pub fn sorted_merge(
+    inputs: Vec<RecordBatchStream>,
+    sort_columns: SortColumns,
+) -> RecordBatchStream {
+    let cursors = inputs.iter().map(|_| None).collect();
+    let state = MergeState {
+        inputs,
+        cursors,
+        heap: BinaryHeap::new(),
+        batches: vec![],
+        sort_columns,
+        converter: None,
+        batch_size: DEFAULT_BATCH_SIZE,
+        initialized: false,
+    };
+
+    futures::stream::try_unfold(state, |mut state| async move {
+        Ok(state.next_batch().await?.map(|batch| (batch, state)))
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int32Array, StringArray};
+
+    use super::*;
+    use crate::types::{Any, Field, NullOrder, Primitive, SortField};
+
+    fn batch(ids: Vec<Option<i32>>, names: Vec<&str>) -> RecordBatch {
+        RecordBatch::try_from_iter([
+            ("id", Arc::new(Int32Array::from(ids)) as ArrayRef),
+            ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    fn stream(batches: Vec<RecordBatch>) -> RecordBatchStream {
+        futures::stream::iter(batches.into_iter().map(Ok)).boxed()
+    }
+
+    fn sort_columns(direction: SortDirection, null_order: NullOrder) -> SortColumns {
+        let schema = Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                Field {
+                    id: 1,
+                    name: "id".to_string(),
+                    required: false,
+                    field_type: Any::Primitive(Primitive::Int),
+                    comment: None,
+                    initial_default: None,
+                    write_default: None,
+                },
+                Field {
+                    id: 2,
+                    name: "name".to_string(),
+                    required: false,
+                    field_type: Any::Primitive(Primitive::String),
+                    comment: None,
+                    initial_default: None,
+                    write_default: None,
+                },
+            ],
+        };
+        let sort_order = SortOrder {
+            order_id: 1,
+            fields: vec![SortField {
+                source_column_id: 1,
+                transform: Transform::Identity,
+                direction,
+                null_order,
+            }],
+        };
+        SortColumns::try_new(&sort_order, &schema).unwrap()
+    }
+
+    fn ids(batches: &[RecordBatch]) -> Vec<Option<i32>> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                b.column(0)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap()
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sorted_merge() {
+        let inputs = vec![
+            stream(vec![
+                batch(vec![Some(1), Some(4)], vec!["a", "b"]),
+                batch(vec![], vec![]),
+                batch(vec![Some(7)], vec!["c"]),
+            ]),
+            stream(vec![batch(
+                vec![Some(2), Some(3), Some(8)],
+                vec!["d", "e", "f"],
+            )]),
+            stream(vec![]),
+            stream(vec![batch(
+                vec![None, Some(5), Some(6)],
+                vec!["g", "h", "i"],
+            )]),
+        ];
+
+        let batches: Vec<_> =
+            sorted_merge(inputs, sort_columns(SortDirection::ASC, NullOrder::First))
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(
+            ids(&batches),
+            vec![
+                None,
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+                Some(8)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sorted_merge_desc_with_small_batch() {
+        let inputs = vec![
+            stream(vec![batch(
+                vec![Some(9), Some(3), None],
+                vec!["a", "b", "c"],
+            )]),
+            stream(vec![batch(vec![Some(5), Some(4)], vec!["d", "e"])]),
+        ];
+        let cursors = inputs.iter().map(|_| None).collect();
+        let mut state = MergeState {
+            inputs,
+            cursors,
+            heap: BinaryHeap::new(),
+            batches: vec![],
+            sort_columns: sort_columns(SortDirection::DESC, NullOrder::Last),
+            converter: None,
+            batch_size: 2,
+            initialized: false,
+        };
+
+        let mut batches = vec![];
+        while let Some(batch) = state.next_batch().await.unwrap() {
+            assert!(batch.num_rows() <= 2);
+            batches.push(batch);
+        }
+        assert_eq!(
+            ids(&batches),
+            vec![Some(9), Some(5), Some(4), Some(3), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_stream() {
+        let input = stream(vec![
+            batch(vec![Some(3), Some(1)], vec!["a", "b"]),
+            batch(vec![Some(2)], vec!["c"]),
+        ]);
+        let batches: Vec<_> =
+            sort_stream(input, sort_columns(SortDirection::ASC, NullOrder::First))
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(ids(&batches), vec![Some(1), Some(2), Some(3)]);
+    }
+}
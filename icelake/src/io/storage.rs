@@ -0,0 +1,360 @@
+//! storage module builds opendal operators for table locations, the
+//! storage service is inferred from the scheme of the location.
+//!
+//! | Scheme | Service |
+//! | --- | --- |
+//! | no scheme, `file://` | local filesystem |
+//! | `s3://`, `s3a://`, `s3n://` | s3 |
+//! | `gs://`, `gcs://` | gcs |
+//! | `oss://` | oss |
+//! | `azblob://` | azure blob storage, the host is the container |
+//! | `hdfs://`, `webhdfs://` | hdfs through its WebHDFS gateway |
+//!
+//! Credentials and endpoints are read from configurations like
+//! [`crate::config::S3_ACCESS_KEY_ID`], which can be captured from
+//! environment variables by [`crate::config::Config::from_env`], or set
+//! explicitly by [`FileIO`].
+
+use std::collections::HashMap;
+
+use opendal::layers::LoggingLayer;
+use opendal::services::{Azblob, Fs, Gcs, Oss, Webhdfs, S3};
+use opendal::Operator;
+use url::Url;
+
+use crate::config::*;
+use crate::{Error, ErrorKind, Result};
+
+/// Build an operator rooted at `location` with given configurations.
+///
+/// # Examples
+///
+/// ```
+/// use icelake::config::{Config, S3_REGION};
+/// use icelake::io::storage::build_operator;
+///
+/// let config = Config::from_env().with_option(S3_REGION, "us-east-1");
+/// let op = build_operator("s3://bucket/warehouse/db/table", &config.overrides())?;
+/// # Ok::<(), icelake::Error>(())
+/// ```
+pub fn build_operator(location: &str, props: &HashMap<String, String>) -> Result<Operator> {
+    let Some(url) = parse_url(location) else {
+        let mut builder = Fs::default();
+        builder.root(location.strip_prefix("file://").unwrap_or(location));
+        return finish(builder);
+    };
+
+    let bucket = url.host_str().unwrap_or_default();
+    let root = match url.path() {
+        "" => "/",
+        v => v,
+    };
+    let get = |key: &str| props.get(key).map(String::as_str);
+    match url.scheme() {
+        "file" => {
+            let mut builder = Fs::default();
+            builder.root(root);
+            finish(builder)
+        }
+        "s3" | "s3a" | "s3n" => {
+            let mut builder = S3::default();
+            builder.bucket(bucket);
+            builder.root(root);
+            if let Some(v) = get(S3_REGION) {
+                builder.region(v);
+            }
+            if let Some(v) = get(S3_ENDPOINT) {
+                builder.endpoint(v);
+            }
+            if let Some(v) = get(S3_ACCESS_KEY_ID) {
+                builder.access_key_id(v);
+            }
+            if let Some(v) = get(S3_SECRET_ACCESS_KEY) {
+                builder.secret_access_key(v);
+            }
+            if let Some(v) = get(S3_SESSION_TOKEN) {
+                builder.security_token(v);
+            }
+            if let Some(v) = get(S3_ASSUME_ROLE_ARN) {
+                builder.role_arn(v);
+            }
+            if let Some(v) = get(S3_ASSUME_ROLE_EXTERNAL_ID) {
+                builder.external_id(v);
+            }
+            if let Some(v) = get(S3_PATH_STYLE_ACCESS) {
+                let path_style: bool = v.trim().parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid value of {S3_PATH_STYLE_ACCESS}: {v}"),
+                    )
+                    .set_source(e)
+                })?;
+                if !path_style {
+                    builder.enable_virtual_host_style();
+                }
+            }
+            finish(builder)
+        }
+        "gs" | "gcs" => {
+            let mut builder = Gcs::default();
+            builder.bucket(bucket);
+            builder.root(root);
+            if let Some(v) = get(GCS_ENDPOINT) {
+                builder.endpoint(v);
+            }
+            if let Some(v) = get(GCS_CREDENTIAL) {
+                builder.credential(v);
+            }
+            if let Some(v) = get(GCS_CREDENTIAL_PATH) {
+                builder.credential_path(v);
+            }
+            finish(builder)
+        }
+        "oss" => {
+            let mut builder = Oss::default();
+            builder.bucket(bucket);
+            builder.root(root);
+            if let Some(v) = get(OSS_ENDPOINT) {
+                builder.endpoint(v);
+            }
+            if let Some(v) = get(OSS_ACCESS_KEY_ID) {
+                builder.access_key_id(v);
+            }
+            if let Some(v) = get(OSS_ACCESS_KEY_SECRET) {
+                builder.access_key_secret(v);
+            }
+            finish(builder)
+        }
+        "azblob" => {
+            let mut builder = Azblob::default();
+            builder.container(bucket);
+            builder.root(root);
+            if let Some(v) = get(ADLS_ACCOUNT_NAME) {
+                builder.account_name(v);
+            }
+            if let Some(v) = get(ADLS_ACCOUNT_KEY) {
+                builder.account_key(v);
+            }
+            match (get(ADLS_ENDPOINT), get(ADLS_ACCOUNT_NAME)) {
+                (Some(v), _) => {
+                    builder.endpoint(v);
+                }
+                (None, Some(account)) => {
+                    builder.endpoint(&format!("https://{account}.blob.core.windows.net"));
+                }
+                (None, None) => {}
+            }
+            finish(builder)
+        }
+        "hdfs" | "webhdfs" => {
+            let mut builder = Webhdfs::default();
+            builder.root(root);
+            match get(HDFS_WEBHDFS_ENDPOINT) {
+                Some(v) => builder.endpoint(v),
+                None => builder.endpoint(&format!("http://{bucket}:9870")),
+            };
+            finish(builder)
+        }
+        scheme => Err(Error::new(
+            ErrorKind::IcebergFeatureUnsupported,
+            format!("Storage of scheme {scheme}"),
+        )
+        .with_context("location", location)),
+    }
+}
+
+/// FileIO holds storage configurations and builds operators of table
+/// locations by them.
+///
+/// Configurations use the same keys as [`crate::config`], options set by
+/// methods of FileIO take precedence over the config it's created from.
+///
+/// # Examples
+///
+/// ```no_run
+/// use icelake::io::storage::FileIO;
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let file_io = FileIO::new()
+///     .with_s3_region("us-east-1")
+///     .with_s3_endpoint("http://127.0.0.1:9000")
+///     .with_s3_credential("access-key", "secret-key", None)
+///     .with_s3_path_style_access(true);
+/// let table = icelake::Table::open_with_file_io("s3://bucket/db/table", &file_io).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIO {
+    props: HashMap<String, String>,
+}
+
+impl FileIO {
+    /// Create a FileIO without configurations, credentials will be loaded
+    /// by storage services themselves if possible.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a FileIO with storage configurations in the config.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            props: config.overrides(),
+        }
+    }
+
+    /// Set a storage configuration.
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.props.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set region of s3 storage.
+    pub fn with_s3_region(self, region: impl Into<String>) -> Self {
+        self.with_property(S3_REGION, region)
+    }
+
+    /// Set endpoint of s3 storage, like `http://127.0.0.1:9000` for minio.
+    pub fn with_s3_endpoint(self, endpoint: impl Into<String>) -> Self {
+        self.with_property(S3_ENDPOINT, endpoint)
+    }
+
+    /// Set static credential of s3 storage.
+    pub fn with_s3_credential(
+        self,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        let file_io = self
+            .with_property(S3_ACCESS_KEY_ID, access_key_id)
+            .with_property(S3_SECRET_ACCESS_KEY, secret_access_key);
+        match session_token {
+            Some(token) => file_io.with_property(S3_SESSION_TOKEN, token),
+            None => file_io,
+        }
+    }
+
+    /// Assume the role to access s3 storage.
+    pub fn with_s3_assume_role(
+        self,
+        role_arn: impl Into<String>,
+        external_id: Option<String>,
+    ) -> Self {
+        let file_io = self.with_property(S3_ASSUME_ROLE_ARN, role_arn);
+        match external_id {
+            Some(id) => file_io.with_property(S3_ASSUME_ROLE_EXTERNAL_ID, id),
+            None => file_io,
+        }
+    }
+
+    /// Set whether to access s3 buckets by path style urls, see
+    /// [`S3_PATH_STYLE_ACCESS`].
+    pub fn with_s3_path_style_access(self, enabled: bool) -> Self {
+        self.with_property(S3_PATH_STYLE_ACCESS, enabled.to_string())
+    }
+
+    /// Returns storage configurations of this FileIO.
+    pub fn properties(&self) -> &HashMap<String, String> {
+        &self.props
+    }
+
+    /// Build an operator rooted at the location.
+    pub fn operator(&self, location: &str) -> Result<Operator> {
+        build_operator(location, &self.props)
+    }
+}
+
+/// Returns the url of the location, or `None` for local paths.
+fn parse_url(location: &str) -> Option<Url> {
+    if !location.contains("://") {
+        return None;
+    }
+    Url::parse(location).ok()
+}
+
+fn finish(builder: impl opendal::Builder) -> Result<Operator> {
+    Ok(Operator::new(builder)?
+        .layer(LoggingLayer::default())
+        .finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use opendal::Scheme;
+
+    use super::*;
+
+    fn props(kvs: &[(&str, &str)]) -> HashMap<String, String> {
+        kvs.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_operator() {
+        let op = build_operator("/tmp/db/table", &HashMap::new()).unwrap();
+        assert_eq!(op.info().scheme(), Scheme::Fs);
+        assert_eq!(op.info().root(), "/tmp/db/table");
+        let op = build_operator("file:///tmp/db/table", &HashMap::new()).unwrap();
+        assert_eq!(op.info().scheme(), Scheme::Fs);
+        assert_eq!(op.info().root(), "/tmp/db/table");
+
+        let op = build_operator(
+            "s3a://bucket/db/table",
+            &props(&[(S3_REGION, "us-east-1"), (S3_ACCESS_KEY_ID, "ak")]),
+        )
+        .unwrap();
+        assert_eq!(op.info().scheme(), Scheme::S3);
+        assert_eq!(op.info().name(), "bucket");
+        assert_eq!(op.info().root(), "/db/table/");
+
+        let op = build_operator(
+            "oss://bucket/t",
+            &props(&[(OSS_ENDPOINT, "http://oss.local")]),
+        )
+        .unwrap();
+        assert_eq!(op.info().scheme(), Scheme::Oss);
+
+        let op = build_operator(
+            "azblob://container/t",
+            &props(&[(ADLS_ACCOUNT_NAME, "account"), (ADLS_ACCOUNT_KEY, "a2V5")]),
+        )
+        .unwrap();
+        assert_eq!(op.info().scheme(), Scheme::Azblob);
+        assert_eq!(op.info().name(), "container");
+
+        let op = build_operator("hdfs://namenode/warehouse/t", &HashMap::new()).unwrap();
+        assert_eq!(op.info().scheme(), Scheme::Webhdfs);
+        assert_eq!(op.info().root(), "/warehouse/t/");
+
+        assert!(build_operator("unknown://bucket/t", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_file_io() {
+        let config = Config::new()
+            .with_option(S3_REGION, "us-west-2")
+            .with_option(S3_ACCESS_KEY_ID, "config");
+        let file_io = FileIO::from_config(&config)
+            .with_s3_region("us-east-1")
+            .with_s3_endpoint("http://127.0.0.1:9000")
+            .with_s3_assume_role("arn:aws:iam::123456789012:role/icelake", None)
+            .with_s3_path_style_access(false);
+        assert_eq!(file_io.properties()[S3_REGION], "us-east-1");
+        assert_eq!(file_io.properties()[S3_ACCESS_KEY_ID], "config");
+        assert!(!file_io
+            .properties()
+            .contains_key(S3_ASSUME_ROLE_EXTERNAL_ID));
+
+        let op = file_io.operator("s3://bucket/db/table").unwrap();
+        assert_eq!(op.info().scheme(), Scheme::S3);
+        assert_eq!(op.info().name(), "bucket");
+
+        assert!(FileIO::new()
+            .with_s3_region("us-east-1")
+            .with_property(S3_PATH_STYLE_ACCESS, "maybe")
+            .operator("s3://bucket/db/table")
+            .is_err());
+    }
+}
@@ -1,15 +1,39 @@
 //! task_writer module provide a task writer for writing data in a table.
 //! table writer used directly by the compute engine.
 
-use arrow::datatypes::Schema as ArrowSchema;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array, FixedSizeBinaryArray,
+    Float32Array, Float64Array, Int32Array, Int64Array, LargeBinaryArray, LargeStringArray,
+    StringArray, StructArray, Time64MicrosecondArray, TimestampMicrosecondArray,
+    TimestampNanosecondArray, UInt32Array,
+};
+use arrow::compute::take;
+use arrow::datatypes::{DataType, Schema as ArrowSchema};
 use arrow::record_batch::RecordBatch;
+use arrow::row::{OwnedRow, Row, RowConverter, Rows, SortField};
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
 use opendal::Operator;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded;
+use uuid::Uuid;
 
 use super::data_file_writer::DataFileWriter;
 use super::location_generator;
+use super::sorted_merge::SortColumns;
+use super::writer_config::WriterConfig;
 use crate::error::Result;
 use crate::io::location_generator::DataFileLocationGenerator;
-use crate::types::{DataFile, TableMetadata};
+use crate::types::expression::timestamp_from_nanos;
+use crate::types::{
+    coerce_batch, create_transform_function, schema_to_arrow_schema, Any, AnyValue,
+    BoxedTransformFunction, DataContentType, DataFile, PartitionSpec, Primitive, PrimitiveValue,
+    Schema, Struct, StructValue, StructValueBuilder, TableMetadata,
+};
+use crate::{Error, ErrorKind};
 
 /// `TaskWriter` used to write data for a table.
 ///
@@ -17,42 +41,44 @@ use crate::types::{DataFile, TableMetadata};
 /// partitioned task writer. The partition task writer will split the data according
 /// the partition key and write them using different data file writer.
 ///
+/// If [`WriterConfig::fanout_enabled`] is false, the partitioned task writer
+/// is a [`ClusteredWriter`] instead, which requires input clustered by
+/// partitions.
+///
 /// If the table metadata has no partition spec, it will create a unpartitioned
 /// task writer. The unpartitioned task writer will write all data using a single
 /// data file writer.
+///
+/// If [`WriterConfig::sort_enabled`], rows of each data file are sorted by
+/// the default sort order of the table.
 pub enum TaskWriter {
     /// Unpartitioned task writer
     Unpartitioned(UnpartitionedWriter),
-    // Partitioned(PartitionedTaskWriter),
+    /// Fanout partitioned task writer
+    Partitioned(FanoutPartitionedWriter),
+    /// Clustered partitioned task writer
+    Clustered(Box<ClusteredWriter>),
 }
 
 impl TaskWriter {
     /// Create a new `TaskWriter`.
+    ///
+    /// `operator` is used to write data files, it must be rooted at the table
+    /// location or the data location (`write.data.path`) of the table.
     pub async fn try_new(
         table_metadata: TableMetadata,
         operator: Operator,
         partition_id: usize,
         task_id: usize,
         suffix: Option<String>,
+        config: WriterConfig,
     ) -> Result<Self> {
-        let schema: ArrowSchema = table_metadata
-            .schemas
-            .clone()
-            .into_iter()
-            .find(|schema| schema.schema_id == table_metadata.current_schema_id)
-            .ok_or_else(|| {
-                crate::error::Error::new(
-                    crate::ErrorKind::IcebergDataInvalid,
-                    "Can't find current schema",
-                )
-            })?
-            .try_into()
-            .map_err(|e| {
-                crate::error::Error::new(
-                    crate::ErrorKind::IcebergDataInvalid,
-                    format!("Can't convert iceberg schema to arrow schema: {}", e),
-                )
-            })?;
+        let schema = schema_to_arrow_schema(table_metadata.current_schema()?).map_err(|e| {
+            crate::error::Error::new(
+                crate::ErrorKind::IcebergDataInvalid,
+                format!("Can't convert iceberg schema to arrow schema: {}", e),
+            )
+        })?;
 
         let partition_spec = table_metadata
             .partition_specs
@@ -62,46 +88,162 @@ impl TaskWriter {
                 "Can't find default partition spec",
             ))?;
 
-        if partition_spec.is_unpartitioned() {
-            Ok(Self::Unpartitioned(
-                UnpartitionedWriter::try_new(
-                    schema,
-                    table_metadata.location.clone(),
-                    location_generator::DataFileLocationGenerator::try_new(
-                        &table_metadata,
-                        partition_id,
-                        task_id,
-                        suffix,
-                    )?,
-                    operator,
-                )
-                .await?,
+        let sort_order = table_metadata.current_sort_order()?;
+        let sort = if config.sort_enabled() && !sort_order.fields.is_empty() {
+            Some((
+                SortColumns::try_new(sort_order, table_metadata.current_schema()?)?,
+                sort_order.order_id,
             ))
         } else {
-            todo!()
+            None
+        };
+
+        if partition_spec.is_unpartitioned() {
+            let iceberg_schema = table_metadata.current_schema()?.clone();
+            let writer = UnpartitionedWriter::try_new(
+                schema,
+                location_generator::DataFileLocationGenerator::try_new(
+                    &table_metadata,
+                    partition_id,
+                    task_id,
+                    suffix,
+                )?
+                .with_file_format(config.file_format())
+                .with_location_provider(config.location_provider()),
+                operator,
+                config,
+            )
+            .await?
+            .with_schema(iceberg_schema);
+            Ok(Self::Unpartitioned(match sort {
+                Some((sort_columns, order_id)) => writer.with_sort_order(sort_columns, order_id),
+                None => writer,
+            }))
+        } else if !config.fanout_enabled() {
+            let partition_spec = partition_spec.clone();
+            let writer = ClusteredWriter::try_new(
+                schema,
+                table_metadata,
+                partition_spec,
+                operator,
+                partition_id,
+                task_id,
+                suffix,
+                config,
+            )?;
+            Ok(Self::Clustered(Box::new(match sort {
+                Some((sort_columns, order_id)) => writer.with_sort_order(sort_columns, order_id),
+                None => writer,
+            })))
+        } else {
+            let partition_spec = partition_spec.clone();
+            let writer = FanoutPartitionedWriter::try_new(
+                schema,
+                table_metadata,
+                partition_spec,
+                operator,
+                partition_id,
+                task_id,
+                suffix,
+                config,
+            )?;
+            Ok(Self::Partitioned(match sort {
+                Some((sort_columns, order_id)) => writer.with_sort_order(sort_columns, order_id),
+                None => writer,
+            }))
         }
     }
 
     /// Write a record batch.
+    ///
+    /// Columns of the batch are matched to fields of the table schema by
+    /// field ids in their metadata, or by names without ids. Columns of
+    /// `int`, `float` and decimals are promoted to wider types of fields,
+    /// and missing optional columns are filled with nulls. Batches with
+    /// unknown columns, missing required columns or other types are
+    /// rejected.
     pub async fn write(&mut self, batch: &RecordBatch) -> Result<()> {
         match self {
             Self::Unpartitioned(writer) => writer.write(batch).await,
+            Self::Partitioned(writer) => writer.write(batch).await,
+            Self::Clustered(writer) => writer.write(batch).await,
         }
     }
 
+    /// Complete data files written so far and return them, for example, at
+    /// a checkpoint of a streaming job. The writer can still be written,
+    /// following rows are written to new data files.
+    ///
+    /// Returned files can be persisted by the caller and committed later by
+    /// [`crate::Table::commit_from_results`].
+    pub async fn flush(&mut self) -> Result<WriteResult> {
+        let data_files = match self {
+            Self::Unpartitioned(writer) => writer.flush().await?,
+            Self::Partitioned(writer) => writer.flush().await?,
+            Self::Clustered(writer) => writer.flush().await?,
+        };
+        Ok(WriteResult::from(data_files))
+    }
+
     /// Close the writer and return the data files.
     pub async fn close(self) -> Result<Vec<DataFile>> {
         match self {
             Self::Unpartitioned(writer) => writer.close().await,
+            Self::Partitioned(writer) => writer.close().await,
+            Self::Clustered(writer) => writer.close().await,
+        }
+    }
+}
+
+/// WriteResult is files written by writers but not committed yet, like
+/// files flushed at a checkpoint.
+///
+/// It's serializable by serde, so that results of distributed writers can
+/// be persisted in checkpoints or shipped to a single committer.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct WriteResult {
+    /// Data files.
+    pub data_files: Vec<DataFile>,
+    /// Position delete files and equality delete files.
+    pub delete_files: Vec<DataFile>,
+}
+
+impl WriteResult {
+    /// Returns true if no file is written.
+    pub fn is_empty(&self) -> bool {
+        self.data_files.is_empty() && self.delete_files.is_empty()
+    }
+
+    /// Merge files of another result into this one.
+    pub fn extend(&mut self, other: WriteResult) {
+        self.data_files.extend(other.data_files);
+        self.delete_files.extend(other.delete_files);
+    }
+
+    /// Returns all files, data files first.
+    pub fn into_files(self) -> Vec<DataFile> {
+        let mut files = self.data_files;
+        files.extend(self.delete_files);
+        files
+    }
+}
+
+/// Files are grouped by their content type.
+impl From<Vec<DataFile>> for WriteResult {
+    fn from(files: Vec<DataFile>) -> Self {
+        let (data_files, delete_files) = files
+            .into_iter()
+            .partition(|v| v.content == DataContentType::Data);
+        Self {
+            data_files,
+            delete_files,
         }
     }
 }
 
 /// Unpartitioned task writer
 pub struct UnpartitionedWriter {
-    /// # TODO
-    ///
-    /// Support to config the data file writer.
+    arrow_schema: Arc<ArrowSchema>,
     data_file_writer: DataFileWriter,
 }
 
@@ -109,26 +251,48 @@ impl UnpartitionedWriter {
     /// Create a new `TaskWriter`.
     pub async fn try_new(
         schema: ArrowSchema,
-        table_location: String,
         location_generator: DataFileLocationGenerator,
         operator: Operator,
+        config: WriterConfig,
     ) -> Result<Self> {
+        let arrow_schema = Arc::new(schema);
         Ok(Self {
             data_file_writer: DataFileWriter::try_new(
                 operator,
-                table_location,
                 location_generator,
-                schema.into(),
-                1024,
-                1024 * 1024,
+                arrow_schema.clone(),
+                config,
             )
             .await?,
+            arrow_schema,
         })
     }
 
+    /// Set the iceberg schema of written rows, see
+    /// [`DataFileWriter::with_schema`].
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.data_file_writer = self.data_file_writer.with_schema(schema);
+        self
+    }
+
+    /// Sort rows of written data files, see [`DataFileWriter::with_sort_order`].
+    pub fn with_sort_order(mut self, sort_columns: SortColumns, order_id: i32) -> Self {
+        self.data_file_writer = self
+            .data_file_writer
+            .with_sort_order(sort_columns, order_id);
+        self
+    }
+
     /// Write a record batch using data file writer.
     pub async fn write(&mut self, batch: &RecordBatch) -> Result<()> {
-        self.data_file_writer.write(batch.clone()).await
+        let batch = coerce_batch(batch, &self.arrow_schema)?;
+        self.data_file_writer.write(batch).await
+    }
+
+    /// Complete data files written so far and return them, see
+    /// [`TaskWriter::flush`].
+    pub async fn flush(&mut self) -> Result<Vec<DataFile>> {
+        self.data_file_writer.flush().await
     }
 
     /// Complete the write and return the data files.
@@ -142,3 +306,1017 @@ impl UnpartitionedWriter {
         self.data_file_writer.close().await
     }
 }
+
+/// Fanout partitioned task writer.
+///
+/// Partition values are computed column-wise by transforms of the partition
+/// spec, then rows are routed to the data file writer of their partition.
+/// Partitions keep open data file writers, so input doesn't need to be
+/// clustered by partitions.
+///
+/// At most [`WriterConfig::max_open_files`] writers are open to bound
+/// memory, the least recently written one is closed to open a writer of
+/// another partition.
+///
+/// Data files of a partition are written under a hive style directory like
+/// `data/day=2023-01-01/`.
+pub struct FanoutPartitionedWriter {
+    factory: PartitionWriterFactory,
+    splitter: PartitionSplitter,
+    /// Open writers and sequences of their last writes.
+    writers: HashMap<OwnedRow, (DataFileWriter, u64)>,
+    write_seq: u64,
+    /// Data files of closed writers.
+    closed_files: Vec<DataFile>,
+}
+
+impl FanoutPartitionedWriter {
+    /// Create a new `FanoutPartitionedWriter`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        arrow_schema: ArrowSchema,
+        table_metadata: TableMetadata,
+        partition_spec: PartitionSpec,
+        operator: Operator,
+        partition_id: usize,
+        task_id: usize,
+        suffix: Option<String>,
+        config: WriterConfig,
+    ) -> Result<Self> {
+        let splitter =
+            PartitionSplitter::try_new(table_metadata.current_schema()?, &partition_spec)?;
+
+        Ok(Self {
+            factory: PartitionWriterFactory {
+                arrow_schema: Arc::new(arrow_schema),
+                table_metadata,
+                operator,
+                partition_id,
+                task_id,
+                suffix,
+                config,
+                sort: None,
+            },
+            splitter,
+            writers: HashMap::new(),
+            write_seq: 0,
+            closed_files: vec![],
+        })
+    }
+
+    /// Sort rows of written data files, see [`DataFileWriter::with_sort_order`].
+    pub fn with_sort_order(mut self, sort_columns: SortColumns, order_id: i32) -> Self {
+        self.factory.sort = Some((sort_columns, order_id));
+        self
+    }
+
+    /// Write a record batch, rows of different partitions are written into
+    /// different data files.
+    pub async fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let batch = coerce_batch(batch, &self.factory.arrow_schema)?;
+        for (key, partition, partitioned) in self.splitter.split(&batch)? {
+            if !self.writers.contains_key(&key) {
+                if self.writers.len() >= self.factory.config.max_open_files() {
+                    self.close_least_recently_written().await?;
+                }
+                let writer = self.factory.new_writer(partition).await?;
+                self.writers.insert(key.clone(), (writer, 0));
+            }
+            self.write_seq += 1;
+            let (writer, last_write_seq) =
+                self.writers.get_mut(&key).expect("writer must be created");
+            *last_write_seq = self.write_seq;
+            writer.write(partitioned).await?;
+        }
+        Ok(())
+    }
+
+    /// Complete data files written so far of all partitions and return
+    /// them, see [`TaskWriter::flush`].
+    pub async fn flush(&mut self) -> Result<Vec<DataFile>> {
+        let mut data_files = std::mem::take(&mut self.closed_files);
+        for (_, (writer, _)) in self.writers.drain() {
+            data_files.extend(writer.close().await?);
+        }
+        Ok(data_files)
+    }
+
+    /// Complete the write and return data files of all partitions.
+    pub async fn close(mut self) -> Result<Vec<DataFile>> {
+        self.flush().await
+    }
+
+    async fn close_least_recently_written(&mut self) -> Result<()> {
+        let key = self
+            .writers
+            .iter()
+            .min_by_key(|(_, (_, last_write_seq))| *last_write_seq)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = key {
+            let (writer, _) = self.writers.remove(&key).expect("writer must exist");
+            self.closed_files.extend(writer.close().await?);
+        }
+        Ok(())
+    }
+}
+
+/// Clustered partitioned task writer.
+///
+/// It's a cheaper alternative of [`FanoutPartitionedWriter`] for input
+/// clustered by partitions, like rows sorted or repartitioned by partition
+/// values upstream. Only the writer of the current partition is open, it's
+/// closed once rows of another partition arrive.
+///
+/// Writing rows of a partition closed before fails, since they would be
+/// written to another small file.
+pub struct ClusteredWriter {
+    factory: PartitionWriterFactory,
+    splitter: PartitionSplitter,
+    /// Key and writer of the current partition.
+    current: Option<(OwnedRow, DataFileWriter)>,
+    /// Keys of closed partitions.
+    completed: HashSet<OwnedRow>,
+    /// Data files of closed writers.
+    closed_files: Vec<DataFile>,
+}
+
+impl ClusteredWriter {
+    /// Create a new `ClusteredWriter`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        arrow_schema: ArrowSchema,
+        table_metadata: TableMetadata,
+        partition_spec: PartitionSpec,
+        operator: Operator,
+        partition_id: usize,
+        task_id: usize,
+        suffix: Option<String>,
+        config: WriterConfig,
+    ) -> Result<Self> {
+        let splitter =
+            PartitionSplitter::try_new(table_metadata.current_schema()?, &partition_spec)?;
+
+        Ok(Self {
+            factory: PartitionWriterFactory {
+                arrow_schema: Arc::new(arrow_schema),
+                table_metadata,
+                operator,
+                partition_id,
+                task_id,
+                suffix,
+                config,
+                sort: None,
+            },
+            splitter,
+            current: None,
+            completed: HashSet::new(),
+            closed_files: vec![],
+        })
+    }
+
+    /// Sort rows of written data files, see [`DataFileWriter::with_sort_order`].
+    pub fn with_sort_order(mut self, sort_columns: SortColumns, order_id: i32) -> Self {
+        self.factory.sort = Some((sort_columns, order_id));
+        self
+    }
+
+    /// Write a record batch, rows must be clustered by partitions.
+    pub async fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let batch = coerce_batch(batch, &self.factory.arrow_schema)?;
+        for (key, partition, partitioned) in self.splitter.split_runs(&batch)? {
+            if self.current.as_ref().map(|(k, _)| k) != Some(&key) {
+                if self.completed.contains(&key) {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!(
+                            "Rows of partition {} arrive after the partition is closed, input of clustered writer must be clustered by partitions",
+                            partition_path(&partition)
+                        ),
+                    ));
+                }
+                self.close_current().await?;
+                let writer = self.factory.new_writer(partition).await?;
+                self.current = Some((key, writer));
+            }
+            let (_, writer) = self.current.as_mut().expect("writer must be created");
+            writer.write(partitioned).await?;
+        }
+        Ok(())
+    }
+
+    /// Complete data files written so far and return them, see
+    /// [`TaskWriter::flush`]. Following rows can be of any partition.
+    pub async fn flush(&mut self) -> Result<Vec<DataFile>> {
+        self.close_current().await?;
+        self.completed.clear();
+        Ok(std::mem::take(&mut self.closed_files))
+    }
+
+    /// Complete the write and return data files of all partitions.
+    pub async fn close(mut self) -> Result<Vec<DataFile>> {
+        self.flush().await
+    }
+
+    async fn close_current(&mut self) -> Result<()> {
+        if let Some((key, writer)) = self.current.take() {
+            self.closed_files.extend(writer.close().await?);
+            self.completed.insert(key);
+        }
+        Ok(())
+    }
+}
+
+/// Creates data file writers of partitions for partitioned writers.
+struct PartitionWriterFactory {
+    arrow_schema: Arc<ArrowSchema>,
+    table_metadata: TableMetadata,
+    operator: Operator,
+    partition_id: usize,
+    task_id: usize,
+    suffix: Option<String>,
+    config: WriterConfig,
+    sort: Option<(SortColumns, i32)>,
+}
+
+impl PartitionWriterFactory {
+    async fn new_writer(&self, partition: StructValue) -> Result<DataFileWriter> {
+        let location_generator = DataFileLocationGenerator::try_new(
+            &self.table_metadata,
+            self.partition_id,
+            self.task_id,
+            self.suffix.clone(),
+        )?
+        .with_file_format(self.config.file_format())
+        .with_location_provider(self.config.location_provider())
+        .with_partition_path(partition_path(&partition));
+        let writer = DataFileWriter::try_new(
+            self.operator.clone(),
+            location_generator,
+            self.arrow_schema.clone(),
+            self.config.clone(),
+        )
+        .await?
+        .with_partition(partition)
+        .with_schema(self.table_metadata.current_schema()?.clone());
+        Ok(match &self.sort {
+            Some((sort_columns, order_id)) => {
+                writer.with_sort_order(sort_columns.clone(), *order_id)
+            }
+            None => writer,
+        })
+    }
+}
+
+/// PartitionSplitter splits rows of batches by their partition values.
+///
+/// Partition values are computed column-wise by transforms of the
+/// partition spec on arrow arrays, then converted into comparable rows to
+/// group rows of batches.
+pub(crate) struct PartitionSplitter {
+    partition_type: Arc<Struct>,
+    /// Source column path and transform of each partition field.
+    partition_columns: Vec<(Vec<String>, BoxedTransformFunction)>,
+    /// Converts transformed partition columns into comparable keys.
+    row_converter: RowConverter,
+}
+
+impl PartitionSplitter {
+    /// Create a splitter of a partitioned spec of the schema.
+    pub fn try_new(schema: &Schema, partition_spec: &PartitionSpec) -> Result<Self> {
+        let partition_type = partition_spec.partition_type(schema)?;
+
+        let mut partition_columns = Vec::with_capacity(partition_spec.fields.len());
+        let mut sort_fields = Vec::with_capacity(partition_spec.fields.len());
+        for (partition_field, field) in partition_spec.fields.iter().zip(partition_type.fields()) {
+            let path = source_column_path(schema, partition_field.source_column_id)?;
+            partition_columns.push((path, create_transform_function(partition_field.transform)));
+            sort_fields.push(SortField::new(field.field_type.clone().try_into()?));
+        }
+
+        Ok(Self {
+            partition_type: Arc::new(partition_type),
+            partition_columns,
+            row_converter: RowConverter::new(sort_fields)?,
+        })
+    }
+
+    /// Split rows of `batch` into runs of consecutive rows of the same
+    /// partition, returns the comparable key, partition values and rows of
+    /// each run. Rows are sliced without copying.
+    pub fn split_runs(
+        &mut self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<(OwnedRow, StructValue, RecordBatch)>> {
+        let (partition_arrays, rows) = self.partition_rows(batch)?;
+        let mut runs = vec![];
+        let mut start = 0;
+        for idx in 1..=rows.num_rows() {
+            if idx == rows.num_rows() || rows.row(idx) != rows.row(start) {
+                let partition = self.partition_value(&partition_arrays, start)?;
+                runs.push((
+                    rows.row(start).owned(),
+                    partition,
+                    batch.slice(start, idx - start),
+                ));
+                start = idx;
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Split rows of `batch` by partitions in the order of first
+    /// appearance, returns the comparable key, partition values and rows of
+    /// each partition.
+    pub fn split(
+        &mut self,
+        batch: &RecordBatch,
+    ) -> Result<Vec<(OwnedRow, StructValue, RecordBatch)>> {
+        let (partition_arrays, rows) = self.partition_rows(batch)?;
+
+        // Rows of streaming inputs are usually of the same partition, which
+        // are passed through without copying.
+        if (1..rows.num_rows()).all(|idx| rows.row(idx) == rows.row(0)) {
+            if rows.num_rows() == 0 {
+                return Ok(vec![]);
+            }
+            let partition = self.partition_value(&partition_arrays, 0)?;
+            return Ok(vec![(rows.row(0).owned(), partition, batch.clone())]);
+        }
+
+        // Group row indices by partition, in the order of first appearance.
+        // Keys borrow the converted rows, and are only owned once per
+        // partition.
+        let mut groups: Vec<(usize, Vec<u32>)> = vec![];
+        let mut group_of_key: HashMap<Row<'_>, usize> = HashMap::new();
+        for (idx, row) in rows.iter().enumerate() {
+            let group = *group_of_key.entry(row).or_insert_with(|| {
+                groups.push((idx, vec![]));
+                groups.len() - 1
+            });
+            groups[group].1.push(idx as u32);
+        }
+
+        groups
+            .into_iter()
+            .map(|(first, indices)| {
+                let partition = self.partition_value(&partition_arrays, first)?;
+                let indices = UInt32Array::from(indices);
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .map(|v| take(v.as_ref(), &indices, None))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                let partitioned = RecordBatch::try_new(batch.schema(), columns)?;
+                Ok((rows.row(first).owned(), partition, partitioned))
+            })
+            .collect()
+    }
+
+    /// Returns transformed partition arrays and comparable partition keys
+    /// of rows of `batch`.
+    fn partition_rows(&mut self, batch: &RecordBatch) -> Result<(Vec<ArrayRef>, Rows)> {
+        let partition_arrays = self
+            .partition_columns
+            .iter()
+            .map(|(path, transform)| {
+                let column = column_by_path(batch, path).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!(
+                            "Partition source column {} not found in batch",
+                            path.join(".")
+                        ),
+                    )
+                })?;
+                transform.transform(column)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let rows = self.row_converter.convert_columns(&partition_arrays)?;
+
+        Ok((partition_arrays, rows))
+    }
+
+    /// Build partition values from the transformed partition arrays at
+    /// given row.
+    fn partition_value(&self, partition_arrays: &[ArrayRef], row: usize) -> Result<StructValue> {
+        let mut builder = StructValueBuilder::new(self.partition_type.clone());
+        for (field, array) in self.partition_type.fields().iter().zip(partition_arrays) {
+            let Any::Primitive(ty) = &field.field_type else {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Partition field {} must be primitive", field.name),
+                ));
+            };
+            let value = primitive_value(array.as_ref(), row, ty)?;
+            builder.add_field(field.id, value.map(AnyValue::Primitive))?;
+        }
+        builder.build()
+    }
+}
+
+/// Returns names from the top level to the source column of a partition
+/// field.
+fn source_column_path(schema: &Schema, field_id: i32) -> Result<Vec<String>> {
+    schema
+        .field_path(field_id)
+        .map(|path| path.into_iter().map(|v| v.to_string()).collect())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Can't find field id {field_id} in schema"),
+            )
+        })
+}
+
+fn column_by_path(batch: &RecordBatch, path: &[String]) -> Option<ArrayRef> {
+    let (first, rest) = path.split_first()?;
+    let mut column = batch.column_by_name(first)?.clone();
+    for name in rest {
+        let array = column.as_any().downcast_ref::<StructArray>()?;
+        column = array.column_by_name(name)?.clone();
+    }
+    Some(column)
+}
+
+/// Directory name of null partition values, the same as hive.
+pub const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Returns the hive style directory of given partition values, like
+/// `a=1/b=x`.
+///
+/// Names and values are escaped like `URLEncoder` of java, which is what
+/// the java implementation does, and null values are written as
+/// [`HIVE_DEFAULT_PARTITION`]. See [`parse_partition_path`] for the
+/// reverse.
+pub fn partition_path(partition: &StructValue) -> String {
+    let escape = |v: &str| form_urlencoded::byte_serialize(v.as_bytes()).collect::<String>();
+    partition
+        .iter()
+        .map(|(_, value, name)| {
+            let value = match value {
+                Some(AnyValue::Primitive(v)) => escape(&partition_value_string(v)),
+                _ => HIVE_DEFAULT_PARTITION.to_string(),
+            };
+            format!("{}={value}", escape(name))
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Parse a hive style directory written by [`partition_path`] into
+/// unescaped names and values of partition fields, `None` for null values.
+///
+/// Returns error if a level of the directory is not like `name=value`.
+pub fn parse_partition_path(path: &str) -> Result<Vec<(String, Option<String>)>> {
+    path.trim_matches('/')
+        .split('/')
+        .filter(|v| !v.is_empty())
+        .map(|level| {
+            if !level.contains('=') {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Partition directory {level} is not like name=value"),
+                )
+                .with_context("path", path));
+            }
+            let (name, value) = form_urlencoded::parse(level.as_bytes())
+                .next()
+                .expect("level containing '=' must be parsed");
+            let value = (value != HIVE_DEFAULT_PARTITION).then(|| value.into_owned());
+            Ok((name.into_owned(), value))
+        })
+        .collect()
+}
+
+/// Human readable string of a partition value used in paths.
+fn partition_value_string(value: &PrimitiveValue) -> String {
+    match value {
+        PrimitiveValue::Boolean(v) => v.to_string(),
+        PrimitiveValue::Int(v) => v.to_string(),
+        PrimitiveValue::Long(v) => v.to_string(),
+        PrimitiveValue::Float(v) => v.to_string(),
+        PrimitiveValue::Double(v) => v.to_string(),
+        PrimitiveValue::Decimal(v) => v.to_string(),
+        PrimitiveValue::Date(v) => v.format("%Y-%m-%d").to_string(),
+        PrimitiveValue::Time(v) => v.format("%H:%M:%S%.f").to_string(),
+        PrimitiveValue::Timestamp(v) | PrimitiveValue::TimestampNs(v) => {
+            v.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+        }
+        PrimitiveValue::Timestampz(v) | PrimitiveValue::TimestampzNs(v) => {
+            v.format("%Y-%m-%dT%H:%M:%S%.f%:z").to_string()
+        }
+        PrimitiveValue::String(v) => v.clone(),
+        PrimitiveValue::Uuid(v) => v.to_string(),
+        PrimitiveValue::Fixed(v) | PrimitiveValue::Binary(v) => {
+            v.iter().map(|b| format!("{b:02x}")).collect()
+        }
+    }
+}
+
+/// Read the value at `row` of an arrow array as iceberg primitive value
+/// of given type.
+fn primitive_value(
+    array: &dyn Array,
+    row: usize,
+    ty: &Primitive,
+) -> Result<Option<PrimitiveValue>> {
+    if array.is_null(row) {
+        return Ok(None);
+    }
+    let unsupported = || {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!(
+                "Can't read partition value of type {ty:?} from arrow type {}",
+                array.data_type()
+            ),
+        )
+    };
+    macro_rules! downcast {
+        ($t:ty) => {
+            array
+                .as_any()
+                .downcast_ref::<$t>()
+                .ok_or_else(unsupported)?
+                .value(row)
+        };
+    }
+
+    let value = match ty {
+        Primitive::Boolean => PrimitiveValue::Boolean(downcast!(BooleanArray)),
+        Primitive::Int => PrimitiveValue::Int(downcast!(Int32Array)),
+        Primitive::Long => PrimitiveValue::Long(downcast!(Int64Array)),
+        Primitive::Float => PrimitiveValue::Float(downcast!(Float32Array).into()),
+        Primitive::Double => PrimitiveValue::Double(downcast!(Float64Array).into()),
+        Primitive::Decimal { scale, .. } => PrimitiveValue::Decimal(
+            Decimal::try_from_i128_with_scale(downcast!(Decimal128Array), *scale as u32)
+                .map_err(|e| unsupported().set_source(e))?,
+        ),
+        Primitive::Date => PrimitiveValue::Date(
+            array
+                .as_any()
+                .downcast_ref::<Date32Array>()
+                .and_then(|v| v.value_as_date(row))
+                .ok_or_else(unsupported)?,
+        ),
+        Primitive::Time => {
+            let micros = downcast!(Time64MicrosecondArray);
+            PrimitiveValue::Time(
+                NaiveTime::from_num_seconds_from_midnight_opt(
+                    (micros / 1_000_000) as u32,
+                    (micros % 1_000_000 * 1000) as u32,
+                )
+                .ok_or_else(unsupported)?,
+            )
+        }
+        Primitive::Timestamp => PrimitiveValue::Timestamp(
+            NaiveDateTime::from_timestamp_micros(downcast!(TimestampMicrosecondArray))
+                .ok_or_else(unsupported)?,
+        ),
+        Primitive::Timestampz => PrimitiveValue::Timestampz(DateTime::from_naive_utc_and_offset(
+            NaiveDateTime::from_timestamp_micros(downcast!(TimestampMicrosecondArray))
+                .ok_or_else(unsupported)?,
+            Utc,
+        )),
+        Primitive::TimestampNs => PrimitiveValue::TimestampNs(
+            timestamp_from_nanos(downcast!(TimestampNanosecondArray)).ok_or_else(unsupported)?,
+        ),
+        Primitive::TimestampzNs => {
+            PrimitiveValue::TimestampzNs(DateTime::from_naive_utc_and_offset(
+                timestamp_from_nanos(downcast!(TimestampNanosecondArray))
+                    .ok_or_else(unsupported)?,
+                Utc,
+            ))
+        }
+        Primitive::String => match array.data_type() {
+            DataType::LargeUtf8 => PrimitiveValue::String(downcast!(LargeStringArray).to_string()),
+            _ => PrimitiveValue::String(downcast!(StringArray).to_string()),
+        },
+        Primitive::Uuid => PrimitiveValue::Uuid(
+            Uuid::from_slice(downcast!(FixedSizeBinaryArray))
+                .map_err(|e| unsupported().set_source(e))?,
+        ),
+        Primitive::Fixed(_) | Primitive::Binary => {
+            let bytes = match array.data_type() {
+                DataType::FixedSizeBinary(_) => downcast!(FixedSizeBinaryArray).to_vec(),
+                DataType::LargeBinary => downcast!(LargeBinaryArray).to_vec(),
+                _ => downcast!(BinaryArray).to_vec(),
+            };
+            if matches!(ty, Primitive::Fixed(_)) {
+                PrimitiveValue::Fixed(bytes)
+            } else {
+                PrimitiveValue::Binary(bytes)
+            }
+        }
+    };
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use arrow::array::{Int64Array, StringArray};
+    use bytes::Bytes;
+    use opendal::services::Memory;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::table_properties::{WRITE_FANOUT_ENABLED, WRITE_MAX_OPEN_FILES, WRITE_SORT_ENABLED};
+    use crate::types::{
+        murmur3_32, parse_table_metadata, Field, NullOrder, PartitionField, SortDirection,
+        SortField, SortOrder, Struct, StructValueBuilder, Transform,
+    };
+
+    fn table_metadata(partition_field: PartitionField) -> TableMetadata {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        metadata.location = "/tmp/table".to_string();
+        metadata.partition_specs[0].fields = vec![partition_field];
+        metadata
+    }
+
+    fn memory_operator() -> Operator {
+        let mut builder = Memory::default();
+        builder.root("/tmp/table");
+        Operator::new(builder).unwrap().finish()
+    }
+
+    #[tokio::test]
+    async fn test_fanout_partitioned_writer() -> anyhow::Result<()> {
+        let metadata = table_metadata(PartitionField {
+            source_column_id: 2,
+            partition_field_id: 1000,
+            transform: Transform::Identity,
+            name: "data".to_string(),
+        });
+        let op = memory_operator();
+
+        let mut writer = TaskWriter::try_new(
+            metadata.clone(),
+            op.clone(),
+            0,
+            0,
+            None,
+            WriterConfig::from_properties(&HashMap::new())?,
+        )
+        .await?;
+        let batch = RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+            (
+                "data",
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    Some("a"),
+                    None,
+                ])) as ArrayRef,
+            ),
+        ])?;
+        writer.write(&batch).await?;
+        writer.write(&batch).await?;
+        let mut data_files = writer.close().await?;
+        data_files.sort_by_key(|v| v.file_path.clone());
+
+        assert_eq!(data_files.len(), 3);
+        let partitions: Vec<_> = data_files
+            .iter()
+            .map(|v| {
+                let (_, value, name) = v.partition.iter().next().unwrap();
+                assert_eq!(name, "data");
+                value.cloned()
+            })
+            .collect();
+        assert_eq!(
+            partitions,
+            vec![
+                None,
+                Some(AnyValue::Primitive(PrimitiveValue::String("a".to_string()))),
+                Some(AnyValue::Primitive(PrimitiveValue::String("b".to_string()))),
+            ]
+        );
+        assert_eq!(
+            data_files
+                .iter()
+                .map(|v| v.record_count)
+                .collect::<Vec<_>>(),
+            vec![2, 4, 2]
+        );
+        assert!(data_files[1]
+            .file_path
+            .starts_with("/tmp/table/data/data=a/"));
+        assert!(data_files[0]
+            .file_path
+            .starts_with("/tmp/table/data/data=__HIVE_DEFAULT_PARTITION__/"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fanout_partitioned_writer_max_open_files() -> anyhow::Result<()> {
+        let metadata = table_metadata(PartitionField {
+            source_column_id: 2,
+            partition_field_id: 1000,
+            transform: Transform::Identity,
+            name: "data".to_string(),
+        });
+        let config = WriterConfig::from_properties(&HashMap::from([(
+            WRITE_MAX_OPEN_FILES.to_string(),
+            "2".to_string(),
+        )]))?;
+        let mut writer =
+            TaskWriter::try_new(metadata, memory_operator(), 0, 0, None, config).await?;
+        let batch = |data: Vec<&str>| {
+            RecordBatch::try_from_iter([
+                (
+                    "id",
+                    Arc::new(Int64Array::from(vec![1; data.len()])) as ArrayRef,
+                ),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])
+        };
+        writer.write(&batch(vec!["a", "b"])?).await?;
+        writer.write(&batch(vec!["a"])?).await?;
+        // Closes the writer of `b`, which is least recently written.
+        writer.write(&batch(vec!["c"])?).await?;
+        writer.write(&batch(vec!["a", "b"])?).await?;
+        let TaskWriter::Partitioned(partitioned) = &writer else {
+            unreachable!()
+        };
+        assert_eq!(partitioned.writers.len(), 2);
+        let data_files = writer.close().await?;
+
+        let mut records: Vec<_> = data_files
+            .iter()
+            .map(|v| {
+                let value = v.partition.iter().next().unwrap().1.cloned();
+                let Some(AnyValue::Primitive(PrimitiveValue::String(partition))) = value else {
+                    unreachable!()
+                };
+                (partition, v.record_count)
+            })
+            .collect();
+        records.sort();
+        assert_eq!(
+            records,
+            vec![
+                ("a".to_string(), 3),
+                ("b".to_string(), 1),
+                ("b".to_string(), 1),
+                ("c".to_string(), 1),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clustered_writer() -> anyhow::Result<()> {
+        let metadata = table_metadata(PartitionField {
+            source_column_id: 2,
+            partition_field_id: 1000,
+            transform: Transform::Identity,
+            name: "data".to_string(),
+        });
+        let config = WriterConfig::from_properties(&HashMap::from([(
+            WRITE_FANOUT_ENABLED.to_string(),
+            "false".to_string(),
+        )]))?;
+        let mut writer =
+            TaskWriter::try_new(metadata, memory_operator(), 0, 0, None, config).await?;
+        assert!(matches!(writer, TaskWriter::Clustered(_)));
+        let batch = |data: Vec<&str>| {
+            RecordBatch::try_from_iter([
+                (
+                    "id",
+                    Arc::new(Int64Array::from(vec![1; data.len()])) as ArrayRef,
+                ),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])
+        };
+        writer.write(&batch(vec!["a", "a", "b"])?).await?;
+        writer.write(&batch(vec!["b", "c"])?).await?;
+        assert!(writer.write(&batch(vec!["c", "a"])?).await.is_err());
+        // Partitions can be written again after flushed.
+        assert_eq!(writer.flush().await?.data_files.len(), 3);
+        writer.write(&batch(vec!["a"])?).await?;
+        let data_files = writer.close().await?;
+        assert_eq!(data_files.len(), 1);
+        assert!(data_files[0]
+            .file_path
+            .starts_with("/tmp/table/data/data=a/"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fanout_partitioned_writer_with_transform() -> anyhow::Result<()> {
+        let metadata = table_metadata(PartitionField {
+            source_column_id: 1,
+            partition_field_id: 1000,
+            transform: Transform::Truncate(10),
+            name: "id_trunc".to_string(),
+        });
+
+        let mut writer = TaskWriter::try_new(
+            metadata,
+            memory_operator(),
+            0,
+            0,
+            None,
+            WriterConfig::from_properties(&HashMap::new())?,
+        )
+        .await?;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 15, 2])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+            ),
+        ])?;
+        writer.write(&batch).await?;
+        let mut data_files = writer.close().await?;
+        data_files.sort_by_key(|v| v.record_count);
+
+        let partitions: Vec<_> = data_files
+            .iter()
+            .map(|v| v.partition.iter().next().unwrap().1.cloned())
+            .collect();
+        assert_eq!(
+            partitions,
+            vec![
+                Some(AnyValue::Primitive(PrimitiveValue::Long(10))),
+                Some(AnyValue::Primitive(PrimitiveValue::Long(0))),
+            ]
+        );
+        assert!(data_files[1]
+            .file_path
+            .starts_with("/tmp/table/data/id_trunc=0/"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partition_path() -> anyhow::Result<()> {
+        let ty = Arc::new(Struct::new(vec![
+            Field::optional(1000, "a b", Any::Primitive(Primitive::String)),
+            Field::optional(1001, "c", Any::Primitive(Primitive::String)),
+            Field::optional(1002, "d", Any::Primitive(Primitive::Int)),
+        ]));
+        let mut builder = StructValueBuilder::new(ty);
+        builder.add_field(
+            1000,
+            Some(AnyValue::Primitive(PrimitiveValue::String(
+                "x/y=1&z%é".to_string(),
+            ))),
+        )?;
+        builder.add_field(1001, None)?;
+        builder.add_field(1002, Some(AnyValue::Primitive(PrimitiveValue::Int(-1))))?;
+        let path = partition_path(&builder.build()?);
+        assert_eq!(
+            path,
+            "a+b=x%2Fy%3D1%26z%25%C3%A9/c=__HIVE_DEFAULT_PARTITION__/d=-1"
+        );
+        assert_eq!(
+            parse_partition_path(&path)?,
+            vec![
+                ("a b".to_string(), Some("x/y=1&z%é".to_string())),
+                ("c".to_string(), None),
+                ("d".to_string(), Some("-1".to_string())),
+            ]
+        );
+        assert!(parse_partition_path("")?.is_empty());
+        assert!(parse_partition_path("a=1/b").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_partition_splitter() -> anyhow::Result<()> {
+        let metadata = table_metadata(PartitionField {
+            source_column_id: 1,
+            partition_field_id: 1000,
+            transform: Transform::Bucket(4),
+            name: "id_bucket".to_string(),
+        });
+        let mut splitter = PartitionSplitter::try_new(
+            metadata.current_schema()?,
+            metadata.current_partition_spec()?,
+        )?;
+        let batch = |ids: Vec<i64>| {
+            let data = vec!["a"; ids.len()];
+            RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])
+        };
+
+        // Rows of a single partition are passed through.
+        let input = batch(vec![34, 34])?;
+        let split = splitter.split(&input)?;
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].2, input);
+        assert!(splitter.split(&batch(vec![])?)?.is_empty());
+
+        // Rows are grouped by buckets in the order of first appearance.
+        let ids = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let split = splitter.split(&batch(ids.clone())?)?;
+        let mut grouped = vec![];
+        for (_, partition, rows) in &split {
+            let Some(AnyValue::Primitive(PrimitiveValue::Int(bucket))) =
+                partition.iter().next().unwrap().1
+            else {
+                panic!("bucket must be int");
+            };
+            let column = rows
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            for id in column.values().iter() {
+                assert_eq!(*bucket, (murmur3_32(&id.to_le_bytes()) & i32::MAX) % 4);
+                grouped.push(*id);
+            }
+        }
+        assert!(split.len() > 1);
+        assert_eq!(grouped[0], 1);
+        grouped.sort();
+        assert_eq!(grouped, ids);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sorted_task_writer() -> anyhow::Result<()> {
+        let mut metadata = table_metadata(PartitionField {
+            source_column_id: 2,
+            partition_field_id: 1000,
+            transform: Transform::Identity,
+            name: "data".to_string(),
+        });
+        metadata.sort_orders.push(SortOrder {
+            order_id: 1,
+            fields: vec![SortField {
+                source_column_id: 1,
+                transform: Transform::Identity,
+                direction: SortDirection::DESC,
+                null_order: NullOrder::Last,
+            }],
+        });
+        metadata.default_sort_order_id = 1;
+        let op = memory_operator();
+
+        let mut writer = TaskWriter::try_new(
+            metadata,
+            op.clone(),
+            0,
+            0,
+            None,
+            WriterConfig::from_properties(&HashMap::from([(
+                WRITE_SORT_ENABLED.to_string(),
+                "true".to_string(),
+            )]))?,
+        )
+        .await?;
+        for ids in [vec![1, 5, 3], vec![2, 6, 4]] {
+            let batch = RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                (
+                    "data",
+                    Arc::new(StringArray::from(vec!["a", "b", "a"])) as ArrayRef,
+                ),
+            ])?;
+            writer.write(&batch).await?;
+        }
+        let mut data_files = writer.close().await?;
+        data_files.sort_by_key(|v| v.file_path.clone());
+
+        let mut ids = vec![];
+        for data_file in &data_files {
+            assert_eq!(data_file.sort_order_id, Some(1));
+            let content = op
+                .read(data_file.file_path.strip_prefix("/tmp/table/").unwrap())
+                .await?;
+            let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(content))?.build()?;
+            let mut file_ids = vec![];
+            for batch in reader {
+                let batch = batch?;
+                let column = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                file_ids.extend(column.values().iter().copied());
+            }
+            ids.push(file_ids);
+        }
+        assert_eq!(ids, vec![vec![4, 3, 2, 1], vec![6, 5]]);
+
+        Ok(())
+    }
+}
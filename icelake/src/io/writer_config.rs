@@ -0,0 +1,501 @@
+//! writer_config module derives the configuration of writers from table
+//! properties.
+//!
+//! The value of a config is decided by the following precedence:
+//!
+//! 1. Write options passed to the writer explicitly, like
+//!    [`crate::Table::task_writer_with_options`].
+//! 2. Table properties (`write.*`) stored in table metadata.
+//! 3. Defaults defined by iceberg, see [`crate::table_properties`].
+//!
+//! Write options use the same keys as table properties.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::{WriterProperties, WriterVersion};
+use parquet::schema::types::ColumnPath;
+
+use super::orc::{OrcCompression, OrcWriterProperties};
+use crate::io::location_provider::{location_provider, LocationProvider};
+use crate::table_properties::*;
+use crate::types::{DataFileFormat, TableMetadata};
+use crate::{Error, ErrorKind, Result};
+
+/// Rows to write between two checks of file size.
+const ROWS_DIVISOR: usize = 1024;
+
+/// MetricsMode decides which column metrics are collected into data files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsMode {
+    /// No metrics is collected except column sizes.
+    None,
+    /// Only value counts and null counts are collected.
+    Counts,
+    /// Counts and bounds are collected, bounds are truncated to given length.
+    Truncate(usize),
+    /// Counts and full bounds are collected.
+    Full,
+}
+
+impl MetricsMode {
+    /// Whether value counts and null counts should be collected.
+    pub fn collect_counts(&self) -> bool {
+        !matches!(self, MetricsMode::None)
+    }
+
+    /// Whether lower and upper bounds should be collected.
+    pub fn collect_bounds(&self) -> bool {
+        matches!(self, MetricsMode::Truncate(_) | MetricsMode::Full)
+    }
+}
+
+impl FromStr for MetricsMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mode = s.trim().to_lowercase();
+        match mode.as_str() {
+            "none" => Ok(MetricsMode::None),
+            "counts" => Ok(MetricsMode::Counts),
+            "full" => Ok(MetricsMode::Full),
+            _ => {
+                let length = mode
+                    .strip_prefix("truncate(")
+                    .and_then(|v| v.strip_suffix(')'))
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Invalid metrics mode: {s}"),
+                        )
+                    })?
+                    .parse::<usize>()?;
+                if length == 0 {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Truncate length should be positive: {s}"),
+                    ));
+                }
+                Ok(MetricsMode::Truncate(length))
+            }
+        }
+    }
+}
+
+impl Display for MetricsMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricsMode::None => write!(f, "none"),
+            MetricsMode::Counts => write!(f, "counts"),
+            MetricsMode::Truncate(length) => write!(f, "truncate({length})"),
+            MetricsMode::Full => write!(f, "full"),
+        }
+    }
+}
+
+/// WriterConfig is the configuration of data file writers.
+#[derive(Debug, Clone)]
+pub struct WriterConfig {
+    file_format: DataFileFormat,
+    target_file_size_in_bytes: u64,
+    sort_enabled: bool,
+    fanout_enabled: bool,
+    max_open_files: usize,
+    metrics_mode: MetricsMode,
+    column_metrics_modes: HashMap<String, MetricsMode>,
+    location_provider: Arc<dyn LocationProvider>,
+
+    parquet_compression: Compression,
+    parquet_row_group_size_bytes: usize,
+    parquet_page_size_bytes: usize,
+    parquet_dict_size_bytes: usize,
+    /// Columns with bloom filter enabled and their fpp.
+    parquet_bloom_filter_columns: HashMap<String, Option<f64>>,
+
+    orc_compression: OrcCompression,
+    orc_stripe_size_bytes: usize,
+}
+
+impl WriterConfig {
+    /// Build writer config from table properties and write options.
+    ///
+    /// Values in `options` take precedence over table properties.
+    pub fn try_new(
+        table_metadata: &TableMetadata,
+        options: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let mut props = table_metadata.properties.clone().unwrap_or_default();
+        props.extend(options.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Self::from_properties(&props)
+    }
+
+    /// Build writer config from merged properties.
+    pub fn from_properties(props: &HashMap<String, String>) -> Result<Self> {
+        let file_format = props
+            .get(DEFAULT_FILE_FORMAT)
+            .map(|v| v.as_str())
+            .unwrap_or(DEFAULT_FILE_FORMAT_DEFAULT)
+            .parse()?;
+
+        let target_file_size_in_bytes = parse_or(
+            props,
+            WRITE_TARGET_FILE_SIZE_BYTES,
+            WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT,
+        )?;
+
+        let sort_enabled = parse_or(props, WRITE_SORT_ENABLED, WRITE_SORT_ENABLED_DEFAULT)?;
+        let fanout_enabled = parse_or(props, WRITE_FANOUT_ENABLED, WRITE_FANOUT_ENABLED_DEFAULT)?;
+        let max_open_files: usize =
+            parse_or(props, WRITE_MAX_OPEN_FILES, WRITE_MAX_OPEN_FILES_DEFAULT)?;
+        if max_open_files == 0 {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("{WRITE_MAX_OPEN_FILES} should be positive"),
+            ));
+        }
+
+        let metrics_mode = props
+            .get(DEFAULT_WRITE_METRICS_MODE)
+            .map(|v| v.as_str())
+            .unwrap_or(DEFAULT_WRITE_METRICS_MODE_DEFAULT)
+            .parse()?;
+        let column_metrics_modes = props
+            .iter()
+            .filter_map(|(k, v)| {
+                k.strip_prefix(METRICS_MODE_COLUMN_CONF_PREFIX)
+                    .map(|col| v.parse().map(|mode| (col.to_string(), mode)))
+            })
+            .collect::<Result<_>>()?;
+
+        let location_provider = location_provider(props)?;
+
+        let parquet_compression = parse_compression(
+            props
+                .get(PARQUET_COMPRESSION)
+                .map(|v| v.as_str())
+                .unwrap_or(PARQUET_COMPRESSION_DEFAULT),
+            props.get(PARQUET_COMPRESSION_LEVEL).map(|v| v.as_str()),
+        )?;
+        let parquet_row_group_size_bytes = parse_or(
+            props,
+            PARQUET_ROW_GROUP_SIZE_BYTES,
+            PARQUET_ROW_GROUP_SIZE_BYTES_DEFAULT,
+        )?;
+        let parquet_page_size_bytes = parse_or(
+            props,
+            PARQUET_PAGE_SIZE_BYTES,
+            PARQUET_PAGE_SIZE_BYTES_DEFAULT,
+        )?;
+        let parquet_dict_size_bytes = parse_or(
+            props,
+            PARQUET_DICT_SIZE_BYTES,
+            PARQUET_DICT_SIZE_BYTES_DEFAULT,
+        )?;
+
+        let mut parquet_bloom_filter_columns = HashMap::new();
+        for (k, v) in props {
+            if let Some(col) = k.strip_prefix(PARQUET_BLOOM_FILTER_COLUMN_ENABLED_PREFIX) {
+                if parse_value::<bool>(k, v)? {
+                    let fpp_key = format!("{PARQUET_BLOOM_FILTER_COLUMN_FPP_PREFIX}{col}");
+                    let fpp = props
+                        .get(&fpp_key)
+                        .map(|fpp| parse_value::<f64>(&fpp_key, fpp))
+                        .transpose()?;
+                    parquet_bloom_filter_columns.insert(col.to_string(), fpp);
+                }
+            }
+        }
+
+        let orc_compression = props
+            .get(ORC_COMPRESSION)
+            .map(|v| v.as_str())
+            .unwrap_or(ORC_COMPRESSION_DEFAULT)
+            .parse()?;
+        let orc_stripe_size_bytes =
+            parse_or(props, ORC_STRIPE_SIZE_BYTES, ORC_STRIPE_SIZE_BYTES_DEFAULT)?;
+
+        Ok(Self {
+            file_format,
+            target_file_size_in_bytes,
+            sort_enabled,
+            fanout_enabled,
+            max_open_files,
+            metrics_mode,
+            column_metrics_modes,
+            location_provider,
+            parquet_compression,
+            parquet_row_group_size_bytes,
+            parquet_page_size_bytes,
+            parquet_dict_size_bytes,
+            parquet_bloom_filter_columns,
+            orc_compression,
+            orc_stripe_size_bytes,
+        })
+    }
+
+    /// Format of data files.
+    pub fn file_format(&self) -> DataFileFormat {
+        self.file_format
+    }
+
+    /// Target size of data files in bytes.
+    pub fn target_file_size_in_bytes(&self) -> u64 {
+        self.target_file_size_in_bytes
+    }
+
+    /// Whether rows of data files are sorted by the default sort order.
+    pub fn sort_enabled(&self) -> bool {
+        self.sort_enabled
+    }
+
+    /// Whether partitioned writers keep files of all partitions open, or
+    /// only one file for input clustered by partitions.
+    pub fn fanout_enabled(&self) -> bool {
+        self.fanout_enabled
+    }
+
+    /// Max number of files partitioned writers keep open.
+    pub fn max_open_files(&self) -> usize {
+        self.max_open_files
+    }
+
+    /// Rows to write between two checks of file size.
+    pub fn rows_divisor(&self) -> usize {
+        ROWS_DIVISOR
+    }
+
+    /// Metrics mode of given column, `column` is the dot separated name of
+    /// the column like `a.b`.
+    pub fn metrics_mode(&self, column: &str) -> MetricsMode {
+        self.column_metrics_modes
+            .get(column)
+            .copied()
+            .unwrap_or(self.metrics_mode)
+    }
+
+    /// Location provider deciding paths of new data files.
+    pub fn location_provider(&self) -> Arc<dyn LocationProvider> {
+        self.location_provider.clone()
+    }
+
+    /// Override the location provider decided by properties.
+    pub fn with_location_provider(mut self, location_provider: Arc<dyn LocationProvider>) -> Self {
+        self.location_provider = location_provider;
+        self
+    }
+
+    /// Size of parquet row groups in bytes.
+    pub fn parquet_row_group_size_bytes(&self) -> usize {
+        self.parquet_row_group_size_bytes
+    }
+
+    /// Build parquet writer properties.
+    pub fn parquet_writer_properties(&self) -> WriterProperties {
+        let mut builder = WriterProperties::builder()
+            .set_writer_version(WriterVersion::PARQUET_2_0)
+            .set_compression(self.parquet_compression)
+            .set_data_page_size_limit(self.parquet_page_size_bytes)
+            .set_dictionary_page_size_limit(self.parquet_dict_size_bytes);
+
+        for (col, fpp) in &self.parquet_bloom_filter_columns {
+            let path = ColumnPath::from(col.split('.').map(|v| v.to_string()).collect::<Vec<_>>());
+            builder = builder.set_column_bloom_filter_enabled(path.clone(), true);
+            if let Some(fpp) = fpp {
+                builder = builder.set_column_bloom_filter_fpp(path, *fpp);
+            }
+        }
+
+        builder.build()
+    }
+
+    /// Build orc writer properties.
+    pub fn orc_writer_properties(&self) -> OrcWriterProperties {
+        OrcWriterProperties::default()
+            .with_compression(self.orc_compression)
+            .with_stripe_size_bytes(self.orc_stripe_size_bytes)
+    }
+}
+
+fn parse_value<T>(key: &str, value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value.trim().parse().map_err(|e| {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Invalid value of property {key}: {value}"),
+        )
+        .set_source(e)
+    })
+}
+
+pub(crate) fn parse_or<T>(props: &HashMap<String, String>, key: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    props
+        .get(key)
+        .map(|v| parse_value(key, v))
+        .transpose()
+        .map(|v| v.unwrap_or(default))
+}
+
+fn parse_compression(codec: &str, level: Option<&str>) -> Result<Compression> {
+    let compression = match codec.to_lowercase().as_str() {
+        "uncompressed" | "none" => Compression::UNCOMPRESSED,
+        "snappy" => Compression::SNAPPY,
+        "lz4" => Compression::LZ4_RAW,
+        "gzip" => Compression::GZIP(match level {
+            Some(level) => GzipLevel::try_new(parse_value(PARQUET_COMPRESSION_LEVEL, level)?)?,
+            None => GzipLevel::default(),
+        }),
+        "zstd" => Compression::ZSTD(match level {
+            Some(level) => ZstdLevel::try_new(parse_value(PARQUET_COMPRESSION_LEVEL, level)?)?,
+            None => ZstdLevel::default(),
+        }),
+        "brotli" => Compression::BROTLI(match level {
+            Some(level) => BrotliLevel::try_new(parse_value(PARQUET_COMPRESSION_LEVEL, level)?)?,
+            None => BrotliLevel::default(),
+        }),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("Unsupported parquet compression codec: {codec}"),
+            ))
+        }
+    };
+    Ok(compression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(kvs: &[(&str, &str)]) -> HashMap<String, String> {
+        kvs.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_metrics_mode_parse() {
+        assert_eq!("none".parse::<MetricsMode>().unwrap(), MetricsMode::None);
+        assert_eq!(
+            "Counts".parse::<MetricsMode>().unwrap(),
+            MetricsMode::Counts
+        );
+        assert_eq!(
+            "truncate(16)".parse::<MetricsMode>().unwrap(),
+            MetricsMode::Truncate(16)
+        );
+        assert_eq!("full".parse::<MetricsMode>().unwrap(), MetricsMode::Full);
+        assert!("truncate(0)".parse::<MetricsMode>().is_err());
+        assert!("unknown".parse::<MetricsMode>().is_err());
+    }
+
+    #[test]
+    fn test_writer_config_default() {
+        let config = WriterConfig::from_properties(&HashMap::new()).unwrap();
+        assert_eq!(config.file_format(), DataFileFormat::Parquet);
+        assert_eq!(
+            config.target_file_size_in_bytes(),
+            WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT
+        );
+        assert_eq!(config.metrics_mode("a"), MetricsMode::Truncate(16));
+        assert!(config.fanout_enabled());
+        assert_eq!(config.max_open_files(), WRITE_MAX_OPEN_FILES_DEFAULT);
+        assert_eq!(
+            config.parquet_row_group_size_bytes(),
+            PARQUET_ROW_GROUP_SIZE_BYTES_DEFAULT
+        );
+
+        let props = config.parquet_writer_properties();
+        let col = ColumnPath::from("a");
+        assert_eq!(
+            props.compression(&col),
+            Compression::GZIP(GzipLevel::default())
+        );
+        assert_eq!(
+            props.data_page_size_limit(),
+            PARQUET_PAGE_SIZE_BYTES_DEFAULT
+        );
+        assert!(props.bloom_filter_properties(&col).is_none());
+
+        assert_eq!(config.orc_compression, OrcCompression::Zlib);
+        assert_eq!(config.orc_stripe_size_bytes, ORC_STRIPE_SIZE_BYTES_DEFAULT);
+    }
+
+    #[test]
+    fn test_writer_config_from_properties() {
+        let config = WriterConfig::from_properties(&props(&[
+            (WRITE_TARGET_FILE_SIZE_BYTES, "1024"),
+            (DEFAULT_WRITE_METRICS_MODE, "counts"),
+            ("write.metadata.metrics.column.a.b", "full"),
+            (PARQUET_COMPRESSION, "zstd"),
+            (PARQUET_COMPRESSION_LEVEL, "3"),
+            (PARQUET_DICT_SIZE_BYTES, "4096"),
+            (PARQUET_ROW_GROUP_SIZE_BYTES, "8192"),
+            ("write.parquet.bloom-filter-enabled.column.a.b", "true"),
+            ("write.parquet.bloom-filter-fpp.column.a.b", "0.01"),
+            ("write.parquet.bloom-filter-enabled.column.c", "false"),
+            (ORC_COMPRESSION, "zstd"),
+            (ORC_STRIPE_SIZE_BYTES, "1024"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.target_file_size_in_bytes(), 1024);
+        assert_eq!(config.parquet_row_group_size_bytes(), 8192);
+        assert_eq!(config.metrics_mode("a.b"), MetricsMode::Full);
+        assert_eq!(config.metrics_mode("c"), MetricsMode::Counts);
+
+        let props = config.parquet_writer_properties();
+        let col = ColumnPath::from(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(
+            props.compression(&col),
+            Compression::ZSTD(ZstdLevel::try_new(3).unwrap())
+        );
+        assert_eq!(props.dictionary_page_size_limit(), 4096);
+        assert_eq!(props.bloom_filter_properties(&col).unwrap().fpp, 0.01);
+        assert!(props
+            .bloom_filter_properties(&ColumnPath::from("c"))
+            .is_none());
+
+        assert_eq!(config.orc_compression, OrcCompression::Zstd);
+        assert_eq!(config.orc_stripe_size_bytes, 1024);
+    }
+
+    #[test]
+    fn test_writer_config_options_precedence() {
+        let metadata = {
+            let path = format!(
+                "{}/../testdata/simple_table/metadata/v1.metadata.json",
+                env!("CARGO_MANIFEST_DIR")
+            );
+            let bs = std::fs::read(path).expect("read_file must succeed");
+            let mut metadata = crate::types::parse_table_metadata(&bs)
+                .expect("parse_table_metadata v1 must succeed");
+            metadata.properties = Some(props(&[
+                (WRITE_TARGET_FILE_SIZE_BYTES, "1024"),
+                (DEFAULT_WRITE_METRICS_MODE, "none"),
+            ]));
+            metadata
+        };
+
+        let config =
+            WriterConfig::try_new(&metadata, &props(&[(WRITE_TARGET_FILE_SIZE_BYTES, "2048")]))
+                .unwrap();
+        assert_eq!(config.target_file_size_in_bytes(), 2048);
+        assert_eq!(config.metrics_mode("a"), MetricsMode::None);
+
+        assert!(
+            WriterConfig::try_new(&metadata, &props(&[(WRITE_TARGET_FILE_SIZE_BYTES, "abc")]),)
+                .is_err()
+        );
+        assert!(WriterConfig::try_new(&metadata, &props(&[(ORC_COMPRESSION, "lzo")])).is_err());
+    }
+}
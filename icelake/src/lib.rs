@@ -6,12 +6,25 @@
 #![allow(dead_code)]
 
 mod table;
-pub use table::Table;
+pub use table::{Table, TableBuilder};
 mod error;
 pub use error::Error;
 pub use error::ErrorKind;
 pub use error::Result;
 
+pub mod catalog;
+pub mod commit;
+pub mod config;
+pub mod events;
+pub mod inspect;
 pub mod io;
+pub mod lock;
+pub mod maintenance;
+pub mod metrics;
+pub mod scan;
+pub mod table_properties;
 pub mod transaction;
 pub mod types;
+
+#[cfg(test)]
+mod test_utils;
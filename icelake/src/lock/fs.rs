@@ -0,0 +1,380 @@
+use std::io::ErrorKind as IoErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use tokio::fs;
+use uuid::Uuid;
+
+use super::LockProvider;
+use crate::{Error, ErrorKind, Result};
+
+/// FileSystemLockProvider implements [`LockProvider`] by lock files in a
+/// local (or mounted) directory.
+///
+/// A lock file contains the owner id, the expiration time and a version
+/// unique for every write of the lock. It's created by hard linking a fully
+/// written file, so creation is exclusive and never observed half written.
+///
+/// A lock file is only removed or refreshed after it's moved away by an
+/// atomic rename, and its version is checked to be the one expected, so a
+/// lock taken over by others is never removed by a stale writer. Expired
+/// lock files are taken over by the next owner in the same way.
+pub struct FileSystemLockProvider {
+    dir: PathBuf,
+    ttl: Duration,
+    acquire_timeout: Duration,
+    retry_interval: Duration,
+}
+
+/// Content of a lock file.
+struct LockFile {
+    owner: String,
+    expire_at: u128,
+    version: String,
+}
+
+impl FileSystemLockProvider {
+    /// Create a new lock provider storing lock files under `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: Duration::from_secs(60),
+            acquire_timeout: Duration::from_secs(30),
+            retry_interval: Duration::from_millis(100),
+        }
+    }
+
+    /// Configure how long a lock is valid without heartbeat.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Configure how long [`LockProvider::acquire`] waits for a lock held
+    /// by others.
+    pub fn with_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Configure the interval between two attempts of acquiring.
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    fn lock_path(&self, key: &str) -> PathBuf {
+        let name: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{name}.lock"))
+    }
+
+    /// Path of a temporary file of given suffix in the lock dir.
+    fn tmp_path(&self, suffix: &str) -> PathBuf {
+        self.dir.join(format!("{}.lock.{suffix}", Uuid::new_v4()))
+    }
+
+    fn now_ms() -> Result<u128> {
+        Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis())
+    }
+
+    fn content(&self, owner: &str) -> Result<String> {
+        Ok(format!(
+            "{owner}\n{}\n{}",
+            Self::now_ms()? + self.ttl.as_millis(),
+            Uuid::new_v4()
+        ))
+    }
+
+    /// Read the lock file, `None` if not exist.
+    async fn read_lock(path: &Path) -> Result<Option<LockFile>> {
+        let content = match fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error("read lock file failed", path, e)),
+        };
+
+        let corrupted = || {
+            Error::new(ErrorKind::Unexpected, "lock file is corrupted")
+                .with_context("path", path.to_string_lossy())
+        };
+        let mut lines = content.lines();
+        let (Some(owner), Some(expire_at), Some(version)) =
+            (lines.next(), lines.next(), lines.next())
+        else {
+            return Err(corrupted());
+        };
+        let expire_at = expire_at
+            .trim()
+            .parse()
+            .map_err(|e| corrupted().set_source(e))?;
+        Ok(Some(LockFile {
+            owner: owner.to_string(),
+            expire_at,
+            version: version.trim().to_string(),
+        }))
+    }
+
+    /// Create the lock file of `owner` exclusively, returns false if the
+    /// lock file exists.
+    async fn create_lock(&self, path: &Path, owner: &str) -> Result<bool> {
+        let tmp = self.tmp_path("tmp");
+        fs::write(&tmp, self.content(owner)?)
+            .await
+            .map_err(|e| io_error("write lock file failed", &tmp, e))?;
+        let linked = fs::hard_link(&tmp, path).await;
+        if let Err(e) = fs::remove_file(&tmp).await {
+            log::warn!("Failed to remove temporary lock file {tmp:?}: {e}");
+        }
+        match linked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == IoErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(io_error("create lock file failed", path, e)),
+        }
+    }
+
+    /// Move the lock file away if `expected` holds for it, returns the lock
+    /// file moved, `None` if it doesn't exist or isn't expected.
+    ///
+    /// The lock file is moved by an atomic rename before checked, so only
+    /// one writer gets it. Unexpected lock files, like one just taken over
+    /// by others, are linked back.
+    async fn take_lock(
+        &self,
+        path: &Path,
+        expected: impl Fn(&LockFile) -> bool,
+    ) -> Result<Option<LockFile>> {
+        let taken = self.tmp_path("taken");
+        match fs::rename(path, &taken).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error("take lock file failed", path, e)),
+        }
+
+        let lock = Self::read_lock(&taken).await;
+        let res = match lock {
+            Ok(Some(lock)) if expected(&lock) => Ok(Some(lock)),
+            Ok(_) => {
+                match fs::hard_link(&taken, path).await {
+                    Ok(()) => {}
+                    // Created by others while it's moved away, its holder
+                    // will find it's not held on next heartbeat.
+                    Err(e) if e.kind() == IoErrorKind::AlreadyExists => {
+                        log::warn!("Lock file {path:?} is replaced while checked");
+                    }
+                    Err(e) => return Err(io_error("restore lock file failed", path, e)),
+                }
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        };
+        if let Err(e) = fs::remove_file(&taken).await {
+            log::warn!("Failed to remove taken lock file {taken:?}: {e}");
+        }
+        res
+    }
+
+    /// Move the lock file away if it's held by `owner` and not expired.
+    async fn take_own_lock(&self, path: &Path, owner: &str) -> Result<()> {
+        let now = Self::now_ms()?;
+        match self
+            .take_lock(path, |v| v.owner == owner && v.expire_at > now)
+            .await?
+        {
+            Some(_) => Ok(()),
+            None => Err(not_held(path, owner)),
+        }
+    }
+
+    /// Overwrite lock file of `owner` atomically.
+    async fn write_lock(&self, path: &Path, owner: &str) -> Result<()> {
+        let tmp = self.tmp_path("tmp");
+        fs::write(&tmp, self.content(owner)?)
+            .await
+            .map_err(|e| io_error("write lock file failed", &tmp, e))?;
+        fs::rename(&tmp, path)
+            .await
+            .map_err(|e| io_error("write lock file failed", path, e))
+    }
+}
+
+fn not_held(path: &Path, owner: &str) -> Error {
+    Error::new(ErrorKind::Unexpected, "lock is not held by owner")
+        .with_context("path", path.to_string_lossy())
+        .with_context("owner", owner)
+}
+
+fn io_error(message: &str, path: &Path, e: std::io::Error) -> Error {
+    Error::new(ErrorKind::Unexpected, message)
+        .with_context("path", path.to_string_lossy())
+        .set_source(e)
+}
+
+#[async_trait]
+impl LockProvider for FileSystemLockProvider {
+    async fn acquire(&self, key: &str, owner: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| io_error("create lock dir failed", &self.dir, e))?;
+
+        let path = self.lock_path(key);
+        let start = Instant::now();
+        loop {
+            if self.create_lock(&path, owner).await? {
+                return Ok(());
+            }
+
+            match Self::read_lock(&path).await? {
+                // Locks held by the same owner or expired are replaced by a
+                // new lock, unless changed since read.
+                Some(lock) if lock.owner == owner || lock.expire_at <= Self::now_ms()? => {
+                    if lock.owner != owner {
+                        log::warn!("lock {key} held by {} expired, taking over", lock.owner);
+                    }
+                    self.take_lock(&path, |v| v.version == lock.version).await?;
+                    continue;
+                }
+                // Removed by holder, try again immediately.
+                None => continue,
+                Some(_) => {}
+            }
+
+            if start.elapsed() >= self.acquire_timeout {
+                return Err(Error::new(ErrorKind::Unexpected, "acquire lock timeout")
+                    .with_context("key", key)
+                    .with_context("owner", owner));
+            }
+            tokio::time::sleep(self.retry_interval).await;
+        }
+    }
+
+    /// The lock file is overwritten in place, since unexpired locks are
+    /// never taken over by others.
+    async fn heartbeat(&self, key: &str, owner: &str) -> Result<()> {
+        let path = self.lock_path(key);
+        match Self::read_lock(&path).await? {
+            Some(lock) if lock.owner == owner && lock.expire_at > Self::now_ms()? => {
+                self.write_lock(&path, owner).await
+            }
+            _ => Err(not_held(&path, owner)),
+        }
+    }
+
+    async fn release(&self, key: &str, owner: &str) -> Result<()> {
+        let path = self.lock_path(key);
+        self.take_own_lock(&path, owner).await
+    }
+
+    fn heartbeat_interval(&self) -> Duration {
+        self.ttl / 3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fs_lock_exclusive() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        let provider = FileSystemLockProvider::new(tmp_dir.path())
+            .with_acquire_timeout(Duration::from_millis(200))
+            .with_retry_interval(Duration::from_millis(10));
+
+        provider.acquire("s3://bucket/table", "a").await?;
+        // Reentrant for the same owner.
+        provider.acquire("s3://bucket/table", "a").await?;
+        provider.heartbeat("s3://bucket/table", "a").await?;
+
+        assert!(provider.acquire("s3://bucket/table", "b").await.is_err());
+        assert!(provider.heartbeat("s3://bucket/table", "b").await.is_err());
+        assert!(provider.release("s3://bucket/table", "b").await.is_err());
+        // Other keys are not affected.
+        provider.acquire("s3://bucket/other", "b").await?;
+
+        provider.release("s3://bucket/table", "a").await?;
+        provider.acquire("s3://bucket/table", "b").await?;
+        assert!(provider.release("s3://bucket/table", "a").await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fs_lock_expired() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        let provider = FileSystemLockProvider::new(tmp_dir.path())
+            .with_ttl(Duration::from_millis(50))
+            .with_retry_interval(Duration::from_millis(10));
+
+        provider.acquire("table", "a").await?;
+        provider.acquire("table", "b").await?;
+        assert!(provider.heartbeat("table", "a").await.is_err());
+        provider.release("table", "b").await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fs_lock_concurrent_takeover() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        FileSystemLockProvider::new(tmp_dir.path())
+            .with_ttl(Duration::from_millis(1))
+            .acquire("table", "crashed")
+            .await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Only one of the owners racing for the expired lock gets it.
+        let provider = Arc::new(
+            FileSystemLockProvider::new(tmp_dir.path())
+                .with_acquire_timeout(Duration::from_millis(200))
+                .with_retry_interval(Duration::from_millis(10)),
+        );
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let provider = provider.clone();
+                tokio::spawn(async move { provider.acquire("table", &i.to_string()).await })
+            })
+            .collect();
+        let mut acquired = 0;
+        for handle in handles {
+            if handle.await.unwrap().is_ok() {
+                acquired += 1;
+            }
+        }
+        assert_eq!(acquired, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fs_lock_stale_takeover() -> Result<()> {
+        let tmp_dir = TempDir::new().unwrap();
+        let provider = FileSystemLockProvider::new(tmp_dir.path());
+        let path = provider.lock_path("table");
+        provider.acquire("table", "a").await?;
+        let stale = FileSystemLockProvider::read_lock(&path).await?.unwrap();
+
+        // The lock is taken over by others after it's read, taking it by
+        // the version read leaves the new lock in place.
+        provider.take_lock(&path, |_| true).await?;
+        provider.acquire("table", "b").await?;
+        assert!(provider
+            .take_lock(&path, |v| v.version == stale.version)
+            .await?
+            .is_none());
+        let lock = FileSystemLockProvider::read_lock(&path).await?.unwrap();
+        assert_eq!(lock.owner, "b");
+        provider.release("table", "b").await?;
+        assert!(FileSystemLockProvider::read_lock(&path).await?.is_none());
+
+        // Only lock files are left in the lock dir.
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+//! lock module provides the [`LockProvider`] used to guard table commits.
+//!
+//! Hadoop style tables rely on atomic rename of metadata file, which is not
+//! safe when several writers commit to the same table at the same time, or
+//! when the storage can't rename atomically. Configuring a lock provider by
+//! [`crate::Table::set_lock_provider`] makes commits of the same table
+//! exclusive across processes.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::Result;
+
+mod fs;
+pub use fs::FileSystemLockProvider;
+
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "redis")]
+pub use self::redis::RedisLockProvider;
+
+/// LockProvider provides exclusive locks identified by a key, like the
+/// location of a table.
+///
+/// Every lock is held by an owner id. An acquired lock will expire if it's
+/// not refreshed by [`LockProvider::heartbeat`] in time, so that locks of
+/// crashed owners can be taken over by others.
+#[async_trait]
+pub trait LockProvider: Send + Sync {
+    /// Acquire the lock of `key` for `owner`.
+    ///
+    /// Acquiring a lock that is already held by the same owner will refresh
+    /// it. Returns error if the lock can't be acquired before timeout.
+    async fn acquire(&self, key: &str, owner: &str) -> Result<()>;
+
+    /// Extend the expiration of the lock of `key` held by `owner`.
+    ///
+    /// Returns error if the lock is not held by `owner` anymore.
+    async fn heartbeat(&self, key: &str, owner: &str) -> Result<()>;
+
+    /// Release the lock of `key` held by `owner`.
+    ///
+    /// Releasing a lock that is not held by `owner` is an error.
+    async fn release(&self, key: &str, owner: &str) -> Result<()>;
+
+    /// Interval of heartbeats keeping an acquired lock alive, which should
+    /// be well below the expiration of locks.
+    fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
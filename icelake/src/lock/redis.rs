@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::{Client, Script};
+
+use super::LockProvider;
+use crate::{Error, ErrorKind, Result};
+
+/// Refresh the expiration only if the lock is held by the owner.
+const HEARTBEAT_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+/// Delete the lock only if it is held by the owner.
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// RedisLockProvider implements [`LockProvider`] by redis keys with
+/// expiration, which is suitable for writers distributed across hosts.
+pub struct RedisLockProvider {
+    conn: ConnectionManager,
+    prefix: String,
+    ttl: Duration,
+    acquire_timeout: Duration,
+    retry_interval: Duration,
+}
+
+impl RedisLockProvider {
+    /// Connect to redis at given url, like `redis://127.0.0.1:6379`.
+    pub async fn try_new(url: &str) -> Result<Self> {
+        let client = Client::open(url).map_err(redis_error)?;
+        let conn = ConnectionManager::new(client).await.map_err(redis_error)?;
+
+        Ok(Self {
+            conn,
+            prefix: "icelake:lock:".to_string(),
+            ttl: Duration::from_secs(60),
+            acquire_timeout: Duration::from_secs(30),
+            retry_interval: Duration::from_millis(100),
+        })
+    }
+
+    /// Configure the prefix of redis keys, default to `icelake:lock:`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Configure how long a lock is valid without heartbeat.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Configure how long [`LockProvider::acquire`] waits for a lock held
+    /// by others.
+    pub fn with_acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Configure the interval between two attempts of acquiring.
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    fn redis_key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+
+    async fn run_owner_script(&self, script: &str, key: &str, owner: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let updated: i64 = Script::new(script)
+            .key(self.redis_key(key))
+            .arg(owner)
+            .arg(self.ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+        Ok(updated == 1)
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> Error {
+    Error::new(ErrorKind::Unexpected, "redis operation failed").set_source(e)
+}
+
+#[async_trait]
+impl LockProvider for RedisLockProvider {
+    async fn acquire(&self, key: &str, owner: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let start = Instant::now();
+        loop {
+            let acquired: Option<String> = redis::cmd("SET")
+                .arg(self.redis_key(key))
+                .arg(owner)
+                .arg("NX")
+                .arg("PX")
+                .arg(self.ttl.as_millis() as u64)
+                .query_async(&mut conn)
+                .await
+                .map_err(redis_error)?;
+            if acquired.is_some() {
+                return Ok(());
+            }
+            // Refresh the lock if it's already held by the owner.
+            if self.run_owner_script(HEARTBEAT_SCRIPT, key, owner).await? {
+                return Ok(());
+            }
+
+            if start.elapsed() >= self.acquire_timeout {
+                return Err(Error::new(ErrorKind::Unexpected, "acquire lock timeout")
+                    .with_context("key", key)
+                    .with_context("owner", owner));
+            }
+            tokio::time::sleep(self.retry_interval).await;
+        }
+    }
+
+    async fn heartbeat(&self, key: &str, owner: &str) -> Result<()> {
+        if self.run_owner_script(HEARTBEAT_SCRIPT, key, owner).await? {
+            Ok(())
+        } else {
+            Err(
+                Error::new(ErrorKind::Unexpected, "lock is not held by owner")
+                    .with_context("key", key)
+                    .with_context("owner", owner),
+            )
+        }
+    }
+
+    async fn release(&self, key: &str, owner: &str) -> Result<()> {
+        if self.run_owner_script(RELEASE_SCRIPT, key, owner).await? {
+            Ok(())
+        } else {
+            Err(
+                Error::new(ErrorKind::Unexpected, "lock is not held by owner")
+                    .with_context("key", key)
+                    .with_context("owner", owner),
+            )
+        }
+    }
+
+    fn heartbeat_interval(&self) -> Duration {
+        self.ttl / 3
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::table_properties::{
+    MAX_REF_AGE_MS, MAX_SNAPSHOT_AGE_MS, MAX_SNAPSHOT_AGE_MS_DEFAULT, MIN_SNAPSHOTS_TO_KEEP,
+    MIN_SNAPSHOTS_TO_KEEP_DEFAULT,
+};
+use crate::types::{Snapshot, SnapshotReferenceType, TableMetadata, MAIN_BRANCH};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// ExpireSnapshots removes old snapshots from table metadata and deletes
+/// files only referenced by them.
+///
+/// Snapshots of each branch are kept if they are one of the last
+/// `retain_last` ancestors of the branch, or newer than `older_than`.
+/// Snapshots referenced by tags and the head of each branch are never
+/// expired. References older than their `max-ref-age-ms` are removed
+/// first, except the main branch.
+///
+/// Files are deleted after the new metadata is committed, so that a failed
+/// deletion only leaks files.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let result = table
+///     .expire_snapshots()
+///     .with_older_than(1686912871713)
+///     .with_retain_last(10)
+///     .commit()
+///     .await?;
+/// println!("expired {:?}", result.expired_snapshot_ids);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ExpireSnapshots<'a> {
+    table: &'a mut Table,
+    older_than_ms: Option<i64>,
+    retain_last: Option<usize>,
+}
+
+/// Result of [`ExpireSnapshots::commit`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExpireSnapshotsResult {
+    /// Ids of snapshots removed from table metadata.
+    pub expired_snapshot_ids: Vec<i64>,
+    /// Number of deleted manifest lists.
+    pub deleted_manifest_lists: usize,
+    /// Number of deleted manifests.
+    pub deleted_manifests: usize,
+    /// Number of deleted data files.
+    pub deleted_data_files: usize,
+}
+
+impl<'a> ExpireSnapshots<'a> {
+    /// Create a new action to expire snapshots of table.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            table,
+            older_than_ms: None,
+            retain_last: None,
+        }
+    }
+
+    /// Expire snapshots older than the timestamp in milliseconds.
+    ///
+    /// Default to now minus table property `history.expire.max-snapshot-age-ms`.
+    /// Branches with `max-snapshot-age-ms` use their own value instead.
+    pub fn with_older_than(mut self, timestamp_ms: i64) -> Self {
+        self.older_than_ms = Some(timestamp_ms);
+        self
+    }
+
+    /// Retain at least `n` last ancestors of each branch, including the
+    /// head, regardless of their age.
+    ///
+    /// Default to table property `history.expire.min-snapshots-to-keep`.
+    /// Branches with `min-snapshots-to-keep` use their own value instead.
+    pub fn with_retain_last(mut self, n: usize) -> Self {
+        self.retain_last = Some(n.max(1));
+        self
+    }
+
+    /// Commit new table metadata without expired snapshots, then delete
+    /// files no longer referenced.
+    pub async fn commit(self) -> Result<ExpireSnapshotsResult> {
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let metadata = self.table.current_table_metadata();
+        let older_than_ms = match self.older_than_ms {
+            Some(v) => v,
+            None => {
+                now_ms
+                    - self
+                        .property(MAX_SNAPSHOT_AGE_MS)?
+                        .unwrap_or(MAX_SNAPSHOT_AGE_MS_DEFAULT)
+            }
+        };
+        let retain_last = match self.retain_last {
+            Some(v) => v,
+            None => self
+                .property(MIN_SNAPSHOTS_TO_KEEP)?
+                .unwrap_or(MIN_SNAPSHOTS_TO_KEEP_DEFAULT),
+        };
+        let max_ref_age_ms: Option<i64> = self.property(MAX_REF_AGE_MS)?;
+
+        let snapshots: HashMap<i64, &Snapshot> = metadata
+            .snapshots
+            .iter()
+            .flatten()
+            .map(|v| (v.snapshot_id, v))
+            .collect();
+
+        // Expire references first, their snapshots may be expired then.
+        let mut refs = metadata.refs.clone();
+        refs.retain(|name, r| {
+            if name == MAIN_BRANCH {
+                return true;
+            }
+            let Some(snapshot) = snapshots.get(&r.snapshot_id) else {
+                return false;
+            };
+            match r.max_ref_age_ms.or(max_ref_age_ms) {
+                Some(max_age) => now_ms - snapshot.timestamp_ms <= max_age,
+                None => true,
+            }
+        });
+
+        let mut retained: HashSet<i64> = HashSet::new();
+        let mut heads: Vec<(i64, Option<usize>, Option<i64>)> = refs
+            .values()
+            .map(|r| match r.typ {
+                SnapshotReferenceType::Branch => (
+                    r.snapshot_id,
+                    r.min_snapshots_to_keep.map(|v| v.max(1) as usize),
+                    r.max_snapshot_age_ms.map(|v| now_ms - v),
+                ),
+                // Only the tagged snapshot is kept.
+                SnapshotReferenceType::Tag => (r.snapshot_id, Some(1), Some(i64::MAX)),
+            })
+            .collect();
+        // Tables written by old writers may not record the main branch.
+        if let Some(current) = metadata.current_snapshot_id {
+            heads.push((current, None, None));
+        }
+        for (head, branch_retain_last, branch_older_than_ms) in heads {
+            let retain_last = branch_retain_last.unwrap_or(retain_last);
+            let older_than_ms = branch_older_than_ms.unwrap_or(older_than_ms);
+            let mut next = snapshots.get(&head);
+            let mut count = 0;
+            while let Some(snapshot) = next {
+                if count >= retain_last && snapshot.timestamp_ms < older_than_ms {
+                    break;
+                }
+                retained.insert(snapshot.snapshot_id);
+                count += 1;
+                next = snapshot
+                    .parent_snapshot_id
+                    .and_then(|id| snapshots.get(&id));
+            }
+        }
+        // Snapshots not in any branch are expired by age only.
+        retained.extend(
+            snapshots
+                .values()
+                .filter(|v| v.timestamp_ms >= older_than_ms)
+                .map(|v| v.snapshot_id),
+        );
+
+        let (kept, expired): (Vec<&Snapshot>, Vec<&Snapshot>) = metadata
+            .snapshots
+            .iter()
+            .flatten()
+            .partition(|v| retained.contains(&v.snapshot_id));
+        if expired.is_empty() && refs.len() == metadata.refs.len() {
+            return Ok(ExpireSnapshotsResult::default());
+        }
+
+        let mut result = ExpireSnapshotsResult {
+            expired_snapshot_ids: expired.iter().map(|v| v.snapshot_id).collect(),
+            ..Default::default()
+        };
+        let new_metadata = TableMetadata {
+            snapshots: Some(kept.iter().map(|v| (*v).clone()).collect()),
+            snapshot_log: metadata.snapshot_log.as_ref().map(|logs| {
+                logs.iter()
+                    .filter(|v| retained.contains(&v.snapshot_id))
+                    .copied()
+                    .collect()
+            }),
+            refs,
+            statistics: metadata
+                .statistics
+                .iter()
+                .filter(|v| retained.contains(&v.snapshot_id))
+                .cloned()
+                .collect(),
+            last_updated_ms: now_ms,
+            ..metadata.clone()
+        };
+        let kept: Vec<Snapshot> = kept.into_iter().cloned().collect();
+        let expired: Vec<Snapshot> = expired.into_iter().cloned().collect();
+
+        self.table.commit(new_metadata).await?;
+
+        let table = &*self.table;
+        let (kept_manifests, kept_data_files) = reachable_files(table, &kept).await?;
+        let (expired_manifests, expired_data_files) = reachable_files(table, &expired).await?;
+        for snapshot in &expired {
+            delete_file(table, &snapshot.manifest_list).await?;
+            result.deleted_manifest_lists += 1;
+        }
+        for path in expired_manifests.difference(&kept_manifests) {
+            delete_file(table, path).await?;
+            result.deleted_manifests += 1;
+        }
+        for path in expired_data_files.difference(&kept_data_files) {
+            delete_file(table, path).await?;
+            result.deleted_data_files += 1;
+        }
+
+        Ok(result)
+    }
+
+    fn property<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.table
+            .config()
+            .get(self.table.current_table_metadata().properties.as_ref(), key)
+            .map(|v| {
+                v.trim().parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid value of property {key}: {v}"),
+                    )
+                    .set_source(e)
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Returns manifests and live data files referenced by snapshots.
+async fn reachable_files(
+    table: &Table,
+    snapshots: &[Snapshot],
+) -> Result<(HashSet<String>, HashSet<String>)> {
+    let mut manifests = HashSet::new();
+    let mut data_files = HashSet::new();
+    for snapshot in snapshots {
+        let manifest_list = snapshot.load_manifest_list(table).await?;
+        for entry in manifest_list.entries {
+            if !manifests.insert(entry.manifest_path.clone()) {
+                continue;
+            }
+            let manifest = entry.load_manifest(table).await?;
+            data_files.extend(
+                manifest
+                    .entries
+                    .into_iter()
+                    .filter(|v| v.is_alive())
+                    .map(|v| v.data_file.file_path),
+            );
+        }
+    }
+    Ok((manifests, data_files))
+}
+
+async fn delete_file(table: &Table, location: &str) -> Result<()> {
+    let (op, path) = table.location_operator(location)?;
+    op.delete(&path).await.map_err(|e| {
+        Error::new(ErrorKind::Unexpected, "Failed to delete expired file")
+            .with_context("location", location)
+            .set_source(e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::types::expression::Predicate;
+    use crate::types::DataFile;
+
+    async fn write_data_files(table: &Table) -> Vec<DataFile> {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        writer.close().await.unwrap()
+    }
+
+    async fn exists(table: &Table, location: &str) -> bool {
+        let (op, path) = table.location_operator(location).unwrap();
+        op.is_exist(&path).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_expire_snapshots() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let old_snapshot = table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .clone();
+        let old_data_files = table.current_data_files().await.unwrap();
+
+        let data_files = write_data_files(&table).await;
+        let mut overwrite = table.new_overwrite();
+        overwrite.overwrite_by_filter(Predicate::AlwaysTrue);
+        overwrite.append_file(data_files);
+        overwrite.commit().await.unwrap();
+        let new_snapshot_id = table.current_table_metadata().current_snapshot_id;
+
+        // The old snapshot is older than 5 days by default.
+        let result = table.expire_snapshots().commit().await.unwrap();
+        assert_eq!(
+            result,
+            ExpireSnapshotsResult {
+                expired_snapshot_ids: vec![old_snapshot.snapshot_id],
+                deleted_manifest_lists: 1,
+                deleted_manifests: 1,
+                deleted_data_files: 3,
+            }
+        );
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_snapshot_id, new_snapshot_id);
+        assert_eq!(metadata.snapshots.as_ref().unwrap().len(), 1);
+        assert_eq!(metadata.snapshot_log.as_ref().unwrap().len(), 1);
+        assert!(table
+            .current_metadata_file_location()
+            .ends_with("v4.metadata.json"));
+        assert!(!exists(&table, &old_snapshot.manifest_list).await);
+        for data_file in &old_data_files {
+            assert!(!exists(&table, &data_file.file_path).await);
+        }
+        assert_eq!(table.current_data_files().await.unwrap().len(), 1);
+
+        // Nothing to expire.
+        let result = table.expire_snapshots().commit().await.unwrap();
+        assert_eq!(result, ExpireSnapshotsResult::default());
+        assert!(table
+            .current_metadata_file_location()
+            .ends_with("v4.metadata.json"));
+    }
+
+    #[tokio::test]
+    async fn test_expire_snapshots_retain_last() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let mut snapshot_ids = vec![table.current_table_metadata().current_snapshot_id.unwrap()];
+        for _ in 0..2 {
+            let data_files = write_data_files(&table).await;
+            let mut append = table.new_append();
+            append.append_file(data_files);
+            append.commit().await.unwrap();
+            snapshot_ids.push(table.current_table_metadata().current_snapshot_id.unwrap());
+        }
+
+        let result = table
+            .expire_snapshots()
+            .with_older_than(i64::MAX)
+            .with_retain_last(2)
+            .commit()
+            .await
+            .unwrap();
+        assert_eq!(result.expired_snapshot_ids, vec![snapshot_ids[0]]);
+        // Manifests and data files are still referenced by kept snapshots.
+        assert_eq!(result.deleted_manifest_lists, 1);
+        assert_eq!(result.deleted_manifests, 0);
+        assert_eq!(result.deleted_data_files, 0);
+        assert_eq!(table.current_data_files().await.unwrap().len(), 5);
+    }
+}
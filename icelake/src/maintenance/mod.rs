@@ -0,0 +1,14 @@
+//! maintenance module provides operations to keep tables healthy, like
+//! expiring old snapshots, removing orphan files and compacting small
+//! data files, delete files and manifests.
+
+mod expire_snapshots;
+pub use expire_snapshots::*;
+mod remove_orphan_files;
+pub use remove_orphan_files::*;
+mod rewrite_data_files;
+pub use rewrite_data_files::*;
+mod rewrite_manifests;
+pub use rewrite_manifests::*;
+mod rewrite_position_deletes;
+pub use rewrite_position_deletes::*;
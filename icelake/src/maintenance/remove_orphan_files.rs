@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use futures::TryStreamExt;
+use opendal::{EntryMode, Metakey, Operator};
+use regex::Regex;
+
+use crate::table::{VERSIONED_TABLE_METADATA_FILE_PATTERN, VERSION_HINT_FILENAME};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// RemoveOrphanFiles deletes files under the data and metadata locations
+/// of a table that are not referenced by any snapshot.
+///
+/// Orphan files are left by failed writes or commits, by engines writing
+/// checksum files, or by snapshot expiration that failed halfway. Files are
+/// only removed if they were last modified before `older_than`, so that
+/// files of in-progress writes are left alone, the timestamp should be
+/// older than the longest running write.
+///
+/// Table metadata files and the version hint are always kept.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// let table = icelake::Table::open("/path/to/table").await?;
+/// let orphans = table
+///     .remove_orphan_files(1686912871713)
+///     .with_dry_run(true)
+///     .execute()
+///     .await?;
+/// println!("orphan files: {orphans:?}");
+/// # Ok(())
+/// # }
+/// ```
+pub struct RemoveOrphanFiles<'a> {
+    table: &'a Table,
+    older_than_ms: i64,
+    dry_run: bool,
+}
+
+impl<'a> RemoveOrphanFiles<'a> {
+    /// Create a new action removing orphan files last modified before the
+    /// timestamp in milliseconds.
+    pub fn new(table: &'a Table, older_than_ms: i64) -> Self {
+        Self {
+            table,
+            older_than_ms,
+            dry_run: false,
+        }
+    }
+
+    /// Only find orphan files without deleting them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Find and delete orphan files.
+    ///
+    /// Returns locations of orphan files, which are deleted unless in dry
+    /// run mode.
+    pub async fn execute(self) -> Result<Vec<String>> {
+        let table = self.table;
+        let metadata = table.current_table_metadata();
+
+        let mut referenced = HashSet::new();
+        for snapshot in metadata.snapshots.iter().flatten() {
+            referenced.insert(self.file_key(&snapshot.manifest_list)?);
+            let manifest_list = snapshot.load_manifest_list(table).await?;
+            for entry in manifest_list.entries {
+                if !referenced.insert(self.file_key(&entry.manifest_path)?) {
+                    continue;
+                }
+                let manifest = entry.load_manifest(table).await?;
+                // Deleted entries are kept too, they may be read by time
+                // travel of older snapshots.
+                for entry in manifest.entries {
+                    referenced.insert(self.file_key(&entry.data_file.file_path)?);
+                }
+            }
+        }
+
+        for statistics_file in &metadata.statistics {
+            referenced.insert(self.file_key(&statistics_file.statistics_path)?);
+        }
+
+        // Checksum files like `.v1.metadata.json.crc` are not kept.
+        let metadata_file_pattern =
+            Regex::new(&format!("^{VERSIONED_TABLE_METADATA_FILE_PATTERN}$"))?;
+        let mut listed = HashSet::new();
+        let mut orphans = vec![];
+        for dir in [
+            table.data_location(),
+            table.metadata_location(),
+            format!("{}/metadata", metadata.location),
+        ] {
+            let (op, dir_path) = table.location_operator(&dir)?;
+            let dir_path = format!("{}/", dir_path.trim_matches('/'));
+            let mut lister = op.scan(&dir_path).await?;
+            while let Some(entry) = lister.try_next().await? {
+                let meta = op
+                    .metadata(&entry, Metakey::Mode | Metakey::LastModified)
+                    .await?;
+                if meta.mode() != EntryMode::FILE {
+                    continue;
+                }
+                let key = file_key(&op, entry.path());
+                // Locations may overlap, like data under metadata location.
+                if !listed.insert(key.clone()) || referenced.contains(&key) {
+                    continue;
+                }
+                let name = entry.name();
+                if name == VERSION_HINT_FILENAME || metadata_file_pattern.is_match(name) {
+                    continue;
+                }
+                match meta.last_modified() {
+                    Some(t) if t.timestamp_millis() < self.older_than_ms => {}
+                    _ => continue,
+                }
+
+                let rel = entry.path().trim_start_matches('/');
+                let location = format!(
+                    "{dir}/{}",
+                    rel.strip_prefix(dir_path.trim_start_matches('/'))
+                        .unwrap_or(rel)
+                );
+                if !self.dry_run {
+                    op.delete(entry.path()).await.map_err(|e| {
+                        Error::new(ErrorKind::Unexpected, "Failed to delete orphan file")
+                            .with_context("location", &location)
+                            .set_source(e)
+                    })?;
+                }
+                orphans.push(location);
+            }
+        }
+
+        orphans.sort();
+        Ok(orphans)
+    }
+
+    fn file_key(&self, location: &str) -> Result<String> {
+        let (op, path) = self.table.location_operator(location)?;
+        Ok(file_key(&op, &path))
+    }
+}
+
+/// Returns a key identifying the file at `path` of the operator, the same
+/// file may be recorded by absolute or relative locations.
+fn file_key(op: &Operator, path: &str) -> String {
+    let info = op.info();
+    format!(
+        "{}:{}:{}/{}",
+        info.scheme(),
+        info.name(),
+        info.root().trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use futures::TryStreamExt;
+
+    use crate::test_utils::prepare_table_dir;
+    use crate::Table;
+
+    #[tokio::test]
+    async fn test_remove_orphan_files() {
+        let tmp_dir = prepare_table_dir();
+        let table_location = tmp_dir.path().to_str().unwrap();
+        let table = Table::open(table_location).await.unwrap();
+        let location = &table.current_table_metadata().location;
+        fs::write(tmp_dir.path().join("data").join("orphan.parquet"), "a").unwrap();
+        fs::write(tmp_dir.path().join("metadata").join("orphan-m0.avro"), "b").unwrap();
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        // Files are too new to be removed.
+        let orphans = table
+            .remove_orphan_files(now_ms - 60_000)
+            .execute()
+            .await
+            .unwrap();
+        assert!(orphans.is_empty());
+
+        let orphans = table
+            .remove_orphan_files(now_ms + 60_000)
+            .with_dry_run(true)
+            .execute()
+            .await
+            .unwrap();
+        // Checksum files written by spark are not referenced either.
+        let crc_files: Vec<_> = orphans.iter().filter(|v| v.ends_with(".crc")).collect();
+        assert_eq!(crc_files.len(), 8);
+        assert!(orphans.contains(&format!("{location}/data/orphan.parquet")));
+        assert!(orphans.contains(&format!("{location}/metadata/orphan-m0.avro")));
+        assert_eq!(orphans.len(), 10);
+        assert!(tmp_dir.path().join("data").join("orphan.parquet").exists());
+
+        let removed = table
+            .remove_orphan_files(now_ms + 60_000)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(removed, orphans);
+        assert!(!tmp_dir.path().join("data").join("orphan.parquet").exists());
+        assert!(!tmp_dir
+            .path()
+            .join("metadata")
+            .join("orphan-m0.avro")
+            .exists());
+
+        // The table is still readable.
+        let table = Table::open(table_location).await.unwrap();
+        let data_files = table.current_data_files().await.unwrap();
+        let batches: Vec<_> = table
+            .read_data_files(&data_files)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 3);
+    }
+}
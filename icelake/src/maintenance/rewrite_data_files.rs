@@ -0,0 +1,329 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::TryStreamExt;
+
+use crate::io::writer_config::WriterConfig;
+use crate::scan::{CombinedScanTask, FileScanTask};
+use crate::table_properties::WRITE_TARGET_FILE_SIZE_BYTES;
+use crate::types::{DataFile, StructValue};
+use crate::{Result, Table};
+
+/// Default min number of small files in a partition to rewrite.
+const MIN_INPUT_FILES_DEFAULT: usize = 2;
+
+/// RewriteDataFiles compacts small data files of each partition into larger
+/// files, and commits them as a `replace` snapshot.
+///
+/// Data files smaller than the target file size are selected, the rows of
+/// selected files in the same partition are read and written again by a
+/// task writer, which rolls files at the target size.
+///
+/// Rows deleted by delete files are removed from the new files. Delete
+/// files only applying to rewritten files are removed by the snapshot
+/// too, so that rewritten partitions are read without merging deletes.
+/// The commit fails if deletes of rewritten files are committed after the
+/// files are planned, which would be lost by the new files.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let result = table
+///     .rewrite_data_files()
+///     .with_target_file_size(128 * 1024 * 1024)
+///     .commit()
+///     .await?;
+/// println!("rewritten {} files", result.rewritten_data_files);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RewriteDataFiles<'a> {
+    table: &'a mut Table,
+    target_file_size: Option<u64>,
+    min_input_files: usize,
+    delete_file_threshold: usize,
+}
+
+/// Result of [`RewriteDataFiles::commit`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RewriteDataFilesResult {
+    /// Number of data files replaced.
+    pub rewritten_data_files: usize,
+    /// Number of data files written.
+    pub added_data_files: usize,
+    /// Number of delete files removed, whose deletes are applied to the
+    /// written data files.
+    pub removed_delete_files: usize,
+}
+
+impl<'a> RewriteDataFiles<'a> {
+    /// Create a new action to rewrite data files of table.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            table,
+            target_file_size: None,
+            min_input_files: MIN_INPUT_FILES_DEFAULT,
+            delete_file_threshold: usize::MAX,
+        }
+    }
+
+    /// Target size of rewritten files in bytes, files smaller than it are
+    /// rewritten.
+    ///
+    /// Default to table property `write.target-file-size-bytes`.
+    pub fn with_target_file_size(mut self, bytes: u64) -> Self {
+        self.target_file_size = Some(bytes);
+        self
+    }
+
+    /// Only rewrite partitions with at least `n` small files, default to
+    /// `2`.
+    pub fn with_min_input_files(mut self, n: usize) -> Self {
+        self.min_input_files = n.max(1);
+        self
+    }
+
+    /// Also rewrite data files with at least `n` delete files regardless of
+    /// their sizes and the number of small files in their partitions, to
+    /// apply the deletes. Default to no such files.
+    pub fn with_delete_file_threshold(mut self, n: usize) -> Self {
+        self.delete_file_threshold = n.max(1);
+        self
+    }
+
+    /// Rewrite selected files and commit the new files.
+    pub async fn commit(self) -> Result<RewriteDataFilesResult> {
+        let table = self.table;
+        let mut options = HashMap::new();
+        let target_file_size = match self.target_file_size {
+            Some(v) => {
+                options.insert(WRITE_TARGET_FILE_SIZE_BYTES.to_string(), v.to_string());
+                v
+            }
+            None => WriterConfig::from_properties(&table.properties())?.target_file_size_in_bytes(),
+        };
+
+        let scan = table.scan().build()?;
+        let snapshot_id = scan.snapshot_id();
+        let tasks = scan.plan_tasks().await?;
+        let has_many_deletes =
+            |task: &FileScanTask| task.delete_files.len() >= self.delete_file_threshold;
+
+        let mut groups: HashMap<&StructValue, Vec<FileScanTask>> = HashMap::new();
+        for task in &tasks {
+            if task.data_file.file_size_in_bytes as u64 >= target_file_size
+                && !has_many_deletes(task)
+            {
+                continue;
+            }
+            groups
+                .entry(&task.data_file.partition)
+                .or_default()
+                .push(task.clone());
+        }
+        groups.retain(|_, tasks| {
+            tasks.len() >= self.min_input_files || tasks.iter().any(has_many_deletes)
+        });
+        if groups.is_empty() {
+            return Ok(RewriteDataFilesResult::default());
+        }
+
+        let mut deleted = vec![];
+        let mut added = vec![];
+        for tasks in groups.into_values() {
+            let mut writer = table.task_writer_with_options(options.clone()).await?;
+            let task = CombinedScanTask { tasks };
+            let mut stream = scan.execute_task(&task).await?;
+            while let Some(batch) = stream.try_next().await? {
+                writer.write(&batch).await?;
+            }
+            added.extend(writer.close().await?);
+            deleted.extend(task.tasks.into_iter().map(|v| v.data_file));
+        }
+
+        // Delete files are removed if all data files they apply to are
+        // rewritten.
+        let rewritten: HashSet<&str> = deleted.iter().map(|v| v.file_path.as_str()).collect();
+        let mut delete_files: HashMap<&str, (&DataFile, bool)> = HashMap::new();
+        for task in &tasks {
+            let is_rewritten = rewritten.contains(task.data_file.file_path.as_str());
+            for delete_file in task.delete_files.iter().map(|v| &v.file) {
+                delete_files
+                    .entry(delete_file.file_path.as_str())
+                    .or_insert((delete_file, true))
+                    .1 &= is_rewritten;
+            }
+        }
+        let removed: Vec<DataFile> = delete_files
+            .into_values()
+            .filter(|(_, removed)| *removed)
+            .map(|(v, _)| v.clone())
+            .collect();
+        drop(scan);
+
+        let result = RewriteDataFilesResult {
+            rewritten_data_files: deleted.len(),
+            added_data_files: added.len(),
+            removed_delete_files: removed.len(),
+        };
+        let mut tx = table.new_transaction();
+        if let Some(snapshot_id) = snapshot_id {
+            tx.validate_from_snapshot(snapshot_id);
+        }
+        tx.rewrite_files(deleted.into_iter().chain(removed), added);
+        tx.commit().await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, Int64Array};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::ErrorKind;
+
+    async fn read_ids(table: &Table) -> Vec<i64> {
+        let data_files = table.current_data_files().await.unwrap();
+        let batches: Vec<_> = table
+            .read_data_files(&data_files)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let mut ids: Vec<i64> = batches
+            .iter()
+            .flat_map(|v| {
+                let ids = v.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                (0..ids.len()).map(|i| ids.value(i)).collect::<Vec<_>>()
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_data_files() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let ids = read_ids(&table).await;
+
+        let result = table.rewrite_data_files().commit().await.unwrap();
+        assert_eq!(
+            result,
+            RewriteDataFilesResult {
+                rewritten_data_files: 3,
+                added_data_files: 1,
+                removed_delete_files: 0,
+            }
+        );
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary["operation"], "replace");
+        assert_eq!(summary["total-data-files"], "1");
+        assert_eq!(summary["total-records"], "3");
+        assert_eq!(table.current_data_files().await.unwrap().len(), 1);
+        assert_eq!(read_ids(&table).await, ids);
+
+        // A single small file is not rewritten.
+        let snapshot_id = table.current_table_metadata().current_snapshot_id;
+        let result = table.rewrite_data_files().commit().await.unwrap();
+        assert_eq!(result, RewriteDataFilesResult::default());
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            snapshot_id
+        );
+    }
+
+    async fn scan_ids(table: &Table) -> Vec<i64> {
+        let scan = table.scan().build().unwrap();
+        let batches: Vec<_> = scan.execute().await.unwrap().try_collect().await.unwrap();
+        let mut ids: Vec<i64> = batches
+            .iter()
+            .flat_map(|v| {
+                let ids = v.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                ids.values().to_vec()
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_data_files_with_deletes() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let data_files = table.current_data_files().await.unwrap();
+        let mut writer = table
+            .position_delete_writer(data_files[0].partition.clone(), None)
+            .await
+            .unwrap();
+        writer.delete(&data_files[0].file_path, [0]).unwrap();
+        let mut append = table.new_append();
+        append.append_file(writer.close().await.unwrap());
+        append.commit().await.unwrap();
+        let ids = scan_ids(&table).await;
+        assert_eq!(ids.len(), 2);
+
+        // The partition is rewritten by the file with deletes, though it has
+        // less small files than required.
+        let result = table
+            .rewrite_data_files()
+            .with_min_input_files(4)
+            .with_delete_file_threshold(1)
+            .commit()
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            RewriteDataFilesResult {
+                rewritten_data_files: 3,
+                added_data_files: 1,
+                removed_delete_files: 1,
+            }
+        );
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary["operation"], "replace");
+        assert_eq!(summary["total-delete-files"], "0");
+        assert_eq!(summary["total-data-files"], "1");
+        assert_eq!(summary["total-records"], "2");
+        assert_eq!(scan_ids(&table).await, ids);
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_data_files_with_concurrent_deletes() {
+        let tmp_dir = prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await.unwrap();
+        let mut stale_table = Table::open(path).await.unwrap();
+
+        // Rows deleted after the stale table is planned are not brought back
+        // by the rewrite.
+        let data_files = table.current_data_files().await.unwrap();
+        let mut writer = table
+            .position_delete_writer(data_files[0].partition.clone(), None)
+            .await
+            .unwrap();
+        writer.delete(&data_files[0].file_path, [0]).unwrap();
+        let mut tx = table.new_transaction();
+        tx.append_file(writer.close().await.unwrap());
+        tx.commit().await.unwrap();
+        let ids = scan_ids(&table).await;
+
+        let err = stale_table.rewrite_data_files().commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+        stale_table.refresh().await.unwrap();
+        assert_eq!(scan_ids(&stale_table).await, ids);
+        assert_eq!(stale_table.current_data_files().await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::table_properties::{MANIFEST_TARGET_SIZE_BYTES, MANIFEST_TARGET_SIZE_BYTES_DEFAULT};
+use crate::transaction::{bin_pack_manifests, MANIFESTS_CREATED, MANIFESTS_REPLACED};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// RewriteManifests merges small data manifests of each partition spec into
+/// fewer larger manifests, and commits them as a `replace` snapshot.
+///
+/// Scan planning reads every manifest of a snapshot, tables appended by
+/// many small commits end up with thousands of tiny manifests, which makes
+/// planning slow. Table data is not changed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let result = table.rewrite_manifests().commit().await?;
+/// println!("replaced {} manifests", result.rewritten_manifests);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RewriteManifests<'a> {
+    table: &'a mut Table,
+    target_size_bytes: Option<u64>,
+}
+
+/// Result of [`RewriteManifests::commit`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RewriteManifestsResult {
+    /// Number of manifests replaced.
+    pub rewritten_manifests: usize,
+    /// Number of manifests written.
+    pub added_manifests: usize,
+}
+
+impl<'a> RewriteManifests<'a> {
+    /// Create a new action to rewrite manifests of table.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            table,
+            target_size_bytes: None,
+        }
+    }
+
+    /// Target size of merged manifests in bytes, manifests smaller than it
+    /// are merged.
+    ///
+    /// Default to table property `commit.manifest.target-size-bytes`.
+    pub fn with_target_size(mut self, bytes: u64) -> Self {
+        self.target_size_bytes = Some(bytes);
+        self
+    }
+
+    /// Merge manifests and commit the new manifest list.
+    pub async fn commit(self) -> Result<RewriteManifestsResult> {
+        let table = self.table;
+        let target_size_bytes = match self.target_size_bytes {
+            Some(v) => v,
+            None => match table.config().get(
+                table.current_table_metadata().properties.as_ref(),
+                MANIFEST_TARGET_SIZE_BYTES,
+            ) {
+                Some(v) => v.trim().parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid value of property {MANIFEST_TARGET_SIZE_BYTES}: {v}"),
+                    )
+                    .set_source(e)
+                })?,
+                None => MANIFEST_TARGET_SIZE_BYTES_DEFAULT,
+            },
+        };
+
+        if table.current_table_metadata().current_snapshot_id.is_none() {
+            return Ok(RewriteManifestsResult::default());
+        }
+        // Avoid an empty snapshot if there is nothing to merge.
+        let manifest_list = table
+            .current_table_metadata()
+            .current_snapshot()?
+            .load_manifest_list(table)
+            .await?;
+        let (_, bins) = bin_pack_manifests(manifest_list.entries, target_size_bytes);
+        if bins.is_empty() {
+            return Ok(RewriteManifestsResult::default());
+        }
+
+        let mut tx = table.new_transaction();
+        tx.rewrite_manifests(target_size_bytes);
+        tx.commit().await?;
+
+        let snapshot = table.current_table_metadata().current_snapshot()?;
+        let count = |key: &str| {
+            snapshot
+                .summary
+                .get(key)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default()
+        };
+        Ok(RewriteManifestsResult {
+            rewritten_manifests: count(MANIFESTS_REPLACED),
+            added_manifests: count(MANIFESTS_CREATED),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+
+    #[tokio::test]
+    async fn test_rewrite_manifests() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        for _ in 0..2 {
+            let batch = RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+                (
+                    "data",
+                    Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+                ),
+            ])
+            .unwrap();
+            let mut writer = table.task_writer().await.unwrap();
+            writer.write(&batch).await.unwrap();
+            let mut append = table.new_append();
+            append.append_file(writer.close().await.unwrap());
+            append.commit().await.unwrap();
+        }
+        let data_files = table.current_data_files().await.unwrap();
+
+        // Manifests are larger than the target.
+        let result = table
+            .rewrite_manifests()
+            .with_target_size(1)
+            .commit()
+            .await
+            .unwrap();
+        assert_eq!(result, RewriteManifestsResult::default());
+
+        let result = table.rewrite_manifests().commit().await.unwrap();
+        assert_eq!(
+            result,
+            RewriteManifestsResult {
+                rewritten_manifests: 3,
+                added_manifests: 1,
+            }
+        );
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        assert_eq!(snapshot.summary["operation"], "replace");
+        assert_eq!(snapshot.summary["total-data-files"], "5");
+        assert_eq!(
+            snapshot
+                .load_manifest_list(&table)
+                .await
+                .unwrap()
+                .entries
+                .len(),
+            1
+        );
+        let mut paths: Vec<_> = data_files.into_iter().map(|v| v.file_path).collect();
+        let mut rewritten: Vec<_> = table
+            .current_data_files()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| v.file_path)
+            .collect();
+        paths.sort();
+        rewritten.sort();
+        assert_eq!(rewritten, paths);
+
+        // Nothing to merge.
+        let result = table.rewrite_manifests().commit().await.unwrap();
+        assert_eq!(result, RewriteManifestsResult::default());
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::TryStreamExt;
+
+use crate::io::delete_filter::load_position_deletes;
+use crate::io::position_delete_writer::POSITION_DELETE_FILE_PATH_FIELD_ID;
+use crate::io::writer_config::WriterConfig;
+use crate::types::{DataContentType, DataFile, StructValue};
+use crate::{Result, Table};
+
+/// Default min number of small position delete files in a partition to
+/// rewrite.
+const MIN_INPUT_FILES_DEFAULT: usize = 2;
+
+/// RewritePositionDeletes compacts small position delete files of each
+/// partition into fewer files, and commits them as a `replace` snapshot,
+/// so that readers open less delete files.
+///
+/// Deletes of data files which are not live anymore are dangling, they
+/// are dropped from rewritten files. Delete files only referencing a
+/// single data file, which is not live, are removed without rewriting.
+///
+/// Only files of the default partition spec are rewritten, and deleted
+/// rows stored in delete files are not kept.
+///
+/// The commit fails if selected files are removed after they are planned,
+/// for example, by a concurrent [`crate::maintenance::RewriteDataFiles`]
+/// applying them.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let result = table.rewrite_position_deletes().commit().await?;
+/// println!("rewritten {} files", result.rewritten_delete_files);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RewritePositionDeletes<'a> {
+    table: &'a mut Table,
+    target_file_size: Option<u64>,
+    min_input_files: usize,
+}
+
+/// Result of [`RewritePositionDeletes::commit`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RewritePositionDeletesResult {
+    /// Number of position delete files replaced by new files.
+    pub rewritten_delete_files: usize,
+    /// Number of position delete files written.
+    pub added_delete_files: usize,
+    /// Number of dangling position delete files removed without rewriting.
+    pub removed_delete_files: usize,
+    /// Number of deletes dropped since their data files are not live.
+    pub removed_dangling_deletes: usize,
+}
+
+impl<'a> RewritePositionDeletes<'a> {
+    /// Create a new action to rewrite position delete files of table.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            table,
+            target_file_size: None,
+            min_input_files: MIN_INPUT_FILES_DEFAULT,
+        }
+    }
+
+    /// Position delete files smaller than it in bytes are rewritten.
+    ///
+    /// Default to table property `write.target-file-size-bytes`.
+    pub fn with_target_file_size(mut self, bytes: u64) -> Self {
+        self.target_file_size = Some(bytes);
+        self
+    }
+
+    /// Only rewrite partitions with at least `n` small position delete
+    /// files, default to `2`.
+    pub fn with_min_input_files(mut self, n: usize) -> Self {
+        self.min_input_files = n.max(1);
+        self
+    }
+
+    /// Rewrite selected files and commit the new files.
+    pub async fn commit(self) -> Result<RewritePositionDeletesResult> {
+        let table = self.table;
+        let target_file_size = match self.target_file_size {
+            Some(v) => v,
+            None => WriterConfig::from_properties(&table.properties())?.target_file_size_in_bytes(),
+        };
+        let default_spec_id = table.current_table_metadata().default_spec_id;
+
+        let scan = table.scan().build()?;
+        let snapshot_id = scan.snapshot_id();
+        let live_paths: HashSet<String> = scan
+            .plan_files()
+            .await?
+            .into_iter()
+            .map(|v| v.file_path)
+            .collect();
+        let delete_entries = scan.plan_delete_entries().await?;
+        drop(scan);
+
+        let mut result = RewritePositionDeletesResult::default();
+        let mut deleted = vec![];
+        let mut groups: HashMap<StructValue, Vec<DataFile>> = HashMap::new();
+        for (spec_id, entry) in delete_entries {
+            let delete_file = entry.data_file;
+            if delete_file.content != DataContentType::PostionDeletes {
+                continue;
+            }
+            if single_file_path(&delete_file).is_some_and(|v| !live_paths.contains(v)) {
+                result.removed_delete_files += 1;
+                result.removed_dangling_deletes += delete_file.record_count.max(0) as usize;
+                deleted.push(delete_file);
+                continue;
+            }
+            if spec_id != default_spec_id
+                || delete_file.file_size_in_bytes as u64 >= target_file_size
+            {
+                continue;
+            }
+            groups
+                .entry(delete_file.partition.clone())
+                .or_default()
+                .push(delete_file);
+        }
+        groups.retain(|_, files| files.len() >= self.min_input_files);
+
+        let mut added = vec![];
+        for (partition, files) in groups {
+            let batches: Vec<_> = table.read_data_files(&files)?.try_collect().await?;
+            let mut positions: Vec<(String, Vec<i64>)> =
+                load_position_deletes(&batches)?.into_iter().collect();
+            positions.retain(|(path, pos)| {
+                let live = live_paths.contains(path);
+                if !live {
+                    result.removed_dangling_deletes += pos.len();
+                }
+                live
+            });
+            if !positions.is_empty() {
+                let mut writer = table.position_delete_writer(partition, None).await?;
+                for (path, pos) in positions {
+                    writer.delete(&path, pos)?;
+                }
+                added.extend(writer.close().await?);
+            }
+            result.rewritten_delete_files += files.len();
+            deleted.extend(files);
+        }
+        if deleted.is_empty() {
+            return Ok(result);
+        }
+
+        result.added_delete_files = added.len();
+        let mut tx = table.new_transaction();
+        if let Some(snapshot_id) = snapshot_id {
+            tx.validate_from_snapshot(snapshot_id);
+        }
+        tx.rewrite_files(deleted, added);
+        tx.commit().await?;
+        Ok(result)
+    }
+}
+
+/// Returns the data file path referenced by all deletes of the position
+/// delete file, if known from its bounds.
+fn single_file_path(delete_file: &DataFile) -> Option<&str> {
+    let lower = delete_file
+        .lower_bounds
+        .as_ref()?
+        .get(&POSITION_DELETE_FILE_PATH_FIELD_ID)?;
+    let upper = delete_file
+        .upper_bounds
+        .as_ref()?
+        .get(&POSITION_DELETE_FILE_PATH_FIELD_ID)?;
+    if lower != upper {
+        return None;
+    }
+    std::str::from_utf8(lower).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::ErrorKind;
+
+    #[tokio::test]
+    async fn test_rewrite_position_deletes() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let data_files = table.current_data_files().await.unwrap();
+        let partition = data_files[0].partition.clone();
+        let mut delete_files = vec![];
+        for paths in [
+            vec![&data_files[0].file_path, &data_files[1].file_path],
+            vec![&data_files[2].file_path],
+            vec![&data_files[1].file_path],
+        ] {
+            let mut writer = table
+                .position_delete_writer(partition.clone(), None)
+                .await
+                .unwrap();
+            for path in paths {
+                writer.delete(path, [0]).unwrap();
+            }
+            delete_files.extend(writer.close().await.unwrap());
+        }
+        let mut append = table.new_append();
+        append.append_file(delete_files);
+        append.commit().await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.delete_file(data_files[1..2].to_vec());
+        tx.commit().await.unwrap();
+
+        // Deletes of the deleted data file are dangling.
+        let result = table.rewrite_position_deletes().commit().await.unwrap();
+        assert_eq!(
+            result,
+            RewritePositionDeletesResult {
+                rewritten_delete_files: 2,
+                added_delete_files: 1,
+                removed_delete_files: 1,
+                removed_dangling_deletes: 2,
+            }
+        );
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary["operation"], "replace");
+        assert_eq!(summary["total-delete-files"], "1");
+        assert_eq!(summary["total-position-deletes"], "2");
+        let tasks = table.scan().build().unwrap().plan_tasks().await.unwrap();
+        assert!(tasks.iter().all(|v| v.delete_files.len() == 1));
+
+        // A single small file is not rewritten.
+        let snapshot_id = table.current_table_metadata().current_snapshot_id;
+        let result = table.rewrite_position_deletes().commit().await.unwrap();
+        assert_eq!(result, RewritePositionDeletesResult::default());
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            snapshot_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_position_deletes_with_concurrent_rewrite() {
+        let tmp_dir = prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await.unwrap();
+        let data_files = table.current_data_files().await.unwrap();
+        let mut delete_files = vec![];
+        for data_file in &data_files[..2] {
+            let mut writer = table
+                .position_delete_writer(data_file.partition.clone(), None)
+                .await
+                .unwrap();
+            writer.delete(&data_file.file_path, [0]).unwrap();
+            delete_files.extend(writer.close().await.unwrap());
+        }
+        let mut append = table.new_append();
+        append.append_file(delete_files);
+        append.commit().await.unwrap();
+
+        // Delete files applied by a data rewrite after planning are not
+        // committed again.
+        let mut stale_table = Table::open(path).await.unwrap();
+        table
+            .rewrite_data_files()
+            .with_delete_file_threshold(1)
+            .commit()
+            .await
+            .unwrap();
+        let err = stale_table
+            .rewrite_position_deletes()
+            .commit()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+        stale_table.refresh().await.unwrap();
+        let summary = &stale_table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary["total-delete-files"], "0");
+        assert_eq!(summary["total-records"], "1");
+    }
+}
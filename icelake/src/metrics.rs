@@ -0,0 +1,247 @@
+//! metrics module reports metrics of scans and commits of tables, like
+//! `ScanReport` and `CommitReport` of the java implementation.
+//!
+//! Reports are sent to the [`MetricsReporter`] set by
+//! [`crate::Table::set_metrics_reporter`], which is
+//! [`LoggingMetricsReporter`] by default.
+
+use std::time::Duration;
+
+use crate::types::expression::Predicate;
+use crate::types::{DataContentType, DataFile};
+
+/// MetricsReporter receives reports of scans and commits.
+pub trait MetricsReporter: Send + Sync {
+    /// Report metrics of a finished scan planning or commit.
+    fn report(&self, report: &MetricsReport);
+}
+
+/// LoggingMetricsReporter logs reports at info level.
+#[derive(Debug, Default)]
+pub struct LoggingMetricsReporter;
+
+impl MetricsReporter for LoggingMetricsReporter {
+    fn report(&self, report: &MetricsReport) {
+        log::info!("Received metrics report: {report:?}");
+    }
+}
+
+/// Report of a scan or a commit.
+#[derive(Debug, Clone)]
+pub enum MetricsReport {
+    /// Report of a scan planning.
+    Scan(ScanReport),
+    /// Report of a commit.
+    Commit(CommitReport),
+}
+
+/// ScanReport reports metrics of planning files of a scan.
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    /// Location of the table.
+    pub table_location: String,
+    /// Id of the scanned snapshot, `None` if the table is empty.
+    pub snapshot_id: Option<i64>,
+    /// Id of the schema the scan reads by.
+    pub schema_id: i32,
+    /// Filter of the scan.
+    pub filter: Option<Predicate>,
+    /// Ids of projected fields.
+    pub projected_field_ids: Vec<i32>,
+    /// Metrics of the planning.
+    pub metrics: ScanMetrics,
+}
+
+/// Metrics of planning files of a scan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanMetrics {
+    /// Time spent on planning.
+    pub total_planning_duration: Duration,
+    /// Number of data files to read.
+    pub result_data_files: u64,
+    /// Number of live delete files, which may apply to data files to read.
+    pub result_delete_files: u64,
+    /// Number of data manifests of the snapshot.
+    pub total_data_manifests: u64,
+    /// Number of delete manifests of the snapshot.
+    pub total_delete_manifests: u64,
+    /// Number of data manifests read.
+    pub scanned_data_manifests: u64,
+    /// Number of data manifests pruned without being read.
+    pub skipped_data_manifests: u64,
+    /// Number of live data files in read manifests pruned by the filter.
+    pub skipped_data_files: u64,
+    /// Total size of data files to read.
+    pub total_file_size_in_bytes: u64,
+    /// Total size of live delete files.
+    pub total_delete_file_size_in_bytes: u64,
+}
+
+/// CommitReport reports metrics of a commit producing a snapshot.
+#[derive(Debug, Clone)]
+pub struct CommitReport {
+    /// Location of the table.
+    pub table_location: String,
+    /// Id of the committed snapshot.
+    pub snapshot_id: i64,
+    /// Sequence number of the committed snapshot.
+    pub sequence_number: i64,
+    /// Operation of the committed snapshot, like `append`.
+    pub operation: String,
+    /// Metrics of the commit.
+    pub metrics: CommitMetrics,
+}
+
+/// Metrics of a commit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommitMetrics {
+    /// Time spent on the commit, including retries.
+    pub total_duration: Duration,
+    /// Number of attempts made, including retries of conflicts.
+    pub attempts: u32,
+    /// Number of added data files.
+    pub added_data_files: u64,
+    /// Number of removed data files.
+    pub removed_data_files: u64,
+    /// Number of added delete files.
+    pub added_delete_files: u64,
+    /// Number of added records of data files.
+    pub added_records: u64,
+    /// Number of removed records of data files.
+    pub removed_records: u64,
+    /// Total size of added files.
+    pub added_files_size_in_bytes: u64,
+    /// Total size of removed files.
+    pub removed_files_size_in_bytes: u64,
+}
+
+impl CommitMetrics {
+    /// Create metrics of a commit adding and removing files.
+    pub(crate) fn new(
+        total_duration: Duration,
+        attempts: u32,
+        added: &[DataFile],
+        removed: &[DataFile],
+    ) -> Self {
+        let is_data = |v: &&DataFile| v.content == DataContentType::Data;
+        let size = |files: &[DataFile]| {
+            files
+                .iter()
+                .map(|v| v.file_size_in_bytes.max(0) as u64)
+                .sum()
+        };
+        Self {
+            total_duration,
+            attempts,
+            added_data_files: added.iter().filter(is_data).count() as u64,
+            removed_data_files: removed.iter().filter(is_data).count() as u64,
+            added_delete_files: added.iter().filter(|v| !is_data(v)).count() as u64,
+            added_records: added
+                .iter()
+                .filter(is_data)
+                .map(|v| v.record_count.max(0) as u64)
+                .sum(),
+            removed_records: removed
+                .iter()
+                .filter(is_data)
+                .map(|v| v.record_count.max(0) as u64)
+                .sum(),
+            added_files_size_in_bytes: size(added),
+            removed_files_size_in_bytes: size(removed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::types::expression::Reference;
+    use crate::types::PrimitiveValue;
+    use crate::{Result, Table};
+
+    #[derive(Default)]
+    struct RecordingReporter {
+        reports: Mutex<Vec<MetricsReport>>,
+    }
+
+    impl MetricsReporter for RecordingReporter {
+        fn report(&self, report: &MetricsReport) {
+            self.reports.lock().unwrap().push(report.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_reporter() -> Result<()> {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let reporter = Arc::new(RecordingReporter::default());
+        table.set_metrics_reporter(reporter.clone());
+        let take = || std::mem::take(&mut *reporter.reports.lock().unwrap());
+
+        let files = table.current_data_files().await?;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        take();
+
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files.clone());
+        tx.delete_file(files[..1].to_vec());
+        tx.commit().await?;
+        let reports = take();
+        let [MetricsReport::Commit(report)] = reports.as_slice() else {
+            panic!("unexpected reports {reports:?}");
+        };
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        assert_eq!(report.snapshot_id, snapshot.snapshot_id);
+        assert_eq!(report.operation, "overwrite");
+        assert_eq!(report.metrics.attempts, 1);
+        assert_eq!(report.metrics.added_data_files, 1);
+        assert_eq!(report.metrics.added_records, 2);
+        assert_eq!(report.metrics.removed_data_files, 1);
+        assert_eq!(report.metrics.removed_records, files[0].record_count as u64);
+
+        let filter = Reference::new("id").greater_than(PrimitiveValue::Long(3));
+        table
+            .scan()
+            .with_columns(["id"])
+            .with_filter(filter.clone())
+            .build()?
+            .plan_files()
+            .await?;
+        let reports = take();
+        let [MetricsReport::Scan(report)] = reports.as_slice() else {
+            panic!("unexpected reports {reports:?}");
+        };
+        assert_eq!(report.snapshot_id, Some(snapshot.snapshot_id));
+        assert_eq!(report.filter, Some(filter));
+        assert_eq!(report.projected_field_ids, vec![1]);
+        let metrics = &report.metrics;
+        assert_eq!(metrics.result_data_files, 1);
+        assert_eq!(
+            metrics.total_file_size_in_bytes,
+            data_files[0].file_size_in_bytes as u64
+        );
+        assert_eq!(metrics.total_data_manifests, 2);
+        assert_eq!(
+            metrics.scanned_data_manifests + metrics.skipped_data_manifests,
+            2
+        );
+        assert_eq!(metrics.skipped_data_files, 2);
+        assert_eq!(metrics.result_delete_files, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,2100 @@
+//! scan module provides [`TableScan`] to read table data as arrow record
+//! batches.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::events::ScanPlannedEvent;
+use crate::io::data_file_reader::RecordBatchStream;
+use crate::io::delete_filter::DeleteIndex;
+use crate::io::ipc::{ipc_stream, IpcByteStream};
+use crate::io::parquet::ParquetProjection;
+use crate::io::position_delete_writer::POSITION_DELETE_FILE_PATH_FIELD_ID;
+use crate::metrics::{MetricsReport, ScanMetrics, ScanReport};
+use crate::table_properties::{
+    READ_SPLIT_OPEN_FILE_COST, READ_SPLIT_OPEN_FILE_COST_DEFAULT, READ_SPLIT_PLANNING_LOOKBACK,
+    READ_SPLIT_PLANNING_LOOKBACK_DEFAULT, READ_SPLIT_TARGET_SIZE, READ_SPLIT_TARGET_SIZE_DEFAULT,
+};
+use crate::types::expression::{DataFileEvaluator, Predicate};
+use crate::types::{
+    self, DataContentType, DataFile, DataFileFormat, ManifestContentType, ManifestEntry,
+    ManifestStatus, NameMapping, MAIN_BRANCH,
+};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// TableScanBuilder is used to build a [`TableScan`], created by
+/// [`Table::scan`].
+pub struct TableScanBuilder<'a> {
+    table: &'a Table,
+    snapshot_id: Option<i64>,
+    ref_name: Option<String>,
+    ordered: bool,
+    columns: Option<ColumnSelection>,
+    filter: Option<Predicate>,
+    /// Whether column names are matched case sensitively, decided by the
+    /// table if unset.
+    case_sensitive: Option<bool>,
+    /// Exclusive start snapshot of an incremental scan.
+    from_snapshot_id: Option<i64>,
+    context: Option<TableScanContext>,
+}
+
+/// Columns selected by users, resolved into field ids while building.
+enum ColumnSelection {
+    Names(Vec<String>),
+    FieldIds(Vec<i32>),
+}
+
+impl<'a> TableScanBuilder<'a> {
+    pub(crate) fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            snapshot_id: None,
+            ref_name: None,
+            ordered: false,
+            columns: None,
+            filter: None,
+            case_sensitive: None,
+            from_snapshot_id: None,
+            context: None,
+        }
+    }
+
+    pub(crate) fn new_incremental(
+        table: &'a Table,
+        from_snapshot_id: i64,
+        to_snapshot_id: i64,
+    ) -> Self {
+        let mut builder = Self::new(table).with_snapshot_id(to_snapshot_id);
+        builder.from_snapshot_id = Some(from_snapshot_id);
+        builder
+    }
+
+    /// Only read given columns, nested columns are named like `a.b`.
+    ///
+    /// Columns are resolved against the current schema, then matched with
+    /// data files by field ids. Top level columns of produced batches are in
+    /// the given order.
+    pub fn with_columns(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.columns = Some(ColumnSelection::Names(
+            columns.into_iter().map(|v| v.into()).collect(),
+        ));
+        self
+    }
+
+    /// Only read columns of given field ids, see
+    /// [`TableScanBuilder::with_columns`].
+    pub fn with_field_ids(mut self, field_ids: impl IntoIterator<Item = i32>) -> Self {
+        self.columns = Some(ColumnSelection::FieldIds(field_ids.into_iter().collect()));
+        self
+    }
+
+    /// Skip data files that can't contain rows matching `filter`, decided
+    /// by partition values and column metrics recorded in manifests.
+    ///
+    /// Rows of the remaining files are not filtered, so that produced
+    /// batches may still contain rows not matching `filter`.
+    pub fn with_filter(mut self, filter: Predicate) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Set whether column names of [`TableScanBuilder::with_columns`] and
+    /// [`TableScanBuilder::with_filter`] are matched case sensitively, which
+    /// overrides [`Table::case_sensitive`].
+    ///
+    /// Building the scan fails if a name matches more than one column case
+    /// insensitively.
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = Some(case_sensitive);
+        self
+    }
+
+    /// Scan the given snapshot instead of the head of the default branch,
+    /// see [`Table::default_branch`].
+    pub fn with_snapshot_id(mut self, snapshot_id: i64) -> Self {
+        self.snapshot_id = Some(snapshot_id);
+        self
+    }
+
+    /// Scan the head of a branch or the snapshot of a tag, which takes
+    /// precedence over [`TableScanBuilder::with_snapshot_id`].
+    pub fn use_ref(mut self, name: impl Into<String>) -> Self {
+        self.ref_name = Some(name.into());
+        self
+    }
+
+    /// Produce batches ordered by the table's default sort order, see
+    /// [`Table::read_data_files_ordered`].
+    pub fn with_ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Scan the snapshot, schema and partition specs captured by another
+    /// scan, see [`TableScan::context`], even if the table has been
+    /// refreshed or committed since.
+    ///
+    /// It can't be used together with [`TableScanBuilder::with_snapshot_id`]
+    /// or [`TableScanBuilder::use_ref`].
+    pub fn with_context(mut self, context: TableScanContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Consume the current builder to build a new scan.
+    ///
+    /// The snapshot, schema and partition specs to scan are captured here,
+    /// later changes of the table don't affect the scan.
+    pub fn build(self) -> Result<TableScan<'a>> {
+        let context = match self.context {
+            Some(_) if self.ref_name.is_some() || self.snapshot_id.is_some() => {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Scan context can't be used with a snapshot id or reference",
+                ))
+            }
+            Some(context) => context,
+            None => {
+                let snapshot_by_ref = |name: &str| {
+                    self.table
+                        .current_table_metadata()
+                        .snapshot_by_ref(name)
+                        .map(|v| v.snapshot_id)
+                        .ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!("reference {name} is not found"),
+                            )
+                        })
+                };
+                let snapshot_id = match (&self.ref_name, self.snapshot_id) {
+                    (Some(name), _) => Some(snapshot_by_ref(name)?),
+                    (None, Some(snapshot_id)) => Some(snapshot_id),
+                    // Empty tables have no main branch, which are scanned
+                    // as empty.
+                    (None, None) if self.table.default_branch() == MAIN_BRANCH => None,
+                    (None, None) => Some(snapshot_by_ref(self.table.default_branch())?),
+                };
+                TableScanContext::try_new(self.table, snapshot_id)?
+            }
+        };
+
+        let appended_snapshot_ids = match (self.from_snapshot_id, context.snapshot_id()) {
+            (Some(from_snapshot_id), Some(to_snapshot_id)) => Some(appended_snapshot_ids(
+                self.table.current_table_metadata(),
+                from_snapshot_id,
+                to_snapshot_id,
+            )?),
+            _ => None,
+        };
+
+        let schema = context.schema();
+        let case_sensitive = self
+            .case_sensitive
+            .unwrap_or_else(|| self.table.case_sensitive());
+        let field_ids = match self.columns {
+            None => None,
+            Some(ColumnSelection::FieldIds(field_ids)) => Some(field_ids),
+            Some(ColumnSelection::Names(names)) => Some(
+                names
+                    .iter()
+                    .map(|name| {
+                        schema.find_field_id(name, case_sensitive)?.ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!("column {name} is not found in schema"),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        };
+        if let (true, Some(field_ids)) = (self.ordered, &field_ids) {
+            let sort_order = context.sort_order()?;
+            if let Some(field) = sort_order
+                .fields
+                .iter()
+                .find(|v| !field_ids.contains(&v.source_column_id))
+            {
+                return Err(Error::new(
+                    ErrorKind::IcebergFeatureUnsupported,
+                    format!(
+                        "Ordered scan without sort column {} projected",
+                        field.source_column_id
+                    ),
+                ));
+            }
+        }
+        let projection = field_ids
+            .map(|field_ids| ParquetProjection::try_new(schema, &field_ids))
+            .transpose()?;
+        // Files written by old partition specs are evaluated by their own specs.
+        let evaluators = self
+            .filter
+            .as_ref()
+            .map(|filter| {
+                context
+                    .partition_specs
+                    .iter()
+                    .map(|spec| {
+                        Ok((
+                            spec.spec_id,
+                            DataFileEvaluator::try_new_with_case_sensitive(
+                                filter,
+                                schema,
+                                spec,
+                                case_sensitive,
+                            )?,
+                        ))
+                    })
+                    .collect::<Result<HashMap<_, _>>>()
+            })
+            .transpose()?;
+
+        Ok(TableScan {
+            table: self.table,
+            context,
+            ordered: self.ordered,
+            projection,
+            filter: self.filter,
+            evaluators,
+            appended_snapshot_ids,
+        })
+    }
+}
+
+/// Manifest entries with partition spec ids of their manifests.
+type SpecEntries = Vec<(i32, ManifestEntry)>;
+
+/// Returns ids of snapshots after `from_snapshot_id` up to
+/// `to_snapshot_id` that may append data, by walking parents of
+/// `to_snapshot_id`.
+///
+/// Snapshots of operation `replace` are excluded since they only rewrite
+/// existing data, for example, by compaction.
+fn appended_snapshot_ids(
+    metadata: &types::TableMetadata,
+    from_snapshot_id: i64,
+    to_snapshot_id: i64,
+) -> Result<HashSet<i64>> {
+    if metadata.snapshot(from_snapshot_id).is_none() {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("snapshot with id {from_snapshot_id} is not found"),
+        ));
+    }
+    if !metadata.is_ancestor_of(from_snapshot_id, to_snapshot_id) {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("snapshot {from_snapshot_id} is not an ancestor of snapshot {to_snapshot_id}"),
+        ));
+    }
+
+    Ok(metadata
+        .ancestors(to_snapshot_id)
+        .into_iter()
+        .take_while(|v| v.snapshot_id != from_snapshot_id)
+        .filter(|v| v.summary.get("operation").map(|v| v.as_str()) != Some("replace"))
+        .map(|v| v.snapshot_id)
+        .collect())
+}
+
+/// TableScan reads data files of a snapshot.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures::TryStreamExt;
+/// use icelake::Table;
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let table = Table::open("/path/to/table").await?;
+/// let batches: Vec<_> = table.scan().build()?.execute().await?.try_collect().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TableScan<'a> {
+    table: &'a Table,
+    context: TableScanContext,
+    ordered: bool,
+    projection: Option<ParquetProjection>,
+    filter: Option<Predicate>,
+    /// Evaluators of the filter by partition spec ids.
+    evaluators: Option<HashMap<i32, DataFileEvaluator>>,
+    /// Only files added by these snapshots are scanned in an incremental
+    /// scan.
+    appended_snapshot_ids: Option<HashSet<i64>>,
+}
+
+impl TableScan<'_> {
+    /// Returns id of the scanned snapshot.
+    pub fn snapshot_id(&self) -> Option<i64> {
+        self.context.snapshot_id()
+    }
+
+    /// Returns the table state captured by this scan, which can be used to
+    /// build scans of the same state later, see
+    /// [`TableScanBuilder::with_context`].
+    pub fn context(&self) -> &TableScanContext {
+        &self.context
+    }
+
+    /// Returns live data files of the scanned snapshot, files pruned by the
+    /// filter are excluded.
+    ///
+    /// For an incremental scan, only files added after the start snapshot
+    /// and still live in the scanned snapshot are returned.
+    ///
+    /// Use [`TableScan::plan_tasks`] instead to get delete files applying to
+    /// each data file, like engines applying deletes themselves.
+    pub async fn plan_files(&self) -> Result<Vec<DataFile>> {
+        Ok(self
+            .plan_tasks()
+            .await?
+            .into_iter()
+            .map(|v| v.data_file)
+            .collect())
+    }
+
+    /// Returns manifest entries of data files returned by
+    /// [`TableScan::plan_files`], with their status, the snapshot adding
+    /// them and their sequence numbers, which are inherited from manifests
+    /// if not recorded in entries.
+    ///
+    /// Entries of deleted files are never returned.
+    pub async fn plan_entries(&self) -> Result<Vec<ManifestEntry>> {
+        let (data_entries, _) = self.plan_live_entries().await?;
+        Ok(data_entries.into_iter().map(|(_, v)| v).collect())
+    }
+
+    /// Returns manifest entries of live delete files of the scanned
+    /// snapshot, with partition spec ids of their manifests.
+    pub(crate) async fn plan_delete_entries(&self) -> Result<Vec<(i32, ManifestEntry)>> {
+        let (_, delete_entries) = self.plan_live_entries().await?;
+        Ok(delete_entries)
+    }
+
+    /// Returns tasks of data files returned by [`TableScan::plan_files`],
+    /// with delete files applying to each of them, and data sequence
+    /// numbers of all files.
+    ///
+    /// A position delete file applies to data files of the same partition
+    /// with a sequence number not greater than it, an equality delete file
+    /// applies to those with a smaller sequence number. Equality deletes of
+    /// an unpartitioned spec apply to all partitions.
+    pub async fn plan_tasks(&self) -> Result<Vec<FileScanTask>> {
+        let (data_entries, delete_entries) = self.plan_live_entries().await?;
+
+        let unpartitioned_specs: HashSet<i32> = self
+            .context
+            .partition_specs
+            .iter()
+            .filter(|v| v.is_unpartitioned())
+            .map(|v| v.spec_id)
+            .collect();
+        Ok(data_entries
+            .into_iter()
+            .map(|(spec_id, entry)| {
+                let data_seq = entry.sequence_number.unwrap_or(0);
+                let data_file = entry.data_file;
+                let delete_files = delete_entries
+                    .iter()
+                    .filter(|(delete_spec_id, delete_entry)| {
+                        let delete_seq = delete_entry.sequence_number.unwrap_or(0);
+                        let delete_file = &delete_entry.data_file;
+                        let same_partition = *delete_spec_id == spec_id
+                            && delete_file.partition == data_file.partition;
+                        match delete_file.content {
+                            DataContentType::PostionDeletes => {
+                                delete_seq >= data_seq
+                                    && same_partition
+                                    && might_contain_path(delete_file, &data_file.file_path)
+                            }
+                            DataContentType::EqualityDeletes => {
+                                delete_seq > data_seq
+                                    && (same_partition
+                                        || unpartitioned_specs.contains(delete_spec_id))
+                            }
+                            DataContentType::Data => false,
+                        }
+                    })
+                    .map(|(_, v)| DeleteFile {
+                        file: v.data_file.clone(),
+                        sequence_number: v.sequence_number.unwrap_or(0),
+                    })
+                    .collect();
+                FileScanTask {
+                    start: 0,
+                    length: data_file.file_size_in_bytes.max(0) as u64,
+                    data_file,
+                    sequence_number: data_seq,
+                    delete_files,
+                }
+            })
+            .collect())
+    }
+
+    /// Returns live entries of data files and delete files to scan.
+    async fn plan_live_entries(&self) -> Result<(SpecEntries, SpecEntries)> {
+        let start = Instant::now();
+        let mut metrics = ScanMetrics::default();
+        let (data_entries, delete_entries) = match &self.context.snapshot {
+            Some(snapshot) => self.load_live_entries(snapshot, &mut metrics).await?,
+            None => (vec![], vec![]),
+        };
+
+        let size = |entries: &SpecEntries| {
+            entries
+                .iter()
+                .map(|(_, v)| v.data_file.file_size_in_bytes.max(0) as u64)
+                .sum()
+        };
+        metrics.total_planning_duration = start.elapsed();
+        metrics.result_data_files = data_entries.len() as u64;
+        metrics.result_delete_files = delete_entries.len() as u64;
+        metrics.total_file_size_in_bytes = size(&data_entries);
+        metrics.total_delete_file_size_in_bytes = size(&delete_entries);
+        let schema = self.context.schema();
+        self.table.report(MetricsReport::Scan(ScanReport {
+            table_location: self.table.current_table_metadata().location.clone(),
+            snapshot_id: self.context.snapshot_id(),
+            schema_id: schema.schema_id,
+            filter: self.filter.clone(),
+            projected_field_ids: match &self.projection {
+                Some(projection) => projection.field_ids(),
+                None => schema.fields.iter().map(|v| v.id).collect(),
+            },
+            metrics,
+        }));
+        self.table.notify(|listener| {
+            listener.on_scan_planned(&ScanPlannedEvent {
+                table_location: &self.table.current_table_metadata().location,
+                snapshot_id: self.context.snapshot_id(),
+                schema_id: self.context.schema().schema_id,
+                filter: self.filter.as_ref(),
+                data_files: data_entries.len(),
+                delete_files: delete_entries.len(),
+            })
+        });
+        Ok((data_entries, delete_entries))
+    }
+
+    /// Load live entries of data files and delete files of the snapshot,
+    /// counting manifests and files into `metrics`.
+    async fn load_live_entries(
+        &self,
+        snapshot: &types::Snapshot,
+        metrics: &mut ScanMetrics,
+    ) -> Result<(SpecEntries, SpecEntries)> {
+        let manifest_list = snapshot.load_manifest_list(self.table).await?;
+        for entry in &manifest_list.entries {
+            match entry.content {
+                ManifestContentType::Data => metrics.total_data_manifests += 1,
+                ManifestContentType::Deletes => metrics.total_delete_manifests += 1,
+            }
+        }
+        // Manifests to load, data manifests are pruned by the filter.
+        let manifest_list_entries: Vec<_> = manifest_list
+            .entries
+            .iter()
+            .filter(|entry| {
+                if entry.content == ManifestContentType::Deletes {
+                    return true;
+                }
+                if let Some(ids) = &self.appended_snapshot_ids {
+                    if !ids.contains(&entry.added_snapshot_id) {
+                        return false;
+                    }
+                }
+                match self
+                    .evaluators
+                    .as_ref()
+                    .and_then(|v| v.get(&entry.partition_spec_id))
+                {
+                    Some(evaluator) => evaluator.might_match_manifest(entry),
+                    None => true,
+                }
+            })
+            .collect();
+        metrics.scanned_data_manifests = manifest_list_entries
+            .iter()
+            .filter(|v| v.content == ManifestContentType::Data)
+            .count() as u64;
+        metrics.skipped_data_manifests =
+            metrics.total_data_manifests - metrics.scanned_data_manifests;
+        let manifests = self
+            .table
+            .load_manifests(manifest_list_entries.iter().copied())
+            .await?;
+
+        // Live entries with partition spec ids of their manifests.
+        let mut data_entries = vec![];
+        let mut delete_entries = vec![];
+        for (manifest_list_entry, manifest) in manifest_list_entries.into_iter().zip(manifests) {
+            let spec_id = manifest_list_entry.partition_spec_id;
+            if manifest_list_entry.content == ManifestContentType::Deletes {
+                delete_entries.extend(
+                    manifest
+                        .entries
+                        .into_iter()
+                        .filter(|v| v.status != ManifestStatus::Deleted)
+                        .filter(|v| v.data_file.content != DataContentType::Data)
+                        .map(|v| (spec_id, v)),
+                );
+                continue;
+            }
+            let evaluator = self.evaluators.as_ref().and_then(|v| v.get(&spec_id));
+            data_entries.extend(
+                manifest
+                    .entries
+                    .into_iter()
+                    .filter(|v| v.status != ManifestStatus::Deleted)
+                    .filter(|v| match &self.appended_snapshot_ids {
+                        Some(ids) => {
+                            v.status == ManifestStatus::Added
+                                && v.snapshot_id.map(|id| ids.contains(&id)).unwrap_or(false)
+                        }
+                        None => true,
+                    })
+                    .filter(|v| v.data_file.content == DataContentType::Data)
+                    .filter(|v| {
+                        let matched = evaluator
+                            .map(|e| e.might_match(&v.data_file))
+                            .unwrap_or(true);
+                        if !matched {
+                            metrics.skipped_data_files += 1;
+                        }
+                        matched
+                    })
+                    .map(|v| (spec_id, v)),
+            );
+        }
+        Ok((data_entries, delete_entries))
+    }
+
+    /// Read all planned data files into a stream of record batches.
+    ///
+    /// Rows deleted by delete files are filtered out. Row groups and pages
+    /// of parquet data files without position deletes are skipped if they
+    /// can't contain rows matching the filter, rows of other pages are
+    /// returned without being filtered.
+    pub async fn execute(&self) -> Result<RecordBatchStream> {
+        let tasks = self.plan_tasks().await?;
+        let projection = self.projection.as_ref();
+        let filter = self.row_group_filter();
+        if tasks.iter().any(|v| !v.delete_files.is_empty()) {
+            return self.read_tasks(tasks).await;
+        }
+
+        let data_files: Vec<_> = tasks.into_iter().map(|v| v.data_file).collect();
+        if self.ordered {
+            self.table.read_data_files_ordered_with_projection(
+                &self.context,
+                &data_files,
+                projection,
+                filter,
+            )
+        } else {
+            self.table.read_data_files_with_projection(
+                &self.context,
+                &data_files,
+                projection,
+                filter,
+            )
+        }
+    }
+
+    /// Returns tasks of [`TableScan::plan_tasks`] split and combined into
+    /// tasks of about [`READ_SPLIT_TARGET_SIZE`] bytes, so that distributed
+    /// engines can schedule balanced units of work. Each combined task is
+    /// read by [`TableScan::execute_task`].
+    ///
+    /// Parquet data files are split at row group offsets recorded in
+    /// `split_offsets`, except files with position deletes whose positions
+    /// of rows must be counted from the start. Splits of small files are
+    /// combined, weighted by their sizes and delete files, at least
+    /// [`READ_SPLIT_OPEN_FILE_COST`] each.
+    pub async fn plan_combined_tasks(&self) -> Result<Vec<CombinedScanTask>> {
+        let target_size = self
+            .table
+            .usize_property(READ_SPLIT_TARGET_SIZE, READ_SPLIT_TARGET_SIZE_DEFAULT)?
+            as u64;
+        let open_file_cost = self
+            .table
+            .usize_property(READ_SPLIT_OPEN_FILE_COST, READ_SPLIT_OPEN_FILE_COST_DEFAULT)?
+            as u64;
+        let lookback = self.table.usize_property(
+            READ_SPLIT_PLANNING_LOOKBACK,
+            READ_SPLIT_PLANNING_LOOKBACK_DEFAULT,
+        )?;
+        if target_size == 0 {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("{READ_SPLIT_TARGET_SIZE} must be positive"),
+            ));
+        }
+
+        let splits = self
+            .plan_tasks()
+            .await?
+            .into_iter()
+            .flat_map(|v| v.split(target_size));
+        Ok(combine(
+            splits,
+            target_size,
+            open_file_cost,
+            lookback.max(1),
+        ))
+    }
+
+    /// Read a task returned by [`TableScan::plan_combined_tasks`] into a
+    /// stream of record batches, like [`TableScan::execute`] reads all
+    /// planned data files.
+    ///
+    /// Batches of an ordered scan are only ordered within the task.
+    pub async fn execute_task(&self, task: &CombinedScanTask) -> Result<RecordBatchStream> {
+        self.read_tasks(task.tasks.clone()).await
+    }
+
+    /// Read scan tasks with deletes applied.
+    async fn read_tasks(&self, tasks: Vec<FileScanTask>) -> Result<RecordBatchStream> {
+        let index = DeleteIndex::load(
+            self.table,
+            &self.context,
+            tasks.iter().flat_map(|v| &v.delete_files).map(|v| &v.file),
+        )
+        .await?;
+        let files = tasks
+            .into_iter()
+            .map(|v| {
+                let filter = index.filter(&v.data_file, v.delete_files.iter().map(|v| &v.file));
+                (v, filter)
+            })
+            .collect();
+        self.table.read_data_files_with_deletes(
+            &self.context,
+            files,
+            self.projection.as_ref(),
+            self.row_group_filter(),
+            self.ordered,
+        )
+    }
+
+    /// Returns the evaluator to prune row groups and pages by.
+    fn row_group_filter(&self) -> Option<&DataFileEvaluator> {
+        // Row groups and pages are pruned by column statistics only, which
+        // don't depend on partition specs.
+        self.evaluators
+            .as_ref()
+            .and_then(|v| v.get(&self.context.default_spec_id))
+    }
+
+    /// Read all planned data files like [`TableScan::execute`], encoded in
+    /// the Arrow IPC stream format, see [`ipc_stream`].
+    ///
+    /// Empty results are encoded with top level columns of the schema
+    /// containing projected fields.
+    pub async fn execute_ipc(&self) -> Result<IpcByteStream> {
+        let schema = self.context.schema();
+        let fields: Vec<types::Field> = match &self.projection {
+            Some(projection) => {
+                let mut names = vec![];
+                for id in projection.field_ids() {
+                    if let Some(path) = schema.field_path(id) {
+                        if !names.contains(&path[0]) {
+                            names.push(path[0]);
+                        }
+                    }
+                }
+                names
+                    .into_iter()
+                    .filter_map(|name| schema.fields.iter().find(|v| v.name == name).cloned())
+                    .collect()
+            }
+            None => schema.fields.clone(),
+        };
+        let arrow_schema = types::schema_to_arrow_schema(&types::Schema {
+            schema_id: schema.schema_id,
+            identifier_field_ids: None,
+            fields,
+        })?;
+
+        Ok(ipc_stream(
+            self.execute().await?,
+            Some(Arc::new(arrow_schema)),
+        ))
+    }
+}
+
+/// TableScanContext is the state of a table captured by a scan when it's
+/// built: the snapshot to scan, with the schema, partition specs, sort order
+/// and name mapping to read it by.
+///
+/// Scans only read the captured state, so that refreshes and commits of the
+/// table never change files or columns of a scan in progress.
+#[derive(Debug, Clone)]
+pub struct TableScanContext {
+    /// `None` means the table has no snapshot yet.
+    snapshot: Option<types::Snapshot>,
+    schema: types::Schema,
+    partition_specs: Vec<types::PartitionSpec>,
+    default_spec_id: i32,
+    /// `None` if the default sort order is not found, which only fails
+    /// ordered reads.
+    sort_order: Option<types::SortOrder>,
+    name_mapping: Option<Arc<NameMapping>>,
+}
+
+impl TableScanContext {
+    /// Capture the current state of `table` to scan the snapshot of
+    /// `snapshot_id`, or the current snapshot if `None`.
+    pub(crate) fn try_new(table: &Table, snapshot_id: Option<i64>) -> Result<Self> {
+        let metadata = table.current_table_metadata();
+        let snapshot = match snapshot_id.or(metadata.current_snapshot_id) {
+            Some(snapshot_id) => Some(
+                metadata
+                    .snapshots
+                    .iter()
+                    .flatten()
+                    .find(|v| v.snapshot_id == snapshot_id)
+                    .cloned()
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("snapshot with id {snapshot_id} is not found"),
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            snapshot,
+            schema: metadata.current_schema()?.clone(),
+            partition_specs: metadata.partition_specs.clone(),
+            default_spec_id: metadata.default_spec_id,
+            sort_order: metadata.current_sort_order().ok().cloned(),
+            name_mapping: table.name_mapping()?.map(Arc::new),
+        })
+    }
+
+    /// Returns the scanned snapshot.
+    pub fn snapshot(&self) -> Option<&types::Snapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Returns id of the scanned snapshot.
+    pub fn snapshot_id(&self) -> Option<i64> {
+        self.snapshot.as_ref().map(|v| v.snapshot_id)
+    }
+
+    /// Returns the schema data files are read by.
+    pub fn schema(&self) -> &types::Schema {
+        &self.schema
+    }
+
+    /// Returns partition specs of the table.
+    pub fn partition_specs(&self) -> &[types::PartitionSpec] {
+        &self.partition_specs
+    }
+
+    /// Returns the default sort order, which ordered reads are sorted by.
+    pub(crate) fn sort_order(&self) -> Result<&types::SortOrder> {
+        self.sort_order.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Default sort order is not found",
+            )
+        })
+    }
+
+    /// Returns the name mapping of files written without field ids.
+    pub(crate) fn name_mapping(&self) -> Option<Arc<NameMapping>> {
+        self.name_mapping.clone()
+    }
+}
+
+/// DeleteFile is a delete file applying to the data file of a
+/// [`FileScanTask`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteFile {
+    /// The position or equality delete file.
+    pub file: DataFile,
+    /// Data sequence number of the delete file, inherited from its
+    /// manifest if not recorded.
+    pub sequence_number: i64,
+}
+
+impl DeleteFile {
+    /// Returns the content type of the delete file, position deletes or
+    /// equality deletes.
+    pub fn content(&self) -> DataContentType {
+        self.file.content
+    }
+}
+
+/// FileScanTask is a data file to read with delete files applying to it.
+///
+/// Engines not reading by icelake apply deletes themselves by delete files
+/// of the task, whose contents and sequence numbers are kept.
+#[derive(Debug, Clone)]
+pub struct FileScanTask {
+    /// The data file to read.
+    pub data_file: DataFile,
+    /// Data sequence number of the data file, inherited from its manifest
+    /// if not recorded.
+    pub sequence_number: i64,
+    /// Position and equality delete files whose deletes must be applied to
+    /// rows of the data file.
+    pub delete_files: Vec<DeleteFile>,
+    /// Start of the byte range to read, only row groups of parquet files
+    /// starting in the range are read.
+    pub start: u64,
+    /// Length of the byte range to read, which covers the whole file unless
+    /// the task is split by [`TableScan::plan_combined_tasks`].
+    pub length: u64,
+}
+
+impl FileScanTask {
+    /// Returns bytes to read by this task, including its delete files.
+    pub fn size_in_bytes(&self) -> u64 {
+        self.length
+            + self
+                .delete_files
+                .iter()
+                .map(|v| v.file.file_size_in_bytes.max(0) as u64)
+                .sum::<u64>()
+    }
+
+    /// Split the task at row group offsets into tasks of at most
+    /// `target_size` bytes, unless a row group is larger.
+    ///
+    /// The task is kept if its file is not parquet, has position deletes, or
+    /// its split offsets are not ascending offsets inside the file.
+    fn split(self, target_size: u64) -> Vec<FileScanTask> {
+        let file_size = self.data_file.file_size_in_bytes;
+        let offsets = &self.data_file.split_offsets;
+        let splittable = self.data_file.file_format == DataFileFormat::Parquet
+            && self.length > target_size
+            && !self
+                .delete_files
+                .iter()
+                .any(|v| v.content() == DataContentType::PostionDeletes)
+            && offsets.first().map(|v| *v >= 0).unwrap_or(false)
+            && offsets.windows(2).all(|v| v[0] < v[1])
+            && offsets.last().map(|v| *v < file_size).unwrap_or(false);
+        if !splittable {
+            return vec![self];
+        }
+
+        let mut bounds: Vec<u64> = offsets.iter().map(|v| *v as u64).collect();
+        // Bytes before the first row group belong to the first split.
+        bounds[0] = 0;
+        bounds.push(file_size as u64);
+        let mut ranges: Vec<(u64, u64)> = vec![];
+        for range in bounds.windows(2) {
+            match ranges.last_mut() {
+                Some((start, end)) if range[1] - *start <= target_size => *end = range[1],
+                _ => ranges.push((range[0], range[1])),
+            }
+        }
+        ranges
+            .into_iter()
+            .map(|(start, end)| FileScanTask {
+                data_file: self.data_file.clone(),
+                sequence_number: self.sequence_number,
+                delete_files: self.delete_files.clone(),
+                start,
+                length: end - start,
+            })
+            .collect()
+    }
+}
+
+/// CombinedScanTask is a unit of work of a scan, made of splits of data
+/// files, see [`TableScan::plan_combined_tasks`].
+#[derive(Debug, Clone)]
+pub struct CombinedScanTask {
+    /// Splits of data files to read in order.
+    pub tasks: Vec<FileScanTask>,
+}
+
+impl CombinedScanTask {
+    /// Returns bytes to read by this task, including delete files.
+    pub fn size_in_bytes(&self) -> u64 {
+        self.tasks.iter().map(|v| v.size_in_bytes()).sum()
+    }
+}
+
+/// Combine splits into tasks of at most `target_size` bytes by their
+/// weights, unless a split is larger.
+///
+/// Each split is put into the first of the last `lookback` open tasks with
+/// enough room, or a new task, which closes the oldest open task if there
+/// are too many of them.
+fn combine(
+    splits: impl IntoIterator<Item = FileScanTask>,
+    target_size: u64,
+    open_file_cost: u64,
+    lookback: usize,
+) -> Vec<CombinedScanTask> {
+    let mut combined = vec![];
+    let mut open: VecDeque<(u64, Vec<FileScanTask>)> = VecDeque::new();
+    for split in splits {
+        let weight = split.size_in_bytes().max(open_file_cost);
+        match open
+            .iter_mut()
+            .find(|(size, _)| size + weight <= target_size)
+        {
+            Some((size, tasks)) => {
+                *size += weight;
+                tasks.push(split);
+            }
+            None => {
+                open.push_back((weight, vec![split]));
+                if open.len() > lookback {
+                    combined.extend(open.pop_front().map(|(_, tasks)| tasks));
+                }
+            }
+        }
+    }
+    combined.extend(open.into_iter().map(|(_, tasks)| tasks));
+    combined
+        .into_iter()
+        .map(|tasks| CombinedScanTask { tasks })
+        .collect()
+}
+
+/// Returns whether a position delete file may delete rows of the data file
+/// at `path`, decided by bounds of its `file_path` column.
+fn might_contain_path(delete_file: &DataFile, path: &str) -> bool {
+    let bound = |bounds: &Option<HashMap<i32, Vec<u8>>>| {
+        bounds
+            .as_ref()
+            .and_then(|v| v.get(&POSITION_DELETE_FILE_PATH_FIELD_ID))
+            .cloned()
+    };
+    let path = path.as_bytes();
+    match (
+        bound(&delete_file.lower_bounds),
+        bound(&delete_file.upper_bounds),
+    ) {
+        (Some(lower), Some(upper)) => lower.as_slice() <= path && path <= upper.as_slice(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::types::expression::Reference;
+    use crate::types::PrimitiveValue;
+
+    #[tokio::test]
+    async fn test_table_scan() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let table = Table::open(&path).await?;
+
+        let scan = table.scan().build()?;
+        assert_eq!(
+            scan.snapshot_id(),
+            table.current_table_metadata().current_snapshot_id
+        );
+        assert_eq!(scan.plan_files().await?.len(), 3);
+
+        let batches: Vec<_> = scan.execute().await?.try_collect().await?;
+        let rows: usize = batches.iter().map(|v| v.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        assert!(table.scan().with_snapshot_id(1).build().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_manifest_concurrency() -> Result<()> {
+        use crate::config::Config;
+        use crate::table_properties::READ_MANIFEST_CONCURRENCY;
+
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let mut table = Table::open(&path).await?;
+        let mut files = table.scan().build()?.plan_files().await?;
+        files.sort_by_key(|v| v.file_path.clone());
+
+        for concurrency in ["1", "0", "64"] {
+            table.set_config(Config::new().with_option(READ_MANIFEST_CONCURRENCY, concurrency));
+            let mut planned = table.scan().build()?.plan_files().await?;
+            planned.sort_by_key(|v| v.file_path.clone());
+            assert_eq!(planned, files);
+        }
+
+        table.set_config(Config::new().with_option(READ_MANIFEST_CONCURRENCY, "x"));
+        assert!(table.scan().build()?.plan_files().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_ipc() -> Result<()> {
+        use arrow::ipc::reader::StreamReader;
+
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let table = Table::open(&path).await?;
+
+        let chunks: Vec<_> = table
+            .scan()
+            .with_columns(["data"])
+            .build()?
+            .execute_ipc()
+            .await?
+            .try_collect()
+            .await?;
+        let reader = StreamReader::try_new(std::io::Cursor::new(chunks.concat()), None)?;
+        assert_eq!(reader.schema().fields().len(), 1);
+        assert_eq!(reader.schema().field(0).name(), "data");
+        let rows: usize = reader.map(|v| v.unwrap().num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_with_projection() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let table = Table::open(&path).await?;
+
+        let batches: Vec<_> = table
+            .scan()
+            .with_columns(["data", "id"])
+            .build()?
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        for batch in &batches {
+            let names: Vec<_> = batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|v| v.name().clone())
+                .collect();
+            assert_eq!(names, vec!["data", "id"]);
+        }
+
+        // `data` is field 2.
+        let batches: Vec<_> = table
+            .scan()
+            .with_field_ids([2])
+            .build()?
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        let rows: usize = batches.iter().map(|v| v.num_rows()).sum();
+        assert_eq!(rows, 3);
+        for batch in &batches {
+            assert_eq!(batch.num_columns(), 1);
+            assert_eq!(batch.schema().field(0).name(), "data");
+        }
+
+        assert!(table.scan().with_columns(["unknown"]).build().is_err());
+        assert!(table.scan().with_field_ids([100]).build().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_case_insensitive() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let table = Table::open(&path).await?;
+        let filter = Reference::new("ID").equal_to(PrimitiveValue::Long(1));
+        assert!(table.scan().with_columns(["Data"]).build().is_err());
+        assert!(table.scan().with_filter(filter.clone()).build().is_err());
+
+        let scan = table
+            .scan()
+            .with_case_sensitive(false)
+            .with_columns(["Data"])
+            .with_filter(filter)
+            .build()?;
+        assert_eq!(scan.plan_files().await?.len(), 1);
+        let batches: Vec<_> = scan.execute().await?.try_collect().await?;
+        assert_eq!(batches[0].schema().field(0).name(), "data");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_with_filter() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+        let table = Table::open(&path).await?;
+        let id = || Reference::new("id");
+
+        let plan = |filter: Predicate| {
+            let scan = table.scan().with_filter(filter).build();
+            async move { scan?.plan_files().await }
+        };
+        assert_eq!(
+            plan(id().greater_than(PrimitiveValue::Long(100)))
+                .await?
+                .len(),
+            0
+        );
+        assert_eq!(plan(id().equal_to(PrimitiveValue::Long(1))).await?.len(), 1);
+        assert_eq!(plan(id().is_not_null()).await?.len(), 3);
+        assert_eq!(
+            plan(
+                id().equal_to(PrimitiveValue::Long(1))
+                    .or(id().greater_than_or_equal_to(PrimitiveValue::Long(3)))
+            )
+            .await?
+            .len(),
+            2
+        );
+
+        assert!(table
+            .scan()
+            .with_filter(Reference::new("unknown").is_null())
+            .build()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_with_written_metrics() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::test_utils::prepare_table_dir;
+        use crate::transaction::Transaction;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![100, 200])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        let written = data_files[0].file_path.clone();
+        let mut tx = Transaction::new(&mut table);
+        tx.append_file(data_files);
+        tx.commit().await?;
+
+        // Files written by icelake are pruned by their column metrics.
+        let plan = |filter: Predicate| {
+            let scan = table.scan().with_filter(filter).build();
+            async move { scan?.plan_files().await }
+        };
+        let tasks = plan(Reference::new("id").greater_than(PrimitiveValue::Long(150))).await?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].file_path, written);
+        let tasks = plan(Reference::new("data").starts_with("y")).await?;
+        assert_eq!(tasks.len(), 1);
+        assert!(
+            plan(Reference::new("id").less_than(PrimitiveValue::Long(100)))
+                .await?
+                .iter()
+                .all(|v| v.file_path != written)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_binary_types() -> Result<()> {
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        use arrow::array::{
+            Array, ArrayRef, Decimal128Array, FixedSizeBinaryArray, Int64Array, LargeBinaryArray,
+            StringArray,
+        };
+        use arrow::record_batch::RecordBatch;
+        use rust_decimal::Decimal;
+        use uuid::Uuid;
+
+        use crate::test_utils::prepare_table_dir;
+        use crate::transaction::UpdateSchema;
+        use crate::types::{Any, Primitive};
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let mut tx = table.new_transaction();
+        tx.update_schema(
+            UpdateSchema::new()
+                .add_column(
+                    None,
+                    "price",
+                    Any::Primitive(Primitive::Decimal {
+                        precision: 9,
+                        scale: 2,
+                    }),
+                )
+                .add_column(None, "uid", Any::Primitive(Primitive::Uuid))
+                .add_column(None, "digest", Any::Primitive(Primitive::Fixed(4)))
+                .add_column(None, "payload", Any::Primitive(Primitive::Binary)),
+        );
+        tx.commit().await?;
+
+        let uuids = [Uuid::from_u128(1), Uuid::from_u128(u128::MAX)];
+        let schema = Arc::new(crate::types::schema_to_arrow_schema(
+            table.current_table_metadata().current_schema()?,
+        )?);
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![100, 200])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["x", "y"])),
+                Arc::new(Decimal128Array::from(vec![-1420, 1420]).with_precision_and_scale(9, 2)?),
+                Arc::new(FixedSizeBinaryArray::try_from_iter(
+                    uuids.iter().map(|v| v.as_bytes().to_vec()),
+                )?),
+                Arc::new(FixedSizeBinaryArray::try_from_iter(
+                    [[0_u8, 1, 2, 3], [0xff, 0, 0, 0]].into_iter(),
+                )?),
+                Arc::new(LargeBinaryArray::from(vec![b"\x00".as_ref(), b"\xff\xff"])),
+            ],
+        )?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        let written = data_files[0].file_path.clone();
+
+        // Bounds are serialized as single values, decimals in minimum bytes.
+        let schema = table.current_table_metadata().current_schema()?;
+        let bounds = |name: &str| {
+            let id = schema.field_id_by_name(name).unwrap();
+            let bound = |v: &Option<HashMap<i32, Vec<u8>>>| v.as_ref().unwrap()[&id].clone();
+            (
+                bound(&data_files[0].lower_bounds),
+                bound(&data_files[0].upper_bounds),
+            )
+        };
+        assert_eq!(bounds("price"), (vec![0xfa, 0x74], vec![0x05, 0x8c]));
+        assert_eq!(
+            bounds("uid"),
+            (uuids[0].as_bytes().to_vec(), uuids[1].as_bytes().to_vec())
+        );
+        assert_eq!(bounds("digest"), (vec![0, 1, 2, 3], vec![0xff, 0, 0, 0]));
+        assert_eq!(bounds("payload"), (vec![0], vec![0xff, 0xff]));
+
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.commit().await?;
+
+        // Written values are read back, and files are pruned by bounds of
+        // written values.
+        let scan = |filter: Predicate| {
+            let scan = table.scan().with_filter(filter).build();
+            let written = &written;
+            async move {
+                let tasks = scan?.plan_files().await?;
+                Result::Ok(tasks.iter().any(|v| &v.file_path == written))
+            }
+        };
+        let decimal = |v| PrimitiveValue::Decimal(Decimal::new(v, 2));
+        let cases = [
+            (Reference::new("price").equal_to(decimal(-1420)), true),
+            (Reference::new("price").less_than(decimal(-1420)), false),
+            (Reference::new("price").greater_than(decimal(1420)), false),
+            (
+                Reference::new("uid").equal_to(PrimitiveValue::Uuid(uuids[1])),
+                true,
+            ),
+            (
+                Reference::new("uid").less_than(PrimitiveValue::Uuid(uuids[0])),
+                false,
+            ),
+            (
+                Reference::new("digest").greater_than(PrimitiveValue::Fixed(vec![0xff, 0, 0, 0])),
+                false,
+            ),
+            (
+                Reference::new("payload").equal_to(PrimitiveValue::Binary(vec![0xff, 0xff])),
+                true,
+            ),
+            (
+                Reference::new("payload").greater_than(PrimitiveValue::Binary(vec![0xff, 0xff])),
+                false,
+            ),
+        ];
+        for (filter, matched) in cases {
+            assert_eq!(scan(filter.clone()).await?, matched, "{filter:?}");
+        }
+
+        let batches: Vec<RecordBatch> = table
+            .scan()
+            .with_filter(Reference::new("id").greater_than(PrimitiveValue::Long(150)))
+            .build()?
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        let batch = batches
+            .iter()
+            .find(|v| v.num_rows() == 2 && v.num_columns() == 6)
+            .expect("written rows must be read");
+        let price = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(price.values().to_vec(), vec![-1420, 1420]);
+        assert_eq!(price.scale(), 2);
+        let uid = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .unwrap();
+        assert_eq!(uid.value(1), uuids[1].as_bytes());
+        let digest = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .unwrap();
+        assert_eq!(digest.value(0), [0, 1, 2, 3]);
+        assert_eq!(batch.column(5).len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_timestamps() -> Result<()> {
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        use arrow::array::{
+            ArrayRef, AsArray, Int64Array, StringArray, TimestampMicrosecondArray,
+            TimestampMillisecondArray,
+        };
+        use arrow::datatypes::{DataType, TimeUnit, TimestampNanosecondType};
+        use arrow::record_batch::RecordBatch;
+        use chrono::{DateTime, NaiveDateTime};
+        use parquet::basic::{LogicalType, TimeUnit as ParquetTimeUnit};
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        use crate::test_utils::prepare_table_dir;
+        use crate::transaction::UpdateSchema;
+        use crate::types::{Any, Primitive};
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let mut tx = table.new_transaction();
+        tx.update_schema(
+            UpdateSchema::new()
+                .add_column(None, "ts", Any::Primitive(Primitive::Timestamp))
+                .add_column(None, "tstz", Any::Primitive(Primitive::Timestampz))
+                .add_column(None, "ts_ns", Any::Primitive(Primitive::TimestampNs)),
+        );
+        tx.commit().await?;
+
+        // Columns of other units and time zones are casted, the instants
+        // of timestamps with time zones are kept.
+        let micros = [1_510_871_468_000_000_i64, 1_510_871_469_000_000];
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![100, 200])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["x", "y"]))),
+            (
+                "ts",
+                Arc::new(TimestampMicrosecondArray::from(micros.to_vec())),
+            ),
+            (
+                "tstz",
+                Arc::new(
+                    TimestampMillisecondArray::from(
+                        micros.iter().map(|v| v / 1000).collect::<Vec<_>>(),
+                    )
+                    .with_timezone("+08:00"),
+                ),
+            ),
+            (
+                "ts_ns",
+                Arc::new(TimestampMicrosecondArray::from(micros.to_vec())),
+            ),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        let written = data_files[0].file_path.clone();
+
+        // Bounds are microseconds or nanoseconds from epoch.
+        let schema = table.current_table_metadata().current_schema()?;
+        let bounds = |name: &str| {
+            let id = schema.field_id_by_name(name).unwrap();
+            let bound = |v: &Option<HashMap<i32, Vec<u8>>>| v.as_ref().unwrap()[&id].clone();
+            (
+                bound(&data_files[0].lower_bounds),
+                bound(&data_files[0].upper_bounds),
+            )
+        };
+        let le = |v: i64| v.to_le_bytes().to_vec();
+        assert_eq!(bounds("tstz"), (le(micros[0]), le(micros[1])));
+        assert_eq!(
+            bounds("ts_ns"),
+            (le(micros[0] * 1000), le(micros[1] * 1000))
+        );
+
+        // Only timestamps with time zone are adjusted to UTC in parquet.
+        let bytes = table.operator().read(&table.rel_path(&written)?).await?;
+        let reader = SerializedFileReader::new(bytes::Bytes::from(bytes))?;
+        let adjusted: Vec<_> = reader
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .filter_map(|v| match v.logical_type() {
+                Some(LogicalType::Timestamp {
+                    is_adjusted_to_u_t_c,
+                    unit,
+                }) => Some((
+                    is_adjusted_to_u_t_c,
+                    matches!(unit, ParquetTimeUnit::NANOS(_)),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(adjusted, vec![(false, false), (true, false), (false, true)]);
+
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.commit().await?;
+
+        let scan = |filter: Predicate| {
+            let scan = table.scan().with_filter(filter).build();
+            let written = &written;
+            async move {
+                let tasks = scan?.plan_files().await?;
+                Result::Ok(tasks.iter().any(|v| &v.file_path == written))
+            }
+        };
+        let timestamp = |micros| NaiveDateTime::from_timestamp_micros(micros).unwrap();
+        let tstz = |micros| {
+            PrimitiveValue::Timestampz(DateTime::from_naive_utc_and_offset(
+                timestamp(micros),
+                chrono::Utc,
+            ))
+        };
+        let cases = [
+            (Reference::new("tstz").equal_to(tstz(micros[1])), true),
+            (Reference::new("tstz").greater_than(tstz(micros[1])), false),
+            (
+                Reference::new("ts_ns")
+                    .less_than(PrimitiveValue::TimestampNs(timestamp(micros[0]))),
+                false,
+            ),
+            (
+                Reference::new("ts_ns")
+                    .less_than_or_equal_to(PrimitiveValue::TimestampNs(timestamp(micros[0]))),
+                true,
+            ),
+        ];
+        for (filter, matched) in cases {
+            assert_eq!(scan(filter.clone()).await?, matched, "{filter:?}");
+        }
+
+        let batches: Vec<RecordBatch> = table
+            .scan()
+            .with_filter(Reference::new("id").greater_than(PrimitiveValue::Long(150)))
+            .build()?
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        let batch = batches
+            .iter()
+            .find(|v| v.num_rows() == 2 && v.num_columns() == 5)
+            .expect("written rows must be read");
+        assert_eq!(
+            batch.column(3).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+        );
+        let ts_ns = batch.column(4).as_primitive::<TimestampNanosecondType>();
+        assert_eq!(
+            ts_ns.values().to_vec(),
+            vec![micros[0] * 1000, micros[1] * 1000]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_incremental_scan() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::test_utils::prepare_table_dir;
+        use crate::transaction::Transaction;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let mut snapshot_ids = vec![table.current_table_metadata().current_snapshot_id.unwrap()];
+        for ids in [vec![4, 5], vec![6]] {
+            let data: Vec<_> = ids.iter().map(|v| v.to_string()).collect();
+            let batch = RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])?;
+            let mut writer = table.task_writer().await?;
+            writer.write(&batch).await?;
+            let data_files = writer.close().await?;
+
+            let mut tx = Transaction::new(&mut table);
+            tx.append_file(data_files);
+            tx.commit().await?;
+            snapshot_ids.push(table.current_table_metadata().current_snapshot_id.unwrap());
+        }
+
+        let scan = table
+            .incremental_scan(snapshot_ids[0], snapshot_ids[2])
+            .build()?;
+        assert_eq!(scan.plan_files().await?.len(), 2);
+        let batches: Vec<_> = scan.execute().await?.try_collect().await?;
+        let rows: usize = batches.iter().map(|v| v.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        let data_files = table
+            .incremental_scan(snapshot_ids[1], snapshot_ids[2])
+            .build()?
+            .plan_files()
+            .await?;
+        assert_eq!(data_files.len(), 1);
+        assert_eq!(data_files[0].record_count, 1);
+
+        let data_files = table
+            .incremental_scan(snapshot_ids[2], snapshot_ids[2])
+            .build()?
+            .plan_files()
+            .await?;
+        assert!(data_files.is_empty());
+
+        assert!(table
+            .incremental_scan(snapshot_ids[2], snapshot_ids[0])
+            .build()
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_scan_context() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::test_utils::prepare_table_dir;
+        use crate::transaction::UpdateSchema;
+        use crate::types::{Any, Primitive};
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let scan = table.scan().build()?;
+        let context = scan.context().clone();
+        let files = scan.plan_files().await?;
+        // Batches are read from the captured state after the scan is
+        // dropped, while the table is committed.
+        let stream = scan.execute().await?;
+
+        let mut tx = table.new_transaction();
+        tx.update_schema(UpdateSchema::new().add_column(
+            None,
+            "score",
+            Any::Primitive(Primitive::Long),
+        ));
+        tx.commit().await?;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"]))),
+            ("score", Arc::new(Int64Array::from(vec![10]))),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.commit().await?;
+        table.refresh().await?;
+
+        let batches: Vec<_> = stream.try_collect().await?;
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 3);
+        assert!(batches.iter().all(|v| v.num_columns() == 2));
+
+        // Scans of the captured context ignore the new snapshot and column.
+        let scan = table.scan().with_context(context.clone()).build()?;
+        assert_eq!(scan.snapshot_id(), context.snapshot_id());
+        assert_eq!(scan.plan_files().await?, files);
+        let batches: Vec<_> = scan.execute().await?.try_collect().await?;
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 3);
+        assert!(batches.iter().all(|v| v.num_columns() == 2));
+        assert!(table
+            .scan()
+            .with_context(context.clone())
+            .with_columns(["score"])
+            .build()
+            .is_err());
+
+        let scan = table.scan().build()?;
+        assert_ne!(scan.snapshot_id(), context.snapshot_id());
+        assert_eq!(scan.context().schema().fields.len(), 3);
+        let batches: Vec<_> = scan.execute().await?.try_collect().await?;
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 4);
+
+        assert!(table
+            .scan()
+            .with_context(context)
+            .with_snapshot_id(1)
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_combined_tasks() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::config::Config;
+        use crate::table_properties::PARQUET_ROW_GROUP_SIZE_BYTES;
+        use crate::test_utils::prepare_table_dir;
+        use crate::transaction::UpdateProperties;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let mut tx = table.new_transaction();
+        tx.update_properties(UpdateProperties::new().set(PARQUET_ROW_GROUP_SIZE_BYTES, "1"));
+        tx.commit().await?;
+
+        // A file of 4 row groups.
+        let mut writer = table.task_writer().await?;
+        for i in 0..4 {
+            let ids: Vec<i64> = (i * 100..(i + 1) * 100).collect();
+            let data: Vec<_> = ids.iter().map(|v| v.to_string()).collect();
+            let batch = RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])?;
+            writer.write(&batch).await?;
+        }
+        let data_files = writer.close().await?;
+        assert_eq!(data_files.len(), 1);
+        let data_file = data_files[0].clone();
+        assert_eq!(data_file.split_offsets.len(), 4);
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.commit().await?;
+
+        async fn read(table: &Table, tasks: Vec<CombinedScanTask>) -> Result<Vec<i64>> {
+            let scan = table.scan().build()?;
+            let mut ids = vec![];
+            for task in &tasks {
+                let batches: Vec<_> = scan.execute_task(task).await?.try_collect().await?;
+                for batch in batches {
+                    let array = batch
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<Int64Array>()
+                        .unwrap();
+                    ids.extend(array.values().iter().copied());
+                }
+            }
+            ids.sort();
+            Ok(ids)
+        }
+        let mut expected: Vec<i64> = (0..400).chain(1..=3).collect();
+        expected.sort();
+
+        // Small files are combined into one task by default.
+        let tasks = table.scan().build()?.plan_combined_tasks().await?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].tasks.len(), 4);
+        assert_eq!(read(&table, tasks).await?, expected);
+
+        // Every row group of the large file is a split.
+        table.set_config(
+            Config::new()
+                .with_option(READ_SPLIT_TARGET_SIZE, "1")
+                .with_option(READ_SPLIT_OPEN_FILE_COST, "1"),
+        );
+        let tasks = table.scan().build()?.plan_combined_tasks().await?;
+        assert_eq!(tasks.len(), 7);
+        let mut splits: Vec<_> = tasks
+            .iter()
+            .flat_map(|v| &v.tasks)
+            .filter(|v| v.data_file.file_path == data_file.file_path)
+            .map(|v| (v.start, v.length))
+            .collect();
+        splits.sort();
+        assert_eq!(splits.len(), 4);
+        assert_eq!(splits[0].0, 0);
+        for (i, split) in splits.iter().enumerate().skip(1) {
+            assert_eq!(split.0, splits[i - 1].0 + splits[i - 1].1);
+            assert_eq!(split.0, data_file.split_offsets[i] as u64);
+        }
+        assert_eq!(
+            splits[3].0 + splits[3].1,
+            data_file.file_size_in_bytes as u64
+        );
+        assert_eq!(read(&table, tasks).await?, expected);
+
+        // Splits are combined up to the target size, weighted by the open
+        // file cost at least.
+        let target_size = data_file.file_size_in_bytes;
+        table.set_config(
+            Config::new()
+                .with_option(READ_SPLIT_TARGET_SIZE, target_size.to_string())
+                .with_option(READ_SPLIT_OPEN_FILE_COST, (target_size / 2).to_string()),
+        );
+        let tasks = table.scan().build()?.plan_combined_tasks().await?;
+        assert!(tasks.iter().all(|v| v.tasks.len() <= 2));
+        assert_eq!(read(&table, tasks).await?, expected);
+
+        table.set_config(Config::new().with_option(READ_SPLIT_TARGET_SIZE, "0"));
+        assert!(table.scan().build()?.plan_combined_tasks().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_entries() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::test_utils::prepare_table_dir;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let old_entries = table.scan().build()?.plan_entries().await?;
+        assert_eq!(old_entries.len(), 3);
+        let old_snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        let old_snapshot_id = old_snapshot.snapshot_id;
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files.clone());
+        tx.delete_file([old_entries[0].data_file.clone()]);
+        tx.commit().await?;
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+
+        // Entries of deleted files are excluded, carried over entries keep
+        // their lineage.
+        let mut entries = table.scan().build()?.plan_entries().await?;
+        entries.sort_by_key(|v| v.data_file.file_path.clone());
+        let mut expected: Vec<_> = old_entries[1..]
+            .iter()
+            .cloned()
+            .map(|mut v| {
+                v.status = ManifestStatus::Existing;
+                v
+            })
+            .collect();
+        expected.push(ManifestEntry {
+            status: ManifestStatus::Added,
+            snapshot_id: Some(snapshot.snapshot_id),
+            sequence_number: Some(snapshot.sequence_number),
+            file_sequence_number: Some(snapshot.sequence_number),
+            data_file: data_files[0].clone(),
+        });
+        expected.sort_by_key(|v| v.data_file.file_path.clone());
+        let lineage = |entries: &[ManifestEntry]| {
+            entries
+                .iter()
+                .map(|v| {
+                    (
+                        v.data_file.file_path.clone(),
+                        v.status,
+                        v.snapshot_id,
+                        v.sequence_number,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lineage(&entries), lineage(&expected));
+
+        let mut current = table.current_manifest_entries().await?;
+        current.sort_by_key(|v| v.data_file.file_path.clone());
+        assert_eq!(lineage(&current), lineage(&entries));
+
+        // Entries of an old snapshot are still planned by its scan.
+        let entries = table
+            .scan()
+            .with_snapshot_id(old_snapshot_id)
+            .build()?
+            .plan_entries()
+            .await?;
+        assert_eq!(lineage(&entries), lineage(&old_entries));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_on_read_scan() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{Array, ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::test_utils::prepare_table_dir;
+
+        async fn read_ids(scan: TableScan<'_>) -> Result<Vec<i64>> {
+            let batches: Vec<RecordBatch> = scan.execute().await?.try_collect().await?;
+            let mut ids: Vec<i64> = batches
+                .iter()
+                .flat_map(|v| {
+                    let ids = v.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                    (0..ids.len()).map(|i| ids.value(i)).collect::<Vec<_>>()
+                })
+                .collect();
+            ids.sort();
+            Ok(ids)
+        }
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let ids = |v: Vec<i64>| Arc::new(Int64Array::from(v)) as ArrayRef;
+
+        // Delete the row of id 1 by position and id 2 by equality.
+        let mut data_files = table.current_data_files().await?;
+        data_files.sort_by_key(|v| v.file_path.clone());
+        let batches: Vec<RecordBatch> = table
+            .read_data_files(&data_files[..1])?
+            .try_collect()
+            .await?;
+        let deleted_id = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0);
+        let partition = data_files[0].partition.clone();
+        let mut writer = table
+            .position_delete_writer(partition.clone(), None)
+            .await?;
+        writer.delete(&data_files[0].file_path, [0])?;
+        let mut delete_files = writer.close().await?;
+        let other_id = if deleted_id == 2 { 3 } else { 2 };
+        let mut writer = table.equality_delete_writer(partition, vec![1]).await?;
+        writer
+            .write(&RecordBatch::try_from_iter([("id", ids(vec![other_id]))])?)
+            .await?;
+        delete_files.extend(writer.close().await?);
+        let mut append = table.new_append();
+        append.append_file(delete_files);
+        append.commit().await?;
+
+        let tasks = table.scan().build()?.plan_tasks().await?;
+        assert_eq!(tasks.len(), 3);
+        for task in &tasks {
+            // The position delete file only applies to the first file.
+            let expected = if task.data_file.file_path == data_files[0].file_path {
+                2
+            } else {
+                1
+            };
+            assert_eq!(task.delete_files.len(), expected);
+            // Deletes are committed after data files.
+            assert!(task
+                .delete_files
+                .iter()
+                .all(|v| v.sequence_number > task.sequence_number));
+            assert_eq!(
+                task.delete_files.last().map(|v| v.content()),
+                Some(DataContentType::EqualityDeletes)
+            );
+        }
+        let mut expected: Vec<i64> = (1..=3)
+            .filter(|v| *v != deleted_id && *v != other_id)
+            .collect();
+        assert_eq!(read_ids(table.scan().build()?).await?, expected);
+
+        // Equality deletes don't apply to rows appended later.
+        let batch = RecordBatch::try_from_iter([
+            ("id", ids(vec![other_id])),
+            ("data", Arc::new(StringArray::from(vec!["x"])) as ArrayRef),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let mut append = table.new_append();
+        append.append_file(writer.close().await?);
+        append.commit().await?;
+        expected.push(other_id);
+        expected.sort();
+        assert_eq!(read_ids(table.scan().build()?).await?, expected);
+
+        // Equality fields are read for deletes but not returned.
+        let batches: Vec<RecordBatch> = table
+            .scan()
+            .with_columns(["data"])
+            .build()?
+            .execute()
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 2);
+        for batch in &batches {
+            assert_eq!(batch.num_columns(), 1);
+            assert_eq!(batch.schema().field(0).name(), "data");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_tasks_delete_sequence_numbers() -> Result<()> {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::test_utils::prepare_table_dir;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let mut data_files = table.current_data_files().await?;
+        data_files.sort_by_key(|v| v.file_path.clone());
+        let partition = data_files[0].partition.clone();
+        let ids = |v: Vec<i64>| Arc::new(Int64Array::from(v)) as ArrayRef;
+
+        // Commit deletes of different kinds with increasing sequence numbers.
+        async fn commit(table: &mut Table, files: Vec<DataFile>) -> Result<i64> {
+            let mut append = table.new_append();
+            append.append_file(files);
+            append.commit().await?;
+            Ok(table
+                .current_table_metadata()
+                .current_snapshot()
+                .unwrap()
+                .sequence_number)
+        }
+        let mut writer = table
+            .position_delete_writer(partition.clone(), None)
+            .await?;
+        writer.delete(&data_files[0].file_path, [0])?;
+        let position_1 = writer.close().await?;
+        let mut writer = table
+            .equality_delete_writer(partition.clone(), vec![1])
+            .await?;
+        writer
+            .write(&RecordBatch::try_from_iter([("id", ids(vec![2]))])?)
+            .await?;
+        let equality_1 = writer.close().await?;
+        let seq_1 = commit(
+            &mut table,
+            [position_1.clone(), equality_1.clone()].concat(),
+        )
+        .await?;
+
+        // A data file committed with deletes of the same sequence number.
+        let mut writer = table.task_writer().await?;
+        writer
+            .write(&RecordBatch::try_from_iter([
+                ("id", ids(vec![2, 4])),
+                (
+                    "data",
+                    Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+                ),
+            ])?)
+            .await?;
+        let new_data = writer.close().await?;
+        let mut writer = table
+            .position_delete_writer(partition.clone(), None)
+            .await?;
+        writer.delete(&new_data[0].file_path, [1])?;
+        let position_2 = writer.close().await?;
+        let mut writer = table
+            .equality_delete_writer(partition.clone(), vec![1])
+            .await?;
+        writer
+            .write(&RecordBatch::try_from_iter([("id", ids(vec![3]))])?)
+            .await?;
+        let equality_2 = writer.close().await?;
+        let seq_2 = commit(
+            &mut table,
+            [new_data.clone(), position_2.clone(), equality_2.clone()].concat(),
+        )
+        .await?;
+
+        let mut writer = table.equality_delete_writer(partition, vec![1]).await?;
+        writer
+            .write(&RecordBatch::try_from_iter([("id", ids(vec![1]))])?)
+            .await?;
+        let equality_3 = writer.close().await?;
+        let seq_3 = commit(&mut table, equality_3.clone()).await?;
+        assert!(seq_1 < seq_2 && seq_2 < seq_3);
+
+        let deletes = |files: &[&Vec<DataFile>], seq: &[i64]| {
+            let mut deletes: Vec<(String, i64)> = files
+                .iter()
+                .zip(seq)
+                .map(|(v, seq)| (v[0].file_path.clone(), *seq))
+                .collect();
+            deletes.sort();
+            deletes
+        };
+        let equality_deletes = [&equality_1, &equality_2, &equality_3];
+        let equality_seq = [seq_1, seq_2, seq_3];
+        let mut expected = HashMap::from([
+            (
+                data_files[0].file_path.clone(),
+                deletes(
+                    &[&position_1, &equality_1, &equality_2, &equality_3],
+                    &[seq_1, seq_1, seq_2, seq_3],
+                ),
+            ),
+            (
+                data_files[1].file_path.clone(),
+                deletes(&equality_deletes, &equality_seq),
+            ),
+            (
+                data_files[2].file_path.clone(),
+                deletes(&equality_deletes, &equality_seq),
+            ),
+            // Position deletes apply to data files of the same sequence
+            // number, equality deletes only to older ones.
+            (
+                new_data[0].file_path.clone(),
+                deletes(&[&position_2, &equality_3], &[seq_2, seq_3]),
+            ),
+        ]);
+
+        let tasks = table.scan().build()?.plan_tasks().await?;
+        assert_eq!(tasks.len(), 4);
+        for task in tasks {
+            let mut actual: Vec<(String, i64)> = task
+                .delete_files
+                .iter()
+                .map(|v| (v.file.file_path.clone(), v.sequence_number))
+                .collect();
+            actual.sort();
+            assert_eq!(
+                Some(actual),
+                expected.remove(&task.data_file.file_path),
+                "{}",
+                task.data_file.file_path
+            );
+            if task.data_file.file_path == new_data[0].file_path {
+                assert_eq!(task.sequence_number, seq_2);
+            }
+        }
+        Ok(())
+    }
+}
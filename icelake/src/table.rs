@@ -1,27 +1,101 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
 use crate::error::Result;
-use futures::StreamExt;
-use opendal::layers::LoggingLayer;
-use opendal::services::Fs;
+use arrow::record_batch::RecordBatch;
+use futures::{Stream, StreamExt, TryStreamExt};
 use opendal::Operator;
 use regex::Regex;
 use url::Url;
 use uuid::Uuid;
 
-use crate::io::task_writer::TaskWriter;
-use crate::types::{serialize_table_meta, DataFile, TableMetadata};
+use crate::catalog::{Catalog, TableIdentifier};
+use crate::commit::{default_commit_strategy, CommitStrategy};
+use crate::config::Config;
+use crate::events::TableEventListener;
+use crate::inspect::{MetadataTables, PartitionStatistics, TableStatistics};
+use crate::io::data_file_reader::{DataFileReader, RecordBatchStream};
+use crate::io::delete_filter::DeleteFilter;
+use crate::io::delta_writer::DeltaWriter;
+use crate::io::equality_delete_writer::EqualityDeleteWriter;
+use crate::io::location_generator::DataFileLocationGenerator;
+use crate::io::location_provider::LocationProvider;
+use crate::io::manifest_cache::ManifestCache;
+use crate::io::parquet::ParquetProjection;
+use crate::io::policy::{IoKind, IoPolicies};
+use crate::io::position_delete_writer::PositionDeleteWriter;
+use crate::io::sorted_merge::{sort_stream, sorted_merge, SortColumns};
+use crate::io::storage::{build_operator, FileIO};
+use crate::io::task_writer::{partition_path, TaskWriter, WriteResult};
+use crate::io::writer_config::WriterConfig;
+use crate::lock::LockProvider;
+use crate::maintenance::{
+    ExpireSnapshots, RemoveOrphanFiles, RewriteDataFiles, RewriteManifests, RewritePositionDeletes,
+};
+use crate::metrics::{LoggingMetricsReporter, MetricsReport, MetricsReporter};
+use crate::scan::{FileScanTask, TableScanBuilder, TableScanContext};
+use crate::table_properties::{
+    DEFAULT_NAME_MAPPING, METADATA_DELETE_AFTER_COMMIT_ENABLED,
+    METADATA_DELETE_AFTER_COMMIT_ENABLED_DEFAULT, METADATA_PREVIOUS_VERSIONS_MAX,
+    METADATA_PREVIOUS_VERSIONS_MAX_DEFAULT, READ_MANIFEST_CONCURRENCY,
+    READ_MANIFEST_CONCURRENCY_DEFAULT, READ_PARQUET_ROW_GROUP_CONCURRENCY,
+    READ_PARQUET_ROW_GROUP_CONCURRENCY_DEFAULT,
+};
+use crate::transaction::{
+    AppendFiles, CherryPickSnapshot, ManageSnapshots, OverwriteFiles, RowDelta, Transaction,
+    CHECKPOINT_ID,
+};
+use crate::types::expression::DataFileEvaluator;
+use crate::types::{
+    parse_name_mapping, serialize_table_meta, DataFile, ManifestContentType, ManifestFile,
+    ManifestListEntry, MetadataLog, NameMapping, StructValue, TableMetadata, MAIN_BRANCH,
+};
 use crate::{types, Error, ErrorKind};
 
 const META_ROOT_PATH: &str = "metadata";
 const METADATA_FILE_EXTENSION: &str = ".metadata.json";
-const VERSION_HINT_FILENAME: &str = "version-hint.text";
-const VERSIONED_TABLE_METADATA_FILE_PATTERN: &str = r"v([0-9]+).metadata.json";
+pub(crate) const VERSION_HINT_FILENAME: &str = "version-hint.text";
+pub(crate) const VERSIONED_TABLE_METADATA_FILE_PATTERN: &str = r"v([0-9]+).metadata.json";
 
 /// Table is the main entry point for the IceLake.
 pub struct Table {
     op: Operator,
+    /// Operators used to access locations outside of the table location,
+    /// like `write.data.path` and `write.metadata.path` in another bucket.
+    ///
+    /// Each operator is rooted at its registered location.
+    location_ops: Vec<(String, Operator)>,
+    /// Lock used to make commits exclusive, commits are not locked if unset.
+    lock_provider: Option<Arc<dyn LockProvider>>,
+    /// Strategy to publish new metadata files, chosen by the storage if
+    /// unset.
+    commit_strategy: Option<Arc<dyn CommitStrategy>>,
+    /// Cache of parsed manifest files and manifest lists, which are read
+    /// from storage every time if unset.
+    manifest_cache: Option<Arc<ManifestCache>>,
+    /// Provider of paths of new data files, which is decided by table
+    /// properties if unset.
+    location_provider: Option<Arc<dyn LocationProvider>>,
+    /// The catalog the table is loaded from, which commits go through.
+    /// Metadata files are written by the table itself if unset.
+    catalog: Option<(Arc<dyn Catalog>, TableIdentifier)>,
+    /// Listeners notified of scans and commits of this table.
+    event_listeners: Vec<Arc<dyn TableEventListener>>,
+    /// Reporter of metrics of scans and commits, which logs reports if
+    /// unset.
+    metrics_reporter: Option<Arc<dyn MetricsReporter>>,
+    /// Retry and timeout policies of IO of this table.
+    io_policies: IoPolicies,
+    /// Config that takes precedence over table properties.
+    config: Config,
+    /// Whether column names of filters and projections are matched case
+    /// sensitively.
+    case_sensitive: bool,
+    /// Branch read by scans and committed by transactions unless another
+    /// one is chosen.
+    default_branch: String,
 
     table_metadata: HashMap<i64, types::TableMetadata>,
 
@@ -32,6 +106,11 @@ pub struct Table {
     current_location: Option<String>,
     /// It's different from `current_version` in that it's the `v[version number]` in metadata file.
     current_table_version: i64,
+    /// Location of current metadata file decided by the catalog.
+    current_metadata_location: Option<String>,
+    /// Static tables are opened from a given metadata file, which can't be
+    /// committed nor refreshed.
+    is_static: bool,
 
     task_id: AtomicUsize,
 }
@@ -41,6 +120,18 @@ impl Table {
     pub fn new(op: Operator) -> Self {
         Self {
             op,
+            location_ops: vec![],
+            lock_provider: None,
+            commit_strategy: None,
+            manifest_cache: None,
+            location_provider: None,
+            catalog: None,
+            event_listeners: vec![],
+            metrics_reporter: None,
+            io_policies: IoPolicies::new(),
+            config: Config::new(),
+            case_sensitive: true,
+            default_branch: MAIN_BRANCH.to_string(),
 
             table_metadata: HashMap::new(),
 
@@ -48,17 +139,97 @@ impl Table {
             current_location: None,
             task_id: AtomicUsize::new(0),
             current_table_version: 0,
+            current_metadata_location: None,
+            is_static: false,
         }
     }
 
+    /// Create a table of metadata loaded from the catalog, whose commits go
+    /// through [`Catalog::update_table`].
+    pub fn with_catalog(
+        op: Operator,
+        catalog: Arc<dyn Catalog>,
+        identifier: TableIdentifier,
+        metadata_location: String,
+        metadata: TableMetadata,
+    ) -> Result<Self> {
+        let mut table = Table::new(op);
+        table.catalog = Some((catalog, identifier));
+        table.set_current_metadata(metadata_location, metadata)?;
+        Ok(table)
+    }
+
+    fn set_current_metadata(
+        &mut self,
+        metadata_location: String,
+        metadata: TableMetadata,
+    ) -> Result<()> {
+        if metadata.last_updated_ms == 0 {
+            return Err(Error::new(
+                crate::ErrorKind::IcebergDataInvalid,
+                "Timestamp when the table was last updated is invalid",
+            ));
+        }
+        self.current_version = metadata.last_updated_ms;
+        self.set_location(&metadata.location)?;
+        self.current_metadata_location = Some(metadata_location);
+        self.table_metadata
+            .insert(metadata.last_updated_ms, metadata);
+        Ok(())
+    }
+
+    /// Set the table location.
+    ///
+    /// Once the table is moved, the previous location is registered with the
+    /// operator serving it, so files of old snapshots are still readable, and
+    /// the new location is served by an operator built from the table config
+    /// unless one is registered.
+    fn set_location(&mut self, location: &str) -> Result<()> {
+        let location = location.trim_end_matches('/');
+        let Some(old) = self.current_location.clone() else {
+            self.current_location = Some(location.to_string());
+            return Ok(());
+        };
+        let old = old.trim_end_matches('/');
+        if old == location {
+            return Ok(());
+        }
+
+        if !self.location_ops.iter().any(|(l, _)| l == old) {
+            self.location_ops.push((old.to_string(), self.op.clone()));
+        }
+        if !self.location_ops.iter().any(|(l, _)| l == location) {
+            let op = build_operator(location, &self.config.overrides())?;
+            self.location_ops.push((location.to_string(), op));
+        }
+        self.current_location = Some(location.to_string());
+        Ok(())
+    }
+
     /// Load metadata and manifest from storage.
     async fn load(&mut self) -> Result<()> {
-        let (cur_table_version, path) = if self.is_version_hint_exist().await? {
-            let version_hint = self.read_version_hint().await?;
-            (
+        let (cur_table_version, path) = self.latest_metadata_path().await?;
+        self.load_metadata(cur_table_version, &path).await
+    }
+
+    /// Returns version and path of the latest metadata file of the hadoop
+    /// style table.
+    async fn latest_metadata_path(&self) -> Result<(i32, String)> {
+        if self.is_version_hint_exist().await? {
+            // The version hint is written after the metadata file, it may
+            // fall behind if the writer failed in between.
+            let mut version_hint = self.read_version_hint().await?;
+            while self
+                .op
+                .is_exist(&Table::metadata_file_path(version_hint as i64 + 1))
+                .await?
+            {
+                version_hint += 1;
+            }
+            Ok((
                 version_hint,
                 format!("metadata/v{}.metadata.json", version_hint),
-            )
+            ))
         } else {
             let files = self.list_table_metadata_paths().await?;
 
@@ -88,10 +259,12 @@ impl Table {
                 }
             };
 
-            (version_hint, path)
-        };
+            Ok((version_hint, path))
+        }
+    }
 
-        let metadata = self.read_table_metadata(&path).await?;
+    async fn load_metadata(&mut self, cur_table_version: i32, path: &str) -> Result<()> {
+        let metadata = self.read_table_metadata(path).await?;
         // TODO: check if the metadata is out of date.
         if metadata.last_updated_ms == 0 {
             return Err(Error::new(
@@ -100,7 +273,7 @@ impl Table {
             ));
         }
         self.current_version = metadata.last_updated_ms;
-        self.current_location = Some(metadata.location.clone());
+        self.set_location(&metadata.location)?;
         self.table_metadata
             .insert(metadata.last_updated_ms, metadata);
         self.current_table_version = cur_table_version as i64;
@@ -109,27 +282,109 @@ impl Table {
     }
 
     /// Open an iceberg table by uri
+    ///
+    /// The table is a hadoop style table committing by version hint, tables
+    /// managed by catalogs are loaded by [`Catalog::load_table`].
+    ///
+    /// The storage is inferred from the scheme of `uri` like `s3://`, with
+    /// credentials from environment variables, see
+    /// [`crate::io::storage`].
     pub async fn open(uri: &str) -> Result<Table> {
-        // Todo(xudong): inferring storage types by uri
-        let mut builder = Fs::default();
-        builder.root(uri);
-
-        let op = Operator::new(builder)?
-            .layer(LoggingLayer::default())
-            .finish();
-
+        let op = build_operator(uri, &Config::from_env().overrides())?;
         let mut table = Table::new(op);
         table.load().await?;
         Ok(table)
     }
 
-    /// Open an iceberg table by operator
-    pub async fn open_with_op(op: Operator) -> Result<Table> {
+    /// Open an iceberg table by uri with given config, which is used to
+    /// access the storage and set as config of the table.
+    pub async fn open_with_config(uri: &str, config: Config) -> Result<Table> {
+        let op = build_operator(uri, &config.overrides())?;
         let mut table = Table::new(op);
+        table.set_config(config);
         table.load().await?;
         Ok(table)
     }
 
+    /// Open an iceberg table by uri, its storage is accessed by the
+    /// configurations of `file_io`.
+    pub async fn open_with_file_io(uri: &str, file_io: &FileIO) -> Result<Table> {
+        Self::open_with_op(file_io.operator(uri)?).await
+    }
+
+    /// Open an iceberg table by operator
+    pub async fn open_with_op(op: Operator) -> Result<Table> {
+        Self::builder(op).load().await
+    }
+
+    /// Create a builder to open a table of `op` with read options, see
+    /// [`TableBuilder`].
+    pub fn builder(op: Operator) -> TableBuilder {
+        TableBuilder::new(op)
+    }
+
+    /// Open a static table of the metadata file at `metadata_path`, which
+    /// is relative to `op` rooted at the table location, like
+    /// `metadata/v1.metadata.json`.
+    ///
+    /// Neither version hint nor other metadata files are read, so historical
+    /// metadata can be replayed. Static tables are read only, commits fail
+    /// and refreshes keep the metadata.
+    pub async fn open_static(metadata_path: &str, op: Operator) -> Result<Table> {
+        Self::builder(op).load_static(metadata_path).await
+    }
+
+    async fn load_static(&mut self, metadata_path: &str) -> Result<()> {
+        let metadata = self.read_table_metadata(metadata_path).await?;
+        let location = format!(
+            "{}/{}",
+            metadata.location,
+            metadata_path.trim_start_matches('/')
+        );
+        self.set_current_metadata(location, metadata)?;
+        self.is_static = true;
+        Ok(())
+    }
+
+    /// Open a static table of a previous metadata file listed in the
+    /// metadata log of the current metadata, see [`Table::open_static`].
+    ///
+    /// It's useful to inspect or read the table as it was before some
+    /// commits, for example, to debug a corrupted commit.
+    pub async fn open_previous_metadata(&self, metadata_file: &str) -> Result<Table> {
+        let listed = self
+            .current_table_metadata()
+            .metadata_log
+            .iter()
+            .flatten()
+            .any(|v| v.metadata_file == metadata_file);
+        if !listed {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Metadata file {metadata_file} is not found in the metadata log"),
+            ));
+        }
+        let (op, path) = self.location_operator(metadata_file)?;
+        Self::builder(op)
+            .with_config(self.config.clone())
+            .with_io_policies(self.io_policies.clone())
+            .with_case_sensitive(self.case_sensitive)
+            .load_static(&path)
+            .await
+    }
+
+    /// Returns an error if the table is static, see [`Table::open_static`].
+    pub(crate) fn check_writable(&self) -> Result<()> {
+        if self.is_static {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                "Committing static table opened from a metadata file",
+            )
+            .with_context("metadata_location", self.current_metadata_file_location()));
+        }
+        Ok(())
+    }
+
     /// Fetch current table metadata.
     pub fn current_table_metadata(&self) -> &types::TableMetadata {
         assert!(
@@ -142,12 +397,60 @@ impl Table {
             .expect("table metadata of current version must be exist")
     }
 
-    /// # TODO
-    ///
-    /// we will have better API to play with snapshots and partitions.
+    /// Returns the identifier of this table if it's loaded from a catalog.
+    pub fn identifier(&self) -> Option<&TableIdentifier> {
+        self.catalog.as_ref().map(|(_, identifier)| identifier)
+    }
+
+    /// Returns properties of current table merged with the config of this
+    /// table, see [`Table::set_config`].
+    pub fn properties(&self) -> HashMap<String, String> {
+        self.config
+            .merge(self.current_table_metadata().properties.as_ref())
+    }
+
+    /// Returns the base location of data files, resolved by
+    /// [`Table::properties`], see [`TableMetadata::data_location`].
+    pub fn data_location(&self) -> String {
+        self.current_table_metadata()
+            .data_location(&self.properties())
+    }
+
+    /// Returns the base location of manifests and manifest lists, resolved
+    /// by [`Table::properties`], see [`TableMetadata::metadata_location`].
+    pub fn metadata_location(&self) -> String {
+        self.current_table_metadata()
+            .metadata_location(&self.properties())
+    }
+
+    /// Returns the location recorded in metadata for a file at `location`,
+    /// resolved by [`Table::properties`], see
+    /// [`TableMetadata::recorded_location`].
+    pub fn recorded_location(&self, location: &str) -> Result<String> {
+        self.current_table_metadata()
+            .recorded_location(&self.properties(), location)
+    }
+
+    /// Returns the absolute location of current table metadata file.
+    pub fn current_metadata_file_location(&self) -> String {
+        if let Some(location) = &self.current_metadata_location {
+            return location.clone();
+        }
+        format!(
+            "{}/{}",
+            self.current_table_metadata().location,
+            Table::metadata_file_path(self.current_table_version)
+        )
+    }
+
+    /// Returns manifest entries of live data files of the current snapshot,
+    /// entries of deleted files are excluded.
     ///
-    /// Currently, we just return all data files of the current version.
-    pub async fn current_data_files(&self) -> Result<Vec<types::DataFile>> {
+    /// Snapshot ids and sequence numbers of entries are always set, which
+    /// are inherited from manifests if not recorded, so that the lineage of
+    /// each file is known, see [`crate::scan::TableScan::plan_entries`] to get entries
+    /// of other snapshots.
+    pub async fn current_manifest_entries(&self) -> Result<Vec<types::ManifestEntry>> {
         assert!(
             self.current_version != 0,
             "table current version must be valid"
@@ -176,21 +479,463 @@ impl Table {
                 format!("snapshot with id {} is not found", current_snapshot_id),
             ))?;
 
-        let manifest_list_path = self.rel_path(&current_snapshot.manifest_list)?;
-        let manifest_list_content = self.op.read(&manifest_list_path).await?;
-        let manifest_list = types::parse_manifest_list(&manifest_list_content)?;
+        let manifest_list = current_snapshot.load_manifest_list(self).await?;
+
+        let manifests = self
+            .load_manifests(
+                manifest_list
+                    .entries
+                    .iter()
+                    .filter(|v| v.content == ManifestContentType::Data),
+            )
+            .await?;
+        Ok(manifests
+            .into_iter()
+            .flat_map(|v| v.entries)
+            .filter(|v| v.is_alive())
+            .collect())
+    }
+
+    /// Returns live data files of the current snapshot, see
+    /// [`Table::current_manifest_entries`].
+    pub async fn current_data_files(&self) -> Result<Vec<types::DataFile>> {
+        Ok(self
+            .current_manifest_entries()
+            .await?
+            .into_iter()
+            .map(|v| v.data_file)
+            .collect())
+    }
+
+    /// Create a transaction to update this table, see [`Transaction`].
+    pub fn new_transaction(&mut self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    /// Create an action to append data files to this table, see
+    /// [`AppendFiles`].
+    pub fn new_append(&mut self) -> AppendFiles<'_> {
+        AppendFiles::new(self)
+    }
+
+    /// Create an action to overwrite data files of this table, see
+    /// [`OverwriteFiles`].
+    pub fn new_overwrite(&mut self) -> OverwriteFiles<'_> {
+        OverwriteFiles::new(self)
+    }
+
+    /// Create an action to add data files and delete files of row-level
+    /// changes to this table, see [`RowDelta`].
+    pub fn new_row_delta(&mut self) -> RowDelta<'_> {
+        RowDelta::new(self)
+    }
+
+    /// Create an action to publish the snapshot, like one staged by
+    /// write-audit-publish, see [`CherryPickSnapshot`].
+    pub fn cherry_pick_snapshot(&mut self, snapshot_id: i64) -> CherryPickSnapshot<'_> {
+        CherryPickSnapshot::new(self, snapshot_id)
+    }
 
-        let mut data_files: Vec<DataFile> = Vec::new();
-        for manifest_list_entry in manifest_list.entries {
-            let manifest_path = self.rel_path(&manifest_list_entry.manifest_path)?;
-            let manifest_content = self.op.read(&manifest_path).await?;
-            let manifest = types::parse_manifest_file(&manifest_content)?;
-            data_files.extend(manifest.entries.into_iter().map(|v| v.data_file));
+    /// Roll the table back to the snapshot in a new metadata version, the
+    /// snapshot must be an ancestor of the current snapshot.
+    pub async fn rollback_to(&mut self, snapshot_id: i64) -> Result<()> {
+        let mut tx = self.new_transaction();
+        tx.manage_snapshots(ManageSnapshots::new().rollback_to(snapshot_id));
+        tx.commit().await
+    }
+
+    /// Fast forward `branch` to the snapshot of `to_ref` in a new metadata
+    /// version, see [`ManageSnapshots::fast_forward`].
+    pub async fn fast_forward(&mut self, branch: &str, to_ref: &str) -> Result<()> {
+        let mut tx = self.new_transaction();
+        tx.manage_snapshots(ManageSnapshots::new().fast_forward(branch, to_ref));
+        tx.commit().await
+    }
+
+    /// Set the snapshot as the current snapshot in a new metadata version,
+    /// which can be any existing snapshot of the table.
+    pub async fn set_current_snapshot(&mut self, snapshot_id: i64) -> Result<()> {
+        let mut tx = self.new_transaction();
+        tx.manage_snapshots(ManageSnapshots::new().set_current_snapshot(snapshot_id));
+        tx.commit().await
+    }
+
+    /// Commit files of write results flushed at the checkpoint, see
+    /// [`TaskWriter::flush`], as a new snapshot which records the
+    /// checkpoint id as `icelake.checkpoint-id` in its summary.
+    ///
+    /// Checkpoints must be committed in order. Results of a checkpoint not
+    /// greater than [`Table::last_committed_checkpoint_id`] are skipped, so
+    /// committing results restored from a checkpoint again is idempotent.
+    /// Returns whether a snapshot is committed.
+    pub async fn commit_from_results(
+        &mut self,
+        checkpoint_id: i64,
+        results: impl IntoIterator<Item = WriteResult>,
+    ) -> Result<bool> {
+        if self
+            .last_committed_checkpoint_id()?
+            .is_some_and(|v| v >= checkpoint_id)
+        {
+            return Ok(false);
         }
+        let mut tx = self.new_transaction();
+        for result in results {
+            tx.append_file(result.into_files());
+        }
+        tx.set_snapshot_property(CHECKPOINT_ID, checkpoint_id.to_string());
+        tx.commit().await?;
+        Ok(true)
+    }
 
+    /// Write record batches of `batches` into data files by a task writer,
+    /// and commit them as a new snapshot like [`AppendFiles`]. Returns the
+    /// committed data files, nothing is committed if there are no rows.
+    ///
+    /// Batches are coerced into the table schema by the task writer, see
+    /// [`TaskWriter::write`]. No files are committed if a batch is rejected.
+    pub async fn append_batches(
+        &mut self,
+        batches: impl Stream<Item = Result<RecordBatch>>,
+    ) -> Result<Vec<DataFile>> {
+        let mut writer = self.task_writer().await?;
+        let mut batches = std::pin::pin!(batches);
+        while let Some(batch) = batches.try_next().await? {
+            writer.write(&batch).await?;
+        }
+        let data_files = writer.close().await?;
+        if data_files.is_empty() {
+            return Ok(data_files);
+        }
+        let mut append = self.new_append();
+        append.append_file(data_files.clone());
+        append.commit().await?;
         Ok(data_files)
     }
 
+    /// Returns the last checkpoint id committed by
+    /// [`Table::commit_from_results`] to the current snapshot.
+    pub fn last_committed_checkpoint_id(&self) -> Result<Option<i64>> {
+        let metadata = self.current_table_metadata();
+        let Some(snapshot_id) = metadata.current_snapshot_id else {
+            return Ok(None);
+        };
+        for snapshot in metadata.ancestors(snapshot_id) {
+            if let Some(v) = snapshot.summary.get(CHECKPOINT_ID) {
+                let checkpoint_id = v.parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!(
+                            "Invalid {CHECKPOINT_ID} of snapshot {}",
+                            snapshot.snapshot_id
+                        ),
+                    )
+                    .set_source(e)
+                })?;
+                return Ok(Some(checkpoint_id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Create an action to expire old snapshots of this table, see
+    /// [`ExpireSnapshots`].
+    pub fn expire_snapshots(&mut self) -> ExpireSnapshots<'_> {
+        ExpireSnapshots::new(self)
+    }
+
+    /// Create an action to remove files not referenced by this table and
+    /// last modified before `older_than_ms`, see [`RemoveOrphanFiles`].
+    pub fn remove_orphan_files(&self, older_than_ms: i64) -> RemoveOrphanFiles<'_> {
+        RemoveOrphanFiles::new(self, older_than_ms)
+    }
+
+    /// Create an action to compact small data files of this table, see
+    /// [`RewriteDataFiles`].
+    pub fn rewrite_data_files(&mut self) -> RewriteDataFiles<'_> {
+        RewriteDataFiles::new(self)
+    }
+
+    /// Create an action to compact small position delete files of this
+    /// table, see [`RewritePositionDeletes`].
+    pub fn rewrite_position_deletes(&mut self) -> RewritePositionDeletes<'_> {
+        RewritePositionDeletes::new(self)
+    }
+
+    /// Create an action to merge small manifests of this table, see
+    /// [`RewriteManifests`].
+    pub fn rewrite_manifests(&mut self) -> RewriteManifests<'_> {
+        RewriteManifests::new(self)
+    }
+
+    /// Returns metadata tables of this table like snapshots and files, see
+    /// [`MetadataTables`].
+    pub fn inspect(&self) -> MetadataTables<'_> {
+        MetadataTables::new(self)
+    }
+
+    /// Returns statistics of live data files of the current snapshot like
+    /// record count and total size, aggregated from manifests, see
+    /// [`TableStatistics`].
+    pub async fn statistics(&self) -> Result<TableStatistics> {
+        self.inspect().statistics().await
+    }
+
+    /// Returns statistics of each partition of the current snapshot like
+    /// file count and the last updated snapshot, like the `partitions`
+    /// metadata table, see [`PartitionStatistics`].
+    pub async fn partitions(&self) -> Result<Vec<PartitionStatistics>> {
+        Ok(self.statistics().await?.partitions)
+    }
+
+    /// Create a builder to scan data of this table.
+    pub fn scan(&self) -> TableScanBuilder<'_> {
+        TableScanBuilder::new(self)
+    }
+
+    /// Create a builder to scan data files appended after snapshot
+    /// `from_snapshot_id` up to snapshot `to_snapshot_id`, so that
+    /// consumers can ingest only new data since last read.
+    ///
+    /// Returns error while building if `from_snapshot_id` is not an ancestor
+    /// of `to_snapshot_id`.
+    pub fn incremental_scan(
+        &self,
+        from_snapshot_id: i64,
+        to_snapshot_id: i64,
+    ) -> TableScanBuilder<'_> {
+        TableScanBuilder::new_incremental(self, from_snapshot_id, to_snapshot_id)
+    }
+
+    /// Read given data files into a stream of record batches.
+    ///
+    /// Each file is decoded by its own `file_format`, so tables containing
+    /// data files of different formats can be read at once.
+    pub fn read_data_files(&self, data_files: &[DataFile]) -> Result<RecordBatchStream> {
+        let context = TableScanContext::try_new(self, None)?;
+        self.read_data_files_with_projection(&context, data_files, None, None)
+    }
+
+    /// Read given data files by the schema and name mapping of `context`,
+    /// with only projected columns decoded, row groups and pages of parquet
+    /// files not matching `filter` are skipped.
+    pub(crate) fn read_data_files_with_projection(
+        &self,
+        context: &TableScanContext,
+        data_files: &[DataFile],
+        projection: Option<&ParquetProjection>,
+        filter: Option<&DataFileEvaluator>,
+    ) -> Result<RecordBatchStream> {
+        let files = data_files
+            .iter()
+            .map(|data_file| {
+                let (op, path) = self.io_operator(IoKind::Data, &data_file.file_path)?;
+                Ok((
+                    self.data_file_reader(context, op, projection, filter)?,
+                    path,
+                    data_file.file_format,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DataFileReader::read_all(files))
+    }
+
+    /// Read given data files into a stream ordered by the table's default
+    /// sort order.
+    ///
+    /// Files whose `sort_order_id` matches the sort order are merged
+    /// directly, others are sorted in memory before merging.
+    pub fn read_data_files_ordered(&self, data_files: &[DataFile]) -> Result<RecordBatchStream> {
+        let context = TableScanContext::try_new(self, None)?;
+        self.read_data_files_ordered_with_projection(&context, data_files, None, None)
+    }
+
+    /// Read given data files in the sort order of `context` with only
+    /// projected columns decoded, which must contain the sort columns. Row
+    /// groups and pages of parquet files not matching `filter` are skipped.
+    pub(crate) fn read_data_files_ordered_with_projection(
+        &self,
+        context: &TableScanContext,
+        data_files: &[DataFile],
+        projection: Option<&ParquetProjection>,
+        filter: Option<&DataFileEvaluator>,
+    ) -> Result<RecordBatchStream> {
+        let sort_order = context.sort_order()?;
+        let sort_columns = SortColumns::try_new(sort_order, context.schema())?;
+
+        let inputs = data_files
+            .iter()
+            .map(|data_file| {
+                let (op, path) = self.io_operator(IoKind::Data, &data_file.file_path)?;
+                let stream = DataFileReader::read_all(vec![(
+                    self.data_file_reader(context, op, projection, filter)?,
+                    path,
+                    data_file.file_format,
+                )]);
+                if data_file.sort_order_id == Some(sort_order.order_id) {
+                    Ok(stream)
+                } else {
+                    Ok(sort_stream(stream, sort_columns.clone()))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(sorted_merge(inputs, sort_columns))
+    }
+
+    /// Read byte ranges of data files in scan tasks with rows deleted by
+    /// their delete filters removed, ordered by the sort order of `context`
+    /// if `ordered`.
+    ///
+    /// Row groups and pages not matching `filter` are only skipped for files
+    /// without position deletes, whose positions of rows must be kept.
+    pub(crate) fn read_data_files_with_deletes(
+        &self,
+        context: &TableScanContext,
+        files: Vec<(FileScanTask, DeleteFilter)>,
+        projection: Option<&ParquetProjection>,
+        filter: Option<&DataFileEvaluator>,
+        ordered: bool,
+    ) -> Result<RecordBatchStream> {
+        let schema = context.schema();
+        let sort = if ordered {
+            let sort_order = context.sort_order()?;
+            Some((sort_order, SortColumns::try_new(sort_order, schema)?))
+        } else {
+            None
+        };
+
+        let mut inputs = Vec::with_capacity(files.len());
+        for (task, delete_filter) in files {
+            let data_file = task.data_file;
+            let (file_projection, extra_columns) = delete_filter.projection(schema, projection)?;
+            let file_filter = if delete_filter.has_position_deletes() {
+                None
+            } else {
+                filter
+            };
+            let (op, path) = self.io_operator(IoKind::Data, &data_file.file_path)?;
+            let reader = self
+                .data_file_reader(context, op, file_projection.as_ref(), file_filter)?
+                .with_range(task.start, task.length);
+            let stream = DataFileReader::read_all(vec![(reader, path, data_file.file_format)]);
+            // Rows must be filtered in the file order to match positions.
+            let stream = delete_filter.apply(stream, extra_columns);
+            inputs.push(match &sort {
+                Some((sort_order, sort_columns))
+                    if data_file.sort_order_id != Some(sort_order.order_id) =>
+                {
+                    sort_stream(stream, sort_columns.clone())
+                }
+                _ => stream,
+            });
+        }
+
+        Ok(match sort {
+            Some((_, sort_columns)) => sorted_merge(inputs, sort_columns),
+            None => futures::stream::iter(inputs).flatten().boxed(),
+        })
+    }
+
+    /// Create a data file reader configured by table properties, reading
+    /// by the schema and name mapping of `context`.
+    fn data_file_reader(
+        &self,
+        context: &TableScanContext,
+        op: Operator,
+        projection: Option<&ParquetProjection>,
+        filter: Option<&DataFileEvaluator>,
+    ) -> Result<DataFileReader> {
+        let concurrency = self.usize_property(
+            READ_PARQUET_ROW_GROUP_CONCURRENCY,
+            READ_PARQUET_ROW_GROUP_CONCURRENCY_DEFAULT,
+        )?;
+        let mut reader = DataFileReader::new(op)
+            .with_row_group_concurrency(concurrency)
+            .with_schema(context.schema().clone());
+        if let Some(name_mapping) = context.name_mapping() {
+            reader = reader.with_name_mapping(name_mapping);
+        }
+        if let Some(filter) = filter {
+            reader = reader.with_filter(filter.clone());
+        }
+        Ok(match projection {
+            Some(projection) => reader.with_projection(projection.clone()),
+            None => reader,
+        })
+    }
+
+    /// Load manifest files of `entries` concurrently, at most
+    /// [`READ_MANIFEST_CONCURRENCY`] at a time. Manifests are returned in
+    /// the order of entries.
+    pub(crate) async fn load_manifests<'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a ManifestListEntry>,
+    ) -> Result<Vec<ManifestFile>> {
+        let concurrency =
+            self.usize_property(READ_MANIFEST_CONCURRENCY, READ_MANIFEST_CONCURRENCY_DEFAULT)?;
+        futures::stream::iter(entries)
+            .map(|entry| entry.load_manifest(self))
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Returns the name mapping in table property
+    /// [`DEFAULT_NAME_MAPPING`] if set.
+    pub fn name_mapping(&self) -> Result<Option<NameMapping>> {
+        self.config
+            .get(
+                self.current_table_metadata().properties.as_ref(),
+                DEFAULT_NAME_MAPPING,
+            )
+            .map(|v| {
+                parse_name_mapping(v.as_bytes()).map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid value of property {DEFAULT_NAME_MAPPING}"),
+                    )
+                    .set_source(e)
+                })
+            })
+            .transpose()
+    }
+
+    /// Returns the value of an unsigned integer property, which is
+    /// overridden by the config.
+    pub(crate) fn usize_property(&self, key: &str, default: usize) -> Result<usize> {
+        self.property(
+            self.current_table_metadata().properties.as_ref(),
+            key,
+            default,
+        )
+    }
+
+    /// Returns the value of a property of given table properties, which is
+    /// overridden by the config.
+    fn property<T>(
+        &self,
+        properties: Option<&HashMap<String, String>>,
+        key: &str,
+        default: T,
+    ) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match self.config.get(properties, key) {
+            Some(v) => v.trim().parse().map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid value of property {key}: {v}"),
+                )
+                .set_source(e)
+            }),
+            None => Ok(default),
+        }
+    }
+
     /// Get the relpath related to the base of table location.
     pub fn rel_path(&self, path: &str) -> Result<String> {
         let location = self.current_location.as_ref().ok_or(Error::new(
@@ -209,9 +954,171 @@ impl Table {
             .map(|v| v.to_string())
     }
 
+    /// Register an operator to access files under given location.
+    ///
+    /// The operator must be rooted at `location`. It's used when
+    /// `write.data.path` or `write.metadata.path` points to a location
+    /// outside of the table location, for example, another bucket.
+    pub fn register_location_operator(&mut self, location: impl Into<String>, op: Operator) {
+        let location = location.into().trim_end_matches('/').to_string();
+        self.location_ops.retain(|(l, _)| l != &location);
+        self.location_ops.push((location, op));
+    }
+
+    /// Set the config used by readers, writers and transactions of this
+    /// table, which takes precedence over table properties.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Returns the config of this table.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns whether column names of filters and projections are matched
+    /// case sensitively, see [`TableBuilder::with_case_sensitive`].
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Returns the branch read by scans and committed by transactions by
+    /// default, see [`TableBuilder::with_default_branch`].
+    pub fn default_branch(&self) -> &str {
+        &self.default_branch
+    }
+
+    /// Set the lock provider used to guard commits of this table.
+    ///
+    /// The lock is keyed by table location, so all writers of the same table
+    /// must share the same lock backend.
+    /// Commits refresh the lock every
+    /// [`LockProvider::heartbeat_interval`] while holding it, and check it's
+    /// still held before publishing new metadata.
+    pub fn set_lock_provider(&mut self, lock_provider: Arc<dyn LockProvider>) {
+        self.lock_provider = Some(lock_provider);
+    }
+
+    /// Set the strategy used to publish new metadata files of this table,
+    /// which overrides [`default_commit_strategy`].
+    ///
+    /// Tables loaded from catalogs commit through the catalogs instead.
+    pub fn set_commit_strategy(&mut self, commit_strategy: Arc<dyn CommitStrategy>) {
+        self.commit_strategy = Some(commit_strategy);
+    }
+
+    /// Set the cache of parsed manifest files and manifest lists, which can
+    /// be shared by tables.
+    pub fn set_manifest_cache(&mut self, manifest_cache: Arc<ManifestCache>) {
+        self.manifest_cache = Some(manifest_cache);
+    }
+
+    /// Set the provider deciding paths of new data files, which overrides
+    /// the one configured by `write.object-storage.enabled`.
+    pub fn set_location_provider(&mut self, location_provider: Arc<dyn LocationProvider>) {
+        self.location_provider = Some(location_provider);
+    }
+
+    /// Register a listener notified of scans and commits of this table, see
+    /// [`TableEventListener`].
+    pub fn register_event_listener(&mut self, listener: Arc<dyn TableEventListener>) {
+        self.event_listeners.push(listener);
+    }
+
+    /// Notify all registered listeners by `f`.
+    pub(crate) fn notify(&self, f: impl Fn(&dyn TableEventListener)) {
+        for listener in &self.event_listeners {
+            f(listener.as_ref());
+        }
+    }
+
+    /// Set the reporter of metrics of scans and commits of this table,
+    /// which overrides [`LoggingMetricsReporter`].
+    pub fn set_metrics_reporter(&mut self, metrics_reporter: Arc<dyn MetricsReporter>) {
+        self.metrics_reporter = Some(metrics_reporter);
+    }
+
+    /// Send a report to the metrics reporter.
+    pub(crate) fn report(&self, report: MetricsReport) {
+        match &self.metrics_reporter {
+            Some(reporter) => reporter.report(&report),
+            None => LoggingMetricsReporter.report(&report),
+        }
+    }
+
+    /// Set the retry and timeout policies of IO of this table, which apply
+    /// to all operators of the table, including registered ones.
+    pub fn set_io_policies(&mut self, io_policies: IoPolicies) {
+        self.io_policies = io_policies;
+    }
+
+    /// Returns the table operator wrapped by the policy of `kind`.
+    fn io_table_operator(&self, kind: IoKind) -> Operator {
+        self.io_policies.apply(kind, self.op.clone())
+    }
+
+    /// Like [`Table::location_operator`], but the operator is wrapped by the
+    /// policy of `kind`.
+    pub(crate) fn io_operator(&self, kind: IoKind, location: &str) -> Result<(Operator, String)> {
+        let (op, path) = self.location_operator(location)?;
+        Ok((self.io_policies.apply(kind, op), path))
+    }
+
+    /// Returns the writer config of given properties.
+    fn writer_config(&self, props: &HashMap<String, String>) -> Result<WriterConfig> {
+        let config = WriterConfig::from_properties(props)?;
+        Ok(match &self.location_provider {
+            Some(v) => config.with_location_provider(v.clone()),
+            None => config,
+        })
+    }
+
+    pub(crate) fn manifest_cache(&self) -> Option<&ManifestCache> {
+        self.manifest_cache.as_deref()
+    }
+
+    /// Returns the operator that can access the given location and the path
+    /// relative to it.
+    ///
+    /// The most specific registered operator is preferred, then the table
+    /// operator. Relative locations like `data/a.parquet` are resolved
+    /// against the table operator.
+    pub(crate) fn location_operator(&self, location: &str) -> Result<(Operator, String)> {
+        if !location.starts_with('/') && !location.contains("://") {
+            return Ok((self.op.clone(), location.to_string()));
+        }
+
+        let registered = self
+            .location_ops
+            .iter()
+            .filter(|(l, _)| {
+                location
+                    .strip_prefix(l.as_str())
+                    .map(|rest| rest.is_empty() || rest.starts_with('/'))
+                    .unwrap_or(false)
+            })
+            .max_by_key(|(l, _)| l.len());
+
+        if let Some((l, op)) = registered {
+            let path = location[l.len()..].to_string();
+            return Ok((op.clone(), path));
+        }
+
+        match self.rel_path(location) {
+            Ok(path) => Ok((self.op.clone(), path)),
+            Err(err) => Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!(
+                    "location {location} is outside of table location and no operator registered for it"
+                ),
+            )
+            .set_source(err)),
+        }
+    }
+
     /// Check if version hint file exist.
     async fn is_version_hint_exist(&self) -> Result<bool> {
-        self.op
+        self.io_table_operator(IoKind::Metadata)
             .is_exist("metadata/version-hint.text")
             .await
             .map_err(|e| {
@@ -224,7 +1131,10 @@ impl Table {
 
     /// Read version hint of table.
     async fn read_version_hint(&self) -> Result<i32> {
-        let content = self.op.read("metadata/version-hint.text").await?;
+        let content = self
+            .io_table_operator(IoKind::Metadata)
+            .read("metadata/version-hint.text")
+            .await?;
         let version_hint = String::from_utf8(content).map_err(|err| {
             Error::new(
                 crate::ErrorKind::IcebergDataInvalid,
@@ -242,7 +1152,7 @@ impl Table {
 
     /// Read table metadata of the given version.
     async fn read_table_metadata(&self, path: &str) -> Result<types::TableMetadata> {
-        let content = self.op.read(path).await?;
+        let content = self.io_table_operator(IoKind::Metadata).read(path).await?;
 
         let metadata = types::parse_table_metadata(&content)?;
 
@@ -255,7 +1165,8 @@ impl Table {
     ///
     /// TODO: we can imporve this by only fetch the latest metadata.
     async fn list_table_metadata_paths(&self) -> Result<Vec<String>> {
-        let mut lister = self.op.list("metadata/").await.map_err(|err| {
+        let op = self.io_table_operator(IoKind::Metadata);
+        let mut lister = op.list("metadata/").await.map_err(|err| {
             Error::new(
                 crate::ErrorKind::Unexpected,
                 format!("list metadata failed: {}", err),
@@ -285,21 +1196,116 @@ impl Table {
     }
 
     /// Return a task writer used to write data into table.
+    ///
+    /// Data files are written to `write.data.path` if set.
     pub async fn task_writer(&self) -> Result<TaskWriter> {
+        self.task_writer_with_options(HashMap::new()).await
+    }
+
+    /// Return a task writer configured by given write options.
+    ///
+    /// Write options use the same keys as table properties and take
+    /// precedence over them and the config of this table, see
+    /// [`crate::io::writer_config`].
+    pub async fn task_writer_with_options(
+        &self,
+        options: HashMap<String, String>,
+    ) -> Result<TaskWriter> {
         let task_id = self
             .task_id
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let task_writer = TaskWriter::try_new(
-            self.current_table_metadata().clone(),
-            self.op.clone(),
-            0,
-            task_id,
-            None,
-        )
-        .await?;
+        let mut props = self.properties();
+        props.extend(options);
+        let table_metadata = self.writer_metadata(&props);
+        let config = self.writer_config(&props)?;
+        let (data_op, _) = self.io_operator(IoKind::Data, &table_metadata.data_location(&props))?;
+        let task_writer =
+            TaskWriter::try_new(table_metadata, data_op, 0, task_id, None, config).await?;
         Ok(task_writer)
     }
 
+    /// Return a writer of position delete files of given partition, which
+    /// stores deleted rows if `row_schema` is given.
+    ///
+    /// Like data files, delete files are written to `write.data.path` if
+    /// set.
+    pub async fn position_delete_writer(
+        &self,
+        partition: StructValue,
+        row_schema: Option<arrow::datatypes::SchemaRef>,
+    ) -> Result<PositionDeleteWriter> {
+        let (data_op, location_generator, config) = self.delete_file_writer_args(&partition)?;
+        Ok(
+            PositionDeleteWriter::try_new(data_op, location_generator, row_schema, config)
+                .await?
+                .with_partition(partition),
+        )
+    }
+
+    /// Return a writer of equality delete files of given partition, delete
+    /// keys are values of fields of `equality_ids` in the current schema.
+    pub async fn equality_delete_writer(
+        &self,
+        partition: StructValue,
+        equality_ids: Vec<i32>,
+    ) -> Result<EqualityDeleteWriter> {
+        let (data_op, location_generator, config) = self.delete_file_writer_args(&partition)?;
+        Ok(EqualityDeleteWriter::try_new(
+            data_op,
+            location_generator,
+            self.current_table_metadata().current_schema()?,
+            equality_ids,
+            config,
+        )
+        .await?
+        .with_partition(partition))
+    }
+
+    /// Return a writer of row changes keyed by values of fields of
+    /// `equality_ids` in the current schema, or identifier fields of the
+    /// schema if empty, see [`DeltaWriter`].
+    pub fn delta_writer(&self, equality_ids: Vec<i32>) -> Result<DeltaWriter> {
+        let task_id = self
+            .task_id
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let props = self.properties();
+        let table_metadata = self.writer_metadata(&props);
+        let config = self.writer_config(&props)?;
+        let (data_op, _) = self.io_operator(IoKind::Data, &table_metadata.data_location(&props))?;
+        DeltaWriter::try_new(table_metadata, data_op, task_id, equality_ids, config)
+    }
+
+    /// Returns the operator, location generator and config to write delete
+    /// files of given partition.
+    fn delete_file_writer_args(
+        &self,
+        partition: &StructValue,
+    ) -> Result<(Operator, DataFileLocationGenerator, WriterConfig)> {
+        let task_id = self
+            .task_id
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let props = self.properties();
+        let table_metadata = self.writer_metadata(&props);
+        let config = self.writer_config(&props)?;
+        let location_generator =
+            DataFileLocationGenerator::try_new(&table_metadata, 0, task_id, None)?
+                .with_file_format(config.file_format())
+                .with_location_provider(config.location_provider())
+                .with_partition_path(partition_path(partition));
+        let (data_op, _) = self.io_operator(IoKind::Data, &table_metadata.data_location(&props))?;
+        Ok((data_op, location_generator, config))
+    }
+
+    /// Returns current table metadata with properties replaced by given
+    /// properties, so that writers resolve properties read from metadata,
+    /// like `write.data.path`, by the config of this table and write
+    /// options too.
+    fn writer_metadata(&self, props: &HashMap<String, String>) -> TableMetadata {
+        let mut table_metadata = self.current_table_metadata().clone();
+        table_metadata.properties = Some(props.clone());
+        table_metadata
+    }
+
     /// Returns path of metadata file relative to the table root path.
     #[inline]
     pub fn metadata_path(filename: impl Into<String>) -> String {
@@ -371,22 +1377,98 @@ impl Table {
         self.op.clone()
     }
 
-    pub(crate) async fn commit(&mut self, next_metadata: TableMetadata) -> Result<()> {
+    pub(crate) async fn commit(&mut self, mut next_metadata: TableMetadata) -> Result<()> {
+        self.check_writable()?;
+        let properties = next_metadata.properties.as_ref();
+        let previous_versions_max = self.property(
+            properties,
+            METADATA_PREVIOUS_VERSIONS_MAX,
+            METADATA_PREVIOUS_VERSIONS_MAX_DEFAULT,
+        )?;
+        let delete_after_commit = self.property(
+            properties,
+            METADATA_DELETE_AFTER_COMMIT_ENABLED,
+            METADATA_DELETE_AFTER_COMMIT_ENABLED_DEFAULT,
+        )?;
+        // The current metadata file becomes a previous version.
+        let removed = next_metadata.add_previous_metadata(
+            MetadataLog {
+                timestamp_ms: self.current_table_metadata().last_updated_ms,
+                metadata_file: self.current_metadata_file_location(),
+            },
+            previous_versions_max,
+        );
+
+        self.commit_with_catalog_or_lock(next_metadata).await?;
+        if delete_after_commit {
+            self.delete_metadata_files(&removed).await;
+        }
+        Ok(())
+    }
+
+    async fn commit_with_catalog_or_lock(&mut self, next_metadata: TableMetadata) -> Result<()> {
+        // Catalogs make commits atomic by themselves.
+        if let Some((catalog, identifier)) = self.catalog.clone() {
+            let (location, metadata) = catalog
+                .update_table(
+                    &identifier,
+                    &self.current_metadata_file_location(),
+                    self.current_table_metadata(),
+                    &next_metadata,
+                )
+                .await?;
+            return self.set_current_metadata(location, metadata);
+        }
+
+        let Some(lock_provider) = self.lock_provider.clone() else {
+            return self.commit_metadata(next_metadata, None).await;
+        };
+
+        let key = self.current_table_metadata().location.clone();
+        let owner = Uuid::new_v4().to_string();
+        lock_provider.acquire(&key, &owner).await?;
+        let lock = HeldLock {
+            provider: lock_provider.as_ref(),
+            key: &key,
+            owner: &owner,
+        };
+        // The lock is kept alive by heartbeats while committing, in case
+        // writing metadata takes longer than the lock expiration.
+        let res = tokio::select! {
+            res = self.commit_metadata(next_metadata, Some(&lock)) => res,
+            _ = lock.keep_alive() => unreachable!("keep_alive never returns"),
+        };
+        if let Err(err) = lock_provider.release(&key, &owner).await {
+            log::warn!("Failed to release lock of table {key}: {err}");
+        }
+        res
+    }
+
+    /// Write the next metadata file, `lock` is checked to be still held
+    /// right before the metadata file is published.
+    async fn commit_metadata(
+        &mut self,
+        next_metadata: TableMetadata,
+        lock: Option<&HeldLock<'_>>,
+    ) -> Result<()> {
         let next_version = self.current_table_version + 1;
-        let tmp_metadata_file_path =
-            Table::metadata_path(format!("{}{METADATA_FILE_EXTENSION}", Uuid::new_v4()));
         let final_metadata_file_path = Table::metadata_file_path(next_version);
-
-        log::debug!("Writing to temporary metadata file path: {tmp_metadata_file_path}");
-        self.op
-            .write(
-                &tmp_metadata_file_path,
-                serialize_table_meta(next_metadata)?,
+        let commit_strategy = self
+            .commit_strategy
+            .clone()
+            .unwrap_or_else(|| default_commit_strategy(&self.op));
+
+        log::debug!("Writing metadata file path: {final_metadata_file_path}");
+        if let Some(lock) = lock {
+            lock.provider.heartbeat(lock.key, lock.owner).await?;
+        }
+        commit_strategy
+            .write_exclusive(
+                &self.io_table_operator(IoKind::Commit),
+                &final_metadata_file_path,
+                serialize_table_meta(next_metadata)?.into_bytes(),
             )
             .await?;
-
-        log::debug!("Renaming temporary metadata file path [{tmp_metadata_file_path}] to final metadata file path [{final_metadata_file_path}]");
-        Table::rename(&self.op, &tmp_metadata_file_path, &final_metadata_file_path).await?;
         self.write_metadata_version_hint(next_version).await?;
 
         // Reload table
@@ -394,27 +1476,231 @@ impl Table {
         Ok(())
     }
 
+    /// Delete metadata files dropped from the metadata log, failures are
+    /// only logged since the commit has succeeded.
+    async fn delete_metadata_files(&self, logs: &[MetadataLog]) {
+        for entry in logs {
+            let res = match self.io_operator(IoKind::Commit, &entry.metadata_file) {
+                Ok((op, path)) => op.delete(&path).await.map_err(Error::from),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = res {
+                log::warn!(
+                    "Failed to delete metadata file {}: {err}",
+                    entry.metadata_file
+                );
+            }
+        }
+    }
+
+    /// Reload the latest metadata if the table has been updated, returns
+    /// whether the table has changed.
+    ///
+    /// Tables loaded from catalogs check the metadata location of the
+    /// catalogs. Hadoop style tables check the version hint and newer
+    /// metadata files, so metadata files are only read if there is a new
+    /// version, which makes polling cheap. Static tables never change.
+    pub async fn refresh(&mut self) -> Result<bool> {
+        if self.is_static {
+            return Ok(false);
+        }
+        let Some((catalog, identifier)) = self.catalog.clone() else {
+            let (version, path) = self.latest_metadata_path().await?;
+            if version != 0 && version as i64 == self.current_table_version {
+                return Ok(false);
+            }
+            let current_version = self.current_version;
+            self.load_metadata(version, &path).await?;
+            return Ok(self.current_version != current_version);
+        };
+        let table = catalog.load_table(&identifier).await?;
+        let metadata_location = table.current_metadata_file_location();
+        if metadata_location == self.current_metadata_file_location() {
+            return Ok(false);
+        }
+        self.set_current_metadata(metadata_location, table.current_table_metadata().clone())?;
+        Ok(true)
+    }
+
     async fn write_metadata_version_hint(&self, version: i64) -> Result<()> {
         let tmp_version_hint_path =
             Table::metadata_path(format!("{}-version-hint.temp", Uuid::new_v4()));
-        self.op
-            .write(&tmp_version_hint_path, format!("{version}"))
+        let op = self.io_table_operator(IoKind::Commit);
+        op.write(&tmp_version_hint_path, format!("{version}"))
             .await?;
 
         let final_version_hint_path = Table::metadata_path(VERSION_HINT_FILENAME);
 
-        self.op.delete(final_version_hint_path.as_str()).await?;
+        op.delete(final_version_hint_path.as_str()).await?;
         log::debug!("Renaming temporary version hint file path [{tmp_version_hint_path}] to final metadata file path [{final_version_hint_path}]");
-        Table::rename(&self.op, &tmp_version_hint_path, &final_version_hint_path).await?;
+        Table::rename(&op, &tmp_version_hint_path, &final_version_hint_path).await?;
 
         Ok(())
     }
 }
 
+/// Lock of a table held by a commit.
+struct HeldLock<'a> {
+    provider: &'a dyn LockProvider,
+    key: &'a str,
+    owner: &'a str,
+}
+
+impl HeldLock<'_> {
+    /// Refresh the lock by heartbeats until dropped, a failed heartbeat is
+    /// only logged, since the lock is checked again before publishing.
+    async fn keep_alive(&self) {
+        loop {
+            tokio::time::sleep(self.provider.heartbeat_interval()).await;
+            if let Err(err) = self.provider.heartbeat(self.key, self.owner).await {
+                log::warn!("Failed to refresh lock of table {}: {err}", self.key);
+            }
+        }
+    }
+}
+
+/// TableBuilder opens a [`Table`] with read options, created by
+/// [`Table::builder`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> icelake::Result<()> {
+/// use icelake::Table;
+/// use opendal::services::Fs;
+/// use opendal::Operator;
+///
+/// let mut builder = Fs::default();
+/// builder.root("/tmp/warehouse/db/table");
+/// let op = Operator::new(builder)?.finish();
+/// let table = Table::builder(op)
+///     .with_manifest_cache_size(64 * 1024 * 1024)
+///     .with_planning_parallelism(16)
+///     .with_default_branch("audit")
+///     .load()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TableBuilder {
+    op: Operator,
+    config: Config,
+    manifest_cache_size: Option<u64>,
+    planning_parallelism: Option<usize>,
+    io_policies: IoPolicies,
+    case_sensitive: bool,
+    default_branch: String,
+}
+
+impl TableBuilder {
+    /// Create a builder of the table of `op`, which is rooted at the table
+    /// location.
+    pub fn new(op: Operator) -> Self {
+        Self {
+            op,
+            config: Config::new(),
+            manifest_cache_size: None,
+            planning_parallelism: None,
+            io_policies: IoPolicies::new(),
+            case_sensitive: true,
+            default_branch: MAIN_BRANCH.to_string(),
+        }
+    }
+
+    /// Set the config of the table, see [`Table::set_config`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Cache parsed manifest files and manifest lists up to `capacity_bytes`
+    /// in a cache owned by the table, see [`Table::set_manifest_cache`] to
+    /// share a cache between tables.
+    pub fn with_manifest_cache_size(mut self, capacity_bytes: u64) -> Self {
+        self.manifest_cache_size = Some(capacity_bytes);
+        self
+    }
+
+    /// Set the number of manifests read concurrently when planning scans,
+    /// which overrides [`READ_MANIFEST_CONCURRENCY`].
+    pub fn with_planning_parallelism(mut self, parallelism: usize) -> Self {
+        self.planning_parallelism = Some(parallelism);
+        self
+    }
+
+    /// Set the retry and timeout policies of IO, see
+    /// [`Table::set_io_policies`].
+    pub fn with_io_policies(mut self, io_policies: IoPolicies) -> Self {
+        self.io_policies = io_policies;
+        self
+    }
+
+    /// Set whether column names of filters and projections of scans and
+    /// transactions are matched case sensitively, which is `true` by
+    /// default. Names matching more than one column case insensitively are
+    /// reported as errors.
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Set the branch read by scans and committed by transactions unless
+    /// [`TableScanBuilder::use_ref`] or [`Transaction::to_branch`] chooses
+    /// another one, which is `main` by default.
+    pub fn with_default_branch(mut self, branch: impl Into<String>) -> Self {
+        self.default_branch = branch.into();
+        self
+    }
+
+    /// Build the table without loading its metadata.
+    fn build(self) -> Result<Table> {
+        if self.planning_parallelism == Some(0) {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Planning parallelism must be positive",
+            ));
+        }
+        let mut table = Table::new(self.op);
+        table.config = match self.planning_parallelism {
+            Some(v) => self
+                .config
+                .with_option(READ_MANIFEST_CONCURRENCY, v.to_string()),
+            None => self.config,
+        };
+        if let Some(capacity_bytes) = self.manifest_cache_size {
+            table.set_manifest_cache(Arc::new(ManifestCache::new(capacity_bytes)));
+        }
+        table.io_policies = self.io_policies;
+        table.case_sensitive = self.case_sensitive;
+        table.default_branch = self.default_branch;
+        Ok(table)
+    }
+
+    /// Load the hadoop style table by version hint, see [`Table::open`].
+    pub async fn load(self) -> Result<Table> {
+        let mut table = self.build()?;
+        table.load().await?;
+        Ok(table)
+    }
+
+    /// Load a static table of the metadata file at `metadata_path`, see
+    /// [`Table::open_static`].
+    pub async fn load_static(self, metadata_path: &str) -> Result<Table> {
+        let mut table = self.build()?;
+        table.load_static(metadata_path).await?;
+        Ok(table)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::env;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
 
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use futures::TryStreamExt;
     use opendal::layers::LoggingLayer;
     use opendal::services::Fs;
 
@@ -515,6 +1801,163 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_table_refresh() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await?;
+        let mut other = Table::open(path).await?;
+        assert!(!table.refresh().await?);
+
+        let mut tx = other.new_transaction();
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        tx.commit().await?;
+        assert!(table.refresh().await?);
+        assert_eq!(
+            table.current_table_metadata(),
+            other.current_table_metadata()
+        );
+        assert!(!table.refresh().await?);
+
+        // Metadata files newer than the version hint are found.
+        let version = other.current_table_version;
+        std::fs::write(
+            tmp_dir.path().join("metadata").join(VERSION_HINT_FILENAME),
+            format!("{}", version - 1),
+        )?;
+        let table = Table::open(path).await?;
+        assert_eq!(table.current_table_version, version);
+        assert_eq!(
+            table.current_table_metadata(),
+            other.current_table_metadata()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_metadata_log() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await?;
+        let first = table.current_metadata_file_location();
+
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([
+            (METADATA_PREVIOUS_VERSIONS_MAX.to_string(), "1".to_string()),
+            (
+                METADATA_DELETE_AFTER_COMMIT_ENABLED.to_string(),
+                "true".to_string(),
+            ),
+        ]));
+        tx.commit().await?;
+        let second = table.current_metadata_file_location();
+        let logs = table.current_table_metadata().metadata_log.clone().unwrap();
+        assert_eq!(logs.last().unwrap().metadata_file, first);
+        assert_eq!(logs.len(), 1);
+
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        tx.commit().await?;
+        let logs = table.current_table_metadata().metadata_log.clone().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].metadata_file, second);
+
+        // Metadata files dropped from the log are deleted.
+        let metadata_dir = tmp_dir.path().join("metadata");
+        let version = table.current_table_version;
+        assert!(!metadata_dir
+            .join(format!("v{}.metadata.json", version - 2))
+            .exists());
+        assert!(metadata_dir
+            .join(format!("v{}.metadata.json", version - 1))
+            .exists());
+
+        let previous = table.open_previous_metadata(&second).await?;
+        assert_eq!(previous.current_metadata_file_location(), second);
+        assert!(!previous.properties().contains_key("a"));
+        assert!(table.open_previous_metadata(&first).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_open_static() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let latest = Table::open(path).await?;
+
+        let mut builder = Fs::default();
+        builder.root(path);
+        let op = Operator::new(builder)?.finish();
+        let mut table = Table::open_static("metadata/v1.metadata.json", op).await?;
+        assert_eq!(
+            table.current_metadata_file_location(),
+            format!(
+                "{}/metadata/v1.metadata.json",
+                latest.current_table_metadata().location
+            )
+        );
+        assert_ne!(
+            table.current_table_metadata(),
+            latest.current_table_metadata()
+        );
+
+        // Static tables are read only.
+        assert!(!table.refresh().await?);
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        let err = tx.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IcebergFeatureUnsupported);
+        assert_eq!(
+            Table::open(path).await?.current_table_metadata(),
+            latest.current_table_metadata()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_builder() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut builder = Fs::default();
+        builder.root(path);
+        let op = Operator::new(builder)?.finish();
+        let mut table = Table::builder(op)
+            .with_manifest_cache_size(1024 * 1024)
+            .with_planning_parallelism(2)
+            .with_case_sensitive(false)
+            .with_default_branch("audit")
+            .load()
+            .await?;
+        assert!(table.manifest_cache.is_some());
+        assert_eq!(table.usize_property(READ_MANIFEST_CONCURRENCY, 8)?, 2);
+        assert!(!table.case_sensitive());
+        let err = table.scan().build().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::IcebergDataInvalid);
+
+        // Commits go to the default branch, leaving main unchanged.
+        let main_files = table.current_data_files().await?;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files.clone());
+        tx.commit().await?;
+
+        let tasks = table.scan().build()?.plan_files().await?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].file_path, data_files[0].file_path);
+        assert_eq!(
+            Table::open(path).await?.current_data_files().await?.len(),
+            main_files.len()
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_table_current_data_files() -> Result<()> {
         let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
@@ -537,4 +1980,285 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_commit_from_results() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await?;
+        assert_eq!(table.last_committed_checkpoint_id()?, None);
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])?;
+
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let first = writer.flush().await?;
+        assert_eq!(first.data_files.len(), 1);
+        // Results can be persisted and restored.
+        let restored: WriteResult = serde_json::from_str(&serde_json::to_string(&first)?)?;
+        assert_eq!(restored, first);
+        assert!(first.delete_files.is_empty());
+        assert!(writer.flush().await?.is_empty());
+        writer.write(&batch).await?;
+        let second = WriteResult::from(writer.close().await?);
+        assert_eq!(second.data_files.len(), 1);
+        assert_ne!(
+            first.data_files[0].file_path,
+            second.data_files[0].file_path
+        );
+
+        assert!(table.commit_from_results(1, [first.clone()]).await?);
+        assert!(table.commit_from_results(2, [second]).await?);
+        assert_eq!(table.last_committed_checkpoint_id()?, Some(2));
+        // Results of committed checkpoints are skipped.
+        assert!(!table.commit_from_results(1, [first]).await?);
+        assert_eq!(table.current_data_files().await?.len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_append_batches() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await?;
+
+        // Columns are matched by names.
+        let batch = RecordBatch::try_from_iter([
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+        ])?;
+        let data_files = table
+            .append_batches(futures::stream::iter([Ok(batch.clone()), Ok(batch)]))
+            .await?;
+        assert_eq!(data_files.len(), 1);
+        let summary = &table.current_table_metadata().current_snapshot()?.summary;
+        assert_eq!(summary["operation"], "append");
+        assert_eq!(summary["total-records"], "7");
+
+        let snapshot_id = table.current_table_metadata().current_snapshot_id;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(StringArray::from(vec!["6"])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["f"])) as ArrayRef),
+        ])?;
+        let err = table
+            .append_batches(futures::stream::iter([Ok(batch)]))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Column id of type Utf8 can't be written as type Int64"));
+        assert!(table
+            .append_batches(futures::stream::empty())
+            .await?
+            .is_empty());
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            snapshot_id
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_config_write_data_path() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let data_location = format!("{}/custom_data", table.current_table_metadata().location);
+        table.set_config(
+            Config::new().with_option(crate::table_properties::WRITE_DATA_LOCATION, &data_location),
+        );
+        assert_eq!(table.data_location(), data_location);
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+        ])?;
+        let data_files = table
+            .append_batches(futures::stream::iter([Ok(batch)]))
+            .await?;
+        assert_eq!(data_files.len(), 1);
+        assert!(data_files[0]
+            .file_path
+            .starts_with(&format!("{data_location}/")));
+        assert!(tmp_dir.path().join("custom_data").is_dir());
+        assert_eq!(table.current_data_files().await?.len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_read_data_files() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+
+        let mut builder = Fs::default();
+        builder.root(&path);
+
+        let op = Operator::new(builder)?
+            .layer(LoggingLayer::default())
+            .finish();
+
+        let mut table = Table::new(op);
+        table.load().await?;
+
+        let data_files = table.current_data_files().await?;
+        let batches: Vec<_> = table.read_data_files(&data_files)?.try_collect().await?;
+        let rows: usize = batches.iter().map(|v| v.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_config() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+
+        let mut builder = Fs::default();
+        builder.root(&path);
+
+        let op = Operator::new(builder)?
+            .layer(LoggingLayer::default())
+            .finish();
+
+        let mut table = Table::new(op);
+        table.load().await?;
+        assert_eq!(table.properties()["owner"], "spark");
+
+        table.set_config(
+            Config::new()
+                .with_option("owner", "icelake")
+                .with_option(READ_PARQUET_ROW_GROUP_CONCURRENCY, "4"),
+        );
+        assert_eq!(table.properties()["owner"], "icelake");
+
+        let data_files = table.current_data_files().await?;
+        let batches: Vec<_> = table.read_data_files(&data_files)?.try_collect().await?;
+        let rows: usize = batches.iter().map(|v| v.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        table.set_config(Config::new().with_option(READ_PARQUET_ROW_GROUP_CONCURRENCY, "x"));
+        assert!(table.read_data_files(&data_files).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_location_operator() -> Result<()> {
+        let path = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+
+        let mut builder = Fs::default();
+        builder.root(&path);
+
+        let op = Operator::new(builder)?
+            .layer(LoggingLayer::default())
+            .finish();
+
+        let mut table = Table::new(op);
+        table.load().await?;
+
+        let (_, path) =
+            table.location_operator("/opt/bitnami/spark/warehouse/db/table/metadata/a.avro")?;
+        assert_eq!(path, "/metadata/a.avro");
+
+        assert!(table
+            .location_operator("s3://other/metadata/a.avro")
+            .is_err());
+
+        let other_op = Operator::new(opendal::services::Memory::default())?.finish();
+        table.register_location_operator("s3://other/", other_op);
+        let (op, path) = table.location_operator("s3://other/metadata/a.avro")?;
+        assert_eq!(op.info().scheme(), opendal::Scheme::Memory);
+        assert_eq!(path, "/metadata/a.avro");
+
+        // Only match on path boundary.
+        assert!(table.location_operator("s3://other2/a.avro").is_err());
+
+        // Relative locations are resolved against table operator.
+        let (op, path) = table.location_operator("data/a.parquet")?;
+        assert_eq!(op.info().scheme(), opendal::Scheme::Fs);
+        assert_eq!(path, "data/a.parquet");
+
+        Ok(())
+    }
+
+    /// Lock provider holding locks in memory, whose heartbeats are counted
+    /// and fail after the lock is revoked.
+    #[derive(Default)]
+    struct CountingLockProvider {
+        heartbeats: AtomicUsize,
+        revoked: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl LockProvider for CountingLockProvider {
+        async fn acquire(&self, _key: &str, _owner: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn heartbeat(&self, _key: &str, _owner: &str) -> Result<()> {
+            self.heartbeats.fetch_add(1, Ordering::SeqCst);
+            if self.revoked.load(Ordering::SeqCst) {
+                return Err(Error::new(ErrorKind::Unexpected, "lock is revoked"));
+            }
+            Ok(())
+        }
+
+        async fn release(&self, _key: &str, _owner: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn heartbeat_interval(&self) -> Duration {
+            Duration::from_millis(10)
+        }
+    }
+
+    /// Commit strategy taking a while to write metadata files.
+    struct SlowCommitStrategy;
+
+    #[async_trait::async_trait]
+    impl CommitStrategy for SlowCommitStrategy {
+        async fn write_exclusive(&self, op: &Operator, path: &str, content: Vec<u8>) -> Result<()> {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            crate::commit::FileSystemCommitStrategy
+                .write_exclusive(op, path, content)
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_lock_heartbeat() -> Result<()> {
+        let tmp_dir = crate::test_utils::prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await?;
+        let lock_provider = Arc::new(CountingLockProvider::default());
+        table.set_lock_provider(lock_provider.clone());
+        table.set_commit_strategy(Arc::new(SlowCommitStrategy));
+
+        // The lock is checked before publishing, and refreshed while the
+        // metadata file is written.
+        let version = table.current_table_version;
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        tx.commit().await?;
+        assert_eq!(table.current_table_version, version + 1);
+        assert!(lock_provider.heartbeats.load(Ordering::SeqCst) > 2);
+
+        // Commits never publish metadata once the lock is lost.
+        lock_provider.revoked.store(true, Ordering::SeqCst);
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("c".to_string(), "d".to_string())]));
+        assert!(tx.commit().await.is_err());
+        table.refresh().await?;
+        assert_eq!(table.current_table_version, version + 1);
+        assert!(!table.properties().contains_key("c"));
+        Ok(())
+    }
 }
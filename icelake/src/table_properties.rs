@@ -0,0 +1,240 @@
+//! table_properties module provides the keys and default values of iceberg
+//! table properties used by icelake.
+//!
+//! Reference: <https://iceberg.apache.org/docs/latest/configuration/#table-properties>
+
+/// Default file format for data files, `parquet` or `orc`. Avro data files
+/// can be read but not written.
+pub const DEFAULT_FILE_FORMAT: &str = "write.format.default";
+/// Default value of [`DEFAULT_FILE_FORMAT`].
+pub const DEFAULT_FILE_FORMAT_DEFAULT: &str = "parquet";
+
+/// Base location for data files.
+///
+/// Defaults to `{table location}/data`.
+pub const WRITE_DATA_LOCATION: &str = "write.data.path";
+/// Deprecated key of [`WRITE_DATA_LOCATION`], only used when
+/// `write.data.path` is not set.
+pub const WRITE_FOLDER_STORAGE_LOCATION: &str = "write.folder-storage.path";
+/// Base location for manifests and manifest lists.
+///
+/// Defaults to `{table location}/metadata`.
+///
+/// # Note
+///
+/// Table metadata files and version hint are always stored under
+/// `{table location}/metadata` so that the table can be found by its location.
+pub const WRITE_METADATA_LOCATION: &str = "write.metadata.path";
+/// Whether to place new data files under hash prefixed directories like
+/// `{data location}/2d9fd2c4/{partition}/{file}`, which spreads writes over
+/// many prefixes to avoid request throttling of object stores like S3.
+pub const WRITE_OBJECT_STORE_ENABLED: &str = "write.object-storage.enabled";
+/// Default value of [`WRITE_OBJECT_STORE_ENABLED`].
+pub const WRITE_OBJECT_STORE_ENABLED_DEFAULT: bool = false;
+/// Whether to keep partition directories after the hash prefix when
+/// [`WRITE_OBJECT_STORE_ENABLED`] is set.
+pub const WRITE_OBJECT_STORE_PARTITIONED_PATHS: &str = "write.object-storage.partitioned-paths";
+/// Default value of [`WRITE_OBJECT_STORE_PARTITIONED_PATHS`].
+pub const WRITE_OBJECT_STORE_PARTITIONED_PATHS_DEFAULT: bool = true;
+/// Whether to record locations of data files, manifests and manifest lists
+/// relative to the table location, so that the table directory can be
+/// copied or mounted elsewhere without rewriting metadata.
+///
+/// Relative locations are always readable, this property only affects
+/// writing. Files outside of the table location are still recorded by
+/// absolute locations.
+///
+/// This is an icelake specific property.
+pub const WRITE_RELATIVE_PATH_ENABLED: &str = "icelake.write.relative-path.enabled";
+/// Default value of [`WRITE_RELATIVE_PATH_ENABLED`].
+pub const WRITE_RELATIVE_PATH_ENABLED_DEFAULT: bool = false;
+
+/// Target size of data files in bytes.
+pub const WRITE_TARGET_FILE_SIZE_BYTES: &str = "write.target-file-size-bytes";
+/// Default value of [`WRITE_TARGET_FILE_SIZE_BYTES`], 512 MiB.
+pub const WRITE_TARGET_FILE_SIZE_BYTES_DEFAULT: u64 = 512 * 1024 * 1024;
+/// Whether task writers sort rows of data files by the default sort order
+/// of the table. Rows of a file are buffered in memory until they reach the
+/// target file size.
+///
+/// This is an icelake specific property.
+pub const WRITE_SORT_ENABLED: &str = "icelake.write.sort.enabled";
+/// Default value of [`WRITE_SORT_ENABLED`].
+pub const WRITE_SORT_ENABLED_DEFAULT: bool = false;
+/// Whether partitioned task writers keep files of all partitions open, so
+/// input doesn't need to be clustered by partitions. Otherwise input must
+/// be clustered, like sorted by partitions, and only one file is open.
+///
+/// This is an icelake specific property.
+pub const WRITE_FANOUT_ENABLED: &str = "icelake.write.fanout.enabled";
+/// Default value of [`WRITE_FANOUT_ENABLED`].
+pub const WRITE_FANOUT_ENABLED_DEFAULT: bool = true;
+/// Max number of files partitioned task writers keep open, the least
+/// recently written file is closed to open a file of another partition.
+/// Rows arriving later to a closed partition are written to a new file.
+///
+/// This is an icelake specific property.
+pub const WRITE_MAX_OPEN_FILES: &str = "icelake.write.max-open-files";
+/// Default value of [`WRITE_MAX_OPEN_FILES`].
+pub const WRITE_MAX_OPEN_FILES_DEFAULT: usize = 100;
+
+/// Target size of manifests in bytes when merging manifests.
+pub const MANIFEST_TARGET_SIZE_BYTES: &str = "commit.manifest.target-size-bytes";
+/// Default value of [`MANIFEST_TARGET_SIZE_BYTES`], 8 MiB.
+pub const MANIFEST_TARGET_SIZE_BYTES_DEFAULT: u64 = 8 * 1024 * 1024;
+
+/// Number of times to retry a commit conflicting with concurrent commits.
+pub const COMMIT_NUM_RETRIES: &str = "commit.retry.num-retries";
+/// Default value of [`COMMIT_NUM_RETRIES`].
+pub const COMMIT_NUM_RETRIES_DEFAULT: u32 = 4;
+/// Minimum time in milliseconds to wait before retrying a commit.
+pub const COMMIT_MIN_RETRY_WAIT_MS: &str = "commit.retry.min-wait-ms";
+/// Default value of [`COMMIT_MIN_RETRY_WAIT_MS`].
+pub const COMMIT_MIN_RETRY_WAIT_MS_DEFAULT: u64 = 100;
+/// Maximum time in milliseconds to wait before retrying a commit.
+pub const COMMIT_MAX_RETRY_WAIT_MS: &str = "commit.retry.max-wait-ms";
+/// Default value of [`COMMIT_MAX_RETRY_WAIT_MS`], 1 minute.
+pub const COMMIT_MAX_RETRY_WAIT_MS_DEFAULT: u64 = 60 * 1000;
+/// Total time in milliseconds to retry a commit.
+pub const COMMIT_TOTAL_RETRY_TIME_MS: &str = "commit.retry.total-timeout-ms";
+/// Default value of [`COMMIT_TOTAL_RETRY_TIME_MS`], 30 minutes.
+pub const COMMIT_TOTAL_RETRY_TIME_MS_DEFAULT: u64 = 30 * 60 * 1000;
+
+/// Default metrics mode of columns, one of `none`, `counts`,
+/// `truncate(length)` and `full`.
+pub const DEFAULT_WRITE_METRICS_MODE: &str = "write.metadata.metrics.default";
+/// Default value of [`DEFAULT_WRITE_METRICS_MODE`].
+pub const DEFAULT_WRITE_METRICS_MODE_DEFAULT: &str = "truncate(16)";
+/// Prefix of per column metrics mode, like
+/// `write.metadata.metrics.column.col1`.
+pub const METRICS_MODE_COLUMN_CONF_PREFIX: &str = "write.metadata.metrics.column.";
+
+/// Max number of changed partitions whose changes are summarized one by
+/// one in snapshot summaries, as `partitions.{path}`.
+pub const WRITE_PARTITION_SUMMARY_LIMIT: &str = "write.summary.partition-limit";
+/// Default value of [`WRITE_PARTITION_SUMMARY_LIMIT`], which summarizes no
+/// partitions.
+pub const WRITE_PARTITION_SUMMARY_LIMIT_DEFAULT: usize = 0;
+
+/// Compression codec of parquet files.
+pub const PARQUET_COMPRESSION: &str = "write.parquet.compression-codec";
+/// Default value of [`PARQUET_COMPRESSION`].
+pub const PARQUET_COMPRESSION_DEFAULT: &str = "gzip";
+/// Compression level of parquet files, default to the codec's default.
+pub const PARQUET_COMPRESSION_LEVEL: &str = "write.parquet.compression-level";
+/// Parquet row group size in bytes, row groups are closed once their
+/// estimated size before encoding reaches it.
+pub const PARQUET_ROW_GROUP_SIZE_BYTES: &str = "write.parquet.row-group-size-bytes";
+/// Default value of [`PARQUET_ROW_GROUP_SIZE_BYTES`], 128 MiB.
+pub const PARQUET_ROW_GROUP_SIZE_BYTES_DEFAULT: usize = 128 * 1024 * 1024;
+/// Parquet page size in bytes.
+pub const PARQUET_PAGE_SIZE_BYTES: &str = "write.parquet.page-size-bytes";
+/// Default value of [`PARQUET_PAGE_SIZE_BYTES`], 1 MiB.
+pub const PARQUET_PAGE_SIZE_BYTES_DEFAULT: usize = 1024 * 1024;
+/// Parquet dictionary page size in bytes.
+pub const PARQUET_DICT_SIZE_BYTES: &str = "write.parquet.dict-size-bytes";
+/// Default value of [`PARQUET_DICT_SIZE_BYTES`], 2 MiB.
+pub const PARQUET_DICT_SIZE_BYTES_DEFAULT: usize = 2 * 1024 * 1024;
+/// Prefix of per column bloom filter switch, like
+/// `write.parquet.bloom-filter-enabled.column.col1`.
+pub const PARQUET_BLOOM_FILTER_COLUMN_ENABLED_PREFIX: &str =
+    "write.parquet.bloom-filter-enabled.column.";
+/// Prefix of per column bloom filter false positive probability, like
+/// `write.parquet.bloom-filter-fpp.column.col1`.
+pub const PARQUET_BLOOM_FILTER_COLUMN_FPP_PREFIX: &str = "write.parquet.bloom-filter-fpp.column.";
+
+/// Compression codec of orc files.
+pub const ORC_COMPRESSION: &str = "write.orc.compression-codec";
+/// Default value of [`ORC_COMPRESSION`].
+pub const ORC_COMPRESSION_DEFAULT: &str = "zlib";
+/// Size of buffered rows in bytes to flush as an orc stripe.
+pub const ORC_STRIPE_SIZE_BYTES: &str = "write.orc.stripe-size-bytes";
+/// Default value of [`ORC_STRIPE_SIZE_BYTES`], 64 MiB.
+pub const ORC_STRIPE_SIZE_BYTES_DEFAULT: usize = 64 * 1024 * 1024;
+
+/// Default name mapping of the table in json, used to read columns of data
+/// files written without field ids, see [`crate::types::NameMapping`].
+pub const DEFAULT_NAME_MAPPING: &str = "schema.name-mapping.default";
+
+/// Max number of row groups of a parquet file decoded concurrently while
+/// reading.
+///
+/// This is an icelake specific property.
+pub const READ_PARQUET_ROW_GROUP_CONCURRENCY: &str = "icelake.read.parquet.row-group-concurrency";
+/// Default value of [`READ_PARQUET_ROW_GROUP_CONCURRENCY`].
+pub const READ_PARQUET_ROW_GROUP_CONCURRENCY_DEFAULT: usize = 1;
+
+/// Max number of manifest files loaded concurrently while planning scans.
+///
+/// This is an icelake specific property.
+pub const READ_MANIFEST_CONCURRENCY: &str = "icelake.read.manifest.concurrency";
+/// Default value of [`READ_MANIFEST_CONCURRENCY`].
+pub const READ_MANIFEST_CONCURRENCY_DEFAULT: usize = 8;
+
+/// Target size in bytes of combined scan tasks, see
+/// [`crate::scan::TableScan::plan_combined_tasks`].
+pub const READ_SPLIT_TARGET_SIZE: &str = "read.split.target-size";
+/// Default value of [`READ_SPLIT_TARGET_SIZE`], 128 MiB.
+pub const READ_SPLIT_TARGET_SIZE_DEFAULT: usize = 128 * 1024 * 1024;
+/// Estimated cost in bytes of opening a file, which is the minimum weight
+/// of a split while combining scan tasks, so that a task doesn't contain
+/// too many small files.
+pub const READ_SPLIT_OPEN_FILE_COST: &str = "read.split.open-file-cost";
+/// Default value of [`READ_SPLIT_OPEN_FILE_COST`], 4 MiB.
+pub const READ_SPLIT_OPEN_FILE_COST_DEFAULT: usize = 4 * 1024 * 1024;
+/// Number of open combined tasks considered for each split while combining.
+pub const READ_SPLIT_PLANNING_LOOKBACK: &str = "read.split.planning-lookback";
+/// Default value of [`READ_SPLIT_PLANNING_LOOKBACK`].
+pub const READ_SPLIT_PLANNING_LOOKBACK_DEFAULT: usize = 10;
+
+/// Name pattern of the tag created for every committed snapshot, like
+/// `audit-%Y-%m-%d`.
+///
+/// The pattern is formatted by `strftime` specifiers with the commit time of
+/// the snapshot in UTC. An existing tag with the same name is moved to the
+/// new snapshot, so the pattern above keeps the last snapshot of each day.
+/// No tag is created if not set.
+///
+/// This is an icelake specific property.
+pub const AUTO_TAG_NAME_PATTERN: &str = "icelake.auto-tag.name-pattern";
+/// Max age in milliseconds of tags created by [`AUTO_TAG_NAME_PATTERN`],
+/// which is recorded as `max-ref-age-ms` of the tag.
+///
+/// This is an icelake specific property.
+pub const AUTO_TAG_MAX_REF_AGE_MS: &str = "icelake.auto-tag.max-ref-age-ms";
+
+/// Whether write-audit-publish is enabled, commits with snapshot property
+/// `wap.id` are staged instead of becoming current if enabled.
+///
+/// Staged snapshots are published by
+/// [`crate::transaction::CherryPickSnapshot`].
+pub const WRITE_WAP_ENABLED: &str = "write.wap.enabled";
+/// Default value of [`WRITE_WAP_ENABLED`].
+pub const WRITE_WAP_ENABLED_DEFAULT: bool = false;
+
+/// Whether metadata files dropped from the metadata log are deleted after
+/// commits.
+pub const METADATA_DELETE_AFTER_COMMIT_ENABLED: &str = "write.metadata.delete-after-commit.enabled";
+/// Default value of [`METADATA_DELETE_AFTER_COMMIT_ENABLED`].
+pub const METADATA_DELETE_AFTER_COMMIT_ENABLED_DEFAULT: bool = false;
+/// Max number of previous metadata files tracked in the metadata log, older
+/// ones are dropped from the log.
+pub const METADATA_PREVIOUS_VERSIONS_MAX: &str = "write.metadata.previous-versions-max";
+/// Default value of [`METADATA_PREVIOUS_VERSIONS_MAX`].
+pub const METADATA_PREVIOUS_VERSIONS_MAX_DEFAULT: usize = 100;
+
+/// Default max age in milliseconds of snapshots to keep while expiring
+/// snapshots.
+pub const MAX_SNAPSHOT_AGE_MS: &str = "history.expire.max-snapshot-age-ms";
+/// Default value of [`MAX_SNAPSHOT_AGE_MS`], 5 days.
+pub const MAX_SNAPSHOT_AGE_MS_DEFAULT: i64 = 5 * 24 * 60 * 60 * 1000;
+/// Default min number of snapshots to keep in a branch while expiring
+/// snapshots.
+pub const MIN_SNAPSHOTS_TO_KEEP: &str = "history.expire.min-snapshots-to-keep";
+/// Default value of [`MIN_SNAPSHOTS_TO_KEEP`].
+pub const MIN_SNAPSHOTS_TO_KEEP_DEFAULT: usize = 1;
+/// Default max age in milliseconds of snapshot references to keep while
+/// expiring snapshots. The main branch never expires.
+///
+/// References never expire if not set.
+pub const MAX_REF_AGE_MS: &str = "history.expire.max-ref-age-ms";
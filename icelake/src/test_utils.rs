@@ -0,0 +1,61 @@
+//! Utilities shared by tests.
+
+use std::fs;
+
+use tempfile::TempDir;
+
+/// Copy simple table into a temp dir so that it can be committed.
+///
+/// The table is upgraded to v2 since only v2 tables can be written.
+pub(crate) fn prepare_table_dir() -> TempDir {
+    let tmp_dir = prepare_v1_table_dir();
+    let metadata_path = tmp_dir.path().join("metadata").join("v2.metadata.json");
+    let metadata = fs::read_to_string(&metadata_path).unwrap().replace(
+        r#""format-version": 1,"#,
+        r#""format-version": 2, "last-sequence-number": 0,"#,
+    );
+    fs::write(metadata_path, metadata).unwrap();
+    tmp_dir
+}
+
+/// Copy simple table into a temp dir as is, which is a v1 table.
+pub(crate) fn prepare_v1_table_dir() -> TempDir {
+    let tmp_dir = TempDir::new().unwrap();
+    let src = format!("{}/../testdata/simple_table", env!("CARGO_MANIFEST_DIR"));
+    for dir in ["metadata", "data"] {
+        fs::create_dir_all(tmp_dir.path().join(dir)).unwrap();
+        for entry in fs::read_dir(format!("{src}/{dir}")).unwrap() {
+            let entry = entry.unwrap();
+            fs::copy(
+                entry.path(),
+                tmp_dir.path().join(dir).join(entry.file_name()),
+            )
+            .unwrap();
+        }
+    }
+    tmp_dir
+}
+
+/// Copy simple table into a temp dir as a v2 table, whose default spec is
+/// changed to spec 1 partitioned by `data`.
+///
+/// Existing data files are still of the unpartitioned spec 0.
+pub(crate) fn prepare_partitioned_table_dir() -> TempDir {
+    let tmp_dir = prepare_table_dir();
+    let metadata_path = tmp_dir.path().join("metadata").join("v2.metadata.json");
+    let mut metadata: serde_json::Value =
+        serde_json::from_slice(&fs::read(&metadata_path).unwrap()).unwrap();
+    metadata["partition-specs"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "spec-id": 1,
+            "fields": [
+                {"source-id": 2, "field-id": 1000, "name": "data", "transform": "identity"}
+            ]
+        }));
+    metadata["default-spec-id"] = 1.into();
+    metadata["last-partition-id"] = 1000.into();
+    fs::write(metadata_path, serde_json::to_vec(&metadata).unwrap()).unwrap();
+    tmp_dir
+}
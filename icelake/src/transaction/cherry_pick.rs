@@ -0,0 +1,218 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::transaction::{Transaction, PUBLISHED_WAP_ID, SOURCE_SNAPSHOT_ID, WAP_ID};
+use crate::types::ManifestStatus;
+use crate::{Error, ErrorKind, Result, Table};
+
+/// CherryPickSnapshot publishes a snapshot, like one staged by
+/// write-audit-publish, to the main branch of a table.
+///
+/// If the snapshot's parent is the current snapshot, it becomes the current
+/// snapshot directly. Otherwise files added by the snapshot are appended
+/// to the current snapshot as a new snapshot, which records
+/// `source-snapshot-id` and `published-wap-id` in its summary. Only
+/// `append` snapshots can be cherry-picked in this way.
+///
+/// A snapshot with a `wap.id` that has been published fails to be
+/// published again.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example(data_files: Vec<icelake::types::DataFile>) -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut tx = table.new_transaction();
+/// tx.append_file(data_files);
+/// tx.set_wap_id("job-1");
+/// tx.stage_only();
+/// tx.commit().await?;
+///
+/// // Audit the staged snapshot, then publish it.
+/// let staged = table.current_table_metadata().snapshots.as_ref().unwrap().last().unwrap();
+/// table.cherry_pick_snapshot(staged.snapshot_id).commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CherryPickSnapshot<'a> {
+    table: &'a mut Table,
+    snapshot_id: i64,
+}
+
+impl<'a> CherryPickSnapshot<'a> {
+    /// Create an action to cherry-pick the snapshot of table.
+    pub fn new(table: &'a mut Table, snapshot_id: i64) -> Self {
+        Self { table, snapshot_id }
+    }
+
+    /// Publish the snapshot.
+    pub async fn commit(self) -> Result<()> {
+        let table = self.table;
+        let metadata = table.current_table_metadata();
+        let snapshot = metadata
+            .snapshot(self.snapshot_id)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Snapshot id {} not found!", self.snapshot_id),
+                )
+            })?
+            .clone();
+        let ancestors = match metadata.current_snapshot_id {
+            Some(snapshot_id) => metadata.ancestors(snapshot_id),
+            None => vec![],
+        };
+        if ancestors
+            .iter()
+            .any(|v| v.snapshot_id == snapshot.snapshot_id)
+        {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!(
+                    "Snapshot {} is already an ancestor of the current snapshot",
+                    snapshot.snapshot_id
+                ),
+            ));
+        }
+        let wap_id = snapshot.summary.get(WAP_ID).cloned();
+        if let Some(wap_id) = &wap_id {
+            let published = ancestors.iter().any(|v| {
+                v.summary.get(WAP_ID) == Some(wap_id)
+                    || v.summary.get(PUBLISHED_WAP_ID) == Some(wap_id)
+            });
+            if published {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Duplicate request to publish wap id {wap_id}"),
+                ));
+            }
+        }
+
+        // Fast forward.
+        if snapshot.parent_snapshot_id == metadata.current_snapshot_id {
+            let mut new_metadata = metadata.clone();
+            let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+            new_metadata.set_current_snapshot(snapshot.snapshot_id, now_ms)?;
+            return table.commit(new_metadata).await;
+        }
+
+        let operation = snapshot.summary.get("operation").map(|v| v.as_str());
+        if operation != Some("append") {
+            return Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!(
+                    "Cherry-picking snapshot of operation {}",
+                    operation.unwrap_or("unknown")
+                ),
+            ));
+        }
+        let mut added = vec![];
+        let manifest_list = snapshot.load_manifest_list(table).await?;
+        for entry in &manifest_list.entries {
+            if entry.added_snapshot_id != snapshot.snapshot_id {
+                continue;
+            }
+            let manifest = entry.load_manifest(table).await?;
+            added.extend(
+                manifest
+                    .entries
+                    .into_iter()
+                    .filter(|v| {
+                        v.status == ManifestStatus::Added
+                            && v.snapshot_id == Some(snapshot.snapshot_id)
+                    })
+                    .map(|v| v.data_file),
+            );
+        }
+
+        let mut tx = Transaction::new(table);
+        tx.append_file(added);
+        tx.set_snapshot_property(SOURCE_SNAPSHOT_ID, snapshot.snapshot_id.to_string());
+        if let Some(wap_id) = wap_id {
+            tx.set_snapshot_property(PUBLISHED_WAP_ID, wap_id);
+        }
+        tx.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::table_properties::WRITE_WAP_ENABLED;
+    use crate::test_utils::prepare_table_dir;
+
+    /// Stage a snapshot appending one file with the wap id, returns its id.
+    async fn stage(table: &mut Table, wap_id: &str) -> i64 {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.append_file(writer.close().await.unwrap());
+        tx.set_wap_id(wap_id);
+        tx.commit().await.unwrap();
+        let metadata = table.current_table_metadata();
+        let snapshot = metadata.snapshots.as_ref().unwrap().last().unwrap();
+        assert_eq!(snapshot.summary[WAP_ID], wap_id);
+        snapshot.snapshot_id
+    }
+
+    #[tokio::test]
+    async fn test_cherry_pick_staged_snapshot() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([(
+            WRITE_WAP_ENABLED.to_string(),
+            "true".to_string(),
+        )]));
+        tx.commit().await.unwrap();
+        let base_snapshot_id = table.current_table_metadata().current_snapshot_id;
+
+        let first = stage(&mut table, "a").await;
+        let second = stage(&mut table, "b").await;
+        // Staged snapshots are not current.
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            base_snapshot_id
+        );
+        assert_eq!(table.current_data_files().await.unwrap().len(), 3);
+        let scan = table.scan().with_snapshot_id(second).build().unwrap();
+        assert_eq!(scan.plan_files().await.unwrap().len(), 4);
+
+        // Published by fast forward.
+        table.cherry_pick_snapshot(first).commit().await.unwrap();
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            Some(first)
+        );
+        assert!(table.cherry_pick_snapshot(first).commit().await.is_err());
+
+        // Published by appending files of the snapshot.
+        table.cherry_pick_snapshot(second).commit().await.unwrap();
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        assert_eq!(snapshot.parent_snapshot_id, Some(first));
+        assert_eq!(snapshot.summary[SOURCE_SNAPSHOT_ID], second.to_string());
+        assert_eq!(snapshot.summary[PUBLISHED_WAP_ID], "b");
+        assert_eq!(table.current_data_files().await.unwrap().len(), 5);
+
+        // Wap ids are published at most once.
+        let duplicate = stage(&mut table, "b").await;
+        assert!(table
+            .cherry_pick_snapshot(duplicate)
+            .commit()
+            .await
+            .is_err());
+    }
+}
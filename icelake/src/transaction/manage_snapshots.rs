@@ -0,0 +1,553 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::types::{SnapshotReference, SnapshotReferenceType, TableMetadata, MAIN_BRANCH};
+use crate::{Error, ErrorKind, Result};
+
+/// ManageSnapshots updates snapshot references of a table, which are
+/// branches and tags, committed by
+/// [`crate::transaction::Transaction::manage_snapshots`].
+///
+/// The current snapshot of the table, which is the head of the main branch,
+/// can be set to an existing snapshot as well, like rolling back to an
+/// earlier snapshot.
+///
+/// Retention of a reference is used by
+/// [`crate::maintenance::ExpireSnapshots`], values not set fall back to
+/// table properties `history.expire.*`.
+///
+/// Updates are applied in the order they were added.
+///
+/// # Examples
+///
+/// ```no_run
+/// use icelake::transaction::ManageSnapshots;
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+/// let mut tx = table.new_transaction();
+/// tx.manage_snapshots(
+///     ManageSnapshots::new()
+///         .create_branch("audit-branch", snapshot_id)
+///         .set_max_ref_age_ms("audit-branch", 7 * 24 * 3600 * 1000)
+///         .create_tag("v1", snapshot_id),
+/// );
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ManageSnapshots {
+    updates: Vec<RefUpdate>,
+}
+
+#[derive(Clone)]
+enum RefUpdate {
+    Create(String, SnapshotReference),
+    Remove(String, SnapshotReferenceType),
+    MinSnapshotsToKeep(String, i32),
+    MaxSnapshotAgeMs(String, i64),
+    MaxRefAgeMs(String, i64),
+    SetCurrentSnapshot(i64),
+    RollbackTo(i64),
+    ReplaceBranch {
+        branch: String,
+        to_ref: String,
+        fast_forward: bool,
+    },
+}
+
+impl ManageSnapshots {
+    /// Create an empty update.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a branch pointing to the snapshot, fails if a reference of
+    /// the same name exists.
+    pub fn create_branch(mut self, name: impl Into<String>, snapshot_id: i64) -> Self {
+        self.updates.push(RefUpdate::Create(
+            name.into(),
+            SnapshotReference::new(snapshot_id, SnapshotReferenceType::Branch),
+        ));
+        self
+    }
+
+    /// Create a tag pointing to the snapshot, fails if a reference of the
+    /// same name exists.
+    pub fn create_tag(mut self, name: impl Into<String>, snapshot_id: i64) -> Self {
+        self.updates.push(RefUpdate::Create(
+            name.into(),
+            SnapshotReference::new(snapshot_id, SnapshotReferenceType::Tag),
+        ));
+        self
+    }
+
+    /// Remove a branch, the main branch can't be removed.
+    ///
+    /// Snapshots of the branch are kept until they are expired.
+    pub fn remove_branch(mut self, name: impl Into<String>) -> Self {
+        self.updates.push(RefUpdate::Remove(
+            name.into(),
+            SnapshotReferenceType::Branch,
+        ));
+        self
+    }
+
+    /// Remove a tag.
+    pub fn remove_tag(mut self, name: impl Into<String>) -> Self {
+        self.updates
+            .push(RefUpdate::Remove(name.into(), SnapshotReferenceType::Tag));
+        self
+    }
+
+    /// Keep at least `n` last snapshots of a branch while expiring
+    /// snapshots.
+    pub fn set_min_snapshots_to_keep(mut self, branch: impl Into<String>, n: i32) -> Self {
+        self.updates
+            .push(RefUpdate::MinSnapshotsToKeep(branch.into(), n));
+        self
+    }
+
+    /// Keep snapshots of a branch younger than `max_age_ms` while expiring
+    /// snapshots.
+    pub fn set_max_snapshot_age_ms(mut self, branch: impl Into<String>, max_age_ms: i64) -> Self {
+        self.updates
+            .push(RefUpdate::MaxSnapshotAgeMs(branch.into(), max_age_ms));
+        self
+    }
+
+    /// Remove a branch or tag whose snapshot is older than `max_age_ms`
+    /// while expiring snapshots. The main branch never expires.
+    pub fn set_max_ref_age_ms(mut self, name: impl Into<String>, max_age_ms: i64) -> Self {
+        self.updates
+            .push(RefUpdate::MaxRefAgeMs(name.into(), max_age_ms));
+        self
+    }
+
+    /// Set the snapshot as the current snapshot of the table, which can be
+    /// any existing snapshot, like a staged one.
+    pub fn set_current_snapshot(mut self, snapshot_id: i64) -> Self {
+        self.updates
+            .push(RefUpdate::SetCurrentSnapshot(snapshot_id));
+        self
+    }
+
+    /// Roll the table back to the snapshot, which must be an ancestor of
+    /// the current snapshot.
+    ///
+    /// Snapshots after it are kept until they are expired.
+    pub fn rollback_to(mut self, snapshot_id: i64) -> Self {
+        self.updates.push(RefUpdate::RollbackTo(snapshot_id));
+        self
+    }
+
+    /// Move the head of a branch to the snapshot of `to_ref`, which is a
+    /// branch or a tag, discarding snapshots of the branch not reachable
+    /// from `to_ref`.
+    ///
+    /// Retention of the branch is kept. Replacing the main branch changes
+    /// the current snapshot of the table.
+    pub fn replace_branch(mut self, branch: impl Into<String>, to_ref: impl Into<String>) -> Self {
+        self.updates.push(RefUpdate::ReplaceBranch {
+            branch: branch.into(),
+            to_ref: to_ref.into(),
+            fast_forward: false,
+        });
+        self
+    }
+
+    /// Like [`ManageSnapshots::replace_branch`], but the head of the branch
+    /// must be an ancestor of the snapshot of `to_ref`, so that no snapshot
+    /// of the branch is discarded, like promoting a staging branch to main.
+    ///
+    /// Nothing changes if the branch is already ahead of `to_ref`.
+    pub fn fast_forward(mut self, branch: impl Into<String>, to_ref: impl Into<String>) -> Self {
+        self.updates.push(RefUpdate::ReplaceBranch {
+            branch: branch.into(),
+            to_ref: to_ref.into(),
+            fast_forward: true,
+        });
+        self
+    }
+
+    /// Apply updates to refs of the table metadata.
+    pub(crate) fn apply(self, metadata: &mut TableMetadata) -> Result<()> {
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        for update in self.updates {
+            match update {
+                RefUpdate::Create(name, reference) => {
+                    if metadata.refs.contains_key(&name) {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Reference {name} already exists"),
+                        ));
+                    }
+                    if metadata.snapshot(reference.snapshot_id).is_none() {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!(
+                                "Can't create reference {name} to unknown snapshot {}",
+                                reference.snapshot_id
+                            ),
+                        ));
+                    }
+                    metadata.refs.insert(name, reference);
+                }
+                RefUpdate::Remove(name, typ) => {
+                    if name == MAIN_BRANCH {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            "Can't remove the main branch",
+                        ));
+                    }
+                    ref_of_type(metadata, &name, typ)?;
+                    metadata.refs.remove(&name);
+                }
+                RefUpdate::MinSnapshotsToKeep(name, n) => {
+                    ref_of_type(metadata, &name, SnapshotReferenceType::Branch)?
+                        .min_snapshots_to_keep = Some(n);
+                }
+                RefUpdate::MaxSnapshotAgeMs(name, max_age_ms) => {
+                    ref_of_type(metadata, &name, SnapshotReferenceType::Branch)?
+                        .max_snapshot_age_ms = Some(max_age_ms);
+                }
+                RefUpdate::MaxRefAgeMs(name, max_age_ms) => {
+                    if name == MAIN_BRANCH {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            "Can't set max ref age of the main branch",
+                        ));
+                    }
+                    let reference = metadata.refs.get_mut(&name).ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Reference {name} is not found"),
+                        )
+                    })?;
+                    reference.max_ref_age_ms = Some(max_age_ms);
+                }
+                RefUpdate::SetCurrentSnapshot(snapshot_id) => {
+                    metadata.set_current_snapshot(snapshot_id, now_ms)?;
+                }
+                RefUpdate::RollbackTo(snapshot_id) => {
+                    let is_ancestor = metadata
+                        .current_snapshot_id
+                        .is_some_and(|current| metadata.is_ancestor_of(snapshot_id, current));
+                    if !is_ancestor {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!(
+                                "Can't roll back to snapshot {snapshot_id}, which is not an ancestor of the current snapshot"
+                            ),
+                        ));
+                    }
+                    metadata.set_current_snapshot(snapshot_id, now_ms)?;
+                }
+                RefUpdate::ReplaceBranch {
+                    branch,
+                    to_ref,
+                    fast_forward,
+                } => {
+                    let head = branch_head(metadata, &branch)?;
+                    let target = metadata
+                        .snapshot_by_ref(&to_ref)
+                        .ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!("Reference {to_ref} is not found"),
+                            )
+                        })?
+                        .snapshot_id;
+                    if fast_forward {
+                        if metadata.is_ancestor_of(target, head) {
+                            continue;
+                        }
+                        if !metadata.is_ancestor_of(head, target) {
+                            return Err(Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!(
+                                    "Can't fast forward branch {branch} to {to_ref}, which is not a descendant of the branch"
+                                ),
+                            ));
+                        }
+                    }
+                    if branch == MAIN_BRANCH {
+                        metadata.set_current_snapshot(target, now_ms)?;
+                    } else {
+                        ref_of_type(metadata, &branch, SnapshotReferenceType::Branch)?
+                            .snapshot_id = target;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the head snapshot id of a branch, fails if it's not found or
+/// is a tag.
+fn branch_head(metadata: &mut TableMetadata, branch: &str) -> Result<i64> {
+    // Tables written by old writers have no ref of the main branch.
+    if branch == MAIN_BRANCH && !metadata.refs.contains_key(MAIN_BRANCH) {
+        return metadata.current_snapshot_id.ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("branch {MAIN_BRANCH} is not found"),
+            )
+        });
+    }
+    Ok(ref_of_type(metadata, branch, SnapshotReferenceType::Branch)?.snapshot_id)
+}
+
+/// Returns the reference of given name, fails if it's not found or not of
+/// type `typ`.
+fn ref_of_type<'a>(
+    metadata: &'a mut TableMetadata,
+    name: &str,
+    typ: SnapshotReferenceType,
+) -> Result<&'a mut SnapshotReference> {
+    match metadata.refs.get_mut(name) {
+        Some(reference) if reference.typ == typ => Ok(reference),
+        Some(_) => Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Reference {name} is not a {typ}"),
+        )),
+        None => Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("{typ} {name} is not found"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::types::parse_table_metadata;
+    use crate::Table;
+
+    fn metadata() -> TableMetadata {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        parse_table_metadata(&fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_manage_snapshots() {
+        let mut metadata = metadata();
+        let snapshot_id = metadata.current_snapshot_id.unwrap();
+        ManageSnapshots::new()
+            .create_branch("b", snapshot_id)
+            .set_min_snapshots_to_keep("b", 2)
+            .set_max_snapshot_age_ms("b", 1000)
+            .create_tag("t", snapshot_id)
+            .set_max_ref_age_ms("t", 2000)
+            .apply(&mut metadata)
+            .unwrap();
+        let branch = &metadata.refs["b"];
+        assert!(branch.is_branch());
+        assert_eq!(branch.snapshot_id, snapshot_id);
+        assert_eq!(branch.min_snapshots_to_keep, Some(2));
+        assert_eq!(branch.max_snapshot_age_ms, Some(1000));
+        let tag = &metadata.refs["t"];
+        assert_eq!(tag.typ, SnapshotReferenceType::Tag);
+        assert_eq!(tag.max_ref_age_ms, Some(2000));
+
+        for update in [
+            ManageSnapshots::new().create_tag("b", snapshot_id),
+            ManageSnapshots::new().create_branch("c", snapshot_id + 1),
+            ManageSnapshots::new().set_min_snapshots_to_keep("t", 1),
+            ManageSnapshots::new().remove_branch("t"),
+            ManageSnapshots::new().remove_tag("x"),
+            ManageSnapshots::new().remove_branch(MAIN_BRANCH),
+        ] {
+            assert!(update.apply(&mut metadata.clone()).is_err());
+        }
+
+        ManageSnapshots::new()
+            .remove_branch("b")
+            .remove_tag("t")
+            .apply(&mut metadata)
+            .unwrap();
+        assert!(!metadata.refs.contains_key("b"));
+        assert!(!metadata.refs.contains_key("t"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_to_branch() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let main_snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.manage_snapshots(
+            ManageSnapshots::new()
+                .create_branch("audit", main_snapshot_id)
+                .create_tag("v1", main_snapshot_id),
+        );
+        tx.commit().await.unwrap();
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let mut append = table.new_append();
+        append.append_file(writer.close().await.unwrap());
+        append.to_branch("audit");
+        append.commit().await.unwrap();
+
+        // The main branch is not changed.
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_snapshot_id, Some(main_snapshot_id));
+        let branch_snapshot = metadata.snapshot_by_ref("audit").unwrap();
+        assert_eq!(branch_snapshot.parent_snapshot_id, Some(main_snapshot_id));
+        assert_eq!(
+            table
+                .scan()
+                .build()
+                .unwrap()
+                .plan_files()
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+        let scan = table.scan().use_ref("audit").build().unwrap();
+        assert_eq!(scan.snapshot_id(), Some(branch_snapshot.snapshot_id));
+        assert_eq!(scan.plan_files().await.unwrap().len(), 4);
+        let scan = table.scan().use_ref("v1").build().unwrap();
+        assert_eq!(scan.plan_files().await.unwrap().len(), 3);
+        assert!(table.scan().use_ref("unknown").build().is_err());
+
+        // Tags can't be committed to.
+        let mut tx = table.new_transaction();
+        tx.append_file(vec![]);
+        tx.to_branch("v1");
+        assert!(tx.commit().await.is_err());
+
+        // Commits to the main branch don't collide with the branch.
+        let mut tx = table.new_transaction();
+        tx.append_file(vec![]);
+        tx.commit().await.unwrap();
+        let metadata = table.current_table_metadata();
+        let snapshot = metadata.current_snapshot().unwrap();
+        assert_eq!(snapshot.parent_snapshot_id, Some(main_snapshot_id));
+        assert_ne!(
+            snapshot.snapshot_id,
+            metadata.snapshot_by_ref("audit").unwrap().snapshot_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fast_forward_and_replace_branch() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let base_snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+        let mut tx = table.new_transaction();
+        tx.manage_snapshots(
+            ManageSnapshots::new()
+                .create_branch("staging", base_snapshot_id)
+                .create_tag("base", base_snapshot_id),
+        );
+        tx.commit().await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.append_file(vec![]);
+        tx.to_branch("staging");
+        tx.commit().await.unwrap();
+        let head = |table: &Table, name: &str| {
+            table
+                .current_table_metadata()
+                .snapshot_by_ref(name)
+                .unwrap()
+                .snapshot_id
+        };
+        let staging_snapshot_id = head(&table, "staging");
+
+        // Promote the staging branch to main.
+        table.fast_forward(MAIN_BRANCH, "staging").await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_snapshot_id, Some(staging_snapshot_id));
+        assert_eq!(metadata.refs[MAIN_BRANCH].snapshot_id, staging_snapshot_id);
+        // Main is already ahead of the tag.
+        table.fast_forward(MAIN_BRANCH, "base").await.unwrap();
+        assert_eq!(head(&table, MAIN_BRANCH), staging_snapshot_id);
+
+        // Diverged branches can't be fast forwarded, but can be replaced.
+        let mut tx = table.new_transaction();
+        tx.manage_snapshots(ManageSnapshots::new().create_branch("old", base_snapshot_id));
+        tx.commit().await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.append_file(vec![]);
+        tx.to_branch("old");
+        tx.commit().await.unwrap();
+        assert!(table.fast_forward(MAIN_BRANCH, "old").await.is_err());
+        assert!(table.fast_forward(MAIN_BRANCH, "unknown").await.is_err());
+        assert!(table.fast_forward("base", MAIN_BRANCH).await.is_err());
+        let mut tx = table.new_transaction();
+        tx.manage_snapshots(ManageSnapshots::new().replace_branch(MAIN_BRANCH, "old"));
+        tx.commit().await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_snapshot_id, Some(head(&table, "old")));
+        assert_eq!(head(&table, MAIN_BRANCH), head(&table, "old"));
+        // Other branches are not changed.
+        assert_eq!(head(&table, "staging"), staging_snapshot_id);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_and_set_current_snapshot() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let base_snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+        let mut tx = table.new_transaction();
+        tx.append_file(vec![]);
+        tx.commit().await.unwrap();
+        let next_snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+
+        table.rollback_to(base_snapshot_id).await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_snapshot_id, Some(base_snapshot_id));
+        assert_eq!(metadata.refs[MAIN_BRANCH].snapshot_id, base_snapshot_id);
+        assert_eq!(
+            metadata
+                .snapshot_log
+                .as_ref()
+                .unwrap()
+                .last()
+                .unwrap()
+                .snapshot_id,
+            base_snapshot_id
+        );
+        let history = table.inspect().history().unwrap();
+        assert_eq!(
+            history.num_rows(),
+            metadata.snapshot_log.as_ref().unwrap().len()
+        );
+
+        // The rolled back snapshot is not an ancestor anymore.
+        assert!(table.rollback_to(next_snapshot_id).await.is_err());
+        assert!(table
+            .set_current_snapshot(next_snapshot_id + 1)
+            .await
+            .is_err());
+        table.set_current_snapshot(next_snapshot_id).await.unwrap();
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            Some(next_snapshot_id)
+        );
+    }
+}
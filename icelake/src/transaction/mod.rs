@@ -0,0 +1,2375 @@
+//! Transaction for manipulating table.
+
+use crate::error::Result;
+use crate::events::{CommitFailedEvent, FilesChangedEvent, SnapshotCommittedEvent};
+use crate::io::policy::IoKind;
+use crate::io::task_writer::partition_path;
+use crate::io::writer_config::parse_or;
+use crate::metrics::{CommitMetrics, CommitReport, MetricsReport};
+use crate::table_properties::{
+    AUTO_TAG_MAX_REF_AGE_MS, AUTO_TAG_NAME_PATTERN, COMMIT_MAX_RETRY_WAIT_MS,
+    COMMIT_MAX_RETRY_WAIT_MS_DEFAULT, COMMIT_MIN_RETRY_WAIT_MS, COMMIT_MIN_RETRY_WAIT_MS_DEFAULT,
+    COMMIT_NUM_RETRIES, COMMIT_NUM_RETRIES_DEFAULT, COMMIT_TOTAL_RETRY_TIME_MS,
+    COMMIT_TOTAL_RETRY_TIME_MS_DEFAULT, WRITE_PARTITION_SUMMARY_LIMIT,
+    WRITE_PARTITION_SUMMARY_LIMIT_DEFAULT, WRITE_WAP_ENABLED, WRITE_WAP_ENABLED_DEFAULT,
+};
+use crate::types::expression::{DataFileEvaluator, Predicate};
+use crate::types::{
+    DataContentType, DataFile, DataFileFormat, ManifestContentType, ManifestEntry, ManifestFile,
+    ManifestList, ManifestListEntry, ManifestListWriter, ManifestMetadata, ManifestStatus,
+    ManifestWriter, Snapshot, SnapshotReference, SnapshotReferenceType, StatisticsFile,
+    StructValue, TableFormatVersion, TableMetadata,
+};
+use crate::{Error, ErrorKind, Table};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{TimeZone, Utc};
+use opendal::Operator;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+mod update_schema;
+pub use update_schema::UpdateSchema;
+
+mod update_sort_order;
+pub use update_sort_order::UpdateSortOrder;
+
+mod update_properties;
+pub use update_properties::UpdateProperties;
+
+mod update_location;
+pub use update_location::UpdateLocation;
+
+mod manage_snapshots;
+pub use manage_snapshots::ManageSnapshots;
+
+mod cherry_pick;
+pub use cherry_pick::CherryPickSnapshot;
+
+mod row_delta;
+pub use row_delta::{IsolationLevel, RowDelta};
+
+mod validation;
+use validation::{DataFileSet, Validation};
+
+/// Operation of a transaction.
+#[derive(Clone)]
+enum Operation {
+    /// Append a new data file.
+    AppendDataFile(Box<DataFile>),
+    /// Delete a data file by its path.
+    DeleteDataFile(String),
+    /// Delete data files whose partition values match the predicate.
+    DeleteByFilter(Predicate),
+    /// Replace data files by new data files with the same rows.
+    RewriteDataFiles {
+        deleted: Vec<String>,
+        added: Vec<DataFile>,
+    },
+    /// Merge small data manifests.
+    RewriteManifests { target_size_bytes: u64 },
+    /// Set and remove table properties.
+    UpdateProperties(UpdateProperties),
+    /// Move the table location.
+    UpdateLocation(UpdateLocation),
+    /// Upgrade table format version.
+    UpgradeFormatVersion(TableFormatVersion),
+    /// Evolve the current schema.
+    UpdateSchema(UpdateSchema),
+    /// Replace the default sort order.
+    UpdateSortOrder(UpdateSortOrder),
+    /// Update branches and tags.
+    ManageSnapshots(ManageSnapshots),
+    /// Set the statistics file of a snapshot.
+    SetStatistics(StatisticsFile),
+    /// Remove the statistics file of a snapshot.
+    RemoveStatistics(i64),
+}
+
+/// A snapshot committed by a transaction, with files it added and deleted.
+struct CommittedSnapshot {
+    snapshot: Snapshot,
+    /// The branch updated to the snapshot, `None` if it's only staged.
+    branch: Option<String>,
+    added: Vec<DataFile>,
+    deleted: Vec<DataFile>,
+}
+
+/// Keys of snapshot summary.
+const OPERATION: &str = "operation";
+const ADDED_DATA_FILES: &str = "added-data-files";
+const ADDED_RECORDS: &str = "added-records";
+const ADDED_FILES_SIZE: &str = "added-files-size";
+const DELETED_DATA_FILES: &str = "deleted-data-files";
+const DELETED_RECORDS: &str = "deleted-records";
+const REMOVED_FILES_SIZE: &str = "removed-files-size";
+const TOTAL_DATA_FILES: &str = "total-data-files";
+const TOTAL_RECORDS: &str = "total-records";
+const TOTAL_FILES_SIZE: &str = "total-files-size";
+const ADDED_DELETE_FILES: &str = "added-delete-files";
+const ADDED_POSITION_DELETE_FILES: &str = "added-position-delete-files";
+const ADDED_POSITION_DELETES: &str = "added-position-deletes";
+const ADDED_EQUALITY_DELETE_FILES: &str = "added-equality-delete-files";
+const ADDED_EQUALITY_DELETES: &str = "added-equality-deletes";
+const TOTAL_DELETE_FILES: &str = "total-delete-files";
+const TOTAL_POSITION_DELETES: &str = "total-position-deletes";
+const TOTAL_EQUALITY_DELETES: &str = "total-equality-deletes";
+const REMOVED_DELETE_FILES: &str = "removed-delete-files";
+const REMOVED_POSITION_DELETE_FILES: &str = "removed-position-delete-files";
+const REMOVED_POSITION_DELETES: &str = "removed-position-deletes";
+const REMOVED_EQUALITY_DELETE_FILES: &str = "removed-equality-delete-files";
+const REMOVED_EQUALITY_DELETES: &str = "removed-equality-deletes";
+const CHANGED_PARTITION_COUNT: &str = "changed-partition-count";
+const PARTITION_SUMMARIES_INCLUDED: &str = "partition-summaries-included";
+const CHANGED_PARTITION_PREFIX: &str = "partitions.";
+pub(crate) const MANIFESTS_CREATED: &str = "manifests-created";
+pub(crate) const MANIFESTS_REPLACED: &str = "manifests-replaced";
+pub(crate) const MANIFESTS_KEPT: &str = "manifests-kept";
+pub(crate) const WAP_ID: &str = "wap.id";
+pub(crate) const PUBLISHED_WAP_ID: &str = "published-wap-id";
+pub(crate) const SOURCE_SNAPSHOT_ID: &str = "source-snapshot-id";
+pub(crate) const CHECKPOINT_ID: &str = "icelake.checkpoint-id";
+
+struct CommitContext {
+    // Uuid of this transaction
+    uuid: Uuid,
+    // Number of manifest files
+    manifest_num: u32,
+    // Attemp num
+    attempt: u32,
+
+    // Operator to write manifests and manifest lists
+    io: Operator,
+    // Metadata location relative to `io`
+    metadata_rel_location: String,
+    // Metadata location recorded in manifest lists and snapshots
+    metadata_location: String,
+}
+
+/// A transaction manipulate iceberg table.
+///
+/// Operations are collected until [`Transaction::commit`], which commits
+/// all of them atomically in a single new metadata version. File
+/// operations produce a single new snapshot.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// # async fn example(data_files: Vec<icelake::types::DataFile>) -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut tx = table.new_transaction();
+/// tx.append_file(data_files);
+/// tx.set_properties(HashMap::from([("owner".to_string(), "icelake".to_string())]));
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Transaction<'a> {
+    table: &'a mut Table,
+
+    // Transaction operations
+    ops: Vec<Operation>,
+    // Overrides table property `icelake.auto-tag.*`
+    auto_tag: Option<AutoTag>,
+    // Branch new snapshot is committed to, default to the main branch
+    branch: Option<String>,
+    // Extra properties recorded in summary of new snapshot
+    snapshot_properties: HashMap<String, String>,
+    // Whether new snapshot is staged instead of committed to a branch
+    stage_only: bool,
+    // Rules checked against snapshots committed since the base snapshot
+    validations: Vec<Validation>,
+    // Base snapshot of validations, default to the branch head when commit
+    validate_from_snapshot_id: Option<i64>,
+}
+
+/// AppendFiles appends data files, like those produced by
+/// [`crate::io::task_writer::TaskWriter`], to a table as a new `append`
+/// snapshot.
+///
+/// It writes a manifest of appended files and a manifest list including
+/// existing manifests, then commits new table metadata pointing to the new
+/// snapshot.
+pub struct AppendFiles<'a> {
+    tx: Transaction<'a>,
+}
+
+impl<'a> AppendFiles<'a> {
+    /// Create a new append action.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            tx: Transaction::new(table),
+        }
+    }
+
+    /// Append data files.
+    pub fn append_file(&mut self, data_file: impl IntoIterator<Item = DataFile>) {
+        self.tx.append_file(data_file);
+    }
+
+    /// Commit to the branch instead of the main branch, see
+    /// [`Transaction::to_branch`].
+    pub fn to_branch(&mut self, branch: impl Into<String>) {
+        self.tx.to_branch(branch);
+    }
+
+    /// Validate changes since the snapshot instead of the branch head when
+    /// committing, see [`Transaction::validate_from_snapshot`].
+    pub fn validate_from_snapshot(&mut self, snapshot_id: i64) {
+        self.tx.validate_from_snapshot(snapshot_id);
+    }
+
+    /// Fail the commit if deletes matching the filter are committed
+    /// concurrently, see [`Transaction::validate_no_conflicting_deletes`].
+    pub fn validate_no_conflicting_deletes(&mut self, filter: Predicate) {
+        self.tx.validate_no_conflicting_deletes(filter);
+    }
+
+    /// Commit appended files as a new snapshot.
+    pub async fn commit(self) -> Result<()> {
+        self.tx.commit().await
+    }
+}
+
+/// OverwriteFiles replaces data files matching a partition predicate with
+/// new data files in a single `overwrite` snapshot.
+///
+/// Overwriting a partition again with the same files gives the same table
+/// content, so batch jobs can safely retry it. Partitions matching nothing
+/// are not an error.
+pub struct OverwriteFiles<'a> {
+    tx: Transaction<'a>,
+    validate_no_conflicting_data: bool,
+    isolation_level: Option<IsolationLevel>,
+}
+
+impl<'a> OverwriteFiles<'a> {
+    /// Create a new overwrite action.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            tx: Transaction::new(table),
+            validate_no_conflicting_data: false,
+            isolation_level: None,
+        }
+    }
+
+    /// Delete data files matching the predicate, see
+    /// [`Transaction::delete_by_filter`].
+    pub fn overwrite_by_filter(&mut self, predicate: Predicate) {
+        self.tx.delete_by_filter(predicate);
+    }
+
+    /// Append data files.
+    pub fn append_file(&mut self, data_file: impl IntoIterator<Item = DataFile>) {
+        self.tx.append_file(data_file);
+    }
+
+    /// Commit to the branch instead of the main branch, see
+    /// [`Transaction::to_branch`].
+    pub fn to_branch(&mut self, branch: impl Into<String>) {
+        self.tx.to_branch(branch);
+    }
+
+    /// Validate changes since the snapshot instead of the branch head when
+    /// committing, see [`Transaction::validate_from_snapshot`].
+    pub fn validate_from_snapshot(&mut self, snapshot_id: i64) {
+        self.tx.validate_from_snapshot(snapshot_id);
+    }
+
+    /// Fail the commit if data files matching the overwrite filters are
+    /// added concurrently, which would be kept by the overwrite. It's
+    /// required for serializable isolation.
+    pub fn validate_no_conflicting_data(&mut self) {
+        self.validate_no_conflicting_data = true;
+    }
+
+    /// Fail the commit if deletes matching the filter are committed
+    /// concurrently, see [`Transaction::validate_no_conflicting_deletes`].
+    pub fn validate_no_conflicting_deletes(&mut self, filter: Predicate) {
+        self.tx.validate_no_conflicting_deletes(filter);
+    }
+
+    /// Validate changes matching the overwrite filters by the isolation
+    /// level, see [`IsolationLevel`]. No changes are validated by default.
+    pub fn isolation_level(&mut self, isolation_level: IsolationLevel) {
+        self.isolation_level = Some(isolation_level);
+    }
+
+    /// Commit deleted and appended files as a new snapshot.
+    pub async fn commit(mut self) -> Result<()> {
+        if self.validate_no_conflicting_data || self.isolation_level.is_some() {
+            let filters: Vec<_> = self
+                .tx
+                .ops
+                .iter()
+                .filter_map(|op| match op {
+                    Operation::DeleteByFilter(predicate) => Some(predicate.clone()),
+                    _ => None,
+                })
+                .collect();
+            for filter in filters {
+                match self.isolation_level {
+                    Some(isolation_level) => isolation_level.validate(&mut self.tx, filter),
+                    None => self.tx.validate_no_conflicting_data(filter),
+                }
+            }
+        }
+        self.tx.commit().await
+    }
+}
+
+/// Tag created automatically for committed snapshot.
+#[derive(Clone)]
+struct AutoTag {
+    name_pattern: String,
+    max_ref_age_ms: Option<i64>,
+}
+
+impl<'a> Transaction<'a> {
+    /// Create a new transaction.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            table,
+            ops: vec![],
+            auto_tag: None,
+            branch: None,
+            snapshot_properties: HashMap::new(),
+            stage_only: false,
+            validations: vec![],
+            validate_from_snapshot_id: None,
+        }
+    }
+
+    /// Record a property in summary of the new snapshot, like `wap.id`.
+    pub fn set_snapshot_property(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.snapshot_properties.insert(key.into(), value.into());
+    }
+
+    /// Record the write-audit-publish id of the new snapshot.
+    ///
+    /// The snapshot is staged if table property `write.wap.enabled` is
+    /// true, see [`Transaction::stage_only`].
+    pub fn set_wap_id(&mut self, wap_id: impl Into<String>) {
+        self.set_snapshot_property(WAP_ID, wap_id);
+    }
+
+    /// Stage the new snapshot instead of committing it to a branch.
+    ///
+    /// A staged snapshot is added to table metadata but never becomes
+    /// current, and it's not read unless scanned by id. It can be published
+    /// later by [`CherryPickSnapshot`].
+    pub fn stage_only(&mut self) {
+        self.stage_only = true;
+    }
+
+    /// Commit the new snapshot to the branch instead of the default branch
+    /// of the table, see [`crate::TableBuilder::with_default_branch`].
+    ///
+    /// File operations apply to the head of the branch, and the current
+    /// snapshot of the table is not changed. The branch is created if not
+    /// exists, starting from an empty table.
+    pub fn to_branch(&mut self, branch: impl Into<String>) {
+        self.branch = Some(branch.into());
+    }
+
+    /// Validate changes committed since the snapshot, instead of the head
+    /// of the branch when the transaction is committed, for example, the
+    /// snapshot the transaction has read.
+    pub fn validate_from_snapshot(&mut self, snapshot_id: i64) {
+        self.validate_from_snapshot_id = Some(snapshot_id);
+    }
+
+    /// Fail the commit if data files that might match the filter are added
+    /// by snapshots committed since the base snapshot, see
+    /// [`Transaction::validate_from_snapshot`].
+    ///
+    /// Commits failing validations are not retried, with errors of
+    /// [`ErrorKind::ValidationFailed`].
+    pub fn validate_no_conflicting_data(&mut self, filter: Predicate) {
+        self.validations.push(Validation::NoConflictingData(filter));
+    }
+
+    /// Fail the commit if delete files that might match the filter are
+    /// added, or data files that might match the filter are deleted, by
+    /// snapshots committed since the base snapshot, see
+    /// [`Transaction::validate_no_conflicting_data`].
+    pub fn validate_no_conflicting_deletes(&mut self, filter: Predicate) {
+        self.validations
+            .push(Validation::NoConflictingDeletes(filter));
+    }
+
+    /// Tag the committed snapshot by name formatted from `name_pattern`,
+    /// like `audit-%Y-%m-%d`.
+    ///
+    /// It overrides table property
+    /// [`crate::table_properties::AUTO_TAG_NAME_PATTERN`], see it for
+    /// details.
+    pub fn auto_tag(&mut self, name_pattern: impl Into<String>, max_ref_age_ms: Option<i64>) {
+        self.auto_tag = Some(AutoTag {
+            name_pattern: name_pattern.into(),
+            max_ref_age_ms,
+        });
+    }
+
+    /// Append a new data file.
+    pub fn append_file(&mut self, data_file: impl IntoIterator<Item = DataFile>) {
+        self.ops.extend(
+            data_file
+                .into_iter()
+                .map(|v| Operation::AppendDataFile(Box::new(v))),
+        );
+    }
+
+    /// Delete data files or delete files, files are matched by their paths.
+    ///
+    /// Commit fails if a file is not a live file of the current snapshot.
+    pub fn delete_file(&mut self, data_file: impl IntoIterator<Item = DataFile>) {
+        self.ops.extend(
+            data_file
+                .into_iter()
+                .map(|v| Operation::DeleteDataFile(v.file_path)),
+        );
+    }
+
+    /// Delete live data files whose partition values match the predicate.
+    ///
+    /// Files are deleted if all their rows match by the strict projection
+    /// of the predicate on partition values, and kept if no row might match
+    /// by partition values and column metrics. Commit fails if a file might
+    /// only partly match, since rows are never rewritten.
+    pub fn delete_by_filter(&mut self, predicate: Predicate) {
+        self.ops.push(Operation::DeleteByFilter(predicate));
+    }
+
+    /// Replace data files by new data files containing the same rows, like
+    /// compaction does. Deleted files are matched by their paths, which may
+    /// include delete files applied to the new data files.
+    ///
+    /// The snapshot is a `replace` snapshot if there are no other file
+    /// operations, which is skipped by incremental reads.
+    ///
+    /// Commit fails if delete files that might apply to the deleted data
+    /// files are added since the base snapshot, see
+    /// [`Transaction::validate_from_snapshot`], since their deletes would be
+    /// lost by the new data files. It also fails if deleted delete files are
+    /// removed since the base snapshot, for example, by a concurrent
+    /// compaction applying them, since the new delete files would apply
+    /// their deletes again.
+    pub fn rewrite_files(
+        &mut self,
+        deleted: impl IntoIterator<Item = DataFile>,
+        added: impl IntoIterator<Item = DataFile>,
+    ) {
+        let deleted: Vec<DataFile> = deleted.into_iter().collect();
+        let data_files: DataFileSet = deleted
+            .iter()
+            .filter(|v| v.content == DataContentType::Data)
+            .collect();
+        if !data_files.is_empty() {
+            self.validations
+                .push(Validation::ReplacedDataFiles(data_files));
+        }
+        let delete_files: HashSet<String> = deleted
+            .iter()
+            .filter(|v| v.content != DataContentType::Data)
+            .map(|v| v.file_path.clone())
+            .collect();
+        if !delete_files.is_empty() {
+            self.validations
+                .push(Validation::RewrittenDeleteFiles(delete_files));
+        }
+        self.ops.push(Operation::RewriteDataFiles {
+            deleted: deleted.into_iter().map(|v| v.file_path).collect(),
+            added: added.into_iter().collect(),
+        });
+    }
+
+    /// Merge small data manifests of each partition spec into manifests of
+    /// about `target_size_bytes`, without changing table data.
+    ///
+    /// Like [`Transaction::rewrite_files`], the snapshot is a `replace`
+    /// snapshot if there are no other file operations.
+    pub fn rewrite_manifests(&mut self, target_size_bytes: u64) {
+        self.ops
+            .push(Operation::RewriteManifests { target_size_bytes });
+    }
+
+    /// Upgrade the table to a newer format version, like from v1 to v2.
+    ///
+    /// Only metadata is updated, existing manifests of the old version are
+    /// still readable. Downgrading fails on commit.
+    pub fn upgrade_format_version(&mut self, format_version: TableFormatVersion) {
+        self.ops
+            .push(Operation::UpgradeFormatVersion(format_version));
+    }
+
+    /// Set table properties, existing values are overwritten.
+    pub fn set_properties(&mut self, properties: HashMap<String, String>) {
+        self.update_properties(
+            properties
+                .into_iter()
+                .fold(UpdateProperties::new(), |update, (k, v)| update.set(k, v)),
+        );
+    }
+
+    /// Remove table properties, keys not set are ignored.
+    pub fn remove_properties(&mut self, keys: impl IntoIterator<Item = impl Into<String>>) {
+        self.update_properties(
+            keys.into_iter()
+                .fold(UpdateProperties::new(), |update, key| update.remove(key)),
+        );
+    }
+
+    /// Set and remove table properties, see [`UpdateProperties`].
+    pub fn update_properties(&mut self, update: UpdateProperties) {
+        self.ops.push(Operation::UpdateProperties(update));
+    }
+
+    /// Move the location of the table, see [`UpdateLocation`].
+    pub fn update_location(&mut self, update: UpdateLocation) {
+        self.ops.push(Operation::UpdateLocation(update));
+    }
+
+    /// Update the current schema of the table, see [`UpdateSchema`].
+    pub fn update_schema(&mut self, update: UpdateSchema) {
+        self.ops.push(Operation::UpdateSchema(update));
+    }
+
+    /// Replace the default sort order of the table, see [`UpdateSortOrder`].
+    pub fn update_sort_order(&mut self, update: UpdateSortOrder) {
+        self.ops.push(Operation::UpdateSortOrder(update));
+    }
+
+    /// Update branches and tags of the table, see [`ManageSnapshots`].
+    pub fn manage_snapshots(&mut self, update: ManageSnapshots) {
+        self.ops.push(Operation::ManageSnapshots(update));
+    }
+
+    /// Set the statistics file of a snapshot, which replaces the existing
+    /// one of the snapshot. See [`crate::types::PuffinWriter`] to write
+    /// statistics files.
+    pub fn set_statistics(&mut self, statistics_file: StatisticsFile) {
+        self.ops.push(Operation::SetStatistics(statistics_file));
+    }
+
+    /// Remove the statistics file of a snapshot, the file itself is not
+    /// deleted.
+    pub fn remove_statistics(&mut self, snapshot_id: i64) {
+        self.ops.push(Operation::RemoveStatistics(snapshot_id));
+    }
+
+    /// Commit this transaction.
+    ///
+    /// A new snapshot is created unless the transaction only updates
+    /// properties, schema, sort order, refs or statistics. Operations are
+    /// applied in the order they were added.
+    ///
+    /// If the table is updated concurrently, the operations are applied
+    /// again on top of the refreshed table and the commit is retried, as
+    /// configured by table properties `commit.retry.*`.
+    pub async fn commit(mut self) -> Result<()> {
+        let start = Instant::now();
+        let mut attempts = 0;
+        let table_location = self.table.current_table_metadata().location.clone();
+        let committed = match self.commit_with_retry(start, &mut attempts).await {
+            Ok(committed) => committed,
+            Err(error) => {
+                self.table.notify(|listener| {
+                    listener.on_commit_failed(&CommitFailedEvent {
+                        table_location: &table_location,
+                        error: &error,
+                        attempts,
+                    })
+                });
+                return Err(error);
+            }
+        };
+
+        let Some(CommittedSnapshot {
+            snapshot,
+            branch,
+            added,
+            deleted,
+        }) = committed
+        else {
+            return Ok(());
+        };
+        let table = &*self.table;
+        let table_location = &table.current_table_metadata().location;
+        let files_changed = |files| FilesChangedEvent {
+            table_location,
+            snapshot_id: snapshot.snapshot_id,
+            files,
+        };
+        table.notify(|listener| {
+            listener.on_snapshot_committed(&SnapshotCommittedEvent {
+                table_location,
+                snapshot: &snapshot,
+                branch: branch.as_deref(),
+            });
+            if !added.is_empty() {
+                listener.on_files_added(&files_changed(&added));
+            }
+            if !deleted.is_empty() {
+                listener.on_files_removed(&files_changed(&deleted));
+            }
+        });
+        table.report(MetricsReport::Commit(CommitReport {
+            table_location: table_location.clone(),
+            snapshot_id: snapshot.snapshot_id,
+            sequence_number: snapshot.sequence_number,
+            operation: snapshot.summary.get(OPERATION).cloned().unwrap_or_default(),
+            metrics: CommitMetrics::new(start.elapsed(), attempts, &added, &deleted),
+        }));
+        Ok(())
+    }
+
+    /// Commit with retries of conflicts since `start`, `attempts` counts
+    /// attempts made.
+    async fn commit_with_retry(
+        &mut self,
+        start: Instant,
+        attempts: &mut u32,
+    ) -> Result<Option<CommittedSnapshot>> {
+        self.table.check_writable()?;
+        let retry = CommitRetry::from_properties(&self.table.properties())?;
+        // Changes are validated since the branch head of the first attempt.
+        let base_snapshot_id = self.validate_from_snapshot_id.or_else(|| {
+            self.table
+                .current_table_metadata()
+                .snapshot_by_ref(&self.target_branch())
+                .map(|v| v.snapshot_id)
+        });
+        let mut attempt = 0;
+        loop {
+            *attempts += 1;
+            match self.commit_once(base_snapshot_id).await {
+                Err(err) if err.kind() == ErrorKind::CommitConflict => {
+                    let Some(wait) = retry.next_wait(attempt, start.elapsed()) else {
+                        return Err(err);
+                    };
+                    attempt += 1;
+                    log::warn!("Retrying commit in {wait:?}, attempt {attempt}: {err}");
+                    tokio::time::sleep(wait).await;
+                    self.table.refresh().await?;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Branch the new snapshot is committed to.
+    fn target_branch(&self) -> String {
+        self.branch
+            .clone()
+            .unwrap_or_else(|| self.table.default_branch().to_string())
+    }
+
+    /// Commit once, returns the new snapshot if one is committed.
+    async fn commit_once(
+        &mut self,
+        base_snapshot_id: Option<i64>,
+    ) -> Result<Option<CommittedSnapshot>> {
+        let branch = self.target_branch();
+        validation::validate(self.table, &branch, base_snapshot_id, &self.validations).await?;
+        let table = &mut *self.table;
+        let staged = self.stage_only
+            || (self.snapshot_properties.contains_key(WAP_ID) && wap_enabled(&table.properties())?);
+        let auto_tag = match self.auto_tag.clone() {
+            Some(auto_tag) => Some(auto_tag),
+            None => AutoTag::from_properties(&table.properties())?,
+        };
+        let metadata_location = table.metadata_location();
+        let (io, metadata_rel_location) = table.io_operator(IoKind::Commit, &metadata_location)?;
+        let commit_ctx = CommitContext {
+            uuid: Uuid::new_v4(),
+            manifest_num: 0,
+            attempt: 0,
+            io,
+            metadata_rel_location: metadata_rel_location.trim_matches('/').to_string(),
+            metadata_location: table.recorded_location(&metadata_location)?,
+        };
+
+        let mut appends = vec![];
+        let mut deletes = HashSet::new();
+        let mut filters = vec![];
+        let mut property_ops = vec![];
+        let mut manifest_target_size = None;
+        // Whether all file operations are rewrites.
+        let mut replace = None;
+        for op in self.ops.iter().cloned() {
+            match op {
+                Operation::AppendDataFile(data_file) => {
+                    appends.push(*data_file);
+                    replace = Some(false);
+                }
+                Operation::DeleteDataFile(path) => {
+                    deletes.insert(path);
+                    replace = Some(false);
+                }
+                Operation::DeleteByFilter(predicate) => {
+                    filters.push(predicate);
+                    replace = Some(false);
+                }
+                Operation::RewriteDataFiles { deleted, added } => {
+                    deletes.extend(deleted);
+                    appends.extend(added);
+                    replace.get_or_insert(true);
+                }
+                Operation::RewriteManifests { target_size_bytes } => {
+                    manifest_target_size = Some(target_size_bytes);
+                    replace.get_or_insert(true);
+                }
+                op => property_ops.push(op),
+            }
+        }
+
+        let mut new_metadata = table.current_table_metadata().clone();
+        let mut committed = None;
+        if replace.is_some() || property_ops.is_empty() {
+            if new_metadata.format_version == TableFormatVersion::V1 {
+                return Err(Error::new(
+                    ErrorKind::IcebergFeatureUnsupported,
+                    "Writing v1 tables, upgrade the table to v2 by a separate transaction first",
+                ));
+            }
+            let operation = if replace == Some(true) {
+                Some("replace")
+            } else if !filters.is_empty() {
+                Some("overwrite")
+            } else {
+                None
+            };
+            let added = appends.clone();
+            let (mut new_snapshot, deleted) = Transaction::produce_new_snapshot(
+                commit_ctx,
+                appends,
+                deletes,
+                filters,
+                manifest_target_size,
+                operation,
+                &branch,
+                table,
+            )
+            .await?;
+            new_snapshot
+                .summary
+                .extend(self.snapshot_properties.clone());
+            let (snapshot_id, timestamp_ms) = (new_snapshot.snapshot_id, new_snapshot.timestamp_ms);
+            committed = Some(CommittedSnapshot {
+                snapshot: new_snapshot.clone(),
+                branch: (!staged).then(|| branch.clone()),
+                added,
+                deleted,
+            });
+            if staged {
+                new_metadata.stage_snapshot(new_snapshot)?;
+            } else {
+                new_metadata.append_snapshot_to_branch(new_snapshot, &branch)?;
+                if let Some(auto_tag) = auto_tag {
+                    auto_tag.apply(&mut new_metadata, snapshot_id, timestamp_ms)?;
+                }
+            }
+        }
+        if !property_ops.is_empty() {
+            for op in property_ops {
+                match op {
+                    Operation::UpdateProperties(update) => {
+                        update.apply(new_metadata.properties.get_or_insert_with(HashMap::new))?
+                    }
+                    Operation::UpdateLocation(update) => update.apply(&mut new_metadata)?,
+                    Operation::UpgradeFormatVersion(v) => {
+                        if (v as u8) < (new_metadata.format_version as u8) {
+                            return Err(Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!(
+                                    "Can't downgrade table format version from {} to {v}",
+                                    new_metadata.format_version
+                                ),
+                            ));
+                        }
+                        new_metadata.format_version = v;
+                    }
+                    Operation::UpdateSchema(update) => {
+                        let (schema, last_column_id) = update.apply(&new_metadata)?;
+                        new_metadata.last_column_id = last_column_id;
+                        new_metadata.current_schema_id = schema.schema_id;
+                        if !new_metadata
+                            .schemas
+                            .iter()
+                            .any(|v| v.schema_id == schema.schema_id)
+                        {
+                            new_metadata.schemas.push(schema);
+                        }
+                    }
+                    Operation::UpdateSortOrder(update) => {
+                        let sort_order = update.apply(&new_metadata)?;
+                        new_metadata.default_sort_order_id = sort_order.order_id;
+                        if !new_metadata
+                            .sort_orders
+                            .iter()
+                            .any(|v| v.order_id == sort_order.order_id)
+                        {
+                            new_metadata.sort_orders.push(sort_order);
+                        }
+                    }
+                    Operation::ManageSnapshots(update) => update.apply(&mut new_metadata)?,
+                    Operation::SetStatistics(statistics_file) => {
+                        if new_metadata.snapshot(statistics_file.snapshot_id).is_none() {
+                            return Err(Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!(
+                                    "Snapshot {} of statistics file is not found",
+                                    statistics_file.snapshot_id
+                                ),
+                            ));
+                        }
+                        new_metadata
+                            .statistics
+                            .retain(|v| v.snapshot_id != statistics_file.snapshot_id);
+                        new_metadata.statistics.push(statistics_file);
+                    }
+                    Operation::RemoveStatistics(snapshot_id) => new_metadata
+                        .statistics
+                        .retain(|v| v.snapshot_id != snapshot_id),
+                    _ => unreachable!("file operations are handled above"),
+                }
+            }
+            new_metadata.last_updated_ms =
+                SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        }
+
+        // Save new metadata
+        table.commit(new_metadata).await?;
+
+        Ok(committed)
+    }
+
+    fn next_manifest_filename(ctx: &mut CommitContext) -> String {
+        ctx.manifest_num += 1;
+        format!(
+            "{}-m{}.{}",
+            &ctx.uuid,
+            ctx.manifest_num,
+            DataFileFormat::Avro
+        )
+    }
+
+    fn manifest_list_filename(ctx: &mut CommitContext, snapshot_id: i64) -> String {
+        ctx.attempt += 1;
+        format!(
+            "snap-{}-{}-{}.{}",
+            snapshot_id,
+            ctx.attempt,
+            &ctx.uuid,
+            DataFileFormat::Avro
+        )
+    }
+
+    /// Returns the path relative to `ctx.io` and the recorded location of a
+    /// file under metadata location.
+    fn metadata_file_location(ctx: &CommitContext, filename: &str) -> (String, String) {
+        let path = if ctx.metadata_rel_location.is_empty() {
+            filename.to_string()
+        } else {
+            format!("{}/{filename}", ctx.metadata_rel_location)
+        };
+        (path, format!("{}/{filename}", ctx.metadata_location))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn produce_new_snapshot(
+        mut ctx: CommitContext,
+        appends: Vec<DataFile>,
+        deletes: HashSet<String>,
+        filters: Vec<Predicate>,
+        manifest_target_size: Option<u64>,
+        operation: Option<&str>,
+        branch: &str,
+        table: &Table,
+    ) -> Result<(Snapshot, Vec<DataFile>)> {
+        let cur_metadata = table.current_table_metadata();
+        let cur_snapshot = cur_metadata.snapshot_by_ref(branch);
+        if cur_snapshot.is_none() && cur_metadata.refs.contains_key(branch) {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Snapshot of branch {branch} is not found"),
+            ));
+        }
+        let cur_snapshot_id = cur_snapshot.map(|v| v.snapshot_id).unwrap_or(0);
+        // Snapshots of other branches may be newer than the parent.
+        let next_snapshot_id = cur_metadata
+            .snapshots
+            .iter()
+            .flatten()
+            .map(|v| v.snapshot_id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let next_seq_number = cur_metadata.last_sequence_number + 1;
+
+        // Load existing manifest list
+        let mut manifest_list = match cur_snapshot {
+            Some(snapshot) => snapshot.load_manifest_list(table).await?,
+            None => ManifestList { entries: vec![] },
+        };
+
+        // Filters are bound to each partition spec.
+        let mut evaluators: HashMap<i32, Vec<DataFileEvaluator>> = HashMap::new();
+        for partition_spec in &cur_metadata.partition_specs {
+            let v = filters
+                .iter()
+                .map(|filter| {
+                    DataFileEvaluator::try_new_with_case_sensitive(
+                        filter,
+                        cur_metadata.current_schema()?,
+                        partition_spec,
+                        table.case_sensitive(),
+                    )
+                })
+                .collect::<Result<_>>()?;
+            evaluators.insert(partition_spec.spec_id, v);
+        }
+
+        let mut deleted = vec![];
+        if !deletes.is_empty() || !filters.is_empty() {
+            let mut entries = Vec::with_capacity(manifest_list.entries.len());
+            for manifest_list_entry in manifest_list.entries {
+                // Delete files are only deleted by paths.
+                if manifest_list_entry.content != ManifestContentType::Data && deletes.is_empty() {
+                    entries.push(manifest_list_entry);
+                    continue;
+                }
+                let spec_evaluators = evaluators
+                    .get(&manifest_list_entry.partition_spec_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                // Files are deleted if all rows match by partition values,
+                // and kept if no row might match.
+                let should_delete = |data_file: &DataFile| {
+                    if deletes.contains(&data_file.file_path) {
+                        return Ok(true);
+                    }
+                    if data_file.content != DataContentType::Data {
+                        return Ok(false);
+                    }
+                    if spec_evaluators
+                        .iter()
+                        .any(|v| v.partition_matches(data_file))
+                    {
+                        return Ok(true);
+                    }
+                    if spec_evaluators.iter().any(|v| v.might_match(data_file)) {
+                        return Err(Error::new(
+                            ErrorKind::IcebergFeatureUnsupported,
+                            "Deleting by filter matching only some rows of a data file",
+                        )
+                        .with_context("file_path", &data_file.file_path)
+                        .with_context(
+                            "partition_spec_id",
+                            manifest_list_entry.partition_spec_id.to_string(),
+                        ));
+                    }
+                    Ok(false)
+                };
+                let rewritten = Transaction::rewrite_manifest_with_deletes(
+                    &mut ctx,
+                    table,
+                    &manifest_list_entry,
+                    &should_delete,
+                    &mut deleted,
+                    next_snapshot_id,
+                    next_seq_number,
+                )
+                .await?;
+                entries.push(rewritten.unwrap_or(manifest_list_entry));
+            }
+            let deleted_paths: HashSet<&String> = deleted.iter().map(|v| &v.file_path).collect();
+            if let Some(path) = deletes.iter().find(|v| !deleted_paths.contains(v)) {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("data file {path} to delete is not found in table"),
+                ));
+            }
+            manifest_list.entries = entries;
+        }
+
+        let partition_limit = parse_or(
+            &table.properties(),
+            WRITE_PARTITION_SUMMARY_LIMIT,
+            WRITE_PARTITION_SUMMARY_LIMIT_DEFAULT,
+        )?;
+        let mut summary =
+            snapshot_summary(cur_snapshot, &appends, &deleted, operation, partition_limit);
+        if let Some(target_size) = manifest_target_size {
+            let (mut kept, bins) = bin_pack_manifests(manifest_list.entries, target_size);
+            summary.insert(MANIFESTS_KEPT.to_string(), kept.len().to_string());
+            summary.insert(
+                MANIFESTS_REPLACED.to_string(),
+                bins.iter().map(|v| v.len()).sum::<usize>().to_string(),
+            );
+            summary.insert(MANIFESTS_CREATED.to_string(), bins.len().to_string());
+            for bin in bins {
+                kept.push(
+                    Transaction::merge_manifests(
+                        &mut ctx,
+                        table,
+                        &bin,
+                        next_snapshot_id,
+                        next_seq_number,
+                    )
+                    .await?,
+                );
+            }
+            manifest_list.entries = kept;
+        }
+        // Delete files are added into delete manifests.
+        let (data_files, delete_files): (Vec<_>, Vec<_>) = appends
+            .into_iter()
+            .partition(|v| v.content == DataContentType::Data);
+        for (content, files) in [
+            (ManifestContentType::Data, data_files),
+            (ManifestContentType::Deletes, delete_files),
+        ] {
+            if files.is_empty() {
+                continue;
+            }
+            let manifest_entries = files
+                .into_iter()
+                .map(|data_file| ManifestEntry {
+                    status: ManifestStatus::Added,
+                    snapshot_id: Some(next_snapshot_id),
+                    sequence_number: Some(next_seq_number),
+                    file_sequence_number: Some(next_seq_number),
+                    data_file,
+                })
+                .collect();
+
+            // Writing manifest file
+            let manifest_filename = Transaction::next_manifest_filename(&mut ctx);
+            let (manifest_path, manifest_location) =
+                Transaction::metadata_file_location(&ctx, &manifest_filename);
+            let writer = ManifestWriter::new(
+                cur_metadata.current_partition_spec()?.clone(),
+                ctx.io.clone(),
+                manifest_path,
+                manifest_location,
+                next_snapshot_id,
+                next_seq_number,
+            );
+            let manifest_file = ManifestFile {
+                metadata: ManifestMetadata {
+                    schema: cur_metadata.current_schema()?.clone(),
+                    schema_id: cur_metadata.current_schema_id,
+                    partition_spec_id: cur_metadata.default_spec_id,
+                    format_version: Some(cur_metadata.format_version),
+                    content,
+                },
+                entries: manifest_entries,
+            };
+            manifest_list
+                .entries
+                .push(writer.write(manifest_file).await?);
+        }
+
+        let manifest_list_path = {
+            let manifest_list_filename =
+                Transaction::manifest_list_filename(&mut ctx, next_snapshot_id);
+            let (manifest_list_path, manifest_list_location) =
+                Transaction::metadata_file_location(&ctx, &manifest_list_filename);
+            // Writing manifest list
+            ManifestListWriter::new(
+                ctx.io.clone(),
+                manifest_list_path,
+                next_snapshot_id,
+                cur_snapshot_id,
+                next_snapshot_id,
+            )
+            .write(manifest_list)
+            .await?;
+
+            // Recorded path stored in snapshot file
+            manifest_list_location
+        };
+
+        let new_snapshot = Snapshot {
+            snapshot_id: next_snapshot_id,
+            parent_snapshot_id: cur_snapshot.map(|v| v.snapshot_id),
+            sequence_number: next_seq_number,
+            timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64,
+            manifest_list: manifest_list_path,
+            summary,
+            schema_id: Some(cur_metadata.current_schema_id as i64),
+        };
+        Ok((new_snapshot, deleted))
+    }
+
+    /// Rewrite a data manifest if it contains files to delete decided by
+    /// `should_delete`, files deleted are marked as `Deleted` and others are
+    /// kept as `Existing`.
+    ///
+    /// Returns `None` if the manifest is not changed.
+    async fn rewrite_manifest_with_deletes(
+        ctx: &mut CommitContext,
+        table: &Table,
+        manifest_list_entry: &ManifestListEntry,
+        should_delete: &dyn Fn(&DataFile) -> Result<bool>,
+        deleted: &mut Vec<DataFile>,
+        snapshot_id: i64,
+        seq_number: i64,
+    ) -> Result<Option<ManifestListEntry>> {
+        let manifest = manifest_list_entry.load_manifest(table).await?;
+        // Files deleted by previous snapshots are dropped.
+        let decisions = manifest
+            .entries
+            .into_iter()
+            .filter(|v| v.is_alive())
+            .map(|entry| Ok((should_delete(&entry.data_file)?, entry)))
+            .collect::<Result<Vec<_>>>()?;
+        if !decisions.iter().any(|(delete, _)| *delete) {
+            return Ok(None);
+        }
+
+        let entries = decisions
+            .into_iter()
+            .map(|(delete, mut entry)| {
+                if delete {
+                    deleted.push(entry.data_file.clone());
+                    entry.status = ManifestStatus::Deleted;
+                    entry.snapshot_id = Some(snapshot_id);
+                } else {
+                    entry.status = ManifestStatus::Existing;
+                }
+                entry
+            })
+            .collect();
+
+        let manifest_list_entry = Transaction::write_manifest(
+            ctx,
+            table,
+            manifest.metadata,
+            entries,
+            snapshot_id,
+            seq_number,
+        )
+        .await?;
+        Ok(Some(manifest_list_entry))
+    }
+
+    /// Merge data manifests of the same partition spec into one manifest,
+    /// live files are kept as `Existing`.
+    async fn merge_manifests(
+        ctx: &mut CommitContext,
+        table: &Table,
+        manifest_list_entries: &[ManifestListEntry],
+        snapshot_id: i64,
+        seq_number: i64,
+    ) -> Result<ManifestListEntry> {
+        let mut metadata = None;
+        let mut entries = vec![];
+        for manifest_list_entry in manifest_list_entries {
+            let manifest = manifest_list_entry.load_manifest(table).await?;
+            metadata.get_or_insert(manifest.metadata);
+            entries.extend(manifest.entries.into_iter().filter(|v| v.is_alive()).map(
+                |mut entry| {
+                    entry.status = ManifestStatus::Existing;
+                    entry
+                },
+            ));
+        }
+        let metadata = metadata.expect("manifests to merge must not be empty");
+        Transaction::write_manifest(ctx, table, metadata, entries, snapshot_id, seq_number).await
+    }
+
+    /// Write entries as a new manifest under metadata location.
+    async fn write_manifest(
+        ctx: &mut CommitContext,
+        table: &Table,
+        metadata: ManifestMetadata,
+        entries: Vec<ManifestEntry>,
+        snapshot_id: i64,
+        seq_number: i64,
+    ) -> Result<ManifestListEntry> {
+        let partition_spec = table
+            .current_table_metadata()
+            .partition_specs
+            .iter()
+            .find(|v| v.spec_id == metadata.partition_spec_id)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!(
+                        "partition spec {} of manifest is not found",
+                        metadata.partition_spec_id
+                    ),
+                )
+            })?
+            .clone();
+        let manifest_filename = Transaction::next_manifest_filename(ctx);
+        let (manifest_path, manifest_location) =
+            Transaction::metadata_file_location(ctx, &manifest_filename);
+        let writer = ManifestWriter::new(
+            partition_spec,
+            ctx.io.clone(),
+            manifest_path,
+            manifest_location,
+            snapshot_id,
+            seq_number,
+        );
+        let manifest_file = ManifestFile {
+            metadata: ManifestMetadata {
+                format_version: Some(table.current_table_metadata().format_version),
+                ..metadata
+            },
+            entries,
+        };
+        writer.write(manifest_file).await
+    }
+}
+
+/// Pack small data manifests of each partition spec into bins of about
+/// `target_size_bytes` to be merged.
+///
+/// Returns manifests kept as is and bins with at least two manifests.
+pub(crate) fn bin_pack_manifests(
+    manifest_list_entries: Vec<ManifestListEntry>,
+    target_size_bytes: u64,
+) -> (Vec<ManifestListEntry>, Vec<Vec<ManifestListEntry>>) {
+    let mut kept = vec![];
+    // Open bin of each partition spec.
+    let mut open: Vec<(i32, Vec<ManifestListEntry>, u64)> = vec![];
+    let mut bins = vec![];
+    for entry in manifest_list_entries {
+        let size = entry.manifest_length as u64;
+        if entry.content != ManifestContentType::Data || size >= target_size_bytes {
+            kept.push(entry);
+            continue;
+        }
+        let spec_id = entry.partition_spec_id;
+        let idx = match open.iter().position(|(id, _, _)| *id == spec_id) {
+            Some(idx) => idx,
+            None => {
+                open.push((spec_id, vec![], 0));
+                open.len() - 1
+            }
+        };
+        let (_, bin, bin_size) = &mut open[idx];
+        bin.push(entry);
+        *bin_size += size;
+        // Full bins always hold two manifests at least.
+        if *bin_size >= target_size_bytes {
+            let (_, bin, _) = open.swap_remove(idx);
+            bins.push(bin);
+        }
+    }
+    for (_, mut bin, _) in open {
+        if bin.len() == 1 {
+            kept.append(&mut bin);
+        } else {
+            bins.push(bin);
+        }
+    }
+    (kept, bins)
+}
+
+/// Summarize changes of a snapshot, totals are carried forward from the
+/// parent snapshot if recorded.
+///
+/// The operation is decided by added and deleted files unless given, adding
+/// delete files only is a `delete`. Sizes of files count both data files
+/// and delete files.
+///
+/// Changes of each partition are summarized as `partitions.{path}` if at
+/// most `partition_limit` partitions are changed, like
+/// `partitions.id=1` of `added-data-files=1,added-records=2`.
+fn snapshot_summary(
+    parent: Option<&Snapshot>,
+    added: &[DataFile],
+    deleted: &[DataFile],
+    operation: Option<&str>,
+    partition_limit: usize,
+) -> HashMap<String, String> {
+    let added_changes = FileChanges::new(added);
+    let deleted_changes = FileChanges::new(deleted);
+    let operation = operation.unwrap_or(
+        match (
+            added_changes.data_files == 0,
+            deleted.is_empty() && added_changes.delete_files == 0,
+        ) {
+            (false, false) => "overwrite",
+            (true, false) => "delete",
+            _ => "append",
+        },
+    );
+    let mut summary = HashMap::from([(OPERATION.to_string(), operation.to_string())]);
+    let mut set = |key: &str, value: i64| {
+        summary.insert(key.to_string(), value.to_string());
+    };
+
+    for (key, value) in change_fields(&added_changes, &deleted_changes) {
+        set(key, value);
+    }
+
+    let mut changed_partitions: HashMap<&StructValue, (Vec<&DataFile>, Vec<&DataFile>)> =
+        HashMap::new();
+    for file in added {
+        changed_partitions
+            .entry(&file.partition)
+            .or_default()
+            .0
+            .push(file);
+    }
+    for file in deleted {
+        changed_partitions
+            .entry(&file.partition)
+            .or_default()
+            .1
+            .push(file);
+    }
+    set(CHANGED_PARTITION_COUNT, changed_partitions.len() as i64);
+
+    for (key, delta) in [
+        (
+            TOTAL_DATA_FILES,
+            added_changes.data_files - deleted_changes.data_files,
+        ),
+        (
+            TOTAL_RECORDS,
+            added_changes.records - deleted_changes.records,
+        ),
+        (
+            TOTAL_FILES_SIZE,
+            added_changes.files_size - deleted_changes.files_size,
+        ),
+        (
+            TOTAL_DELETE_FILES,
+            added_changes.delete_files - deleted_changes.delete_files,
+        ),
+        (
+            TOTAL_POSITION_DELETES,
+            added_changes.position_deletes - deleted_changes.position_deletes,
+        ),
+        (
+            TOTAL_EQUALITY_DELETES,
+            added_changes.equality_deletes - deleted_changes.equality_deletes,
+        ),
+    ] {
+        let total = match parent {
+            None => Some(0),
+            Some(parent) => parent.summary.get(key).and_then(|v| v.parse::<i64>().ok()),
+        };
+        // Totals unknown if the parent doesn't record it.
+        if let Some(total) = total {
+            set(key, total + delta);
+        }
+    }
+
+    if !changed_partitions.is_empty() && changed_partitions.len() <= partition_limit {
+        summary.insert(PARTITION_SUMMARIES_INCLUDED.to_string(), "true".to_string());
+        for (partition, (added, deleted)) in changed_partitions {
+            let fields = change_fields(&FileChanges::new(added), &FileChanges::new(deleted));
+            summary.insert(
+                format!("{CHANGED_PARTITION_PREFIX}{}", partition_path(partition)),
+                fields
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+    }
+    summary
+}
+
+/// Summary fields of added and deleted files, fields of files not changed
+/// are skipped.
+fn change_fields(added: &FileChanges, deleted: &FileChanges) -> Vec<(&'static str, i64)> {
+    let mut fields = vec![];
+    if added.files > 0 {
+        fields.push((ADDED_FILES_SIZE, added.files_size));
+    }
+    if added.data_files > 0 {
+        fields.push((ADDED_DATA_FILES, added.data_files));
+        fields.push((ADDED_RECORDS, added.records));
+    }
+    if added.delete_files > 0 {
+        fields.push((ADDED_DELETE_FILES, added.delete_files));
+    }
+    if added.position_delete_files > 0 {
+        fields.push((ADDED_POSITION_DELETE_FILES, added.position_delete_files));
+        fields.push((ADDED_POSITION_DELETES, added.position_deletes));
+    }
+    if added.equality_delete_files > 0 {
+        fields.push((ADDED_EQUALITY_DELETE_FILES, added.equality_delete_files));
+        fields.push((ADDED_EQUALITY_DELETES, added.equality_deletes));
+    }
+    if deleted.files > 0 {
+        fields.push((REMOVED_FILES_SIZE, deleted.files_size));
+    }
+    if deleted.data_files > 0 {
+        fields.push((DELETED_DATA_FILES, deleted.data_files));
+        fields.push((DELETED_RECORDS, deleted.records));
+    }
+    if deleted.delete_files > 0 {
+        fields.push((REMOVED_DELETE_FILES, deleted.delete_files));
+    }
+    if deleted.position_delete_files > 0 {
+        fields.push((REMOVED_POSITION_DELETE_FILES, deleted.position_delete_files));
+        fields.push((REMOVED_POSITION_DELETES, deleted.position_deletes));
+    }
+    if deleted.equality_delete_files > 0 {
+        fields.push((REMOVED_EQUALITY_DELETE_FILES, deleted.equality_delete_files));
+        fields.push((REMOVED_EQUALITY_DELETES, deleted.equality_deletes));
+    }
+    fields
+}
+
+/// Counts of added or deleted files of a snapshot.
+#[derive(Default)]
+struct FileChanges {
+    /// Data files and delete files.
+    files: i64,
+    data_files: i64,
+    /// Records of data files.
+    records: i64,
+    /// Size of data files and delete files.
+    files_size: i64,
+    delete_files: i64,
+    position_delete_files: i64,
+    position_deletes: i64,
+    equality_delete_files: i64,
+    equality_deletes: i64,
+}
+
+impl FileChanges {
+    fn new<'a>(files: impl IntoIterator<Item = &'a DataFile>) -> Self {
+        let mut changes = Self::default();
+        for file in files {
+            changes.files += 1;
+            changes.files_size += file.file_size_in_bytes;
+            match file.content {
+                DataContentType::Data => {
+                    changes.data_files += 1;
+                    changes.records += file.record_count;
+                }
+                DataContentType::PostionDeletes => {
+                    changes.delete_files += 1;
+                    changes.position_delete_files += 1;
+                    changes.position_deletes += file.record_count;
+                }
+                DataContentType::EqualityDeletes => {
+                    changes.delete_files += 1;
+                    changes.equality_delete_files += 1;
+                    changes.equality_deletes += file.record_count;
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Backoff of retrying conflicting commits.
+struct CommitRetry {
+    num_retries: u32,
+    min_wait_ms: u64,
+    max_wait_ms: u64,
+    total_timeout_ms: u64,
+}
+
+impl CommitRetry {
+    fn from_properties(props: &HashMap<String, String>) -> Result<Self> {
+        fn parse<T: std::str::FromStr>(
+            props: &HashMap<String, String>,
+            key: &str,
+            default: T,
+        ) -> Result<T>
+        where
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            match props.get(key) {
+                Some(v) => v.trim().parse().map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid value of property {key}: {v}"),
+                    )
+                    .set_source(e)
+                }),
+                None => Ok(default),
+            }
+        }
+
+        Ok(Self {
+            num_retries: parse(props, COMMIT_NUM_RETRIES, COMMIT_NUM_RETRIES_DEFAULT)?,
+            min_wait_ms: parse(
+                props,
+                COMMIT_MIN_RETRY_WAIT_MS,
+                COMMIT_MIN_RETRY_WAIT_MS_DEFAULT,
+            )?,
+            max_wait_ms: parse(
+                props,
+                COMMIT_MAX_RETRY_WAIT_MS,
+                COMMIT_MAX_RETRY_WAIT_MS_DEFAULT,
+            )?,
+            total_timeout_ms: parse(
+                props,
+                COMMIT_TOTAL_RETRY_TIME_MS,
+                COMMIT_TOTAL_RETRY_TIME_MS_DEFAULT,
+            )?,
+        })
+    }
+
+    /// Returns the time to wait before the next retry, or `None` if retries
+    /// are exhausted.
+    ///
+    /// The wait doubles every attempt from `min_wait_ms` up to
+    /// `max_wait_ms`.
+    fn next_wait(&self, attempt: u32, elapsed: Duration) -> Option<Duration> {
+        if attempt >= self.num_retries {
+            return None;
+        }
+        let wait_ms = self
+            .min_wait_ms
+            .saturating_mul(1u64.checked_shl(attempt).unwrap_or(u64::MAX))
+            .min(self.max_wait_ms);
+        let wait = Duration::from_millis(wait_ms);
+        if elapsed + wait > Duration::from_millis(self.total_timeout_ms) {
+            return None;
+        }
+        Some(wait)
+    }
+}
+
+/// Returns whether write-audit-publish is enabled by table properties.
+fn wap_enabled(props: &HashMap<String, String>) -> Result<bool> {
+    match props.get(WRITE_WAP_ENABLED) {
+        Some(v) => v.trim().parse().map_err(|e| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Invalid value of property {WRITE_WAP_ENABLED}: {v}"),
+            )
+            .set_source(e)
+        }),
+        None => Ok(WRITE_WAP_ENABLED_DEFAULT),
+    }
+}
+
+impl AutoTag {
+    fn from_properties(props: &HashMap<String, String>) -> Result<Option<Self>> {
+        let Some(name_pattern) = props.get(AUTO_TAG_NAME_PATTERN) else {
+            return Ok(None);
+        };
+        let max_ref_age_ms = props
+            .get(AUTO_TAG_MAX_REF_AGE_MS)
+            .map(|v| v.parse::<i64>())
+            .transpose()?;
+
+        Ok(Some(Self {
+            name_pattern: name_pattern.clone(),
+            max_ref_age_ms,
+        }))
+    }
+
+    /// Format tag name by the commit time of snapshot.
+    fn tag_name(&self, timestamp_ms: i64) -> Result<String> {
+        let items = StrftimeItems::new(&self.name_pattern).collect::<Vec<_>>();
+        if items.iter().any(|v| matches!(v, Item::Error)) {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Invalid auto tag name pattern: {}", self.name_pattern),
+            ));
+        }
+        let time = Utc
+            .timestamp_millis_opt(timestamp_ms)
+            .single()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid snapshot timestamp: {timestamp_ms}"),
+                )
+            })?;
+
+        Ok(time.format_with_items(items.into_iter()).to_string())
+    }
+
+    fn apply(
+        &self,
+        metadata: &mut TableMetadata,
+        snapshot_id: i64,
+        timestamp_ms: i64,
+    ) -> Result<()> {
+        let name = self.tag_name(timestamp_ms)?;
+        if let Some(existing) = metadata.refs.get(&name) {
+            if existing.typ != SnapshotReferenceType::Tag {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Can't auto tag snapshot as {name}, which is a branch"),
+                ));
+            }
+        }
+
+        let mut tag = SnapshotReference::new(snapshot_id, SnapshotReferenceType::Tag);
+        tag.max_ref_age_ms = self.max_ref_age_ms;
+        metadata.refs.insert(name, tag);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_utils::{
+        prepare_partitioned_table_dir, prepare_table_dir, prepare_v1_table_dir,
+    };
+
+    #[test]
+    fn test_auto_tag_name() {
+        let auto_tag = AutoTag {
+            name_pattern: "audit-%Y-%m-%d".to_string(),
+            max_ref_age_ms: None,
+        };
+        // 2023-06-16T10:54:31.713Z
+        assert_eq!(
+            auto_tag.tag_name(1686912871713).unwrap(),
+            "audit-2023-06-16"
+        );
+
+        let auto_tag = AutoTag {
+            name_pattern: "audit-%Q".to_string(),
+            max_ref_age_ms: None,
+        };
+        assert!(auto_tag.tag_name(1686912871713).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_auto_tag() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let mut tx = Transaction::new(&mut table);
+        tx.append_file(vec![]);
+        tx.auto_tag("audit-%Y", Some(1000));
+        tx.commit().await.unwrap();
+
+        let metadata = table.current_table_metadata();
+        let snapshot_id = metadata.current_snapshot_id.unwrap();
+        let tag = metadata
+            .refs
+            .iter()
+            .find(|(name, _)| name.starts_with("audit-"))
+            .map(|(_, tag)| tag)
+            .unwrap();
+        assert_eq!(tag.typ, SnapshotReferenceType::Tag);
+        assert_eq!(tag.snapshot_id, snapshot_id);
+        assert_eq!(tag.max_ref_age_ms, Some(1000));
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_relative_path() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+        use futures::TryStreamExt;
+
+        let tmp_dir = prepare_table_dir();
+        let metadata_path = tmp_dir.path().join("metadata").join("v2.metadata.json");
+        let metadata = fs::read_to_string(&metadata_path).unwrap().replace(
+            r#""owner": "spark""#,
+            r#""owner": "spark", "icelake.write.relative-path.enabled": "true""#,
+        );
+        fs::write(metadata_path, metadata).unwrap();
+
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+        assert!(data_files[0].file_path.starts_with("data/"));
+
+        let mut tx = Transaction::new(&mut table);
+        tx.append_file(data_files);
+        tx.commit().await.unwrap();
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        assert!(snapshot.manifest_list.starts_with("metadata/snap-"));
+
+        // The table is still readable after moved to another directory.
+        let moved_dir = TempDir::new().unwrap();
+        let moved_path = moved_dir.path().join("table");
+        fs::rename(tmp_dir.path(), &moved_path).unwrap();
+        let table = Table::open(moved_path.to_str().unwrap()).await.unwrap();
+        let data_files = table.current_data_files().await.unwrap();
+        let batches: Vec<_> = table
+            .read_data_files(&data_files)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let rows: usize = batches.iter().map(|v| v.num_rows()).sum();
+        assert_eq!(rows, 5);
+    }
+
+    #[test]
+    fn test_auto_tag_from_table_properties() {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let bs = fs::read(path).expect("read_file must succeed");
+        let mut metadata =
+            crate::types::parse_table_metadata(&bs).expect("parse_table_metadata must succeed");
+        assert!(
+            AutoTag::from_properties(metadata.properties.as_ref().unwrap())
+                .unwrap()
+                .is_none()
+        );
+
+        let props = HashMap::from([(AUTO_TAG_NAME_PATTERN.to_string(), "main".to_string())]);
+        let auto_tag = AutoTag::from_properties(&props).unwrap().unwrap();
+        assert_eq!(auto_tag.max_ref_age_ms, None);
+        // Never replace a branch.
+        metadata.refs.insert(
+            "main".to_string(),
+            SnapshotReference::new(1, SnapshotReferenceType::Branch),
+        );
+        assert!(auto_tag.apply(&mut metadata, 2, 1686912871713).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_multiple_operations() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+        use futures::TryStreamExt;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let existing_files = table.current_data_files().await.unwrap();
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.delete_file(existing_files[..1].to_vec());
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        tx.remove_properties(["owner"]);
+        tx.commit().await.unwrap();
+
+        // All operations are committed in one version.
+        assert!(table
+            .current_metadata_file_location()
+            .ends_with("v3.metadata.json"));
+        let properties = table.current_table_metadata().properties.clone().unwrap();
+        assert_eq!(properties.get("a").unwrap(), "b");
+        assert!(!properties.contains_key("owner"));
+        let data_files = table.current_data_files().await.unwrap();
+        assert_eq!(data_files.len(), 3);
+        assert!(data_files
+            .iter()
+            .all(|v| v.file_path != existing_files[0].file_path));
+        let batches: Vec<_> = table
+            .read_data_files(&data_files)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|v| v.num_rows()).sum::<usize>(), 4);
+
+        // Updating properties only doesn't produce snapshot.
+        let snapshot_id = table.current_table_metadata().current_snapshot_id;
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("c".to_string(), "d".to_string())]));
+        tx.commit().await.unwrap();
+        assert_eq!(
+            table.current_table_metadata().current_snapshot_id,
+            snapshot_id
+        );
+
+        // Deleted files can't be deleted again.
+        let mut tx = table.new_transaction();
+        tx.delete_file(existing_files[..1].to_vec());
+        assert!(tx.commit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_retry_on_conflict() {
+        let tmp_dir = prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await.unwrap();
+        let mut stale_table = Table::open(path).await.unwrap();
+        let existing_files = table.current_data_files().await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("a".to_string(), "b".to_string())]));
+        tx.commit().await.unwrap();
+
+        // Retried on top of the commit above.
+        let mut tx = stale_table.new_transaction();
+        tx.delete_file(existing_files[..1].to_vec());
+        tx.commit().await.unwrap();
+        assert!(stale_table
+            .current_metadata_file_location()
+            .ends_with("v4.metadata.json"));
+        let properties = stale_table.current_table_metadata().properties.clone();
+        assert_eq!(properties.unwrap().get("a").unwrap(), "b");
+        assert_eq!(
+            stale_table.current_data_files().await.unwrap().len(),
+            existing_files.len() - 1
+        );
+
+        // Not retried if retries are disabled.
+        let mut tx = stale_table.new_transaction();
+        tx.set_properties(HashMap::from([(
+            COMMIT_NUM_RETRIES.to_string(),
+            "0".to_string(),
+        )]));
+        tx.commit().await.unwrap();
+        table.refresh().await.unwrap();
+        let mut stale_table = Table::open(path).await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([("c".to_string(), "d".to_string())]));
+        tx.commit().await.unwrap();
+        let mut tx = stale_table.new_transaction();
+        tx.set_properties(HashMap::from([("e".to_string(), "f".to_string())]));
+        let err = tx.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommitConflict);
+    }
+
+    #[tokio::test]
+    async fn test_commit_validations() {
+        let tmp_dir = prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await.unwrap();
+        let existing_files = table.current_data_files().await.unwrap();
+        let base_snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+
+        // Data appended concurrently is kept by overwrites.
+        let mut stale_table = Table::open(path).await.unwrap();
+        let mut append = table.new_append();
+        append.append_file(existing_files[..1].to_vec());
+        append.commit().await.unwrap();
+        let mut overwrite = stale_table.new_overwrite();
+        overwrite.overwrite_by_filter(Predicate::AlwaysTrue);
+        overwrite.validate_no_conflicting_data();
+        let err = overwrite.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+
+        let mut stale_table = Table::open(path).await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.delete_file(existing_files[1..2].to_vec());
+        tx.commit().await.unwrap();
+        let mut append = stale_table.new_append();
+        append.append_file(existing_files[..1].to_vec());
+        append.validate_no_conflicting_deletes(Predicate::AlwaysTrue);
+        let err = append.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+
+        // Changes are validated since the given snapshot.
+        let mut tx = table.new_transaction();
+        tx.append_file(existing_files[..1].to_vec());
+        tx.validate_from_snapshot(base_snapshot_id);
+        tx.validate_no_conflicting_data(Predicate::AlwaysFalse);
+        tx.validate_no_conflicting_deletes(Predicate::AlwaysTrue);
+        let err = tx.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+        let mut tx = table.new_transaction();
+        tx.append_file(existing_files[..1].to_vec());
+        tx.validate_no_conflicting_deletes(Predicate::AlwaysTrue);
+        tx.commit().await.unwrap();
+    }
+
+    #[test]
+    fn test_commit_retry_wait() {
+        let retry = CommitRetry::from_properties(&HashMap::from([
+            (COMMIT_NUM_RETRIES.to_string(), "3".to_string()),
+            (COMMIT_MAX_RETRY_WAIT_MS.to_string(), "300".to_string()),
+            (COMMIT_TOTAL_RETRY_TIME_MS.to_string(), "1000".to_string()),
+        ]))
+        .unwrap();
+        let wait = |attempt, elapsed_ms| {
+            retry
+                .next_wait(attempt, Duration::from_millis(elapsed_ms))
+                .map(|v| v.as_millis())
+        };
+        assert_eq!(wait(0, 0), Some(100));
+        assert_eq!(wait(1, 0), Some(200));
+        assert_eq!(wait(2, 0), Some(300));
+        assert_eq!(wait(3, 0), None);
+        assert_eq!(wait(1, 900), None);
+
+        assert!(CommitRetry::from_properties(&HashMap::from([(
+            COMMIT_NUM_RETRIES.to_string(),
+            "x".to_string(),
+        )]))
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_append_files() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let parent_id = table.current_table_metadata().current_snapshot_id;
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+        let added_size = data_files[0].file_size_in_bytes;
+
+        let mut append = table.new_append();
+        append.append_file(data_files);
+        append.commit().await.unwrap();
+
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        assert_eq!(snapshot.parent_snapshot_id, parent_id);
+        let summary = &snapshot.summary;
+        assert_eq!(summary[OPERATION], "append");
+        assert_eq!(summary[ADDED_DATA_FILES], "1");
+        assert_eq!(summary[ADDED_RECORDS], "2");
+        assert_eq!(summary[ADDED_FILES_SIZE], added_size.to_string());
+        assert_eq!(summary[TOTAL_DATA_FILES], "4");
+        assert_eq!(summary[TOTAL_RECORDS], "5");
+        assert_eq!(summary[TOTAL_FILES_SIZE], (1929 + added_size).to_string());
+        assert!(!summary.contains_key("spark.app.id"));
+        assert_eq!(table.current_data_files().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_append_position_deletes() {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let data_files = table.current_data_files().await.unwrap();
+
+        let mut writer = table
+            .position_delete_writer(data_files[0].partition.clone(), None)
+            .await
+            .unwrap();
+        writer.delete(&data_files[0].file_path, [0]).unwrap();
+        let delete_files = writer.close().await.unwrap();
+        let delete_size = delete_files[0].file_size_in_bytes;
+        let mut append = table.new_append();
+        append.append_file(delete_files.clone());
+        append.commit().await.unwrap();
+
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        let summary = &snapshot.summary;
+        assert_eq!(summary[OPERATION], "delete");
+        assert_eq!(summary[ADDED_DELETE_FILES], "1");
+        assert_eq!(summary[ADDED_POSITION_DELETE_FILES], "1");
+        assert_eq!(summary[ADDED_POSITION_DELETES], "1");
+        assert_eq!(summary[TOTAL_DELETE_FILES], "1");
+        assert_eq!(summary[TOTAL_POSITION_DELETES], "1");
+        assert_eq!(summary[TOTAL_DATA_FILES], "3");
+        assert_eq!(summary[ADDED_FILES_SIZE], delete_size.to_string());
+        assert_eq!(summary[TOTAL_FILES_SIZE], (1929 + delete_size).to_string());
+        assert_eq!(summary[CHANGED_PARTITION_COUNT], "1");
+        assert!(!summary.contains_key(ADDED_DATA_FILES));
+
+        let manifest_list = snapshot.load_manifest_list(&table).await.unwrap();
+        let delete_manifest = manifest_list
+            .entries
+            .iter()
+            .find(|v| v.content == ManifestContentType::Deletes)
+            .unwrap();
+        let manifest = delete_manifest.load_manifest(&table).await.unwrap();
+        assert_eq!(manifest.metadata.content, ManifestContentType::Deletes);
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(
+            manifest.entries[0].data_file.content,
+            DataContentType::PostionDeletes
+        );
+        // Delete files are not data files.
+        assert_eq!(table.current_data_files().await.unwrap().len(), 3);
+
+        // Removed delete files are summarized apart from data files.
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        let summary = &snapshot_summary(Some(snapshot), &[], &delete_files, None, 0);
+        assert_eq!(summary[OPERATION], "delete");
+        assert_eq!(summary[REMOVED_DELETE_FILES], "1");
+        assert_eq!(summary[REMOVED_POSITION_DELETE_FILES], "1");
+        assert_eq!(summary[REMOVED_POSITION_DELETES], "1");
+        assert_eq!(summary[REMOVED_FILES_SIZE], delete_size.to_string());
+        assert_eq!(summary[TOTAL_DELETE_FILES], "0");
+        assert_eq!(summary[TOTAL_POSITION_DELETES], "0");
+        assert_eq!(summary[TOTAL_DATA_FILES], "3");
+        assert_eq!(summary[TOTAL_FILES_SIZE], "1929");
+        assert!(!summary.contains_key(DELETED_DATA_FILES));
+    }
+
+    #[tokio::test]
+    async fn test_commit_partition_summaries() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        let tmp_dir = prepare_partitioned_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let mut tx = table.new_transaction();
+        tx.set_properties(HashMap::from([(
+            WRITE_PARTITION_SUMMARY_LIMIT.to_string(),
+            "2".to_string(),
+        )]));
+        tx.commit().await.unwrap();
+
+        let write = |data: Vec<&'static str>| {
+            let ids = (0..data.len() as i64).collect::<Vec<_>>();
+            RecordBatch::try_from_iter([
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("data", Arc::new(StringArray::from(data)) as ArrayRef),
+            ])
+            .unwrap()
+        };
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&write(vec!["a", "b", "a"])).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+        let size_of = |partition: &str| {
+            data_files
+                .iter()
+                .find(|v| partition_path(&v.partition) == partition)
+                .unwrap()
+                .file_size_in_bytes
+        };
+        let (size_a, size_b) = (size_of("data=a"), size_of("data=b"));
+        let mut append = table.new_append();
+        append.append_file(data_files);
+        append.commit().await.unwrap();
+
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary[CHANGED_PARTITION_COUNT], "2");
+        assert_eq!(summary[PARTITION_SUMMARIES_INCLUDED], "true");
+        assert_eq!(
+            summary["partitions.data=a"],
+            format!("added-files-size={size_a},added-data-files=1,added-records=2")
+        );
+        assert_eq!(
+            summary["partitions.data=b"],
+            format!("added-files-size={size_b},added-data-files=1,added-records=1")
+        );
+
+        // Partitions are not summarized one by one over the limit.
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&write(vec!["a", "b", "c"])).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+        let mut append = table.new_append();
+        append.append_file(data_files);
+        append.commit().await.unwrap();
+
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary[CHANGED_PARTITION_COUNT], "3");
+        assert_eq!(summary[ADDED_DATA_FILES], "3");
+        assert!(!summary.contains_key(PARTITION_SUMMARIES_INCLUDED));
+        assert!(!summary
+            .keys()
+            .any(|v| v.starts_with(CHANGED_PARTITION_PREFIX)));
+        // Manifest lists of partitioned manifests are readable.
+        assert_eq!(table.current_data_files().await.unwrap().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_append_equality_deletes() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array};
+        use arrow::record_batch::RecordBatch;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let partition = table.current_data_files().await.unwrap()[0]
+            .partition
+            .clone();
+
+        let mut writer = table
+            .equality_delete_writer(partition, vec![1])
+            .await
+            .unwrap();
+        let batch = RecordBatch::try_from_iter([(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap();
+        writer.write(&batch).await.unwrap();
+        let delete_files = writer.close().await.unwrap();
+        let mut append = table.new_append();
+        append.append_file(delete_files);
+        append.commit().await.unwrap();
+
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        let summary = &snapshot.summary;
+        assert_eq!(summary[OPERATION], "delete");
+        assert_eq!(summary[ADDED_DELETE_FILES], "1");
+        assert_eq!(summary[ADDED_EQUALITY_DELETE_FILES], "1");
+        assert_eq!(summary[ADDED_EQUALITY_DELETES], "2");
+        assert_eq!(summary[TOTAL_EQUALITY_DELETES], "2");
+        assert_eq!(summary[TOTAL_POSITION_DELETES], "0");
+        assert!(!summary.contains_key(ADDED_POSITION_DELETE_FILES));
+
+        let manifest_list = snapshot.load_manifest_list(&table).await.unwrap();
+        let delete_manifest = manifest_list
+            .entries
+            .iter()
+            .find(|v| v.content == ManifestContentType::Deletes)
+            .unwrap();
+        let manifest = delete_manifest.load_manifest(&table).await.unwrap();
+        let delete_file = &manifest.entries[0].data_file;
+        assert_eq!(delete_file.content, DataContentType::EqualityDeletes);
+        assert_eq!(delete_file.equality_ids, vec![1]);
+        assert_eq!(delete_file.record_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_files() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        use crate::types::expression::Reference;
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+
+        let paths =
+            |files: Vec<DataFile>| files.into_iter().map(|v| v.file_path).collect::<Vec<_>>();
+        let expected = paths(data_files.clone());
+
+        // The table is unpartitioned, so only `AlwaysTrue` can be decided by
+        // partition values.
+        let mut overwrite = table.new_overwrite();
+        overwrite.overwrite_by_filter(Predicate::AlwaysTrue);
+        overwrite.append_file(data_files.clone());
+        overwrite.commit().await.unwrap();
+
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary[OPERATION], "overwrite");
+        assert_eq!(summary[DELETED_DATA_FILES], "3");
+        assert_eq!(summary[ADDED_DATA_FILES], "1");
+        assert_eq!(summary[TOTAL_RECORDS], "2");
+        assert_eq!(paths(table.current_data_files().await.unwrap()), expected);
+
+        // Overwriting again gives the same files.
+        let mut overwrite = table.new_overwrite();
+        overwrite.overwrite_by_filter(Predicate::AlwaysTrue);
+        overwrite.append_file(data_files.clone());
+        overwrite.commit().await.unwrap();
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary[DELETED_DATA_FILES], "1");
+        assert_eq!(summary[TOTAL_DATA_FILES], "1");
+        assert_eq!(paths(table.current_data_files().await.unwrap()), expected);
+
+        // Filters matching nothing are fine.
+        let mut tx = table.new_transaction();
+        tx.delete_by_filter(Predicate::AlwaysFalse);
+        tx.commit().await.unwrap();
+        let summary = &table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .summary;
+        assert_eq!(summary[OPERATION], "overwrite");
+        assert_eq!(summary[TOTAL_DATA_FILES], "1");
+
+        let mut tx = table.new_transaction();
+        tx.delete_by_filter(Reference::new("id").equal_to(crate::types::PrimitiveValue::Long(4)));
+        assert_eq!(
+            tx.commit().await.unwrap_err().kind(),
+            ErrorKind::IcebergFeatureUnsupported
+        );
+
+        // Files not matching by metrics are kept.
+        let mut tx = table.new_transaction();
+        tx.delete_by_filter(Reference::new("id").equal_to(crate::types::PrimitiveValue::Long(6)));
+        tx.commit().await.unwrap();
+        assert_eq!(paths(table.current_data_files().await.unwrap()), expected);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_format_version() {
+        use std::sync::Arc;
+
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use arrow::record_batch::RecordBatch;
+
+        let tmp_dir = prepare_v1_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4, 5])) as ArrayRef),
+            (
+                "data",
+                Arc::new(StringArray::from(vec!["d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut writer = table.task_writer().await.unwrap();
+        writer.write(&batch).await.unwrap();
+        let data_files = writer.close().await.unwrap();
+
+        let mut append = table.new_append();
+        append.append_file(data_files.clone());
+        assert_eq!(
+            append.commit().await.unwrap_err().kind(),
+            ErrorKind::IcebergFeatureUnsupported
+        );
+
+        let mut tx = table.new_transaction();
+        tx.upgrade_format_version(TableFormatVersion::V2);
+        tx.commit().await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.format_version, TableFormatVersion::V2);
+        assert_eq!(metadata.last_sequence_number, 0);
+
+        let mut append = table.new_append();
+        append.append_file(data_files);
+        append.commit().await.unwrap();
+        let snapshot = table.current_table_metadata().current_snapshot().unwrap();
+        assert_eq!(snapshot.sequence_number, 1);
+
+        // Files of v1 manifests have sequence number 0.
+        let manifest_list = snapshot.load_manifest_list(&table).await.unwrap();
+        let mut sequence_numbers = vec![];
+        for entry in &manifest_list.entries {
+            let manifest = entry.load_manifest(&table).await.unwrap();
+            sequence_numbers.extend(manifest.entries.iter().map(|v| v.sequence_number));
+        }
+        sequence_numbers.sort();
+        assert_eq!(sequence_numbers, vec![Some(0), Some(0), Some(0), Some(1)]);
+
+        let mut tx = table.new_transaction();
+        tx.upgrade_format_version(TableFormatVersion::V1);
+        assert!(tx.commit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_statistics() {
+        use bytes::Bytes;
+
+        use crate::types::{
+            PuffinBlob, PuffinCompressionCodec, PuffinReader, PuffinWriter,
+            APACHE_DATASKETCHES_THETA_V1,
+        };
+
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let snapshot = table
+            .current_table_metadata()
+            .current_snapshot()
+            .unwrap()
+            .clone();
+
+        let location = format!(
+            "{}/{}.stats",
+            table.metadata_location(),
+            snapshot.snapshot_id
+        );
+        let (op, path) = table.location_operator(&location).unwrap();
+        let mut writer = PuffinWriter::new(op.clone(), path.clone(), location.clone());
+        writer
+            .add(
+                PuffinBlob {
+                    typ: APACHE_DATASKETCHES_THETA_V1.to_string(),
+                    fields: vec![1],
+                    snapshot_id: snapshot.snapshot_id,
+                    sequence_number: snapshot.sequence_number,
+                    data: Bytes::from_static(b"sketch"),
+                    properties: HashMap::from([("ndv".to_string(), "3".to_string())]),
+                },
+                PuffinCompressionCodec::Zstd,
+            )
+            .unwrap();
+        let statistics_file = writer.close(snapshot.snapshot_id).await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.set_statistics(statistics_file.clone());
+        tx.commit().await.unwrap();
+        // Statistics are kept in the table metadata.
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_snapshot_id, Some(snapshot.snapshot_id));
+        let recorded = metadata.statistics_file(snapshot.snapshot_id).unwrap();
+        assert_eq!(recorded, &statistics_file);
+        assert_eq!(recorded.blob_metadata[0].properties["ndv"], "3");
+
+        let reader = PuffinReader::new(op, path).with_file_size(recorded.file_size_in_bytes as u64);
+        let blobs = reader.file_metadata().await.unwrap().blobs;
+        assert_eq!(
+            reader.read_blob(&blobs[0]).await.unwrap(),
+            Bytes::from_static(b"sketch")
+        );
+
+        let mut tx = table.new_transaction();
+        tx.set_statistics(StatisticsFile {
+            snapshot_id: -1,
+            ..statistics_file
+        });
+        assert!(tx.commit().await.is_err());
+
+        let mut tx = table.new_transaction();
+        tx.remove_statistics(snapshot.snapshot_id);
+        tx.commit().await.unwrap();
+        assert!(table.current_table_metadata().statistics.is_empty());
+    }
+}
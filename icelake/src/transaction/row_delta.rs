@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use crate::transaction::Transaction;
+use crate::types::expression::Predicate;
+use crate::types::DataFile;
+use crate::{Error, ErrorKind, Result, Table};
+
+/// IsolationLevel decides which changes committed concurrently since the
+/// base snapshot abort a [`RowDelta`] or an
+/// [`crate::transaction::OverwriteFiles`], like isolation levels of
+/// iceberg in java.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+    /// Fail if data files or deletes matching the conflict detection
+    /// filter are committed concurrently, as if the commit had run after
+    /// them.
+    #[default]
+    Serializable,
+    /// Fail only if deletes matching the conflict detection filter are
+    /// committed concurrently, data files appended concurrently are
+    /// allowed.
+    Snapshot,
+}
+
+impl FromStr for IsolationLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "serializable" => Ok(Self::Serializable),
+            "snapshot" => Ok(Self::Snapshot),
+            _ => Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Invalid isolation level: {s}"),
+            )),
+        }
+    }
+}
+
+impl IsolationLevel {
+    /// Add validations of the isolation level with the filter to the
+    /// transaction.
+    pub(crate) fn validate(self, tx: &mut Transaction<'_>, filter: Predicate) {
+        if self == IsolationLevel::Serializable {
+            tx.validate_no_conflicting_data(filter.clone());
+        }
+        tx.validate_no_conflicting_deletes(filter);
+    }
+}
+
+/// RowDelta adds data files and delete files, like those produced by
+/// [`crate::io::task_writer::TaskWriter`] and
+/// [`crate::io::delta_writer::DeltaWriter`], to a table as a new
+/// snapshot, encoding row-level changes like updates and deletes.
+///
+/// Conflicts with changes committed since the base snapshot are checked
+/// by the isolation level, default to [`IsolationLevel::Serializable`],
+/// for rows matching the conflict detection filter, default to all rows.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use icelake::types::DataFile;
+/// # async fn example(data_files: Vec<DataFile>, delete_files: Vec<DataFile>, base_snapshot_id: i64) -> icelake::Result<()> {
+/// use icelake::transaction::IsolationLevel;
+///
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut row_delta = table.new_row_delta();
+/// row_delta.add_rows(data_files);
+/// row_delta.add_deletes(delete_files);
+/// row_delta.validate_from_snapshot(base_snapshot_id);
+/// row_delta.isolation_level(IsolationLevel::Snapshot);
+/// row_delta.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RowDelta<'a> {
+    tx: Transaction<'a>,
+    isolation_level: IsolationLevel,
+    conflict_detection_filter: Predicate,
+}
+
+impl<'a> RowDelta<'a> {
+    /// Create a new row delta action.
+    pub fn new(table: &'a mut Table) -> Self {
+        Self {
+            tx: Transaction::new(table),
+            isolation_level: IsolationLevel::default(),
+            conflict_detection_filter: Predicate::AlwaysTrue,
+        }
+    }
+
+    /// Add data files of inserted rows.
+    pub fn add_rows(&mut self, data_file: impl IntoIterator<Item = DataFile>) {
+        self.tx.append_file(data_file);
+    }
+
+    /// Add position delete files or equality delete files of deleted rows.
+    pub fn add_deletes(&mut self, delete_file: impl IntoIterator<Item = DataFile>) {
+        self.tx.append_file(delete_file);
+    }
+
+    /// Commit to the branch instead of the main branch, see
+    /// [`Transaction::to_branch`].
+    pub fn to_branch(&mut self, branch: impl Into<String>) {
+        self.tx.to_branch(branch);
+    }
+
+    /// Validate changes since the snapshot instead of the branch head when
+    /// committing, usually the snapshot the changed rows were read from,
+    /// see [`Transaction::validate_from_snapshot`].
+    pub fn validate_from_snapshot(&mut self, snapshot_id: i64) {
+        self.tx.validate_from_snapshot(snapshot_id);
+    }
+
+    /// Set the isolation level of the commit.
+    pub fn isolation_level(&mut self, isolation_level: IsolationLevel) {
+        self.isolation_level = isolation_level;
+    }
+
+    /// Only check conflicts of concurrent changes that might match the
+    /// filter, like the condition of an update.
+    pub fn conflict_detection_filter(&mut self, filter: Predicate) {
+        self.conflict_detection_filter = filter;
+    }
+
+    /// Commit added files as a new snapshot.
+    pub async fn commit(mut self) -> Result<()> {
+        self.isolation_level
+            .validate(&mut self.tx, self.conflict_detection_filter);
+        self.tx.commit().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+
+    #[tokio::test]
+    async fn test_row_delta_isolation_level() {
+        let tmp_dir = prepare_table_dir();
+        let path = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(path).await.unwrap();
+        let existing_files = table.current_data_files().await.unwrap();
+        let base_snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+        assert_eq!(
+            "Snapshot".parse::<IsolationLevel>().unwrap(),
+            IsolationLevel::Snapshot
+        );
+        assert!("read-committed".parse::<IsolationLevel>().is_err());
+
+        // Data appended concurrently only conflicts in serializable isolation.
+        let mut append = table.new_append();
+        append.append_file(existing_files[..1].to_vec());
+        append.commit().await.unwrap();
+        let mut row_delta = table.new_row_delta();
+        row_delta.add_rows(existing_files[..1].to_vec());
+        row_delta.validate_from_snapshot(base_snapshot_id);
+        let err = row_delta.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+        let mut row_delta = table.new_row_delta();
+        row_delta.add_rows(existing_files[..1].to_vec());
+        row_delta.validate_from_snapshot(base_snapshot_id);
+        row_delta.conflict_detection_filter(Predicate::AlwaysFalse);
+        row_delta.commit().await.unwrap();
+        let mut row_delta = table.new_row_delta();
+        row_delta.add_rows(existing_files[..1].to_vec());
+        row_delta.validate_from_snapshot(base_snapshot_id);
+        row_delta.isolation_level(IsolationLevel::Snapshot);
+        row_delta.commit().await.unwrap();
+
+        // Data deleted concurrently always conflicts.
+        let snapshot_id = table.current_table_metadata().current_snapshot_id.unwrap();
+        let mut tx = table.new_transaction();
+        tx.delete_file(existing_files[1..2].to_vec());
+        tx.commit().await.unwrap();
+        let mut row_delta = table.new_row_delta();
+        row_delta.add_rows(existing_files[..1].to_vec());
+        row_delta.validate_from_snapshot(snapshot_id);
+        row_delta.isolation_level(IsolationLevel::Snapshot);
+        let err = row_delta.commit().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValidationFailed);
+    }
+}
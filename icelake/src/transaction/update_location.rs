@@ -0,0 +1,163 @@
+use crate::table_properties::{WRITE_DATA_LOCATION, WRITE_METADATA_LOCATION};
+use crate::types::TableMetadata;
+use crate::{Error, ErrorKind, Result};
+
+/// UpdateLocation moves the base location of a table, committed by
+/// [`crate::transaction::Transaction::update_location`], so that the table
+/// can be migrated to another bucket or file system.
+///
+/// Only new files are written under the new location, existing files are
+/// not moved. Files of old snapshots are still read from their recorded
+/// locations: the committing table keeps reading files under its previous
+/// location by its previous operator, and files under the new location by
+/// an operator built from the table config. Tables opened afterwards need
+/// operators of locations not served by the table operator registered by
+/// [`crate::Table::register_location_operator`].
+///
+/// Locations of data files and manifests set by `write.data.path` and
+/// `write.metadata.path` are kept unless they're updated too. Metadata files
+/// of hadoop style tables are still written to the directory the table is
+/// opened from.
+///
+/// # Examples
+///
+/// ```no_run
+/// use icelake::transaction::UpdateLocation;
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut tx = table.new_transaction();
+/// tx.update_location(
+///     UpdateLocation::new("s3://new-bucket/db/table")
+///         .with_data_location("s3://new-bucket/db/table/data"),
+/// );
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct UpdateLocation {
+    location: String,
+    data_location: Option<String>,
+    metadata_location: Option<String>,
+}
+
+impl UpdateLocation {
+    /// Move the table to `location`.
+    pub fn new(location: impl Into<String>) -> Self {
+        Self {
+            location: location.into(),
+            data_location: None,
+            metadata_location: None,
+        }
+    }
+
+    /// Write new data files under `location`, which sets `write.data.path`.
+    pub fn with_data_location(mut self, location: impl Into<String>) -> Self {
+        self.data_location = Some(location.into());
+        self
+    }
+
+    /// Write new manifests and manifest lists under `location`, which sets
+    /// `write.metadata.path`.
+    pub fn with_metadata_location(mut self, location: impl Into<String>) -> Self {
+        self.metadata_location = Some(location.into());
+        self
+    }
+
+    /// Apply the update to the table metadata.
+    pub(crate) fn apply(self, metadata: &mut TableMetadata) -> Result<()> {
+        let location = self.location.trim_end_matches('/');
+        if location.is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Table location can't be empty",
+            ));
+        }
+        metadata.location = location.to_string();
+
+        let properties = metadata.properties.get_or_insert_with(Default::default);
+        for (key, value) in [
+            (WRITE_DATA_LOCATION, self.data_location),
+            (WRITE_METADATA_LOCATION, self.metadata_location),
+        ] {
+            if let Some(value) = value {
+                properties.insert(key.to_string(), value.trim_end_matches('/').to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use futures::TryStreamExt;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::io::storage::build_operator;
+    use crate::test_utils::prepare_table_dir;
+    use crate::Table;
+
+    #[tokio::test]
+    async fn test_update_location() -> Result<()> {
+        let tmp_dir = prepare_table_dir();
+        let new_dir = TempDir::new().unwrap();
+        let new_location = new_dir.path().to_str().unwrap().to_string();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+
+        let mut tx = table.new_transaction();
+        tx.update_location(UpdateLocation::new(format!("{new_location}/")));
+        tx.commit().await?;
+        assert_eq!(table.current_table_metadata().location, new_location);
+        assert_eq!(table.data_location(), format!("{new_location}/data"));
+
+        // New files are written under the new location.
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+            ("data", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+        ])?;
+        let mut writer = table.task_writer().await?;
+        writer.write(&batch).await?;
+        let data_files = writer.close().await?;
+        assert!(data_files[0].file_path.starts_with(&new_location));
+        let mut tx = table.new_transaction();
+        tx.append_file(data_files);
+        tx.commit().await?;
+        assert!(new_dir.path().join("data").exists());
+        assert!(new_dir.path().join("metadata").exists());
+
+        // Files of old snapshots are still readable.
+        let rows = |batches: Vec<RecordBatch>| batches.iter().map(|v| v.num_rows()).sum::<usize>();
+        let batches = table.scan().build()?.execute().await?.try_collect().await?;
+        assert_eq!(rows(batches), 4);
+
+        // Tables opened later need operators of both locations, since the
+        // table operator is rooted at the previous location.
+        let old_location = "/opt/bitnami/spark/warehouse/db/table";
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        assert_eq!(table.current_table_metadata().location, new_location);
+        table.register_location_operator(old_location, table.operator());
+        table.register_location_operator(
+            &new_location,
+            build_operator(&new_location, &HashMap::new())?,
+        );
+        let batches = table.scan().build()?.execute().await?.try_collect().await?;
+        assert_eq!(rows(batches), 4);
+
+        let mut tx = table.new_transaction();
+        tx.update_location(UpdateLocation::new(&new_location).with_data_location("/data/"));
+        tx.commit().await?;
+        assert_eq!(table.data_location(), "/data");
+
+        let mut tx = table.new_transaction();
+        tx.update_location(UpdateLocation::new(""));
+        assert!(tx.commit().await.is_err());
+        Ok(())
+    }
+}
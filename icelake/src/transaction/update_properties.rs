@@ -0,0 +1,107 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Error, ErrorKind, Result};
+
+/// UpdateProperties sets and removes table properties, committed by
+/// [`crate::transaction::Transaction::update_properties`].
+///
+/// Only a new metadata version is committed, snapshots are not changed.
+/// Existing values of set properties are overwritten, and removed
+/// properties not set are ignored.
+///
+/// # Examples
+///
+/// ```no_run
+/// use icelake::transaction::UpdateProperties;
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut tx = table.new_transaction();
+/// tx.update_properties(
+///     UpdateProperties::new()
+///         .set("write.format.default", "avro")
+///         .remove("owner"),
+/// );
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct UpdateProperties {
+    updates: HashMap<String, String>,
+    removals: HashSet<String>,
+}
+
+impl UpdateProperties {
+    /// Create an empty update.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a property.
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.updates.insert(key.into(), value.into());
+        self
+    }
+
+    /// Remove a property.
+    pub fn remove(mut self, key: impl Into<String>) -> Self {
+        self.removals.insert(key.into());
+        self
+    }
+
+    /// Apply the update to properties of the table.
+    ///
+    /// A property can't be both set and removed by the same update.
+    pub(crate) fn apply(self, properties: &mut HashMap<String, String>) -> Result<()> {
+        if let Some(key) = self.updates.keys().find(|v| self.removals.contains(*v)) {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Property {key} is both set and removed"),
+            ));
+        }
+        for key in &self.removals {
+            properties.remove(key);
+        }
+        properties.extend(self.updates);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::Table;
+
+    #[tokio::test]
+    async fn test_update_properties() -> Result<()> {
+        let tmp_dir = prepare_table_dir();
+        let mut table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        let snapshots = table.current_table_metadata().snapshots.clone();
+        let location = table.current_metadata_file_location();
+
+        let mut tx = table.new_transaction();
+        tx.update_properties(UpdateProperties::new().set("k1", "v1").set("k2", "v2"));
+        tx.commit().await?;
+        let mut tx = table.new_transaction();
+        tx.update_properties(UpdateProperties::new().set("k1", "v3").remove("k2"));
+        tx.commit().await?;
+
+        // A new metadata version is committed without new snapshots.
+        let table = Table::open(tmp_dir.path().to_str().unwrap()).await?;
+        assert_ne!(table.current_metadata_file_location(), location);
+        assert_eq!(table.current_table_metadata().snapshots, snapshots);
+        let properties = table.properties();
+        assert_eq!(properties.get("k1").map(String::as_str), Some("v3"));
+        assert!(!properties.contains_key("k2"));
+
+        let mut properties = HashMap::new();
+        assert!(UpdateProperties::new()
+            .set("k", "v")
+            .remove("k")
+            .apply(&mut properties)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,801 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::types::{Any, Field, List, Map, Primitive, Schema, Struct, TableMetadata};
+use crate::{Error, ErrorKind, Result};
+
+/// A change of [`UpdateSchema`].
+#[derive(Clone)]
+enum SchemaChange {
+    AddColumn {
+        parent: Option<String>,
+        name: String,
+        field_type: Any,
+    },
+    DeleteColumn(String),
+    RenameColumn {
+        name: String,
+        new_name: String,
+    },
+    UpdateColumn {
+        name: String,
+        field_type: Primitive,
+    },
+    UpdateColumnDoc {
+        name: String,
+        doc: String,
+    },
+    MakeColumnOptional(String),
+    MoveColumn {
+        name: String,
+        position: MovePosition,
+    },
+    SetIdentifierFields(Vec<String>),
+}
+
+#[derive(Clone)]
+enum MovePosition {
+    First,
+    Before(String),
+    After(String),
+}
+
+/// UpdateSchema evolves the current schema of a table into a new schema,
+/// committed by [`crate::transaction::Transaction::update_schema`].
+///
+/// Columns are named by their path from the top level like `a.b`, only
+/// columns nested in structs can be named. Changes are applied in order,
+/// so a column added or renamed by a change is known by its new name to
+/// later changes.
+///
+/// Only changes compatible with existing data files are allowed: added
+/// columns are optional and get new field ids after `last-column-id` of the
+/// table, and types can only be widened.
+///
+/// # Examples
+///
+/// ```no_run
+/// use icelake::transaction::UpdateSchema;
+/// use icelake::types::{Any, Primitive};
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut tx = table.new_transaction();
+/// tx.update_schema(
+///     UpdateSchema::new()
+///         .add_column(None, "ts", Any::Primitive(Primitive::Timestampz))
+///         .rename_column("data", "payload")
+///         .move_first("ts"),
+/// );
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct UpdateSchema {
+    changes: Vec<SchemaChange>,
+}
+
+impl UpdateSchema {
+    /// Create an empty schema update.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an optional column to the top level, or to the struct column
+    /// `parent`.
+    ///
+    /// Fields nested in `field_type` get new ids too, their ids in
+    /// `field_type` are ignored.
+    pub fn add_column(
+        mut self,
+        parent: Option<&str>,
+        name: impl Into<String>,
+        field_type: Any,
+    ) -> Self {
+        self.changes.push(SchemaChange::AddColumn {
+            parent: parent.map(|v| v.to_string()),
+            name: name.into(),
+            field_type,
+        });
+        self
+    }
+
+    /// Delete a column.
+    ///
+    /// Columns referenced by the default partition spec, sort order or
+    /// identifier fields can't be deleted.
+    pub fn delete_column(mut self, name: impl Into<String>) -> Self {
+        self.changes.push(SchemaChange::DeleteColumn(name.into()));
+        self
+    }
+
+    /// Rename a column, `new_name` is the name in its parent.
+    pub fn rename_column(mut self, name: impl Into<String>, new_name: impl Into<String>) -> Self {
+        self.changes.push(SchemaChange::RenameColumn {
+            name: name.into(),
+            new_name: new_name.into(),
+        });
+        self
+    }
+
+    /// Widen the type of a primitive column, allowed promotions are `int`
+    /// to `long`, `float` to `double` and decimals to a larger precision
+    /// with the same scale.
+    pub fn update_column(mut self, name: impl Into<String>, field_type: Primitive) -> Self {
+        self.changes.push(SchemaChange::UpdateColumn {
+            name: name.into(),
+            field_type,
+        });
+        self
+    }
+
+    /// Update the doc of a column.
+    pub fn update_column_doc(mut self, name: impl Into<String>, doc: impl Into<String>) -> Self {
+        self.changes.push(SchemaChange::UpdateColumnDoc {
+            name: name.into(),
+            doc: doc.into(),
+        });
+        self
+    }
+
+    /// Make a required column optional.
+    pub fn make_column_optional(mut self, name: impl Into<String>) -> Self {
+        self.changes
+            .push(SchemaChange::MakeColumnOptional(name.into()));
+        self
+    }
+
+    /// Move a column to the first of its parent.
+    pub fn move_first(mut self, name: impl Into<String>) -> Self {
+        self.changes.push(SchemaChange::MoveColumn {
+            name: name.into(),
+            position: MovePosition::First,
+        });
+        self
+    }
+
+    /// Move a column before `before`, which must have the same parent.
+    pub fn move_before(mut self, name: impl Into<String>, before: impl Into<String>) -> Self {
+        self.changes.push(SchemaChange::MoveColumn {
+            name: name.into(),
+            position: MovePosition::Before(before.into()),
+        });
+        self
+    }
+
+    /// Move a column after `after`, which must have the same parent.
+    pub fn move_after(mut self, name: impl Into<String>, after: impl Into<String>) -> Self {
+        self.changes.push(SchemaChange::MoveColumn {
+            name: name.into(),
+            position: MovePosition::After(after.into()),
+        });
+        self
+    }
+
+    /// Set identifier fields of the schema, which identify rows like a
+    /// primary key, and are the default equality fields of
+    /// [`crate::io::delta_writer::DeltaWriter`]. An empty list unsets them.
+    ///
+    /// Identifier fields must be required primitive columns other than
+    /// `float` and `double`, and can only be nested in required structs.
+    pub fn set_identifier_fields(
+        mut self,
+        names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.changes.push(SchemaChange::SetIdentifierFields(
+            names.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Apply changes to the current schema of the table, returns the new
+    /// schema and the last assigned column id.
+    ///
+    /// Id of the new schema is the id of an existing identical schema, or a
+    /// new id otherwise.
+    pub(crate) fn apply(self, metadata: &TableMetadata) -> Result<(Schema, i32)> {
+        let base = metadata.current_schema()?;
+        // Columns that can't be deleted, except identifier fields which may
+        // be changed.
+        let referenced: HashSet<i32> = metadata
+            .current_partition_spec()?
+            .fields
+            .iter()
+            .map(|v| v.source_column_id)
+            .chain(
+                metadata
+                    .current_sort_order()?
+                    .fields
+                    .iter()
+                    .map(|v| v.source_column_id),
+            )
+            .collect();
+
+        let mut fields = base.fields.clone();
+        let mut identifier_field_ids = base.identifier_field_ids.clone();
+        let mut last_column_id = metadata.last_column_id;
+        for change in self.changes {
+            match change {
+                SchemaChange::AddColumn {
+                    parent,
+                    name,
+                    field_type,
+                } => {
+                    if name.is_empty() || name.contains('.') {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Invalid column name {name:?}"),
+                        ));
+                    }
+                    let parent: Vec<&str> = parent.as_deref().map(path).unwrap_or_default();
+                    with_fields(&mut fields, &parent, |fields| {
+                        check_unique(fields, &name)?;
+                        last_column_id += 1;
+                        let id = last_column_id;
+                        fields.push(Field {
+                            id,
+                            name,
+                            required: false,
+                            field_type: assign_ids(&field_type, &mut last_column_id),
+                            comment: None,
+                            initial_default: None,
+                            write_default: None,
+                        });
+                        Ok(())
+                    })?;
+                }
+                SchemaChange::DeleteColumn(name) => {
+                    with_column(&mut fields, &name, |fields, idx| {
+                        let mut ids = vec![];
+                        collect_ids(&fields[idx], &mut ids);
+                        if ids.iter().any(|id| {
+                            referenced.contains(id)
+                                || identifier_field_ids.iter().flatten().any(|v| v == id)
+                        }) {
+                            return Err(Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!(
+                                    "Can't delete column {name} referenced by partition spec, sort order or identifier fields"
+                                ),
+                            ));
+                        }
+                        fields.remove(idx);
+                        Ok(())
+                    })?;
+                }
+                SchemaChange::RenameColumn { name, new_name } => {
+                    if new_name.is_empty() || new_name.contains('.') {
+                        return Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Invalid column name {new_name:?}"),
+                        ));
+                    }
+                    with_column(&mut fields, &name, |fields, idx| {
+                        if fields[idx].name != new_name {
+                            check_unique(fields, &new_name)?;
+                        }
+                        fields[idx].name = new_name;
+                        Ok(())
+                    })?;
+                }
+                SchemaChange::UpdateColumn { name, field_type } => {
+                    with_column(&mut fields, &name, |fields, idx| {
+                        let field = &mut fields[idx];
+                        match &field.field_type {
+                            Any::Primitive(v) if can_promote(v, &field_type) => {
+                                field.field_type = Any::Primitive(field_type);
+                                Ok(())
+                            }
+                            v => Err(Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!("Can't change type of column {name} from {v:?} to {field_type:?}"),
+                            )),
+                        }
+                    })?;
+                }
+                SchemaChange::UpdateColumnDoc { name, doc } => {
+                    with_column(&mut fields, &name, |fields, idx| {
+                        fields[idx].comment = Some(doc);
+                        Ok(())
+                    })?;
+                }
+                SchemaChange::MakeColumnOptional(name) => {
+                    with_column(&mut fields, &name, |fields, idx| {
+                        fields[idx].required = false;
+                        Ok(())
+                    })?;
+                }
+                SchemaChange::MoveColumn { name, position } => {
+                    let (parent, _) = split(&name);
+                    let target = match &position {
+                        MovePosition::First => None,
+                        MovePosition::Before(v) | MovePosition::After(v) => {
+                            let (target_parent, target) = split(v);
+                            if target_parent != parent {
+                                return Err(Error::new(
+                                    ErrorKind::IcebergDataInvalid,
+                                    format!("Can't move column {name} out of its parent to {v}"),
+                                ));
+                            }
+                            Some(target)
+                        }
+                    };
+                    with_column(&mut fields, &name, |fields, idx| {
+                        let field = fields.remove(idx);
+                        let pos = match target {
+                            None => 0,
+                            Some(target) => {
+                                let pos = fields.iter().position(|v| v.name == target).ok_or_else(
+                                    || {
+                                        Error::new(
+                                            ErrorKind::IcebergDataInvalid,
+                                            format!("Can't move column {name} relative to itself or an unknown column"),
+                                        )
+                                    },
+                                )?;
+                                match position {
+                                    MovePosition::After(_) => pos + 1,
+                                    _ => pos,
+                                }
+                            }
+                        };
+                        fields.insert(pos, field);
+                        Ok(())
+                    })?;
+                }
+                SchemaChange::SetIdentifierFields(names) => {
+                    let schema = Schema {
+                        schema_id: 0,
+                        identifier_field_ids: None,
+                        fields: fields.clone(),
+                    };
+                    let ids = names
+                        .iter()
+                        .map(|name| {
+                            schema.field_id_by_name(name).ok_or_else(|| {
+                                Error::new(
+                                    ErrorKind::IcebergDataInvalid,
+                                    format!("Identifier field {name} is not found"),
+                                )
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    identifier_field_ids = (!ids.is_empty()).then_some(ids);
+                }
+            }
+        }
+
+        // Identifier fields are checked after all changes, like those made
+        // optional.
+        for id in identifier_field_ids.iter().flatten() {
+            check_identifier_field(&fields, *id)?;
+        }
+        let mut schema = Schema {
+            schema_id: 0,
+            identifier_field_ids,
+            fields,
+        };
+        schema.schema_id = match metadata.schemas.iter().find(|v| {
+            v.fields == schema.fields && v.identifier_field_ids == schema.identifier_field_ids
+        }) {
+            Some(v) => v.schema_id,
+            None => {
+                metadata
+                    .schemas
+                    .iter()
+                    .map(|v| v.schema_id)
+                    .max()
+                    .unwrap_or(-1)
+                    + 1
+            }
+        };
+        Ok((schema, last_column_id))
+    }
+}
+
+/// Check the field of `id` is a required primitive field, which is not
+/// `float` or `double` and only nested in required structs.
+fn check_identifier_field(fields: &[Field], id: i32) -> Result<()> {
+    for field in fields {
+        let nested = match &field.field_type {
+            Any::Struct(s) => s.fields(),
+            _ => &[],
+        };
+        let mut nested_ids = vec![];
+        collect_ids(field, &mut nested_ids);
+        if !nested_ids.contains(&id) {
+            continue;
+        }
+        let invalid = |reason: &str| {
+            Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!(
+                    "Invalid identifier field {id}: column {} {reason}",
+                    field.name
+                ),
+            ))
+        };
+        if !field.required {
+            return invalid("is optional");
+        }
+        return match &field.field_type {
+            _ if field.id != id && !nested.is_empty() => check_identifier_field(nested, id),
+            Any::Primitive(Primitive::Float | Primitive::Double) => invalid("is float or double"),
+            Any::Primitive(_) if field.id == id => Ok(()),
+            _ => invalid("is not primitive, or is a list or map"),
+        };
+    }
+    Err(Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Identifier field {id} is not found"),
+    ))
+}
+
+/// Split path `a.b.c` of a column into its parent path `a.b` and name `c`.
+fn split(name: &str) -> (&str, &str) {
+    name.rsplit_once('.').unwrap_or(("", name))
+}
+
+fn path(name: &str) -> Vec<&str> {
+    if name.is_empty() {
+        vec![]
+    } else {
+        name.split('.').collect()
+    }
+}
+
+fn check_unique(fields: &[Field], name: &str) -> Result<()> {
+    if fields.iter().any(|v| v.name == name) {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Column {name} already exists"),
+        ));
+    }
+    Ok(())
+}
+
+/// Call `f` with fields of the struct column at `parent`, structs on the
+/// path are rebuilt from the changed fields.
+fn with_fields<T>(
+    fields: &mut Vec<Field>,
+    parent: &[&str],
+    f: impl FnOnce(&mut Vec<Field>) -> Result<T>,
+) -> Result<T> {
+    let Some((first, rest)) = parent.split_first() else {
+        return f(fields);
+    };
+    let field = fields
+        .iter_mut()
+        .find(|v| v.name == *first)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Column {first} is not found"),
+            )
+        })?;
+    let Any::Struct(s) = &field.field_type else {
+        return Err(Error::new(
+            ErrorKind::IcebergFeatureUnsupported,
+            format!("Updating fields nested in non-struct column {first}"),
+        ));
+    };
+    let mut children = s.fields().to_vec();
+    let result = with_fields(&mut children, rest, f)?;
+    field.field_type = Any::Struct(Arc::new(Struct::new(children)));
+    Ok(result)
+}
+
+/// Call `f` with fields of the parent of column `name` and its index.
+fn with_column<T>(
+    fields: &mut Vec<Field>,
+    name: &str,
+    f: impl FnOnce(&mut Vec<Field>, usize) -> Result<T>,
+) -> Result<T> {
+    let (parent, name) = split(name);
+    with_fields(fields, &path(parent), |fields| {
+        let idx = fields.iter().position(|v| v.name == name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Column {name} is not found"),
+            )
+        })?;
+        f(fields, idx)
+    })
+}
+
+/// Returns the type with new ids assigned to nested fields.
+fn assign_ids(field_type: &Any, last_column_id: &mut i32) -> Any {
+    let mut next_id = || {
+        *last_column_id += 1;
+        *last_column_id
+    };
+    match field_type {
+        Any::Primitive(_) => field_type.clone(),
+        Any::Struct(s) => {
+            let ids: Vec<i32> = s.fields().iter().map(|_| next_id()).collect();
+            let fields = s
+                .fields()
+                .iter()
+                .zip(ids)
+                .map(|(field, id)| Field {
+                    id,
+                    field_type: assign_ids(&field.field_type, last_column_id),
+                    ..field.clone()
+                })
+                .collect();
+            Any::Struct(Arc::new(Struct::new(fields)))
+        }
+        Any::List(list) => {
+            let element_id = next_id();
+            Any::List(List {
+                element_id,
+                element_required: list.element_required,
+                element_type: Box::new(assign_ids(&list.element_type, last_column_id)),
+            })
+        }
+        Any::Map(map) => {
+            let key_id = next_id();
+            let value_id = next_id();
+            Any::Map(Map {
+                key_id,
+                key_type: Box::new(assign_ids(&map.key_type, last_column_id)),
+                value_id,
+                value_required: map.value_required,
+                value_type: Box::new(assign_ids(&map.value_type, last_column_id)),
+            })
+        }
+    }
+}
+
+/// Collect ids of the field and its nested fields.
+fn collect_ids(field: &Field, ids: &mut Vec<i32>) {
+    fn collect(field_type: &Any, ids: &mut Vec<i32>) {
+        match field_type {
+            Any::Primitive(_) => {}
+            Any::Struct(s) => s.fields().iter().for_each(|v| collect_ids(v, ids)),
+            Any::List(list) => {
+                ids.push(list.element_id);
+                collect(&list.element_type, ids);
+            }
+            Any::Map(map) => {
+                ids.extend([map.key_id, map.value_id]);
+                collect(&map.key_type, ids);
+                collect(&map.value_type, ids);
+            }
+        }
+    }
+
+    ids.push(field.id);
+    collect(&field.field_type, ids);
+}
+
+fn can_promote(from: &Primitive, to: &Primitive) -> bool {
+    match (from, to) {
+        (Primitive::Int, Primitive::Long) | (Primitive::Float, Primitive::Double) => true,
+        (
+            Primitive::Decimal { precision, scale },
+            Primitive::Decimal {
+                precision: to_precision,
+                scale: to_scale,
+            },
+        ) => scale == to_scale && precision <= to_precision,
+        _ => from == to,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::types::parse_table_metadata;
+    use crate::Table;
+
+    fn metadata() -> TableMetadata {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        parse_table_metadata(&fs::read(path).unwrap()).unwrap()
+    }
+
+    fn field(id: i32, name: &str, field_type: Any) -> Field {
+        Field {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        }
+    }
+
+    #[test]
+    fn test_add_nested_columns() {
+        let metadata = metadata();
+        let (schema, last_column_id) = UpdateSchema::new()
+            .add_column(
+                None,
+                "point",
+                Any::Struct(Arc::new(Struct::new(vec![
+                    field(0, "x", Any::Primitive(Primitive::Double)),
+                    field(
+                        0,
+                        "tags",
+                        Any::List(List {
+                            element_id: 0,
+                            element_required: true,
+                            element_type: Box::new(Any::Primitive(Primitive::String)),
+                        }),
+                    ),
+                ]))),
+            )
+            .add_column(Some("point"), "y", Any::Primitive(Primitive::Double))
+            .apply(&metadata)
+            .unwrap();
+
+        assert_eq!(schema.schema_id, 1);
+        assert_eq!(last_column_id, 7);
+        assert_eq!(
+            schema.fields[2],
+            field(
+                3,
+                "point",
+                Any::Struct(Arc::new(Struct::new(vec![
+                    field(4, "x", Any::Primitive(Primitive::Double)),
+                    field(
+                        5,
+                        "tags",
+                        Any::List(List {
+                            element_id: 6,
+                            element_required: true,
+                            element_type: Box::new(Any::Primitive(Primitive::String)),
+                        }),
+                    ),
+                    field(7, "y", Any::Primitive(Primitive::Double)),
+                ])))
+            )
+        );
+
+        assert!(UpdateSchema::new()
+            .add_column(None, "id", Any::Primitive(Primitive::Int))
+            .apply(&metadata)
+            .is_err());
+        assert!(UpdateSchema::new()
+            .add_column(Some("id"), "x", Any::Primitive(Primitive::Int))
+            .apply(&metadata)
+            .is_err());
+    }
+
+    #[test]
+    fn test_update_columns() {
+        let metadata = metadata();
+        let (schema, last_column_id) = UpdateSchema::new()
+            .delete_column("id")
+            .add_column(None, "id", Any::Primitive(Primitive::Int))
+            .update_column("id", Primitive::Long)
+            .rename_column("data", "payload")
+            .update_column_doc("payload", "raw data")
+            .move_first("id")
+            .apply(&metadata)
+            .unwrap();
+        assert_eq!(last_column_id, 3);
+        assert_eq!(
+            schema.fields,
+            vec![
+                field(3, "id", Any::Primitive(Primitive::Long)),
+                Field {
+                    comment: Some("raw data".to_string()),
+                    ..field(2, "payload", Any::Primitive(Primitive::String))
+                },
+            ]
+        );
+
+        let (schema, _) = UpdateSchema::new()
+            .move_after("id", "data")
+            .move_before("data", "id")
+            .apply(&metadata)
+            .unwrap();
+        assert_eq!(schema.fields[0].name, "data");
+        assert_eq!(schema.schema_id, 1);
+
+        // Reverting changes reuses the existing schema.
+        let (schema, _) = UpdateSchema::new()
+            .move_after("id", "data")
+            .move_first("id")
+            .apply(&metadata)
+            .unwrap();
+        assert_eq!(schema.schema_id, 0);
+
+        assert!(UpdateSchema::new()
+            .update_column("data", Primitive::Binary)
+            .apply(&metadata)
+            .is_err());
+        assert!(UpdateSchema::new()
+            .rename_column("data", "id")
+            .apply(&metadata)
+            .is_err());
+        assert!(UpdateSchema::new()
+            .move_after("id", "id")
+            .apply(&metadata)
+            .is_err());
+        assert!(UpdateSchema::new()
+            .delete_column("unknown")
+            .apply(&metadata)
+            .is_err());
+    }
+
+    #[test]
+    fn test_delete_referenced_column() {
+        let mut metadata = metadata();
+        metadata.schemas[0].identifier_field_ids = Some(vec![1]);
+        assert!(UpdateSchema::new()
+            .delete_column("id")
+            .apply(&metadata)
+            .is_err());
+    }
+
+    #[test]
+    fn test_set_identifier_fields() {
+        let mut metadata = metadata();
+        metadata.schemas[0].fields[0].required = true;
+        let (schema, _) = UpdateSchema::new()
+            .set_identifier_fields(["id"])
+            .apply(&metadata)
+            .unwrap();
+        assert_eq!(schema.identifier_field_ids, Some(vec![1]));
+        assert_eq!(schema.identifier_fields()[0].name, "id");
+        assert_ne!(schema.schema_id, 0);
+
+        metadata.schemas[0].identifier_field_ids = Some(vec![1]);
+        // Identifier fields must be required, and can't be deleted unless
+        // unset.
+        for update in [
+            UpdateSchema::new().set_identifier_fields(["data"]),
+            UpdateSchema::new().set_identifier_fields(["unknown"]),
+            UpdateSchema::new().make_column_optional("id"),
+            UpdateSchema::new().delete_column("id"),
+        ] {
+            assert!(update.apply(&metadata).is_err());
+        }
+        let (schema, _) = UpdateSchema::new()
+            .set_identifier_fields(Vec::<String>::new())
+            .delete_column("id")
+            .apply(&metadata)
+            .unwrap();
+        assert_eq!(schema.identifier_field_ids, None);
+        assert!(schema.identifier_fields().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_commit_update_schema() {
+        let tmp_dir = prepare_table_dir();
+        let table_root = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(table_root).await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.update_schema(
+            UpdateSchema::new()
+                .add_column(None, "ts", Any::Primitive(Primitive::Timestampz))
+                .delete_column("data"),
+        );
+        tx.commit().await.unwrap();
+
+        let table = Table::open(table_root).await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.current_schema_id, 1);
+        assert_eq!(metadata.last_column_id, 3);
+        assert_eq!(metadata.schemas.len(), 2);
+        let names: Vec<_> = metadata
+            .current_schema()
+            .unwrap()
+            .fields
+            .iter()
+            .map(|v| (v.id, v.name.as_str()))
+            .collect();
+        assert_eq!(names, vec![(1, "id"), (3, "ts")]);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::types::{Any, NullOrder, SortDirection, SortField, SortOrder, TableMetadata, Transform};
+use crate::{Error, ErrorKind, Result};
+
+/// UpdateSortOrder replaces the default sort order of a table, committed by
+/// [`crate::transaction::Transaction::update_sort_order`].
+///
+/// Columns are named by their path from the top level like `a.b`. An update
+/// without sort fields makes the table unsorted.
+///
+/// Existing data files are not rewritten, they keep the id of the sort order
+/// they were written with.
+///
+/// # Examples
+///
+/// ```no_run
+/// use icelake::transaction::UpdateSortOrder;
+/// use icelake::types::{NullOrder, SortDirection, Transform};
+///
+/// # async fn example() -> icelake::Result<()> {
+/// let mut table = icelake::Table::open("/path/to/table").await?;
+/// let mut tx = table.new_transaction();
+/// tx.update_sort_order(
+///     UpdateSortOrder::new()
+///         .asc("id")
+///         .sort_by("ts", Transform::Day, SortDirection::DESC, NullOrder::Last),
+/// );
+/// tx.commit().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct UpdateSortOrder {
+    fields: Vec<(String, Transform, SortDirection, NullOrder)>,
+}
+
+impl UpdateSortOrder {
+    /// Create an update to an unsorted order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sort by the column ascending with nulls first.
+    pub fn asc(self, column: impl Into<String>) -> Self {
+        self.sort_by(
+            column,
+            Transform::Identity,
+            SortDirection::ASC,
+            NullOrder::First,
+        )
+    }
+
+    /// Sort by the column descending with nulls last.
+    pub fn desc(self, column: impl Into<String>) -> Self {
+        self.sort_by(
+            column,
+            Transform::Identity,
+            SortDirection::DESC,
+            NullOrder::Last,
+        )
+    }
+
+    /// Sort by transformed values of the column.
+    pub fn sort_by(
+        mut self,
+        column: impl Into<String>,
+        transform: Transform,
+        direction: SortDirection,
+        null_order: NullOrder,
+    ) -> Self {
+        self.fields
+            .push((column.into(), transform, direction, null_order));
+        self
+    }
+
+    /// Resolve the sort order against the current schema of the table.
+    ///
+    /// Id of the sort order is the id of an existing identical sort order,
+    /// or a new id otherwise. Id `0` is reserved for the unsorted order.
+    pub(crate) fn apply(self, metadata: &TableMetadata) -> Result<SortOrder> {
+        let schema = metadata.current_schema()?;
+        let fields = self
+            .fields
+            .into_iter()
+            .map(|(column, transform, direction, null_order)| {
+                let field = schema
+                    .field_id_by_name(&column)
+                    .and_then(|id| schema.field_by_id(id))
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Sort column {column} is not found"),
+                        )
+                    })?;
+                if !matches!(field.field_type, Any::Primitive(_)) {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Sort column {column} is not a primitive column"),
+                    ));
+                }
+                transform.result_type(&field.field_type)?;
+                Ok(SortField {
+                    source_column_id: field.id,
+                    transform,
+                    direction,
+                    null_order,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let order_id = match metadata.sort_orders.iter().find(|v| v.fields == fields) {
+            Some(v) => v.order_id,
+            None if fields.is_empty() => 0,
+            None => {
+                metadata
+                    .sort_orders
+                    .iter()
+                    .map(|v| v.order_id)
+                    .max()
+                    .unwrap_or(0)
+                    + 1
+            }
+        };
+        Ok(SortOrder { order_id, fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::prepare_table_dir;
+    use crate::types::parse_table_metadata;
+    use crate::Table;
+
+    fn metadata() -> TableMetadata {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        parse_table_metadata(&fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_update_sort_order() {
+        let mut metadata = metadata();
+        let sort_order = UpdateSortOrder::new()
+            .asc("id")
+            .sort_by(
+                "data",
+                Transform::Truncate(2),
+                SortDirection::DESC,
+                NullOrder::First,
+            )
+            .apply(&metadata)
+            .unwrap();
+        assert_eq!(
+            sort_order,
+            SortOrder {
+                order_id: 1,
+                fields: vec![
+                    SortField {
+                        source_column_id: 1,
+                        transform: Transform::Identity,
+                        direction: SortDirection::ASC,
+                        null_order: NullOrder::First,
+                    },
+                    SortField {
+                        source_column_id: 2,
+                        transform: Transform::Truncate(2),
+                        direction: SortDirection::DESC,
+                        null_order: NullOrder::First,
+                    },
+                ],
+            }
+        );
+
+        metadata.sort_orders.push(sort_order.clone());
+        let order_id = |update: UpdateSortOrder| update.apply(&metadata).unwrap().order_id;
+        assert_eq!(
+            order_id(UpdateSortOrder::new().asc("id").sort_by(
+                "data",
+                Transform::Truncate(2),
+                SortDirection::DESC,
+                NullOrder::First,
+            )),
+            1
+        );
+        assert_eq!(order_id(UpdateSortOrder::new().desc("id")), 2);
+        assert_eq!(order_id(UpdateSortOrder::new()), 0);
+
+        assert!(UpdateSortOrder::new()
+            .asc("unknown")
+            .apply(&metadata)
+            .is_err());
+        assert!(UpdateSortOrder::new()
+            .sort_by("id", Transform::Day, SortDirection::ASC, NullOrder::First)
+            .apply(&metadata)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_update_sort_order() {
+        let tmp_dir = prepare_table_dir();
+        let table_root = tmp_dir.path().to_str().unwrap();
+        let mut table = Table::open(table_root).await.unwrap();
+
+        let mut tx = table.new_transaction();
+        tx.update_sort_order(UpdateSortOrder::new().desc("id"));
+        tx.commit().await.unwrap();
+
+        let table = Table::open(table_root).await.unwrap();
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.default_sort_order_id, 1);
+        assert_eq!(metadata.sort_orders.len(), 2);
+        assert_eq!(
+            metadata.current_sort_order().unwrap().fields[0].direction,
+            SortDirection::DESC
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! validation module checks changes committed concurrently since the base
+//! snapshot of a transaction, so that a transaction computed from the base
+//! doesn't silently override them.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::io::position_delete_writer::POSITION_DELETE_FILE_PATH_FIELD_ID;
+use crate::types::expression::{DataFileEvaluator, Predicate};
+use crate::types::{
+    DataContentType, DataFile, ManifestStatus, Snapshot, StructValue, TableMetadata,
+};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// Validation is a rule checked against files added and deleted by
+/// snapshots committed since the base snapshot.
+///
+/// Snapshots of operation `replace` are skipped since they don't change
+/// table data, for example, compaction, except by
+/// [`Validation::RewrittenDeleteFiles`].
+#[derive(Clone)]
+pub(crate) enum Validation {
+    /// No data file that might match the filter is added.
+    NoConflictingData(Predicate),
+    /// No delete file that might match the filter is added, and no data
+    /// file that might match the filter is deleted.
+    NoConflictingDeletes(Predicate),
+    /// Data files are replaced, no delete file that might apply to them is
+    /// added.
+    ReplacedDataFiles(DataFileSet),
+    /// Delete files of the paths are replaced, they are still live, not
+    /// deleted by any snapshot including `replace` snapshots.
+    RewrittenDeleteFiles(HashSet<String>),
+}
+
+/// Paths and partitions of data files checked by
+/// [`Validation::ReplacedDataFiles`].
+#[derive(Clone, Default)]
+pub(crate) struct DataFileSet {
+    paths: HashSet<String>,
+    partitions: HashSet<StructValue>,
+}
+
+impl<'a> FromIterator<&'a DataFile> for DataFileSet {
+    fn from_iter<T: IntoIterator<Item = &'a DataFile>>(iter: T) -> Self {
+        let mut set = DataFileSet::default();
+        for data_file in iter {
+            set.paths.insert(data_file.file_path.clone());
+            set.partitions.insert(data_file.partition.clone());
+        }
+        set
+    }
+}
+
+impl DataFileSet {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Returns true if the delete file might apply to a data file of the
+    /// set, by partition and, for position deletes, bounds of file paths.
+    ///
+    /// Equality deletes of unpartitioned specs apply to all partitions.
+    fn might_apply(&self, delete_file: &DataFile) -> bool {
+        match delete_file.content {
+            DataContentType::Data => false,
+            DataContentType::EqualityDeletes => {
+                delete_file.partition.type_info().is_empty()
+                    || self.partitions.contains(&delete_file.partition)
+            }
+            DataContentType::PostionDeletes => {
+                if !self.partitions.contains(&delete_file.partition) {
+                    return false;
+                }
+                let bound = |bounds: &Option<HashMap<i32, Vec<u8>>>| {
+                    bounds
+                        .as_ref()
+                        .and_then(|v| v.get(&POSITION_DELETE_FILE_PATH_FIELD_ID))
+                        .cloned()
+                };
+                match (
+                    bound(&delete_file.lower_bounds),
+                    bound(&delete_file.upper_bounds),
+                ) {
+                    (Some(lower), Some(upper)) => self.paths.iter().any(|v| {
+                        lower.as_slice() <= v.as_bytes() && v.as_bytes() <= upper.as_slice()
+                    }),
+                    _ => true,
+                }
+            }
+        }
+    }
+}
+
+impl Validation {
+    /// Returns the filter of files checked, all files if `None`.
+    fn filter(&self) -> Option<&Predicate> {
+        match self {
+            Validation::NoConflictingData(filter) | Validation::NoConflictingDeletes(filter) => {
+                Some(filter)
+            }
+            Validation::ReplacedDataFiles(_) | Validation::RewrittenDeleteFiles(_) => None,
+        }
+    }
+
+    /// Returns true if `replace` snapshots are checked by this rule.
+    fn checks_replace(&self) -> bool {
+        matches!(self, Validation::RewrittenDeleteFiles(_))
+    }
+
+    /// Returns true if the change of `data_file` conflicts with this rule.
+    fn conflicts(&self, status: ManifestStatus, data_file: &DataFile) -> bool {
+        match (self, status, data_file.content) {
+            (Validation::NoConflictingData(_), ManifestStatus::Added, DataContentType::Data) => {
+                true
+            }
+            (Validation::NoConflictingDeletes(_), ManifestStatus::Added, content) => {
+                content != DataContentType::Data
+            }
+            (
+                Validation::NoConflictingDeletes(_),
+                ManifestStatus::Deleted,
+                DataContentType::Data,
+            ) => true,
+            (Validation::ReplacedDataFiles(data_files), ManifestStatus::Added, _) => {
+                data_files.might_apply(data_file)
+            }
+            (Validation::RewrittenDeleteFiles(paths), ManifestStatus::Deleted, content) => {
+                content != DataContentType::Data && paths.contains(&data_file.file_path)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Check `validations` against snapshots of `branch` committed after
+/// `base_snapshot_id`, all snapshots of the branch if `None`.
+///
+/// Returns error of [`ErrorKind::ValidationFailed`] if a rule is violated,
+/// or if the base snapshot is not an ancestor of the branch head anymore.
+pub(crate) async fn validate(
+    table: &Table,
+    branch: &str,
+    base_snapshot_id: Option<i64>,
+    validations: &[Validation],
+) -> Result<()> {
+    if validations.is_empty() {
+        return Ok(());
+    }
+    let metadata = table.current_table_metadata();
+    let snapshots = snapshots_since(metadata, branch, base_snapshot_id)?;
+    if snapshots.is_empty() {
+        return Ok(());
+    }
+
+    // Filters are bound to each partition spec.
+    let mut evaluators: HashMap<i32, Vec<Option<DataFileEvaluator>>> = HashMap::new();
+    for partition_spec in &metadata.partition_specs {
+        let v = validations
+            .iter()
+            .map(|validation| {
+                validation
+                    .filter()
+                    .map(|filter| {
+                        DataFileEvaluator::try_new_with_case_sensitive(
+                            filter,
+                            metadata.current_schema()?,
+                            partition_spec,
+                            table.case_sensitive(),
+                        )
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        evaluators.insert(partition_spec.spec_id, v);
+    }
+
+    let check_replace = validations.iter().any(Validation::checks_replace);
+    for snapshot in snapshots {
+        let replace = is_replace(snapshot);
+        if replace && !check_replace {
+            continue;
+        }
+        let manifest_list = snapshot.load_manifest_list(table).await?;
+        for manifest_list_entry in manifest_list
+            .entries
+            .iter()
+            .filter(|v| v.added_snapshot_id == snapshot.snapshot_id)
+        {
+            let spec_evaluators = evaluators
+                .get(&manifest_list_entry.partition_spec_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let manifest = manifest_list_entry.load_manifest(table).await?;
+            for entry in manifest
+                .entries
+                .iter()
+                .filter(|v| v.snapshot_id == Some(snapshot.snapshot_id))
+            {
+                let violated =
+                    validations
+                        .iter()
+                        .zip(spec_evaluators)
+                        .find(|(validation, evaluator)| {
+                            (!replace || validation.checks_replace())
+                                && validation.conflicts(entry.status, &entry.data_file)
+                                && evaluator
+                                    .as_ref()
+                                    .is_none_or(|v| v.might_match(&entry.data_file))
+                        });
+                if let Some((validation, _)) = violated {
+                    let change = match validation {
+                        Validation::NoConflictingData(_) => "Found conflicting data file",
+                        Validation::NoConflictingDeletes(_) => "Found conflicting deletes",
+                        Validation::ReplacedDataFiles(_) => {
+                            "Found new deletes for replaced data files"
+                        }
+                        Validation::RewrittenDeleteFiles(_) => {
+                            "Found rewritten delete file removed"
+                        }
+                    };
+                    let mut err = Error::new(
+                        ErrorKind::ValidationFailed,
+                        format!("{change} of concurrent snapshot {}", snapshot.snapshot_id),
+                    )
+                    .with_context("file_path", &entry.data_file.file_path);
+                    if let Some(filter) = validation.filter() {
+                        err = err.with_context("filter", format!("{filter:?}"));
+                    }
+                    return Err(err);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns snapshots of `branch` committed after `base_snapshot_id`, from
+/// the newest.
+fn snapshots_since<'a>(
+    metadata: &'a TableMetadata,
+    branch: &str,
+    base_snapshot_id: Option<i64>,
+) -> Result<Vec<&'a Snapshot>> {
+    let Some(head) = metadata.snapshot_by_ref(branch) else {
+        return match base_snapshot_id {
+            None => Ok(vec![]),
+            Some(id) => Err(history_unknown(id, branch)),
+        };
+    };
+    if let Some(id) = base_snapshot_id {
+        if !metadata.is_ancestor_of(id, head.snapshot_id) {
+            return Err(history_unknown(id, branch));
+        }
+    }
+    Ok(head
+        .ancestors(metadata)
+        .into_iter()
+        .take_while(|v| Some(v.snapshot_id) != base_snapshot_id)
+        .collect())
+}
+
+fn is_replace(snapshot: &Snapshot) -> bool {
+    snapshot.summary.get("operation").map(|v| v.as_str()) == Some("replace")
+}
+
+fn history_unknown(base_snapshot_id: i64, branch: &str) -> Error {
+    Error::new(
+        ErrorKind::ValidationFailed,
+        format!(
+            "Cannot determine history of branch {branch} since snapshot {base_snapshot_id}, which is not an ancestor of the branch"
+        ),
+    )
+}
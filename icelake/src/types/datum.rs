@@ -0,0 +1,304 @@
+//! datum module provides [`Datum`], a primitive value with its type, and
+//! [`StructLike`] to access values of tuples like partitions by position.
+
+use std::cmp::Ordering;
+
+use rust_decimal::Decimal;
+
+use super::expression::compare_values;
+use super::{decode_bound, encode_bound};
+use super::{Any, AnyValue, Primitive, PrimitiveValue, StructValue};
+use crate::{Error, ErrorKind, Result};
+
+/// Datum is a primitive value with its type, like a partition value or a
+/// literal bound to a column.
+///
+/// Datums of the same type are ordered like iceberg orders values, datums
+/// of different types are not comparable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Datum {
+    ty: Primitive,
+    value: PrimitiveValue,
+}
+
+impl Datum {
+    /// Create a datum of given type, fails if the value is not of the type,
+    /// for example, a decimal of another scale.
+    pub fn new(ty: Primitive, value: PrimitiveValue) -> Result<Self> {
+        let matched = match (&ty, &value) {
+            (Primitive::Boolean, PrimitiveValue::Boolean(_))
+            | (Primitive::Int, PrimitiveValue::Int(_))
+            | (Primitive::Long, PrimitiveValue::Long(_))
+            | (Primitive::Float, PrimitiveValue::Float(_))
+            | (Primitive::Double, PrimitiveValue::Double(_))
+            | (Primitive::Date, PrimitiveValue::Date(_))
+            | (Primitive::Time, PrimitiveValue::Time(_))
+            | (Primitive::Timestamp, PrimitiveValue::Timestamp(_))
+            | (Primitive::Timestampz, PrimitiveValue::Timestampz(_))
+            | (Primitive::TimestampNs, PrimitiveValue::TimestampNs(_))
+            | (Primitive::TimestampzNs, PrimitiveValue::TimestampzNs(_))
+            | (Primitive::String, PrimitiveValue::String(_))
+            | (Primitive::Uuid, PrimitiveValue::Uuid(_))
+            | (Primitive::Binary, PrimitiveValue::Binary(_)) => true,
+            (Primitive::Decimal { precision, scale }, PrimitiveValue::Decimal(v)) => {
+                let digits = v.mantissa().unsigned_abs().to_string().len();
+                v.scale() == *scale as u32 && digits <= *precision as usize
+            }
+            (Primitive::Fixed(len), PrimitiveValue::Fixed(v)) => v.len() as u64 == *len,
+            _ => false,
+        };
+        if !matched {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Value {value:?} is not of type {ty:?}"),
+            ));
+        }
+        Ok(Self { ty, value })
+    }
+
+    /// Decode a datum of given type from the binary single value
+    /// serialization of iceberg, see [`Datum::to_bytes`].
+    pub fn try_from_bytes(ty: Primitive, bytes: &[u8]) -> Result<Self> {
+        let value = decode_bound(&ty, bytes).ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Bytes {bytes:?} are invalid for type {ty:?}"),
+            )
+        })?;
+        Self::new(ty, value)
+    }
+
+    /// Encode the datum by the binary single value serialization of
+    /// iceberg, which is how bounds are stored in manifests.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode_bound(&self.value)
+    }
+
+    /// Returns the type of the datum.
+    pub fn ty(&self) -> &Primitive {
+        &self.ty
+    }
+
+    /// Returns the value of the datum.
+    pub fn value(&self) -> &PrimitiveValue {
+        &self.value
+    }
+
+    /// Consume the datum to get its value.
+    pub fn into_value(self) -> PrimitiveValue {
+        self.value
+    }
+
+    /// Returns the value if it's a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.value {
+            PrimitiveValue::Boolean(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's an int or a date, dates are days from the
+    /// unix epoch.
+    pub fn as_int(&self) -> Option<i32> {
+        match self.value {
+            PrimitiveValue::Int(v) => Some(v),
+            PrimitiveValue::Date(_) => Some(i32::from_le_bytes(self.to_bytes().try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a long, a time or a timestamp, which are
+    /// microseconds, or nanoseconds for timestamps of nanosecond precision.
+    pub fn as_long(&self) -> Option<i64> {
+        match self.value {
+            PrimitiveValue::Long(v) => Some(v),
+            PrimitiveValue::Time(_)
+            | PrimitiveValue::Timestamp(_)
+            | PrimitiveValue::Timestampz(_)
+            | PrimitiveValue::TimestampNs(_)
+            | PrimitiveValue::TimestampzNs(_) => {
+                Some(i64::from_le_bytes(self.to_bytes().try_into().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a float.
+    pub fn as_float(&self) -> Option<f32> {
+        match self.value {
+            PrimitiveValue::Float(v) => Some(v.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a double.
+    pub fn as_double(&self) -> Option<f64> {
+        match self.value {
+            PrimitiveValue::Double(v) => Some(v.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a decimal.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self.value {
+            PrimitiveValue::Decimal(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            PrimitiveValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's fixed or binary.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.value {
+            PrimitiveValue::Fixed(v) | PrimitiveValue::Binary(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Datums of values are typed by the values, decimals are of the max
+/// precision `38` and fixed of the length of the value.
+impl From<PrimitiveValue> for Datum {
+    fn from(value: PrimitiveValue) -> Self {
+        let ty = match &value {
+            PrimitiveValue::Boolean(_) => Primitive::Boolean,
+            PrimitiveValue::Int(_) => Primitive::Int,
+            PrimitiveValue::Long(_) => Primitive::Long,
+            PrimitiveValue::Float(_) => Primitive::Float,
+            PrimitiveValue::Double(_) => Primitive::Double,
+            PrimitiveValue::Decimal(v) => Primitive::Decimal {
+                precision: 38,
+                scale: v.scale() as u8,
+            },
+            PrimitiveValue::Date(_) => Primitive::Date,
+            PrimitiveValue::Time(_) => Primitive::Time,
+            PrimitiveValue::Timestamp(_) => Primitive::Timestamp,
+            PrimitiveValue::Timestampz(_) => Primitive::Timestampz,
+            PrimitiveValue::TimestampNs(_) => Primitive::TimestampNs,
+            PrimitiveValue::TimestampzNs(_) => Primitive::TimestampzNs,
+            PrimitiveValue::String(_) => Primitive::String,
+            PrimitiveValue::Uuid(_) => Primitive::Uuid,
+            PrimitiveValue::Fixed(v) => Primitive::Fixed(v.len() as u64),
+            PrimitiveValue::Binary(_) => Primitive::Binary,
+        };
+        Self { ty, value }
+    }
+}
+
+impl PartialOrd for Datum {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.ty != other.ty {
+            return None;
+        }
+        compare_values(&self.value, &other.value)
+    }
+}
+
+/// StructLike is a tuple of primitive values accessed by position, like
+/// partition values of data files.
+pub trait StructLike {
+    /// Returns the number of fields.
+    fn size(&self) -> usize;
+
+    /// Returns the value of the field at `pos`, `None` if it's null or out
+    /// of range.
+    fn get(&self, pos: usize) -> Option<Datum>;
+}
+
+/// Fields of nested types are read as nulls, which are never partition
+/// values.
+impl StructLike for StructValue {
+    fn size(&self) -> usize {
+        self.type_info().len()
+    }
+
+    fn get(&self, pos: usize) -> Option<Datum> {
+        let (_, value, _) = self.iter().nth(pos)?;
+        let field = &self.type_info().fields()[pos];
+        match (&field.field_type, value?) {
+            (Any::Primitive(ty), AnyValue::Primitive(value)) => Some(Datum {
+                ty: *ty,
+                value: value.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl StructLike for [Option<Datum>] {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, pos: usize) -> Option<Datum> {
+        self.get(pos).cloned().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::types::{Field, Struct, StructValueBuilder};
+
+    #[test]
+    fn test_datum() -> Result<()> {
+        let decimal = Primitive::Decimal {
+            precision: 5,
+            scale: 2,
+        };
+        let datum = Datum::new(decimal, PrimitiveValue::Decimal(Decimal::new(-12345, 2)))?;
+        assert_eq!(datum.to_bytes(), vec![0xcf, 0xc7]);
+        assert_eq!(Datum::try_from_bytes(decimal, &datum.to_bytes())?, datum);
+        assert!(Datum::new(decimal, PrimitiveValue::Decimal(Decimal::new(1, 3))).is_err());
+        assert!(Datum::new(decimal, PrimitiveValue::Decimal(Decimal::new(123456, 2))).is_err());
+        assert!(Datum::new(Primitive::Int, PrimitiveValue::Long(1)).is_err());
+        assert!(Datum::try_from_bytes(Primitive::Int, &[1]).is_err());
+
+        let int = |v| Datum::from(PrimitiveValue::Int(v));
+        assert!(int(1) < int(2));
+        assert_eq!(
+            int(1).partial_cmp(&Datum::from(PrimitiveValue::Long(1))),
+            None
+        );
+        let nan = Datum::from(PrimitiveValue::Double(f64::NAN.into()));
+        assert!(Datum::from(PrimitiveValue::Double(f64::INFINITY.into())) < nan);
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct_like() -> Result<()> {
+        let ty = Arc::new(Struct::new(vec![
+            Field::optional(1000, "a", Any::Primitive(Primitive::Int)),
+            Field::optional(1001, "b", Any::Primitive(Primitive::String)),
+        ]));
+        let mut builder = StructValueBuilder::new(ty);
+        builder.add_field(1000, None)?;
+        builder.add_field(
+            1001,
+            Some(AnyValue::Primitive(PrimitiveValue::String("x".to_string()))),
+        )?;
+        let value = builder.build()?;
+        assert_eq!(value.size(), 2);
+        assert_eq!(value.get(0), None);
+        assert_eq!(
+            value.get(1),
+            Some(Datum::from(PrimitiveValue::String("x".to_string())))
+        );
+        assert_eq!(value.get(2), None);
+
+        let values = [None, Some(Datum::from(PrimitiveValue::Int(1)))];
+        assert_eq!(values.size(), 2);
+        assert_eq!(StructLike::get(&values[..], 1), values[1]);
+        Ok(())
+    }
+}
@@ -0,0 +1,1698 @@
+//! expression module provides iceberg expressions to filter table data.
+//!
+//! Expressions are built from [`Reference`]s to columns:
+//!
+//! ```
+//! use icelake::types::expression::Reference;
+//! use icelake::types::PrimitiveValue;
+//!
+//! let predicate = Reference::new("id")
+//!     .greater_than(PrimitiveValue::Long(10))
+//!     .and(Reference::new("data").is_not_null());
+//! ```
+//!
+//! Scans use expressions to prune data files by partition values and
+//! column metrics, see [`DataFileEvaluator`].
+//!
+//! Predicates on columns are projected to predicates on partition values
+//! by transforms of partition fields, see [`inclusive_projection`] and
+//! [`strict_projection`].
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Not;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use super::transform::transform_value;
+use super::{
+    decode_bound, Any, DataFile, FieldSummary, ManifestListEntry, PartitionSpec, Primitive,
+    PrimitiveValue, Schema, StructLike, Transform,
+};
+use crate::{Error, ErrorKind, Result};
+
+/// Reference to a column by name, nested columns are named like `a.b`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    name: String,
+}
+
+impl Reference {
+    /// Create a reference to given column.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    /// Returns name of the referenced column.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Build `self IS NULL`.
+    pub fn is_null(self) -> Predicate {
+        Predicate::IsNull(self)
+    }
+
+    /// Build `self IS NOT NULL`.
+    pub fn is_not_null(self) -> Predicate {
+        Predicate::NotNull(self)
+    }
+
+    /// Build `self = value`.
+    pub fn equal_to(self, value: PrimitiveValue) -> Predicate {
+        Predicate::Eq(self, value)
+    }
+
+    /// Build `self != value`.
+    pub fn not_equal_to(self, value: PrimitiveValue) -> Predicate {
+        Predicate::NotEq(self, value)
+    }
+
+    /// Build `self < value`.
+    pub fn less_than(self, value: PrimitiveValue) -> Predicate {
+        Predicate::Lt(self, value)
+    }
+
+    /// Build `self <= value`.
+    pub fn less_than_or_equal_to(self, value: PrimitiveValue) -> Predicate {
+        Predicate::LtEq(self, value)
+    }
+
+    /// Build `self > value`.
+    pub fn greater_than(self, value: PrimitiveValue) -> Predicate {
+        Predicate::Gt(self, value)
+    }
+
+    /// Build `self >= value`.
+    pub fn greater_than_or_equal_to(self, value: PrimitiveValue) -> Predicate {
+        Predicate::GtEq(self, value)
+    }
+
+    /// Build `self IN (values)`.
+    pub fn is_in(self, values: impl IntoIterator<Item = PrimitiveValue>) -> Predicate {
+        Predicate::In(self, values.into_iter().collect())
+    }
+
+    /// Build `self NOT IN (values)`.
+    pub fn is_not_in(self, values: impl IntoIterator<Item = PrimitiveValue>) -> Predicate {
+        Predicate::NotIn(self, values.into_iter().collect())
+    }
+
+    /// Build `self LIKE 'prefix%'`.
+    pub fn starts_with(self, prefix: impl Into<String>) -> Predicate {
+        Predicate::StartsWith(self, prefix.into())
+    }
+
+    /// Build `self NOT LIKE 'prefix%'`.
+    pub fn not_starts_with(self, prefix: impl Into<String>) -> Predicate {
+        Predicate::NotStartsWith(self, prefix.into())
+    }
+}
+
+/// Predicate is a boolean expression over columns.
+///
+/// Comparisons with nulls are never true, like sql.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// Always true.
+    AlwaysTrue,
+    /// Always false.
+    AlwaysFalse,
+    /// Column is null.
+    IsNull(Reference),
+    /// Column is not null.
+    NotNull(Reference),
+    /// Column equals to value.
+    Eq(Reference, PrimitiveValue),
+    /// Column doesn't equal to value.
+    NotEq(Reference, PrimitiveValue),
+    /// Column is less than value.
+    Lt(Reference, PrimitiveValue),
+    /// Column is less than or equal to value.
+    LtEq(Reference, PrimitiveValue),
+    /// Column is greater than value.
+    Gt(Reference, PrimitiveValue),
+    /// Column is greater than or equal to value.
+    GtEq(Reference, PrimitiveValue),
+    /// Column is one of values.
+    In(Reference, Vec<PrimitiveValue>),
+    /// Column is none of values.
+    NotIn(Reference, Vec<PrimitiveValue>),
+    /// String column starts with prefix.
+    StartsWith(Reference, String),
+    /// String column doesn't start with prefix.
+    NotStartsWith(Reference, String),
+    /// Both predicates are true.
+    And(Box<Predicate>, Box<Predicate>),
+    /// Either predicate is true.
+    Or(Box<Predicate>, Box<Predicate>),
+    /// The predicate is false.
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    /// Build `self AND other`.
+    pub fn and(self, other: Predicate) -> Predicate {
+        Predicate::And(Box::new(self), Box::new(other))
+    }
+
+    /// Build `self OR other`.
+    pub fn or(self, other: Predicate) -> Predicate {
+        Predicate::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Returns an equivalent predicate without `NOT`, by pushing negations
+    /// into leaves.
+    pub fn rewrite_not(self) -> Predicate {
+        match self {
+            Predicate::Not(p) => p.negate(),
+            Predicate::And(l, r) => l.rewrite_not().and(r.rewrite_not()),
+            Predicate::Or(l, r) => l.rewrite_not().or(r.rewrite_not()),
+            p => p,
+        }
+    }
+
+    /// Returns the negation of this predicate without `NOT`.
+    fn negate(self) -> Predicate {
+        match self {
+            Predicate::AlwaysTrue => Predicate::AlwaysFalse,
+            Predicate::AlwaysFalse => Predicate::AlwaysTrue,
+            Predicate::IsNull(r) => Predicate::NotNull(r),
+            Predicate::NotNull(r) => Predicate::IsNull(r),
+            Predicate::Eq(r, v) => Predicate::NotEq(r, v),
+            Predicate::NotEq(r, v) => Predicate::Eq(r, v),
+            Predicate::Lt(r, v) => Predicate::GtEq(r, v),
+            Predicate::LtEq(r, v) => Predicate::Gt(r, v),
+            Predicate::Gt(r, v) => Predicate::LtEq(r, v),
+            Predicate::GtEq(r, v) => Predicate::Lt(r, v),
+            Predicate::In(r, v) => Predicate::NotIn(r, v),
+            Predicate::NotIn(r, v) => Predicate::In(r, v),
+            Predicate::StartsWith(r, v) => Predicate::NotStartsWith(r, v),
+            Predicate::NotStartsWith(r, v) => Predicate::StartsWith(r, v),
+            Predicate::And(l, r) => l.negate().or(r.negate()),
+            Predicate::Or(l, r) => l.negate().and(r.negate()),
+            Predicate::Not(p) => p.rewrite_not(),
+        }
+    }
+
+    /// Returns the referenced column of a leaf predicate.
+    fn reference(&self) -> Option<&Reference> {
+        match self {
+            Predicate::IsNull(r)
+            | Predicate::NotNull(r)
+            | Predicate::Eq(r, _)
+            | Predicate::NotEq(r, _)
+            | Predicate::Lt(r, _)
+            | Predicate::LtEq(r, _)
+            | Predicate::Gt(r, _)
+            | Predicate::GtEq(r, _)
+            | Predicate::In(r, _)
+            | Predicate::NotIn(r, _)
+            | Predicate::StartsWith(r, _)
+            | Predicate::NotStartsWith(r, _) => Some(r),
+            _ => None,
+        }
+    }
+
+    /// Visit all references of this predicate.
+    fn references<'a>(&'a self, refs: &mut Vec<&'a Reference>) {
+        match self {
+            Predicate::And(l, r) | Predicate::Or(l, r) => {
+                l.references(refs);
+                r.references(refs);
+            }
+            Predicate::Not(p) => p.references(refs),
+            p => refs.extend(p.reference()),
+        }
+    }
+}
+
+impl Not for Predicate {
+    type Output = Predicate;
+
+    fn not(self) -> Predicate {
+        Predicate::Not(Box::new(self))
+    }
+}
+
+/// Compare two values of compatible types, int and long, float and double
+/// are compared after promotion.
+///
+/// Returns `None` if types are not comparable.
+pub(crate) fn compare_values(l: &PrimitiveValue, r: &PrimitiveValue) -> Option<Ordering> {
+    use PrimitiveValue::*;
+
+    match (l, r) {
+        (Boolean(l), Boolean(r)) => Some(l.cmp(r)),
+        (Int(l), Int(r)) => Some(l.cmp(r)),
+        (Long(l), Long(r)) => Some(l.cmp(r)),
+        (Int(l), Long(r)) => Some((*l as i64).cmp(r)),
+        (Long(l), Int(r)) => Some(l.cmp(&(*r as i64))),
+        (Float(l), Float(r)) => Some(l.cmp(r)),
+        (Double(l), Double(r)) => Some(l.cmp(r)),
+        (Float(l), Double(r)) => (l.0 as f64).partial_cmp(&r.0),
+        (Double(l), Float(r)) => l.0.partial_cmp(&(r.0 as f64)),
+        (Decimal(l), Decimal(r)) => Some(l.cmp(r)),
+        (Date(l), Date(r)) => Some(l.cmp(r)),
+        (Time(l), Time(r)) => Some(l.cmp(r)),
+        (Timestamp(l), Timestamp(r)) => Some(l.cmp(r)),
+        (Timestampz(l), Timestampz(r)) => Some(l.cmp(r)),
+        (TimestampNs(l), TimestampNs(r)) => Some(l.cmp(r)),
+        (TimestampzNs(l), TimestampzNs(r)) => Some(l.cmp(r)),
+        (String(l), String(r)) => Some(l.cmp(r)),
+        (Uuid(l), Uuid(r)) => Some(l.cmp(r)),
+        (Fixed(l), Fixed(r)) | (Binary(l), Binary(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+/// Nanoseconds from the unix epoch, saturated for timestamps out of the
+/// range of `i64`, which are about before 1677 or after 2262.
+pub(crate) fn timestamp_nanos(v: &NaiveDateTime) -> i64 {
+    v.timestamp_nanos_opt().unwrap_or(if v.timestamp() < 0 {
+        i64::MIN
+    } else {
+        i64::MAX
+    })
+}
+
+/// Timestamp of nanoseconds from the unix epoch.
+pub(crate) fn timestamp_from_nanos(nanos: i64) -> Option<NaiveDateTime> {
+    NaiveDateTime::from_timestamp_opt(
+        nanos.div_euclid(1_000_000_000),
+        nanos.rem_euclid(1_000_000_000) as u32,
+    )
+}
+
+/// Minimal big-endian two's complement bytes of the unscaled value.
+pub(crate) fn decimal_bytes(unscaled: i128) -> Vec<u8> {
+    let bytes = unscaled.to_be_bytes();
+    let sign = if unscaled < 0 { 0xff } else { 0 };
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && bytes[start] == sign
+        && (bytes[start + 1] & 0x80) == (sign & 0x80)
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+/// Referenced column resolved against schema and partition spec.
+#[derive(Debug, Clone)]
+struct BoundColumn {
+    field_id: i32,
+    ty: Primitive,
+    /// Index of the identity partition field of this column.
+    identity_partition: Option<usize>,
+}
+
+/// DataFileEvaluator decides whether a data file might contain rows
+/// matching a predicate, without reading the file.
+///
+/// A file is pruned only if it's proved that no row matches by partition
+/// values or column metrics (value counts, null counts, NaN counts, lower
+/// and upper bounds). Missing metrics never prune files.
+#[derive(Debug, Clone)]
+pub struct DataFileEvaluator {
+    predicate: Predicate,
+    columns: HashMap<String, BoundColumn>,
+    partition_spec: PartitionSpec,
+    /// Result types of partition fields, `None` if the source column is
+    /// not found in schema.
+    partition_types: Vec<Option<Primitive>>,
+}
+
+impl DataFileEvaluator {
+    /// Create an evaluator of given predicate over data files written by
+    /// `schema` and `partition_spec`.
+    ///
+    /// Returns error if the predicate references a column not found in
+    /// schema or not a primitive column.
+    pub fn try_new(
+        predicate: &Predicate,
+        schema: &Schema,
+        partition_spec: &PartitionSpec,
+    ) -> Result<Self> {
+        Self::try_new_with_case_sensitive(predicate, schema, partition_spec, true)
+    }
+
+    /// Like [`DataFileEvaluator::try_new`], but column names referenced by
+    /// the predicate are matched case insensitively unless
+    /// `case_sensitive`, see [`Schema::find_field_id`].
+    pub fn try_new_with_case_sensitive(
+        predicate: &Predicate,
+        schema: &Schema,
+        partition_spec: &PartitionSpec,
+        case_sensitive: bool,
+    ) -> Result<Self> {
+        let mut refs = vec![];
+        predicate.references(&mut refs);
+
+        let mut columns = HashMap::new();
+        for reference in refs {
+            let invalid = || {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!(
+                        "column {} referenced by predicate is not a primitive column of schema",
+                        reference.name()
+                    ),
+                )
+            };
+            let field_id = schema
+                .find_field_id(reference.name(), case_sensitive)?
+                .ok_or_else(invalid)?;
+            let ty = match schema.field_by_id(field_id).map(|v| &v.field_type) {
+                Some(Any::Primitive(ty)) => *ty,
+                _ => return Err(invalid()),
+            };
+            let identity_partition = partition_spec
+                .fields
+                .iter()
+                .position(|v| v.source_column_id == field_id && v.transform == Transform::Identity);
+            columns.insert(
+                reference.name().to_string(),
+                BoundColumn {
+                    field_id,
+                    ty,
+                    identity_partition,
+                },
+            );
+        }
+
+        let partition_types = partition_spec
+            .fields
+            .iter()
+            .map(|field| {
+                let source = schema.field_by_id(field.source_column_id)?;
+                match field.transform.result_type(&source.field_type) {
+                    Ok(Any::Primitive(ty)) => Some(ty),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            predicate: predicate.clone().rewrite_not(),
+            columns,
+            partition_spec: partition_spec.clone(),
+            partition_types,
+        })
+    }
+
+    /// Returns false if no row of the data file matches the predicate.
+    pub fn might_match(&self, data_file: &DataFile) -> bool {
+        let partition = &data_file.partition;
+        self.eval(&self.predicate, &|p, column| {
+            self.metrics_might_match(p, column, data_file)
+                && self.eval_partition(&self.project(p, column, false), true, &|p, idx| {
+                    partition_value_eval(p, partition, idx)
+                })
+        })
+    }
+
+    /// Returns whether the predicate only references identity partition
+    /// columns, so that it can be decided exactly by partition values, see
+    /// [`DataFileEvaluator::partition_matches`].
+    pub fn is_partition_predicate(&self) -> bool {
+        self.columns
+            .values()
+            .all(|v| v.identity_partition.is_some())
+    }
+
+    /// Returns true if all rows of the data file match the predicate,
+    /// decided by the strict projection of the predicate on partition
+    /// values, see [`strict_projection`].
+    ///
+    /// Files are always decided exactly if the predicate is a partition
+    /// predicate, see [`DataFileEvaluator::is_partition_predicate`].
+    pub fn partition_matches(&self, data_file: &DataFile) -> bool {
+        let partition = &data_file.partition;
+        self.eval(&self.predicate, &|p, column| {
+            self.eval_partition(&self.project(p, column, true), false, &|p, idx| {
+                partition_value_eval(p, partition, idx)
+            })
+        })
+    }
+
+    /// Returns false if no data file of the manifest matches the
+    /// predicate, decided by the inclusive projection of the predicate on
+    /// partition summaries in the manifest.
+    pub fn might_match_manifest(&self, manifest: &ManifestListEntry) -> bool {
+        if manifest.partition_spec_id != self.partition_spec.spec_id {
+            return true;
+        }
+        self.eval(&self.predicate, &|p, column| {
+            self.eval_partition(&self.project(p, column, false), true, &|p, idx| {
+                let summary = manifest.partitions.get(idx)?;
+                let ty = self.partition_types.get(idx)?.as_ref()?;
+                Some(summary_might_match(p, ty, summary))
+            })
+        })
+    }
+
+    /// Project the predicate to a predicate on partition fields, see
+    /// [`inclusive_projection`] and [`strict_projection`].
+    fn projection(&self, strict: bool) -> Predicate {
+        fn fold(evaluator: &DataFileEvaluator, p: &Predicate, strict: bool) -> Predicate {
+            match p {
+                Predicate::And(l, r) => {
+                    and_simplified(fold(evaluator, l, strict), fold(evaluator, r, strict))
+                }
+                Predicate::Or(l, r) => {
+                    or_simplified(fold(evaluator, l, strict), fold(evaluator, r, strict))
+                }
+                Predicate::AlwaysTrue | Predicate::AlwaysFalse => p.clone(),
+                // Removed by `rewrite_not`.
+                Predicate::Not(_) => Predicate::AlwaysTrue,
+                p => {
+                    let column =
+                        &evaluator.columns[p.reference().expect("leaf must have reference").name()];
+                    evaluator.project(p, column, strict)
+                }
+            }
+        }
+        fold(self, &self.predicate, strict)
+    }
+
+    /// Project a leaf predicate to a predicate on partition fields of its
+    /// column.
+    ///
+    /// Inclusive projections of all partition fields are combined by `AND`,
+    /// `AlwaysTrue` if none can be projected. Strict projections are
+    /// combined by `OR`, `AlwaysFalse` if none can be projected.
+    fn project(&self, p: &Predicate, column: &BoundColumn, strict: bool) -> Predicate {
+        let projected = self
+            .partition_spec
+            .fields
+            .iter()
+            .filter(|field| field.source_column_id == column.field_id)
+            .filter_map(|field| project_leaf(p, &field.name, field.transform, &column.ty, strict));
+        if strict {
+            projected.fold(Predicate::AlwaysFalse, or_simplified)
+        } else {
+            projected.fold(Predicate::AlwaysTrue, and_simplified)
+        }
+    }
+
+    /// Evaluate a predicate on partition fields by `leaf` of leaf
+    /// predicates with indexes of their partition fields. Leaves that
+    /// can't be decided are `unknown`.
+    fn eval_partition(
+        &self,
+        p: &Predicate,
+        unknown: bool,
+        leaf: &dyn Fn(&Predicate, usize) -> Option<bool>,
+    ) -> bool {
+        match p {
+            Predicate::AlwaysTrue => true,
+            Predicate::AlwaysFalse => false,
+            Predicate::And(l, r) => {
+                self.eval_partition(l, unknown, leaf) && self.eval_partition(r, unknown, leaf)
+            }
+            Predicate::Or(l, r) => {
+                self.eval_partition(l, unknown, leaf) || self.eval_partition(r, unknown, leaf)
+            }
+            Predicate::Not(_) => unknown,
+            p => {
+                let name = p.reference().expect("leaf must have reference").name();
+                self.partition_spec
+                    .fields
+                    .iter()
+                    .position(|field| field.name == name)
+                    .and_then(|idx| leaf(p, idx))
+                    .unwrap_or(unknown)
+            }
+        }
+    }
+
+    fn eval(&self, p: &Predicate, leaf: &dyn Fn(&Predicate, &BoundColumn) -> bool) -> bool {
+        match p {
+            Predicate::AlwaysTrue => true,
+            Predicate::AlwaysFalse => false,
+            Predicate::And(l, r) => self.eval(l, leaf) && self.eval(r, leaf),
+            Predicate::Or(l, r) => self.eval(l, leaf) || self.eval(r, leaf),
+            // Removed by `rewrite_not`.
+            Predicate::Not(_) => true,
+            p => {
+                let column = &self.columns[p.reference().expect("leaf must have reference").name()];
+                leaf(p, column)
+            }
+        }
+    }
+
+    fn metrics_might_match(&self, p: &Predicate, column: &BoundColumn, file: &DataFile) -> bool {
+        let id = column.field_id;
+        let bound = |bounds: &Option<HashMap<i32, Vec<u8>>>| {
+            bounds
+                .as_ref()
+                .and_then(|v| v.get(&id))
+                .and_then(|v| decode_bound(&column.ty, v))
+        };
+        let stats = ColumnStats {
+            value_count: file.value_counts.as_ref().and_then(|v| v.get(&id)).copied(),
+            null_count: file
+                .null_value_counts
+                .as_ref()
+                .and_then(|v| v.get(&id))
+                .copied(),
+            nan_count: file
+                .nan_value_counts
+                .as_ref()
+                .and_then(|v| v.get(&id))
+                .copied(),
+            lower: bound(&file.lower_bounds),
+            upper: bound(&file.upper_bounds),
+        };
+        stats_might_match(p, &stats)
+    }
+
+    /// Returns the field id of a column referenced by the predicate.
+    pub(crate) fn field_id(&self, name: &str) -> Option<i32> {
+        self.columns.get(name).map(|v| v.field_id)
+    }
+
+    /// Returns false if no row matches the predicate, decided by statistics
+    /// of columns returned by `stats` of field ids, like statistics of a
+    /// parquet row group. Columns without statistics never prune rows.
+    pub(crate) fn might_match_stats(
+        &self,
+        stats: &dyn Fn(i32, &Primitive) -> Option<ColumnStats>,
+    ) -> bool {
+        self.eval(&self.predicate, &|p, column| {
+            stats(column.field_id, &column.ty)
+                .map(|v| stats_might_match(p, &v))
+                .unwrap_or(true)
+        })
+    }
+
+    /// Evaluate the predicate into `T` by `leaf` of leaf predicates with
+    /// field ids and types of their columns, combined by `and` and `or`.
+    /// `constant` evaluates `AlwaysTrue` and `AlwaysFalse`.
+    pub(crate) fn fold<T>(
+        &self,
+        leaf: &dyn Fn(&Predicate, i32, &Primitive) -> T,
+        constant: &dyn Fn(bool) -> T,
+        and: &dyn Fn(T, T) -> T,
+        or: &dyn Fn(T, T) -> T,
+    ) -> T {
+        fn fold<T>(
+            evaluator: &DataFileEvaluator,
+            p: &Predicate,
+            leaf: &dyn Fn(&Predicate, i32, &Primitive) -> T,
+            constant: &dyn Fn(bool) -> T,
+            and: &dyn Fn(T, T) -> T,
+            or: &dyn Fn(T, T) -> T,
+        ) -> T {
+            match p {
+                Predicate::AlwaysTrue => constant(true),
+                Predicate::AlwaysFalse => constant(false),
+                Predicate::And(l, r) => and(
+                    fold(evaluator, l, leaf, constant, and, or),
+                    fold(evaluator, r, leaf, constant, and, or),
+                ),
+                Predicate::Or(l, r) => or(
+                    fold(evaluator, l, leaf, constant, and, or),
+                    fold(evaluator, r, leaf, constant, and, or),
+                ),
+                // Removed by `rewrite_not`.
+                Predicate::Not(_) => constant(true),
+                p => {
+                    let column =
+                        &evaluator.columns[p.reference().expect("leaf must have reference").name()];
+                    leaf(p, column.field_id, &column.ty)
+                }
+            }
+        }
+        fold(self, &self.predicate, leaf, constant, and, or)
+    }
+}
+
+/// Project a predicate on columns of `schema` to a predicate on partition
+/// values of `partition_spec`, which is true for a partition if any row of
+/// the partition might match the predicate.
+///
+/// Projected predicates reference partition fields by names. Partitions
+/// not matching the projection are proved to contain no matching row, so
+/// they can be pruned.
+///
+/// Returns error if the predicate references a column not found in schema
+/// or not a primitive column.
+pub fn inclusive_projection(
+    predicate: &Predicate,
+    schema: &Schema,
+    partition_spec: &PartitionSpec,
+) -> Result<Predicate> {
+    Ok(DataFileEvaluator::try_new(predicate, schema, partition_spec)?.projection(false))
+}
+
+/// Project a predicate on columns of `schema` to a predicate on partition
+/// values of `partition_spec`, which is true for a partition only if all
+/// rows of the partition match the predicate.
+///
+/// Projected predicates reference partition fields by names. Partitions
+/// matching the projection can be replaced entirely, like overwriting by
+/// filter does.
+///
+/// Returns error if the predicate references a column not found in schema
+/// or not a primitive column.
+pub fn strict_projection(
+    predicate: &Predicate,
+    schema: &Schema,
+    partition_spec: &PartitionSpec,
+) -> Result<Predicate> {
+    Ok(DataFileEvaluator::try_new(predicate, schema, partition_spec)?.projection(true))
+}
+
+/// Build `l AND r`, simplified if either is a constant.
+fn and_simplified(l: Predicate, r: Predicate) -> Predicate {
+    match (l, r) {
+        (Predicate::AlwaysFalse, _) | (_, Predicate::AlwaysFalse) => Predicate::AlwaysFalse,
+        (Predicate::AlwaysTrue, p) | (p, Predicate::AlwaysTrue) => p,
+        (l, r) => l.and(r),
+    }
+}
+
+/// Build `l OR r`, simplified if either is a constant.
+fn or_simplified(l: Predicate, r: Predicate) -> Predicate {
+    match (l, r) {
+        (Predicate::AlwaysTrue, _) | (_, Predicate::AlwaysTrue) => Predicate::AlwaysTrue,
+        (Predicate::AlwaysFalse, p) | (p, Predicate::AlwaysFalse) => p,
+        (l, r) => l.or(r),
+    }
+}
+
+/// Project a leaf predicate on a column of type `ty` to a predicate on the
+/// partition field `name` of `transform`, inclusively or strictly.
+///
+/// Returns `None` if it can't be projected.
+fn project_leaf(
+    p: &Predicate,
+    name: &str,
+    transform: Transform,
+    ty: &Primitive,
+    strict: bool,
+) -> Option<Predicate> {
+    let r = Reference::new(name);
+    let t = |v: &PrimitiveValue| transform_value(transform, &coerce_literal(v, ty)?);
+    let ts = |values: &[PrimitiveValue]| values.iter().map(t).collect::<Option<Vec<_>>>();
+    // Transformed value next to the literal, like `t(v - 1)`.
+    let t_adjacent = |v: &PrimitiveValue, delta: i64| {
+        transform_value(transform, &adjacent_value(&coerce_literal(v, ty)?, delta)?)
+    };
+
+    match (p, transform) {
+        // Partition values are always null.
+        (_, Transform::Void) => None,
+        (Predicate::IsNull(_), _) => Some(r.is_null()),
+        (Predicate::NotNull(_), _) => Some(r.is_not_null()),
+        (p, Transform::Identity) => Some(match p {
+            Predicate::Eq(_, v) => r.equal_to(v.clone()),
+            Predicate::NotEq(_, v) => r.not_equal_to(v.clone()),
+            Predicate::Lt(_, v) => r.less_than(v.clone()),
+            Predicate::LtEq(_, v) => r.less_than_or_equal_to(v.clone()),
+            Predicate::Gt(_, v) => r.greater_than(v.clone()),
+            Predicate::GtEq(_, v) => r.greater_than_or_equal_to(v.clone()),
+            Predicate::In(_, values) => r.is_in(values.clone()),
+            Predicate::NotIn(_, values) => r.is_not_in(values.clone()),
+            Predicate::StartsWith(_, prefix) => r.starts_with(prefix.clone()),
+            Predicate::NotStartsWith(_, prefix) => r.not_starts_with(prefix.clone()),
+            _ => return None,
+        }),
+        // Buckets are not ordered, so only equality is projected.
+        (p, Transform::Bucket(_)) => match (p, strict) {
+            (Predicate::Eq(_, v), false) => Some(r.equal_to(t(v)?)),
+            (Predicate::In(_, values), false) => Some(r.is_in(ts(values)?)),
+            (Predicate::NotEq(_, v), true) => Some(r.not_equal_to(t(v)?)),
+            (Predicate::NotIn(_, values), true) => Some(r.is_not_in(ts(values)?)),
+            _ => None,
+        },
+        (p, Transform::Truncate(width)) if matches!(ty, Primitive::String | Primitive::Binary) => {
+            let width = usize::try_from(width).ok()?;
+            // Length of strings in chars, or binary in bytes.
+            let len = |v: &PrimitiveValue| match v {
+                PrimitiveValue::String(v) => Some(v.chars().count()),
+                PrimitiveValue::Binary(v) => Some(v.len()),
+                _ => None,
+            };
+            let t_prefix = |prefix: &str| match t(&PrimitiveValue::String(prefix.to_string()))? {
+                PrimitiveValue::String(v) => Some(v),
+                _ => None,
+            };
+            let prefix_len = |prefix: &str| prefix.chars().count();
+            if !strict {
+                return match p {
+                    Predicate::Lt(_, v) | Predicate::LtEq(_, v) => {
+                        Some(r.less_than_or_equal_to(t(v)?))
+                    }
+                    Predicate::Gt(_, v) | Predicate::GtEq(_, v) => {
+                        Some(r.greater_than_or_equal_to(t(v)?))
+                    }
+                    Predicate::Eq(_, v) => Some(r.equal_to(t(v)?)),
+                    Predicate::In(_, values) => Some(r.is_in(ts(values)?)),
+                    Predicate::StartsWith(_, prefix) => Some(r.starts_with(t_prefix(prefix)?)),
+                    // Truncated values start with a shorter prefix only if
+                    // the values do.
+                    Predicate::NotStartsWith(_, prefix) if prefix_len(prefix) < width => {
+                        Some(r.not_starts_with(prefix.clone()))
+                    }
+                    Predicate::NotStartsWith(_, prefix) if prefix_len(prefix) == width => {
+                        Some(r.not_equal_to(PrimitiveValue::String(prefix.clone())))
+                    }
+                    _ => None,
+                };
+            }
+            match p {
+                Predicate::Lt(_, v) | Predicate::LtEq(_, v) => Some(r.less_than(t(v)?)),
+                Predicate::Gt(_, v) | Predicate::GtEq(_, v) => Some(r.greater_than(t(v)?)),
+                // Only values shorter than the width are kept entirely.
+                Predicate::Eq(_, v) if len(v)? < width => Some(r.equal_to(coerce_literal(v, ty)?)),
+                Predicate::In(_, values) => {
+                    let values = values
+                        .iter()
+                        .map(|v| match len(v)? < width {
+                            true => coerce_literal(v, ty),
+                            false => None,
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(r.is_in(values))
+                }
+                Predicate::NotEq(_, v) => Some(r.not_equal_to(t(v)?)),
+                Predicate::NotIn(_, values) => Some(r.is_not_in(ts(values)?)),
+                Predicate::StartsWith(_, prefix) if prefix_len(prefix) <= width => {
+                    Some(r.starts_with(prefix.clone()))
+                }
+                Predicate::NotStartsWith(_, prefix) if prefix_len(prefix) <= width => {
+                    Some(r.not_starts_with(prefix.clone()))
+                }
+                Predicate::NotStartsWith(_, prefix) => Some(r.not_starts_with(t_prefix(prefix)?)),
+                _ => None,
+            }
+        }
+        // Truncating numbers and temporal transforms are monotonic, so
+        // bounds are projected to bounds of transformed values.
+        (p, _) => match (p, strict) {
+            (Predicate::Lt(_, v), false) => Some(r.less_than_or_equal_to(t_adjacent(v, -1)?)),
+            (Predicate::LtEq(_, v), false) => Some(r.less_than_or_equal_to(t(v)?)),
+            (Predicate::Gt(_, v), false) => Some(r.greater_than_or_equal_to(t_adjacent(v, 1)?)),
+            (Predicate::GtEq(_, v), false) => Some(r.greater_than_or_equal_to(t(v)?)),
+            (Predicate::Eq(_, v), false) => Some(r.equal_to(t(v)?)),
+            (Predicate::In(_, values), false) => Some(r.is_in(ts(values)?)),
+            (Predicate::Lt(_, v), true) => Some(r.less_than(t(v)?)),
+            (Predicate::LtEq(_, v), true) => Some(r.less_than(t_adjacent(v, 1)?)),
+            (Predicate::Gt(_, v), true) => Some(r.greater_than(t(v)?)),
+            (Predicate::GtEq(_, v), true) => Some(r.greater_than(t_adjacent(v, -1)?)),
+            (Predicate::NotEq(_, v), true) => Some(r.not_equal_to(t(v)?)),
+            (Predicate::NotIn(_, values), true) => Some(r.is_not_in(ts(values)?)),
+            _ => None,
+        },
+    }
+}
+
+/// Convert a literal to a value of column type `ty`, like ints of long
+/// columns and decimals of the column scale, so that transforms of the
+/// literal give the same results as values of the column.
+///
+/// Returns `None` if the literal can't be converted exactly.
+fn coerce_literal(value: &PrimitiveValue, ty: &Primitive) -> Option<PrimitiveValue> {
+    use PrimitiveValue as V;
+    match (value, ty) {
+        (V::Int(v), Primitive::Long) => Some(V::Long(*v as i64)),
+        (V::Decimal(v), Primitive::Decimal { scale, .. }) => {
+            let scale = *scale as u32;
+            if v.scale() > scale {
+                return None;
+            }
+            let mut v = *v;
+            v.rescale(scale);
+            (v.scale() == scale).then_some(V::Decimal(v))
+        }
+        (V::Timestamp(v), Primitive::TimestampNs) => Some(V::TimestampNs(*v)),
+        (V::Timestampz(v), Primitive::TimestampzNs) => Some(V::TimestampzNs(*v)),
+        (V::Boolean(_), Primitive::Boolean)
+        | (V::Int(_), Primitive::Int)
+        | (V::Long(_), Primitive::Long)
+        | (V::Float(_), Primitive::Float)
+        | (V::Double(_), Primitive::Double)
+        | (V::Date(_), Primitive::Date)
+        | (V::Time(_), Primitive::Time)
+        | (V::Timestamp(_), Primitive::Timestamp)
+        | (V::Timestampz(_), Primitive::Timestampz)
+        | (V::TimestampNs(_), Primitive::TimestampNs)
+        | (V::TimestampzNs(_), Primitive::TimestampzNs)
+        | (V::String(_), Primitive::String)
+        | (V::Uuid(_), Primitive::Uuid)
+        | (V::Fixed(_), Primitive::Fixed(_))
+        | (V::Binary(_), Primitive::Binary) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the value `delta` units away from the value, in the smallest
+/// unit of its type, like days of dates and unscaled values of decimals.
+///
+/// Returns `None` if the type is not ordered by units, or the result
+/// overflows.
+fn adjacent_value(value: &PrimitiveValue, delta: i64) -> Option<PrimitiveValue> {
+    use PrimitiveValue as V;
+    Some(match value {
+        V::Int(v) => V::Int(v.checked_add(i32::try_from(delta).ok()?)?),
+        V::Long(v) => V::Long(v.checked_add(delta)?),
+        V::Decimal(v) => V::Decimal(
+            Decimal::try_from_i128_with_scale(v.mantissa().checked_add(delta as i128)?, v.scale())
+                .ok()?,
+        ),
+        V::Date(v) => V::Date(v.checked_add_signed(chrono::Duration::days(delta))?),
+        V::Timestamp(v) => {
+            V::Timestamp(v.checked_add_signed(chrono::Duration::microseconds(delta))?)
+        }
+        V::Timestampz(v) => {
+            V::Timestampz(v.checked_add_signed(chrono::Duration::microseconds(delta))?)
+        }
+        V::TimestampNs(v) => {
+            V::TimestampNs(v.checked_add_signed(chrono::Duration::nanoseconds(delta))?)
+        }
+        V::TimestampzNs(v) => {
+            V::TimestampzNs(v.checked_add_signed(chrono::Duration::nanoseconds(delta))?)
+        }
+        _ => return None,
+    })
+}
+
+/// Evaluate a leaf predicate on the partition value at `idx` of a data
+/// file.
+///
+/// Returns `None` if it can't be decided by the value.
+fn partition_value_eval(p: &Predicate, partition: &impl StructLike, idx: usize) -> Option<bool> {
+    if idx >= partition.size() {
+        return None;
+    }
+    let datum = match partition.get(idx) {
+        // Only null checks are true on nulls.
+        None => return Some(matches!(p, Predicate::IsNull(_))),
+        Some(datum) => datum,
+    };
+    let value = datum.value();
+    let cmp = |v: &PrimitiveValue, expected: &[Ordering]| {
+        compare_values(value, v).map(|o| expected.contains(&o))
+    };
+
+    match p {
+        Predicate::IsNull(_) => Some(false),
+        Predicate::NotNull(_) => Some(true),
+        Predicate::Eq(_, v) => cmp(v, &[Ordering::Equal]),
+        Predicate::NotEq(_, v) => cmp(v, &[Ordering::Less, Ordering::Greater]),
+        Predicate::Lt(_, v) => cmp(v, &[Ordering::Less]),
+        Predicate::LtEq(_, v) => cmp(v, &[Ordering::Less, Ordering::Equal]),
+        Predicate::Gt(_, v) => cmp(v, &[Ordering::Greater]),
+        Predicate::GtEq(_, v) => cmp(v, &[Ordering::Greater, Ordering::Equal]),
+        Predicate::In(_, values) => values
+            .iter()
+            .map(|v| cmp(v, &[Ordering::Equal]))
+            .try_fold(false, |acc, v| Some(acc || v?)),
+        Predicate::NotIn(_, values) => values
+            .iter()
+            .map(|v| cmp(v, &[Ordering::Less, Ordering::Greater]))
+            .try_fold(true, |acc, v| Some(acc && v?)),
+        Predicate::StartsWith(_, prefix) => match value {
+            PrimitiveValue::String(s) => Some(s.starts_with(prefix.as_str())),
+            _ => None,
+        },
+        Predicate::NotStartsWith(_, prefix) => match value {
+            PrimitiveValue::String(s) => Some(!s.starts_with(prefix.as_str())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Statistics of a column over some rows, like a data file, a row group or
+/// a page of a parquet file. Unknown statistics are `None`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ColumnStats {
+    pub value_count: Option<i64>,
+    pub null_count: Option<i64>,
+    pub nan_count: Option<i64>,
+    pub lower: Option<PrimitiveValue>,
+    pub upper: Option<PrimitiveValue>,
+}
+
+/// Evaluate a leaf predicate on column statistics, returns false if no row
+/// matches.
+pub(crate) fn stats_might_match(p: &Predicate, stats: &ColumnStats) -> bool {
+    let value_count = stats.value_count.as_ref();
+    let null_count = stats.null_count.as_ref();
+    let nan_count = stats.nan_count.as_ref();
+    let (lower, upper) = (&stats.lower, &stats.upper);
+    let all_null = matches!((value_count, null_count), (Some(v), Some(n)) if v == n);
+    // NaN never matches comparisons, so rows of only nulls and NaN can
+    // be pruned like rows of only nulls.
+    let all_null_or_nan = all_null
+        || matches!(
+            (value_count, null_count, nan_count),
+            (Some(v), Some(n), Some(nan)) if *v == n + nan
+        );
+    // Returns true if `bound` compared with `value` is one of `expected`.
+    let cmp = |bound: &Option<PrimitiveValue>, value: &PrimitiveValue, expected: &[Ordering]| {
+        bound
+            .as_ref()
+            .and_then(|b| compare_values(b, value))
+            .map(|o| expected.contains(&o))
+            .unwrap_or(false)
+    };
+
+    match p {
+        Predicate::IsNull(_) => null_count != Some(&0),
+        Predicate::NotNull(_) => !all_null,
+        Predicate::Lt(_, v) => {
+            !all_null_or_nan && !cmp(lower, v, &[Ordering::Greater, Ordering::Equal])
+        }
+        Predicate::LtEq(_, v) => !all_null_or_nan && !cmp(lower, v, &[Ordering::Greater]),
+        Predicate::Gt(_, v) => {
+            !all_null_or_nan && !cmp(upper, v, &[Ordering::Less, Ordering::Equal])
+        }
+        Predicate::GtEq(_, v) => !all_null_or_nan && !cmp(upper, v, &[Ordering::Less]),
+        Predicate::Eq(_, v) => {
+            !all_null_or_nan
+                && !cmp(lower, v, &[Ordering::Greater])
+                && !cmp(upper, v, &[Ordering::Less])
+        }
+        Predicate::In(_, values) => {
+            !all_null_or_nan
+                && values.iter().any(|v| {
+                    !cmp(lower, v, &[Ordering::Greater]) && !cmp(upper, v, &[Ordering::Less])
+                })
+        }
+        Predicate::StartsWith(_, prefix) => {
+            if all_null_or_nan {
+                return false;
+            }
+            let truncated = |bound: &Option<PrimitiveValue>| match bound {
+                Some(PrimitiveValue::String(s)) => {
+                    Some(s.chars().take(prefix.chars().count()).collect::<String>())
+                }
+                _ => None,
+            };
+            let lower_above = truncated(lower).map(|l| l.as_str() > prefix.as_str());
+            let upper_below = truncated(upper).map(|u| u.as_str() < prefix.as_str());
+            lower_above != Some(true) && upper_below != Some(true)
+        }
+        Predicate::NotStartsWith(_, prefix) => {
+            // Only pruned if no null and both bounds start with the
+            // prefix, then all values start with it.
+            let starts_with = |bound: &Option<PrimitiveValue>| matches!(bound, Some(PrimitiveValue::String(s)) if s.starts_with(prefix.as_str()));
+            null_count != Some(&0) || !starts_with(lower) || !starts_with(upper)
+        }
+        // Statistics can't prove these never match.
+        _ => true,
+    }
+}
+
+/// Evaluate a leaf predicate on the summary of a partition field of type
+/// `ty`, returns false if no partition of the manifest matches.
+fn summary_might_match(p: &Predicate, ty: &Primitive, summary: &FieldSummary) -> bool {
+    let bound = |bound: &Option<Vec<u8>>| bound.as_ref().and_then(|v| decode_bound(ty, v));
+    let lower = bound(&summary.lower_bound);
+    let upper = bound(&summary.upper_bound);
+    // Bounds are absent if all values are null or NaN, or if the writer
+    // didn't record them.
+    let contains_nan = summary.contains_nan == Some(true);
+    let all_null_or_nan = summary.lower_bound.is_none() && (summary.contains_null || contains_nan);
+    let cmp = |bound: &Option<PrimitiveValue>, value: &PrimitiveValue, expected: &[Ordering]| {
+        bound
+            .as_ref()
+            .and_then(|b| compare_values(b, value))
+            .map(|o| expected.contains(&o))
+            .unwrap_or(false)
+    };
+
+    match p {
+        Predicate::IsNull(_) => summary.contains_null,
+        Predicate::NotNull(_) => {
+            !(summary.lower_bound.is_none() && summary.contains_null && !contains_nan)
+        }
+        Predicate::Lt(_, v) => {
+            !all_null_or_nan && !cmp(&lower, v, &[Ordering::Greater, Ordering::Equal])
+        }
+        Predicate::LtEq(_, v) => !all_null_or_nan && !cmp(&lower, v, &[Ordering::Greater]),
+        Predicate::Gt(_, v) => {
+            !all_null_or_nan && !cmp(&upper, v, &[Ordering::Less, Ordering::Equal])
+        }
+        Predicate::GtEq(_, v) => !all_null_or_nan && !cmp(&upper, v, &[Ordering::Less]),
+        Predicate::Eq(_, v) => {
+            !all_null_or_nan
+                && !cmp(&lower, v, &[Ordering::Greater])
+                && !cmp(&upper, v, &[Ordering::Less])
+        }
+        Predicate::In(_, values) => {
+            !all_null_or_nan
+                && values.iter().any(|v| {
+                    !cmp(&lower, v, &[Ordering::Greater]) && !cmp(&upper, v, &[Ordering::Less])
+                })
+        }
+        // Summaries can't prove these never match.
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::types::{
+        bucket_hash, encode_bound, AnyValue, DataContentType, DataFileFormat, Field,
+        ManifestContentType, PartitionField, Struct, StructValueBuilder,
+    };
+
+    fn schema() -> Schema {
+        let field = |id, name: &str, ty| Field {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type: Any::Primitive(ty),
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        };
+        Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "id", Primitive::Long),
+                field(2, "data", Primitive::String),
+                field(3, "day", Primitive::Date),
+                field(4, "v", Primitive::Double),
+            ],
+        }
+    }
+
+    fn partition_spec() -> PartitionSpec {
+        PartitionSpec {
+            spec_id: 0,
+            fields: vec![PartitionField {
+                source_column_id: 3,
+                partition_field_id: 1000,
+                transform: Transform::Identity,
+                name: "day".to_string(),
+            }],
+        }
+    }
+
+    fn data_file(day: Option<NaiveDate>) -> DataFile {
+        let partition_type = Arc::new(Struct::new(vec![Field {
+            id: 1000,
+            name: "day".to_string(),
+            required: false,
+            field_type: Any::Primitive(Primitive::Date),
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        }]));
+        let mut builder = StructValueBuilder::new(partition_type);
+        builder
+            .add_field(
+                1000,
+                day.map(|v| AnyValue::Primitive(PrimitiveValue::Date(v))),
+            )
+            .unwrap();
+
+        DataFile {
+            content: DataContentType::Data,
+            file_path: "a.parquet".to_string(),
+            file_format: DataFileFormat::Parquet,
+            partition: builder.build().unwrap(),
+            record_count: 10,
+            file_size_in_bytes: 100,
+            column_sizes: None,
+            value_counts: Some(HashMap::from([(1, 10), (2, 10)])),
+            null_value_counts: Some(HashMap::from([(1, 0), (2, 10)])),
+            nan_value_counts: None,
+            distinct_counts: None,
+            lower_bounds: Some(HashMap::from([
+                (1, 10_i64.to_le_bytes().to_vec()),
+                (2, b"aaa".to_vec()),
+            ])),
+            upper_bounds: Some(HashMap::from([
+                (1, 20_i64.to_le_bytes().to_vec()),
+                (2, b"ccc".to_vec()),
+            ])),
+            key_metadata: None,
+            split_offsets: vec![],
+            equality_ids: vec![],
+            sort_order_id: None,
+        }
+    }
+
+    fn might_match(predicate: Predicate, file: &DataFile) -> bool {
+        DataFileEvaluator::try_new(&predicate, &schema(), &partition_spec())
+            .unwrap()
+            .might_match(file)
+    }
+
+    #[test]
+    fn test_rewrite_not() {
+        let p = !(Reference::new("id")
+            .less_than(PrimitiveValue::Long(1))
+            .and(Reference::new("data").is_null()));
+        assert_eq!(
+            p.rewrite_not(),
+            Reference::new("id")
+                .greater_than_or_equal_to(PrimitiveValue::Long(1))
+                .or(Reference::new("data").is_not_null())
+        );
+    }
+
+    #[test]
+    fn test_metrics_evaluation() {
+        let file = data_file(None);
+        let id = || Reference::new("id");
+
+        assert!(might_match(id().equal_to(PrimitiveValue::Long(15)), &file));
+        assert!(!might_match(id().equal_to(PrimitiveValue::Long(21)), &file));
+        // Int literal is promoted to compare with long column.
+        assert!(!might_match(id().equal_to(PrimitiveValue::Int(9)), &file));
+        assert!(!might_match(
+            id().less_than(PrimitiveValue::Long(10)),
+            &file
+        ));
+        assert!(might_match(
+            id().less_than_or_equal_to(PrimitiveValue::Long(10)),
+            &file
+        ));
+        assert!(!might_match(
+            id().greater_than(PrimitiveValue::Long(20)),
+            &file
+        ));
+        assert!(might_match(
+            id().greater_than_or_equal_to(PrimitiveValue::Long(20)),
+            &file
+        ));
+        assert!(!might_match(
+            id().is_in([PrimitiveValue::Long(1), PrimitiveValue::Long(30)]),
+            &file
+        ));
+        assert!(might_match(
+            id().is_in([PrimitiveValue::Long(1), PrimitiveValue::Long(11)]),
+            &file
+        ));
+        assert!(!might_match(id().is_null(), &file));
+        assert!(might_match(!id().equal_to(PrimitiveValue::Long(15)), &file));
+        assert!(!might_match(!id().is_not_null(), &file));
+
+        // All values of `data` are null.
+        assert!(!might_match(Reference::new("data").is_not_null(), &file));
+        assert!(!might_match(Reference::new("data").starts_with("b"), &file));
+
+        assert!(might_match(
+            id().equal_to(PrimitiveValue::Long(1))
+                .or(Reference::new("data").is_null()),
+            &file
+        ));
+        assert!(!might_match(
+            id().equal_to(PrimitiveValue::Long(15))
+                .and(Predicate::AlwaysFalse),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_starts_with_bounds() {
+        let mut file = data_file(None);
+        file.null_value_counts = Some(HashMap::from([(2, 0)]));
+        let data = || Reference::new("data");
+        assert!(might_match(data().starts_with("b"), &file));
+        assert!(might_match(data().starts_with("aa"), &file));
+        assert!(!might_match(data().starts_with("0"), &file));
+        assert!(!might_match(data().starts_with("cd"), &file));
+    }
+
+    #[test]
+    fn test_not_starts_with_bounds() {
+        let mut file = data_file(None);
+        file.lower_bounds = Some(HashMap::from([(2, b"abc".to_vec())]));
+        file.upper_bounds = Some(HashMap::from([(2, b"abz".to_vec())]));
+        let data = || Reference::new("data");
+        // Nulls may match.
+        assert!(might_match(data().not_starts_with("ab"), &file));
+
+        file.null_value_counts = Some(HashMap::from([(2, 0)]));
+        assert!(!might_match(data().not_starts_with("ab"), &file));
+        assert!(!might_match(!data().starts_with("a"), &file));
+        assert!(might_match(data().not_starts_with("abc"), &file));
+    }
+
+    #[test]
+    fn test_nan_metrics_evaluation() {
+        let mut file = data_file(None);
+        file.value_counts = Some(HashMap::from([(4, 10)]));
+        file.null_value_counts = Some(HashMap::from([(4, 4)]));
+        file.nan_value_counts = Some(HashMap::from([(4, 6)]));
+        let v = || Reference::new("v");
+
+        // All values are null or NaN.
+        assert!(!might_match(
+            v().less_than(PrimitiveValue::Double(1.0.into())),
+            &file
+        ));
+        assert!(!might_match(
+            v().equal_to(PrimitiveValue::Double(1.0.into())),
+            &file
+        ));
+        assert!(might_match(v().is_not_null(), &file));
+        assert!(might_match(v().is_null(), &file));
+
+        file.nan_value_counts = Some(HashMap::from([(4, 5)]));
+        assert!(might_match(
+            v().less_than(PrimitiveValue::Double(1.0.into())),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_partition_evaluation() {
+        let d1 = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let d2 = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        let file = data_file(Some(d1));
+        let day = || Reference::new("day");
+
+        assert!(might_match(day().equal_to(PrimitiveValue::Date(d1)), &file));
+        assert!(!might_match(
+            day().equal_to(PrimitiveValue::Date(d2)),
+            &file
+        ));
+        assert!(!might_match(
+            day().greater_than(PrimitiveValue::Date(d1)),
+            &file
+        ));
+        assert!(!might_match(
+            day().is_not_in([PrimitiveValue::Date(d1)]),
+            &file
+        ));
+        assert!(!might_match(day().is_null(), &file));
+
+        let file = data_file(None);
+        assert!(might_match(day().is_null(), &file));
+        assert!(!might_match(
+            day().equal_to(PrimitiveValue::Date(d1)),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_partition_matches() {
+        let d1 = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let d2 = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        let evaluator =
+            |p: Predicate| DataFileEvaluator::try_new(&p, &schema(), &partition_spec()).unwrap();
+        let day = || Reference::new("day");
+
+        let e = evaluator(day().is_in([PrimitiveValue::Date(d1), PrimitiveValue::Date(d2)]));
+        assert!(e.is_partition_predicate());
+        assert!(e.partition_matches(&data_file(Some(d1))));
+        assert!(!e.partition_matches(&data_file(None)));
+
+        let e = evaluator(!day().equal_to(PrimitiveValue::Date(d1)));
+        assert!(e.partition_matches(&data_file(Some(d2))));
+        assert!(!e.partition_matches(&data_file(Some(d1))));
+        // Comparisons on nulls are never true.
+        assert!(!e.partition_matches(&data_file(None)));
+
+        // `id` is not a partition column, so nothing matches.
+        let e = evaluator(Reference::new("id").is_not_null());
+        assert!(!e.is_partition_predicate());
+        assert!(!e.partition_matches(&data_file(Some(d1))));
+    }
+
+    fn transform_spec(fields: Vec<(i32, Transform, &str)>) -> PartitionSpec {
+        PartitionSpec {
+            spec_id: 0,
+            fields: fields
+                .into_iter()
+                .enumerate()
+                .map(
+                    |(idx, (source_column_id, transform, name))| PartitionField {
+                        source_column_id,
+                        partition_field_id: 1000 + idx as i32,
+                        transform,
+                        name: name.to_string(),
+                    },
+                )
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_inclusive_projection() {
+        let spec = transform_spec(vec![
+            (1, Transform::Truncate(10), "id_trunc"),
+            (2, Transform::Truncate(2), "data_trunc"),
+            (3, Transform::Month, "day_month"),
+        ]);
+        let project = |p: Predicate| inclusive_projection(&p, &schema(), &spec).unwrap();
+        let (id, data, day) = (
+            || Reference::new("id"),
+            || Reference::new("data"),
+            || Reference::new("day"),
+        );
+        let id_trunc = || Reference::new("id_trunc");
+        let data_trunc = || Reference::new("data_trunc");
+
+        assert_eq!(
+            project(id().less_than(PrimitiveValue::Long(25))),
+            id_trunc().less_than_or_equal_to(PrimitiveValue::Long(20))
+        );
+        assert_eq!(
+            project(id().less_than(PrimitiveValue::Long(20))),
+            id_trunc().less_than_or_equal_to(PrimitiveValue::Long(10))
+        );
+        assert_eq!(
+            project(id().greater_than(PrimitiveValue::Long(29))),
+            id_trunc().greater_than_or_equal_to(PrimitiveValue::Long(30))
+        );
+        // Int literal is promoted like the long column.
+        assert_eq!(
+            project(id().equal_to(PrimitiveValue::Int(25))),
+            id_trunc().equal_to(PrimitiveValue::Long(20))
+        );
+        assert_eq!(
+            project(id().not_equal_to(PrimitiveValue::Long(25))),
+            Predicate::AlwaysTrue
+        );
+        assert_eq!(project(id().is_null()), id_trunc().is_null());
+
+        assert_eq!(
+            project(data().starts_with("abc")),
+            data_trunc().starts_with("ab")
+        );
+        assert_eq!(
+            project(data().not_starts_with("a")),
+            data_trunc().not_starts_with("a")
+        );
+        assert_eq!(
+            project(data().not_starts_with("ab")),
+            data_trunc().not_equal_to(PrimitiveValue::String("ab".to_string()))
+        );
+        assert_eq!(
+            project(data().not_starts_with("abc")),
+            Predicate::AlwaysTrue
+        );
+        assert_eq!(
+            project(data().less_than(PrimitiveValue::String("abc".to_string()))),
+            data_trunc().less_than_or_equal_to(PrimitiveValue::String("ab".to_string()))
+        );
+
+        // 2023-01 is month 636 from 1970-01.
+        let d = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        assert_eq!(
+            project(day().less_than(PrimitiveValue::Date(d))),
+            Reference::new("day_month").less_than_or_equal_to(PrimitiveValue::Int(636))
+        );
+
+        assert_eq!(
+            project(
+                id().greater_than_or_equal_to(PrimitiveValue::Long(25))
+                    .and(data().equal_to(PrimitiveValue::String("abc".to_string())))
+            ),
+            id_trunc()
+                .greater_than_or_equal_to(PrimitiveValue::Long(20))
+                .and(data_trunc().equal_to(PrimitiveValue::String("ab".to_string())))
+        );
+        // `v` is not partitioned, so any partition might match.
+        assert_eq!(
+            project(
+                id().equal_to(PrimitiveValue::Long(1))
+                    .or(Reference::new("v").is_null())
+            ),
+            Predicate::AlwaysTrue
+        );
+    }
+
+    #[test]
+    fn test_strict_projection() {
+        let spec = transform_spec(vec![
+            (1, Transform::Truncate(10), "id_trunc"),
+            (2, Transform::Truncate(2), "data_trunc"),
+        ]);
+        let project = |p: Predicate| strict_projection(&p, &schema(), &spec).unwrap();
+        let (id, data) = (|| Reference::new("id"), || Reference::new("data"));
+        let id_trunc = || Reference::new("id_trunc");
+        let data_trunc = || Reference::new("data_trunc");
+        let string = |v: &str| PrimitiveValue::String(v.to_string());
+
+        assert_eq!(
+            project(id().less_than(PrimitiveValue::Long(25))),
+            id_trunc().less_than(PrimitiveValue::Long(20))
+        );
+        assert_eq!(
+            project(id().less_than_or_equal_to(PrimitiveValue::Long(29))),
+            id_trunc().less_than(PrimitiveValue::Long(30))
+        );
+        assert_eq!(
+            project(id().greater_than_or_equal_to(PrimitiveValue::Long(20))),
+            id_trunc().greater_than(PrimitiveValue::Long(10))
+        );
+        assert_eq!(
+            project(id().not_equal_to(PrimitiveValue::Long(25))),
+            id_trunc().not_equal_to(PrimitiveValue::Long(20))
+        );
+        assert_eq!(
+            project(id().equal_to(PrimitiveValue::Long(25))),
+            Predicate::AlwaysFalse
+        );
+
+        assert_eq!(
+            project(data().equal_to(string("a"))),
+            data_trunc().equal_to(string("a"))
+        );
+        assert_eq!(
+            project(data().equal_to(string("abc"))),
+            Predicate::AlwaysFalse
+        );
+        assert_eq!(
+            project(data().starts_with("ab")),
+            data_trunc().starts_with("ab")
+        );
+        assert_eq!(project(data().starts_with("abc")), Predicate::AlwaysFalse);
+        assert_eq!(
+            project(data().not_starts_with("abc")),
+            data_trunc().not_starts_with("ab")
+        );
+        assert_eq!(
+            project(
+                id().less_than(PrimitiveValue::Long(25))
+                    .or(Reference::new("v").is_null())
+            ),
+            id_trunc().less_than(PrimitiveValue::Long(20))
+        );
+    }
+
+    #[test]
+    fn test_bucket_projection() {
+        let spec = transform_spec(vec![
+            (1, Transform::Bucket(16), "id_bucket"),
+            (1, Transform::Truncate(10), "id_trunc"),
+        ]);
+        let id = || Reference::new("id");
+        let bucket =
+            |v: i64| PrimitiveValue::Int(bucket_hash(&PrimitiveValue::Long(v), 16).unwrap());
+
+        // Projections of all partition fields of the column are combined.
+        assert_eq!(
+            inclusive_projection(&id().equal_to(PrimitiveValue::Long(25)), &schema(), &spec)
+                .unwrap(),
+            Reference::new("id_bucket")
+                .equal_to(bucket(25))
+                .and(Reference::new("id_trunc").equal_to(PrimitiveValue::Long(20)))
+        );
+        assert_eq!(
+            strict_projection(
+                &id().is_not_in([PrimitiveValue::Long(25)]),
+                &schema(),
+                &spec
+            )
+            .unwrap(),
+            Reference::new("id_bucket")
+                .is_not_in([bucket(25)])
+                .or(Reference::new("id_trunc").is_not_in([PrimitiveValue::Long(20)]))
+        );
+        // Buckets are not ordered.
+        assert_eq!(
+            inclusive_projection(&id().less_than(PrimitiveValue::Long(25)), &schema(), &spec)
+                .unwrap(),
+            Reference::new("id_trunc").less_than_or_equal_to(PrimitiveValue::Long(20))
+        );
+    }
+
+    #[test]
+    fn test_transform_partition_evaluation() {
+        let spec = transform_spec(vec![(3, Transform::Month, "day_month")]);
+        let mut file = data_file(None);
+        let partition_type = Arc::new(Struct::new(vec![Field {
+            id: 1000,
+            name: "day_month".to_string(),
+            required: false,
+            field_type: Any::Primitive(Primitive::Int),
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        }]));
+        let mut builder = StructValueBuilder::new(partition_type);
+        builder
+            .add_field(1000, Some(AnyValue::Primitive(PrimitiveValue::Int(636))))
+            .unwrap();
+        file.partition = builder.build().unwrap();
+        let evaluator = |p: Predicate| DataFileEvaluator::try_new(&p, &schema(), &spec).unwrap();
+        let day = |m, d| PrimitiveValue::Date(NaiveDate::from_ymd_opt(2023, m, d).unwrap());
+        let r = || Reference::new("day");
+
+        assert!(evaluator(r().equal_to(day(1, 15))).might_match(&file));
+        assert!(!evaluator(r().equal_to(day(2, 1))).might_match(&file));
+        assert!(!evaluator(r().less_than(day(1, 1))).might_match(&file));
+
+        // All days of 2023-01 match.
+        let e = evaluator(r().greater_than_or_equal_to(day(1, 1)));
+        assert!(!e.is_partition_predicate());
+        assert!(e.partition_matches(&file));
+        assert!(!evaluator(r().greater_than_or_equal_to(day(1, 2))).partition_matches(&file));
+        assert!(evaluator(r().less_than(day(2, 1))).partition_matches(&file));
+
+        let manifest = ManifestListEntry {
+            manifest_path: "m.avro".to_string(),
+            manifest_length: 100,
+            partition_spec_id: 0,
+            content: ManifestContentType::Data,
+            sequence_number: 1,
+            min_sequence_number: 1,
+            added_snapshot_id: 1,
+            added_data_files_count: 1,
+            existing_data_files_count: 0,
+            deleted_data_files_count: 0,
+            added_rows_count: 10,
+            existing_rows_count: 0,
+            deleted_rows_count: 0,
+            partitions: vec![FieldSummary {
+                contains_null: false,
+                contains_nan: None,
+                lower_bound: Some(encode_bound(&PrimitiveValue::Int(636))),
+                upper_bound: Some(encode_bound(&PrimitiveValue::Int(637))),
+            }],
+            key_metadata: None,
+        };
+        assert!(evaluator(r().equal_to(day(2, 10))).might_match_manifest(&manifest));
+        assert!(!evaluator(r().equal_to(day(3, 1))).might_match_manifest(&manifest));
+    }
+
+    #[test]
+    fn test_invalid_reference() {
+        assert!(DataFileEvaluator::try_new(
+            &Reference::new("unknown").is_null(),
+            &schema(),
+            &partition_spec()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_case_insensitive_binding() {
+        let d1 = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let p = Reference::new("DAY").equal_to(PrimitiveValue::Date(d1));
+        let bind = |schema: &Schema, case_sensitive| {
+            DataFileEvaluator::try_new_with_case_sensitive(
+                &p,
+                schema,
+                &partition_spec(),
+                case_sensitive,
+            )
+        };
+        let mut schema = schema();
+        assert!(bind(&schema, true).is_err());
+        let evaluator = bind(&schema, false).unwrap();
+        assert_eq!(evaluator.field_id("DAY"), Some(3));
+        assert!(evaluator.is_partition_predicate());
+
+        // Names matching more than one column are conflicts.
+        let mut conflict = schema.fields[2].clone();
+        conflict.id = 5;
+        conflict.name = "Day".to_string();
+        schema.fields.push(conflict);
+        let err = bind(&schema, false).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IcebergDataInvalid);
+    }
+
+    #[test]
+    fn test_manifest_evaluation() {
+        let d1 = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let d2 = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        let d3 = NaiveDate::from_ymd_opt(2023, 1, 3).unwrap();
+        let manifest = |summary: FieldSummary| ManifestListEntry {
+            manifest_path: "m.avro".to_string(),
+            manifest_length: 100,
+            partition_spec_id: 0,
+            content: ManifestContentType::Data,
+            sequence_number: 1,
+            min_sequence_number: 1,
+            added_snapshot_id: 1,
+            added_data_files_count: 1,
+            existing_data_files_count: 0,
+            deleted_data_files_count: 0,
+            added_rows_count: 10,
+            existing_rows_count: 0,
+            deleted_rows_count: 0,
+            partitions: vec![summary],
+            key_metadata: None,
+        };
+        let might_match = |p: Predicate, manifest: &ManifestListEntry| {
+            DataFileEvaluator::try_new(&p, &schema(), &partition_spec())
+                .unwrap()
+                .might_match_manifest(manifest)
+        };
+        let day = || Reference::new("day");
+
+        let m = manifest(FieldSummary {
+            contains_null: false,
+            contains_nan: Some(false),
+            lower_bound: Some(encode_bound(&PrimitiveValue::Date(d1))),
+            upper_bound: Some(encode_bound(&PrimitiveValue::Date(d2))),
+        });
+        assert!(might_match(day().equal_to(PrimitiveValue::Date(d2)), &m));
+        assert!(!might_match(day().equal_to(PrimitiveValue::Date(d3)), &m));
+        assert!(!might_match(day().less_than(PrimitiveValue::Date(d1)), &m));
+        assert!(might_match(
+            day().less_than_or_equal_to(PrimitiveValue::Date(d1)),
+            &m
+        ));
+        assert!(!might_match(
+            day().greater_than(PrimitiveValue::Date(d2)),
+            &m
+        ));
+        assert!(might_match(
+            day().is_in([PrimitiveValue::Date(d2), PrimitiveValue::Date(d3)]),
+            &m
+        ));
+        assert!(!might_match(day().is_null(), &m));
+        assert!(might_match(day().is_not_null(), &m));
+        // Columns not partitioned by identity are not pruned.
+        assert!(might_match(
+            Reference::new("id").equal_to(PrimitiveValue::Long(100)),
+            &m
+        ));
+
+        // All partition values are null.
+        let m = manifest(FieldSummary {
+            contains_null: true,
+            ..Default::default()
+        });
+        assert!(might_match(day().is_null(), &m));
+        assert!(!might_match(day().is_not_null(), &m));
+        assert!(!might_match(day().equal_to(PrimitiveValue::Date(d1)), &m));
+
+        // Summaries written without bounds are not pruned.
+        let m = manifest(FieldSummary::default());
+        assert!(might_match(day().equal_to(PrimitiveValue::Date(d1)), &m));
+    }
+}
@@ -1,5 +1,6 @@
 //! in_memory module provides the definition of iceberg in-memory data types.
 
+use std::fmt::{self, Display, Formatter};
 use std::hash::Hasher;
 use std::sync::Arc;
 use std::{collections::HashMap, str::FromStr};
@@ -10,7 +11,6 @@ use chrono::NaiveDate;
 use chrono::NaiveDateTime;
 use chrono::NaiveTime;
 use chrono::Utc;
-use opendal::Operator;
 use ordered_float::OrderedFloat;
 use rust_decimal::Decimal;
 use serde::ser::SerializeMap;
@@ -19,13 +19,15 @@ use serde::Serialize;
 use std::hash::Hash;
 use uuid::Uuid;
 
-use crate::types::parse_manifest_list;
+use crate::io::policy::IoKind;
+use crate::table_properties;
+use crate::types::{parse_manifest_file, parse_manifest_list, Datum};
 use crate::ErrorKind;
 use crate::Result;
 use crate::{Error, Table};
 
 pub(crate) const UNASSIGNED_SEQ_NUM: i64 = -1;
-const MAIN_BRANCH: &str = "main";
+pub(crate) const MAIN_BRANCH: &str = "main";
 
 /// All data types are either primitives or nested types, which are maps, lists, or structs.
 #[derive(Debug, PartialEq, Clone, Eq)]
@@ -87,7 +89,7 @@ impl Serialize for AnyValue {
 }
 
 /// Primitive Types within a schema.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Primitive {
     /// True or False
     Boolean,
@@ -131,6 +133,16 @@ pub enum Primitive {
     /// values are stored as UTC and do not retain a source time zone
     /// (`2017-11-16 17:10:34 PST` is stored/retrieved as `2017-11-17 01:10:34 UTC` and these values are considered identical).
     Timestampz,
+    /// Timestamp without timezone, with nanosecond precision.
+    ///
+    /// Added in format version 3, values are stored as a long that encodes
+    /// nanoseconds from the unix epoch.
+    TimestampNs,
+    /// Timestamp with timezone, with nanosecond precision.
+    ///
+    /// Added in format version 3, values are stored as a long that encodes
+    /// nanoseconds from the unix epoch in UTC.
+    TimestampzNs,
     /// Arbitrary-length character sequences, Encoded with UTF-8
     ///
     /// Character strings must be stored as UTF-8 encoded byte arrays.
@@ -182,6 +194,10 @@ pub enum PrimitiveValue {
     /// values are stored as UTC and do not retain a source time zone
     /// (`2017-11-16 17:10:34 PST` is stored/retrieved as `2017-11-17 01:10:34 UTC` and these values are considered identical).
     Timestampz(DateTime<Utc>),
+    /// Timestamp without timezone, with nanosecond precision.
+    TimestampNs(NaiveDateTime),
+    /// Timestamp with timezone, with nanosecond precision.
+    TimestampzNs(DateTime<Utc>),
     /// Arbitrary-length character sequences, Encoded with UTF-8
     ///
     /// Character strings must be stored as UTF-8 encoded byte arrays.
@@ -208,8 +224,18 @@ impl Serialize for PrimitiveValue {
             PrimitiveValue::Decimal(value) => serializer.serialize_str(&value.to_string()),
             PrimitiveValue::Date(value) => serializer.serialize_str(&value.to_string()),
             PrimitiveValue::Time(value) => serializer.serialize_str(&value.to_string()),
-            PrimitiveValue::Timestamp(value) => serializer.serialize_str(&value.to_string()),
-            PrimitiveValue::Timestampz(value) => serializer.serialize_str(&value.to_string()),
+            PrimitiveValue::Timestamp(value) => {
+                serializer.serialize_str(&value.format("%Y-%m-%dT%H:%M:%S%.6f").to_string())
+            }
+            PrimitiveValue::Timestampz(value) => {
+                serializer.serialize_str(&value.format("%Y-%m-%dT%H:%M:%S%.6f+00:00").to_string())
+            }
+            PrimitiveValue::TimestampNs(value) => {
+                serializer.serialize_str(&value.format("%Y-%m-%dT%H:%M:%S%.9f").to_string())
+            }
+            PrimitiveValue::TimestampzNs(value) => {
+                serializer.serialize_str(&value.format("%Y-%m-%dT%H:%M:%S%.9f+00:00").to_string())
+            }
             PrimitiveValue::String(value) => serializer.serialize_str(value),
             PrimitiveValue::Uuid(value) => serializer.serialize_str(&value.to_string()),
             PrimitiveValue::Fixed(value) => serializer.serialize_bytes(value),
@@ -288,7 +314,7 @@ pub struct Field {
 }
 
 impl Field {
-    fn required(id: i32, name: impl Into<String>, r#type: Any) -> Self {
+    pub(crate) fn required(id: i32, name: impl Into<String>, r#type: Any) -> Self {
         Self {
             id,
             name: name.into(),
@@ -300,7 +326,7 @@ impl Field {
         }
     }
 
-    fn optional(id: i32, name: impl Into<String>, r#type: Any) -> Self {
+    pub(crate) fn optional(id: i32, name: impl Into<String>, r#type: Any) -> Self {
         Self {
             id,
             name: name.into(),
@@ -332,6 +358,11 @@ pub struct StructValue {
 }
 
 impl StructValue {
+    /// Returns the struct type of this value.
+    pub fn type_info(&self) -> &Struct {
+        &self.type_info
+    }
+
     /// Create a iterator to read the field in order of (field_id, field_value, field_name).
     pub fn iter(&self) -> impl Iterator<Item = (i32, Option<&AnyValue>, &str)> {
         self.null_bitmap
@@ -485,6 +516,114 @@ pub struct Schema {
     pub fields: Vec<Field>,
 }
 
+impl Schema {
+    /// Returns names of fields from the top level to the field with given
+    /// id, fields nested in lists or maps are not searched.
+    pub fn field_path(&self, field_id: i32) -> Option<Vec<&str>> {
+        fn search(fields: &[Field], field_id: i32) -> Option<Vec<&str>> {
+            for field in fields {
+                if field.id == field_id {
+                    return Some(vec![field.name.as_str()]);
+                }
+                if let Any::Struct(s) = &field.field_type {
+                    if let Some(mut path) = search(s.fields(), field_id) {
+                        path.insert(0, field.name.as_str());
+                        return Some(path);
+                    }
+                }
+            }
+            None
+        }
+
+        search(&self.fields, field_id)
+    }
+
+    /// Returns the field with given id, fields nested in lists or maps are
+    /// not searched.
+    pub fn field_by_id(&self, field_id: i32) -> Option<&Field> {
+        fn search(fields: &[Field], field_id: i32) -> Option<&Field> {
+            fields.iter().find_map(|field| {
+                if field.id == field_id {
+                    return Some(field);
+                }
+                match &field.field_type {
+                    Any::Struct(s) => search(s.fields(), field_id),
+                    _ => None,
+                }
+            })
+        }
+
+        search(&self.fields, field_id)
+    }
+
+    /// Returns fields of `identifier_field_ids`, which identify rows of the
+    /// table like a primary key. Ids not found in the schema are skipped.
+    pub fn identifier_fields(&self) -> Vec<&Field> {
+        self.identifier_field_ids
+            .iter()
+            .flatten()
+            .filter_map(|id| self.field_by_id(*id))
+            .collect()
+    }
+
+    /// Returns id of the field with given name, nested fields are named
+    /// like `a.b`.
+    pub fn field_id_by_name(&self, name: &str) -> Option<i32> {
+        let mut fields = self.fields.as_slice();
+        let mut result = None;
+        for part in name.split('.') {
+            let field = fields.iter().find(|v| v.name == part)?;
+            fields = match &field.field_type {
+                Any::Struct(s) => s.fields(),
+                _ => &[],
+            };
+            result = Some(field.id);
+        }
+        result
+    }
+
+    /// Like [`Schema::field_id_by_name`], but names are matched case
+    /// insensitively.
+    ///
+    /// Returns error if a part of the name matches more than one field,
+    /// like `a` matching both `a` and `A`.
+    pub fn field_id_by_name_case_insensitive(&self, name: &str) -> Result<Option<i32>> {
+        let mut fields = self.fields.as_slice();
+        let mut result = None;
+        for part in name.split('.') {
+            let part = part.to_lowercase();
+            let mut matched = fields.iter().filter(|v| v.name.to_lowercase() == part);
+            let Some(field) = matched.next() else {
+                return Ok(None);
+            };
+            if let Some(other) = matched.next() {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!(
+                        "column {name} is ambiguous, matching both {} and {} case insensitively",
+                        field.name, other.name
+                    ),
+                ));
+            }
+            fields = match &field.field_type {
+                Any::Struct(s) => s.fields(),
+                _ => &[],
+            };
+            result = Some(field.id);
+        }
+        Ok(result)
+    }
+
+    /// Returns id of the field with given name, matched case sensitively
+    /// or not, see [`Schema::field_id_by_name_case_insensitive`].
+    pub fn find_field_id(&self, name: &str, case_sensitive: bool) -> Result<Option<i32>> {
+        match case_sensitive {
+            true => Ok(self.field_id_by_name(name)),
+            false => self.field_id_by_name_case_insensitive(name),
+        }
+    }
+}
+
 /// Transform is used to transform predicates to partition predicates,
 /// in addition to transforming data values.
 ///
@@ -583,28 +722,64 @@ pub enum Transform {
 }
 
 impl Transform {
-    fn result_type(&self, input_type: &Any) -> Result<Any> {
+    pub(crate) fn result_type(&self, input_type: &Any) -> Result<Any> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Transform {self} can't be applied on type {input_type:?}"),
+            )
+        };
+        let Any::Primitive(primitive) = input_type else {
+            return match self {
+                Transform::Identity | Transform::Void => Ok(input_type.clone()),
+                _ => Err(invalid()),
+            };
+        };
+
         match self {
-            Transform::Identity => Ok(input_type.clone()),
-            _ => Err(Error::new(
-                ErrorKind::IcebergFeatureUnsupported,
-                format!("Transform {:?} not supported yet!", &self),
-            )),
+            Transform::Identity | Transform::Void => Ok(input_type.clone()),
+            Transform::Bucket(_) => match primitive {
+                Primitive::Boolean | Primitive::Float | Primitive::Double => Err(invalid()),
+                _ => Ok(Any::Primitive(Primitive::Int)),
+            },
+            Transform::Truncate(_) => match primitive {
+                Primitive::Int
+                | Primitive::Long
+                | Primitive::Decimal { .. }
+                | Primitive::String
+                | Primitive::Binary => Ok(input_type.clone()),
+                _ => Err(invalid()),
+            },
+            Transform::Year | Transform::Month | Transform::Day => match primitive {
+                Primitive::Date
+                | Primitive::Timestamp
+                | Primitive::Timestampz
+                | Primitive::TimestampNs
+                | Primitive::TimestampzNs => Ok(Any::Primitive(Primitive::Int)),
+                _ => Err(invalid()),
+            },
+            Transform::Hour => match primitive {
+                Primitive::Timestamp
+                | Primitive::Timestampz
+                | Primitive::TimestampNs
+                | Primitive::TimestampzNs => Ok(Any::Primitive(Primitive::Int)),
+                _ => Err(invalid()),
+            },
         }
     }
 }
 
-impl<'a> ToString for &'a Transform {
-    fn to_string(&self) -> String {
+impl Display for Transform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Transform::Identity => "identity".to_string(),
-            Transform::Year => "year".to_string(),
-            Transform::Month => "month".to_string(),
-            Transform::Day => "day".to_string(),
-            Transform::Hour => "hour".to_string(),
-            Transform::Void => "void".to_string(),
-            Transform::Bucket(length) => format!("bucket[{}]", length),
-            Transform::Truncate(width) => format!("truncate[{}]", width),
+            Transform::Identity => write!(f, "identity"),
+            Transform::Year => write!(f, "year"),
+            Transform::Month => write!(f, "month"),
+            Transform::Day => write!(f, "day"),
+            Transform::Hour => write!(f, "hour"),
+            Transform::Void => write!(f, "void"),
+            Transform::Bucket(length) => write!(f, "bucket[{}]", length),
+            Transform::Truncate(width) => write!(f, "truncate[{}]", width),
         }
     }
 }
@@ -785,11 +960,11 @@ pub enum SortDirection {
     DESC,
 }
 
-impl ToString for SortDirection {
-    fn to_string(&self) -> String {
+impl Display for SortDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            SortDirection::ASC => "asc".to_string(),
-            SortDirection::DESC => "desc".to_string(),
+            SortDirection::ASC => write!(f, "asc"),
+            SortDirection::DESC => write!(f, "desc"),
         }
     }
 }
@@ -819,11 +994,11 @@ pub enum NullOrder {
     Last,
 }
 
-impl ToString for NullOrder {
-    fn to_string(&self) -> String {
+impl Display for NullOrder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            NullOrder::First => "nulls-first".to_string(),
-            NullOrder::Last => "nulls-last".to_string(),
+            NullOrder::First => write!(f, "nulls-first"),
+            NullOrder::Last => write!(f, "nulls-last"),
         }
     }
 }
@@ -843,6 +1018,83 @@ impl FromStr for NullOrder {
     }
 }
 
+/// Name mapping maps names of columns in data files written without field
+/// ids, like files migrated from hive tables, to field ids of the table
+/// schema.
+///
+/// It's stored as json in the table property `schema.name-mapping.default`,
+/// see [`crate::types::parse_name_mapping`].
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct NameMapping {
+    /// Mapped fields of the top level.
+    pub fields: Vec<MappedField>,
+}
+
+/// Field of a name mapping.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MappedField {
+    /// Field id that columns of given names are mapped to, columns are
+    /// ignored if not set.
+    pub field_id: Option<i32>,
+    /// Names of columns mapped to this field, which may contain old names
+    /// of the field.
+    pub names: Vec<String>,
+    /// Mapped fields of nested struct fields, list elements and map keys
+    /// and values.
+    pub fields: Vec<MappedField>,
+}
+
+impl NameMapping {
+    /// Create a name mapping mapping current names of fields in `schema`
+    /// to their ids.
+    pub fn from_schema(schema: &Schema) -> Self {
+        Self {
+            fields: schema.fields.iter().map(MappedField::from_field).collect(),
+        }
+    }
+
+    /// Find the mapped field of a top level column named `name`.
+    pub fn find(&self, name: &str) -> Option<&MappedField> {
+        find_mapped_field(&self.fields, name)
+    }
+}
+
+impl MappedField {
+    fn from_field(field: &Field) -> Self {
+        Self {
+            field_id: Some(field.id),
+            names: vec![field.name.clone()],
+            fields: Self::from_type(&field.field_type),
+        }
+    }
+
+    fn from_type(ty: &Any) -> Vec<Self> {
+        let mapped = |id: i32, name: &str, ty: &Any| Self {
+            field_id: Some(id),
+            names: vec![name.to_string()],
+            fields: Self::from_type(ty),
+        };
+        match ty {
+            Any::Primitive(_) => vec![],
+            Any::Struct(v) => v.fields().iter().map(Self::from_field).collect(),
+            Any::List(v) => vec![mapped(v.element_id, "element", &v.element_type)],
+            Any::Map(v) => vec![
+                mapped(v.key_id, "key", &v.key_type),
+                mapped(v.value_id, "value", &v.value_type),
+            ],
+        }
+    }
+
+    /// Find the mapped nested field of a column named `name`.
+    pub fn find(&self, name: &str) -> Option<&MappedField> {
+        find_mapped_field(&self.fields, name)
+    }
+}
+
+fn find_mapped_field<'a>(fields: &'a [MappedField], name: &str) -> Option<&'a MappedField> {
+    fields.iter().find(|v| v.names.iter().any(|v| v == name))
+}
+
 /// Snapshots are embedded in table metadata, but the list of manifests for a
 /// snapshot are stored in a separate manifest list file.
 ///
@@ -966,6 +1218,39 @@ pub struct ManifestListEntry {
     pub key_metadata: Option<Vec<u8>>,
 }
 
+impl ManifestListEntry {
+    /// Load the manifest file of this entry.
+    ///
+    /// Snapshot ids and sequence numbers of entries are inherited from this
+    /// entry if not recorded, like entries of v1 manifests and entries
+    /// added by v2 writers, so that they are always set after loading.
+    pub(crate) async fn load_manifest(&self, table: &Table) -> Result<ManifestFile> {
+        let cache = table.manifest_cache();
+        let mut manifest = match cache.and_then(|v| v.get_manifest(&self.manifest_path)) {
+            Some(manifest) => manifest.as_ref().clone(),
+            None => {
+                let (op, path) = table.io_operator(IoKind::Metadata, &self.manifest_path)?;
+                let content = op.read(&path).await?;
+                let manifest = parse_manifest_file(&content)?;
+                if let Some(cache) = cache {
+                    cache.insert_manifest(
+                        &self.manifest_path,
+                        content.len() as u64,
+                        Arc::new(manifest.clone()),
+                    );
+                }
+                manifest
+            }
+        };
+        for entry in &mut manifest.entries {
+            entry.snapshot_id = entry.snapshot_id.or(Some(self.added_snapshot_id));
+            entry.sequence_number = entry.sequence_number.or(Some(self.sequence_number));
+            entry.file_sequence_number = entry.file_sequence_number.or(entry.sequence_number);
+        }
+        Ok(manifest)
+    }
+}
+
 mod manifest_list {
     use super::*;
     use once_cell::sync::Lazy;
@@ -1012,17 +1297,17 @@ mod manifest_list {
         Lazy::new(|| Field::required(514, "deleted_rows_count", Any::Primitive(Primitive::Long)));
     pub static PARTITIONS: Lazy<Field> = Lazy::new(|| {
         Field::optional(
-            13,
+            507,
             "partitions",
             Any::List(List {
-                element_id: 13,
-                element_required: false,
+                element_id: 508,
+                element_required: true,
                 element_type: Box::new(Any::Struct(
                     Struct::new(vec![
-                        Field::required(0, "contains_null", Any::Primitive(Primitive::Boolean)),
-                        Field::optional(1, "contains_nan", Any::Primitive(Primitive::Boolean)),
-                        Field::optional(2, "lower_bound", Any::Primitive(Primitive::Binary)),
-                        Field::optional(2, "upper_bound", Any::Primitive(Primitive::Binary)),
+                        Field::required(509, "contains_null", Any::Primitive(Primitive::Boolean)),
+                        Field::optional(518, "contains_nan", Any::Primitive(Primitive::Boolean)),
+                        Field::optional(510, "lower_bound", Any::Primitive(Primitive::Binary)),
+                        Field::optional(511, "upper_bound", Any::Primitive(Primitive::Binary)),
                     ])
                     .into(),
                 )),
@@ -1036,9 +1321,7 @@ mod manifest_list {
 /// Field summary for partition field in the spec.
 ///
 /// Each field in the list corresponds to a field in the manifest file’s partition spec.
-///
-/// TODO: add lower_bound and upper_bound support
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct FieldSummary {
     /// field: 509
     ///
@@ -1049,6 +1332,18 @@ pub struct FieldSummary {
     /// Whether the manifest contains at least one partition with a NaN
     /// value for the field
     pub contains_nan: Option<bool>,
+    /// field: 510
+    ///
+    /// Lower bound for the non-null, non-NaN values in the partition
+    /// field, or null if all values are null or NaN, stored by the binary
+    /// single value serialization
+    pub lower_bound: Option<Vec<u8>>,
+    /// field: 511
+    ///
+    /// Upper bound for the non-null, non-NaN values in the partition
+    /// field, or null if all values are null or NaN, stored by the binary
+    /// single value serialization
+    pub upper_bound: Option<Vec<u8>>,
 }
 
 /// A manifest is an immutable Avro file that lists data files or delete
@@ -1187,11 +1482,11 @@ pub enum ManifestContentType {
     Deletes = 1,
 }
 
-impl ToString for ManifestContentType {
-    fn to_string(&self) -> String {
+impl Display for ManifestContentType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            ManifestContentType::Data => "data".to_string(),
-            ManifestContentType::Deletes => "deletes".to_string(),
+            ManifestContentType::Data => write!(f, "data"),
+            ManifestContentType::Deletes => write!(f, "deletes"),
         }
     }
 }
@@ -1563,6 +1858,36 @@ impl DataFile {
             sort_order_id: None,
         }
     }
+
+    /// Returns the lower bound of the column of `field_id` decoded as type
+    /// `ty`, `None` if the bound is not recorded.
+    ///
+    /// Bounds of string and binary columns may be truncated, so they are
+    /// only bounds rather than the min values.
+    pub fn lower_bound(&self, field_id: i32, ty: Primitive) -> Result<Option<Datum>> {
+        Self::bound(&self.lower_bounds, field_id, ty)
+    }
+
+    /// Returns the upper bound of the column of `field_id` decoded as type
+    /// `ty`, see [`DataFile::lower_bound`].
+    pub fn upper_bound(&self, field_id: i32, ty: Primitive) -> Result<Option<Datum>> {
+        Self::bound(&self.upper_bounds, field_id, ty)
+    }
+
+    fn bound(
+        bounds: &Option<HashMap<i32, Vec<u8>>>,
+        field_id: i32,
+        ty: Primitive,
+    ) -> Result<Option<Datum>> {
+        bounds
+            .as_ref()
+            .and_then(|v| v.get(&field_id))
+            .map(|v| {
+                Datum::try_from_bytes(ty, v)
+                    .map_err(|e| e.with_context("field_id", field_id.to_string()))
+            })
+            .transpose()
+    }
 }
 
 /// Type of content stored by the data file: data, equality deletes, or
@@ -1620,14 +1945,14 @@ impl FromStr for DataFileFormat {
     }
 }
 
-impl ToString for DataFileFormat {
-    fn to_string(&self) -> String {
+impl Display for DataFileFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             DataFileFormat::Avro => "avro",
             DataFileFormat::Orc => "orc",
             DataFileFormat::Parquet => "parquet",
         }
-        .to_string()
+        .fmt(f)
     }
 }
 
@@ -1684,11 +2009,40 @@ pub struct Snapshot {
 }
 
 impl Snapshot {
-    pub(crate) async fn load_manifest_list(&self, op: &Operator) -> Result<ManifestList> {
-        parse_manifest_list(
-            &op.read(Table::relative_path(op, self.manifest_list.as_str())?.as_str())
-                .await?,
-        )
+    pub(crate) async fn load_manifest_list(&self, table: &Table) -> Result<ManifestList> {
+        let cache = table.manifest_cache();
+        if let Some(manifest_list) = cache.and_then(|v| v.get_manifest_list(&self.manifest_list)) {
+            return Ok(manifest_list.as_ref().clone());
+        }
+        let (op, path) = table.io_operator(IoKind::Metadata, self.manifest_list.as_str())?;
+        let content = op.read(path.as_str()).await?;
+        let manifest_list = parse_manifest_list(&content)?;
+        if let Some(cache) = cache {
+            cache.insert_manifest_list(
+                &self.manifest_list,
+                content.len() as u64,
+                Arc::new(manifest_list.clone()),
+            );
+        }
+        Ok(manifest_list)
+    }
+
+    /// Returns this snapshot and its ancestors in `metadata`, from the
+    /// newest to the oldest, by walking `parent_snapshot_id`.
+    ///
+    /// Walking stops at the first parent not found, for example, expired.
+    pub fn ancestors<'a>(&'a self, metadata: &'a TableMetadata) -> Vec<&'a Snapshot> {
+        let parents = self
+            .parent_snapshot_id
+            .map(|id| metadata.ancestors(id))
+            .unwrap_or_default();
+        std::iter::once(self)
+            .chain(
+                parents
+                    .into_iter()
+                    .take_while(|v| v.snapshot_id != self.snapshot_id),
+            )
+            .collect()
     }
 
     pub(crate) fn log(&self) -> SnapshotLog {
@@ -1759,6 +2113,11 @@ impl SnapshotReference {
             max_ref_age_ms: None,
         }
     }
+
+    /// Returns true if the reference is a branch.
+    pub fn is_branch(&self) -> bool {
+        self.typ == SnapshotReferenceType::Branch
+    }
 }
 
 /// Type of the reference
@@ -1770,11 +2129,11 @@ pub enum SnapshotReferenceType {
     Branch,
 }
 
-impl ToString for SnapshotReferenceType {
-    fn to_string(&self) -> String {
+impl Display for SnapshotReferenceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            SnapshotReferenceType::Tag => "tag".to_string(),
-            SnapshotReferenceType::Branch => "branch".to_string(),
+            SnapshotReferenceType::Tag => write!(f, "tag"),
+            SnapshotReferenceType::Branch => write!(f, "branch"),
         }
     }
 }
@@ -1804,13 +2163,47 @@ pub struct MetadataLog {
     pub metadata_file: String,
 }
 
+/// Statistics file of a snapshot, which is a puffin file containing blobs
+/// of statistics like NDV sketches of columns.
+///
+/// See [`crate::types::PuffinReader`] to read blobs of the file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StatisticsFile {
+    /// ID of the Iceberg table's snapshot the statistics file is associated
+    /// with.
+    pub snapshot_id: i64,
+    /// Location of the statistics file.
+    pub statistics_path: String,
+    /// Size of the statistics file.
+    pub file_size_in_bytes: i64,
+    /// Size of the puffin footer.
+    pub file_footer_size_in_bytes: i64,
+    /// Base64-encoded implementation-specific key metadata for encryption.
+    pub key_metadata: Option<String>,
+    /// Metadata of blobs in the statistics file.
+    pub blob_metadata: Vec<BlobMetadata>,
+}
+
+/// Metadata of a blob in a statistics file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlobMetadata {
+    /// Type of the blob, like `apache-datasketches-theta-v1`.
+    pub typ: String,
+    /// ID of the snapshot the blob was computed from.
+    pub snapshot_id: i64,
+    /// Sequence number of the snapshot the blob was computed from.
+    pub sequence_number: i64,
+    /// Ordered list of field ids the blob was computed from.
+    pub fields: Vec<i32>,
+    /// Additional properties of the blob, like `ndv`.
+    pub properties: HashMap<String, String>,
+}
+
 /// Table metadata is stored as JSON. Each table metadata change creates a
 /// new table metadata file that is committed by an atomic operation. This
 /// operation is used to ensure that a new version of table metadata replaces
 /// the version on which it was based. This produces a linear history of
 /// table versions and ensures that concurrent writes are not lost.
-///
-/// TODO: statistics is not supported.
 #[derive(Debug, PartialEq, Clone)]
 pub struct TableMetadata {
     /// Currently, this can be 1 or 2 based on the spec. Implementations
@@ -1896,6 +2289,8 @@ pub struct TableMetadata {
     /// There is always a main branch reference pointing to the
     /// `current-snapshot-id` even if the refs map is null.
     pub refs: HashMap<String, SnapshotReference>,
+    /// Statistics files of snapshots, at most one for each snapshot.
+    pub statistics: Vec<StatisticsFile>,
 }
 
 impl TableMetadata {
@@ -1925,6 +2320,26 @@ impl TableMetadata {
             })
     }
 
+    /// Statistics file of given snapshot.
+    pub fn statistics_file(&self, snapshot_id: i64) -> Option<&StatisticsFile> {
+        self.statistics
+            .iter()
+            .find(|v| v.snapshot_id == snapshot_id)
+    }
+
+    /// Default sort order.
+    pub fn current_sort_order(&self) -> Result<&SortOrder> {
+        self.sort_orders
+            .iter()
+            .find(|s| s.order_id == self.default_sort_order_id)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Sort order id {} not found!", self.default_sort_order_id),
+                )
+            })
+    }
+
     /// Current schema.
     pub fn current_snapshot(&self) -> Result<&Snapshot> {
         if let (Some(snapshots), Some(snapshot_id)) = (&self.snapshots, self.current_snapshot_id) {
@@ -1945,20 +2360,228 @@ impl TableMetadata {
         }
     }
 
+    /// Base location of data files.
+    ///
+    /// Use `write.data.path` (or the deprecated `write.folder-storage.path`)
+    /// of `properties` if set, otherwise `{location}/data`. `properties`
+    /// are the effective properties of the table, like
+    /// [`crate::Table::properties`] merged with the config of the table.
+    pub fn data_location(&self, properties: &HashMap<String, String>) -> String {
+        properties
+            .get(table_properties::WRITE_DATA_LOCATION)
+            .or(properties.get(table_properties::WRITE_FOLDER_STORAGE_LOCATION))
+            .map(|v| v.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("{}/data", self.location))
+    }
+
+    /// Base location of manifests and manifest lists.
+    ///
+    /// Use `write.metadata.path` of `properties` if set, otherwise
+    /// `{location}/metadata`, see [`TableMetadata::data_location`].
+    pub fn metadata_location(&self, properties: &HashMap<String, String>) -> String {
+        properties
+            .get(table_properties::WRITE_METADATA_LOCATION)
+            .map(|v| v.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("{}/metadata", self.location))
+    }
+
+    /// Returns the location recorded in metadata for a file at `location`.
+    ///
+    /// Locations under table location are recorded relative to it if
+    /// `icelake.write.relative-path.enabled` of `properties` is set, see
+    /// [`TableMetadata::data_location`].
+    pub fn recorded_location(
+        &self,
+        properties: &HashMap<String, String>,
+        location: &str,
+    ) -> Result<String> {
+        let enabled = match properties.get(table_properties::WRITE_RELATIVE_PATH_ENABLED) {
+            Some(v) => v.trim().parse::<bool>().map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!(
+                        "Invalid value of property {}: {v}",
+                        table_properties::WRITE_RELATIVE_PATH_ENABLED
+                    ),
+                )
+                .set_source(e)
+            })?,
+            None => table_properties::WRITE_RELATIVE_PATH_ENABLED_DEFAULT,
+        };
+        if !enabled {
+            return Ok(location.to_string());
+        }
+
+        let relative = location
+            .strip_prefix(self.location.trim_end_matches('/'))
+            .filter(|rest| rest.starts_with('/'));
+        Ok(match relative {
+            Some(rest) => rest.trim_start_matches('/').to_string(),
+            None => location.to_string(),
+        })
+    }
+
+    /// Returns the snapshot of given id.
+    pub fn snapshot(&self, snapshot_id: i64) -> Option<&Snapshot> {
+        self.snapshots
+            .iter()
+            .flatten()
+            .find(|s| s.snapshot_id == snapshot_id)
+    }
+
+    /// Returns the head snapshot of a branch, or the snapshot of a tag.
+    ///
+    /// The main branch falls back to the current snapshot for tables
+    /// written by old writers without refs.
+    pub fn snapshot_by_ref(&self, name: &str) -> Option<&Snapshot> {
+        match self.refs.get(name) {
+            Some(r) => self.snapshot(r.snapshot_id),
+            None if name == MAIN_BRANCH => self
+                .current_snapshot_id
+                .and_then(|snapshot_id| self.snapshot(snapshot_id)),
+            None => None,
+        }
+    }
+
+    /// Set an existing snapshot as the current snapshot and the head of the
+    /// main branch at `timestamp_ms`.
+    pub(crate) fn set_current_snapshot(
+        &mut self,
+        snapshot_id: i64,
+        timestamp_ms: i64,
+    ) -> Result<()> {
+        if self.snapshot(snapshot_id).is_none() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Snapshot id {snapshot_id} not found!"),
+            ));
+        }
+        self.last_updated_ms = timestamp_ms;
+        self.current_snapshot_id = Some(snapshot_id);
+        self.refs
+            .entry(MAIN_BRANCH.to_string())
+            .and_modify(|s| s.snapshot_id = snapshot_id)
+            .or_insert_with(|| SnapshotReference::new(snapshot_id, SnapshotReferenceType::Branch));
+        self.snapshot_log
+            .get_or_insert_with(Vec::new)
+            .push(SnapshotLog {
+                timestamp_ms,
+                snapshot_id,
+            });
+        Ok(())
+    }
+
+    /// Add a previous metadata file to the metadata log, keeping at most
+    /// `max` (at least 1) latest entries. The file is added once even if
+    /// the metadata is committed again, like by catalogs of hadoop tables.
+    ///
+    /// Returns entries dropped from the log, from the oldest.
+    pub(crate) fn add_previous_metadata(
+        &mut self,
+        previous: MetadataLog,
+        max: usize,
+    ) -> Vec<MetadataLog> {
+        let logs = self.metadata_log.get_or_insert_with(Vec::new);
+        if logs.last().map(|v| &v.metadata_file) != Some(&previous.metadata_file) {
+            logs.push(previous);
+        }
+        let removed = logs.len().saturating_sub(max.max(1));
+        logs.drain(..removed).collect()
+    }
+
+    /// Add a snapshot without changing the current snapshot or any
+    /// branch, which can be published later.
+    pub(crate) fn stage_snapshot(&mut self, snapshot: Snapshot) -> Result<()> {
+        self.validate_sequence_number(&snapshot)?;
+        self.last_updated_ms = snapshot.timestamp_ms;
+        self.last_sequence_number = snapshot.sequence_number;
+        self.snapshots.get_or_insert_with(Vec::new).push(snapshot);
+        self.snapshot_log.get_or_insert_with(Vec::new);
+        Ok(())
+    }
+
+    /// Checks new snapshots of v2 tables have sequence numbers greater than
+    /// the last sequence number, except for snapshots without parents.
+    fn validate_sequence_number(&self, snapshot: &Snapshot) -> Result<()> {
+        if self.format_version == TableFormatVersion::V1
+            || snapshot.sequence_number > self.last_sequence_number
+            || snapshot.parent_snapshot_id.is_none()
+        {
+            return Ok(());
+        }
+        Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!(
+                "Sequence number {} of snapshot {} is not greater than last sequence number {}",
+                snapshot.sequence_number, snapshot.snapshot_id, self.last_sequence_number
+            ),
+        ))
+    }
+
+    /// Returns the snapshot of given id and its ancestors, from the newest
+    /// to the oldest.
+    pub fn ancestors(&self, snapshot_id: i64) -> Vec<&Snapshot> {
+        let mut ancestors: Vec<&Snapshot> = vec![];
+        let mut next = Some(snapshot_id);
+        while let Some(snapshot) = next.and_then(|id| self.snapshot(id)) {
+            // Guard against cycles of malformed metadata.
+            if ancestors
+                .iter()
+                .any(|v| v.snapshot_id == snapshot.snapshot_id)
+            {
+                break;
+            }
+            ancestors.push(snapshot);
+            next = snapshot.parent_snapshot_id;
+        }
+        ancestors
+    }
+
+    /// Returns true if snapshot `ancestor_id` is `snapshot_id` or one of its
+    /// ancestors, see [`Snapshot::ancestors`].
+    pub fn is_ancestor_of(&self, ancestor_id: i64, snapshot_id: i64) -> bool {
+        self.ancestors(snapshot_id)
+            .iter()
+            .any(|v| v.snapshot_id == ancestor_id)
+    }
+
     pub(crate) fn append_snapshot(&mut self, snapshot: Snapshot) -> Result<()> {
+        self.append_snapshot_to_branch(snapshot, MAIN_BRANCH)
+    }
+
+    /// Add a snapshot as the new head of `branch`, the branch is created if
+    /// not exists.
+    ///
+    /// Only commits to the main branch change the current snapshot.
+    pub(crate) fn append_snapshot_to_branch(
+        &mut self,
+        snapshot: Snapshot,
+        branch: &str,
+    ) -> Result<()> {
+        if let Some(r) = self.refs.get(branch) {
+            if !r.is_branch() {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Can't commit to {branch}, which is a tag"),
+                ));
+            }
+        }
+        self.validate_sequence_number(&snapshot)?;
         self.last_updated_ms = snapshot.timestamp_ms;
         self.last_sequence_number = snapshot.sequence_number;
-        self.current_snapshot_id = Some(snapshot.snapshot_id);
 
         self.refs
-            .entry(MAIN_BRANCH.to_string())
-            .and_modify(|s| {
-                s.snapshot_id = snapshot.snapshot_id;
-                s.typ = SnapshotReferenceType::Branch;
-            })
+            .entry(branch.to_string())
+            .and_modify(|s| s.snapshot_id = snapshot.snapshot_id)
             .or_insert_with(|| {
                 SnapshotReference::new(snapshot.snapshot_id, SnapshotReferenceType::Branch)
             });
+        if branch != MAIN_BRANCH {
+            self.snapshots.get_or_insert_with(Vec::new).push(snapshot);
+            self.snapshot_log.get_or_insert_with(Vec::new);
+            return Ok(());
+        }
+        self.current_snapshot_id = Some(snapshot.snapshot_id);
 
         if let Some(snapshots) = &mut self.snapshots {
             self.snapshot_log
@@ -2011,11 +2634,11 @@ impl TryFrom<u8> for TableFormatVersion {
     }
 }
 
-impl ToString for TableFormatVersion {
-    fn to_string(&self) -> String {
+impl Display for TableFormatVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            TableFormatVersion::V1 => "1".to_string(),
-            TableFormatVersion::V2 => "2".to_string(),
+            TableFormatVersion::V1 => write!(f, "1"),
+            TableFormatVersion::V2 => write!(f, "2"),
         }
     }
 }
@@ -2024,10 +2647,83 @@ impl ToString for TableFormatVersion {
 mod test {
     use apache_avro::{schema, types::Value};
 
-    use crate::types::{Field, PrimitiveValue, Struct, StructValueBuilder};
+    use crate::types::{
+        parse_table_metadata, Field, PrimitiveValue, Snapshot, Struct, StructValueBuilder,
+        TableFormatVersion,
+    };
 
     use super::AnyValue;
 
+    #[test]
+    fn test_snapshot_ancestors() {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&std::fs::read(path).unwrap()).unwrap();
+        let first = metadata.current_snapshot().unwrap().clone();
+        let child = |snapshot_id, parent: &Snapshot| Snapshot {
+            snapshot_id,
+            parent_snapshot_id: Some(parent.snapshot_id),
+            ..parent.clone()
+        };
+        // `first` <- 2 <- 3, and `first` <- 4 of another branch.
+        let second = child(2, &first);
+        let third = child(3, &second);
+        let other = child(4, &first);
+        let snapshots = metadata.snapshots.get_or_insert_with(Vec::new);
+        snapshots.extend([second.clone(), third.clone(), other.clone()]);
+
+        let ids = |v: Vec<&Snapshot>| v.iter().map(|v| v.snapshot_id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(third.ancestors(&metadata)),
+            vec![3, 2, first.snapshot_id]
+        );
+        assert_eq!(ids(other.ancestors(&metadata)), vec![4, first.snapshot_id]);
+        assert_eq!(ids(first.ancestors(&metadata)), vec![first.snapshot_id]);
+        assert!(metadata.is_ancestor_of(first.snapshot_id, 3));
+        assert!(metadata.is_ancestor_of(3, 3));
+        assert!(!metadata.is_ancestor_of(3, 2));
+        assert!(!metadata.is_ancestor_of(2, 4));
+
+        // Walking stops at expired parents.
+        metadata
+            .snapshots
+            .as_mut()
+            .unwrap()
+            .retain(|v| v.snapshot_id != 2);
+        assert_eq!(ids(third.ancestors(&metadata)), vec![3]);
+        assert!(!metadata.is_ancestor_of(first.snapshot_id, 3));
+    }
+
+    #[test]
+    fn test_validate_sequence_number() {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut metadata = parse_table_metadata(&std::fs::read(path).unwrap()).unwrap();
+        metadata.format_version = TableFormatVersion::V2;
+        let first = metadata.current_snapshot().unwrap().clone();
+        let child = |snapshot_id, sequence_number| Snapshot {
+            snapshot_id,
+            parent_snapshot_id: Some(first.snapshot_id),
+            sequence_number,
+            ..first.clone()
+        };
+
+        metadata.append_snapshot(child(2, 1)).unwrap();
+        assert_eq!(metadata.last_sequence_number, 1);
+        assert!(metadata.append_snapshot(child(3, 1)).is_err());
+        assert!(metadata.stage_snapshot(child(3, 0)).is_err());
+        metadata.stage_snapshot(child(3, 2)).unwrap();
+        assert_eq!(metadata.last_sequence_number, 2);
+
+        // Sequence numbers are not tracked by v1.
+        metadata.format_version = TableFormatVersion::V1;
+        metadata.append_snapshot(child(4, 0)).unwrap();
+    }
+
     #[test]
     fn test_struct_to_avro() {
         let value = {
@@ -7,7 +7,14 @@ pub use in_memory::*;
 mod on_disk;
 pub use on_disk::*;
 
+mod datum;
+pub use datum::{Datum, StructLike};
+
+pub mod expression;
+
 mod to_arrow;
+pub use to_arrow::{arrow_schema_to_schema, schema_to_arrow_schema, PARQUET_FIELD_ID_META_KEY};
+pub(crate) use to_arrow::{cast_timestamps, coerce_batch, UTC_TIMEZONE};
 
 mod to_avro;
 
@@ -0,0 +1,252 @@
+//! Serde representation of data files and delete files, which is stable
+//! across processes, so that files written by distributed workers can be
+//! shipped to a single committer.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::types::Types;
+use super::{decode_bound, encode_bound};
+use crate::types::{self, Any, AnyValue, DataContentType, DataFileFormat, StructValueBuilder};
+use crate::{Error, ErrorKind, Result};
+
+/// Data file serialized with the partition type, so that it can be
+/// deserialized without the table metadata.
+///
+/// Partition values, bounds and key metadata are hex strings of the
+/// binary single-value serialization of iceberg.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DataFile {
+    content: u8,
+    file_path: String,
+    file_format: String,
+    partition_type: Types,
+    partition: Vec<Option<String>>,
+    record_count: i64,
+    file_size_in_bytes: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    column_sizes: Option<HashMap<i32, i64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value_counts: Option<HashMap<i32, i64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    null_value_counts: Option<HashMap<i32, i64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nan_value_counts: Option<HashMap<i32, i64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    distinct_counts: Option<HashMap<i32, i64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lower_bounds: Option<HashMap<i32, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    upper_bounds: Option<HashMap<i32, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_metadata: Option<String>,
+    #[serde(default)]
+    split_offsets: Vec<i64>,
+    #[serde(default)]
+    equality_ids: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sort_order_id: Option<i32>,
+}
+
+impl TryFrom<DataFile> for types::DataFile {
+    type Error = Error;
+
+    fn try_from(v: DataFile) -> Result<Self> {
+        let Any::Struct(partition_type) = Any::try_from(v.partition_type)? else {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Partition type of data file must be a struct",
+            ));
+        };
+        if partition_type.len() != v.partition.len() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!(
+                    "Data file has {} partition values, but {} partition fields",
+                    v.partition.len(),
+                    partition_type.len()
+                ),
+            ));
+        }
+        let mut builder = StructValueBuilder::new(partition_type.clone());
+        for (field, value) in partition_type.fields().iter().zip(v.partition) {
+            let value = match (&field.field_type, value) {
+                (Any::Primitive(ty), Some(value)) => {
+                    let bytes = from_hex(&value)?;
+                    let value = decode_bound(ty, &bytes).ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!("Partition value {value} is invalid for type {ty:?}"),
+                        )
+                    })?;
+                    Some(AnyValue::Primitive(value))
+                }
+                (_, None) => None,
+                (ty, Some(_)) => {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Partition field of type {ty:?} is not primitive"),
+                    ))
+                }
+            };
+            builder.add_field(field.id, value)?;
+        }
+
+        Ok(types::DataFile {
+            content: DataContentType::try_from(v.content)?,
+            file_path: v.file_path,
+            file_format: v.file_format.parse::<DataFileFormat>()?,
+            partition: builder.build()?,
+            record_count: v.record_count,
+            file_size_in_bytes: v.file_size_in_bytes,
+            column_sizes: v.column_sizes,
+            value_counts: v.value_counts,
+            null_value_counts: v.null_value_counts,
+            nan_value_counts: v.nan_value_counts,
+            distinct_counts: v.distinct_counts,
+            lower_bounds: v.lower_bounds.map(from_hex_map).transpose()?,
+            upper_bounds: v.upper_bounds.map(from_hex_map).transpose()?,
+            key_metadata: v.key_metadata.as_deref().map(from_hex).transpose()?,
+            split_offsets: v.split_offsets,
+            equality_ids: v.equality_ids,
+            sort_order_id: v.sort_order_id,
+        })
+    }
+}
+
+impl TryFrom<types::DataFile> for DataFile {
+    type Error = Error;
+
+    fn try_from(v: types::DataFile) -> Result<Self> {
+        let partition = v
+            .partition
+            .iter()
+            .map(|(_, value, name)| match value {
+                None => Ok(None),
+                Some(AnyValue::Primitive(value)) => {
+                    Ok(Some(faster_hex::hex_string(&encode_bound(value))))
+                }
+                Some(_) => Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Partition value of {name} is not primitive"),
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let partition_type = Arc::new(v.partition.type_info().clone());
+
+        Ok(DataFile {
+            content: v.content as u8,
+            file_path: v.file_path,
+            file_format: v.file_format.to_string(),
+            partition_type: Types::try_from(Any::Struct(partition_type))?,
+            partition,
+            record_count: v.record_count,
+            file_size_in_bytes: v.file_size_in_bytes,
+            column_sizes: v.column_sizes,
+            value_counts: v.value_counts,
+            null_value_counts: v.null_value_counts,
+            nan_value_counts: v.nan_value_counts,
+            distinct_counts: v.distinct_counts,
+            lower_bounds: v.lower_bounds.map(to_hex_map),
+            upper_bounds: v.upper_bounds.map(to_hex_map),
+            key_metadata: v.key_metadata.map(|v| faster_hex::hex_string(&v)),
+            split_offsets: v.split_offsets,
+            equality_ids: v.equality_ids,
+            sort_order_id: v.sort_order_id,
+        })
+    }
+}
+
+impl Serialize for types::DataFile {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        DataFile::try_from(self.clone())
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for types::DataFile {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        DataFile::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn from_hex(v: &str) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; v.len() / 2];
+    faster_hex::hex_decode(v.as_bytes(), &mut bytes).map_err(|e| {
+        Error::new(ErrorKind::IcebergDataInvalid, format!("Invalid hex {v}")).set_source(e)
+    })?;
+    Ok(bytes)
+}
+
+fn from_hex_map(v: HashMap<i32, String>) -> Result<HashMap<i32, Vec<u8>>> {
+    v.into_iter().map(|(k, v)| Ok((k, from_hex(&v)?))).collect()
+}
+
+fn to_hex_map(v: HashMap<i32, Vec<u8>>) -> HashMap<i32, String> {
+    v.into_iter()
+        .map(|(k, v)| (k, faster_hex::hex_string(&v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Field, Primitive, PrimitiveValue, Struct};
+
+    #[test]
+    fn test_data_file_serde() -> Result<()> {
+        let partition_type = Arc::new(Struct::new(vec![
+            Field::optional(1000, "day", Any::Primitive(Primitive::Date)),
+            Field::optional(1001, "bucket", Any::Primitive(Primitive::Int)),
+        ]));
+        let mut builder = StructValueBuilder::new(partition_type);
+        builder.add_field(
+            1000,
+            Some(AnyValue::Primitive(PrimitiveValue::Date(
+                chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            ))),
+        )?;
+        builder.add_field(1001, None)?;
+        let data_file = types::DataFile {
+            content: DataContentType::EqualityDeletes,
+            file_path: "s3://bucket/table/data/day=2023-01-01/1.parquet".to_string(),
+            file_format: DataFileFormat::Parquet,
+            partition: builder.build()?,
+            record_count: 3,
+            file_size_in_bytes: 1024,
+            column_sizes: Some(HashMap::from([(1, 100)])),
+            value_counts: Some(HashMap::from([(1, 3)])),
+            null_value_counts: None,
+            nan_value_counts: None,
+            distinct_counts: None,
+            lower_bounds: Some(HashMap::from([(1, 1i64.to_le_bytes().to_vec())])),
+            upper_bounds: Some(HashMap::from([(1, 3i64.to_le_bytes().to_vec())])),
+            key_metadata: Some(vec![0xab]),
+            split_offsets: vec![4],
+            equality_ids: vec![1],
+            sort_order_id: None,
+        };
+
+        let json = serde_json::to_value(&data_file)?;
+        assert_eq!(json["partition"], serde_json::json!(["9e4b0000", null]));
+        assert_eq!(json["lower-bounds"]["1"], "0100000000000000");
+        let decoded: types::DataFile = serde_json::from_value(json)?;
+        assert_eq!(decoded, data_file);
+
+        let invalid = r#"{"content":0,"file-path":"a","file-format":"parquet","partition-type":{"type":"struct","fields":[]},"partition":[null],"record-count":1,"file-size-in-bytes":1}"#;
+        assert!(serde_json::from_str::<types::DataFile>(invalid).is_err());
+        Ok(())
+    }
+}
@@ -1,19 +1,29 @@
-use std::cmp::min;
+use std::cmp::{min, Ordering};
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
 
+use apache_avro::types::Value as AvroValue;
 use apache_avro::Reader;
-use apache_avro::{from_value, Schema as AvroSchema};
+use apache_avro::{from_value, to_value, Schema as AvroSchema};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
 use opendal::Operator;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use serde_with::Bytes;
+use uuid::Uuid;
 
 use super::parse_schema;
-use crate::types::on_disk::partition_spec::serialize_partition_spec_fields;
+use crate::types::expression::{
+    compare_values, decimal_bytes, timestamp_from_nanos, timestamp_nanos,
+};
+use crate::types::on_disk::partition_spec::{
+    parse_partition_spec_fields, serialize_partition_spec_fields,
+};
 use crate::types::on_disk::schema::serialize_schema;
-use crate::types::to_avro::to_avro_schema;
-use crate::types::{self, StructValue};
+use crate::types::to_avro::{partition_from_avro, partition_to_avro, to_avro_schema};
+use crate::types::{self, Primitive, PrimitiveValue, StructLike, StructValue};
 use crate::types::{DataContentType, ManifestContentType, ManifestListEntry, UNASSIGNED_SEQ_NUM};
 use crate::types::{ManifestStatus, TableFormatVersion};
 use crate::Error;
@@ -90,16 +100,97 @@ pub fn parse_manifest_file(bs: &[u8]) -> Result<types::ManifestFile> {
         },
     };
 
+    // Partition values are parsed by the partition type of the spec, which
+    // is missing in manifests written without partition specs.
+    let partition_type = match meta.get("partition-spec") {
+        Some(v) => types::PartitionSpec {
+            spec_id: metadata.partition_spec_id,
+            fields: parse_partition_spec_fields(v)?,
+        }
+        .partition_type(&metadata.schema)?,
+        None => types::Struct::default(),
+    };
+    let partition_type = Arc::new(partition_type);
+
+    // Manifests without format version are written by v1 writers.
+    let is_v1 = metadata.format_version.unwrap_or(TableFormatVersion::V1) == TableFormatVersion::V1;
+
     // Parse manifest entries
     let mut entries = Vec::<types::ManifestEntry>::new();
     for value in reader {
-        let v = value?;
-        entries.push(from_value::<ManifestEntry>(&v)?.try_into()?);
+        let mut v = value?;
+        // Partition values are removed before deserializing, since avro
+        // values of logical types can't be ignored by serde.
+        let partition = take_data_file_field(&mut v, "partition");
+        remove_null_data_file_fields(&mut v);
+        let mut entry: types::ManifestEntry = from_value::<ManifestEntry>(&v)?.try_into()?;
+        if let Some(partition) = partition {
+            entry.data_file.partition = partition_from_avro(&partition, partition_type.clone())?;
+        }
+        // Sequence numbers are not tracked by v1, files of v1 manifests
+        // have sequence number 0, even if tables are upgraded to v2.
+        if is_v1 {
+            entry.sequence_number = entry.sequence_number.or(Some(0));
+            entry.file_sequence_number = entry.file_sequence_number.or(Some(0));
+        }
+        entries.push(entry);
     }
 
     Ok(types::ManifestFile { metadata, entries })
 }
 
+/// Returns fields of data file of a manifest entry.
+fn data_file_fields(entry: &mut AvroValue) -> Option<&mut Vec<(String, AvroValue)>> {
+    let AvroValue::Record(fields) = entry else {
+        return None;
+    };
+    let (_, AvroValue::Record(fields)) = fields.iter_mut().find(|(k, _)| k == "data_file")? else {
+        return None;
+    };
+    Some(fields)
+}
+
+/// Removes the field of data file of a manifest entry.
+fn take_data_file_field(entry: &mut AvroValue, name: &str) -> Option<AvroValue> {
+    let fields = data_file_fields(entry)?;
+    let idx = fields.iter().position(|(k, _)| k == name)?;
+    Some(fields.remove(idx).1)
+}
+
+/// Removes null fields of data file of a manifest entry, which are read as
+/// missing fields.
+///
+/// Optional lists like `split_offsets` are null in manifests of writers like
+/// spark, and read as empty lists.
+fn remove_null_data_file_fields(entry: &mut AvroValue) {
+    if let Some(fields) = data_file_fields(entry) {
+        fields.retain(|(_, v)| match v {
+            AvroValue::Union(_, v) => **v != AvroValue::Null,
+            v => *v != AvroValue::Null,
+        });
+    }
+}
+
+/// Convert a manifest entry to an avro value, with partition values of
+/// `partition_type`.
+fn to_avro_entry(entry: types::ManifestEntry, partition_type: &types::Struct) -> Result<AvroValue> {
+    let partition = partition_to_avro(&entry.data_file.partition, partition_type)?;
+    let mut value = to_value(ManifestEntry::try_from(entry)?)?;
+    if let AvroValue::Record(fields) = &mut value {
+        if let Some((_, AvroValue::Record(fields))) =
+            fields.iter_mut().find(|(k, _)| k == "data_file")
+        {
+            // Fields are encoded in order, partition follows file format.
+            let idx = fields
+                .iter()
+                .position(|(k, _)| k == "file_format")
+                .map_or(fields.len(), |v| v + 1);
+            fields.insert(idx, ("partition".to_string(), partition));
+        }
+    }
+    Ok(value)
+}
+
 #[derive(Serialize, Deserialize)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
 struct ManifestEntry {
@@ -146,7 +237,8 @@ struct DataFile {
     content: i32,
     file_path: String,
     file_format: String,
-    #[serde(skip_deserializing)]
+    /// Converted by the partition type, see [`to_avro_entry`].
+    #[serde(skip)]
     partition: StructValue,
     record_count: i64,
     file_size_in_bytes: i64,
@@ -160,6 +252,9 @@ struct DataFile {
     upper_bounds: Option<Vec<BytesEntry>>,
     #[serde_as(as = "Option<Bytes>")]
     key_metadata: Option<Vec<u8>>,
+    /// Optional and nullable, older writers like spark of v1 tables may
+    /// write nulls or nothing.
+    #[serde(default)]
     split_offsets: Vec<i64>,
     #[serde(default)]
     equality_ids: Vec<i32>,
@@ -269,17 +364,173 @@ fn to_i64_entry(entries: HashMap<i32, i64>) -> Vec<I64Entry> {
         .collect()
 }
 
+/// Decode a lower or upper bound stored in manifests by the binary single
+/// value serialization of iceberg.
+///
+/// Returns `None` if the bytes are invalid for the type.
+pub(crate) fn decode_bound(ty: &Primitive, bytes: &[u8]) -> Option<PrimitiveValue> {
+    fn int(bytes: &[u8]) -> Option<i32> {
+        Some(i32::from_le_bytes(bytes.try_into().ok()?))
+    }
+    fn long(bytes: &[u8]) -> Option<i64> {
+        // Bounds of int columns promoted to long are stored in 4 bytes.
+        match bytes.len() {
+            4 => int(bytes).map(|v| v as i64),
+            _ => Some(i64::from_le_bytes(bytes.try_into().ok()?)),
+        }
+    }
+
+    let value = match ty {
+        Primitive::Boolean => PrimitiveValue::Boolean(*bytes.first()? != 0),
+        Primitive::Int => PrimitiveValue::Int(int(bytes)?),
+        Primitive::Long => PrimitiveValue::Long(long(bytes)?),
+        Primitive::Float => {
+            PrimitiveValue::Float(f32::from_le_bytes(bytes.try_into().ok()?).into())
+        }
+        Primitive::Double => match bytes.len() {
+            4 => PrimitiveValue::Double((f32::from_le_bytes(bytes.try_into().ok()?) as f64).into()),
+            _ => PrimitiveValue::Double(f64::from_le_bytes(bytes.try_into().ok()?).into()),
+        },
+        Primitive::Decimal { scale, .. } => {
+            if bytes.is_empty() || bytes.len() > 16 {
+                return None;
+            }
+            // Big-endian two's complement, sign extended to 16 bytes.
+            let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+            let mut buf = [fill; 16];
+            buf[16 - bytes.len()..].copy_from_slice(bytes);
+            let unscaled = i128::from_be_bytes(buf);
+            PrimitiveValue::Decimal(
+                Decimal::try_from_i128_with_scale(unscaled, *scale as u32).ok()?,
+            )
+        }
+        Primitive::Date => {
+            // Days from unix epoch, which is day 719163 from CE.
+            PrimitiveValue::Date(NaiveDate::from_num_days_from_ce_opt(int(bytes)? + 719163)?)
+        }
+        Primitive::Time => {
+            let micros = long(bytes)?;
+            PrimitiveValue::Time(NaiveTime::from_num_seconds_from_midnight_opt(
+                (micros / 1_000_000) as u32,
+                (micros % 1_000_000 * 1000) as u32,
+            )?)
+        }
+        Primitive::Timestamp => {
+            PrimitiveValue::Timestamp(NaiveDateTime::from_timestamp_micros(long(bytes)?)?)
+        }
+        Primitive::Timestampz => PrimitiveValue::Timestampz(DateTime::from_naive_utc_and_offset(
+            NaiveDateTime::from_timestamp_micros(long(bytes)?)?,
+            Utc,
+        )),
+        Primitive::TimestampNs => PrimitiveValue::TimestampNs(timestamp_from_nanos(long(bytes)?)?),
+        Primitive::TimestampzNs => PrimitiveValue::TimestampzNs(
+            DateTime::from_naive_utc_and_offset(timestamp_from_nanos(long(bytes)?)?, Utc),
+        ),
+        Primitive::String => PrimitiveValue::String(String::from_utf8(bytes.to_vec()).ok()?),
+        Primitive::Uuid => PrimitiveValue::Uuid(Uuid::from_slice(bytes).ok()?),
+        Primitive::Fixed(_) => PrimitiveValue::Fixed(bytes.to_vec()),
+        Primitive::Binary => PrimitiveValue::Binary(bytes.to_vec()),
+    };
+    Some(value)
+}
+
+/// Encode a value by the binary single value serialization of iceberg,
+/// which is how bounds are stored in manifests.
+pub(crate) fn encode_bound(value: &PrimitiveValue) -> Vec<u8> {
+    match value {
+        PrimitiveValue::Boolean(v) => vec![*v as u8],
+        PrimitiveValue::Int(v) => v.to_le_bytes().to_vec(),
+        PrimitiveValue::Long(v) => v.to_le_bytes().to_vec(),
+        PrimitiveValue::Float(v) => v.0.to_le_bytes().to_vec(),
+        PrimitiveValue::Double(v) => v.0.to_le_bytes().to_vec(),
+        PrimitiveValue::Decimal(v) => decimal_bytes(v.mantissa()),
+        PrimitiveValue::Date(v) => (v.num_days_from_ce() - 719163).to_le_bytes().to_vec(),
+        PrimitiveValue::Time(v) => {
+            let micros =
+                v.num_seconds_from_midnight() as i64 * 1_000_000 + v.nanosecond() as i64 / 1000;
+            micros.to_le_bytes().to_vec()
+        }
+        PrimitiveValue::Timestamp(v) => v.timestamp_micros().to_le_bytes().to_vec(),
+        PrimitiveValue::Timestampz(v) => v.timestamp_micros().to_le_bytes().to_vec(),
+        PrimitiveValue::TimestampNs(v) => timestamp_nanos(v).to_le_bytes().to_vec(),
+        PrimitiveValue::TimestampzNs(v) => timestamp_nanos(&v.naive_utc()).to_le_bytes().to_vec(),
+        PrimitiveValue::String(v) => v.as_bytes().to_vec(),
+        PrimitiveValue::Uuid(v) => v.as_bytes().to_vec(),
+        PrimitiveValue::Fixed(v) | PrimitiveValue::Binary(v) => v.clone(),
+    }
+}
+
 fn parse_data_file_format(s: &str) -> Result<types::DataFileFormat> {
     types::DataFileFormat::from_str(s)
 }
 
 /// Manifest writer to write manifest to file.
+/// Collects summaries of partition fields of entries in a manifest.
+struct PartitionSummary {
+    fields: Vec<PartitionFieldSummary>,
+}
+
+#[derive(Default)]
+struct PartitionFieldSummary {
+    contains_null: bool,
+    contains_nan: bool,
+    lower: Option<PrimitiveValue>,
+    upper: Option<PrimitiveValue>,
+}
+
+impl PartitionSummary {
+    fn new(num_fields: usize) -> Self {
+        Self {
+            fields: (0..num_fields)
+                .map(|_| PartitionFieldSummary::default())
+                .collect(),
+        }
+    }
+
+    fn update(&mut self, partition: &impl StructLike) {
+        for (idx, summary) in self.fields.iter_mut().enumerate() {
+            let Some(datum) = partition.get(idx) else {
+                summary.contains_null = true;
+                continue;
+            };
+            match datum.value() {
+                PrimitiveValue::Float(v) if v.is_nan() => summary.contains_nan = true,
+                PrimitiveValue::Double(v) if v.is_nan() => summary.contains_nan = true,
+                value => {
+                    let cmp = |bound: &Option<PrimitiveValue>| {
+                        bound.as_ref().map(|v| compare_values(value, v))
+                    };
+                    if matches!(cmp(&summary.lower), None | Some(Some(Ordering::Less))) {
+                        summary.lower = Some(value.clone());
+                    }
+                    if matches!(cmp(&summary.upper), None | Some(Some(Ordering::Greater))) {
+                        summary.upper = Some(value.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> Vec<types::FieldSummary> {
+        self.fields
+            .into_iter()
+            .map(|v| types::FieldSummary {
+                contains_null: v.contains_null,
+                contains_nan: Some(v.contains_nan),
+                lower_bound: v.lower.as_ref().map(encode_bound),
+                upper_bound: v.upper.as_ref().map(encode_bound),
+            })
+            .collect()
+    }
+}
+
 pub(crate) struct ManifestWriter {
     partition_spec: types::PartitionSpec,
     op: Operator,
-    table_location: String,
     // Output path relative to operator root.
     output_path: String,
+    // Full location of output file, stored in manifest list.
+    output_location: String,
     snapshot_id: i64,
 
     added_files: i64,
@@ -296,16 +547,16 @@ impl ManifestWriter {
     pub(crate) fn new(
         partition_spec: types::PartitionSpec,
         op: Operator,
-        table_location: impl Into<String>,
         output_path: impl Into<String>,
+        output_location: impl Into<String>,
         snapshot_id: i64,
         seq_num: i64,
     ) -> Self {
         Self {
             partition_spec,
             op,
-            table_location: table_location.into(),
             output_path: output_path.into(),
+            output_location: output_location.into(),
             snapshot_id,
 
             added_files: 0,
@@ -326,6 +577,9 @@ impl ManifestWriter {
         );
         // A place holder for avro schema since avro writer needs its reference.
         let avro_schema;
+        let partition_type = self
+            .partition_spec
+            .partition_type(&manifest.metadata.schema)?;
         let mut avro_writer = match manifest
             .metadata
             .format_version
@@ -338,18 +592,21 @@ impl ManifestWriter {
                 ));
             }
             TableFormatVersion::V2 => {
-                let partition_type = self
-                    .partition_spec
-                    .partition_type(&manifest.metadata.schema)?;
                 avro_schema = to_avro_schema(
-                    &types::ManifestFile::v2_schema(partition_type),
+                    &types::ManifestFile::v2_schema(partition_type.clone()),
                     Some("manifest_entry"),
                 )?;
-                self.v2_writer(&avro_schema, &manifest.metadata.schema)?
+                self.v2_writer(
+                    &avro_schema,
+                    &manifest.metadata.schema,
+                    manifest.metadata.content,
+                )?
             }
         };
 
+        let mut partition_summary = PartitionSummary::new(self.partition_spec.fields.len());
         for entry in manifest.entries {
+            partition_summary.update(&entry.data_file.partition);
             match entry.status {
                 ManifestStatus::Added => {
                     self.added_files += 1;
@@ -378,8 +635,7 @@ impl ManifestWriter {
                 }
             }
 
-            // TODO: Add partition summary
-            avro_writer.append_ser(ManifestEntry::try_from(entry)?)?;
+            avro_writer.append(to_avro_entry(entry, &partition_type)?)?;
         }
 
         let length = avro_writer.flush()?;
@@ -387,7 +643,7 @@ impl ManifestWriter {
         self.op.write(self.output_path.as_str(), connect).await?;
 
         Ok(ManifestListEntry {
-            manifest_path: format!("{}/{}", self.table_location, &self.output_path),
+            manifest_path: self.output_location,
             manifest_length: length as i64,
             partition_spec_id: manifest.metadata.partition_spec_id,
             content: manifest.metadata.content,
@@ -400,7 +656,7 @@ impl ManifestWriter {
             added_rows_count: self.added_rows,
             existing_rows_count: self.existing_rows,
             deleted_rows_count: self.deleted_rows,
-            partitions: Vec::default(),
+            partitions: partition_summary.finish(),
             key_metadata: None,
         })
     }
@@ -409,6 +665,7 @@ impl ManifestWriter {
         &self,
         avro_schema: &'a AvroSchema,
         table_schema: &types::Schema,
+        content: ManifestContentType,
     ) -> Result<AvroWriter<'a, Vec<u8>>> {
         let mut writer = AvroWriter::new(avro_schema, Vec::new());
         writer.add_user_metadata("schema".to_string(), serialize_schema(table_schema)?)?;
@@ -424,7 +681,7 @@ impl ManifestWriter {
             "format-version".to_string(),
             TableFormatVersion::V2.to_string(),
         )?;
-        writer.add_user_metadata("content".to_string(), "data")?;
+        writer.add_user_metadata("content".to_string(), content.to_string())?;
 
         Ok(writer)
     }
@@ -443,6 +700,7 @@ mod tests {
     use tempfile::TempDir;
 
     use super::*;
+    use crate::types::AnyValue;
 
     #[test]
     fn test_load_manifest_entry() -> Result<()> {
@@ -525,10 +783,56 @@ mod tests {
         assert_eq!(entries[0].data_file.file_path, "/opt/bitnami/spark/warehouse/db/table/data/00000-0-b8982382-f016-467a-84e4-5e6bbe0ff19a-00001.parquet");
         assert_eq!(entries[1].data_file.file_path, "/opt/bitnami/spark/warehouse/db/table/data/00001-1-b8982382-f016-467a-84e4-5e6bbe0ff19a-00001.parquet");
         assert_eq!(entries[2].data_file.file_path, "/opt/bitnami/spark/warehouse/db/table/data/00002-2-b8982382-f016-467a-84e4-5e6bbe0ff19a-00001.parquet");
+        assert!(entries
+            .iter()
+            .all(|v| v.sequence_number == Some(0) && v.file_sequence_number == Some(0)));
 
         Ok(())
     }
 
+    #[test]
+    fn test_parse_manifest_entry_tolerance() -> Result<()> {
+        // Entry of a v1 manifest, with fields removed by v2, nulls of
+        // optional fields and without fields added by v2.
+        let mut value = AvroValue::Record(vec![
+            ("status".to_string(), AvroValue::Int(1)),
+            (
+                "snapshot_id".to_string(),
+                AvroValue::Union(1, Box::new(AvroValue::Long(1))),
+            ),
+            (
+                "data_file".to_string(),
+                AvroValue::Record(vec![
+                    (
+                        "file_path".to_string(),
+                        AvroValue::String("/tmp/1.parquet".to_string()),
+                    ),
+                    (
+                        "file_format".to_string(),
+                        AvroValue::String("PARQUET".to_string()),
+                    ),
+                    ("record_count".to_string(), AvroValue::Long(10)),
+                    ("file_size_in_bytes".to_string(), AvroValue::Long(100)),
+                    ("block_size_in_bytes".to_string(), AvroValue::Long(64)),
+                    (
+                        "split_offsets".to_string(),
+                        AvroValue::Union(0, Box::new(AvroValue::Null)),
+                    ),
+                ]),
+            ),
+        ]);
+        remove_null_data_file_fields(&mut value);
+        let entry: types::ManifestEntry = from_value::<ManifestEntry>(&value)?.try_into()?;
+        assert_eq!(entry.status, types::ManifestStatus::Added);
+        assert_eq!(entry.snapshot_id, Some(1));
+        assert_eq!(entry.sequence_number, None);
+        assert_eq!(entry.data_file.content, types::DataContentType::Data);
+        assert_eq!(entry.data_file.record_count, 10);
+        assert!(entry.data_file.split_offsets.is_empty());
+        assert!(entry.data_file.equality_ids.is_empty());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_read_write_manifest_file_v2() {
         let manifest_file = types::ManifestFile {
@@ -592,10 +896,134 @@ mod tests {
             ],
         };
 
-        check_manifest_file_serde(manifest_file).await
+        check_manifest_file_serde(manifest_file, vec![]).await
     }
 
-    async fn check_manifest_file_serde(manifest_file: types::ManifestFile) {
+    #[tokio::test]
+    async fn test_read_write_partitioned_manifest_file() {
+        let schema = types::Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                types::Field::optional(
+                    1,
+                    "price",
+                    types::Any::Primitive(types::Primitive::Decimal {
+                        precision: 9,
+                        scale: 2,
+                    }),
+                ),
+                types::Field::optional(2, "uid", types::Any::Primitive(types::Primitive::Uuid)),
+                types::Field::optional(
+                    3,
+                    "digest",
+                    types::Any::Primitive(types::Primitive::Fixed(4)),
+                ),
+                types::Field::optional(
+                    4,
+                    "payload",
+                    types::Any::Primitive(types::Primitive::Binary),
+                ),
+                types::Field::optional(5, "ts", types::Any::Primitive(types::Primitive::Timestamp)),
+            ],
+        };
+        let fields = [
+            (1, "price", types::Transform::Identity),
+            (1, "price_trunc", types::Transform::Truncate(100)),
+            (2, "uid", types::Transform::Identity),
+            (2, "uid_bucket", types::Transform::Bucket(16)),
+            (3, "digest", types::Transform::Identity),
+            (4, "payload_trunc", types::Transform::Truncate(2)),
+            (5, "ts_day", types::Transform::Day),
+            (5, "ts_hour", types::Transform::Hour),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(
+            |(i, (source_column_id, name, transform))| types::PartitionField {
+                source_column_id,
+                partition_field_id: 1000 + i as i32,
+                transform,
+                name: name.to_string(),
+            },
+        )
+        .collect::<Vec<_>>();
+        let partition_type = Arc::new(
+            types::PartitionSpec {
+                spec_id: 1,
+                fields: fields.clone(),
+            }
+            .partition_type(&schema)
+            .unwrap(),
+        );
+        let partition = |values: Vec<Option<PrimitiveValue>>| {
+            let mut builder = types::StructValueBuilder::new(partition_type.clone());
+            for (field, value) in partition_type.fields().iter().zip(values) {
+                builder
+                    .add_field(field.id, value.map(AnyValue::Primitive))
+                    .unwrap();
+            }
+            builder.build().unwrap()
+        };
+        let data_file = |path: &str, partition: StructValue| {
+            let mut data_file = types::DataFile::new(
+                types::DataContentType::Data,
+                path,
+                types::DataFileFormat::Parquet,
+                100,
+                200,
+            );
+            data_file.partition = partition;
+            data_file
+        };
+
+        let manifest_file = types::ManifestFile {
+            metadata: types::ManifestMetadata {
+                schema,
+                schema_id: 0,
+                partition_spec_id: 1,
+                format_version: Some(TableFormatVersion::V2),
+                content: types::ManifestContentType::Data,
+            },
+            entries: vec![
+                types::ManifestEntry {
+                    status: types::ManifestStatus::Added,
+                    snapshot_id: None,
+                    sequence_number: Some(2),
+                    file_sequence_number: Some(2),
+                    data_file: data_file(
+                        "/tmp/1.parquet",
+                        partition(vec![
+                            Some(PrimitiveValue::Decimal("-123.45".parse().unwrap())),
+                            Some(PrimitiveValue::Decimal("-124.00".parse().unwrap())),
+                            Some(PrimitiveValue::Uuid(
+                                "f79c3e09-677c-4bbd-a479-3f349cb785e7".parse().unwrap(),
+                            )),
+                            Some(PrimitiveValue::Int(5)),
+                            Some(PrimitiveValue::Fixed(vec![0, 1, 0xfe, 0xff])),
+                            Some(PrimitiveValue::Binary(vec![0xca, 0xfe])),
+                            Some(PrimitiveValue::Int(17486)),
+                            Some(PrimitiveValue::Int(420042)),
+                        ]),
+                    ),
+                },
+                types::ManifestEntry {
+                    status: types::ManifestStatus::Added,
+                    snapshot_id: None,
+                    sequence_number: Some(2),
+                    file_sequence_number: Some(2),
+                    data_file: data_file("/tmp/2.parquet", partition(vec![None; 8])),
+                },
+            ],
+        };
+
+        check_manifest_file_serde(manifest_file, fields).await
+    }
+
+    async fn check_manifest_file_serde(
+        manifest_file: types::ManifestFile,
+        fields: Vec<types::PartitionField>,
+    ) {
         let tmp_dir = TempDir::new().unwrap();
         let dir_path = {
             let canonicalize = canonicalize(tmp_dir.path().to_str().unwrap()).unwrap();
@@ -611,11 +1039,17 @@ mod tests {
 
         let partition_spec = types::PartitionSpec {
             spec_id: manifest_file.metadata.partition_spec_id,
-            fields: vec![],
+            fields,
         };
 
-        let writer =
-            ManifestWriter::new(partition_spec, operator, dir_path.as_str(), filename, 3, 1);
+        let writer = ManifestWriter::new(
+            partition_spec,
+            operator,
+            filename,
+            format!("{dir_path}/{filename}"),
+            3,
+            1,
+        );
         let manifest_list_entry = writer.write(manifest_file.clone()).await.unwrap();
 
         assert_eq!(
@@ -629,4 +1063,77 @@ mod tests {
 
         assert_eq!(manifest_file, restored_manifest_file);
     }
+
+    #[test]
+    fn test_data_file_bounds() -> Result<()> {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/10d28031-9739-484c-92db-cdf2975cead4-m0.avro",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let manifest = parse_manifest_file(&fs::read(path)?)?;
+        let mut ids = vec![];
+        for entry in &manifest.entries {
+            let data_file = &entry.data_file;
+            let lower = data_file.lower_bound(1, Primitive::Long)?.unwrap();
+            let upper = data_file.upper_bound(1, Primitive::Long)?.unwrap();
+            assert!(lower <= upper);
+            ids.push(lower.as_long().unwrap());
+            let data = data_file.lower_bound(2, Primitive::String)?.unwrap();
+            assert!(data.as_str().is_some());
+            assert_eq!(data.as_long(), None);
+            assert_eq!(data_file.lower_bound(100, Primitive::Long)?, None);
+            // Bounds of longs are 8 bytes, which are invalid as ints.
+            assert!(data_file.lower_bound(1, Primitive::Int).is_err());
+        }
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_bound() {
+        let values = [
+            PrimitiveValue::Long(-3),
+            PrimitiveValue::Date(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()),
+            PrimitiveValue::String("abc".to_string()),
+            PrimitiveValue::Decimal(Decimal::new(-200, 2)),
+        ];
+        let types = [
+            Primitive::Long,
+            Primitive::Date,
+            Primitive::String,
+            Primitive::Decimal {
+                precision: 9,
+                scale: 2,
+            },
+        ];
+        for (value, ty) in values.into_iter().zip(types) {
+            assert_eq!(decode_bound(&ty, &encode_bound(&value)), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_decode_bound() {
+        assert_eq!(
+            decode_bound(&Primitive::Long, &5_i32.to_le_bytes()),
+            Some(PrimitiveValue::Long(5))
+        );
+        assert_eq!(
+            decode_bound(&Primitive::Date, &19358_i32.to_le_bytes()),
+            Some(PrimitiveValue::Date(
+                NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()
+            ))
+        );
+        assert_eq!(
+            decode_bound(
+                &Primitive::Decimal {
+                    precision: 9,
+                    scale: 2
+                },
+                &[0xff, 0x38]
+            ),
+            Some(PrimitiveValue::Decimal(Decimal::new(-200, 2)))
+        );
+        assert_eq!(decode_bound(&Primitive::Int, &[1, 2]), None);
+    }
 }
@@ -5,6 +5,8 @@ use apache_avro::Writer as AvroWriter;
 use opendal::Operator;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_with::serde_as;
+use serde_with::Bytes;
 
 use crate::types;
 use crate::types::to_avro::to_avro_schema;
@@ -127,6 +129,7 @@ impl From<types::ManifestListEntry> for ManifestListEntry {
     }
 }
 
+#[serde_as]
 #[derive(Serialize, Deserialize)]
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 struct FieldSummary {
@@ -139,6 +142,14 @@ struct FieldSummary {
     /// Whether the manifest contains at least one partition with a NaN
     /// value for the field
     contains_nan: Option<bool>,
+    /// field: 510
+    #[serde(default)]
+    #[serde_as(as = "Option<Bytes>")]
+    lower_bound: Option<Vec<u8>>,
+    /// field: 511
+    #[serde(default)]
+    #[serde_as(as = "Option<Bytes>")]
+    upper_bound: Option<Vec<u8>>,
 }
 
 impl TryFrom<FieldSummary> for types::FieldSummary {
@@ -148,6 +159,8 @@ impl TryFrom<FieldSummary> for types::FieldSummary {
         Ok(types::FieldSummary {
             contains_null: v.contains_null,
             contains_nan: v.contains_nan,
+            lower_bound: v.lower_bound,
+            upper_bound: v.upper_bound,
         })
     }
 }
@@ -157,6 +170,8 @@ impl From<types::FieldSummary> for FieldSummary {
         Self {
             contains_null: v.contains_null,
             contains_nan: v.contains_nan,
+            lower_bound: v.lower_bound,
+            upper_bound: v.upper_bound,
         }
     }
 }
@@ -205,14 +220,14 @@ impl ManifestListWriter {
 
     fn v2_writer<'a>(&self, avro_schema: &'a AvroSchema) -> Result<AvroWriter<'a, Vec<u8>>> {
         let mut writer = AvroWriter::new(avro_schema, Vec::new());
-        writer.add_user_metadata("snapshot-id".to_string(), &self.snapshot_id.to_string())?;
+        writer.add_user_metadata("snapshot-id".to_string(), self.snapshot_id.to_string())?;
         writer.add_user_metadata(
             "parent-snapshot-id".to_string(),
-            &self.parent_snapshot_id.to_string(),
+            self.parent_snapshot_id.to_string(),
         )?;
         writer.add_user_metadata(
             "sequence-number".to_string(),
-            &self.sequence_number.to_string(),
+            self.sequence_number.to_string(),
         )?;
         writer.add_user_metadata("format-version".to_string(), "2")?;
         Ok(writer)
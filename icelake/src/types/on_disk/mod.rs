@@ -1,30 +1,48 @@
 //! on_disk module provides the definition of iceberg on-disk data
 //! formats and the convert functions to in-memory.
 
+mod data_file;
+
 mod manifest_file;
 pub use manifest_file::parse_manifest_file;
 pub(crate) use manifest_file::ManifestWriter;
+pub(crate) use manifest_file::{decode_bound, encode_bound};
 
 mod manifest_list;
 pub use manifest_list::parse_manifest_list;
 pub(crate) use manifest_list::ManifestListWriter;
 
+mod name_mapping;
+pub use name_mapping::parse_name_mapping;
+pub use name_mapping::serialize_name_mapping;
+
 mod partition_spec;
 pub use partition_spec::parse_partition_spec;
 
+mod puffin;
+pub use puffin::{
+    PuffinBlob, PuffinBlobMetadata, PuffinCompressionCodec, PuffinFileMetadata, PuffinReader,
+    PuffinWriter, APACHE_DATASKETCHES_THETA_V1,
+};
+
 mod schema;
 pub use schema::parse_schema;
+pub use schema::serialize_schema;
 
 mod sort_order;
 pub use sort_order::parse_sort_order;
+pub use sort_order::serialize_sort_order;
 
 mod transform;
 
 mod snapshot;
 pub use snapshot::parse_snapshot;
+pub use snapshot::serialize_snapshot;
 
 mod table_metadata;
+pub use table_metadata::parse_statistics_file;
 pub use table_metadata::parse_table_metadata;
+pub use table_metadata::serialize_statistics_file;
 pub use table_metadata::serialize_table_meta;
 
 mod types;
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types;
+use crate::Result;
+
+/// Parse name mapping from json bytes.
+pub fn parse_name_mapping(bs: &[u8]) -> Result<types::NameMapping> {
+    let fields: Vec<MappedField> = serde_json::from_slice(bs)?;
+    Ok(types::NameMapping {
+        fields: fields.into_iter().map(Into::into).collect(),
+    })
+}
+
+/// Serialize name mapping to json string.
+pub fn serialize_name_mapping(name_mapping: &types::NameMapping) -> Result<String> {
+    let fields: Vec<MappedField> = name_mapping.fields.iter().map(Into::into).collect();
+    Ok(serde_json::to_string(&fields)?)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MappedField {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    field_id: Option<i32>,
+    names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<MappedField>,
+}
+
+impl From<MappedField> for types::MappedField {
+    fn from(v: MappedField) -> Self {
+        Self {
+            field_id: v.field_id,
+            names: v.names,
+            fields: v.fields.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&types::MappedField> for MappedField {
+    fn from(v: &types::MappedField) -> Self {
+        Self {
+            field_id: v.field_id,
+            names: v.names.clone(),
+            fields: v.fields.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_mapping() {
+        let content = r#"
+[ { "field-id": 1, "names": ["id", "record_id"] },
+  { "field-id": 2, "names": ["data"] },
+  { "field-id": 3, "names": ["location"], "fields": [
+      { "field-id": 4, "names": ["latitude", "lat"] },
+      { "field-id": 5, "names": ["longitude", "long"] }
+  ] },
+  { "names": ["unused"] } ]
+        "#;
+
+        let v = parse_name_mapping(content.as_bytes()).unwrap();
+
+        assert_eq!(v.fields.len(), 4);
+        assert_eq!(v.find("record_id").unwrap().field_id, Some(1));
+        assert_eq!(
+            v.find("location").unwrap().find("lat").unwrap().field_id,
+            Some(4)
+        );
+        assert_eq!(v.find("unused").unwrap().field_id, None);
+        assert!(v.find("unknown").is_none());
+
+        let restored = parse_name_mapping(serialize_name_mapping(&v).unwrap().as_bytes()).unwrap();
+        assert_eq!(v, restored);
+    }
+}
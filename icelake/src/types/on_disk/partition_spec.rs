@@ -16,6 +16,13 @@ pub fn serialize_partition_spec(spec: &types::PartitionSpec) -> Result<String> {
     Ok(serde_json::to_string(&t)?)
 }
 
+/// Parse partition fields from json bytes, like the partition spec in
+/// manifest metadata.
+pub fn parse_partition_spec_fields(bs: &[u8]) -> Result<Vec<types::PartitionField>> {
+    let fields: Vec<PartitionField> = serde_json::from_slice(bs)?;
+    fields.into_iter().map(|v| v.try_into()).collect()
+}
+
 pub fn serialize_partition_spec_fields(spec: &types::PartitionSpec) -> Result<String> {
     let t = PartitionSpec::try_from(spec)?;
     Ok(serde_json::to_string(&t.fields)?)
@@ -24,8 +31,8 @@ pub fn serialize_partition_spec_fields(spec: &types::PartitionSpec) -> Result<St
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct PartitionSpec {
-    spec_id: i32,
-    fields: Vec<PartitionField>,
+    pub(super) spec_id: i32,
+    pub(super) fields: Vec<PartitionField>,
 }
 
 impl TryFrom<PartitionSpec> for types::PartitionSpec {
@@ -60,7 +67,7 @@ impl<'a> TryFrom<&'a types::PartitionSpec> for PartitionSpec {
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
-struct PartitionField {
+pub(super) struct PartitionField {
     source_id: i32,
     field_id: i32,
     name: String,
@@ -90,7 +97,7 @@ impl<'a> TryFrom<&'a types::PartitionField> for PartitionField {
             source_id: v.source_column_id,
             field_id: v.partition_field_id,
             name: v.name.clone(),
-            transform: (&v.transform).to_string(),
+            transform: v.transform.to_string(),
         })
     }
 }
@@ -0,0 +1,530 @@
+//! Puffin files store blobs of statistics and indexes that can't be stored
+//! in manifests, like theta sketches to estimate the number of distinct
+//! values of columns.
+//!
+//! Reference: <https://iceberg.apache.org/puffin-spec/>
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use bytes::Bytes;
+use opendal::Operator;
+use serde::{Deserialize, Serialize};
+
+use crate::types;
+use crate::{Error, ErrorKind, Result};
+
+/// Magic bytes at the start of puffin files and at both ends of footers.
+const MAGIC: [u8; 4] = *b"PFA1";
+/// Length of footer payload size, flags and the trailing magic.
+const FOOTER_TAIL_LENGTH: usize = 12;
+/// Flag of footers compressed by lz4, which is bit 0 of the first flag byte.
+const FOOTER_PAYLOAD_COMPRESSED: u8 = 0x01;
+/// Default zstd compression level.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Blob type of theta sketches of apache datasketches, which estimate the
+/// number of distinct values of a column.
+pub const APACHE_DATASKETCHES_THETA_V1: &str = "apache-datasketches-theta-v1";
+
+/// Compression codec of blobs and footers in puffin files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum PuffinCompressionCodec {
+    /// Not compressed.
+    #[default]
+    None,
+    /// A single lz4 frame.
+    Lz4,
+    /// A single zstd frame.
+    Zstd,
+}
+
+impl PuffinCompressionCodec {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            PuffinCompressionCodec::None => Ok(data.to_vec()),
+            PuffinCompressionCodec::Lz4 => {
+                let mut encoder = lz4::EncoderBuilder::new().build(vec![])?;
+                encoder.write_all(data)?;
+                let (compressed, res) = encoder.finish();
+                res?;
+                Ok(compressed)
+            }
+            PuffinCompressionCodec::Zstd => Ok(zstd::encode_all(data, ZSTD_LEVEL)?),
+        }
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            PuffinCompressionCodec::None => Ok(data.to_vec()),
+            PuffinCompressionCodec::Lz4 => {
+                let mut decompressed = vec![];
+                lz4::Decoder::new(data)?.read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            PuffinCompressionCodec::Zstd => Ok(zstd::decode_all(data)?),
+        }
+    }
+}
+
+impl Display for PuffinCompressionCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuffinCompressionCodec::None => write!(f, "none"),
+            PuffinCompressionCodec::Lz4 => write!(f, "lz4"),
+            PuffinCompressionCodec::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for PuffinCompressionCodec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "lz4" => Ok(PuffinCompressionCodec::Lz4),
+            "zstd" => Ok(PuffinCompressionCodec::Zstd),
+            v => Err(Error::new(
+                ErrorKind::IcebergFeatureUnsupported,
+                format!("puffin compression codec {v:?}"),
+            )),
+        }
+    }
+}
+
+/// Blob to write into a puffin file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PuffinBlob {
+    /// Type of the blob, like [`APACHE_DATASKETCHES_THETA_V1`].
+    pub typ: String,
+    /// Ordered list of field ids the blob was computed from.
+    pub fields: Vec<i32>,
+    /// ID of the snapshot the blob was computed from.
+    pub snapshot_id: i64,
+    /// Sequence number of the snapshot the blob was computed from.
+    pub sequence_number: i64,
+    /// Uncompressed content of the blob.
+    pub data: Bytes,
+    /// Additional properties of the blob, like `ndv`.
+    pub properties: HashMap<String, String>,
+}
+
+/// Metadata of a blob recorded in the footer of a puffin file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PuffinBlobMetadata {
+    /// Type of the blob.
+    pub typ: String,
+    /// Ordered list of field ids the blob was computed from.
+    pub fields: Vec<i32>,
+    /// ID of the snapshot the blob was computed from.
+    pub snapshot_id: i64,
+    /// Sequence number of the snapshot the blob was computed from.
+    pub sequence_number: i64,
+    /// Offset of the stored blob in the file.
+    pub offset: i64,
+    /// Length of the stored blob, which may be compressed.
+    pub length: i64,
+    /// Compression codec of the stored blob.
+    pub compression_codec: PuffinCompressionCodec,
+    /// Additional properties of the blob.
+    pub properties: HashMap<String, String>,
+}
+
+impl From<&PuffinBlobMetadata> for types::BlobMetadata {
+    fn from(v: &PuffinBlobMetadata) -> Self {
+        Self {
+            typ: v.typ.clone(),
+            snapshot_id: v.snapshot_id,
+            sequence_number: v.sequence_number,
+            fields: v.fields.clone(),
+            properties: v.properties.clone(),
+        }
+    }
+}
+
+/// Content of the footer of a puffin file.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PuffinFileMetadata {
+    /// Metadata of blobs in the file.
+    pub blobs: Vec<PuffinBlobMetadata>,
+    /// Properties of the file, like `created-by`.
+    pub properties: HashMap<String, String>,
+}
+
+/// PuffinReader reads the footer and blobs of a puffin file.
+pub struct PuffinReader {
+    op: Operator,
+    path: String,
+    file_size: Option<u64>,
+}
+
+impl PuffinReader {
+    /// Create a reader of the puffin file at `path` relative to `op`.
+    pub fn new(op: Operator, path: impl Into<String>) -> Self {
+        Self {
+            op,
+            path: path.into(),
+            file_size: None,
+        }
+    }
+
+    /// Use the known size of the file, like `file_size_in_bytes` of
+    /// [`types::StatisticsFile`], instead of reading it from storage.
+    pub fn with_file_size(mut self, file_size: u64) -> Self {
+        self.file_size = Some(file_size);
+        self
+    }
+
+    /// Read metadata of blobs and properties in the footer.
+    pub async fn file_metadata(&self) -> Result<PuffinFileMetadata> {
+        let file_size = match self.file_size {
+            Some(v) => v,
+            None => self.op.stat(&self.path).await?.content_length(),
+        };
+        let min_size = (MAGIC.len() * 2 + FOOTER_TAIL_LENGTH) as u64;
+        if file_size < min_size {
+            return Err(self.invalid(format!("file size {file_size} is too small")));
+        }
+
+        let tail = self
+            .op
+            .range_read(&self.path, file_size - FOOTER_TAIL_LENGTH as u64..file_size)
+            .await?;
+        if tail[8..] != MAGIC {
+            return Err(self.invalid("footer magic mismatch"));
+        }
+        let payload_size = i32::from_le_bytes(tail[0..4].try_into().unwrap());
+        let footer_size = payload_size as u64 + (MAGIC.len() + FOOTER_TAIL_LENGTH) as u64;
+        if payload_size < 0 || footer_size + MAGIC.len() as u64 > file_size {
+            return Err(self.invalid(format!("footer payload size {payload_size} is invalid")));
+        }
+
+        let footer = self
+            .op
+            .range_read(&self.path, file_size - footer_size..file_size)
+            .await?;
+        if footer[..MAGIC.len()] != MAGIC {
+            return Err(self.invalid("footer magic mismatch"));
+        }
+        let payload = &footer[MAGIC.len()..MAGIC.len() + payload_size as usize];
+        let payload = if tail[4] & FOOTER_PAYLOAD_COMPRESSED != 0 {
+            PuffinCompressionCodec::Lz4.decompress(payload)?
+        } else {
+            payload.to_vec()
+        };
+        let footer: FileMetadata = serde_json::from_slice(&payload)?;
+        footer.try_into()
+    }
+
+    /// Read the uncompressed content of a blob.
+    pub async fn read_blob(&self, blob: &PuffinBlobMetadata) -> Result<Bytes> {
+        let (offset, length) = (blob.offset as u64, blob.length as u64);
+        let data = self
+            .op
+            .range_read(&self.path, offset..offset + length)
+            .await?;
+        if data.len() as u64 != length {
+            return Err(self.invalid(format!(
+                "blob at offset {offset} is truncated, expected {length} bytes but got {}",
+                data.len()
+            )));
+        }
+        Ok(blob.compression_codec.decompress(&data)?.into())
+    }
+
+    fn invalid(&self, message: impl Into<String>) -> Error {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Invalid puffin file: {}", message.into()),
+        )
+        .with_context("path", &self.path)
+    }
+}
+
+/// PuffinWriter writes blobs into a puffin file.
+///
+/// Blobs are buffered in memory until the writer is closed.
+pub struct PuffinWriter {
+    op: Operator,
+    // Output path relative to operator root.
+    output_path: String,
+    // Full location of output file, stored in table metadata.
+    output_location: String,
+    buf: Vec<u8>,
+    blobs: Vec<PuffinBlobMetadata>,
+    properties: HashMap<String, String>,
+    compress_footer: bool,
+}
+
+impl PuffinWriter {
+    /// Create a writer of a puffin file at `output_path` relative to `op`,
+    /// whose full location is `output_location`.
+    pub fn new(
+        op: Operator,
+        output_path: impl Into<String>,
+        output_location: impl Into<String>,
+    ) -> Self {
+        Self {
+            op,
+            output_path: output_path.into(),
+            output_location: output_location.into(),
+            buf: MAGIC.to_vec(),
+            blobs: vec![],
+            properties: HashMap::from([(
+                "created-by".to_string(),
+                format!("icelake version {}", env!("CARGO_PKG_VERSION")),
+            )]),
+            compress_footer: false,
+        }
+    }
+
+    /// Set a property of the file.
+    pub fn set_property(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.properties.insert(key.into(), value.into());
+    }
+
+    /// Compress the footer by lz4, which is not compressed by default.
+    pub fn with_footer_compressed(mut self) -> Self {
+        self.compress_footer = true;
+        self
+    }
+
+    /// Append a blob compressed by `codec`.
+    pub fn add(&mut self, blob: PuffinBlob, codec: PuffinCompressionCodec) -> Result<()> {
+        let data = codec.compress(&blob.data)?;
+        self.blobs.push(PuffinBlobMetadata {
+            typ: blob.typ,
+            fields: blob.fields,
+            snapshot_id: blob.snapshot_id,
+            sequence_number: blob.sequence_number,
+            offset: self.buf.len() as i64,
+            length: data.len() as i64,
+            compression_codec: codec,
+            properties: blob.properties,
+        });
+        self.buf.extend_from_slice(&data);
+        Ok(())
+    }
+
+    /// Write the file with the footer, returns the statistics file to add
+    /// to table metadata for given snapshot.
+    pub async fn close(mut self, snapshot_id: i64) -> Result<types::StatisticsFile> {
+        let footer = FileMetadata::from(PuffinFileMetadata {
+            blobs: self.blobs.clone(),
+            properties: self.properties,
+        });
+        let payload = serde_json::to_vec(&footer)?;
+        let (payload, flags) = if self.compress_footer {
+            (
+                PuffinCompressionCodec::Lz4.compress(&payload)?,
+                [FOOTER_PAYLOAD_COMPRESSED, 0, 0, 0],
+            )
+        } else {
+            (payload, [0; 4])
+        };
+
+        let footer_start = self.buf.len();
+        self.buf.extend_from_slice(&MAGIC);
+        self.buf.extend_from_slice(&payload);
+        self.buf
+            .extend_from_slice(&(payload.len() as i32).to_le_bytes());
+        self.buf.extend_from_slice(&flags);
+        self.buf.extend_from_slice(&MAGIC);
+
+        let file_size = self.buf.len() as i64;
+        let footer_size = file_size - footer_start as i64;
+        self.op.write(&self.output_path, self.buf).await?;
+
+        Ok(types::StatisticsFile {
+            snapshot_id,
+            statistics_path: self.output_location,
+            file_size_in_bytes: file_size,
+            file_footer_size_in_bytes: footer_size,
+            key_metadata: None,
+            blob_metadata: self.blobs.iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileMetadata {
+    blobs: Vec<BlobMetadata>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    properties: HashMap<String, String>,
+}
+
+impl TryFrom<FileMetadata> for PuffinFileMetadata {
+    type Error = Error;
+
+    fn try_from(v: FileMetadata) -> Result<Self> {
+        Ok(Self {
+            blobs: v
+                .blobs
+                .into_iter()
+                .map(PuffinBlobMetadata::try_from)
+                .collect::<Result<_>>()?,
+            properties: v.properties,
+        })
+    }
+}
+
+impl From<PuffinFileMetadata> for FileMetadata {
+    fn from(value: PuffinFileMetadata) -> Self {
+        Self {
+            blobs: value.blobs.into_iter().map(Into::into).collect(),
+            properties: value.properties,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BlobMetadata {
+    #[serde(rename = "type")]
+    typ: String,
+    fields: Vec<i32>,
+    snapshot_id: i64,
+    sequence_number: i64,
+    offset: i64,
+    length: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression_codec: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    properties: HashMap<String, String>,
+}
+
+impl TryFrom<BlobMetadata> for PuffinBlobMetadata {
+    type Error = Error;
+
+    fn try_from(v: BlobMetadata) -> Result<Self> {
+        Ok(Self {
+            typ: v.typ,
+            fields: v.fields,
+            snapshot_id: v.snapshot_id,
+            sequence_number: v.sequence_number,
+            offset: v.offset,
+            length: v.length,
+            compression_codec: v
+                .compression_codec
+                .map(|v| v.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            properties: v.properties,
+        })
+    }
+}
+
+impl From<PuffinBlobMetadata> for BlobMetadata {
+    fn from(value: PuffinBlobMetadata) -> Self {
+        Self {
+            typ: value.typ,
+            fields: value.fields,
+            snapshot_id: value.snapshot_id,
+            sequence_number: value.sequence_number,
+            offset: value.offset,
+            length: value.length,
+            compression_codec: match value.compression_codec {
+                PuffinCompressionCodec::None => None,
+                codec => Some(codec.to_string()),
+            },
+            properties: value.properties,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opendal::services::Memory;
+
+    use super::*;
+
+    fn blob(data: &'static [u8], field: i32) -> PuffinBlob {
+        PuffinBlob {
+            typ: APACHE_DATASKETCHES_THETA_V1.to_string(),
+            fields: vec![field],
+            snapshot_id: 1,
+            sequence_number: 2,
+            data: Bytes::from_static(data),
+            properties: HashMap::from([("ndv".to_string(), "3".to_string())]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_puffin_roundtrip() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        for compress_footer in [false, true] {
+            let mut writer =
+                PuffinWriter::new(op.clone(), "stats.puffin", "memory:///stats.puffin");
+            if compress_footer {
+                writer = writer.with_footer_compressed();
+            }
+            writer.add(blob(b"abcabcabc", 1), PuffinCompressionCodec::None)?;
+            writer.add(blob(b"defdefdef", 2), PuffinCompressionCodec::Lz4)?;
+            writer.add(blob(b"ghighighi", 3), PuffinCompressionCodec::Zstd)?;
+            let statistics_file = writer.close(1).await?;
+
+            assert_eq!(statistics_file.statistics_path, "memory:///stats.puffin");
+            assert_eq!(
+                statistics_file.file_size_in_bytes,
+                op.stat("stats.puffin").await?.content_length() as i64
+            );
+            assert_eq!(statistics_file.blob_metadata.len(), 3);
+            assert_eq!(statistics_file.blob_metadata[1].fields, vec![2]);
+
+            let reader = PuffinReader::new(op.clone(), "stats.puffin");
+            let metadata = reader.file_metadata().await?;
+            assert!(metadata.properties.contains_key("created-by"));
+            assert_eq!(metadata.blobs.len(), 3);
+            assert_eq!(metadata.blobs[0].offset, 4);
+            assert_eq!(
+                metadata.blobs[2].compression_codec,
+                PuffinCompressionCodec::Zstd
+            );
+            let blobs = [b"abcabcabc", b"defdefdef", b"ghighighi"];
+            for (metadata, data) in metadata.blobs.iter().zip(blobs) {
+                assert_eq!(reader.read_blob(metadata).await?, Bytes::from_static(data));
+            }
+
+            // The footer size is enough to locate the footer.
+            let file = op.read("stats.puffin").await?;
+            let footer_start = (statistics_file.file_size_in_bytes
+                - statistics_file.file_footer_size_in_bytes)
+                as usize;
+            assert_eq!(file[footer_start..footer_start + 4], MAGIC);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_puffin_footer() -> Result<()> {
+        // An empty file written by the java implementation.
+        let op = Operator::new(Memory::default())?.finish();
+        let payload = br#"{"blobs":[],"properties":{"created-by":"Test 1234"}}"#;
+        let mut file = MAGIC.to_vec();
+        file.extend_from_slice(&MAGIC);
+        file.extend_from_slice(payload);
+        file.extend_from_slice(&(payload.len() as i32).to_le_bytes());
+        file.extend_from_slice(&[0; 4]);
+        file.extend_from_slice(&MAGIC);
+        op.write("empty.puffin", file.clone()).await?;
+
+        let metadata = PuffinReader::new(op.clone(), "empty.puffin")
+            .with_file_size(file.len() as u64)
+            .file_metadata()
+            .await?;
+        assert!(metadata.blobs.is_empty());
+        assert_eq!(metadata.properties["created-by"], "Test 1234");
+
+        // Files truncated or of wrong magic are rejected.
+        op.write("invalid.puffin", file[..file.len() - 1].to_vec())
+            .await?;
+        assert!(PuffinReader::new(op.clone(), "invalid.puffin")
+            .file_metadata()
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -19,6 +19,8 @@ pub fn serialize_schema(schema: &types::Schema) -> Result<String> {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct Schema {
+    /// Missing in the `schema` field of v1 table metadata.
+    #[serde(default)]
     schema_id: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     identifier_field_ids: Option<Vec<i32>>,
@@ -143,6 +145,76 @@ mod tests {
         check_schema_serde_and_parse(json_schema, expected_schema);
     }
 
+    #[test]
+    fn test_schema_timestamps() {
+        let json_schema = r#"
+{
+    "type": "struct",
+    "schema-id": 0,
+    "fields": [ {
+        "id": 1,
+        "name": "ts",
+        "required": false,
+        "type": "timestamp_ns",
+        "initial-default": "2017-11-16T22:31:08.123456789"
+    }, {
+        "id": 2,
+        "name": "tstz",
+        "required": false,
+        "type": "timestamptz",
+        "initial-default": "2017-11-16T14:31:08.123456-08:00"
+    }, {
+        "id": 3,
+        "name": "tstz_ns",
+        "required": false,
+        "type": "timestamptz_ns"
+    } ]
+}
+        "#;
+
+        let timestamp = chrono::NaiveDate::from_ymd_opt(2017, 11, 16)
+            .unwrap()
+            .and_hms_nano_opt(22, 31, 8, 123456789)
+            .unwrap();
+        let field = |id, name: &str, ty, default: Option<types::PrimitiveValue>| types::Field {
+            id,
+            name: name.to_string(),
+            required: false,
+            field_type: types::Any::Primitive(ty),
+            comment: None,
+            initial_default: default.map(types::AnyValue::Primitive),
+            write_default: None,
+        };
+        // Timestamps with time zone are normalized to UTC.
+        let expected_schema = types::Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(
+                    1,
+                    "ts",
+                    types::Primitive::TimestampNs,
+                    Some(types::PrimitiveValue::TimestampNs(timestamp)),
+                ),
+                field(
+                    2,
+                    "tstz",
+                    types::Primitive::Timestampz,
+                    Some(types::PrimitiveValue::Timestampz(
+                        (timestamp - chrono::Duration::nanoseconds(789)).and_utc(),
+                    )),
+                ),
+                field(3, "tstz_ns", types::Primitive::TimestampzNs, None),
+            ],
+        };
+
+        check_schema_serde_and_parse(json_schema, expected_schema.clone());
+        let json = serialize_schema(&expected_schema).unwrap();
+        assert!(json.contains(r#""initial-default":"2017-11-16T22:31:08.123456789""#));
+        assert!(json.contains(r#""initial-default":"2017-11-16T22:31:08.123456+00:00""#));
+        assert!(json.contains(r#""type":"timestamptz_ns""#));
+    }
+
     #[test]
     fn test_schema_list() {
         let json_schema = r#"
@@ -232,4 +304,227 @@ mod tests {
 
         check_schema_serde_and_parse(json_schema, expected_schema);
     }
+
+    #[test]
+    fn test_schema_nested() {
+        // Schema written by spark, with docs and defaults of nested fields.
+        let json_schema = r#"
+{
+    "type" : "struct",
+    "schema-id" : 1,
+    "identifier-field-ids" : [ 1 ],
+    "fields" : [ {
+        "id" : 1,
+        "name" : "id",
+        "required" : true,
+        "type" : "long"
+    }, {
+        "id" : 2,
+        "name" : "orders",
+        "required" : false,
+        "type" : {
+            "type" : "map",
+            "key-id" : 3,
+            "key" : "string",
+            "value-id" : 4,
+            "value-required" : true,
+            "value" : {
+                "type" : "list",
+                "element-id" : 5,
+                "element-required" : false,
+                "element" : {
+                    "type" : "struct",
+                    "fields" : [ {
+                        "id" : 6,
+                        "name" : "price",
+                        "required" : true,
+                        "type" : "decimal(9, 2)",
+                        "doc" : "price of the order"
+                    }, {
+                        "id" : 7,
+                        "name" : "quantity",
+                        "required" : false,
+                        "type" : "int",
+                        "initial-default" : 1,
+                        "write-default" : 2
+                    }, {
+                        "id" : 8,
+                        "name" : "digest",
+                        "required" : false,
+                        "type" : "fixed[16]"
+                    } ]
+                }
+            }
+        },
+        "doc" : "orders by region"
+    } ]
+}
+        "#;
+
+        let field = |id, name: &str, required, field_type| types::Field {
+            id,
+            name: name.to_string(),
+            required,
+            field_type,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        };
+        let order = types::Struct::new(vec![
+            types::Field {
+                comment: Some("price of the order".to_string()),
+                ..field(
+                    6,
+                    "price",
+                    true,
+                    types::Any::Primitive(types::Primitive::Decimal {
+                        precision: 9,
+                        scale: 2,
+                    }),
+                )
+            },
+            types::Field {
+                initial_default: Some(types::AnyValue::Primitive(types::PrimitiveValue::Int(1))),
+                write_default: Some(types::AnyValue::Primitive(types::PrimitiveValue::Int(2))),
+                ..field(
+                    7,
+                    "quantity",
+                    false,
+                    types::Any::Primitive(types::Primitive::Int),
+                )
+            },
+            field(
+                8,
+                "digest",
+                false,
+                types::Any::Primitive(types::Primitive::Fixed(16)),
+            ),
+        ]);
+        let expected_schema = types::Schema {
+            schema_id: 1,
+            identifier_field_ids: Some(vec![1]),
+            fields: vec![
+                field(1, "id", true, types::Any::Primitive(types::Primitive::Long)),
+                types::Field {
+                    comment: Some("orders by region".to_string()),
+                    ..field(
+                        2,
+                        "orders",
+                        false,
+                        types::Any::Map(types::Map {
+                            key_id: 3,
+                            key_type: types::Any::Primitive(types::Primitive::String).into(),
+                            value_id: 4,
+                            value_required: true,
+                            value_type: types::Any::List(types::List {
+                                element_id: 5,
+                                element_required: false,
+                                element_type: types::Any::Struct(order.into()).into(),
+                            })
+                            .into(),
+                        }),
+                    )
+                },
+            ],
+        };
+
+        check_schema_serde_and_parse(json_schema, expected_schema);
+    }
+
+    /// Generates random nested types by a xorshift generator.
+    struct TypeGenerator {
+        state: u64,
+        next_id: i32,
+    }
+
+    impl TypeGenerator {
+        fn next(&mut self, n: u64) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state % n
+        }
+
+        fn id(&mut self) -> i32 {
+            self.next_id += 1;
+            self.next_id
+        }
+
+        fn field(&mut self, depth: usize) -> types::Field {
+            let id = self.id();
+            types::Field {
+                id,
+                name: format!("f{id}"),
+                required: self.next(2) == 0,
+                field_type: self.any(depth),
+                comment: (self.next(3) == 0).then(|| format!("doc of f{id}")),
+                initial_default: None,
+                write_default: None,
+            }
+        }
+
+        fn any(&mut self, depth: usize) -> types::Any {
+            let kind = if depth == 0 { 0 } else { self.next(4) };
+            match kind {
+                1 => {
+                    let fields = (0..=self.next(3)).map(|_| self.field(depth - 1)).collect();
+                    types::Any::Struct(types::Struct::new(fields).into())
+                }
+                2 => types::Any::List(types::List {
+                    element_id: self.id(),
+                    element_required: self.next(2) == 0,
+                    element_type: self.any(depth - 1).into(),
+                }),
+                3 => types::Any::Map(types::Map {
+                    key_id: self.id(),
+                    key_type: self.any(depth - 1).into(),
+                    value_id: self.id(),
+                    value_required: self.next(2) == 0,
+                    value_type: self.any(depth - 1).into(),
+                }),
+                _ => types::Any::Primitive(match self.next(16) {
+                    0 => types::Primitive::Boolean,
+                    1 => types::Primitive::Int,
+                    2 => types::Primitive::Long,
+                    3 => types::Primitive::Float,
+                    4 => types::Primitive::Double,
+                    5 => types::Primitive::Decimal {
+                        precision: 1 + self.next(38) as u8,
+                        scale: self.next(10) as u8,
+                    },
+                    6 => types::Primitive::Date,
+                    7 => types::Primitive::Time,
+                    8 => types::Primitive::Timestamp,
+                    9 => types::Primitive::Timestampz,
+                    10 => types::Primitive::String,
+                    11 => types::Primitive::Uuid,
+                    12 => types::Primitive::Fixed(1 + self.next(32)),
+                    13 => types::Primitive::TimestampNs,
+                    14 => types::Primitive::TimestampzNs,
+                    _ => types::Primitive::Binary,
+                }),
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_random_nested_round_trip() {
+        for seed in 1..=200_u64 {
+            let mut generator = TypeGenerator {
+                state: seed.wrapping_mul(0x9e3779b97f4a7c15),
+                next_id: 0,
+            };
+            let fields = (0..=generator.next(5))
+                .map(|_| generator.field(4))
+                .collect();
+            let schema = types::Schema {
+                schema_id: seed as i32,
+                identifier_field_ids: None,
+                fields,
+            };
+
+            let json = serialize_schema(&schema).unwrap();
+            assert_eq!(parse_schema(json.as_bytes()).unwrap(), schema, "{json}");
+        }
+    }
 }
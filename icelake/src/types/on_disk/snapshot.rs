@@ -12,6 +12,12 @@ pub fn parse_snapshot(bs: &[u8]) -> Result<types::Snapshot> {
     v.try_into()
 }
 
+/// Serialize snapshot to json format.
+pub fn serialize_snapshot(snapshot: types::Snapshot) -> Result<String> {
+    let v = Snapshot::try_from(snapshot)?;
+    Ok(serde_json::to_string(&v)?)
+}
+
 #[derive(Serialize, Deserialize)]
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 #[serde(rename_all = "kebab-case")]
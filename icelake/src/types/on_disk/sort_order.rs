@@ -4,12 +4,19 @@ use crate::types;
 use crate::Error;
 use crate::Result;
 
-/// Parse schema from json bytes.
+/// Parse sort order from json bytes.
 pub fn parse_sort_order(bs: &[u8]) -> Result<types::SortOrder> {
     let t: SortOrder = serde_json::from_slice(bs)?;
     t.try_into()
 }
 
+/// Serialize sort order to json string.
+pub fn serialize_sort_order(sort_order: &types::SortOrder) -> Result<String> {
+    Ok(serde_json::to_string(&SortOrder::try_from(
+        sort_order.clone(),
+    )?)?)
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct SortOrder {
@@ -75,7 +82,7 @@ impl TryFrom<types::SortField> for SortField {
 
     fn try_from(value: types::SortField) -> Result<Self> {
         Ok(Self {
-            transform: (&value.transform).to_string(),
+            transform: value.transform.to_string(),
             source_id: value.source_column_id,
             direction: value.direction.to_string(),
             null_order: value.null_order.to_string(),
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use super::partition_spec::PartitionSpec;
+use super::partition_spec::{PartitionField, PartitionSpec};
 use super::schema::Schema;
 use super::snapshot::Snapshot;
 use super::sort_order::SortOrder;
@@ -23,6 +23,24 @@ pub fn serialize_table_meta(table_meta: types::TableMetadata) -> Result<String>
     Ok(serde_json::to_string(&v)?)
 }
 
+/// Parse statistics file from json bytes.
+pub fn parse_statistics_file(bs: &[u8]) -> Result<types::StatisticsFile> {
+    let v: StatisticsFile = serde_json::from_slice(bs)?;
+    Ok(v.into())
+}
+
+/// Serialize statistics file to json string.
+pub fn serialize_statistics_file(statistics_file: &types::StatisticsFile) -> Result<String> {
+    Ok(serde_json::to_string(&StatisticsFile::from(
+        statistics_file.clone(),
+    ))?)
+}
+
+/// Table metadata of both v1 and v2.
+///
+/// Fields introduced by v2 are optional in v1, v1 tables written by old
+/// writers may only have the deprecated `schema` and `partition-spec`.
+/// Writers of v1 tables must write both the deprecated and new fields.
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 struct TableMetadata {
@@ -30,22 +48,53 @@ struct TableMetadata {
     table_uuid: String,
     location: String,
     #[serde(default)]
-    last_sequence_number: i64,
+    last_sequence_number: Option<i64>,
     last_updated_ms: i64,
     last_column_id: i32,
-    schemas: Vec<Schema>,
-    current_schema_id: i32,
-    partition_specs: Vec<PartitionSpec>,
-    default_spec_id: i32,
-    last_partition_id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema: Option<Schema>,
+    #[serde(default)]
+    schemas: Option<Vec<Schema>>,
+    #[serde(default)]
+    current_schema_id: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    partition_spec: Option<Vec<PartitionField>>,
+    #[serde(default)]
+    partition_specs: Option<Vec<PartitionSpec>>,
+    #[serde(default)]
+    default_spec_id: Option<i32>,
+    #[serde(default)]
+    last_partition_id: Option<i32>,
     properties: Option<HashMap<String, String>>,
     current_snapshot_id: Option<i64>,
     snapshots: Option<Vec<Snapshot>>,
     snapshot_log: Option<Vec<SnapshotLog>>,
     metadata_log: Option<Vec<MetadataLog>>,
-    sort_orders: Vec<SortOrder>,
-    default_sort_order_id: i32,
+    #[serde(default)]
+    sort_orders: Option<Vec<SortOrder>>,
+    #[serde(default)]
+    default_sort_order_id: Option<i32>,
+    #[serde(default)]
     refs: HashMap<String, SnapshotReference>,
+    #[serde(default)]
+    statistics: Vec<StatisticsFile>,
+}
+
+/// Returns the value of a field, which is required by v2.
+fn required_by_v2<T>(
+    format_version: types::TableFormatVersion,
+    field: &str,
+    value: Option<T>,
+    v1_default: impl FnOnce() -> T,
+) -> Result<T> {
+    match (value, format_version) {
+        (Some(v), _) => Ok(v),
+        (None, types::TableFormatVersion::V1) => Ok(v1_default()),
+        (None, types::TableFormatVersion::V2) => Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("{field} is required in v2 table metadata but not found"),
+        )),
+    }
 }
 
 impl TryFrom<TableMetadata> for types::TableMetadata {
@@ -63,15 +112,64 @@ impl TryFrom<TableMetadata> for types::TableMetadata {
             }
         };
 
-        let mut schemas = Vec::with_capacity(v.schemas.len());
-        for schema in v.schemas {
-            schemas.push(schema.try_into()?);
-        }
-
-        let mut partition_specs = Vec::with_capacity(v.partition_specs.len());
-        for partition_spec in v.partition_specs {
-            partition_specs.push(partition_spec.try_into()?);
+        let legacy_schema = v.schema;
+        let schemas = required_by_v2(format_version, "schemas", v.schemas, || {
+            legacy_schema.into_iter().collect()
+        })?;
+        if schemas.is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "schemas is required in table metadata but not found",
+            ));
         }
+        let schemas = schemas
+            .into_iter()
+            .map(types::Schema::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let current_schema_id = required_by_v2(
+            format_version,
+            "current-schema-id",
+            v.current_schema_id,
+            || schemas[0].schema_id,
+        )?;
+
+        let legacy_partition_spec = v.partition_spec;
+        let partition_specs =
+            required_by_v2(format_version, "partition-specs", v.partition_specs, || {
+                vec![PartitionSpec {
+                    spec_id: 0,
+                    fields: legacy_partition_spec.unwrap_or_default(),
+                }]
+            })?
+            .into_iter()
+            .map(types::PartitionSpec::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let default_spec_id =
+            required_by_v2(format_version, "default-spec-id", v.default_spec_id, || {
+                partition_specs
+                    .first()
+                    .map(|v| v.spec_id)
+                    .unwrap_or_default()
+            })?;
+        // Partition field ids start from 1000 in v1.
+        let last_partition_id = required_by_v2(
+            format_version,
+            "last-partition-id",
+            v.last_partition_id,
+            || {
+                partition_specs
+                    .iter()
+                    .flat_map(|v| v.fields.iter().map(|f| f.partition_field_id))
+                    .max()
+                    .unwrap_or(999)
+            },
+        )?;
+        let last_sequence_number = required_by_v2(
+            format_version,
+            "last-sequence-number",
+            v.last_sequence_number,
+            || 0,
+        )?;
 
         let snapshots = match v.snapshots {
             Some(v) => {
@@ -106,10 +204,30 @@ impl TryFrom<TableMetadata> for types::TableMetadata {
             None => None,
         };
 
-        let mut sort_orders = Vec::with_capacity(v.sort_orders.len());
-        for sort_order in v.sort_orders {
-            sort_orders.push(sort_order.try_into()?);
-        }
+        // v1 tables without sort orders are unsorted.
+        let (sort_orders, default_sort_order_id) = match v.sort_orders {
+            Some(sort_orders) => (
+                sort_orders
+                    .into_iter()
+                    .map(types::SortOrder::try_from)
+                    .collect::<Result<Vec<_>>>()?,
+                required_by_v2(
+                    format_version,
+                    "default-sort-order-id",
+                    v.default_sort_order_id,
+                    || 0,
+                )?,
+            ),
+            None => {
+                let unsorted = types::SortOrder {
+                    order_id: 0,
+                    fields: vec![],
+                };
+                let sort_orders =
+                    required_by_v2(format_version, "sort-orders", None, || vec![unsorted])?;
+                (sort_orders, 0)
+            }
+        };
 
         let refs = {
             let mut refs = HashMap::with_capacity(v.refs.len());
@@ -123,22 +241,23 @@ impl TryFrom<TableMetadata> for types::TableMetadata {
             format_version,
             table_uuid: v.table_uuid,
             location: v.location,
-            last_sequence_number: v.last_sequence_number,
+            last_sequence_number,
             last_updated_ms: v.last_updated_ms,
             last_column_id: v.last_column_id,
             schemas,
-            current_schema_id: v.current_schema_id,
+            current_schema_id,
             partition_specs,
-            default_spec_id: v.default_spec_id,
-            last_partition_id: v.last_partition_id,
+            default_spec_id,
+            last_partition_id,
             properties: v.properties,
             current_snapshot_id: v.current_snapshot_id,
             snapshots,
             snapshot_log,
             metadata_log,
             sort_orders,
-            default_sort_order_id: v.default_sort_order_id,
+            default_sort_order_id,
             refs,
+            statistics: v.statistics.into_iter().map(Into::into).collect(),
         })
     }
 }
@@ -147,26 +266,39 @@ impl TryFrom<types::TableMetadata> for TableMetadata {
     type Error = Error;
 
     fn try_from(value: types::TableMetadata) -> Result<Self> {
+        let (schema, partition_spec) = match value.format_version {
+            types::TableFormatVersion::V1 => (
+                Some(Schema::try_from(value.current_schema()?)?),
+                Some(PartitionSpec::try_from(value.current_partition_spec()?)?.fields),
+            ),
+            types::TableFormatVersion::V2 => (None, None),
+        };
         Ok(Self {
             format_version: value.format_version as i32,
             table_uuid: value.table_uuid,
             location: value.location,
-            last_sequence_number: value.last_sequence_number,
+            last_sequence_number: Some(value.last_sequence_number),
             last_updated_ms: value.last_updated_ms,
             last_column_id: value.last_column_id,
-            schemas: value
-                .schemas
-                .iter()
-                .map(Schema::try_from)
-                .collect::<Result<Vec<Schema>>>()?,
-            current_schema_id: value.current_schema_id,
-            partition_specs: value
-                .partition_specs
-                .iter()
-                .map(PartitionSpec::try_from)
-                .collect::<Result<Vec<PartitionSpec>>>()?,
-            default_spec_id: value.default_spec_id,
-            last_partition_id: value.last_partition_id,
+            schema,
+            schemas: Some(
+                value
+                    .schemas
+                    .iter()
+                    .map(Schema::try_from)
+                    .collect::<Result<Vec<Schema>>>()?,
+            ),
+            current_schema_id: Some(value.current_schema_id),
+            partition_spec,
+            partition_specs: Some(
+                value
+                    .partition_specs
+                    .iter()
+                    .map(PartitionSpec::try_from)
+                    .collect::<Result<Vec<PartitionSpec>>>()?,
+            ),
+            default_spec_id: Some(value.default_spec_id),
+            last_partition_id: Some(value.last_partition_id),
             properties: value.properties,
             current_snapshot_id: value.current_snapshot_id,
             snapshots: value
@@ -193,17 +325,20 @@ impl TryFrom<types::TableMetadata> for TableMetadata {
                         .collect::<Result<Vec<MetadataLog>>>()
                 })
                 .transpose()?,
-            sort_orders: value
-                .sort_orders
-                .into_iter()
-                .map(SortOrder::try_from)
-                .collect::<Result<Vec<SortOrder>>>()?,
-            default_sort_order_id: value.default_sort_order_id,
+            sort_orders: Some(
+                value
+                    .sort_orders
+                    .into_iter()
+                    .map(SortOrder::try_from)
+                    .collect::<Result<Vec<SortOrder>>>()?,
+            ),
+            default_sort_order_id: Some(value.default_sort_order_id),
             refs: value
                 .refs
                 .into_iter()
                 .map(|e| SnapshotReference::try_from(e.1).map(|s| (e.0, s)))
                 .collect::<Result<HashMap<String, SnapshotReference>>>()?,
+            statistics: value.statistics.into_iter().map(Into::into).collect(),
         })
     }
 }
@@ -305,6 +440,80 @@ impl TryFrom<types::SnapshotReference> for SnapshotReference {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct StatisticsFile {
+    snapshot_id: i64,
+    statistics_path: String,
+    file_size_in_bytes: i64,
+    file_footer_size_in_bytes: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_metadata: Option<String>,
+    blob_metadata: Vec<BlobMetadata>,
+}
+
+impl From<StatisticsFile> for types::StatisticsFile {
+    fn from(v: StatisticsFile) -> Self {
+        Self {
+            snapshot_id: v.snapshot_id,
+            statistics_path: v.statistics_path,
+            file_size_in_bytes: v.file_size_in_bytes,
+            file_footer_size_in_bytes: v.file_footer_size_in_bytes,
+            key_metadata: v.key_metadata,
+            blob_metadata: v.blob_metadata.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<types::StatisticsFile> for StatisticsFile {
+    fn from(value: types::StatisticsFile) -> Self {
+        Self {
+            snapshot_id: value.snapshot_id,
+            statistics_path: value.statistics_path,
+            file_size_in_bytes: value.file_size_in_bytes,
+            file_footer_size_in_bytes: value.file_footer_size_in_bytes,
+            key_metadata: value.key_metadata,
+            blob_metadata: value.blob_metadata.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BlobMetadata {
+    #[serde(rename = "type")]
+    typ: String,
+    snapshot_id: i64,
+    sequence_number: i64,
+    fields: Vec<i32>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    properties: HashMap<String, String>,
+}
+
+impl From<BlobMetadata> for types::BlobMetadata {
+    fn from(v: BlobMetadata) -> Self {
+        Self {
+            typ: v.typ,
+            snapshot_id: v.snapshot_id,
+            sequence_number: v.sequence_number,
+            fields: v.fields,
+            properties: v.properties,
+        }
+    }
+}
+
+impl From<types::BlobMetadata> for BlobMetadata {
+    fn from(value: types::BlobMetadata) -> Self {
+        Self {
+            typ: value.typ,
+            snapshot_id: value.snapshot_id,
+            sequence_number: value.sequence_number,
+            fields: value.fields,
+            properties: value.properties,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::types::TableFormatVersion;
@@ -406,6 +615,20 @@ mod tests {
             sort_orders: vec![],
             default_sort_order_id: 1,
             refs: HashMap::default(),
+            statistics: vec![types::StatisticsFile {
+                snapshot_id: 1,
+                statistics_path: "/opt/bitnami/spark/warehouse/db/table/1.stats".to_string(),
+                file_size_in_bytes: 100,
+                file_footer_size_in_bytes: 50,
+                key_metadata: None,
+                blob_metadata: vec![types::BlobMetadata {
+                    typ: "apache-datasketches-theta-v1".to_string(),
+                    snapshot_id: 1,
+                    sequence_number: 2,
+                    fields: vec![1],
+                    properties: HashMap::from([("ndv".to_string(), "10".to_string())]),
+                }],
+            }],
         };
 
         let json = serialize_table_meta(metadata.clone()).unwrap();
@@ -413,4 +636,53 @@ mod tests {
         let parsed_table_meta = parse_table_metadata(json.as_bytes()).unwrap();
         assert_eq!(metadata, parsed_table_meta);
     }
+
+    #[test]
+    fn test_parse_legacy_table_metadata_v1() {
+        let json = r#"{
+            "format-version": 1,
+            "table-uuid": "1932a94b-d2bf-43ca-a66f-3158a09baf1f",
+            "location": "/opt/bitnami/spark/warehouse/db/table",
+            "last-updated-ms": 1686911664577,
+            "last-column-id": 1,
+            "schema": {
+                "type": "struct",
+                "fields": [{"id": 1, "name": "id", "required": false, "type": "long"}]
+            },
+            "partition-spec": [
+                {"name": "id_bucket", "transform": "bucket[4]", "source-id": 1, "field-id": 1000}
+            ],
+            "properties": {}
+        }"#;
+
+        let metadata = parse_table_metadata(json.as_bytes()).unwrap();
+        assert_eq!(metadata.schemas.len(), 1);
+        assert_eq!(metadata.current_schema_id, 0);
+        assert_eq!(metadata.current_partition_spec().unwrap().fields.len(), 1);
+        assert_eq!(metadata.last_partition_id, 1000);
+        assert_eq!(metadata.last_sequence_number, 0);
+        assert_eq!(metadata.current_sort_order().unwrap().fields.len(), 0);
+
+        // v1 metadata is written with deprecated fields too.
+        let json = serialize_table_meta(metadata.clone()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("schema").is_some());
+        assert_eq!(value["partition-spec"].as_array().unwrap().len(), 1);
+        assert_eq!(parse_table_metadata(json.as_bytes()).unwrap(), metadata);
+
+        // Fields introduced by v2 are required by v2.
+        let json = r#"{
+            "format-version": 2,
+            "table-uuid": "1932a94b-d2bf-43ca-a66f-3158a09baf1f",
+            "location": "/opt/bitnami/spark/warehouse/db/table",
+            "last-updated-ms": 1686911664577,
+            "last-column-id": 1,
+            "schema": {
+                "type": "struct",
+                "fields": [{"id": 1, "name": "id", "required": false, "type": "long"}]
+            },
+            "partition-spec": []
+        }"#;
+        assert!(parse_table_metadata(json.as_bytes()).is_err());
+    }
 }
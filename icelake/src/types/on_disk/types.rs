@@ -62,6 +62,8 @@ impl TryFrom<Types> for types::Any {
             "time" => types::Any::Primitive(types::Primitive::Time),
             "timestamp" => types::Any::Primitive(types::Primitive::Timestamp),
             "timestamptz" => types::Any::Primitive(types::Primitive::Timestampz),
+            "timestamp_ns" => types::Any::Primitive(types::Primitive::TimestampNs),
+            "timestamptz_ns" => types::Any::Primitive(types::Primitive::TimestampzNs),
             "string" => types::Any::Primitive(types::Primitive::String),
             "uuid" => types::Any::Primitive(types::Primitive::Uuid),
             "binary" => types::Any::Primitive(types::Primitive::Binary),
@@ -89,6 +91,7 @@ impl TryFrom<Types> for types::Any {
                     .trim_start_matches('(')
                     .trim_end_matches(')')
                     .split(',')
+                    .map(str::trim)
                     .collect::<Vec<_>>();
                 if parts.len() != 2 {
                     return Err(Error::new(
@@ -119,17 +122,7 @@ impl TryFrom<Types> for types::Any {
 
                 let mut fields = Vec::with_capacity(raw_fields.len());
                 for f in raw_fields {
-                    let field = types::Field {
-                        id: f.id,
-                        name: f.name.clone(),
-                        required: f.required,
-                        field_type: f.typ.try_into()?,
-                        comment: f.doc.clone(),
-                        initial_default: None,
-                        write_default: None,
-                    };
-
-                    fields.push(field);
+                    fields.push(f.try_into()?);
                 }
 
                 types::Any::Struct(types::Struct::new(fields).into())
@@ -194,6 +187,8 @@ impl TryFrom<Any> for Types {
                     types::Primitive::Time => "time".to_string(),
                     types::Primitive::Timestamp => "timestamp".to_string(),
                     types::Primitive::Timestampz => "timestamptz".to_string(),
+                    types::Primitive::TimestampNs => "timestamp_ns".to_string(),
+                    types::Primitive::TimestampzNs => "timestamptz_ns".to_string(),
                     types::Primitive::String => "string".to_string(),
                     types::Primitive::Uuid => "uuid".to_string(),
                     types::Primitive::Binary => "binary".to_string(),
@@ -208,28 +203,10 @@ impl TryFrom<Any> for Types {
                 })
             }
             types::Any::Struct(struct_type) => {
-                let fields = struct_type.fields();
-                let json_fields = fields
+                let json_fields = struct_type
+                    .fields()
                     .iter()
-                    .map(|f| -> Result<Field> {
-                        Ok(Field {
-                            id: f.id,
-                            name: f.name.clone(),
-                            required: f.required,
-                            typ: f.field_type.clone().try_into()?,
-                            doc: f.comment.clone(),
-                            initial_default: f
-                                .initial_default
-                                .clone()
-                                .map(serialize_value_to_json)
-                                .transpose()?,
-                            write_default: f
-                                .initial_default
-                                .clone()
-                                .map(serialize_value_to_json)
-                                .transpose()?,
-                        })
-                    })
+                    .map(|f| Field::try_from(f.clone()))
                     .collect::<Result<Vec<Field>>>()?;
 
                 Ok(Types {
@@ -343,8 +320,10 @@ fn parse_json_value(expect_type: &types::Any, value: serde_json::Value) -> Resul
             types::Primitive::Decimal { .. } => parse_json_value_to_decimal(value),
             types::Primitive::Date => parse_json_value_to_date(value),
             types::Primitive::Time => parse_json_value_to_time(value),
-            types::Primitive::Timestamp => parse_json_value_to_timestamp(value),
-            types::Primitive::Timestampz => parse_json_value_to_timestampz(value),
+            types::Primitive::Timestamp => parse_json_value_to_timestamp(value, false),
+            types::Primitive::Timestampz => parse_json_value_to_timestampz(value, false),
+            types::Primitive::TimestampNs => parse_json_value_to_timestamp(value, true),
+            types::Primitive::TimestampzNs => parse_json_value_to_timestampz(value, true),
             types::Primitive::String => parse_json_value_to_string(value),
             types::Primitive::Uuid => parse_json_value_to_uuid(value),
             types::Primitive::Fixed(size) => parse_json_value_to_fixed(value, *size),
@@ -605,9 +584,10 @@ fn parse_json_value_to_time(value: serde_json::Value) -> Result<types::AnyValue>
 
 /// JSON single-value serialization requires Timestamp been
 /// stored as string in ISO-8601 standard time, like
-/// `2017-11-16T22:31:08.123456`.
+/// `2017-11-16T22:31:08.123456`, or `2017-11-16T22:31:08.123456789` of
+/// nanosecond precision.
 #[inline]
-fn parse_json_value_to_timestamp(value: serde_json::Value) -> Result<types::AnyValue> {
+fn parse_json_value_to_timestamp(value: serde_json::Value, nanos: bool) -> Result<types::AnyValue> {
     let expect_type = "`Timestamp`";
 
     match value {
@@ -620,9 +600,11 @@ fn parse_json_value_to_timestamp(value: serde_json::Value) -> Result<types::AnyV
                 .set_source(err)
             })?;
 
-            Ok(types::AnyValue::Primitive(
-                types::PrimitiveValue::Timestamp(v),
-            ))
+            Ok(types::AnyValue::Primitive(if nanos {
+                types::PrimitiveValue::TimestampNs(v)
+            } else {
+                types::PrimitiveValue::Timestamp(v)
+            }))
         }
         _ => Err(Error::new(
             ErrorKind::IcebergDataInvalid,
@@ -633,9 +615,13 @@ fn parse_json_value_to_timestamp(value: serde_json::Value) -> Result<types::AnyV
 
 /// JSON single-value serialization requires Timestampz been
 /// stored as string in ISO-8601 standard time with timezone, like
-/// `2017-11-16T22:31:08.123456+00:00`.
+/// `2017-11-16T22:31:08.123456+00:00`. Values of other offsets are
+/// normalized to UTC.
 #[inline]
-fn parse_json_value_to_timestampz(value: serde_json::Value) -> Result<types::AnyValue> {
+fn parse_json_value_to_timestampz(
+    value: serde_json::Value,
+    nanos: bool,
+) -> Result<types::AnyValue> {
     let expect_type = "`Timestampz`";
 
     match value {
@@ -648,9 +634,11 @@ fn parse_json_value_to_timestampz(value: serde_json::Value) -> Result<types::Any
                 .set_source(err)
             })?;
 
-            Ok(types::AnyValue::Primitive(
-                types::PrimitiveValue::Timestampz(v),
-            ))
+            Ok(types::AnyValue::Primitive(if nanos {
+                types::PrimitiveValue::TimestampzNs(v)
+            } else {
+                types::PrimitiveValue::Timestampz(v)
+            }))
         }
         _ => Err(Error::new(
             ErrorKind::IcebergDataInvalid,
@@ -709,7 +697,7 @@ fn parse_json_value_to_fixed(value: serde_json::Value, size: u64) -> Result<type
 
     match value {
         serde_json::Value::String(v) => {
-            let mut bs = vec![0; v.as_bytes().len() / 2];
+            let mut bs = vec![0; v.len() / 2];
             faster_hex::hex_decode(v.as_bytes(), &mut bs).map_err(|err| {
                 Error::new(
                     ErrorKind::IcebergDataInvalid,
@@ -745,7 +733,7 @@ fn parse_json_value_to_binary(value: serde_json::Value) -> Result<types::AnyValu
 
     match value {
         serde_json::Value::String(v) => {
-            let mut bs = vec![0; v.as_bytes().len() / 2];
+            let mut bs = vec![0; v.len() / 2];
             faster_hex::hex_decode(v.as_bytes(), &mut bs).map_err(|err| {
                 Error::new(
                     ErrorKind::IcebergDataInvalid,
@@ -875,46 +863,37 @@ fn parse_json_value_to_map(
     Ok(types::AnyValue::Map { keys, values })
 }
 
-/// We need to support both `T` and `Box<T>` so we can't use
-/// the `std::str::FromStr` trait directly.
-pub trait FromTypesStr {
-    fn from_str(s: &str) -> Self;
+/// We need to support `T`, `Box<T>` and `Option<Box<T>>`, so types are
+/// always deserialized as `Types` and then converted.
+pub trait FromTypes {
+    fn from_types(t: Types) -> Self;
 }
 
-impl FromTypesStr for Types {
-    fn from_str(s: &str) -> Self {
-        Types {
-            typ: s.to_string(),
-            ..Default::default()
-        }
+impl FromTypes for Types {
+    fn from_types(t: Types) -> Self {
+        t
     }
 }
 
-impl FromTypesStr for Box<Types> {
-    fn from_str(s: &str) -> Self {
-        Box::new(Types {
-            typ: s.to_string(),
-            ..Default::default()
-        })
+impl FromTypes for Box<Types> {
+    fn from_types(t: Types) -> Self {
+        Box::new(t)
     }
 }
 
-impl FromTypesStr for Option<Box<Types>> {
-    fn from_str(s: &str) -> Self {
-        Some(Box::new(Types {
-            typ: s.to_string(),
-            ..Default::default()
-        }))
+impl FromTypes for Option<Box<Types>> {
+    fn from_types(t: Types) -> Self {
+        Some(Box::new(t))
     }
 }
 
 pub fn string_or_struct<'de, T, D>(deserializer: D) -> std::result::Result<T, D::Error>
 where
-    T: Deserialize<'de> + FromTypesStr,
+    T: FromTypes,
     D: Deserializer<'de>,
 {
-    // This is a Visitor that forwards string types to T's `FromStr` impl and
-    // forwards map types to T's `Deserialize` impl. The `PhantomData` is to
+    // This is a Visitor that forwards string types to primitive `Types`,
+    // and map types to `Types`'s `Deserialize` impl. The `PhantomData` is to
     // keep the compiler from complaining about T being an unused generic type
     // parameter. We need T in order to know the Value type for the Visitor
     // impl.
@@ -922,7 +901,7 @@ where
 
     impl<'de, T> Visitor<'de> for StringOrStruct<T>
     where
-        T: Deserialize<'de> + FromTypesStr,
+        T: FromTypes,
     {
         type Value = T;
 
@@ -934,7 +913,10 @@ where
         where
             E: de::Error,
         {
-            Ok(FromTypesStr::from_str(value))
+            Ok(T::from_types(Types {
+                typ: value.to_string(),
+                ..Default::default()
+            }))
         }
 
         fn visit_map<M>(self, map: M) -> std::result::Result<T, M::Error>
@@ -942,10 +924,10 @@ where
             M: MapAccess<'de>,
         {
             // `MapAccessDeserializer` is a wrapper that turns a `MapAccess`
-            // into a `Deserializer`, allowing it to be used as the input to T's
-            // `Deserialize` implementation. T then deserializes itself using
-            // the entries from the map visitor.
-            Deserialize::deserialize(de::value::MapAccessDeserializer::new(map))
+            // into a `Deserializer`, allowing it to be used as the input to
+            // `Types`'s `Deserialize` implementation.
+            let t = Types::deserialize(de::value::MapAccessDeserializer::new(map))?;
+            Ok(T::from_types(t))
         }
     }
 
@@ -959,7 +941,9 @@ impl Serialize for Types {
     {
         match self.typ.as_str() {
             "boolean" | "int" | "long" | "float" | "double" | "date" | "time" | "timestamp"
-            | "timestamptz" | "string" | "uuid" | "binary" => serializer.serialize_str(&self.typ),
+            | "timestamptz" | "timestamp_ns" | "timestamptz_ns" | "string" | "uuid" | "binary" => {
+                serializer.serialize_str(&self.typ)
+            }
             v if v.starts_with("decimal") || v.starts_with("fixed") => {
                 serializer.serialize_str(&self.typ)
             }
@@ -1,16 +1,288 @@
-//! to_arrow module provices the convert functions from iceberg in-memory
-//! schema to arrow schema.
+//! to_arrow module provides the conversions between iceberg in-memory
+//! schema and arrow schema.
+//!
+//! Field ids of iceberg fields are kept in the metadata of arrow fields by
+//! the key [`PARQUET_FIELD_ID_META_KEY`], including ids of list elements,
+//! map keys and map values. Note that the parquet writer doesn't write
+//! these ids into parquet schemas yet, they're only kept by the arrow schema
+//! embedded in written files.
+//!
+//! Timestamps with time zone are converted to arrow timestamps in UTC,
+//! while arrow timestamps in any time zone are converted to timestamps with
+//! time zone, since arrow timestamps are stored as instants in UTC
+//! regardless of their time zones.
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
-use crate::error::Error;
+use arrow::array::{
+    new_null_array, Array, ArrayRef, AsArray, Int64Array, TimestampMicrosecondArray,
+    TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
+};
+use arrow::compute::cast;
 use arrow::datatypes::DataType as ArrowDataType;
 use arrow::datatypes::Field as ArrowField;
+use arrow::datatypes::Int64Type;
 use arrow::datatypes::Schema as ArrowSchema;
 use arrow::datatypes::TimeUnit;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
 
 use super::in_memory as types;
+use crate::error::Error;
+use crate::ErrorKind;
+
+/// Metadata key of field ids in arrow fields, which is the same key used by
+/// parquet to keep field ids in arrow schemas.
+pub const PARQUET_FIELD_ID_META_KEY: &str = "PARQUET:field_id";
+
+/// Metadata key of arrow extension type names.
+const EXTENSION_NAME_META_KEY: &str = "ARROW:extension:name";
+
+/// Extension type name of uuids, which are stored as 16 bytes.
+const UUID_EXTENSION_NAME: &str = "arrow.uuid";
+
+/// Time zone of arrow timestamps converted from timestamps with time zone.
+pub(crate) const UTC_TIMEZONE: &str = "+00:00";
+
+/// Convert an iceberg schema into an arrow schema.
+///
+/// Field ids are kept in the metadata of arrow fields, see
+/// [`PARQUET_FIELD_ID_META_KEY`].
+pub fn schema_to_arrow_schema(schema: &types::Schema) -> crate::Result<ArrowSchema> {
+    schema.clone().try_into()
+}
+
+/// Convert an arrow schema into an iceberg schema with schema id 0.
+///
+/// All arrow fields must have field ids in their metadata, see
+/// [`PARQUET_FIELD_ID_META_KEY`].
+pub fn arrow_schema_to_schema(schema: &ArrowSchema) -> crate::Result<types::Schema> {
+    schema.try_into()
+}
+
+/// Cast top level timestamp columns of `batch` to the time units and time
+/// zones of the columns of the same names in `schema`.
+///
+/// Arrow timestamps are instants in UTC whatever their time zones are, so
+/// values are kept as they are, only converted to the time unit of the
+/// column: timestamps with time zones written into columns without time
+/// zone are the wall clock times in UTC, and timestamps without time zone
+/// written into columns with time zone are taken as in UTC. Values are
+/// floored when converted to a coarser unit, and values overflowing a finer
+/// unit are errors.
+pub(crate) fn cast_timestamps(
+    batch: &RecordBatch,
+    schema: &ArrowSchema,
+) -> crate::Result<RecordBatch> {
+    let batch_schema = batch.schema();
+    let target = |field: &ArrowField| match (
+        field.data_type(),
+        schema.field_with_name(field.name()).map(|v| v.data_type()),
+    ) {
+        (from @ ArrowDataType::Timestamp(..), Ok(to @ ArrowDataType::Timestamp(..)))
+            if from != to =>
+        {
+            Some(to.clone())
+        }
+        _ => None,
+    };
+    if batch_schema.fields().iter().all(|v| target(v).is_none()) {
+        return Ok(batch.clone());
+    }
+
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in batch_schema.fields().iter().zip(batch.columns()) {
+        match target(field) {
+            Some(to) => {
+                columns.push(cast_timestamp(column, &to)?);
+                fields.push(field.as_ref().clone().with_data_type(to));
+            }
+            None => {
+                columns.push(column.clone());
+                fields.push(field.as_ref().clone());
+            }
+        }
+    }
+    let schema = ArrowSchema::new_with_metadata(fields, batch_schema.metadata().clone());
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// Project columns of `batch` into `schema` of the table, so that batches
+/// with columns in other orders, without field ids or of narrower types
+/// are written as the table schema.
+///
+/// Columns are matched to top level fields of the schema by field ids
+/// kept in their metadata, or by names if they have no ids. Columns are
+/// cast by safe promotions of iceberg, `int` to `long`, `float` to
+/// `double` and decimals to larger precisions, and timestamps are cast to
+/// the units and time zones of their fields like [`cast_timestamps`].
+/// Missing optional columns are filled with nulls.
+///
+/// Returns error naming the field if a column is not in the schema, two
+/// columns match the same field, a required column is missing, or a
+/// column is of an incompatible type.
+pub(crate) fn coerce_batch(
+    batch: &RecordBatch,
+    schema: &Arc<ArrowSchema>,
+) -> crate::Result<RecordBatch> {
+    let batch_schema = batch.schema();
+    // Indices of columns in `batch` of each field of `schema`.
+    let mut indices = vec![None; schema.fields().len()];
+    for (i, column) in batch_schema.fields().iter().enumerate() {
+        let position = match field_id(column) {
+            Ok(id) => schema
+                .fields()
+                .iter()
+                .position(|v| field_id(v).ok() == Some(id)),
+            Err(_) => schema.index_of(column.name()).ok(),
+        };
+        match position {
+            Some(position) => {
+                if let Some(previous) = indices[position].replace(i) {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!(
+                            "Columns {} and {} are both written to field {}",
+                            batch_schema.field(previous).name(),
+                            column.name(),
+                            schema.field(position).name()
+                        ),
+                    ));
+                }
+            }
+            None => {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Column {} is not in the table schema", column.name()),
+                ))
+            }
+        }
+    }
+
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(indices)
+        .map(|(field, index)| {
+            let Some(index) = index else {
+                return if field.is_nullable() {
+                    Ok(new_null_array(field.data_type(), batch.num_rows()))
+                } else {
+                    Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Required column {} is missing", field.name()),
+                    ))
+                };
+            };
+            let column = batch.column(index);
+            let (from, to) = (column.data_type(), field.data_type());
+            if from == to {
+                return Ok(column.clone());
+            }
+            if matches!(
+                (from, to),
+                (ArrowDataType::Timestamp(..), ArrowDataType::Timestamp(..))
+            ) {
+                return cast_timestamp(column, to);
+            }
+            // Types of nested fields only differ in names and metadata.
+            let compatible = from.equals_datatype(to)
+                || matches!(
+                    (from, to),
+                    (ArrowDataType::Int32, ArrowDataType::Int64)
+                        | (ArrowDataType::Float32, ArrowDataType::Float64)
+                )
+                || matches!(
+                    (from, to),
+                    (ArrowDataType::Decimal128(p1, s1), ArrowDataType::Decimal128(p2, s2))
+                        if p1 <= p2 && s1 == s2
+                );
+            let error = || {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!(
+                        "Column {} of type {from} can't be written as type {to}",
+                        field.name()
+                    ),
+                )
+            };
+            if !compatible {
+                return Err(error());
+            }
+            cast(column, to).map_err(|e| error().set_source(e))
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    RecordBatch::try_new(schema.clone(), columns).map_err(|e| {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            "Batch doesn't match the table schema",
+        )
+        .set_source(e)
+    })
+}
+
+fn cast_timestamp(array: &ArrayRef, to: &ArrowDataType) -> crate::Result<ArrayRef> {
+    let (ArrowDataType::Timestamp(from_unit, _), ArrowDataType::Timestamp(to_unit, tz)) =
+        (array.data_type(), to)
+    else {
+        unreachable!("only timestamps are casted");
+    };
+    let unit = |v: &TimeUnit| match v {
+        TimeUnit::Second => 1_i64,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    };
+    let (from, to_size) = (unit(from_unit), unit(to_unit));
+
+    let values = cast(array, &ArrowDataType::Int64)?;
+    let values = values.as_primitive::<Int64Type>();
+    let values: Int64Array = if from >= to_size {
+        values.unary(|v| v.div_euclid(from / to_size))
+    } else {
+        values.try_unary(|v| {
+            v.checked_mul(to_size / from).ok_or_else(|| {
+                ArrowError::ComputeError(format!("Timestamp {v} overflows {to_unit:?}"))
+            })
+        })?
+    };
+    let (values, nulls) = (values.values().clone(), Array::nulls(&values).cloned());
+    let array: ArrayRef = match to_unit {
+        TimeUnit::Second => {
+            Arc::new(TimestampSecondArray::new(values, nulls).with_timezone_opt(tz.clone()))
+        }
+        TimeUnit::Millisecond => {
+            Arc::new(TimestampMillisecondArray::new(values, nulls).with_timezone_opt(tz.clone()))
+        }
+        TimeUnit::Microsecond => {
+            Arc::new(TimestampMicrosecondArray::new(values, nulls).with_timezone_opt(tz.clone()))
+        }
+        TimeUnit::Nanosecond => {
+            Arc::new(TimestampNanosecondArray::new(values, nulls).with_timezone_opt(tz.clone()))
+        }
+    };
+    Ok(array)
+}
+
+/// Create an arrow field with the field id of an iceberg field.
+fn arrow_field(
+    name: impl Into<String>,
+    ty: &types::Any,
+    nullable: bool,
+    id: i32,
+) -> crate::Result<ArrowField> {
+    let mut metadata = HashMap::from([(PARQUET_FIELD_ID_META_KEY.to_string(), id.to_string())]);
+    if ty == &types::Any::Primitive(types::Primitive::Uuid) {
+        metadata.insert(
+            EXTENSION_NAME_META_KEY.to_string(),
+            UUID_EXTENSION_NAME.to_string(),
+        );
+    }
+    Ok(ArrowField::new(name, ty.clone().try_into()?, nullable).with_metadata(metadata))
+}
 
 impl TryFrom<types::Schema> for ArrowSchema {
     type Error = Error;
@@ -30,13 +302,7 @@ impl TryFrom<types::Field> for ArrowField {
     type Error = Error;
 
     fn try_from(value: types::Field) -> Result<Self, Self::Error> {
-        Ok(ArrowField::new_dict(
-            value.name,
-            value.field_type.try_into()?,
-            !value.required,
-            value.id as i64,
-            false,
-        ))
+        arrow_field(value.name, &value.field_type, !value.required, value.id)
     }
 }
 
@@ -54,39 +320,23 @@ impl TryFrom<types::Any> for ArrowDataType {
                 Ok(ArrowDataType::Struct(fields.into()))
             }
             super::Any::List(v) => {
-                let field = ArrowField::new_dict(
-                    "item",
-                    (*v.element_type).try_into()?,
-                    !v.element_required,
-                    v.element_id as i64,
-                    false,
-                );
+                let field =
+                    arrow_field("item", &v.element_type, !v.element_required, v.element_id)?;
 
                 Ok(ArrowDataType::List(Arc::new(field)))
             }
             super::Any::Map(v) => {
+                // Entries of arrow maps are never null.
                 let field = ArrowField::new(
                     "entries",
                     ArrowDataType::Struct(
                         vec![
-                            ArrowField::new_dict(
-                                "key",
-                                (*v.key_type).try_into()?,
-                                false,
-                                v.key_id as i64,
-                                false,
-                            ),
-                            ArrowField::new_dict(
-                                "value",
-                                (*v.value_type).try_into()?,
-                                !v.value_required,
-                                v.value_id as i64,
-                                false,
-                            ),
+                            arrow_field("key", &v.key_type, false, v.key_id)?,
+                            arrow_field("value", &v.value_type, !v.value_required, v.value_id)?,
                         ]
                         .into(),
                     ),
-                    v.value_required,
+                    false,
                 );
 
                 Ok(ArrowDataType::Map(Arc::new(field), false))
@@ -109,14 +359,21 @@ impl TryFrom<types::Primitive> for ArrowDataType {
                 Ok(ArrowDataType::Decimal128(precision, scale as i8))
             }
             types::Primitive::Date => Ok(ArrowDataType::Date32),
-            types::Primitive::Time => Ok(ArrowDataType::Time32(TimeUnit::Microsecond)),
+            types::Primitive::Time => Ok(ArrowDataType::Time64(TimeUnit::Microsecond)),
             types::Primitive::Timestamp => {
                 Ok(ArrowDataType::Timestamp(TimeUnit::Microsecond, None))
             }
-            types::Primitive::Timestampz => {
-                // Timestampz always stored as UTC
-                Ok(ArrowDataType::Timestamp(TimeUnit::Microsecond, None))
+            types::Primitive::Timestampz => Ok(ArrowDataType::Timestamp(
+                TimeUnit::Microsecond,
+                Some(UTC_TIMEZONE.into()),
+            )),
+            types::Primitive::TimestampNs => {
+                Ok(ArrowDataType::Timestamp(TimeUnit::Nanosecond, None))
             }
+            types::Primitive::TimestampzNs => Ok(ArrowDataType::Timestamp(
+                TimeUnit::Nanosecond,
+                Some(UTC_TIMEZONE.into()),
+            )),
             types::Primitive::String => Ok(ArrowDataType::Utf8),
             types::Primitive::Uuid => Ok(ArrowDataType::FixedSizeBinary(16)),
             types::Primitive::Fixed(i) => {
@@ -132,8 +389,140 @@ impl TryFrom<types::Primitive> for ArrowDataType {
     }
 }
 
+impl TryFrom<&ArrowSchema> for types::Schema {
+    type Error = Error;
+
+    fn try_from(value: &ArrowSchema) -> Result<Self, Self::Error> {
+        let fields = value
+            .fields()
+            .iter()
+            .map(|v| types::Field::try_from(v.as_ref()))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(types::Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields,
+        })
+    }
+}
+
+impl TryFrom<&ArrowField> for types::Field {
+    type Error = Error;
+
+    fn try_from(value: &ArrowField) -> Result<Self, Self::Error> {
+        Ok(types::Field {
+            id: field_id(value)?,
+            name: value.name().clone(),
+            required: !value.is_nullable(),
+            field_type: field_type(value)?,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        })
+    }
+}
+
+/// Returns the field id in the metadata of an arrow field.
+fn field_id(field: &ArrowField) -> crate::Result<i32> {
+    let id = field
+        .metadata()
+        .get(PARQUET_FIELD_ID_META_KEY)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Field id of arrow field {} not found", field.name()),
+            )
+        })?;
+    id.parse().map_err(|e| {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Invalid field id {id} of arrow field {}", field.name()),
+        )
+        .set_source(e)
+    })
+}
+
+/// Returns the iceberg type of an arrow field.
+fn field_type(field: &ArrowField) -> crate::Result<types::Any> {
+    let unsupported = || {
+        Error::new(
+            ErrorKind::IcebergFeatureUnsupported,
+            format!(
+                "Arrow type {} of field {} is not supported",
+                field.data_type(),
+                field.name()
+            ),
+        )
+    };
+
+    let primitive = match field.data_type() {
+        ArrowDataType::Boolean => types::Primitive::Boolean,
+        ArrowDataType::Int32 => types::Primitive::Int,
+        ArrowDataType::Int64 => types::Primitive::Long,
+        ArrowDataType::Float32 => types::Primitive::Float,
+        ArrowDataType::Float64 => types::Primitive::Double,
+        ArrowDataType::Decimal128(precision, scale) => types::Primitive::Decimal {
+            precision: *precision,
+            scale: u8::try_from(*scale).map_err(|_| unsupported())?,
+        },
+        ArrowDataType::Date32 => types::Primitive::Date,
+        ArrowDataType::Time64(TimeUnit::Microsecond) => types::Primitive::Time,
+        ArrowDataType::Timestamp(TimeUnit::Microsecond, None) => types::Primitive::Timestamp,
+        ArrowDataType::Timestamp(TimeUnit::Microsecond, Some(_)) => types::Primitive::Timestampz,
+        ArrowDataType::Timestamp(TimeUnit::Nanosecond, None) => types::Primitive::TimestampNs,
+        ArrowDataType::Timestamp(TimeUnit::Nanosecond, Some(_)) => types::Primitive::TimestampzNs,
+        ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => types::Primitive::String,
+        ArrowDataType::FixedSizeBinary(16)
+            if field
+                .metadata()
+                .get(EXTENSION_NAME_META_KEY)
+                .map(String::as_str)
+                == Some(UUID_EXTENSION_NAME) =>
+        {
+            types::Primitive::Uuid
+        }
+        ArrowDataType::FixedSizeBinary(size) => types::Primitive::Fixed(*size as u64),
+        ArrowDataType::Binary | ArrowDataType::LargeBinary => types::Primitive::Binary,
+        ArrowDataType::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|v| types::Field::try_from(v.as_ref()))
+                .collect::<crate::Result<Vec<_>>>()?;
+            return Ok(types::Any::Struct(types::Struct::new(fields).into()));
+        }
+        ArrowDataType::List(element) | ArrowDataType::LargeList(element) => {
+            return Ok(types::Any::List(types::List {
+                element_id: field_id(element)?,
+                element_required: !element.is_nullable(),
+                element_type: Box::new(field_type(element)?),
+            }));
+        }
+        ArrowDataType::Map(entries, _) => {
+            let ArrowDataType::Struct(entry_fields) = entries.data_type() else {
+                return Err(unsupported());
+            };
+            let [key, value] = entry_fields.iter().collect::<Vec<_>>()[..] else {
+                return Err(unsupported());
+            };
+            return Ok(types::Any::Map(types::Map {
+                key_id: field_id(key)?,
+                key_type: Box::new(field_type(key)?),
+                value_id: field_id(value)?,
+                value_required: !value.is_nullable(),
+                value_type: Box::new(field_type(value)?),
+            }));
+        }
+        _ => return Err(unsupported()),
+    };
+    Ok(types::Any::Primitive(primitive))
+}
+
 #[cfg(test)]
 mod tests {
+    use arrow::array::{Float32Array, Int32Array, TimestampMillisecondArray};
+    use arrow::datatypes::TimestampMicrosecondType;
+
     use super::*;
 
     #[test]
@@ -170,5 +559,286 @@ mod tests {
         assert_eq!(arrow_schema.fields()[0].data_type(), &ArrowDataType::Int64);
         assert_eq!(arrow_schema.fields()[1].name(), "data");
         assert_eq!(arrow_schema.fields()[1].data_type(), &ArrowDataType::Utf8);
+        assert_eq!(
+            arrow_schema.fields()[1]
+                .metadata()
+                .get(PARQUET_FIELD_ID_META_KEY)
+                .map(String::as_str),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn test_arrow_schema_round_trip() {
+        let field = |id, name: &str, required, ty| types::Field {
+            id,
+            name: name.to_string(),
+            required,
+            field_type: ty,
+            comment: None,
+            initial_default: None,
+            write_default: None,
+        };
+        let primitive = types::Any::Primitive;
+        let schema = types::Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "id", true, primitive(types::Primitive::Long)),
+                field(2, "uuid", false, primitive(types::Primitive::Uuid)),
+                field(3, "fixed", false, primitive(types::Primitive::Fixed(16))),
+                field(4, "time", false, primitive(types::Primitive::Time)),
+                field(
+                    5,
+                    "price",
+                    false,
+                    primitive(types::Primitive::Decimal {
+                        precision: 9,
+                        scale: 2,
+                    }),
+                ),
+                field(
+                    6,
+                    "location",
+                    false,
+                    types::Any::Struct(
+                        types::Struct::new(vec![
+                            field(7, "lat", true, primitive(types::Primitive::Double)),
+                            field(8, "long", true, primitive(types::Primitive::Double)),
+                        ])
+                        .into(),
+                    ),
+                ),
+                field(
+                    9,
+                    "tags",
+                    false,
+                    types::Any::List(types::List {
+                        element_id: 10,
+                        element_required: false,
+                        element_type: Box::new(primitive(types::Primitive::String)),
+                    }),
+                ),
+                field(
+                    11,
+                    "props",
+                    true,
+                    types::Any::Map(types::Map {
+                        key_id: 12,
+                        key_type: Box::new(primitive(types::Primitive::String)),
+                        value_id: 13,
+                        value_required: true,
+                        value_type: Box::new(types::Any::List(types::List {
+                            element_id: 14,
+                            element_required: true,
+                            element_type: Box::new(primitive(types::Primitive::Int)),
+                        })),
+                    }),
+                ),
+            ],
+        };
+
+        let arrow_schema = schema_to_arrow_schema(&schema).unwrap();
+        let ArrowDataType::Map(entries, _) = arrow_schema.field(7).data_type() else {
+            panic!("props must be map");
+        };
+        assert!(!entries.is_nullable());
+        assert_eq!(arrow_schema_to_schema(&arrow_schema).unwrap(), schema);
+
+        // Fields without field ids can't be converted.
+        let arrow_schema =
+            ArrowSchema::new(vec![ArrowField::new("id", ArrowDataType::Int64, false)]);
+        assert!(arrow_schema_to_schema(&arrow_schema).is_err());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let utc = Some(UTC_TIMEZONE.into());
+        for (ty, arrow_type) in [
+            (
+                types::Primitive::Timestamp,
+                ArrowDataType::Timestamp(TimeUnit::Microsecond, None),
+            ),
+            (
+                types::Primitive::Timestampz,
+                ArrowDataType::Timestamp(TimeUnit::Microsecond, utc.clone()),
+            ),
+            (
+                types::Primitive::TimestampNs,
+                ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+            ),
+            (
+                types::Primitive::TimestampzNs,
+                ArrowDataType::Timestamp(TimeUnit::Nanosecond, utc.clone()),
+            ),
+        ] {
+            assert_eq!(ArrowDataType::try_from(ty).unwrap(), arrow_type);
+        }
+        // Timestamps in any time zone are instants.
+        let field = ArrowField::new(
+            "ts",
+            ArrowDataType::Timestamp(TimeUnit::Microsecond, Some("Asia/Shanghai".into())),
+            true,
+        )
+        .with_metadata(HashMap::from([(
+            PARQUET_FIELD_ID_META_KEY.to_string(),
+            "1".to_string(),
+        )]));
+        assert_eq!(
+            types::Field::try_from(&field).unwrap().field_type,
+            types::Any::Primitive(types::Primitive::Timestampz)
+        );
+
+        let schema = ArrowSchema::new(vec![
+            ArrowField::new(
+                "ts",
+                ArrowDataType::Timestamp(TimeUnit::Microsecond, None),
+                true,
+            ),
+            ArrowField::new(
+                "tstz",
+                ArrowDataType::Timestamp(TimeUnit::Nanosecond, utc.clone()),
+                true,
+            ),
+        ]);
+        let batch = RecordBatch::try_from_iter([
+            (
+                "ts",
+                Arc::new(
+                    TimestampNanosecondArray::from(vec![Some(-1), Some(1_999), None])
+                        .with_timezone("+08:00"),
+                ) as ArrayRef,
+            ),
+            (
+                "tstz",
+                Arc::new(TimestampMillisecondArray::from(vec![
+                    Some(-1),
+                    Some(1),
+                    None,
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let batch = cast_timestamps(&batch, &schema).unwrap();
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            schema.field(0).data_type()
+        );
+        assert_eq!(
+            batch.schema().field(1).data_type(),
+            schema.field(1).data_type()
+        );
+        let values = |i: usize| -> Vec<Option<i64>> {
+            cast(batch.column(i), &ArrowDataType::Int64)
+                .unwrap()
+                .as_primitive::<Int64Type>()
+                .iter()
+                .collect()
+        };
+        assert_eq!(values(0), vec![Some(-1), Some(1), None]);
+        assert_eq!(values(1), vec![Some(-1_000_000), Some(1_000_000), None]);
+
+        // Timestamps overflowing nanoseconds can't be casted.
+        let batch = RecordBatch::try_from_iter([(
+            "tstz",
+            Arc::new(TimestampMicrosecondArray::from(vec![i64::MAX])) as ArrayRef,
+        )])
+        .unwrap();
+        assert!(cast_timestamps(&batch, &schema).is_err());
+    }
+
+    #[test]
+    fn test_coerce_batch() {
+        let field =
+            |id: i32, name: &str, field_type: types::Primitive, required: bool| types::Field {
+                name: name.to_string(),
+                field_type: types::Any::Primitive(field_type),
+                id,
+                required,
+                comment: None,
+                initial_default: None,
+                write_default: None,
+            };
+        let schema = types::Schema {
+            schema_id: 0,
+            identifier_field_ids: None,
+            fields: vec![
+                field(1, "id", types::Primitive::Long, true),
+                field(2, "score", types::Primitive::Double, false),
+                field(3, "data", types::Primitive::String, false),
+                field(4, "ts", types::Primitive::Timestamp, false),
+            ],
+        };
+        let schema = Arc::new(schema_to_arrow_schema(&schema).unwrap());
+        let with_id = |field: ArrowField, id: &str| {
+            field.with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+
+        // `score` and `ts` are matched by their field ids, `id` by its name.
+        let score = with_id(
+            ArrowField::new("renamed", ArrowDataType::Float32, true),
+            "2",
+        );
+        let ts = with_id(
+            ArrowField::new(
+                "event_time",
+                ArrowDataType::Timestamp(TimeUnit::Millisecond, None),
+                true,
+            ),
+            "4",
+        );
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                score,
+                ArrowField::new("id", ArrowDataType::Int32, false),
+                ts,
+            ])),
+            vec![
+                Arc::new(Float32Array::from(vec![0.5, 1.5])),
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(TimestampMillisecondArray::from(vec![1, 2])),
+            ],
+        )
+        .unwrap();
+        let batch = coerce_batch(&batch, &schema).unwrap();
+        assert_eq!(batch.schema(), schema);
+        assert_eq!(
+            batch
+                .column(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![1, 2]
+        );
+        assert_eq!(batch.column(2).null_count(), 2);
+        assert_eq!(
+            batch
+                .column(3)
+                .as_primitive::<TimestampMicrosecondType>()
+                .values()
+                .to_vec(),
+            vec![1000, 2000]
+        );
+
+        let ids = || Arc::new(Int64Array::from(vec![1])) as ArrayRef;
+        let err = |batch: RecordBatch| coerce_batch(&batch, &schema).unwrap_err().to_string();
+        let batch = RecordBatch::try_from_iter([("id", ids()), ("unknown", ids())]).unwrap();
+        assert!(err(batch).contains("Column unknown is not in the table schema"));
+        let batch = RecordBatch::try_from_iter([("score", ids())]).unwrap();
+        assert!(err(batch).contains("Required column id is missing"));
+        let batch = RecordBatch::try_from_iter([("id", ids()), ("data", ids())]).unwrap();
+        assert!(err(batch).contains("Column data of type Int64 can't be written as type Utf8"));
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                ArrowField::new("id", ArrowDataType::Int64, false),
+                with_id(ArrowField::new("key", ArrowDataType::Int64, false), "1"),
+            ])),
+            vec![ids(), ids()],
+        )
+        .unwrap();
+        assert!(err(batch).contains("Columns id and key are both written to field id"));
     }
 }
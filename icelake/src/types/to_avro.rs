@@ -1,16 +1,24 @@
 //! Avro data types related functions.
 
 use crate::error::Result;
-use crate::types::in_memory::{Any, Field, Primitive, Schema};
+use crate::types::decode_bound;
+use crate::types::expression::{decimal_bytes, timestamp_nanos};
+use crate::types::in_memory::{
+    Any, AnyValue, Field, Primitive, PrimitiveValue, Schema, Struct, StructValue,
+    StructValueBuilder,
+};
 use crate::{Error, ErrorKind};
 use apache_avro::schema::{
-    Name, RecordField as AvroRecordField, RecordFieldOrder, RecordSchema as AvroRecordSchema,
-    UnionSchema,
+    DecimalSchema, FixedSchema, Name, RecordField as AvroRecordField, RecordFieldOrder,
+    RecordSchema as AvroRecordSchema, UnionSchema,
 };
+use apache_avro::types::Value as AvroValue;
 use apache_avro::Schema as AvroSchema;
+use chrono::{Datelike, Timelike};
 use serde_json::{Number, Value as JsonValue};
 use std::collections::BTreeMap;
 use std::iter::Iterator;
+use std::sync::Arc;
 
 pub fn to_avro_schema(value: &Schema, name: Option<&str>) -> Result<AvroSchema> {
     let avro_fields: Vec<AvroRecordField> = value
@@ -47,6 +55,21 @@ impl<'a> TryFrom<&'a Field> for AvroRecordField {
             Any::List(_list) => AvroSchema::try_from(&value.field_type)?,
             _ => {
                 let mut avro_schema = AvroSchema::try_from(&value.field_type)?;
+                // Fixed types are named types in avro, names of them are
+                // suffixed by field ids so that they're never defined twice.
+                match &mut avro_schema {
+                    AvroSchema::Fixed(fixed) => {
+                        fixed.name =
+                            Name::from(format!("{}_{}", fixed.name.name, value.id).as_str())
+                    }
+                    AvroSchema::Decimal(DecimalSchema { inner, .. }) => {
+                        if let AvroSchema::Fixed(fixed) = inner.as_mut() {
+                            fixed.name =
+                                Name::from(format!("{}_{}", fixed.name.name, value.id).as_str())
+                        }
+                    }
+                    _ => {}
+                }
                 if !value.required {
                     avro_schema =
                         AvroSchema::Union(UnionSchema::new(vec![AvroSchema::Null, avro_schema])?);
@@ -83,18 +106,20 @@ impl<'a> TryFrom<&'a Any> for AvroSchema {
                 Primitive::Time => AvroSchema::TimeMicros,
                 Primitive::Timestamp => AvroSchema::TimestampMicros,
                 Primitive::Timestampz => AvroSchema::TimestampMicros,
+                // Logical type `timestamp-nanos` is not supported by avro yet.
+                Primitive::TimestampNs | Primitive::TimestampzNs => AvroSchema::Long,
                 Primitive::String => AvroSchema::String,
-                Primitive::Uuid => AvroSchema::Uuid,
+                Primitive::Uuid => avro_fixed_schema("uuid_fixed", 16),
+                Primitive::Fixed(len) => avro_fixed_schema(format!("fixed_{len}"), *len as usize),
                 Primitive::Binary => AvroSchema::Bytes,
-                _ => {
-                    return Err(Error::new(
-                        ErrorKind::IcebergFeatureUnsupported,
-                        format!(
-                            "Unable to convert iceberg data type {:?} to avro type",
-                            data_type
-                        ),
-                    ))
-                }
+                Primitive::Decimal { precision, scale } => AvroSchema::Decimal(DecimalSchema {
+                    precision: *precision as usize,
+                    scale: *scale as usize,
+                    inner: Box::new(avro_fixed_schema(
+                        format!("decimal_{precision}_{scale}"),
+                        decimal_required_bytes(*precision),
+                    )),
+                }),
             },
 
             Any::Map(map) => {
@@ -194,6 +219,150 @@ fn avro_record_schema(
     }
 }
 
+fn avro_fixed_schema(name: impl Into<String>, size: usize) -> AvroSchema {
+    AvroSchema::Fixed(FixedSchema {
+        name: Name::from(name.into().as_str()),
+        aliases: None,
+        doc: None,
+        size,
+        attributes: BTreeMap::default(),
+    })
+}
+
+/// Minimum number of bytes to store unscaled values of decimals of
+/// `precision` in two's complement.
+fn decimal_required_bytes(precision: u8) -> usize {
+    ((precision as f64 * 10f64.log2() + 1.0) / 8.0).ceil() as usize
+}
+
+/// Convert partition values to an avro record of `partition_type`.
+pub(crate) fn partition_to_avro(
+    partition: &StructValue,
+    partition_type: &Struct,
+) -> Result<AvroValue> {
+    let mut values = partition.iter();
+    let mut fields = Vec::with_capacity(partition_type.len());
+    for field in partition_type.fields() {
+        let value = match values.next() {
+            Some((_, Some(AnyValue::Primitive(value)), _)) => {
+                let Any::Primitive(ty) = &field.field_type else {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Partition field {} is not primitive", field.name),
+                    ));
+                };
+                AvroValue::Union(1, Box::new(primitive_to_avro(value, ty)?))
+            }
+            Some((_, None, _)) | None => AvroValue::Union(0, Box::new(AvroValue::Null)),
+            Some((_, Some(value), _)) => {
+                return Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Partition value {value:?} is not primitive"),
+                ))
+            }
+        };
+        fields.push((field.name.clone(), value));
+    }
+    Ok(AvroValue::Record(fields))
+}
+
+fn primitive_to_avro(value: &PrimitiveValue, ty: &Primitive) -> Result<AvroValue> {
+    let value = match (value, ty) {
+        (PrimitiveValue::Boolean(v), _) => AvroValue::Boolean(*v),
+        (PrimitiveValue::Int(v), _) => AvroValue::Int(*v),
+        (PrimitiveValue::Long(v), _) => AvroValue::Long(*v),
+        (PrimitiveValue::Float(v), _) => AvroValue::Float(v.0),
+        (PrimitiveValue::Double(v), _) => AvroValue::Double(v.0),
+        (PrimitiveValue::Decimal(v), Primitive::Decimal { scale, .. }) => {
+            let mut v = *v;
+            v.rescale(*scale as u32);
+            AvroValue::Decimal(decimal_bytes(v.mantissa()).into())
+        }
+        (PrimitiveValue::Date(v), _) => AvroValue::Date(v.num_days_from_ce() - 719163),
+        (PrimitiveValue::Time(v), _) => AvroValue::TimeMicros(
+            v.num_seconds_from_midnight() as i64 * 1_000_000 + v.nanosecond() as i64 / 1000,
+        ),
+        (PrimitiveValue::Timestamp(v), _) => AvroValue::TimestampMicros(v.timestamp_micros()),
+        (PrimitiveValue::Timestampz(v), _) => AvroValue::TimestampMicros(v.timestamp_micros()),
+        (PrimitiveValue::TimestampNs(v), _) => AvroValue::Long(timestamp_nanos(v)),
+        (PrimitiveValue::TimestampzNs(v), _) => AvroValue::Long(timestamp_nanos(&v.naive_utc())),
+        (PrimitiveValue::String(v), _) => AvroValue::String(v.clone()),
+        (PrimitiveValue::Uuid(v), _) => AvroValue::Fixed(16, v.as_bytes().to_vec()),
+        (PrimitiveValue::Fixed(v), _) => AvroValue::Fixed(v.len(), v.clone()),
+        (PrimitiveValue::Binary(v), _) => AvroValue::Bytes(v.clone()),
+        (value, ty) => {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Value {value:?} is not of type {ty:?}"),
+            ))
+        }
+    };
+    Ok(value)
+}
+
+/// Parse partition values from an avro record of `partition_type`.
+///
+/// Fields are matched by position, since names of them might be sanitized
+/// by writers.
+pub(crate) fn partition_from_avro(
+    value: &AvroValue,
+    partition_type: Arc<Struct>,
+) -> Result<StructValue> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Partition {value:?} is not of type {partition_type:?}"),
+        )
+    };
+    let AvroValue::Record(values) = value else {
+        return Err(invalid());
+    };
+    if values.len() != partition_type.len() {
+        return Err(invalid());
+    }
+    let mut builder = StructValueBuilder::new(partition_type.clone());
+    for (field, (_, value)) in partition_type.fields().iter().zip(values) {
+        let Any::Primitive(ty) = &field.field_type else {
+            return Err(invalid());
+        };
+        let value = match value {
+            AvroValue::Union(_, v) => v.as_ref(),
+            v => v,
+        };
+        let value = match value {
+            AvroValue::Null => None,
+            value => Some(AnyValue::Primitive(
+                primitive_from_avro(value, ty).ok_or_else(invalid)?,
+            )),
+        };
+        builder.add_field(field.id, value)?;
+    }
+    builder.build()
+}
+
+/// Avro values are converted by the binary single-value serialization of
+/// them.
+fn primitive_from_avro(value: &AvroValue, ty: &Primitive) -> Option<PrimitiveValue> {
+    let bytes = match value {
+        AvroValue::Boolean(v) => vec![*v as u8],
+        AvroValue::Int(v) | AvroValue::Date(v) => v.to_le_bytes().to_vec(),
+        AvroValue::Long(v) | AvroValue::TimeMicros(v) | AvroValue::TimestampMicros(v) => {
+            v.to_le_bytes().to_vec()
+        }
+        AvroValue::Float(v) => v.to_le_bytes().to_vec(),
+        AvroValue::Double(v) => v.to_le_bytes().to_vec(),
+        AvroValue::String(v) if *ty == Primitive::Uuid => {
+            v.parse::<uuid::Uuid>().ok()?.as_bytes().to_vec()
+        }
+        AvroValue::String(v) => v.as_bytes().to_vec(),
+        AvroValue::Uuid(v) => v.as_bytes().to_vec(),
+        AvroValue::Bytes(v) | AvroValue::Fixed(_, v) => v.clone(),
+        AvroValue::Decimal(v) => Vec::<u8>::try_from(v).ok()?,
+        _ => return None,
+    };
+    decode_bound(ty, &bytes)
+}
+
 fn is_avro_option(avro_schema: &AvroSchema) -> bool {
     match avro_schema {
         AvroSchema::Union(u) => u.variants().len() == 2 && u.is_nullable(),
@@ -310,8 +479,7 @@ mod tests {
             "fields": [
                 {
                     "name": "c",
-                    "type": "string",
-                    "logicalType": "uuid",
+                    "type": { "type": "fixed", "name": "uuid_fixed_3", "size": 16 },
                     "doc": "comment_c",
                     "order": "ignore"
                 },
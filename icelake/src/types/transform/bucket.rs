@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, BinaryArray, Date32Array, Decimal128Array, FixedSizeBinaryArray, Int32Array,
+    Int64Array, LargeBinaryArray, LargeStringArray, StringArray, Time64MicrosecondArray,
+    TimestampMicrosecondArray, TimestampNanosecondArray,
+};
+use arrow::datatypes::{DataType, Int32Type, TimeUnit};
+use chrono::{Datelike, NaiveTime};
+
+use super::unsupported_input;
+use crate::types::{PrimitiveValue, Transform, TransformFunction};
+use crate::{Error, ErrorKind, Result};
+
+/// Bucket transform hashes values by 32-bit murmur3 into `n` buckets.
+pub struct Bucket {
+    n: i32,
+}
+
+impl Bucket {
+    pub fn new(n: i32) -> Self {
+        Self { n }
+    }
+
+    fn bucket(&self, hash: i32) -> i32 {
+        (hash & i32::MAX) % self.n
+    }
+}
+
+impl TransformFunction for Bucket {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        let unsupported = || unsupported_input(Transform::Bucket(self.n), &input);
+        // Values of primitive arrays are hashed by the `unary` kernel, and
+        // values of byte arrays are hashed regardless of validity, so that
+        // nulls are kept by the validity buffer instead of checked per row.
+        macro_rules! bucket_primitive {
+            ($t:ty, $hash:expr) => {{
+                let hash = $hash;
+                input
+                    .as_any()
+                    .downcast_ref::<$t>()
+                    .ok_or_else(unsupported)?
+                    .unary::<_, Int32Type>(|v| self.bucket(hash(v)))
+            }};
+        }
+        macro_rules! bucket_bytes {
+            ($t:ty) => {{
+                let array = input
+                    .as_any()
+                    .downcast_ref::<$t>()
+                    .ok_or_else(unsupported)?;
+                let values: Vec<i32> = (0..array.len())
+                    .map(|i| self.bucket(murmur3_32(array.value(i).as_ref())))
+                    .collect();
+                Int32Array::new(values.into(), array.nulls().cloned())
+            }};
+        }
+
+        let result = match input.data_type() {
+            DataType::Int32 => bucket_primitive!(Int32Array, |v: i32| hash_long(v as i64)),
+            DataType::Int64 => bucket_primitive!(Int64Array, hash_long),
+            DataType::Decimal128(_, _) => bucket_primitive!(Decimal128Array, hash_decimal),
+            DataType::Date32 => bucket_primitive!(Date32Array, |v: i32| hash_long(v as i64)),
+            DataType::Time64(TimeUnit::Microsecond) => {
+                bucket_primitive!(Time64MicrosecondArray, hash_long)
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                bucket_primitive!(TimestampMicrosecondArray, hash_long)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                bucket_primitive!(TimestampNanosecondArray, |v: i64| hash_long(
+                    v.div_euclid(1000)
+                ))
+            }
+            DataType::Utf8 => bucket_bytes!(StringArray),
+            DataType::LargeUtf8 => bucket_bytes!(LargeStringArray),
+            DataType::FixedSizeBinary(_) => bucket_bytes!(FixedSizeBinaryArray),
+            DataType::Binary => bucket_bytes!(BinaryArray),
+            DataType::LargeBinary => bucket_bytes!(LargeBinaryArray),
+            _ => return Err(unsupported()),
+        };
+        Ok(Arc::new(result))
+    }
+}
+
+/// Returns the bucket of a value in `n` buckets, the same as the bucket
+/// transform of iceberg, so that data partitioned by engines can be
+/// co-located with buckets of tables.
+///
+/// Values are hashed by 32-bit murmur3 as defined in the appendix B of
+/// iceberg spec:
+///
+/// - Ints, longs, dates, times and timestamps are hashed as longs, and
+///   timestamps of nanoseconds are hashed by their microseconds.
+/// - Decimals are hashed by the minimum bytes of their unscaled values, so
+///   they must be of the scale of their column.
+/// - Strings are hashed by their UTF-8 bytes, and uuids by their 16 bytes in
+///   big-endian.
+///
+/// Returns error if `n` is not positive, or values of the type can't be
+/// bucketed, like booleans and floats.
+///
+/// # Examples
+///
+/// ```
+/// use icelake::types::{bucket_hash, PrimitiveValue};
+///
+/// let bucket = bucket_hash(&PrimitiveValue::Long(34), 16).unwrap();
+/// assert_eq!(bucket, 2017239379 % 16);
+/// ```
+pub fn bucket_hash(value: &PrimitiveValue, n: i32) -> Result<i32> {
+    if n <= 0 {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Number of buckets must be positive, got {n}"),
+        ));
+    }
+    let hash = match value {
+        PrimitiveValue::Int(v) => hash_long(*v as i64),
+        PrimitiveValue::Long(v) => hash_long(*v),
+        PrimitiveValue::Decimal(v) => hash_decimal(v.mantissa()),
+        // Days from unix epoch, which is day 719163 from CE.
+        PrimitiveValue::Date(v) => hash_long(v.num_days_from_ce() as i64 - 719163),
+        PrimitiveValue::Time(v) => hash_long(
+            v.signed_duration_since(NaiveTime::MIN)
+                .num_microseconds()
+                .expect("microseconds of a day can't overflow"),
+        ),
+        PrimitiveValue::Timestamp(v) => hash_long(v.timestamp_micros()),
+        PrimitiveValue::Timestampz(v) => hash_long(v.timestamp_micros()),
+        PrimitiveValue::TimestampNs(v) => hash_long(v.timestamp_micros()),
+        PrimitiveValue::TimestampzNs(v) => hash_long(v.timestamp_micros()),
+        PrimitiveValue::String(v) => murmur3_32(v.as_bytes()),
+        PrimitiveValue::Uuid(v) => murmur3_32(v.as_bytes()),
+        PrimitiveValue::Fixed(v) | PrimitiveValue::Binary(v) => murmur3_32(v),
+        PrimitiveValue::Boolean(_) | PrimitiveValue::Float(_) | PrimitiveValue::Double(_) => {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Can't bucket value {value:?}"),
+            ))
+        }
+    };
+    Ok(Bucket::new(n).bucket(hash))
+}
+
+fn hash_long(v: i64) -> i32 {
+    murmur3_32(&v.to_le_bytes())
+}
+
+/// Decimals are hashed by the minimum bytes of big-endian two's complement
+/// of their unscaled values.
+fn hash_decimal(v: i128) -> i32 {
+    let bytes = v.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    murmur3_32(&bytes[start..])
+}
+
+/// 32-bit murmur3 of x86 with seed 0.
+pub(crate) fn murmur3_32(data: &[u8]) -> i32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+
+    fn mix_k(k: u32) -> u32 {
+        k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2)
+    }
+
+    let mut h: u32 = 0;
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let k = u32::from_le_bytes(chunk.try_into().expect("chunk must be 4 bytes"));
+        h ^= mix_k(k);
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe6546b64);
+    }
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .enumerate()
+            .fold(0_u32, |k, (i, b)| k ^ ((*b as u32) << (8 * i)));
+        h ^= mix_k(k);
+    }
+
+    h ^= data.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2ae35);
+    h ^= h >> 16;
+    h as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_hash_reference_values() {
+        // Reference values from the appendix B of iceberg spec.
+        let date = NaiveDate::from_ymd_opt(2017, 11, 16).unwrap();
+        let timestamp = date.and_hms_opt(22, 31, 8).unwrap();
+        let cases = [
+            (PrimitiveValue::Int(34), 2017239379),
+            (PrimitiveValue::Long(34), 2017239379),
+            (PrimitiveValue::Decimal(Decimal::new(1420, 2)), -500754589),
+            (PrimitiveValue::Date(date), -653330422),
+            (
+                PrimitiveValue::Time(NaiveTime::from_hms_opt(22, 31, 8).unwrap()),
+                -662762989,
+            ),
+            (PrimitiveValue::Timestamp(timestamp), -2047944441),
+            (
+                PrimitiveValue::Timestampz(
+                    DateTime::parse_from_rfc3339("2017-11-16T14:31:08-08:00")
+                        .unwrap()
+                        .into(),
+                ),
+                -2047944441,
+            ),
+            // Nanoseconds are hashed as microseconds.
+            (
+                PrimitiveValue::TimestampNs(timestamp + chrono::Duration::nanoseconds(999)),
+                -2047944441,
+            ),
+            (
+                PrimitiveValue::TimestampzNs(
+                    DateTime::parse_from_rfc3339("2017-11-16T14:31:08.000000999-08:00")
+                        .unwrap()
+                        .into(),
+                ),
+                -2047944441,
+            ),
+            (PrimitiveValue::String("iceberg".to_string()), 1210000089),
+            (
+                PrimitiveValue::Uuid(
+                    Uuid::parse_str("f79c3e09-677c-4bbd-a479-3f349cb785e7").unwrap(),
+                ),
+                1488055340,
+            ),
+            (PrimitiveValue::Fixed(vec![0, 1, 2, 3]), -188683207),
+            (PrimitiveValue::Binary(vec![0, 1, 2, 3]), -188683207),
+        ];
+        for (value, hash) in cases {
+            for n in [1, 16, 100, i32::MAX] {
+                assert_eq!(
+                    bucket_hash(&value, n).unwrap(),
+                    (hash & i32::MAX) % n,
+                    "{value:?}"
+                );
+            }
+        }
+
+        assert!(bucket_hash(&PrimitiveValue::Boolean(true), 16).is_err());
+        assert!(bucket_hash(&PrimitiveValue::Double(1.0.into()), 16).is_err());
+        assert!(bucket_hash(&PrimitiveValue::Long(34), 0).is_err());
+    }
+
+    #[test]
+    fn test_bucket_transform() {
+        let input = Arc::new(Int32Array::from(vec![Some(34), None])) as ArrayRef;
+        let result = Bucket::new(16).transform(input).unwrap();
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(result.value(0), 2017239379 % 16);
+        assert!(result.is_null(1));
+
+        let input = Arc::new(StringArray::from(vec!["iceberg"])) as ArrayRef;
+        let result = Bucket::new(100).transform(input).unwrap();
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(result.value(0), 1210000089 % 100);
+
+        // Buckets of arrays are the same as buckets of values.
+        let input = Arc::new(
+            Decimal128Array::from(vec![1420, -1420, 0])
+                .with_precision_and_scale(9, 2)
+                .unwrap(),
+        ) as ArrayRef;
+        let result = Bucket::new(7).transform(input).unwrap();
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        for (i, v) in [1420, -1420, 0].into_iter().enumerate() {
+            let value = PrimitiveValue::Decimal(Decimal::new(v, 2));
+            assert_eq!(result.value(i), bucket_hash(&value, 7).unwrap());
+        }
+
+        let timestamp = NaiveDate::from_ymd_opt(1969, 12, 31)
+            .unwrap()
+            .and_hms_nano_opt(23, 59, 59, 999_999_999)
+            .unwrap();
+        let input = Arc::new(TimestampNanosecondArray::from(vec![-1])) as ArrayRef;
+        let result = Bucket::new(7).transform(input).unwrap();
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        let value = PrimitiveValue::TimestampNs(timestamp);
+        assert_eq!(result.value(0), bucket_hash(&value, 7).unwrap());
+
+        let input = Arc::new(arrow::array::BooleanArray::from(vec![true])) as ArrayRef;
+        assert!(Bucket::new(16).transform(input).is_err());
+    }
+}
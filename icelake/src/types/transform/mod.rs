@@ -1,13 +1,22 @@
-use super::Transform;
+use super::{PrimitiveValue, Transform};
+use crate::Result;
 use arrow::array::ArrayRef;
+mod bucket;
+pub use bucket::bucket_hash;
+pub(crate) use bucket::murmur3_32;
 mod identity;
+mod temporal;
+mod truncate;
+mod void;
 
 /// TransformFunction is a trait that defines the interface of a transform function.
-pub trait TransformFunction {
+pub trait TransformFunction: Send + Sync {
     /// transform will take an input array and transform it into a new array.
     /// The implementation of this function will need to check and downcast the input to specific
     /// type.
-    fn transform(&self, input: ArrayRef) -> ArrayRef;
+    ///
+    /// Returns error if the transform doesn't support the type of input.
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef>;
 }
 
 /// BoxedTransformFunction is a boxed trait object of TransformFunction.
@@ -17,6 +26,42 @@ pub type BoxedTransformFunction = Box<dyn TransformFunction>;
 pub fn create_transform_function(transform: Transform) -> BoxedTransformFunction {
     match transform {
         Transform::Identity => Box::new(identity::Identity {}),
-        _ => todo!(),
+        Transform::Bucket(n) => Box::new(bucket::Bucket::new(n)),
+        Transform::Truncate(width) => Box::new(truncate::Truncate::new(width)),
+        Transform::Year => Box::new(temporal::Year {}),
+        Transform::Month => Box::new(temporal::Month {}),
+        Transform::Day => Box::new(temporal::Day {}),
+        Transform::Hour => Box::new(temporal::Hour {}),
+        Transform::Void => Box::new(void::Void {}),
     }
 }
+
+/// Apply the transform on a single value, like literals of predicates
+/// projected on partition values.
+///
+/// Returns `None` if the transform doesn't support the type of value, or
+/// the result is always null like the void transform.
+pub(crate) fn transform_value(
+    transform: Transform,
+    value: &PrimitiveValue,
+) -> Option<PrimitiveValue> {
+    match transform {
+        Transform::Identity => Some(value.clone()),
+        Transform::Bucket(n) => bucket_hash(value, n).ok().map(PrimitiveValue::Int),
+        Transform::Truncate(width) => truncate::truncate_value(value, width),
+        Transform::Year | Transform::Month | Transform::Day | Transform::Hour => {
+            temporal::temporal_value(transform, value).map(PrimitiveValue::Int)
+        }
+        Transform::Void => None,
+    }
+}
+
+fn unsupported_input(transform: Transform, input: &ArrayRef) -> crate::Error {
+    crate::Error::new(
+        crate::ErrorKind::IcebergDataInvalid,
+        format!(
+            "Transform {transform} doesn't support input type {}",
+            input.data_type()
+        ),
+    )
+}
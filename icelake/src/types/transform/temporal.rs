@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, Date32Array, Int32Array, TimestampMicrosecondArray, TimestampNanosecondArray,
+};
+use arrow::datatypes::{DataType, TimeUnit};
+use chrono::Datelike;
+
+use super::unsupported_input;
+use crate::types::{PrimitiveValue, Transform, TransformFunction};
+use crate::Result;
+
+const MICROS_PER_HOUR: i64 = 3_600_000_000;
+const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+
+/// Year transform extracts years from 1970 of dates or timestamps.
+pub struct Year {}
+
+/// Month transform extracts months from 1970-01 of dates or timestamps.
+pub struct Month {}
+
+/// Day transform extracts days from 1970-01-01 of dates or timestamps.
+pub struct Day {}
+
+/// Hour transform extracts hours from 1970-01-01 00:00:00 of timestamps.
+pub struct Hour {}
+
+fn days_from_micros(micros: i64) -> i32 {
+    micros.div_euclid(MICROS_PER_DAY) as i32
+}
+
+/// Returns the year and zero based month of days from epoch.
+///
+/// This is the branchless conversion of proleptic gregorian calendar from
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>,
+/// which is infallible and much cheaper than building `chrono` dates per
+/// value in kernels.
+fn year_month0_from_days(days: i32) -> (i32, i32) {
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    // Months starting from March.
+    let month0 = if mp < 10 { mp + 2 } else { mp - 10 };
+    let year = yoe + era * 400 + i64::from(month0 < 2);
+    (year as i32, month0 as i32)
+}
+
+/// Returns years from 1970 of days from epoch.
+fn years_from_days(days: i32) -> i32 {
+    year_month0_from_days(days).0 - 1970
+}
+
+/// Returns months from 1970-01 of days from epoch.
+fn months_from_days(days: i32) -> i32 {
+    let (year, month0) = year_month0_from_days(days);
+    (year - 1970) * 12 + month0
+}
+
+/// Returns the result of a temporal transform on a date or timestamp
+/// value, like [`transform_temporal`] on arrays.
+///
+/// Returns `None` if the transform doesn't support the type of value.
+pub(super) fn temporal_value(transform: Transform, value: &PrimitiveValue) -> Option<i32> {
+    let micros = match value {
+        // Days from unix epoch, which is day 719163 from CE.
+        PrimitiveValue::Date(v) => {
+            let days = v.num_days_from_ce() - 719163;
+            return match transform {
+                Transform::Year => Some(years_from_days(days)),
+                Transform::Month => Some(months_from_days(days)),
+                Transform::Day => Some(days),
+                _ => None,
+            };
+        }
+        PrimitiveValue::Timestamp(v) | PrimitiveValue::TimestampNs(v) => v.timestamp_micros(),
+        PrimitiveValue::Timestampz(v) | PrimitiveValue::TimestampzNs(v) => v.timestamp_micros(),
+        _ => return None,
+    };
+    let days = days_from_micros(micros);
+    match transform {
+        Transform::Year => Some(years_from_days(days)),
+        Transform::Month => Some(months_from_days(days)),
+        Transform::Day => Some(days),
+        Transform::Hour => Some(micros.div_euclid(MICROS_PER_HOUR) as i32),
+        _ => None,
+    }
+}
+
+/// Apply `days` on days from epoch of dates, or `micros` on microseconds
+/// from epoch of timestamps, by the `unary` kernel. Days of timestamps are
+/// passed to `days` if `micros` is not given.
+///
+/// Timestamps of nanoseconds are floored to microseconds, and timestamps
+/// with time zones are transformed in UTC.
+fn transform_temporal(
+    transform: Transform,
+    input: &ArrayRef,
+    days: impl Fn(i32) -> i32,
+    micros: Option<&dyn Fn(i64) -> i32>,
+) -> Result<ArrayRef> {
+    let result: Int32Array = match input.data_type() {
+        DataType::Date32 => input
+            .as_any()
+            .downcast_ref::<Date32Array>()
+            .expect("type must be date32")
+            .unary(days),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            let array = input
+                .as_any()
+                .downcast_ref::<TimestampMicrosecondArray>()
+                .expect("type must be timestamp");
+            match micros {
+                Some(f) => array.unary(f),
+                None => array.unary(|v| days(days_from_micros(v))),
+            }
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            let array = input
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+                .expect("type must be timestamp");
+            match micros {
+                Some(f) => array.unary(|v| f(v.div_euclid(1000))),
+                None => array.unary(|v| days(days_from_micros(v.div_euclid(1000)))),
+            }
+        }
+        _ => return Err(unsupported_input(transform, input)),
+    };
+    Ok(Arc::new(result))
+}
+
+impl TransformFunction for Year {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        transform_temporal(Transform::Year, &input, years_from_days, None)
+    }
+}
+
+impl TransformFunction for Month {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        transform_temporal(Transform::Month, &input, months_from_days, None)
+    }
+}
+
+impl TransformFunction for Day {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        transform_temporal(Transform::Day, &input, |v| v, Some(&days_from_micros))
+    }
+}
+
+impl TransformFunction for Hour {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        if input.data_type() == &DataType::Date32 {
+            return Err(unsupported_input(Transform::Hour, &input));
+        }
+        transform_temporal(
+            Transform::Hour,
+            &input,
+            |v| v,
+            Some(&|v: i64| v.div_euclid(MICROS_PER_HOUR) as i32),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, NaiveDate, NaiveDateTime};
+
+    use super::*;
+
+    fn micros(ts: &str) -> i64 {
+        NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .timestamp_micros()
+    }
+
+    fn transform(f: &dyn TransformFunction, input: ArrayRef) -> Vec<Option<i32>> {
+        let result = f.transform(input).unwrap();
+        result
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap()
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_temporal_transforms() {
+        // 2017-11-16 and 1969-12-31
+        let dates = Arc::new(Date32Array::from(vec![Some(17486), Some(-1), None])) as ArrayRef;
+        assert_eq!(
+            transform(&Year {}, dates.clone()),
+            vec![Some(47), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Month {}, dates.clone()),
+            vec![Some(574), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Day {}, dates.clone()),
+            vec![Some(17486), Some(-1), None]
+        );
+        assert!(Hour {}.transform(dates).is_err());
+
+        let timestamps = Arc::new(TimestampMicrosecondArray::from(vec![
+            Some(micros("2017-11-16 22:31:08")),
+            Some(micros("1969-12-31 23:59:59")),
+            None,
+        ])) as ArrayRef;
+        assert_eq!(
+            transform(&Year {}, timestamps.clone()),
+            vec![Some(47), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Month {}, timestamps.clone()),
+            vec![Some(574), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Day {}, timestamps.clone()),
+            vec![Some(17486), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Hour {}, timestamps),
+            vec![Some(17486 * 24 + 22), Some(-1), None]
+        );
+
+        // Nanoseconds before epoch are floored.
+        let timestamps = Arc::new(
+            TimestampNanosecondArray::from(vec![
+                Some(micros("2017-11-16 22:31:08") * 1000 + 999),
+                Some(-1),
+                None,
+            ])
+            .with_timezone("+00:00"),
+        ) as ArrayRef;
+        assert_eq!(
+            transform(&Month {}, timestamps.clone()),
+            vec![Some(574), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Day {}, timestamps.clone()),
+            vec![Some(17486), Some(-1), None]
+        );
+        assert_eq!(
+            transform(&Hour {}, timestamps),
+            vec![Some(17486 * 24 + 22), Some(-1), None]
+        );
+    }
+
+    #[test]
+    fn test_temporal_value() {
+        let date = PrimitiveValue::Date(NaiveDate::from_ymd_opt(2017, 11, 16).unwrap());
+        assert_eq!(temporal_value(Transform::Year, &date), Some(47));
+        assert_eq!(temporal_value(Transform::Month, &date), Some(574));
+        assert_eq!(temporal_value(Transform::Day, &date), Some(17486));
+        assert_eq!(temporal_value(Transform::Hour, &date), None);
+
+        let ts = |v: &str| NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S").unwrap();
+        let before_epoch = PrimitiveValue::Timestamp(ts("1969-12-31 23:59:59"));
+        assert_eq!(temporal_value(Transform::Month, &before_epoch), Some(-1));
+        assert_eq!(temporal_value(Transform::Hour, &before_epoch), Some(-1));
+        let value = PrimitiveValue::TimestampNs(ts("2017-11-16 22:31:08"));
+        assert_eq!(
+            temporal_value(Transform::Hour, &value),
+            Some(17486 * 24 + 22)
+        );
+        assert_eq!(
+            temporal_value(Transform::Day, &PrimitiveValue::Long(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_year_month_from_days() {
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        for days in (-800_000..800_000).step_by(7) {
+            let date = epoch + chrono::Duration::days(days as i64);
+            assert_eq!(
+                year_month0_from_days(days),
+                (date.year(), date.month0() as i32),
+                "{date}"
+            );
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, BinaryArray, Decimal128Array, GenericByteArray, GenericByteBuilder,
+    Int32Array, Int64Array, LargeBinaryArray, LargeStringArray, StringArray,
+};
+use arrow::datatypes::{ByteArrayType, DataType};
+
+use super::unsupported_input;
+use crate::types::{PrimitiveValue, Transform, TransformFunction};
+use crate::Result;
+
+/// Truncate transform truncates numbers to multiples of width, and strings
+/// or binaries to at most width code points or bytes.
+pub struct Truncate {
+    width: i32,
+}
+
+impl Truncate {
+    pub fn new(width: i32) -> Self {
+        Self { width }
+    }
+}
+
+/// Returns the largest multiple of `width` not greater than `v`.
+macro_rules! truncate_number {
+    ($v:expr, $width:expr) => {
+        $v - ((($v % $width) + $width) % $width)
+    };
+}
+
+/// Returns the byte length of the first `width` code points of `s`.
+fn truncate_str(s: &str, width: usize) -> &str {
+    match s.char_indices().nth(width) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+/// Returns the result of truncating a single value like [`Truncate`], which
+/// keeps the scale of decimals.
+///
+/// Returns `None` if the type of value is not supported, or the result
+/// overflows.
+pub(super) fn truncate_value(value: &PrimitiveValue, width: i32) -> Option<PrimitiveValue> {
+    if width <= 0 {
+        return None;
+    }
+    Some(match value {
+        PrimitiveValue::Int(v) => PrimitiveValue::Int(v.checked_sub(v.rem_euclid(width))?),
+        PrimitiveValue::Long(v) => PrimitiveValue::Long(v.checked_sub(v.rem_euclid(width as i64))?),
+        PrimitiveValue::Decimal(v) => {
+            let unscaled = v.mantissa();
+            let unscaled = unscaled.checked_sub(unscaled.rem_euclid(width as i128))?;
+            PrimitiveValue::Decimal(
+                rust_decimal::Decimal::try_from_i128_with_scale(unscaled, v.scale()).ok()?,
+            )
+        }
+        PrimitiveValue::String(v) => {
+            PrimitiveValue::String(truncate_str(v, width as usize).to_string())
+        }
+        PrimitiveValue::Binary(v) => {
+            PrimitiveValue::Binary(v[..v.len().min(width as usize)].to_vec())
+        }
+        _ => return None,
+    })
+}
+
+/// Truncate values of a string or binary array into a builder allocated
+/// once by the size of input values.
+fn truncate_bytes<T: ByteArrayType>(
+    array: &GenericByteArray<T>,
+    truncate: impl Fn(&T::Native) -> &T::Native,
+) -> GenericByteArray<T> {
+    let mut builder = GenericByteBuilder::<T>::with_capacity(array.len(), array.value_data().len());
+    for v in array.iter() {
+        builder.append_option(v.map(&truncate));
+    }
+    builder.finish()
+}
+
+impl TransformFunction for Truncate {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        let unsupported = || unsupported_input(Transform::Truncate(self.width), &input);
+        macro_rules! downcast {
+            ($t:ty) => {
+                input
+                    .as_any()
+                    .downcast_ref::<$t>()
+                    .ok_or_else(unsupported)?
+            };
+        }
+
+        let width = self.width;
+        let result: ArrayRef = match input.data_type() {
+            DataType::Int32 => Arc::new(
+                downcast!(Int32Array)
+                    .unary::<_, arrow::datatypes::Int32Type>(|v| truncate_number!(v, width)),
+            ),
+            DataType::Int64 => Arc::new(
+                downcast!(Int64Array)
+                    .unary::<_, arrow::datatypes::Int64Type>(|v| truncate_number!(v, width as i64)),
+            ),
+            DataType::Decimal128(precision, scale) => Arc::new(
+                downcast!(Decimal128Array)
+                    .unary::<_, arrow::datatypes::Decimal128Type>(|v| {
+                        truncate_number!(v, width as i128)
+                    })
+                    .with_precision_and_scale(*precision, *scale)?,
+            ),
+            DataType::Utf8 => Arc::new(truncate_bytes(downcast!(StringArray), |v| {
+                truncate_str(v, width as usize)
+            })),
+            DataType::LargeUtf8 => Arc::new(truncate_bytes(downcast!(LargeStringArray), |v| {
+                truncate_str(v, width as usize)
+            })),
+            DataType::Binary => Arc::new(truncate_bytes(downcast!(BinaryArray), |v| {
+                &v[..v.len().min(width as usize)]
+            })),
+            DataType::LargeBinary => Arc::new(truncate_bytes(downcast!(LargeBinaryArray), |v| {
+                &v[..v.len().min(width as usize)]
+            })),
+            _ => return Err(unsupported()),
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_transform() {
+        let input = Arc::new(Int32Array::from(vec![Some(1), Some(-1), Some(10), None])) as ArrayRef;
+        let result = Truncate::new(10).transform(input).unwrap();
+        assert_eq!(
+            result.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(0), Some(-10), Some(10), None])
+        );
+
+        let input = Arc::new(
+            Decimal128Array::from(vec![1065])
+                .with_precision_and_scale(9, 2)
+                .unwrap(),
+        ) as ArrayRef;
+        let result = Truncate::new(50).transform(input).unwrap();
+        let result = result.as_any().downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!(result.value(0), 1050);
+        assert_eq!(result.scale(), 2);
+
+        let input = Arc::new(StringArray::from(vec!["iceberg", "冰山湖", "ic"])) as ArrayRef;
+        let result = Truncate::new(3).transform(input).unwrap();
+        assert_eq!(
+            result.as_any().downcast_ref::<StringArray>().unwrap(),
+            &StringArray::from(vec!["ice", "冰山湖", "ic"])
+        );
+    }
+
+    #[test]
+    fn test_truncate_value() {
+        assert_eq!(
+            truncate_value(&PrimitiveValue::Int(-1), 10),
+            Some(PrimitiveValue::Int(-10))
+        );
+        assert_eq!(
+            truncate_value(&PrimitiveValue::Long(25), 10),
+            Some(PrimitiveValue::Long(20))
+        );
+        // Overflowing results are not truncated.
+        assert_eq!(truncate_value(&PrimitiveValue::Int(i32::MIN), 10), None);
+        assert_eq!(
+            truncate_value(
+                &PrimitiveValue::Decimal(rust_decimal::Decimal::new(1065, 2)),
+                50
+            ),
+            Some(PrimitiveValue::Decimal(rust_decimal::Decimal::new(1050, 2)))
+        );
+        assert_eq!(
+            truncate_value(&PrimitiveValue::String("冰山湖".to_string()), 2),
+            Some(PrimitiveValue::String("冰山".to_string()))
+        );
+        assert_eq!(
+            truncate_value(&PrimitiveValue::Binary(vec![1, 2, 3]), 2),
+            Some(PrimitiveValue::Binary(vec![1, 2]))
+        );
+        assert_eq!(
+            truncate_value(&PrimitiveValue::Date(Default::default()), 2),
+            None
+        );
+    }
+}
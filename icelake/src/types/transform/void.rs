@@ -0,0 +1,12 @@
+use crate::types::TransformFunction;
+use crate::Result;
+use arrow::array::{new_null_array, ArrayRef};
+
+/// Void transform produces nulls of the input type.
+pub struct Void {}
+
+impl TransformFunction for Void {
+    fn transform(&self, input: ArrayRef) -> Result<ArrayRef> {
+        Ok(new_null_array(input.data_type(), input.len()))
+    }
+}
@@ -28,10 +28,10 @@ impl AddSortOrderUpdate {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum Action {
     #[serde(rename = "upgrade-format-version")]
+    #[default]
     UpgradeFormatVersion,
     #[serde(rename = "add-schema")]
     AddSchema,
@@ -61,9 +61,3 @@ pub enum Action {
     RemoveProperties,
 }
 
-impl Default for Action {
-    fn default() -> Action {
-        Self::UpgradeFormatVersion
-    }
-}
-
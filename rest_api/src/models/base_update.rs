@@ -25,10 +25,10 @@ impl BaseUpdate {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum Action {
     #[serde(rename = "upgrade-format-version")]
+    #[default]
     UpgradeFormatVersion,
     #[serde(rename = "add-schema")]
     AddSchema,
@@ -58,9 +58,3 @@ pub enum Action {
     RemoveProperties,
 }
 
-impl Default for Action {
-    fn default() -> Action {
-        Self::UpgradeFormatVersion
-    }
-}
-
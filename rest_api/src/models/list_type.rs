@@ -34,16 +34,10 @@ impl ListType {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "list")]
+    #[default]
     List,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::List
-    }
-}
-
@@ -40,16 +40,10 @@ impl MapType {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "map")]
+    #[default]
     Map,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::Map
-    }
-}
-
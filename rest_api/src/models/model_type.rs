@@ -52,16 +52,10 @@ impl Type {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "map")]
+    #[default]
     Map,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::Map
-    }
-}
-
@@ -9,31 +9,25 @@
  */
 
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum NullOrder {
     #[serde(rename = "nulls-first")]
+    #[default]
     First,
     #[serde(rename = "nulls-last")]
     Last,
 
 }
 
-impl ToString for NullOrder {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for NullOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::First => String::from("nulls-first"),
-            Self::Last => String::from("nulls-last"),
+            Self::First => write!(f, "nulls-first"),
+            Self::Last => write!(f, "nulls-last"),
         }
     }
 }
 
-impl Default for NullOrder {
-    fn default() -> NullOrder {
-        Self::First
-    }
-}
-
 
 
 
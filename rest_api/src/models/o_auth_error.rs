@@ -31,10 +31,10 @@ impl OAuthError {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum Error {
     #[serde(rename = "invalid_request")]
+    #[default]
     InvalidRequest,
     #[serde(rename = "invalid_client")]
     InvalidClient,
@@ -48,9 +48,3 @@ pub enum Error {
     InvalidScope,
 }
 
-impl Default for Error {
-    fn default() -> Error {
-        Self::InvalidRequest
-    }
-}
-
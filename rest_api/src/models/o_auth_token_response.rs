@@ -46,9 +46,10 @@ impl OAuthTokenResponse {
 }
 
 /// Access token type for client credentials or token exchange  See https://datatracker.ietf.org/doc/html/rfc6749#section-7.1
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum TokenType {
     #[serde(rename = "bearer")]
+    #[default]
     Bearer,
     #[serde(rename = "mac")]
     Mac,
@@ -56,9 +57,3 @@ pub enum TokenType {
     NA,
 }
 
-impl Default for TokenType {
-    fn default() -> TokenType {
-        Self::Bearer
-    }
-}
-
@@ -43,10 +43,10 @@ impl SetSnapshotRefUpdate {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum Action {
     #[serde(rename = "upgrade-format-version")]
+    #[default]
     UpgradeFormatVersion,
     #[serde(rename = "add-schema")]
     AddSchema,
@@ -75,24 +75,12 @@ pub enum Action {
     #[serde(rename = "remove-properties")]
     RemoveProperties,
 }
-
-impl Default for Action {
-    fn default() -> Action {
-        Self::UpgradeFormatVersion
-    }
-}
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "tag")]
+    #[default]
     Tag,
     #[serde(rename = "branch")]
     Branch,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::Tag
-    }
-}
-
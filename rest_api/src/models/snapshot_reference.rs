@@ -37,18 +37,12 @@ impl SnapshotReference {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "tag")]
+    #[default]
     Tag,
     #[serde(rename = "branch")]
     Branch,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::Tag
-    }
-}
-
@@ -28,10 +28,10 @@ impl SnapshotSummary {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum Operation {
     #[serde(rename = "append")]
+    #[default]
     Append,
     #[serde(rename = "replace")]
     Replace,
@@ -41,9 +41,3 @@ pub enum Operation {
     Delete,
 }
 
-impl Default for Operation {
-    fn default() -> Operation {
-        Self::Append
-    }
-}
-
@@ -9,31 +9,25 @@
  */
 
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum SortDirection {
     #[serde(rename = "asc")]
+    #[default]
     Asc,
     #[serde(rename = "desc")]
     Desc,
 
 }
 
-impl ToString for SortDirection {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Asc => String::from("asc"),
-            Self::Desc => String::from("desc"),
+            Self::Asc => write!(f, "asc"),
+            Self::Desc => write!(f, "desc"),
         }
     }
 }
 
-impl Default for SortDirection {
-    fn default() -> SortDirection {
-        Self::Asc
-    }
-}
-
 
 
 
@@ -28,16 +28,10 @@ impl StructType {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "struct")]
+    #[default]
     Struct,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::Struct
-    }
-}
-
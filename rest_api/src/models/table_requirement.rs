@@ -51,10 +51,10 @@ impl TableRequirement {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum Requirement {
     #[serde(rename = "assert-create")]
+    #[default]
     Create,
     #[serde(rename = "assert-table-uuid")]
     TableUuid,
@@ -72,9 +72,3 @@ pub enum Requirement {
     DefaultSortOrderId,
 }
 
-impl Default for Requirement {
-    fn default() -> Requirement {
-        Self::Create
-    }
-}
-
@@ -8,12 +8,11 @@
  * Generated by: https://openapi-generator.tech
  */
 
-/// TokenType : Token type identifier, from RFC 8693 Section 3  See https://datatracker.ietf.org/doc/html/rfc8693#section-3
-
 /// Token type identifier, from RFC 8693 Section 3  See https://datatracker.ietf.org/doc/html/rfc8693#section-3
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum TokenType {
     #[serde(rename = "urn:ietf:params:oauth:token-type:access_token")]
+    #[default]
     AccessToken,
     #[serde(rename = "urn:ietf:params:oauth:token-type:refresh_token")]
     RefreshToken,
@@ -28,25 +27,19 @@ pub enum TokenType {
 
 }
 
-impl ToString for TokenType {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::AccessToken => String::from("urn:ietf:params:oauth:token-type:access_token"),
-            Self::RefreshToken => String::from("urn:ietf:params:oauth:token-type:refresh_token"),
-            Self::IdToken => String::from("urn:ietf:params:oauth:token-type:id_token"),
-            Self::Saml1 => String::from("urn:ietf:params:oauth:token-type:saml1"),
-            Self::Saml2 => String::from("urn:ietf:params:oauth:token-type:saml2"),
-            Self::Jwt => String::from("urn:ietf:params:oauth:token-type:jwt"),
+            Self::AccessToken => write!(f, "urn:ietf:params:oauth:token-type:access_token"),
+            Self::RefreshToken => write!(f, "urn:ietf:params:oauth:token-type:refresh_token"),
+            Self::IdToken => write!(f, "urn:ietf:params:oauth:token-type:id_token"),
+            Self::Saml1 => write!(f, "urn:ietf:params:oauth:token-type:saml1"),
+            Self::Saml2 => write!(f, "urn:ietf:params:oauth:token-type:saml2"),
+            Self::Jwt => write!(f, "urn:ietf:params:oauth:token-type:jwt"),
         }
     }
 }
 
-impl Default for TokenType {
-    fn default() -> TokenType {
-        Self::AccessToken
-    }
-}
-
 
 
 
@@ -31,16 +31,10 @@ impl TransformTerm {
     }
 }
 
-/// 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Default)]
 pub enum RHashType {
     #[serde(rename = "transform")]
+    #[default]
     Transform,
 }
 
-impl Default for RHashType {
-    fn default() -> RHashType {
-        Self::Transform
-    }
-}
-